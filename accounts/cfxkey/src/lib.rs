@@ -16,6 +16,7 @@
 
 // #![warn(missing_docs)]
 
+extern crate bip39;
 extern crate cfx_types;
 extern crate edit_distance;
 extern crate parity_crypto;
@@ -28,6 +29,7 @@ extern crate rand;
 extern crate rustc_hex;
 extern crate secp256k1;
 extern crate serde;
+extern crate threadpool;
 extern crate tiny_keccak;
 extern crate zeroize;
 
@@ -53,6 +55,7 @@ mod signature;
 pub mod brain_recover;
 pub mod crypto;
 pub mod math;
+pub mod mnemonic;
 
 pub use self::{
     brain::Brain,
@@ -69,7 +72,10 @@ pub use self::{
     prefix::Prefix,
     random::Random,
     secret::Secret,
-    signature::{recover, sign, verify_address, verify_public, Signature},
+    signature::{
+        recover, recover_batch, sign, verify_address, verify_public,
+        verify_public_batch, Signature,
+    },
 };
 
 use cfx_types::H256;
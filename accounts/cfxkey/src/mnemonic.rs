@@ -0,0 +1,155 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Hierarchical deterministic keys derived from a mnemonic phrase, following
+//! BIP-39 for the phrase and BIP-32/BIP-44 for the derivation path.
+
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use extended::{Derivation, ExtendedKeyPair};
+use Error;
+use KeyPair;
+
+/// The coin type of Conflux registered in SLIP-44.
+pub const CONFLUX_COIN_TYPE: u32 = 503;
+
+/// The offset of the hardened indices in a derivation path.
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Generate a new English mnemonic phrase of `words` words, which is one of
+/// 12, 15, 18, 21 or 24.
+pub fn generate_mnemonic(words: usize) -> Result<String, Error> {
+    let mnemonic_type = MnemonicType::for_word_count(words)
+        .map_err(|e| Error::Custom(format!("{}", e)))?;
+    Ok(Mnemonic::new(mnemonic_type, Language::English).into_phrase())
+}
+
+/// Check that `phrase` is a valid English mnemonic phrase, including its
+/// checksum.
+pub fn validate_mnemonic(phrase: &str) -> Result<(), Error> {
+    Mnemonic::validate(phrase, Language::English)
+        .map_err(|e| Error::Custom(format!("Invalid mnemonic: {}", e)))
+}
+
+/// The BIP-44 path of the `index`-th external address of `account` of
+/// Conflux, i.e. `m/44'/503'/account'/0/index`.
+pub fn bip44_path(account: u32, index: u32) -> String {
+    format!("m/44'/{}'/{}'/0/{}", CONFLUX_COIN_TYPE, account, index)
+}
+
+/// Parse a derivation path like `m/44'/503'/0'/0/0` into the BIP-32
+/// indices, where the hardened ones are offset by 2^31.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>, Error> {
+    let invalid_path =
+        || Error::Custom(format!("Invalid derivation path: {}", path));
+    let mut parts = path.trim().split('/');
+    if parts.next() != Some("m") {
+        return Err(invalid_path());
+    }
+    parts
+        .map(|part| {
+            let (index, hardened) = match part.strip_suffix('\'') {
+                Some(index) => (index, true),
+                None => (part, false),
+            };
+            let index: u32 = index.parse().map_err(|_| invalid_path())?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid_path());
+            }
+            Ok(if hardened {
+                index + HARDENED_OFFSET
+            } else {
+                index
+            })
+        })
+        .collect()
+}
+
+/// Derive the key pair at the derivation `path` from the mnemonic `phrase`
+/// and the optional BIP-39 `passphrase`, which is empty if not used.
+pub fn derive_from_mnemonic(
+    phrase: &str, passphrase: &str, path: &str,
+) -> Result<KeyPair, Error> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+        .map_err(|e| Error::Custom(format!("Invalid mnemonic: {}", e)))?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    derive_from_seed(seed.as_bytes(), path)
+}
+
+/// Derive the key pair at the derivation `path` from the BIP-32 `seed`.
+pub fn derive_from_seed(seed: &[u8], path: &str) -> Result<KeyPair, Error> {
+    let derivation_error =
+        |e| Error::Custom(format!("Key derivation failed: {:?}", e));
+    let mut key_pair =
+        ExtendedKeyPair::with_seed(seed).map_err(derivation_error)?;
+    for index in parse_derivation_path(path)? {
+        key_pair = key_pair
+            .derive(Derivation::from(index))
+            .map_err(derivation_error)?;
+    }
+    KeyPair::from_secret(key_pair.secret().as_raw().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_generate_mnemonic() {
+        let phrase = generate_mnemonic(24).unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 24);
+        assert!(validate_mnemonic(&phrase).is_ok());
+        assert!(generate_mnemonic(13).is_err());
+        assert!(validate_mnemonic("abandon abandon abandon").is_err());
+    }
+
+    #[test]
+    fn test_parse_derivation_path() {
+        assert_eq!(bip44_path(1, 2), "m/44'/503'/1'/0/2");
+        assert_eq!(
+            parse_derivation_path(&bip44_path(1, 2)).unwrap(),
+            vec![
+                44 + HARDENED_OFFSET,
+                503 + HARDENED_OFFSET,
+                1 + HARDENED_OFFSET,
+                0,
+                2
+            ]
+        );
+        assert_eq!(parse_derivation_path("m").unwrap(), vec![]);
+        assert!(parse_derivation_path("44'/503'").is_err());
+        assert!(parse_derivation_path("m/x").is_err());
+        assert!(parse_derivation_path("m/2147483648").is_err());
+    }
+
+    #[test]
+    fn test_derive_from_mnemonic() {
+        let derive = |passphrase, index| {
+            derive_from_mnemonic(PHRASE, passphrase, &bip44_path(0, index))
+                .unwrap()
+        };
+        // The derivation is deterministic.
+        assert_eq!(derive("", 0), derive("", 0));
+        assert_ne!(derive("", 0), derive("", 1));
+        // The passphrase changes the seed.
+        assert_ne!(derive("", 0), derive("TREZOR", 0));
+
+        // The well-known first address of the Ethereum path, with the type
+        // bits of Conflux.
+        let key_pair =
+            derive_from_mnemonic(PHRASE, "", "m/44'/60'/0'/0/0").unwrap();
+        assert_eq!(
+            format!("{:x}", key_pair.address()),
+            "1858effd232b4033e47d90003d41ec34ecaeda94"
+        );
+        assert!(derive_from_mnemonic(
+            "abandon abandon abandon",
+            "",
+            &bip44_path(0, 0)
+        )
+        .is_err());
+    }
+}
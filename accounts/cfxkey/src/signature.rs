@@ -20,15 +20,17 @@ use rustc_hex::{FromHex, ToHex};
 use secp256k1::{
     key::{PublicKey, SecretKey},
     Error as SecpError, Message as SecpMessage, RecoverableSignature,
-    RecoveryId,
+    RecoveryId, Secp256k1,
 };
 use std::{
-    cmp::PartialEq,
+    cmp::{max, PartialEq},
     fmt,
     hash::{Hash, Hasher},
     ops::{Deref, DerefMut},
     str::FromStr,
+    sync::mpsc::channel,
 };
+use threadpool::ThreadPool;
 use Address;
 use Error;
 use Message;
@@ -36,6 +38,10 @@ use Public;
 use Secret;
 use SECP256K1;
 
+/// The least number of the signatures handed to a worker by the batch
+/// functions, as a smaller chunk costs more to dispatch than to process.
+const MIN_BATCH_PER_WORKER: usize = 8;
+
 /// Signature encoded as RSV components
 #[repr(C)]
 pub struct Signature([u8; 65]);
@@ -207,7 +213,23 @@ pub fn sign(secret: &Secret, message: &Message) -> Result<Signature, Error> {
 pub fn verify_public(
     public: &Public, signature: &Signature, message: &Message,
 ) -> Result<bool, Error> {
-    let context = &SECP256K1;
+    verify_public_with(&SECP256K1, public, signature, message)
+}
+
+/// Verifies each `(public, signature, message)` on the `workers` and returns
+/// the results in the same order.
+pub fn verify_public_batch(
+    signed: Vec<(Public, Signature, Message)>, workers: &ThreadPool,
+) -> Vec<Result<bool, Error>> {
+    map_on_workers(signed, workers, |(public, signature, message)| {
+        verify_public_with(&SECP256K1, &public, &signature, &message)
+    })
+}
+
+fn verify_public_with(
+    context: &Secp256k1, public: &Public, signature: &Signature,
+    message: &Message,
+) -> Result<bool, Error> {
     let rsig = RecoverableSignature::from_compact(
         context,
         &signature[0..64],
@@ -240,7 +262,22 @@ pub fn verify_address(
 pub fn recover(
     signature: &Signature, message: &Message,
 ) -> Result<Public, Error> {
-    let context = &SECP256K1;
+    recover_with(&SECP256K1, signature, message)
+}
+
+/// Recovers the public key of each `(signature, message)` on the `workers` and
+/// returns the results in the same order.
+pub fn recover_batch(
+    signed: Vec<(Signature, Message)>, workers: &ThreadPool,
+) -> Vec<Result<Public, Error>> {
+    map_on_workers(signed, workers, |(signature, message)| {
+        recover_with(&SECP256K1, &signature, &message)
+    })
+}
+
+fn recover_with(
+    context: &Secp256k1, signature: &Signature, message: &Message,
+) -> Result<Public, Error> {
     let rsig = RecoverableSignature::from_compact(
         context,
         &signature[0..64],
@@ -255,10 +292,64 @@ pub fn recover(
     Ok(public)
 }
 
+/// Applies `f` to the `items` split into chunks among the `workers`, and
+/// returns the results in the order of the `items`. A batch which fits in a
+/// single chunk is processed on the calling thread.
+fn map_on_workers<T, R>(
+    items: Vec<T>, workers: &ThreadPool, f: fn(T) -> R,
+) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+{
+    let max_workers = max(workers.max_count(), 1);
+    let chunk_size = max(
+        MIN_BATCH_PER_WORKER,
+        (items.len() + max_workers - 1) / max_workers,
+    );
+    if items.len() <= chunk_size {
+        return items.into_iter().map(f).collect();
+    }
+
+    let (sender, receiver) = channel();
+    let mut items = items.into_iter();
+    let mut num_chunks = 0;
+    loop {
+        let chunk: Vec<T> = items.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        let sender = sender.clone();
+        let chunk_index = num_chunks;
+        workers.execute(move || {
+            let results: Vec<R> = chunk.into_iter().map(f).collect();
+            // The receiver waits for all the chunks, unless a worker panics.
+            sender.send((chunk_index, results)).ok();
+        });
+        num_chunks += 1;
+    }
+    // Drop our sender, so that a panicking worker ends the iteration below
+    // instead of blocking it forever.
+    drop(sender);
+
+    let mut chunks: Vec<(usize, Vec<R>)> =
+        receiver.iter().take(num_chunks).collect();
+    assert_eq!(chunks.len(), num_chunks, "a batch worker panicked");
+    chunks.sort_unstable_by_key(|(chunk_index, _)| *chunk_index);
+    chunks
+        .into_iter()
+        .flat_map(|(_, results)| results)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{recover, sign, verify_address, verify_public, Signature};
+    use super::{
+        recover, recover_batch, sign, verify_address, verify_public,
+        verify_public_batch, Signature,
+    };
     use std::str::FromStr;
+    use threadpool::ThreadPool;
     use Generator;
     use Message;
     use Random;
@@ -313,4 +404,46 @@ mod tests {
             verify_address(&keypair.address(), &signature, &message).unwrap()
         );
     }
+
+    #[test]
+    fn recover_and_verify_batch() {
+        // Large enough to be split among the workers.
+        let keypairs: Vec<_> =
+            (0..20).map(|_| Random.generate().unwrap()).collect();
+        let message = Message::from_low_u64_be(7);
+        let signed: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| sign(keypair.secret(), &message).unwrap())
+            .collect();
+        let workers = ThreadPool::new(2);
+
+        let recovered = recover_batch(
+            signed.iter().map(|s| (s.clone(), message)).collect(),
+            &workers,
+        );
+        assert_eq!(recovered.len(), keypairs.len());
+        for (keypair, public) in keypairs.iter().zip(recovered) {
+            assert_eq!(keypair.public(), &public.unwrap());
+        }
+
+        // The signatures 1 and 15 are checked against the key of the first
+        // one, one in each chunk.
+        let verified = verify_public_batch(
+            signed
+                .iter()
+                .enumerate()
+                .map(|(i, signature)| {
+                    let signer = if i == 1 || i == 15 { 0 } else { i };
+                    (*keypairs[signer].public(), signature.clone(), message)
+                })
+                .collect(),
+            &workers,
+        );
+        let expected: Vec<_> =
+            (0..keypairs.len()).map(|i| i != 1 && i != 15).collect();
+        assert_eq!(
+            verified.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+            expected
+        );
+    }
 }
@@ -20,6 +20,7 @@
 
 mod account_data;
 mod error;
+mod signer;
 mod stores;
 
 use self::{
@@ -32,7 +33,9 @@ use std::{
     time::{Duration, Instant},
 };
 
-use cfxkey::{Address, Generator, Message, Password, Public, Random, Secret};
+use cfxkey::{
+    mnemonic, Address, Generator, Message, Password, Public, Random, Secret,
+};
 use cfxstore::{
     accounts_dir::MemoryDirectory, random_string, CfxMultiStore, CfxStore,
     OpaqueSecret, SecretStore, SecretVaultRef, SimpleSecretStore,
@@ -44,7 +47,11 @@ use parking_lot::RwLock;
 pub use cfxkey::Signature;
 pub use cfxstore::{Derivation, Error, IndexDerivation, KeyFile};
 
-pub use self::{account_data::AccountMeta, error::SignError};
+pub use self::{
+    account_data::AccountMeta,
+    error::SignError,
+    signer::{RoutingSigner, TransactionSigner},
+};
 
 type AccountToken = Password;
 
@@ -169,6 +176,28 @@ impl AccountProvider {
         Ok(account.address)
     }
 
+    /// Inserts the accounts derived from the mnemonic `phrase` and the BIP-39
+    /// `passphrase` along the BIP-44 path of Conflux, i.e. the address
+    /// indices `start..start + count` of the first account.
+    /// Does not unlock accounts!
+    pub fn insert_mnemonic_accounts(
+        &self, phrase: &str, passphrase: &str, start: u32, count: u32,
+        password: &Password,
+    ) -> Result<Vec<Address>, Error>
+    {
+        mnemonic::validate_mnemonic(phrase)?;
+        (start..start.saturating_add(count))
+            .map(|index| {
+                let key_pair = mnemonic::derive_from_mnemonic(
+                    phrase,
+                    passphrase,
+                    &mnemonic::bip44_path(0, index),
+                )?;
+                self.insert_account(key_pair.secret().clone(), password)
+            })
+            .collect()
+    }
+
     /// Generates new derived account based on the existing one
     /// If password is not provided, account must be unlocked
     /// New account will be created with the same password (if save: true)
@@ -684,7 +713,7 @@ impl AccountProvider {
 mod tests {
     use super::{AccountProvider, Unlock};
     use cfx_types::H256;
-    use cfxkey::{Address, Generator, Random};
+    use cfxkey::{mnemonic, Address, Generator, Random};
     use cfxstore::{Derivation, StoreAccountRef};
     use std::time::{Duration, Instant};
 
@@ -705,6 +734,54 @@ mod tests {
         assert!(ap.sign(kp.address(), None, Default::default()).is_err());
     }
 
+    #[test]
+    fn export_change_password_and_remove() {
+        let kp = Random.generate().unwrap();
+        let ap = AccountProvider::transient_provider();
+        let address = ap
+            .insert_account(kp.secret().clone(), &"test".into())
+            .unwrap();
+        assert!(ap.export_account(&address, "test1".into()).is_err());
+        let key_file = ap.export_account(&address, "test".into()).unwrap();
+        let json = serde_json::to_value(&key_file).unwrap();
+        assert_eq!(json["address"], format!("{:x}", address));
+
+        assert!(ap
+            .change_password(&address, "test1".into(), "new".into())
+            .is_err());
+        assert!(ap
+            .change_password(&address, "test".into(), "new".into())
+            .is_ok());
+        assert!(ap.export_account(&address, "test".into()).is_err());
+        assert!(ap.export_account(&address, "new".into()).is_ok());
+
+        assert!(ap.kill_account(&address, &"test".into()).is_err());
+        assert!(ap.kill_account(&address, &"new".into()).is_ok());
+        assert!(!ap.has_account(address));
+    }
+
+    #[test]
+    fn insert_mnemonic_accounts() {
+        let phrase = mnemonic::generate_mnemonic(12).unwrap();
+        let ap = AccountProvider::transient_provider();
+        let addresses = ap
+            .insert_mnemonic_accounts(&phrase, "", 0, 3, &"test".into())
+            .unwrap();
+        assert_eq!(addresses.len(), 3);
+        assert!(addresses.iter().all(|address| ap.has_account(*address)));
+
+        // The same accounts are derived again.
+        let ap = AccountProvider::transient_provider();
+        assert_eq!(
+            ap.insert_mnemonic_accounts(&phrase, "", 1, 1, &"test".into())
+                .unwrap(),
+            vec![addresses[1]]
+        );
+        assert!(ap
+            .insert_mnemonic_accounts("abandon", "", 0, 1, &"test".into())
+            .is_err());
+    }
+
     #[test]
     fn derived_account_nosave() {
         let kp = Random.generate().unwrap();
@@ -0,0 +1,117 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{AccountProvider, SignError};
+use cfxkey::{Address, Message, Password, Signature};
+use std::sync::Arc;
+
+/// Signs the transactions and messages of the accounts managed by the node,
+/// either with the keys in the local key store or with a remote signer
+/// which keeps the keys in an HSM or a KMS.
+pub trait TransactionSigner: Send + Sync {
+    /// Signs the message with the key of `address`. The password unlocks the
+    /// key if it's kept locally and not unlocked.
+    fn sign(
+        &self, address: Address, password: Option<Password>, message: Message,
+    ) -> Result<Signature, SignError>;
+}
+
+impl TransactionSigner for AccountProvider {
+    fn sign(
+        &self, address: Address, password: Option<Password>, message: Message,
+    ) -> Result<Signature, SignError> {
+        AccountProvider::sign(self, address, password, message)
+    }
+}
+
+/// Signs with the local key store for the accounts in it, and with the
+/// remote signer for the other accounts.
+pub struct RoutingSigner {
+    local: Arc<AccountProvider>,
+    remote: Arc<dyn TransactionSigner>,
+}
+
+impl RoutingSigner {
+    pub fn new(
+        local: Arc<AccountProvider>, remote: Arc<dyn TransactionSigner>,
+    ) -> Self {
+        RoutingSigner { local, remote }
+    }
+}
+
+impl TransactionSigner for RoutingSigner {
+    fn sign(
+        &self, address: Address, password: Option<Password>, message: Message,
+    ) -> Result<Signature, SignError> {
+        if self.local.has_account(address) {
+            self.local.sign(address, password, message)
+        } else {
+            self.remote.sign(address, password, message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RoutingSigner, TransactionSigner};
+    use crate::{AccountProvider, SignError};
+    use cfxkey::{
+        sign, verify_address, Address, Generator, KeyPair, Message, Password,
+        Random, Signature,
+    };
+    use std::sync::Arc;
+
+    /// A remote signer which keeps the key of a single account.
+    struct KeyPairSigner(KeyPair);
+
+    impl TransactionSigner for KeyPairSigner {
+        fn sign(
+            &self, address: Address, _password: Option<Password>,
+            message: Message,
+        ) -> Result<Signature, SignError>
+        {
+            if address != self.0.address() {
+                return Err(SignError::Remote("unknown account".into()));
+            }
+            Ok(sign(self.0.secret(), &message).unwrap())
+        }
+    }
+
+    #[test]
+    fn routes_by_account() {
+        let local_key = Random.generate().unwrap();
+        let remote_key = Random.generate().unwrap();
+        let local = Arc::new(AccountProvider::transient_provider());
+        local
+            .insert_account(local_key.secret().clone(), &"test".into())
+            .unwrap();
+        let signer = RoutingSigner::new(
+            local,
+            Arc::new(KeyPairSigner(remote_key.clone())),
+        );
+
+        let message = Message::random();
+        let signature = signer
+            .sign(local_key.address(), Some("test".into()), message)
+            .unwrap();
+        assert!(
+            verify_address(&local_key.address(), &signature, &message).unwrap()
+        );
+        // The local accounts are never sent to the remote signer, so a locked
+        // local account is not signed.
+        assert!(matches!(
+            signer.sign(local_key.address(), None, message),
+            Err(SignError::NotUnlocked)
+        ));
+
+        let signature =
+            signer.sign(remote_key.address(), None, message).unwrap();
+        assert!(verify_address(&remote_key.address(), &signature, &message)
+            .unwrap());
+        assert!(matches!(
+            signer.sign(Random.generate().unwrap().address(), None, message),
+            Err(SignError::Remote(_))
+        ));
+    }
+}
@@ -3,11 +3,17 @@
 // See http://www.gnu.org/licenses/
 mod miner;
 
+pub use crate::miner::remote_work::{RemoteWork, SubmitWorkError};
+
 use crate::miner::{
+    cpu::{search_nonce, NonceRange, SearchResult},
     stratum::{Options as StratumOption, Stratum},
     work_notify::NotifyWork,
 };
-use cfx_parameters::consensus::GENESIS_GAS_LIMIT;
+use cfx_parameters::consensus::{
+    DEFERRED_STATE_EPOCH_COUNT, GENESIS_GAS_LIMIT,
+};
+use cfx_stratum::VardiffConfig;
 use cfx_types::{Address, H256, U256};
 use cfxcore::{
     block_parameters::*, consensus::consensus_inner::StateBlameInfo, pow::*,
@@ -17,14 +23,15 @@ use cfxcore::{
 };
 use lazy_static::lazy_static;
 use log::{debug, trace, warn};
-use metrics::{Gauge, GaugeUsize};
+use metrics::{register_meter_with_group, Gauge, GaugeUsize, Meter};
 use parking_lot::{Mutex, RwLock};
 use primitives::*;
 use std::{
     cmp::max,
     collections::HashSet,
     sync::{
-        mpsc::{self, TryRecvError},
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::{self, RecvTimeoutError, TryRecvError},
         Arc,
     },
     thread, time,
@@ -34,6 +41,8 @@ use txgen::SharedTransactionGenerator;
 lazy_static! {
     static ref PACKED_ACCOUNT_SIZE: Arc<dyn Gauge<usize>> =
         GaugeUsize::register_with_group("txpool", "packed_account_size");
+    static ref CPU_MINER_HASHES: Arc<dyn Meter> =
+        register_meter_with_group("blockgen", "cpu_miner_hashes");
 }
 
 /// This determined the frequency of checking a new PoW problem.
@@ -42,6 +51,8 @@ lazy_static! {
 const MINING_ITERATION: u64 = 20;
 const BLOCK_FORCE_UPDATE_INTERVAL_IN_SECS: u64 = 10;
 const BLOCKGEN_LOOP_SLEEP_IN_MILISECS: u64 = 30;
+/// The interval of checking the transaction pool in the `dev` mode.
+const DEV_TX_CHECK_INTERVAL_IN_MILISECS: u64 = 20;
 
 enum MiningState {
     Start,
@@ -59,7 +70,10 @@ pub struct BlockGenerator {
     sync: SharedSynchronizationService,
     state: RwLock<MiningState>,
     workers: Mutex<Vec<(Worker, mpsc::Sender<ProofOfWorkProblem>)>>,
+    /// Increased when the problem of the CPU workers is outdated.
+    work_version: AtomicUsize,
     pub stratum: RwLock<Option<Stratum>>,
+    remote_work: RemoteWork,
 }
 
 pub struct Worker {
@@ -68,8 +82,10 @@ pub struct Worker {
 }
 
 impl Worker {
+    /// Start the mining thread `index` of `num_workers`, each searching its
+    /// own part of the nonce space.
     pub fn new(
-        bg: Arc<BlockGenerator>,
+        bg: Arc<BlockGenerator>, index: u64, num_workers: u64,
         solution_sender: mpsc::Sender<ProofOfWorkSolution>,
         problem_receiver: mpsc::Receiver<ProofOfWorkProblem>,
     ) -> Self
@@ -77,11 +93,16 @@ impl Worker {
         let bg_handle = bg;
 
         let thread = thread::Builder::new()
-            .name("blockgen".into())
+            .name(format!("blockgen-{}", index))
             .spawn(move || {
                 let sleep_duration = time::Duration::from_millis(100);
                 let mut problem: Option<ProofOfWorkProblem> = None;
-                let bg_pow = Arc::new(PowComputer::new(bg_handle.pow_config.use_octopus()));
+                let mut work_version = 0;
+                let nonce_range = NonceRange::new(index, num_workers);
+                let mut nonce = nonce_range.random_nonce();
+                let bg_pow = Arc::new(PowComputer::from_spec(
+                    &bg_handle.pow_config.pow_spec,
+                ));
 
                 loop {
                     match *bg_handle.state.read() {
@@ -98,38 +119,80 @@ impl Worker {
                             Err(TryRecvError::Disconnected) => return,
                             Ok(new_problem) => {
                                 problem = Some(new_problem);
+                                work_version = bg_handle
+                                    .work_version
+                                    .load(AtomicOrdering::SeqCst);
+                                nonce = nonce_range.random_nonce();
                             }
                         }
                     }
                     // check if there is a problem to be solved
-                    if problem.is_some() {
-                        trace!("problem is {:?}", problem);
-                        let boundary = problem.as_ref().unwrap().boundary;
-                        let block_hash = problem.as_ref().unwrap().block_hash;
-                        let block_height = problem.as_ref().unwrap().block_height;
-                        let mut nonce: u64 = rand::random();
-                        for _i in 0..MINING_ITERATION {
-                            let nonce_u256 = U256::from(nonce);
-                            let hash = bg_pow.compute(&nonce_u256, &block_hash, block_height);
-                            if ProofOfWorkProblem::validate_hash_against_boundary(&hash, &nonce_u256, &boundary) {
-                                // problem solved
-                                match solution_sender
-                                    .send(ProofOfWorkSolution { nonce: nonce_u256 })
-                                {
-                                    Ok(_) => {}
-                                    Err(e) => {
-                                        warn!("{}", e);
+                    if let Some(current_problem) = problem {
+                        trace!("problem is {:?}", current_problem);
+                        let (result, hashes) = search_nonce(
+                            &bg_pow,
+                            &current_problem,
+                            &nonce_range,
+                            &mut nonce,
+                            MINING_ITERATION,
+                            || {
+                                bg_handle
+                                    .work_version
+                                    .load(AtomicOrdering::SeqCst)
+                                    != work_version
+                            },
+                        );
+                        CPU_MINER_HASHES.mark(hashes);
+                        match result {
+                            SearchResult::Solved(nonce) => {
+                                // Stop the other workers on the solved problem,
+                                // unless the version was already increased for
+                                // a new problem or by another worker solving
+                                // the same problem, whose solution is outdated
+                                // or already sent.
+                                let current = bg_handle
+                                    .work_version
+                                    .compare_exchange(
+                                        work_version,
+                                        work_version + 1,
+                                        AtomicOrdering::SeqCst,
+                                        AtomicOrdering::SeqCst,
+                                    )
+                                    .is_ok();
+                                if current {
+                                    match solution_sender
+                                        .send(ProofOfWorkSolution { nonce })
+                                    {
+                                        Ok(_) => {}
+                                        Err(e) => {
+                                            warn!("{}", e);
+                                        }
                                     }
+                                    trace!("problem solved");
+                                } else {
+                                    trace!("problem outdated");
                                 }
-
-                                trace!("problem solved");
                                 problem = None;
-                                break;
                             }
-                            nonce += 1;
+                            SearchResult::Aborted => {
+                                trace!("problem outdated");
+                                problem = None;
+                            }
+                            SearchResult::Unsolved => {}
                         }
                     } else {
-                        thread::sleep(sleep_duration);
+                        // Wait for the next problem to start immediately.
+                        match problem_receiver.recv_timeout(sleep_duration) {
+                            Ok(new_problem) => {
+                                problem = Some(new_problem);
+                                work_version = bg_handle
+                                    .work_version
+                                    .load(AtomicOrdering::SeqCst);
+                                nonce = nonce_range.random_nonce();
+                            }
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
                     }
                 }
             })
@@ -149,7 +212,7 @@ impl BlockGenerator {
     {
         BlockGenerator {
             pow_config,
-            pow,
+            pow: pow.clone(),
             mining_author,
             graph,
             txpool,
@@ -157,7 +220,9 @@ impl BlockGenerator {
             sync,
             state: RwLock::new(MiningState::Start),
             workers: Mutex::new(Vec::new()),
+            work_version: AtomicUsize::new(0),
             stratum: RwLock::new(None),
+            remote_work: RemoteWork::new(pow),
         }
     }
 
@@ -186,12 +251,15 @@ impl BlockGenerator {
             let stratum = bg.stratum.read();
             stratum.as_ref().unwrap().notify(problem);
         } else {
+            // Abort the outdated problem in the workers immediately.
+            bg.work_version.fetch_add(1, AtomicOrdering::SeqCst);
             for item in bg.workers.lock().iter() {
                 item.1
                     .send(problem)
                     .expect("Failed to send the PoW problem.")
             }
         }
+        bg.remote_work.notify(problem);
     }
 
     // TODO: should not hold and pass write lock to consensus.
@@ -344,6 +412,17 @@ impl BlockGenerator {
         )
     }
 
+    /// Assemble the block to mine next with the transactions selected by the
+    /// packing strategy. The transactions are left in the pool until the
+    /// block is mined.
+    pub fn assemble_block_template(&self) -> Block {
+        self.assemble_new_block(
+            MAX_TRANSACTION_COUNT_PER_BLOCK,
+            self.graph.verification_config.max_block_size_in_bytes,
+            vec![],
+        )
+    }
+
     /// Assemble a new block without nonce and with options to override the
     /// states/blame. This function is used for testing only to generate
     /// incorrect blocks
@@ -621,23 +700,30 @@ impl BlockGenerator {
 
     pub fn pow_config(&self) -> ProofOfWorkConfig { self.pow_config.clone() }
 
+    /// The work package for the miners polling through RPC.
+    pub fn remote_work(&self) -> &RemoteWork { &self.remote_work }
+
     /// Start num_worker new workers
     pub fn start_new_worker(
         num_worker: u32, bg: Arc<BlockGenerator>,
     ) -> mpsc::Receiver<ProofOfWorkSolution> {
         let (solution_sender, solution_receiver) = mpsc::channel();
         let mut workers = bg.workers.lock();
-        for _ in 0..num_worker {
+        for index in 0..num_worker {
             let (problem_sender, problem_receiver) = mpsc::channel();
             workers.push((
                 Worker::new(
                     bg.clone(),
+                    index as u64,
+                    num_worker as u64,
                     solution_sender.clone(),
                     problem_receiver,
                 ),
                 problem_sender,
             ));
         }
+        // The miners polling through RPC work alongside the CPU miner.
+        bg.remote_work.start(solution_sender);
         solution_receiver
     }
 
@@ -649,11 +735,27 @@ impl BlockGenerator {
             listen_addr: bg.pow_config.stratum_listen_addr.clone(),
             port: bg.pow_config.stratum_port,
             secret: bg.pow_config.stratum_secret,
+            vardiff: bg.pow_config.stratum_share_difficulty.map(
+                |initial_difficulty| VardiffConfig {
+                    initial_difficulty: initial_difficulty.into(),
+                    min_difficulty: bg
+                        .pow_config
+                        .stratum_min_share_difficulty
+                        .into(),
+                    target_share_interval: bg
+                        .pow_config
+                        .stratum_share_interval_ms
+                        .map(Duration::from_millis),
+                },
+            ),
         };
-        let stratum = Stratum::start(&cfg, bg.pow.clone(), solution_sender)
-            .expect("Failed to start Stratum service.");
+        let stratum =
+            Stratum::start(&cfg, bg.pow.clone(), solution_sender.clone())
+                .expect("Failed to start Stratum service.");
         let mut bg_stratum = bg.stratum.write();
         *bg_stratum = Some(stratum);
+        // The miners polling through RPC share the work with stratum.
+        bg.remote_work.start(solution_sender);
         solution_receiver
     }
 
@@ -667,7 +769,10 @@ impl BlockGenerator {
             if bg.pow_config.use_stratum() {
                 BlockGenerator::start_new_stratum_worker(bg.clone())
             } else {
-                BlockGenerator::start_new_worker(1, bg.clone())
+                BlockGenerator::start_new_worker(
+                    max(bg.pow_config.cpu_miner_threads, 1) as u32,
+                    bg.clone(),
+                )
             };
 
         let mut last_notify = SystemTime::now();
@@ -688,11 +793,7 @@ impl BlockGenerator {
                     continue;
                 }
 
-                current_mining_block = Some(bg.assemble_new_block(
-                    MAX_TRANSACTION_COUNT_PER_BLOCK,
-                    bg.graph.verification_config.max_block_size_in_bytes,
-                    vec![],
-                ));
+                current_mining_block = Some(bg.assemble_block_template());
 
                 // set a mining problem
                 let current_difficulty = current_mining_block
@@ -798,6 +899,37 @@ impl BlockGenerator {
             thread::sleep(interval);
         }
     }
+
+    /// Generate a block as soon as there are transactions to pack, followed
+    /// by `DEFERRED_STATE_EPOCH_COUNT` empty blocks so that the transactions
+    /// are executed and their receipts are available.
+    pub fn auto_block_generation_on_transactions(&self) {
+        let interval = Duration::from_millis(DEV_TX_CHECK_INTERVAL_IN_MILISECS);
+        let block_size_limit =
+            self.graph.verification_config.max_block_size_in_bytes;
+        loop {
+            match *self.state.read() {
+                MiningState::Stop => return,
+                _ => {}
+            }
+            if !self.sync.catch_up_mode()
+                && self.txpool.total_ready_accounts() > 0
+            {
+                // The ready transactions may not be packable, e.g. if their
+                // epoch heights are out of range, and then no block is
+                // generated for them.
+                let block =
+                    self.assemble_new_block(3000, block_size_limit, vec![]);
+                if !block.transactions.is_empty() {
+                    self.generate_block_impl(block);
+                    for _ in 0..DEFERRED_STATE_EPOCH_COUNT {
+                        self.generate_block(0, block_size_limit, vec![]);
+                    }
+                }
+            }
+            thread::sleep(interval);
+        }
+    }
 }
 
 impl Stopable for BlockGenerator {
@@ -0,0 +1,139 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The nonce search of the internal CPU miner.
+
+use cfx_types::U256;
+use cfxcore::pow::{PowComputer, ProofOfWorkProblem};
+
+/// The part of the nonce space searched by one of the mining threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonceRange {
+    start: u64,
+    len: u64,
+}
+
+impl NonceRange {
+    /// The range of the thread `index` of `num_threads`, which don't overlap
+    /// with each other.
+    pub fn new(index: u64, num_threads: u64) -> Self {
+        let len = u64::max_value() / num_threads;
+        NonceRange {
+            start: index * len,
+            len,
+        }
+    }
+
+    /// A random nonce in the range to start the search from, so that the
+    /// threads of different nodes don't repeat each other.
+    pub fn random_nonce(&self) -> u64 {
+        self.start + rand::random::<u64>() % self.len
+    }
+
+    /// The nonce after `nonce`, wrapping around in the range.
+    pub fn next(&self, nonce: u64) -> u64 {
+        self.start + (nonce - self.start + 1) % self.len
+    }
+}
+
+pub enum SearchResult {
+    /// The nonce solving the problem.
+    Solved(U256),
+    /// The search is aborted, because the problem is outdated.
+    Aborted,
+    /// No nonce solves the problem in this round.
+    Unsolved,
+}
+
+/// Search at most `iterations` nonces of `range` from `nonce` for the
+/// solution of `problem`, and abort as soon as `is_outdated` returns true.
+/// `nonce` is advanced to the next nonce to search, and the number of the
+/// computed hashes is returned along with the result.
+pub fn search_nonce<F>(
+    pow: &PowComputer, problem: &ProofOfWorkProblem, range: &NonceRange,
+    nonce: &mut u64, iterations: u64, is_outdated: F,
+) -> (SearchResult, usize)
+where
+    F: Fn() -> bool,
+{
+    let mut hashes = 0;
+    for _ in 0..iterations {
+        if is_outdated() {
+            return (SearchResult::Aborted, hashes);
+        }
+        let nonce_u256 = U256::from(*nonce);
+        let hash =
+            pow.compute(&nonce_u256, &problem.block_hash, problem.block_height);
+        hashes += 1;
+        *nonce = range.next(*nonce);
+        if ProofOfWorkProblem::validate_hash_against_boundary(
+            &hash,
+            &nonce_u256,
+            &problem.boundary,
+        ) {
+            return (SearchResult::Solved(nonce_u256), hashes);
+        }
+    }
+    (SearchResult::Unsolved, hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfx_types::H256;
+    use cfxcore::pow::PowAlgorithmType;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_nonce_range() {
+        let ranges: Vec<_> = (0..4).map(|i| NonceRange::new(i, 4)).collect();
+        for (i, range) in ranges.iter().enumerate() {
+            let nonce = range.random_nonce();
+            assert!(nonce >= range.start && nonce - range.start < range.len);
+            if i + 1 < ranges.len() {
+                assert_eq!(range.start + range.len, ranges[i + 1].start);
+            }
+        }
+        // The nonce wraps around in the range.
+        assert_eq!(ranges[0].next(ranges[0].len - 1), 0);
+        assert_eq!(ranges[1].next(ranges[1].start), ranges[1].start + 1);
+    }
+
+    #[test]
+    fn test_search_nonce() {
+        let pow = PowComputer::new(PowAlgorithmType::Dev);
+        let range = NonceRange::new(1, 2);
+
+        // Any nonce solves the problem of difficulty 1.
+        let problem = ProofOfWorkProblem::new(1, H256::random(), 1.into());
+        let mut nonce = range.start;
+        match search_nonce(&pow, &problem, &range, &mut nonce, 10, || false) {
+            (SearchResult::Solved(solution), 1) => {
+                assert_eq!(solution, range.start.into())
+            }
+            _ => panic!("Expected the first nonce to solve the problem"),
+        }
+        assert_eq!(nonce, range.start + 1);
+
+        // No nonce solves it in practice.
+        let problem =
+            ProofOfWorkProblem::new(2, H256::random(), U256::max_value());
+        match search_nonce(&pow, &problem, &range, &mut nonce, 10, || false) {
+            (SearchResult::Unsolved, 10) => {}
+            _ => panic!("Expected the problem to be unsolved"),
+        }
+
+        // The search is aborted once the problem is outdated.
+        let checks = Cell::new(0);
+        let is_outdated = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        };
+        match search_nonce(&pow, &problem, &range, &mut nonce, 10, is_outdated)
+        {
+            (SearchResult::Aborted, 3) => {}
+            _ => panic!("Expected the search to be aborted"),
+        }
+    }
+}
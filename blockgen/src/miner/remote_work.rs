@@ -0,0 +1,218 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Work packages for the miners polling the node through RPC.
+
+use crate::miner::work_notify::NotifyWork;
+use cfx_types::{H256, U256};
+use cfxcore::pow::{PowComputer, ProofOfWorkProblem, ProofOfWorkSolution};
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+/// The hashrate reported by a miner is dropped if it's not updated within
+/// this duration.
+const HASHRATE_TIMEOUT: Duration = Duration::from_secs(60);
+/// The most miners whose hashrates are kept, as the miner ids are chosen by
+/// the RPC clients. The one reported least recently is dropped for a new one.
+const MAX_HASHRATE_REPORTERS: usize = 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum SubmitWorkError {
+    /// Remote mining is not enabled.
+    NotMining,
+    /// The work is not the current one, or it's already solved.
+    StaleWork,
+    /// The nonce does not solve the work.
+    InvalidNonce,
+}
+
+impl fmt::Display for SubmitWorkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            SubmitWorkError::NotMining => "Remote mining is not enabled",
+            SubmitWorkError::StaleWork => "Stale work",
+            SubmitWorkError::InvalidNonce => "Invalid nonce",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Keeps the current work package for the remote miners, and forwards their
+/// solutions to the block generator.
+pub struct RemoteWork {
+    pow: Arc<PowComputer>,
+    current_problem: Mutex<Option<ProofOfWorkProblem>>,
+    solution_sender: Mutex<Option<mpsc::Sender<ProofOfWorkSolution>>>,
+    /// The hashrate reported by each miner, with the time of the report.
+    hashrates: Mutex<HashMap<H256, (U256, Instant)>>,
+}
+
+impl RemoteWork {
+    pub fn new(pow: Arc<PowComputer>) -> Self {
+        RemoteWork {
+            pow,
+            current_problem: Mutex::new(None),
+            solution_sender: Mutex::new(None),
+            hashrates: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start accepting the solutions, which are sent to `solution_sender`.
+    pub fn start(&self, solution_sender: mpsc::Sender<ProofOfWorkSolution>) {
+        *self.solution_sender.lock() = Some(solution_sender);
+    }
+
+    pub fn is_mining(&self) -> bool { self.solution_sender.lock().is_some() }
+
+    /// The current work package, if any.
+    pub fn work(&self) -> Option<ProofOfWorkProblem> {
+        *self.current_problem.lock()
+    }
+
+    /// Submit the `nonce` solving the work of `pow_hash`.
+    pub fn submit(
+        &self, nonce: U256, pow_hash: H256,
+    ) -> Result<(), SubmitWorkError> {
+        let solution_sender = self.solution_sender.lock();
+        let solution_sender = match *solution_sender {
+            Some(ref sender) => sender,
+            None => return Err(SubmitWorkError::NotMining),
+        };
+        let mut current_problem = self.current_problem.lock();
+        let problem = match *current_problem {
+            Some(problem) if problem.block_hash == pow_hash => problem,
+            _ => return Err(SubmitWorkError::StaleWork),
+        };
+        let hash =
+            self.pow
+                .compute(&nonce, &problem.block_hash, problem.block_height);
+        if !ProofOfWorkProblem::validate_hash_against_boundary(
+            &hash,
+            &nonce,
+            &problem.boundary,
+        ) {
+            return Err(SubmitWorkError::InvalidNonce);
+        }
+
+        info!("Remote miner mined a block! nonce = {}", nonce);
+        *current_problem = None;
+        if let Err(e) = solution_sender.send(ProofOfWorkSolution { nonce }) {
+            warn!("{}", e);
+        }
+        Ok(())
+    }
+
+    /// Record the `hashrate` reported by the miner `id`.
+    pub fn submit_hashrate(&self, hashrate: U256, id: H256) {
+        let mut hashrates = self.hashrates.lock();
+        let now = Instant::now();
+        Self::remove_expired(&mut hashrates, now);
+        if hashrates.len() >= MAX_HASHRATE_REPORTERS
+            && !hashrates.contains_key(&id)
+        {
+            let least_recent = hashrates
+                .iter()
+                .min_by_key(|(_, (_, reported))| *reported)
+                .map(|(id, _)| *id);
+            if let Some(least_recent) = least_recent {
+                hashrates.remove(&least_recent);
+            }
+        }
+        hashrates.insert(id, (hashrate, now));
+    }
+
+    /// The total hashrate reported by the miners recently.
+    pub fn hashrate(&self) -> U256 {
+        let mut hashrates = self.hashrates.lock();
+        Self::remove_expired(&mut hashrates, Instant::now());
+        hashrates.values().fold(U256::zero(), |sum, (hashrate, _)| {
+            sum.saturating_add(*hashrate)
+        })
+    }
+
+    fn remove_expired(
+        hashrates: &mut HashMap<H256, (U256, Instant)>, now: Instant,
+    ) {
+        hashrates.retain(|_, (_, reported)| {
+            now.duration_since(*reported) < HASHRATE_TIMEOUT
+        });
+    }
+}
+
+impl NotifyWork for RemoteWork {
+    fn notify(&self, prob: ProofOfWorkProblem) {
+        *self.current_problem.lock() = Some(prob);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfxcore::pow::PowAlgorithmType;
+    use std::thread;
+
+    #[test]
+    fn test_submit_work() {
+        let remote_work =
+            RemoteWork::new(Arc::new(PowComputer::new(PowAlgorithmType::Dev)));
+        // Any nonce solves the problem of difficulty 1.
+        let problem = ProofOfWorkProblem::new(1, H256::random(), 1.into());
+        remote_work.notify(problem);
+        assert_eq!(
+            remote_work.submit(0.into(), problem.block_hash),
+            Err(SubmitWorkError::NotMining)
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        remote_work.start(sender);
+        assert_eq!(
+            remote_work.submit(0.into(), H256::random()),
+            Err(SubmitWorkError::StaleWork)
+        );
+        assert_eq!(remote_work.submit(7.into(), problem.block_hash), Ok(()));
+        assert_eq!(receiver.try_recv().unwrap().nonce, 7.into());
+        // The work is solved.
+        assert_eq!(remote_work.work(), None);
+        assert_eq!(
+            remote_work.submit(8.into(), problem.block_hash),
+            Err(SubmitWorkError::StaleWork)
+        );
+
+        // No nonce solves it in practice.
+        let problem =
+            ProofOfWorkProblem::new(2, H256::random(), U256::max_value());
+        remote_work.notify(problem);
+        assert_eq!(
+            remote_work.submit(0.into(), problem.block_hash),
+            Err(SubmitWorkError::InvalidNonce)
+        );
+    }
+
+    #[test]
+    fn test_hashrate() {
+        let remote_work =
+            RemoteWork::new(Arc::new(PowComputer::new(PowAlgorithmType::Dev)));
+        remote_work.submit_hashrate(100.into(), H256::from_low_u64_be(1));
+        remote_work.submit_hashrate(200.into(), H256::from_low_u64_be(2));
+        // Tell the reports apart for the eviction below.
+        thread::sleep(Duration::from_millis(1));
+        remote_work.submit_hashrate(300.into(), H256::from_low_u64_be(1));
+        assert_eq!(remote_work.hashrate(), 500.into());
+
+        // The miner reported least recently is dropped for a new one.
+        for i in 3..=MAX_HASHRATE_REPORTERS as u64 + 1 {
+            remote_work.submit_hashrate(1.into(), H256::from_low_u64_be(i));
+        }
+        let hashrates = remote_work.hashrates.lock();
+        assert_eq!(hashrates.len(), MAX_HASHRATE_REPORTERS);
+        assert!(!hashrates.contains_key(&H256::from_low_u64_be(2)));
+        assert!(hashrates.contains_key(&H256::from_low_u64_be(1)));
+    }
+}
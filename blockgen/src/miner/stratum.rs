@@ -26,12 +26,13 @@
 
 use crate::miner::work_notify::NotifyWork;
 use cfx_stratum::{
-    Error as StratumServiceError, JobDispatcher, PushWorkHandler,
-    Stratum as StratumService,
+    Error as StratumServiceError, Job, JobDispatcher, PushWorkHandler,
+    Stratum as StratumService, VardiffConfig,
 };
 use cfx_types::{H256, U256};
 use cfxcore::pow::{
-    validate, PowComputer, ProofOfWorkProblem, ProofOfWorkSolution,
+    difficulty_to_boundary, PowComputer, ProofOfWorkProblem,
+    ProofOfWorkSolution,
 };
 use log::{info, trace, warn};
 use parking_lot::Mutex;
@@ -50,6 +51,9 @@ pub struct Options {
     pub port: u16,
     /// Secret for peers
     pub secret: Option<H256>,
+    /// Share difficulty of the workers, if the shares are accepted below the
+    /// block difficulty
+    pub vardiff: Option<VardiffConfig>,
 }
 
 fn clean_0x(s: &str) -> &str {
@@ -120,6 +124,12 @@ pub struct StratumJobDispatcher {
 
 impl JobDispatcher for StratumJobDispatcher {
     fn submit(&self, payload: Vec<String>) -> Result<(), StratumServiceError> {
+        self.submit_share(payload, None)
+    }
+
+    fn submit_share(
+        &self, payload: Vec<String>, share_difficulty: Option<U256>,
+    ) -> Result<(), StratumServiceError> {
         let payload = SubmitPayload::from_args(payload)
             .map_err(|e| StratumServiceError::Dispatch(e.to_string()))?;
 
@@ -146,7 +156,31 @@ impl JobDispatcher for StratumJobDispatcher {
                         .into(),
                     ));
                 }
-                if !validate(self.pow.clone(), &prob, &sol) {
+                let hash = self.pow.compute(
+                    &sol.nonce,
+                    &prob.block_hash,
+                    prob.block_height,
+                );
+                let validate = |boundary| {
+                    ProofOfWorkProblem::validate_hash_against_boundary(
+                        &hash, &sol.nonce, &boundary,
+                    )
+                };
+                if !validate(prob.boundary) {
+                    if let Some(share_difficulty) = share_difficulty {
+                        if share_difficulty < prob.difficulty
+                            && validate(difficulty_to_boundary(
+                                &share_difficulty,
+                            ))
+                        {
+                            trace!(
+                                target: "stratum",
+                                "Stratum worker {} submitted a share",
+                                payload.worker_id
+                            );
+                            return Ok(());
+                        }
+                    }
                     return Err(StratumServiceError::InvalidSolution(
                         format!(
                             "Incorrect Nonce! worker_id = {}!",
@@ -177,6 +211,28 @@ impl JobDispatcher for StratumJobDispatcher {
 
         Ok(())
     }
+
+    fn job(&self, share_difficulty: Option<U256>) -> Option<Job> {
+        self.current_problem.lock().map(|prob| {
+            // The share boundary is never harder than the block boundary.
+            let (id, boundary) = match share_difficulty {
+                Some(difficulty) if difficulty < prob.difficulty => (
+                    format!("0x{:x}-{:x}", prob.block_hash, difficulty),
+                    difficulty_to_boundary(&difficulty),
+                ),
+                _ => (format!("0x{:x}", prob.block_hash), prob.boundary),
+            };
+            Job {
+                payload: self.payload(
+                    &id,
+                    prob.block_height,
+                    prob.block_hash,
+                    boundary,
+                ),
+                id,
+            }
+        })
+    }
 }
 
 impl StratumJobDispatcher {
@@ -199,13 +255,11 @@ impl StratumJobDispatcher {
 
     /// Serializes payload for stratum service
     fn payload(
-        &self, block_height: u64, pow_hash: H256, boundary: U256,
+        &self, job_id: &str, block_height: u64, pow_hash: H256, boundary: U256,
     ) -> String {
-        // Now we just fill the job_id as pow_hash. This will be more consistent
-        // with the convention.
         format!(
-            r#"["0x{:x}", "{}", "0x{:x}","0x{:x}"]"#,
-            pow_hash, block_height, pow_hash, boundary
+            r#"["{}", "{}", "0x{:x}","0x{:x}"]"#,
+            job_id, block_height, pow_hash, boundary
         )
     }
 }
@@ -240,10 +294,8 @@ impl NotifyWork for Stratum {
         trace!(target: "stratum", "Notify work");
 
         self.dispatcher.set_current_problem(&prob);
-        self.service.push_work_all(
-            self.dispatcher.payload(prob.block_height, prob.block_hash, prob.boundary)
-        ).unwrap_or_else(
-            |e| warn!(target: "stratum", "Error while pushing work: {:?}", e)
+        self.service.push_job_all().unwrap_or_else(
+            |e| warn!(target: "stratum", "Error while pushing work: {:?}", e),
         );
     }
 }
@@ -268,6 +320,7 @@ impl Stratum {
             ),
             dispatcher.clone(),
             options.secret.clone(),
+            options.vardiff.clone(),
         )?;
 
         Ok(Stratum {
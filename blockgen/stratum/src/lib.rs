@@ -37,8 +37,12 @@ extern crate tokio;
 extern crate tokio_io;
 
 mod traits;
+mod vardiff;
 
-pub use traits::{Error, JobDispatcher, PushWorkHandler, ServiceConfiguration};
+pub use traits::{
+    Error, Job, JobDispatcher, PushWorkHandler, ServiceConfiguration,
+};
+pub use vardiff::VardiffConfig;
 
 use jsonrpc_core::{
     to_value, Compatibility, IoDelegate, MetaIoHandler, Metadata, Params, Value,
@@ -49,13 +53,14 @@ use jsonrpc_tcp_server::{
 };
 use std::sync::Arc;
 
-use crate::traits::Error::InvalidSolution;
-use cfx_types::H256;
+use crate::{traits::Error::InvalidSolution, vardiff::WorkerDifficulty};
+use cfx_types::{H256, U256};
 use hash::keccak;
 use parking_lot::RwLock;
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    time::Instant,
 };
 
 type RpcResult = Result<jsonrpc_core::Value, jsonrpc_core::Error>;
@@ -82,13 +87,14 @@ pub struct Stratum {
 impl Stratum {
     pub fn start(
         addr: &SocketAddr, dispatcher: Arc<dyn JobDispatcher>,
-        secret: Option<H256>,
+        secret: Option<H256>, vardiff: Option<VardiffConfig>,
     ) -> Result<Arc<Stratum>, Error>
     {
         let implementation = Arc::new(StratumImpl {
             dispatcher,
             workers: Arc::new(RwLock::default()),
             secret,
+            vardiff,
             notify_counter: RwLock::new(NOTIFY_COUNTER_INITIAL),
         });
 
@@ -127,6 +133,10 @@ impl PushWorkHandler for Stratum {
         self.implementation
             .push_work_all(payload, &self.tcp_dispatcher)
     }
+
+    fn push_job_all(&self) -> Result<(), Error> {
+        self.implementation.push_job_all(&self.tcp_dispatcher)
+    }
 }
 
 impl Drop for Stratum {
@@ -138,13 +148,38 @@ impl Drop for Stratum {
     }
 }
 
+/// An authorized worker
+struct Worker {
+    worker_id: String,
+    /// The share difficulty, if the shares are accepted below the block
+    /// difficulty
+    difficulty: Option<WorkerDifficulty>,
+    /// The share difficulty of the jobs pushed to the worker for the current
+    /// problem, by job id
+    jobs: HashMap<String, U256>,
+}
+
+impl Worker {
+    fn share_difficulty(&self) -> Option<U256> {
+        self.difficulty.as_ref().map(WorkerDifficulty::difficulty)
+    }
+
+    fn on_job(&mut self, job: &Job, share_difficulty: Option<U256>) {
+        if let Some(share_difficulty) = share_difficulty {
+            self.jobs.insert(job.id.clone(), share_difficulty);
+        }
+    }
+}
+
 struct StratumImpl {
     /// Payload manager
     dispatcher: Arc<dyn JobDispatcher>,
-    /// Authorized workers (socket - worker_id)
-    workers: Arc<RwLock<HashMap<SocketAddr, String>>>,
+    /// Authorized workers (socket - worker)
+    workers: Arc<RwLock<HashMap<SocketAddr, Worker>>>,
     /// Secret if any
     secret: Option<H256>,
+    /// Share difficulty of the workers if any
+    vardiff: Option<VardiffConfig>,
     /// Dispatch notify couinter
     notify_counter: RwLock<u32>,
 }
@@ -160,25 +195,45 @@ impl StratumImpl {
                 }
             }
             debug!(target: "stratum", "New worker #{} registered", worker_id);
-            self.workers.write().insert(meta.addr().clone(), worker_id);
+            let difficulty = self.vardiff.as_ref().map(|config| {
+                WorkerDifficulty::new(config, Instant::now())
+            });
+            self.workers.write().insert(
+                meta.addr().clone(),
+                Worker { worker_id, difficulty, jobs: HashMap::new() },
+            );
             to_value(true)
         }).map(|v| v.expect("Only true/false is returned and it's always serializable; qed"))
     }
 
     /// rpc method `mining.submit`
-    fn submit(&self, params: Params, _meta: SocketMetadata) -> RpcResult {
+    fn submit(&self, params: Params, meta: SocketMetadata) -> RpcResult {
         Ok(Value::Array(match params {
             Params::Array(vals) => {
+                // The share is validated against the difficulty of the job
+                // it is mined for, which may be retargeted since.
+                let share_difficulty = match vals.get(1) {
+                    Some(Value::String(job_id)) => self
+                        .workers
+                        .read()
+                        .get(meta.addr())
+                        .and_then(|worker| worker.jobs.get(job_id).cloned()),
+                    _ => None,
+                };
                 // first two elements are service messages (worker_id & job_id)
-                match self.dispatcher.submit(
+                match self.dispatcher.submit_share(
                     vals.iter()
                         .filter_map(|val| match *val {
                             Value::String(ref s) => Some(s.to_owned()),
                             _ => None,
                         })
                         .collect::<Vec<String>>(),
+                    share_difficulty,
                 ) {
-                    Ok(()) => vec![to_value(true).expect("serializable")],
+                    Ok(()) => {
+                        self.on_share(&meta);
+                        vec![to_value(true).expect("serializable")]
+                    }
                     Err(InvalidSolution(msg)) => {
                         // When we have invalid solution, we propagate the
                         // reason to the client
@@ -201,27 +256,99 @@ impl StratumImpl {
         }))
     }
 
+    /// Retarget the share difficulty of the worker on an accepted share, and
+    /// push the current job with the new difficulty to it.
+    fn on_share(&self, meta: &SocketMetadata) {
+        let config = match self.vardiff {
+            Some(ref config) => config,
+            None => return,
+        };
+        let mut workers = self.workers.write();
+        let worker = match workers.get_mut(meta.addr()) {
+            Some(worker) => worker,
+            None => return,
+        };
+        let new_difficulty = match worker.difficulty {
+            Some(ref mut difficulty) => {
+                difficulty.on_share(config, Instant::now())
+            }
+            None => None,
+        };
+        if let Some(new_difficulty) = new_difficulty {
+            debug!(target: "stratum", "Share difficulty of {} retargeted to {}", meta.addr(), new_difficulty);
+            let job = match self.dispatcher.job(Some(new_difficulty)) {
+                Some(job) => job,
+                None => return,
+            };
+            worker.on_job(&job, Some(new_difficulty));
+            drop(workers);
+            if let Some(ref tcp_dispatcher) = meta.tcp_dispatcher {
+                let msg = self.notify_message(&job.payload);
+                if let Err(e) = tcp_dispatcher.push_message(meta.addr(), msg) {
+                    debug!(target: "stratum", "Failed to push job to {}: {:?}", meta.addr(), e);
+                }
+            }
+        }
+    }
+
+    fn notify_message(&self, payload: &str) -> String {
+        let next_request_id = {
+            let mut counter = self.notify_counter.write();
+            if *counter == ::std::u32::MAX {
+                *counter = NOTIFY_COUNTER_INITIAL;
+            } else {
+                *counter += 1
+            }
+            *counter
+        };
+        format!("{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}", next_request_id, payload)
+    }
+
     fn push_work_all(
         &self, payload: String, tcp_dispatcher: &Dispatcher,
     ) -> Result<(), Error> {
+        let workers_msg = self.notify_message(&payload);
+        self.push_messages(|_| Some(workers_msg.clone()), tcp_dispatcher)
+    }
+
+    /// Push the current job of the dispatcher to each worker with its share
+    /// difficulty.
+    fn push_job_all(&self, tcp_dispatcher: &Dispatcher) -> Result<(), Error> {
+        // The jobs of the same share difficulty are shared by the workers.
+        let mut jobs = HashMap::new();
+        let mut msgs = HashMap::new();
+        for (addr, worker) in self.workers.write().iter_mut() {
+            // The jobs of the previous problem are stale.
+            worker.jobs.clear();
+            let share_difficulty = worker.share_difficulty();
+            let job = jobs
+                .entry(share_difficulty)
+                .or_insert_with(|| self.dispatcher.job(share_difficulty));
+            if let Some(job) = job {
+                worker.on_job(job, share_difficulty);
+                msgs.insert(*addr, self.notify_message(&job.payload));
+            }
+        }
+        self.push_messages(|addr| msgs.remove(addr), tcp_dispatcher)
+    }
+
+    fn push_messages<F>(
+        &self, mut message: F, tcp_dispatcher: &Dispatcher,
+    ) -> Result<(), Error>
+    where F: FnMut(&SocketAddr) -> Option<String> {
         let hup_peers = {
             let workers = self.workers.read();
-            let next_request_id = {
-                let mut counter = self.notify_counter.write();
-                if *counter == ::std::u32::MAX {
-                    *counter = NOTIFY_COUNTER_INITIAL;
-                } else {
-                    *counter += 1
-                }
-                *counter
-            };
 
             let mut hup_peers = HashSet::with_capacity(0); // most of the cases won't be needed, hence avoid allocation
-            let workers_msg = format!("{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}", next_request_id, payload);
-            trace!(target: "stratum", "Pushing work for {} workers (payload: '{}')", workers.len(), &workers_msg);
-            for (ref addr, worker_id) in workers.iter() {
-                trace!(target: "stratum", "Pushing work to {} at addr {}", &worker_id, &addr);
-                match tcp_dispatcher.push_message(addr, workers_msg.clone()) {
+            trace!(target: "stratum", "Pushing work for {} workers", workers.len());
+            for (ref addr, worker) in workers.iter() {
+                let worker_id = &worker.worker_id;
+                let workers_msg = match message(addr) {
+                    Some(msg) => msg,
+                    None => continue,
+                };
+                trace!(target: "stratum", "Pushing work to {} at addr {} (payload: '{}')", &worker_id, &addr, &workers_msg);
+                match tcp_dispatcher.push_message(addr, workers_msg) {
                     Err(PushMessageError::NoSuchPeer) => {
                         debug!(target: "stratum", "Worker no longer connected: {} addr {}", &worker_id, &addr);
                         hup_peers.insert(**addr);
@@ -339,6 +466,7 @@ mod tests {
             &"127.0.0.1:19980".parse().unwrap(),
             Arc::new(VoidManager),
             None,
+            None,
         );
         assert!(stratum.is_ok());
     }
@@ -381,6 +509,7 @@ mod tests {
                     .of_initial(r#"["dummy autorize payload"]"#),
             ),
             None,
+            None,
         )
         .expect("There should be no error starting stratum");
 
@@ -395,6 +524,88 @@ mod tests {
         assert_eq!(1, stratum.implementation.workers.read().len());
     }
 
+    #[test]
+    fn can_subscribe_with_share_difficulty() {
+        let addr = "127.0.0.1:19971".parse().unwrap();
+        let stratum = Stratum::start(
+            &addr,
+            Arc::new(DummyManager::build()),
+            None,
+            Some(VardiffConfig {
+                initial_difficulty: U256::from(1000),
+                min_difficulty: U256::from(100),
+                target_share_interval: None,
+            }),
+        )
+        .expect("There should be no error starting stratum");
+
+        let request = r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner1", ""], "id": 1}"#;
+        dummy_request(&addr, request);
+
+        let workers = stratum.implementation.workers.read();
+        let worker = workers.values().next().expect("Worker is registered");
+        assert_eq!(worker.share_difficulty(), Some(U256::from(1000)));
+    }
+
+    #[derive(Default)]
+    struct ShareManager {
+        share_difficulties: parking_lot::Mutex<Vec<Option<U256>>>,
+    }
+
+    impl JobDispatcher for ShareManager {
+        fn submit(&self, _payload: Vec<String>) -> Result<(), Error> { Ok(()) }
+
+        fn submit_share(
+            &self, _payload: Vec<String>, share_difficulty: Option<U256>,
+        ) -> Result<(), Error> {
+            self.share_difficulties.lock().push(share_difficulty);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn validates_share_against_job_difficulty() {
+        let config = VardiffConfig {
+            initial_difficulty: U256::from(1000),
+            min_difficulty: U256::from(100),
+            target_share_interval: None,
+        };
+        let dispatcher = Arc::new(ShareManager::default());
+        let implementation = StratumImpl {
+            dispatcher: dispatcher.clone(),
+            workers: Default::default(),
+            secret: None,
+            vardiff: Some(config.clone()),
+            notify_counter: RwLock::new(NOTIFY_COUNTER_INITIAL),
+        };
+        let meta = SocketMetadata::default();
+        // The job was pushed before the share difficulty is retargeted to
+        // 1000.
+        let mut worker = Worker {
+            worker_id: "miner1".into(),
+            difficulty: Some(WorkerDifficulty::new(&config, Instant::now())),
+            jobs: HashMap::new(),
+        };
+        let job = Job {
+            id: "job1".into(),
+            payload: "[]".into(),
+        };
+        worker.on_job(&job, Some(U256::from(500)));
+        implementation.workers.write().insert(*meta.addr(), worker);
+
+        for job_id in &["job1", "stale job"] {
+            let params = Params::Array(vec![
+                Value::String("miner1".into()),
+                Value::String(job_id.to_string()),
+            ]);
+            implementation.submit(params, meta.clone()).unwrap();
+        }
+        assert_eq!(
+            *dispatcher.share_difficulties.lock(),
+            vec![Some(U256::from(500)), None]
+        );
+    }
+
     #[test]
     fn can_push_work() {
         let _ = ::env_logger::try_init();
@@ -407,6 +618,7 @@ mod tests {
                     .of_initial(r#"["dummy autorize payload"]"#),
             ),
             None,
+            None,
         )
         .expect("There should be no error starting stratum");
 
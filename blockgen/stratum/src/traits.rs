@@ -18,7 +18,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity Ethereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use cfx_types::H256;
+use cfx_types::{H256, U256};
 use jsonrpc_tcp_server::PushMessageError;
 use std;
 
@@ -46,6 +46,28 @@ impl From<PushMessageError> for Error {
 pub trait JobDispatcher: Send + Sync {
     // miner job result
     fn submit(&self, payload: Vec<String>) -> Result<(), Error>;
+
+    /// Submit a share of a job pushed with the share difficulty
+    /// `share_difficulty`. The share is only accepted as a solution if
+    /// `share_difficulty` is `None`.
+    fn submit_share(
+        &self, payload: Vec<String>, _share_difficulty: Option<U256>,
+    ) -> Result<(), Error> {
+        self.submit(payload)
+    }
+
+    /// The current job for a worker whose share difficulty is
+    /// `share_difficulty`, if any.
+    fn job(&self, _share_difficulty: Option<U256>) -> Option<Job> { None }
+}
+
+/// A job pushed to the workers
+pub struct Job {
+    /// The id the shares of the job are submitted with, which differs for
+    /// each share difficulty
+    pub id: String,
+    /// json of pow-specific set of work specification
+    pub payload: String,
 }
 
 /// Interface that can handle requests to push job for workers
@@ -53,6 +75,10 @@ pub trait PushWorkHandler: Send + Sync {
     /// push the same work package for all workers (`payload`: json of
     /// pow-specific set of work specification)
     fn push_work_all(&self, payload: String) -> Result<(), Error>;
+
+    /// push the current job of the dispatcher for each worker, with the share
+    /// difficulty of the worker
+    fn push_job_all(&self) -> Result<(), Error>;
 }
 
 pub struct ServiceConfiguration {
@@ -0,0 +1,154 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Per-connection share difficulty with variable difficulty (vardiff)
+//! adjustment.
+
+use cfx_types::U256;
+use std::time::{Duration, Instant};
+
+/// The number of shares after which the share difficulty is retargeted.
+const RETARGET_SHARE_COUNT: u32 = 16;
+/// The maximal factor by which the share difficulty changes at a retarget.
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Configures the share difficulty of the workers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VardiffConfig {
+    /// The share difficulty of a newly subscribed worker.
+    pub initial_difficulty: U256,
+    /// The lower bound of the share difficulty.
+    pub min_difficulty: U256,
+    /// The expected interval between the shares of a worker, to which the
+    /// share difficulty is adjusted. If it's `None`, the share difficulty is
+    /// fixed.
+    pub target_share_interval: Option<Duration>,
+}
+
+/// The share difficulty is at least 1.
+fn min_difficulty(config: &VardiffConfig) -> U256 {
+    config.min_difficulty.max(U256::one())
+}
+
+/// The share difficulty of a worker.
+#[derive(Debug, Clone)]
+pub struct WorkerDifficulty {
+    difficulty: U256,
+    shares_since_retarget: u32,
+    last_retarget: Instant,
+}
+
+impl WorkerDifficulty {
+    pub fn new(config: &VardiffConfig, now: Instant) -> Self {
+        WorkerDifficulty {
+            difficulty: config.initial_difficulty.max(min_difficulty(config)),
+            shares_since_retarget: 0,
+            last_retarget: now,
+        }
+    }
+
+    pub fn difficulty(&self) -> U256 { self.difficulty }
+
+    /// Record an accepted share, and return the new difficulty if it's
+    /// retargeted.
+    ///
+    /// The difficulty is retargeted after `RETARGET_SHARE_COUNT` shares, or
+    /// earlier if the shares are slower than expected, so that a worker
+    /// starting with a too high difficulty is not stuck.
+    pub fn on_share(
+        &mut self, config: &VardiffConfig, now: Instant,
+    ) -> Option<U256> {
+        let target = config.target_share_interval?;
+        self.shares_since_retarget += 1;
+        let elapsed = now.saturating_duration_since(self.last_retarget);
+        if self.shares_since_retarget < RETARGET_SHARE_COUNT
+            && elapsed < target * RETARGET_SHARE_COUNT
+        {
+            return None;
+        }
+
+        // difficulty * (target * shares) / elapsed, bounded by the
+        // retarget factor.
+        let expected_ms = U256::from(target.as_millis() as u64)
+            * U256::from(self.shares_since_retarget);
+        let elapsed_ms = U256::from((elapsed.as_millis() as u64).max(1));
+        let lower = self.difficulty / U256::from(MAX_RETARGET_FACTOR);
+        let upper = self
+            .difficulty
+            .saturating_mul(U256::from(MAX_RETARGET_FACTOR));
+        let adjusted = match self.difficulty.checked_mul(expected_ms) {
+            Some(product) => product / elapsed_ms,
+            None => upper,
+        };
+        let new_difficulty =
+            adjusted.max(lower).min(upper).max(min_difficulty(config));
+
+        self.shares_since_retarget = 0;
+        self.last_retarget = now;
+        if new_difficulty == self.difficulty {
+            None
+        } else {
+            self.difficulty = new_difficulty;
+            Some(new_difficulty)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(target_share_interval: Option<Duration>) -> VardiffConfig {
+        VardiffConfig {
+            initial_difficulty: U256::from(1000),
+            min_difficulty: U256::from(100),
+            target_share_interval,
+        }
+    }
+
+    #[test]
+    fn test_fixed_difficulty() {
+        let config = config(None);
+        let start = Instant::now();
+        let mut worker = WorkerDifficulty::new(&config, start);
+        for i in 0..100 {
+            assert_eq!(
+                worker.on_share(&config, start + Duration::from_millis(i)),
+                None
+            );
+        }
+        assert_eq!(worker.difficulty(), U256::from(1000));
+    }
+
+    #[test]
+    fn test_retarget_up() {
+        let config = config(Some(Duration::from_secs(10)));
+        let start = Instant::now();
+        let mut worker = WorkerDifficulty::new(&config, start);
+        // The shares are found twice as fast as expected.
+        for i in 1..RETARGET_SHARE_COUNT {
+            let now = start + Duration::from_secs(5 * i as u64);
+            assert_eq!(worker.on_share(&config, now), None);
+        }
+        let now = start + Duration::from_secs(5 * RETARGET_SHARE_COUNT as u64);
+        assert_eq!(worker.on_share(&config, now), Some(U256::from(2000)));
+        assert_eq!(worker.difficulty(), U256::from(2000));
+    }
+
+    #[test]
+    fn test_retarget_down_bounded() {
+        let config = config(Some(Duration::from_secs(1)));
+        let start = Instant::now();
+        let mut worker = WorkerDifficulty::new(&config, start);
+        // A single share after a long time retargets immediately, by at most
+        // the retarget factor.
+        let now = start + Duration::from_secs(1000);
+        assert_eq!(worker.on_share(&config, now), Some(U256::from(250)));
+        // It never drops below the minimal difficulty.
+        let now = now + Duration::from_secs(1000);
+        assert_eq!(worker.on_share(&config, now), Some(U256::from(100)));
+        let now = now + Duration::from_secs(1000);
+        assert_eq!(worker.on_share(&config, now), None);
+    }
+}
@@ -0,0 +1,356 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The CIP-37 base32 address format, e.g.
+//! `cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p`. An address is prefixed
+//! by its network, and ends with a 40-bit BCH checksum of the network prefix
+//! and the address, so that mistyped addresses and the addresses of other
+//! networks are rejected.
+
+use super::{
+    address_util::{
+        AddressUtil, TYPE_BITS_BUILTIN, TYPE_BITS_CONTRACT,
+        TYPE_BITS_USER_ACCOUNT,
+    },
+    Address,
+};
+use std::fmt;
+
+pub const MAINNET_ID: u64 = 1029;
+pub const TESTNET_ID: u64 = 1;
+
+const MAINNET_PREFIX: &str = "cfx";
+const TESTNET_PREFIX: &str = "cfxtest";
+const OTHER_NETWORK_PREFIX: &str = "net";
+
+const CHARSET: &[u8; 32] = b"abcdefghjkmnprstuvwxyz0123456789";
+
+/// The version byte of 160-bit addresses.
+const VERSION_BYTE: u8 = 0x00;
+
+const CHECKSUM_LEN: usize = 8;
+
+/// The length of the version byte and the address in 5-bit groups.
+const PAYLOAD_LEN: usize = 34;
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MixedCase,
+    MissingPrefix,
+    InvalidPrefix(String),
+    InvalidOption(String),
+    InvalidLength(usize),
+    InvalidChar(char),
+    InvalidPadding,
+    InvalidVersion(u8),
+    InvalidChecksum,
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodingError::MixedCase => write!(f, "mixed case"),
+            DecodingError::MissingPrefix => write!(f, "missing network prefix"),
+            DecodingError::InvalidPrefix(prefix) => {
+                write!(f, "invalid network prefix {}", prefix)
+            }
+            DecodingError::InvalidOption(option) => {
+                write!(f, "invalid option {}", option)
+            }
+            DecodingError::InvalidLength(len) => {
+                write!(f, "invalid length {}", len)
+            }
+            DecodingError::InvalidChar(c) => {
+                write!(f, "invalid character {:?}", c)
+            }
+            DecodingError::InvalidPadding => write!(f, "invalid padding"),
+            DecodingError::InvalidVersion(version) => {
+                write!(f, "invalid version byte {:#04x}", version)
+            }
+            DecodingError::InvalidChecksum => write!(f, "invalid checksum"),
+        }
+    }
+}
+
+/// The network prefix of the addresses on network `network_id`.
+pub fn network_prefix(network_id: u64) -> String {
+    match network_id {
+        MAINNET_ID => MAINNET_PREFIX.into(),
+        TESTNET_ID => TESTNET_PREFIX.into(),
+        _ => format!("{}{}", OTHER_NETWORK_PREFIX, network_id),
+    }
+}
+
+fn network_id(prefix: &str) -> Result<u64, DecodingError> {
+    let invalid = || DecodingError::InvalidPrefix(prefix.into());
+    match prefix {
+        MAINNET_PREFIX => Ok(MAINNET_ID),
+        TESTNET_PREFIX => Ok(TESTNET_ID),
+        _ if prefix.starts_with(OTHER_NETWORK_PREFIX) => {
+            let id = &prefix[OTHER_NETWORK_PREFIX.len()..];
+            // the ids with a named prefix and the ids with leading zeros are
+            // not accepted, so that each network has a unique prefix
+            let network_id = match id.parse::<u64>() {
+                Ok(network_id) if network_id.to_string() == id => network_id,
+                _ => return Err(invalid()),
+            };
+            match network_id {
+                MAINNET_ID | TESTNET_ID => Err(invalid()),
+                _ => Ok(network_id),
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn address_type(address: &Address) -> &'static str {
+    if address.is_null_address() {
+        return "null";
+    }
+    match address.address_type_bits() {
+        TYPE_BITS_BUILTIN => "builtin",
+        TYPE_BITS_CONTRACT => "contract",
+        TYPE_BITS_USER_ACCOUNT => "user",
+        _ => "unknown",
+    }
+}
+
+/// Encode `address` on network `network_id`. The verbose format is in upper
+/// case and includes the address type, e.g. `CFX:TYPE.USER:AAJG...`.
+pub fn encode(address: &Address, network_id: u64, verbose: bool) -> String {
+    let prefix = network_prefix(network_id);
+
+    let mut bytes = vec![VERSION_BYTE];
+    bytes.extend_from_slice(address.as_bytes());
+    let mut payload =
+        convert_bits(&bytes, 8, 5, true).expect("padding is allowed; qed");
+
+    let checksum = polymod(&checksum_input(&prefix, &payload, true));
+    for i in (0..CHECKSUM_LEN).rev() {
+        payload.push(((checksum >> (5 * i)) & 0x1f) as u8);
+    }
+
+    let encoded: String = payload
+        .iter()
+        .map(|&x| CHARSET[x as usize] as char)
+        .collect();
+
+    if verbose {
+        format!("{}:type.{}:{}", prefix, address_type(address), encoded)
+            .to_uppercase()
+    } else {
+        format!("{}:{}", prefix, encoded)
+    }
+}
+
+/// Decode a base32 address in either the short or the verbose format, and
+/// return the address with the id of its network.
+pub fn decode(encoded: &str) -> Result<(Address, u64), DecodingError> {
+    let lowercase = encoded.to_lowercase();
+    if lowercase != encoded && encoded.to_uppercase() != encoded {
+        return Err(DecodingError::MixedCase);
+    }
+
+    let parts: Vec<&str> = lowercase.split(':').collect();
+    if parts.len() < 2 {
+        return Err(DecodingError::MissingPrefix);
+    }
+    let prefix = parts[0];
+    let network_id = network_id(prefix)?;
+    let options = &parts[1..parts.len() - 1];
+    let encoded_payload = parts[parts.len() - 1];
+
+    if encoded_payload.len() != PAYLOAD_LEN + CHECKSUM_LEN {
+        return Err(DecodingError::InvalidLength(encoded_payload.len()));
+    }
+    let payload = encoded_payload
+        .chars()
+        .map(|c| {
+            CHARSET
+                .iter()
+                .position(|&x| x as char == c)
+                .map(|x| x as u8)
+                .ok_or(DecodingError::InvalidChar(c))
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+
+    if polymod(&checksum_input(prefix, &payload, false)) != 0 {
+        return Err(DecodingError::InvalidChecksum);
+    }
+
+    let bytes = convert_bits(&payload[..PAYLOAD_LEN], 5, 8, false)
+        .ok_or(DecodingError::InvalidPadding)?;
+    if bytes[0] != VERSION_BYTE {
+        return Err(DecodingError::InvalidVersion(bytes[0]));
+    }
+    let address = Address::from_slice(&bytes[1..]);
+
+    for option in options {
+        match option.splitn(2, '.').collect::<Vec<_>>()[..] {
+            ["type", address_type_name]
+                if address_type_name == address_type(&address) => {}
+            _ => return Err(DecodingError::InvalidOption(option.to_string())),
+        }
+    }
+
+    Ok((address, network_id))
+}
+
+/// The input of the checksum: the lower 5 bits of each prefix character, a
+/// zero separator, the payload, and with `template`, zeros in place of the
+/// checksum.
+fn checksum_input(prefix: &str, payload: &[u8], template: bool) -> Vec<u8> {
+    let mut input: Vec<u8> = prefix.bytes().map(|b| b & 0x1f).collect();
+    input.push(0);
+    input.extend_from_slice(payload);
+    if template {
+        input.extend_from_slice(&[0; CHECKSUM_LEN]);
+    }
+    input
+}
+
+/// The BCH code of cashaddr, over 5-bit groups.
+fn polymod(values: &[u8]) -> u64 {
+    let mut c = 1u64;
+    for &value in values {
+        let c0 = (c >> 35) as u8;
+        c = ((c & 0x07_ffff_ffff) << 5) ^ value as u64;
+        if c0 & 0x01 != 0 {
+            c ^= 0x98_f2bc_8e61;
+        }
+        if c0 & 0x02 != 0 {
+            c ^= 0x79_b76d_99e2;
+        }
+        if c0 & 0x04 != 0 {
+            c ^= 0xf3_3e5f_b3c4;
+        }
+        if c0 & 0x08 != 0 {
+            c ^= 0xae_2eab_e2a8;
+        }
+        if c0 & 0x10 != 0 {
+            c ^= 0x1e_4f43_e470;
+        }
+    }
+    c ^ 1
+}
+
+/// Regroup `data` of `from`-bit groups into `to`-bit groups. Without `pad`,
+/// the remaining bits must be zeros and fewer than `from`.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let mut result = Vec::new();
+    let max_value = (1u32 << to) - 1;
+    for &value in data {
+        acc = (acc << from) | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & max_value) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max_value != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, DecodingError, MAINNET_ID, TESTNET_ID};
+    use crate::Address;
+    use std::str::FromStr;
+
+    fn address(hex: &str) -> Address { Address::from_str(hex).unwrap() }
+
+    #[test]
+    fn test_encode_decode() {
+        let user = address("106d49f8505410eb4e671d51f7d96d2c87807b09");
+        let cases = [
+            (
+                MAINNET_ID,
+                "cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p",
+                "CFX:TYPE.USER:AAJG4WT2MBMBB44SP6SZD783RY0JTAD5BEA80XDY7P",
+            ),
+            (
+                TESTNET_ID,
+                "cfxtest:aajg4wt2mbmbb44sp6szd783ry0jtad5bemzfdf83g",
+                "CFXTEST:TYPE.USER:AAJG4WT2MBMBB44SP6SZD783RY0JTAD5BEMZFDF83G",
+            ),
+        ];
+        for (network_id, short, verbose) in &cases {
+            assert_eq!(encode(&user, *network_id, false), *short);
+            assert_eq!(encode(&user, *network_id, true), *verbose);
+            assert_eq!(decode(short), Ok((user, *network_id)));
+            assert_eq!(decode(verbose), Ok((user, *network_id)));
+        }
+
+        for hex in &[
+            "0000000000000000000000000000000000000000",
+            "0888000000000000000000000000000000000002",
+            "806d49f8505410eb4e671d51f7d96d2c87807b09",
+            "ffffffffffffffffffffffffffffffffffffffff",
+        ] {
+            let address = address(hex);
+            for network_id in &[MAINNET_ID, TESTNET_ID, 10001] {
+                for verbose in &[false, true] {
+                    let encoded = encode(&address, *network_id, *verbose);
+                    assert_eq!(decode(&encoded), Ok((address, *network_id)));
+                }
+            }
+        }
+        let builtin = address("0888000000000000000000000000000000000002");
+        assert!(
+            encode(&builtin, 10001, true).starts_with("NET10001:TYPE.BUILTIN:")
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let valid = "cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p";
+        assert_eq!(
+            decode("cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7P"),
+            Err(DecodingError::MixedCase)
+        );
+        assert_eq!(
+            decode("aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p"),
+            Err(DecodingError::MissingPrefix)
+        );
+        for prefix in &["bch", "net1029", "net1", "net01", "net"] {
+            let encoded = valid.replacen("cfx", prefix, 1);
+            assert_eq!(
+                decode(&encoded),
+                Err(DecodingError::InvalidPrefix(prefix.to_string()))
+            );
+        }
+        // a mistyped character
+        assert_eq!(
+            decode("cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7r"),
+            Err(DecodingError::InvalidChecksum)
+        );
+        // the address of another network
+        assert_eq!(
+            decode("cfxtest:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p"),
+            Err(DecodingError::InvalidChecksum)
+        );
+        assert_eq!(
+            decode("cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7"),
+            Err(DecodingError::InvalidLength(41))
+        );
+        assert_eq!(
+            decode("cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7o"),
+            Err(DecodingError::InvalidChar('o'))
+        );
+        assert_eq!(
+            decode(
+                "cfx:type.contract:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p"
+            ),
+            Err(DecodingError::InvalidOption("type.contract".into()))
+        );
+    }
+}
@@ -4,6 +4,8 @@
 
 extern crate ethereum_types;
 
+pub mod base32_address;
+
 pub use ethereum_types::{
     Address, BigEndianHash, Bloom, BloomInput, Public, Secret, Signature, H128,
     H160, H256, H512, H520, H64, U128, U256, U512, U64,
@@ -48,7 +48,7 @@ fn secret_store(
 }
 
 /// Default data path
-fn default_data_path() -> String {
+pub fn default_data_path() -> String {
     let app_info = AppInfo {
         name: "Conflux",
         author: "conflux",
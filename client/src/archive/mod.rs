@@ -3,11 +3,15 @@
 // See http://www.gnu.org/licenses/
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
 use crate::{
-    common::{initialize_not_light_node_modules, ClientComponents},
+    common::{
+        close_rpc_servers, initialize_not_light_node_modules, ClientComponents,
+        ClientServices,
+    },
     configuration::Configuration,
 };
 use blockgen::BlockGenerator;
@@ -23,6 +27,8 @@ use std::sync::Arc;
 pub struct ArchiveClientExtraComponents {
     pub consensus: Arc<ConsensusGraph>,
     pub debug_rpc_http_server: Option<HttpServer>,
+    pub debug_rpc_ipc_server: Option<IpcServer>,
+    pub explorer_http_server: Option<HttpServer>,
     pub rpc_http_server: Option<HttpServer>,
     pub rpc_tcp_server: Option<TcpServer>,
     pub rpc_ws_server: Option<WsServer>,
@@ -42,6 +48,24 @@ impl MallocSizeOf for ArchiveClientExtraComponents {
     }
 }
 
+impl ClientServices for ArchiveClientExtraComponents {
+    fn stop_services(&mut self) {
+        close_rpc_servers(
+            self.debug_rpc_http_server.take(),
+            self.debug_rpc_ipc_server.take(),
+            self.rpc_http_server.take(),
+            self.rpc_tcp_server.take(),
+            self.rpc_ws_server.take(),
+        );
+        if let Some(server) = self.explorer_http_server.take() {
+            server.close();
+        }
+        self.sync.stop_network();
+    }
+
+    fn is_importing_blocks(&self) -> bool { self.sync.is_importing_blocks() }
+}
+
 pub struct ArchiveClient {}
 
 impl ArchiveClient {
@@ -61,9 +85,11 @@ impl ArchiveClient {
             sync,
             blockgen,
             debug_rpc_http_server,
+            debug_rpc_ipc_server,
             rpc_http_server,
             rpc_tcp_server,
             rpc_ws_server,
+            explorer_http_server,
             runtime,
         ) = initialize_not_light_node_modules(&conf, exit, NodeType::Archive)?;
         Ok(Box::new(ClientComponents {
@@ -72,6 +98,8 @@ impl ArchiveClient {
             other_components: ArchiveClientExtraComponents {
                 consensus,
                 debug_rpc_http_server,
+                debug_rpc_ipc_server,
+                explorer_http_server,
                 rpc_http_server,
                 rpc_tcp_server,
                 rpc_ws_server,
@@ -0,0 +1,239 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Export blocks to and import blocks from flat files of RLP encoded blocks,
+//! for offline backups and setting up the fixtures of private chains.
+
+use crate::{
+    common::{initialize_consensus_modules, initialize_data_manager},
+    configuration::Configuration,
+};
+use cfx_parameters::block::MAX_BLOCK_SIZE_IN_BYTES;
+use cfxcore::{block_data_manager::BlockDataManager, NodeType};
+use parking_lot::{Condvar, Mutex};
+use primitives::Block;
+use rlp::{Encodable, Rlp};
+use secret_store::SecretStore;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    sync::{Arc, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// The transactions of a block take at most `MAX_BLOCK_SIZE_IN_BYTES`, and
+/// its header and the RLP headers of the transactions take far less than the
+/// margin.
+const MAX_BLOCK_RLP_BYTES: usize = MAX_BLOCK_SIZE_IN_BYTES + 64 * 1024;
+
+/// Export the blocks of the epochs from `from` to `to` into the file at
+/// `path`. The blocks are written in the order of the epochs, so that a block
+/// always follows its parent and referees. If `to` is `None`, the blocks are
+/// exported until the last executed epoch.
+pub fn export_blocks(
+    conf: Configuration, from: u64, to: Option<u64>, path: &str,
+) -> Result<String, String> {
+    if from == 0 {
+        return Err(
+            "The genesis block is not exported, start from epoch 1".into()
+        );
+    }
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let secret_store = SecretStore::new();
+    let (_, data_man, _) =
+        initialize_data_manager(&conf, &secret_store, exit.clone())?;
+
+    let export = || -> Result<String, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {:?}", path, e))?;
+        let mut writer = BufWriter::new(file);
+        let mut epoch = from;
+        let mut count = 0;
+        while to.map_or(true, |to| epoch <= to) {
+            let hashes = match data_man.all_epoch_set_hashes_from_db(epoch) {
+                Some(hashes) => hashes,
+                None if to.is_none() => break,
+                None => return Err(format!("Epoch {} is not executed", epoch)),
+            };
+            for hash in hashes {
+                let block = data_man
+                    .block_by_hash(&hash, false /* update_cache */)
+                    .ok_or_else(|| format!("Block {:?} is not found", hash))?;
+                writer
+                    .write_all(&block.rlp_bytes())
+                    .map_err(|e| format!("Failed to write block: {:?}", e))?;
+                count += 1;
+            }
+            epoch += 1;
+        }
+        writer
+            .flush()
+            .map_err(|e| format!("Failed to write block: {:?}", e))?;
+        Ok(format!(
+            "Exported {} blocks of epoch {} to {}",
+            count,
+            from,
+            epoch - 1
+        ))
+    };
+    let result = export();
+
+    // Stop the storage usage logger.
+    *exit.0.lock() = true;
+    exit.1.notify_all();
+    result
+}
+
+/// Import the blocks in the file at `path` with full verification. The
+/// blocks must be ordered so that a block follows its parent and referees,
+/// as in the files written by `export_blocks`.
+pub fn import_blocks(
+    conf: Configuration, path: &str,
+) -> Result<String, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open {}: {:?}", path, e))?;
+    let mut reader = BufReader::new(file);
+
+    let shutdown_timeout = conf.shutdown_timeout();
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let (_, _, _, data_man, _, _, _, sync_graph, _) =
+        initialize_consensus_modules(&conf, exit.clone(), NodeType::Archive)?;
+    let data_man = Arc::downgrade(&data_man);
+
+    let mut import = || -> Result<String, String> {
+        // The parents of the imported blocks are looked up in the graph.
+        sync_graph.recover_graph_from_db(false /* header_only */);
+
+        let (mut imported, mut existing) = (0, 0);
+        while let Some(bytes) = read_rlp_item(&mut reader, MAX_BLOCK_RLP_BYTES)
+            .map_err(|e| format!("Failed to read block: {:?}", e))?
+        {
+            let index = imported + existing;
+            let block: Block = Rlp::new(&bytes)
+                .as_val()
+                .map_err(|e| format!("Invalid block {}: {:?}", index, e))?;
+            let hash = block.hash();
+            match sync_graph.import_block(block) {
+                Ok(true) => imported += 1,
+                Ok(false) => existing += 1,
+                Err(e) => {
+                    return Err(format!(
+                        "Failed to import block {} {:?}: {}",
+                        index, hash, e
+                    ))
+                }
+            }
+        }
+
+        while sync_graph.is_consensus_worker_busy() {
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(format!(
+            "Imported {} blocks, {} blocks already exist",
+            imported, existing
+        ))
+    };
+    let result = import();
+
+    drop(sync_graph);
+    *exit.0.lock() = true;
+    exit.1.notify_all();
+    // The background threads hold the data manager until they stop, and the
+    // database is closed with the data manager.
+    if !wait_for_release(&data_man, shutdown_timeout) {
+        warn!("The database is not closed within the shutdown timeout");
+    }
+    result
+}
+
+fn wait_for_release(
+    data_man: &Weak<BlockDataManager>, timeout: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    while data_man.upgrade().is_some() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    true
+}
+
+/// Read the next RLP list from `reader`, or `None` at the end of the file.
+/// The lists longer than `max_len` bytes are rejected before they are read.
+pub(crate) fn read_rlp_item<R: Read>(
+    reader: &mut R, max_len: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut prefix = [0u8];
+    if reader.read(&mut prefix)? == 0 {
+        return Ok(None);
+    }
+    let mut item = vec![prefix[0]];
+    let payload_len = match prefix[0] {
+        0xc0..=0xf7 => (prefix[0] - 0xc0) as usize,
+        0xf8..=0xff => {
+            let mut len_bytes = vec![0u8; (prefix[0] - 0xf7) as usize];
+            reader.read_exact(&mut len_bytes)?;
+            item.extend_from_slice(&len_bytes);
+            len_bytes
+                .iter()
+                .try_fold(0usize, |len, byte| {
+                    len.checked_mul(256).map(|len| len + *byte as usize)
+                })
+                .unwrap_or(usize::MAX)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a block is not an RLP list",
+            ))
+        }
+    };
+    let header_len = item.len();
+    if payload_len > max_len.saturating_sub(header_len) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("a block of {} bytes is too large", payload_len),
+        ));
+    }
+    item.resize(header_len + payload_len, 0);
+    reader.read_exact(&mut item[header_len..])?;
+    Ok(Some(item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_rlp_item;
+    use rlp::RlpStream;
+
+    #[test]
+    fn test_read_rlp_items() {
+        let mut short = RlpStream::new_list(2);
+        short.append(&1u64).append(&"block");
+        let mut long = RlpStream::new_list(1);
+        long.append(&vec![7u8; 1000]);
+        let (short, long) = (short.out(), long.out());
+
+        let data = [short.clone(), long.clone()].concat();
+        let mut reader = &data[..];
+        assert_eq!(read_rlp_item(&mut reader, 2000).unwrap(), Some(short));
+        assert_eq!(
+            read_rlp_item(&mut reader, 2000).unwrap(),
+            Some(long.clone())
+        );
+        assert_eq!(read_rlp_item(&mut reader, 2000).unwrap(), None);
+
+        // A truncated item.
+        let mut reader = &long[..long.len() - 1];
+        assert!(read_rlp_item(&mut reader, 2000).is_err());
+
+        // An item longer than the limit is rejected without allocating it.
+        let mut reader = &long[..];
+        assert!(read_rlp_item(&mut reader, 1000).is_err());
+        let mut reader = &[0xffu8; 9][..];
+        assert!(read_rlp_item(&mut reader, 1000).is_err());
+    }
+}
@@ -25,7 +25,7 @@ impl<BlockGenT, Rest: MallocSizeOf> MallocSizeOf
     }
 }
 
-impl<BlockGenT: 'static + Stopable, Rest> ClientTrait
+impl<BlockGenT: 'static + Stopable, Rest: ClientServices> ClientTrait
     for ClientComponents<BlockGenT, Rest>
 {
     fn take_out_components_for_shutdown(
@@ -39,18 +39,65 @@ impl<BlockGenT: 'static + Stopable, Rest> ClientTrait
 
         (data_manager_weak_ptr, blockgen)
     }
+
+    fn stop_services(&mut self) { self.other_components.stop_services(); }
+
+    fn is_importing_blocks(&self) -> bool {
+        self.other_components.is_importing_blocks()
+    }
 }
 
 pub trait ClientTrait {
     fn take_out_components_for_shutdown(
         &self,
     ) -> (Weak<BlockDataManager>, Option<Arc<dyn Stopable>>);
+
+    /// Stop accepting RPC requests and P2P messages.
+    fn stop_services(&mut self);
+
+    /// Whether there are received blocks not processed by consensus yet.
+    fn is_importing_blocks(&self) -> bool;
+}
+
+/// The services through which a client receives work, which are stopped
+/// first in a shutdown.
+pub trait ClientServices {
+    fn stop_services(&mut self);
+
+    fn is_importing_blocks(&self) -> bool;
+}
+
+/// Close the RPC servers, so that no more requests are accepted.
+pub fn close_rpc_servers(
+    debug_rpc_http_server: Option<HttpServer>,
+    debug_rpc_ipc_server: Option<IpcServer>,
+    rpc_http_server: Option<HttpServer>, rpc_tcp_server: Option<TcpServer>,
+    rpc_ws_server: Option<WSServer>,
+)
+{
+    if let Some(server) = debug_rpc_http_server {
+        server.close();
+    }
+    if let Some(server) = debug_rpc_ipc_server {
+        server.close();
+    }
+    if let Some(server) = rpc_http_server {
+        server.close();
+    }
+    if let Some(server) = rpc_tcp_server {
+        server.close();
+    }
+    if let Some(server) = rpc_ws_server {
+        server.close();
+    }
 }
 
 pub mod client_methods {
     pub fn run(
         this: Box<dyn ClientTrait>, exit_cond_var: Arc<(Mutex<bool>, Condvar)>,
-    ) -> bool {
+        shutdown_timeout: Duration,
+    ) -> bool
+    {
         CtrlC::set_handler({
             let e = exit_cond_var.clone();
             move || {
@@ -64,37 +111,64 @@ pub mod client_methods {
             exit_cond_var.1.wait(&mut lock);
         }
 
-        shutdown(this)
+        shutdown(this, shutdown_timeout)
     }
 
-    /// Returns whether the shutdown is considered clean.
-    pub fn shutdown(this: Box<dyn ClientTrait>) -> bool {
+    /// Shut down the client in order:
+    /// 1. Stop the block generator, the RPC servers and the network, so that no
+    ///    more work is accepted.
+    /// 2. Wait for the received blocks to be processed by consensus, so that
+    ///    they don't need to be recovered on restart.
+    /// 3. Drop all the components, so that the data is flushed and the
+    ///    databases are closed.
+    ///
+    /// Returns whether the shutdown is considered clean, i.e. it's finished
+    /// within `timeout`.
+    pub fn shutdown(mut this: Box<dyn ClientTrait>, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
         let (ledger_db, maybe_blockgen) =
             this.take_out_components_for_shutdown();
-        drop(this);
         if let Some(blockgen) = maybe_blockgen {
             blockgen.stop();
             drop(blockgen);
         }
+        this.stop_services();
+        info!("Services stopped, draining block import.");
+
+        if !wait_until(deadline, || !this.is_importing_blocks()) {
+            warn!("Shutdown deadline reached with blocks not processed.");
+        }
+        drop(this);
 
         // Make sure ledger_db is properly dropped, so rocksdb can be closed
         // cleanly
-        check_graceful_shutdown(ledger_db)
+        check_graceful_shutdown(ledger_db, deadline)
     }
 
     /// Most Conflux components references block data manager.
     /// When block data manager is freed, all background threads must have
     /// already stopped.
     fn check_graceful_shutdown(
-        blockdata_manager_weak_ptr: Weak<BlockDataManager>,
+        blockdata_manager_weak_ptr: Weak<BlockDataManager>, deadline: Instant,
     ) -> bool {
-        let sleep_duration = Duration::from_secs(1);
+        if wait_until(deadline, || {
+            blockdata_manager_weak_ptr.upgrade().is_none()
+        }) {
+            return true;
+        }
+        eprintln!("Shutdown timeout reached, exiting uncleanly.");
+        false
+    }
+
+    /// Wait until `done` returns true or `deadline` is reached, and return
+    /// whether it's done.
+    fn wait_until<F: Fn() -> bool>(deadline: Instant, done: F) -> bool {
+        let sleep_duration = Duration::from_millis(100);
         let warn_timeout = Duration::from_secs(5);
-        let max_timeout = Duration::from_secs(1200);
         let instant = Instant::now();
         let mut warned = false;
-        while instant.elapsed() < max_timeout {
-            if blockdata_manager_weak_ptr.upgrade().is_none() {
+        while Instant::now() < deadline {
+            if done() {
                 return true;
             }
             if !warned && instant.elapsed() > warn_timeout {
@@ -103,8 +177,7 @@ pub mod client_methods {
             }
             thread::sleep(sleep_duration);
         }
-        eprintln!("Shutdown timeout reached, exiting uncleanly.");
-        false
+        done()
     }
     use super::ClientTrait;
     use cfxcore::block_data_manager::BlockDataManager;
@@ -117,47 +190,36 @@ pub mod client_methods {
     };
 }
 
-pub fn initialize_common_modules(
-    conf: &Configuration, exit: Arc<(Mutex<bool>, Condvar)>,
-    node_type: NodeType,
+/// Open the ledger database and the storage, and load the genesis block.
+/// Returns the genesis accounts, the data manager and the pow computer.
+pub fn initialize_data_manager(
+    conf: &Configuration, secret_store: &SecretStore,
+    exit: Arc<(Mutex<bool>, Condvar)>,
 ) -> Result<
     (
-        Arc<Machine>,
-        Arc<SecretStore>,
         HashMap<Address, U256>,
         Arc<BlockDataManager>,
         Arc<PowComputer>,
-        Arc<TransactionPool>,
-        Arc<ConsensusGraph>,
-        Arc<SynchronizationGraph>,
-        Arc<NetworkService>,
-        Arc<CommonRpcImpl>,
-        Arc<AccountProvider>,
-        Arc<Notifications>,
-        PubSubClient,
-        Runtime,
     ),
     String,
 >
 {
-    info!("Working directory: {:?}", std::env::current_dir());
-
-    metrics::initialize(conf.metrics_config());
-
     let worker_thread_pool = Arc::new(Mutex::new(ThreadPool::with_name(
         "Tx Recover".into(),
         WORKER_COMPUTATION_PARALLELISM,
     )));
 
-    let network_config = conf.net_config()?;
     let cache_config = conf.cache_config();
 
     let db_config = conf.db_config();
+    cfxcore::db_migration::migrate_database(
+        conf.raw_conf.block_db_dir.as_str(),
+        &db_config,
+    )?;
     let ledger_db =
         db::open_database(conf.raw_conf.block_db_dir.as_str(), &db_config)
             .map_err(|e| format!("Failed to open database {:?}", e))?;
 
-    let secret_store = Arc::new(SecretStore::new());
     let storage_manager = Arc::new(
         StorageManager::new(conf.storage_config())
             .expect("Failed to initialize storage."),
@@ -184,12 +246,17 @@ pub fn initialize_common_modules(
     }
 
     let genesis_accounts = if conf.is_test_or_dev_mode() {
-        match conf.raw_conf.genesis_secrets {
-            Some(ref file) => {
-                genesis::load_secrets_file(file, secret_store.as_ref())?
-            }
+        let mut accounts = match conf.raw_conf.genesis_secrets {
+            Some(ref file) => genesis::load_secrets_file(file, secret_store)?,
             None => genesis::default(conf.is_test_or_dev_mode()),
+        };
+        // Pre-fund the accounts of the developers.
+        if conf.is_dev_mode() {
+            if let Some(ref file) = conf.raw_conf.genesis_accounts {
+                accounts.extend(genesis::load_file(file)?);
+            }
         }
+        accounts
     } else {
         match conf.raw_conf.genesis_accounts {
             Some(ref file) => genesis::load_file(file)?,
@@ -205,8 +272,9 @@ pub fn initialize_common_modules(
     );
     debug!("Initialize genesis_block={:?}", genesis_block);
 
-    let pow_config = conf.pow_config();
-    let pow = Arc::new(PowComputer::new(pow_config.use_octopus()));
+    let pow_spec = conf.pow_config()?.pow_spec;
+    info!("Proof-of-work algorithms: {:?}", pow_spec);
+    let pow = Arc::new(PowComputer::from_spec(&pow_spec));
 
     let data_man = Arc::new(BlockDataManager::new(
         cache_config,
@@ -218,9 +286,96 @@ pub fn initialize_common_modules(
         pow.clone(),
     ));
 
-    let consensus_conf = conf.consensus_config();
-    let machine =
-        Arc::new(new_machine_with_builtin(consensus_conf.chain_id.clone()));
+    Ok((genesis_accounts, data_man, pow))
+}
+
+/// Initialize the modules to verify, order and execute the blocks, without
+/// the network, the RPC and the mining, e.g. to import blocks offline.
+pub fn initialize_consensus_modules(
+    conf: &Configuration, exit: Arc<(Mutex<bool>, Condvar)>,
+    node_type: NodeType,
+) -> Result<
+    (
+        Arc<Machine>,
+        Arc<SecretStore>,
+        HashMap<Address, U256>,
+        Arc<BlockDataManager>,
+        Arc<PowComputer>,
+        Arc<TransactionPool>,
+        Arc<ConsensusGraph>,
+        Arc<SynchronizationGraph>,
+        Arc<Notifications>,
+    ),
+    String,
+>
+{
+    let secret_store = Arc::new(SecretStore::new());
+    let (genesis_accounts, data_man, pow) =
+        initialize_data_manager(conf, &secret_store, exit.clone())?;
+    let pow_config = conf.pow_config()?;
+
+    let consensus_conf = conf.consensus_config()?;
+    set_address_format(
+        consensus_conf.chain_id.chain_id as u64,
+        conf.raw_conf.jsonrpc_hex_address,
+    );
+    let mut machine = new_machine_with_builtin(consensus_conf.chain_id.clone());
+    // The repriced spec is scheduled first, so that the spec changes below
+    // which activate earlier are kept by it.
+    match (
+        conf.raw_conf.collateral_per_byte_activation_epoch,
+        conf.raw_conf.collateral_per_byte,
+    ) {
+        (Some(epoch), Some(collateral_per_byte)) => {
+            let mut spec = machine.spec(epoch);
+            spec.collateral_per_byte = collateral_per_byte.into();
+            machine.set_spec_at(epoch, spec);
+        }
+        (None, None) => {}
+        _ => {
+            return Err("collateral_per_byte and \
+                        collateral_per_byte_activation_epoch must be set \
+                        together"
+                .into());
+        }
+    }
+    if let Some(epoch) = conf.raw_conf.wasm_activation_epoch {
+        if !cfg!(feature = "wasm") {
+            return Err(
+                "wasm_activation_epoch is set but the wasm engine is not built"
+                    .into(),
+            );
+        }
+        machine.update_spec_from(epoch, |spec| {
+            spec.wasm = Some(Default::default())
+        });
+    }
+    if conf.raw_conf.unused_gas_refund_divisor == Some(0) {
+        return Err("unused_gas_refund_divisor must be positive".into());
+    }
+    machine.update_spec_from(
+        conf.raw_conf.spec_policy_activation_epoch,
+        |spec| {
+            if let Some(max_code_size) = conf.raw_conf.max_code_size {
+                spec.create_data_limit = max_code_size;
+            }
+            if let Some(suicide_enabled) = conf.raw_conf.suicide_enabled {
+                spec.have_suicide = suicide_enabled;
+            }
+            if let Some(divisor) = conf.raw_conf.unused_gas_refund_divisor {
+                spec.unused_gas_refund_divisor = divisor;
+            }
+        },
+    );
+    if let Some(epoch) = conf.raw_conf.batch_transfer_activation_epoch {
+        machine.update_spec_from(epoch, |spec| spec.batch_transfer = true);
+    }
+    if let Some(epoch) = conf.raw_conf.strict_signature_activation_epoch {
+        machine.update_spec_from(epoch, |spec| {
+            spec.signature_policy = SignaturePolicy::strict()
+        });
+    }
+    let machine = Arc::new(machine);
 
     let txpool = Arc::new(TransactionPool::new(
         conf.txpool_config(),
@@ -261,11 +416,92 @@ pub fn initialize_common_modules(
         machine.clone(),
     ));
 
+    Ok((
+        machine,
+        secret_store,
+        genesis_accounts,
+        data_man,
+        pow,
+        txpool,
+        consensus,
+        sync_graph,
+        notifications,
+    ))
+}
+
+pub fn initialize_common_modules(
+    conf: &Configuration, exit: Arc<(Mutex<bool>, Condvar)>,
+    node_type: NodeType,
+) -> Result<
+    (
+        Arc<Machine>,
+        Arc<SecretStore>,
+        HashMap<Address, U256>,
+        Arc<BlockDataManager>,
+        Arc<PowComputer>,
+        Arc<TransactionPool>,
+        Arc<ConsensusGraph>,
+        Arc<SynchronizationGraph>,
+        Arc<NetworkService>,
+        Arc<CommonRpcImpl>,
+        Arc<dyn TransactionSigner>,
+        Arc<Notifications>,
+        PubSubClient,
+        Runtime,
+    ),
+    String,
+>
+{
+    info!("Working directory: {:?}", std::env::current_dir());
+
+    reload::initialize(conf);
+    metrics::initialize(conf.metrics_config());
+    if let Some(watchdog_config) = conf.watchdog_config()? {
+        WATCHDOG.start(watchdog_config);
+    }
+
+    if let Some(report_period_s) = conf.raw_conf.evm_profile_report_period_s {
+        evm_profile::enable();
+        thread::Builder::new()
+            .name("EVM Profile".into())
+            .spawn(move || loop {
+                thread::sleep(Duration::from_secs(report_period_s));
+                // Report the 20 most time consuming opcodes and contracts.
+                info!("{}", evm_profile::report(20));
+            })
+            .expect("EVM profile thread start fails");
+    }
+
+    let network_config = conf.net_config()?;
+    let (
+        machine,
+        secret_store,
+        genesis_accounts,
+        data_man,
+        pow,
+        txpool,
+        consensus,
+        sync_graph,
+        notifications,
+    ) = initialize_consensus_modules(conf, exit.clone(), node_type)?;
+    reload::register(&txpool, |txpool, conf| {
+        txpool.set_policy(conf.tx_pool_packing_strategy());
+        txpool.set_admission_limits(conf.txpool_admission_limits());
+    });
+
     let network = {
         let mut network = NetworkService::new(network_config);
         network.start().unwrap();
         Arc::new(network)
     };
+    reload::register(&network, |network, conf| {
+        network
+            .set_peer_limits(
+                conf.raw_conf.max_outgoing_peers,
+                conf.raw_conf.max_incoming_peers,
+            )
+            .expect("The network is started before registered");
+    });
 
     let refresh_time =
         Duration::from_millis(conf.raw_conf.account_provider_refresh_time_ms);
@@ -279,13 +515,31 @@ pub fn initialize_common_modules(
         .ok()
         .expect("failed to initialize account provider"),
     );
+    let signer: Arc<dyn TransactionSigner> =
+        match (conf.remote_signer_config(), conf.threshold_signer_config()?) {
+            (Some(_), Some(_)) => {
+                return Err("remote_signer_url and threshold_signer_cosigners \
+                            can not be both set"
+                    .into())
+            }
+            (Some(config), None) => Arc::new(RoutingSigner::new(
+                accounts.clone(),
+                Arc::new(RemoteSigner::new(config)),
+            )),
+            (None, Some(config)) => Arc::new(RoutingSigner::new(
+                accounts.clone(),
+                Arc::new(ThresholdSigner::new(config)),
+            )),
+            (None, None) => accounts.clone(),
+        };
 
     let common_impl = Arc::new(CommonRpcImpl::new(
         exit,
         consensus.clone(),
         network.clone(),
         txpool.clone(),
-        accounts.clone(),
+        accounts,
+        signer.clone(),
     ));
 
     let runtime = Runtime::with_default_thread_count();
@@ -305,7 +559,7 @@ pub fn initialize_common_modules(
         sync_graph,
         network,
         common_impl,
-        accounts,
+        signer,
         notifications,
         pubsub,
         runtime,
@@ -324,9 +578,11 @@ pub fn initialize_not_light_node_modules(
         Arc<SynchronizationService>,
         Arc<BlockGenerator>,
         Option<HttpServer>,
+        Option<IpcServer>,
         Option<HttpServer>,
         Option<TcpServer>,
         Option<WSServer>,
+        Option<HttpServer>,
         Runtime,
     ),
     String,
@@ -343,7 +599,7 @@ pub fn initialize_not_light_node_modules(
         sync_graph,
         network,
         common_impl,
-        accounts,
+        signer,
         _notifications,
         pubsub,
         runtime,
@@ -375,46 +631,23 @@ pub fn initialize_not_light_node_modules(
     ));
     sync.register().unwrap();
 
-    if let Some(print_memory_usage_period_s) =
-        conf.raw_conf.print_memory_usage_period_s
-    {
-        let secret_store = secret_store.clone();
-        let data_man = data_man.clone();
-        let txpool = txpool.clone();
-        let consensus = consensus.clone();
-        let sync = sync.clone();
-        thread::Builder::new().name("MallocSizeOf".into()).spawn(
-            move || loop {
-                let start = Instant::now();
-                let mb = 1_000_000;
-                let mut ops = new_malloc_size_ops();
-                let secret_store_size = secret_store.size_of(&mut ops) / mb;
-                // Note `db_manager` is not wrapped in Arc, so it will still be included
-                // in `data_man_size`.
-                let data_manager_db_cache_size = data_man.db_manager.size_of(&mut ops) / mb;
-                let storage_manager_size = data_man.storage_manager.size_of(&mut ops) / mb;
-                let data_man_size = data_man.size_of(&mut ops) / mb;
-                let tx_pool_size = txpool.size_of(&mut ops) / mb;
-                let consensus_graph_size = consensus.size_of(&mut ops) / mb;
-                let sync_graph_size =
-                    sync.get_synchronization_graph().size_of(&mut ops) / mb;
-                let sync_service_size = sync.size_of(&mut ops) / mb;
-                info!(
-                    "Malloc Size(MB): secret_store={} data_manager_db_cache_size={} \
-                    storage_manager_size={} data_man={} txpool={} consensus={} sync_graph={}\
-                    sync_service={}, \
-                    time elapsed={:?}",
-                    secret_store_size,data_manager_db_cache_size,storage_manager_size,
-                    data_man_size, tx_pool_size, consensus_graph_size, sync_graph_size,
-                    sync_service_size, start.elapsed(),
-                );
-                thread::sleep(Duration::from_secs(
-                    print_memory_usage_period_s,
-                ));
-            },
-        ).expect("Memory usage thread start fails");
-    }
+    let memory_accountant =
+        conf.raw_conf.print_memory_usage_period_s.map(|period_s| {
+            let memory_accountant = Arc::new(MemoryAccountant::new());
+            MemoryAccountant::start(
+                &memory_accountant,
+                Duration::from_secs(period_s),
+                &secret_store,
+                &data_man,
+                &txpool,
+                &consensus,
+                &sync,
+            );
+            memory_accountant
+        });
 
+    let genesis_addresses: Vec<Address> =
+        genesis_accounts.keys().cloned().collect();
     let (maybe_txgen, maybe_direct_txgen) = initialize_txgens(
         consensus.clone(),
         txpool.clone(),
@@ -435,18 +668,23 @@ pub fn initialize_not_light_node_modules(
         txpool.clone(),
         sync.clone(),
         maybe_txgen.clone(),
-        conf.pow_config(),
+        conf.pow_config()?,
         pow.clone(),
         maybe_author.clone().unwrap_or_default(),
     ));
     if conf.is_dev_mode() {
         let bg = blockgen.clone();
         let interval_ms = conf.raw_conf.dev_block_interval_ms;
+        let pack_tx_immediately = conf.raw_conf.dev_pack_tx_immediately;
         info!("Start auto block generation");
         thread::Builder::new()
             .name("auto_mining".into())
             .spawn(move || {
-                bg.auto_block_generation(interval_ms);
+                if pack_tx_immediately {
+                    bg.auto_block_generation_on_transactions();
+                } else {
+                    bg.auto_block_generation(interval_ms);
+                }
             })
             .expect("Mining thread spawn error");
     } else if let Some(author) = maybe_author {
@@ -464,7 +702,7 @@ pub fn initialize_not_light_node_modules(
         }
     }
 
-    let rpc_impl = Arc::new(RpcImpl::new(
+    let mut rpc_impl = RpcImpl::new(
         consensus.clone(),
         sync.clone(),
         blockgen.clone(),
@@ -472,8 +710,27 @@ pub fn initialize_not_light_node_modules(
         maybe_txgen.clone(),
         maybe_direct_txgen,
         conf.rpc_impl_config(),
-        accounts,
-    ));
+        signer,
+    );
+    if conf.raw_conf.chain_statistics_epochs > 0 {
+        let chain_statistics = Arc::new(ChainStatistics::new(
+            conf.raw_conf.chain_statistics_epochs,
+        ));
+        ChainStatistics::start_collector(
+            &chain_statistics,
+            &consensus,
+            &txpool,
+            &network,
+        );
+        rpc_impl = rpc_impl.with_chain_statistics(chain_statistics);
+    }
+    if let Some(memory_accountant) = memory_accountant {
+        rpc_impl = rpc_impl.with_memory_accountant(memory_accountant);
+    }
+    let rpc_impl = Arc::new(rpc_impl);
+    reload::register(&rpc_impl, |rpc_impl, conf| {
+        rpc_impl.set_config(conf.rpc_impl_config())
+    });
 
     let debug_rpc_http_server = super::rpc::start_http(
         conf.local_http_config(),
@@ -485,6 +742,17 @@ pub fn initialize_not_light_node_modules(
         ),
     )?;
 
+    let debug_rpc_ipc_server = super::rpc::start_ipc(
+        conf.local_ipc_config(),
+        setup_debug_rpc_apis(
+            common_impl.clone(),
+            rpc_impl.clone(),
+            Some(pubsub.clone()),
+            &conf,
+        ),
+        RpcExtractor,
+    )?;
+
     let rpc_tcp_server = super::rpc::start_tcp(
         conf.tcp_config(),
         if conf.is_test_or_dev_mode() {
@@ -533,6 +801,16 @@ pub fn initialize_not_light_node_modules(
             setup_public_rpc_apis(common_impl, rpc_impl, None, &conf)
         },
     )?;
+
+    let explorer =
+        Arc::new(ExplorerImpl::new(consensus.clone(), genesis_addresses));
+    let explorer_http_server = super::rpc::start_http(
+        conf.explorer_http_config(),
+        setup_explorer_rpc_apis(explorer.clone(), &conf),
+    )?;
+    if explorer_http_server.is_some() {
+        explorer.start_scanner();
+    }
     Ok((
         data_man,
         pow,
@@ -541,9 +819,11 @@ pub fn initialize_not_light_node_modules(
         sync,
         blockgen,
         debug_rpc_http_server,
+        debug_rpc_ipc_server,
         rpc_http_server,
         rpc_tcp_server,
         rpc_ws_server,
+        explorer_http_server,
         runtime,
     ))
 }
@@ -718,14 +998,18 @@ pub use crate::configuration::Configuration;
 use crate::{
     accounts::{account_provider, keys_path},
     configuration::parse_hex_string,
+    reload,
+    remote_signer::RemoteSigner,
     rpc::{
         extractor::RpcExtractor,
         impls::{
             cfx::RpcImpl, common::RpcImpl as CommonRpcImpl,
-            pubsub::PubSubClient,
+            explorer::ExplorerImpl, pubsub::PubSubClient,
         },
-        setup_debug_rpc_apis, setup_public_rpc_apis,
+        set_address_format, setup_debug_rpc_apis, setup_explorer_rpc_apis,
+        setup_public_rpc_apis,
     },
+    threshold_signer::ThresholdSigner,
     GENESIS_VERSION,
 };
 use blockgen::BlockGenerator;
@@ -733,25 +1017,29 @@ use cfx_storage::StorageManager;
 use cfx_types::{address_util::AddressUtil, Address, U256};
 use cfxcore::{
     block_data_manager::BlockDataManager,
+    evm_profile,
     genesis::{self, genesis_block, DEV_GENESIS_KEY_PAIR_2},
     machine::{new_machine_with_builtin, Machine},
     pow::PowComputer,
-    statistics::Statistics,
+    statistics::{ChainStatistics, MemoryAccountant, Statistics},
     sync::SyncPhaseType,
     vm_factory::VmFactory,
+    watchdog::WATCHDOG,
     ConsensusGraph, LightProvider, NodeType, Notifications, Stopable,
     SynchronizationGraph, SynchronizationService, TransactionPool,
     WORKER_COMPUTATION_PARALLELISM,
 };
-use cfxcore_accounts::AccountProvider;
+use cfxcore_accounts::{RoutingSigner, TransactionSigner};
 use cfxkey::public_to_address;
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WSServer;
 use keylib::KeyPair;
-use malloc_size_of::{new_malloc_size_ops, MallocSizeOf, MallocSizeOfOps};
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use network::NetworkService;
 use parking_lot::{Condvar, Mutex};
+use primitives::SignaturePolicy;
 use runtime::Runtime;
 use secret_store::{SecretStore, SharedSecretStore};
 use std::{
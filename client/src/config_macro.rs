@@ -66,59 +66,91 @@ macro_rules! build_config{
             // Replace the ones from config file with the ones
             // from commandline if duplicates.
             pub fn parse(matches: &clap::ArgMatches) -> Result<RawConfiguration, String> {
+                let mut config = match matches.value_of("config") {
+                    Some(config_filename) => RawConfiguration::from_file(config_filename)?,
+                    None => RawConfiguration::default(),
+                };
+                for (name, value) in RawConfiguration::command_line_args(matches) {
+                    config.set(&name, &value)?;
+                }
+                Ok(config)
+            }
+
+            pub fn from_file(config_filename: &str) -> Result<RawConfiguration, String> {
                 let mut config = RawConfiguration::default();
+                let mut config_file = File::open(config_filename)
+                    .map_err(|e| format!("failed to open configuration file: {:?}", e))?;
 
-                let config_filename = matches.value_of("config");
-                if config_filename.is_some() {
-                    let mut config_file = File::open(config_filename.unwrap())
-                        .map_err(|e| format!("failed to open configuration file: {:?}", e))?;
+                let mut config_str = String::new();
+                config_file
+                    .read_to_string(&mut config_str)
+                    .map_err(|e| format!("failed to read configuration file: {:?}", e))?;
 
-                    let mut config_str = String::new();
-                    config_file
-                        .read_to_string(&mut config_str)
-                        .map_err(|e| format!("failed to read configuration file: {:?}", e))?;
+                let config_value = config_str.parse::<toml::Value>()
+                    .map_err(|e| format!("failed to parse configuration file: {:?}", e))?;
+                $(
+                    if let Some(value) = config_value.get(stringify!($name)) {
+                        config.$name = if_option!(
+                            $($type)+,
+                            THEN{ Some(value.clone().try_into().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())?) }
+                            ELSE{ value.clone().try_into().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())? }
+                        );
+                    }
+                )*
+                $(
+                    if let Some(value) = config_value.get(stringify!($c_name)) {
+                        config.$c_name = if_option!(
+                            $($c_type)+,
+                            THEN{ Some($converter(value.as_str().unwrap())?) }
+                            ELSE{ $converter(value.as_str().unwrap())? }
+                        )
+                    }
+                )*
+                Ok(config)
+            }
+
+            /// The configurations given in the commandline, as pairs of the
+            /// names and the values.
+            pub fn command_line_args(matches: &clap::ArgMatches) -> Vec<(String, String)> {
+                let mut args = Vec::new();
+                $(
+                    if let Some(value) = matches.value_of(underscore_to_hyphen!(stringify!($name))) {
+                        args.push((stringify!($name).to_string(), value.to_string()));
+                    }
+                )*
+                $(
+                    if let Some(value) = matches.value_of(underscore_to_hyphen!(stringify!($c_name))) {
+                        args.push((stringify!($c_name).to_string(), value.to_string()));
+                    }
+                )*
+                args
+            }
 
-                    let config_value = config_str.parse::<toml::Value>()
-                        .map_err(|e| format!("failed to parse configuration file: {:?}", e))?;
+            /// Set the configuration `name` to `value` given in the
+            /// commandline.
+            pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+                match name {
                     $(
-                        if let Some(value) = config_value.get(stringify!($name)) {
-                            config.$name = if_option!(
+                        stringify!($name) => {
+                            self.$name = if_option!(
                                 $($type)+,
-                                THEN{ Some(value.clone().try_into().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())?) }
-                                ELSE{ value.clone().try_into().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())? }
+                                THEN{ Some(value.parse().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())?) }
+                                ELSE{ value.parse().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())? }
                             );
                         }
                     )*
                     $(
-                        if let Some(value) = config_value.get(stringify!($c_name)) {
-                            config.$c_name = if_option!(
+                        stringify!($c_name) => {
+                            self.$c_name = if_option!(
                                 $($c_type)+,
-                                THEN{ Some($converter(value.as_str().unwrap())?) }
-                                ELSE{ $converter(value.as_str().unwrap())? }
-                            )
+                                THEN{ Some($converter(value)?) }
+                                ELSE{ $converter(value)? }
+                            );
                         }
                     )*
+                    _ => return Err(format!("Unknown configuration {}", name)),
                 }
-
-                $(
-                    if let Some(value) = matches.value_of(underscore_to_hyphen!(stringify!($name))) {
-                        config.$name = if_option!(
-                                $($type)+,
-                                THEN{ Some(value.parse().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())?) }
-                                ELSE{ value.parse().map_err(|_| concat!("Invalid ", stringify!($name)).to_owned())? }
-                            );
-                    }
-                )*
-                $(
-                    if let Some(value) = matches.value_of(underscore_to_hyphen!(stringify!($c_name))) {
-                    config.$c_name = if_option!(
-                                $($c_type)+,
-                                THEN{ Some($converter(value)?) }
-                                ELSE{ $converter(value)? }
-                            )
-                    }
-                )*
-                Ok(config)
+                Ok(())
             }
         }
     }
@@ -2,15 +2,20 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::{
-    impls::RpcImplConfiguration, HttpConfiguration, TcpConfiguration,
-    WsConfiguration,
+use crate::{
+    logging::LogFormat,
+    remote_signer::RemoteSignerConfig,
+    rpc::{
+        impls::RpcImplConfiguration, HttpConfiguration, IpcConfiguration,
+        RpcMiddlewareConfig, TcpConfiguration, WsConfiguration,
+    },
+    threshold_signer::ThresholdSignerConfig,
 };
 use cfx_storage::{
     defaults::DEFAULT_DEBUG_SNAPSHOT_CHECKER_THREADS, storage_dir,
     ConsensusParam, StorageConfiguration,
 };
-use cfx_types::H256;
+use cfx_types::{base32_address::MAINNET_ID, H256};
 use cfxcore::{
     block_data_manager::{DataManagerConfiguration, DbType},
     block_parameters::*,
@@ -25,15 +30,24 @@ use cfxcore::{
     },
     consensus_internal_parameters::*,
     consensus_parameters::*,
+    pow::{PowAlgorithmSpec, PowAlgorithmTransition, PowAlgorithmType},
     sync::{ProtocolConfiguration, StateSyncConfiguration, SyncGraphConfig},
     sync_parameters::*,
-    transaction_pool::{TxPoolConfig, DEFAULT_MAX_TRANSACTION_GAS_LIMIT},
+    transaction_pool::{
+        PackingStrategy, TxAdmissionLimits, TxPoolConfig,
+        DEFAULT_MAX_TRANSACTION_DATA_BYTES, DEFAULT_MAX_TRANSACTION_GAS_LIMIT,
+    },
+    watchdog::{WatchdogConfig, MIN_STALL_TIMEOUT},
 };
 use metrics::MetricsConfiguration;
 use network::DiscoveryConfiguration;
-use primitives::ChainIdParams;
+use primitives::{ChainIdParams, ChainIdTransition};
 use rand::Rng;
-use std::convert::TryInto;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    net::{IpAddr, SocketAddr},
+};
 use txgen::TransactionGeneratorConfig;
 
 // usage:
@@ -73,12 +87,15 @@ build_config! {
         //     * Allow setting genesis accounts and generate tx from secrets
         //
         // `dev` mode is for users to run a single node that automatically
-        //     generates blocks with fixed intervals
+        //     generates blocks periodically, or when transactions are received.
+        //     It can also be set with `--dev`.
         //     * Open port 12535 for ws rpc if `jsonrpc_ws_port` is not provided.
         //     * Open port 12536 for tcp rpc if `jsonrpc_tcp_port` is not provided.
         //     * Open port 12537 for http rpc if `jsonrpc_http_port` is not provided.
         //     * generate blocks automatically without PoW.
         //     * Skip catch-up mode even there is no peer
+        //     * Fund the accounts in `genesis_accounts` in addition to the
+        //       accounts of `genesis_secrets`
         //
         (mode, (Option<String>), None)
         // Development related section.
@@ -88,6 +105,10 @@ build_config! {
         // Controls block generation speed.
         // Only effective in `dev` mode
         (dev_block_interval_ms, (u64), 250)
+        // If true, blocks are generated as soon as transactions are received
+        // instead of every `dev_block_interval_ms`.
+        // Only effective in `dev` mode
+        (dev_pack_tx_immediately, (bool), false)
         (enable_state_expose, (bool), false)
         (generate_tx, (bool), false)
         (generate_tx_period_us, (Option<u64>), Some(100_000))
@@ -101,6 +122,7 @@ build_config! {
         (metrics_influxdb_password, (Option<String>), None)
         (metrics_influxdb_node, (Option<String>), None)
         (metrics_output_file, (Option<String>), None)
+        (metrics_prometheus_listen_addr, (Option<String>), None)
         (metrics_report_interval_ms, (u64), 3_000)
         (rocksdb_disable_wal, (bool), false)
         (txgen_account_count, (usize), 10)
@@ -109,6 +131,9 @@ build_config! {
         (adaptive_weight_beta, (u64), ADAPTIVE_WEIGHT_DEFAULT_BETA)
         (anticone_penalty_ratio, (u64), ANTICONE_PENALTY_RATIO)
         (chain_id, (Option<u32>), None)
+        // The chain_id transitions in the format of
+        // "activation_epoch:chain_id,...".
+        (chain_id_schedule, (Option<String>), None)
         // Snapshot Epoch Count is a consensus parameter. This flag overrides
         // the parameter, which only take effect in `dev` mode.
         (dev_snapshot_epoch_count, (u32), SNAPSHOT_EPOCHS_CAPACITY)
@@ -117,6 +142,9 @@ build_config! {
         (genesis_accounts, (Option<String>), None)
         (genesis_secrets, (Option<String>), None)
         (initial_difficulty, (Option<u64>), None)
+        // The pow_algorithm transitions in the format of
+        // "activation_height:algorithm,...".
+        (pow_algorithm_schedule, (Option<String>), None)
         (referee_bound, (usize), REFEREE_DEFAULT_BOUND)
         (timer_chain_beta, (u64), TIMER_CHAIN_DEFAULT_BETA)
         (timer_chain_block_difficulty_ratio, (u64), TIMER_CHAIN_BLOCK_DEFAULT_DIFFICULTY_RATIO)
@@ -125,26 +153,40 @@ build_config! {
 
         // Mining section.
         (mining_author, (Option<String>), None)
+        (mining_cpu_threads, (usize), 1)
         (mining_type, (Option<String>), None)
+        (pow_algorithm, (Option<String>), None)
         (stratum_listen_address, (String), "127.0.0.1".into())
+        (stratum_min_share_difficulty, (u64), 1)
         (stratum_port, (u16), 32525)
         (stratum_secret, (Option<String>), None)
+        (stratum_share_difficulty, (Option<u64>), None)
+        (stratum_share_interval_ms, (Option<u64>), None)
         (use_octopus_in_test_mode, (bool), false)
 
         // Network section.
         (jsonrpc_local_tcp_port, (Option<u16>), None)
         (jsonrpc_local_http_port, (Option<u16>), None)
+        (jsonrpc_local_ipc_path, (Option<String>), None)
         (jsonrpc_ws_port, (Option<u16>), None)
         (jsonrpc_tcp_port, (Option<u16>), None)
         (jsonrpc_http_port, (Option<u16>), None)
+        (jsonrpc_explorer_http_port, (Option<u16>), None)
         (jsonrpc_cors, (Option<String>), None)
         (jsonrpc_http_keep_alive, (bool), false)
+        (jsonrpc_hex_address, (bool), false)
+        (jsonrpc_client_rate_limit, (Option<usize>), None)
+        (jsonrpc_max_request_size, (Option<usize>), None)
+        (jsonrpc_slow_call_threshold_ms, (Option<u64>), None)
         // The network_id, if unset, defaults to the chain_id.
         // Only override the network_id for local experiments,
         // when user would like to keep the existing blockchain data
         // but disconnect from the public network.
         (network_id, (Option<u64>), None)
         (tcp_port, (u16), 32323)
+        // The IP address to listen on, e.g. "::" to accept both IPv4 and IPv6
+        // peers on dual-stack hosts. Defaults to "0.0.0.0".
+        (listen_address, (Option<String>), None)
         (public_tcp_port, (Option<u16>), None)
         (public_address, (Option<String>), None)
         (udp_port, (Option<u16>), Some(32323))
@@ -163,6 +205,7 @@ build_config! {
         (heartbeat_timeout_ms, (u64), 180_000)
         (inflight_pending_tx_index_maintain_timeout_ms, (u64), 30_000)
         (max_allowed_timeout_in_observing_period, (u64), 10)
+        (max_buffered_block_body_size_mb, (usize), 256)
         (max_chunk_number_in_manifest, (usize), 500)
         (max_downloading_chunks, (usize), 8)
         (max_handshakes, (usize), 64)
@@ -199,6 +242,11 @@ build_config! {
         (discovery_throttling_limit_ping, (usize), 20)
         (discovery_throttling_limit_find_nodes, (usize), 10)
         (enable_discovery, (bool), true)
+        (enable_p2p_compression, (bool), true)
+        (enable_p2p_encryption, (bool), true)
+        (require_p2p_encryption, (bool), false)
+        (static_nodes, (Option<String>), None)
+        (trusted_nodes, (Option<String>), None)
         (netconf_dir, (Option<String>), Some("./net_config".to_string()))
         (net_key, (Option<String>), None)
         (node_table_timeout_s, (u64), 300)
@@ -208,8 +256,11 @@ build_config! {
 
         // Transaction cache/transaction pool section.
         (tx_cache_index_maintain_timeout_ms, (u64), 300_000)
+        (tx_pool_packing_strategy, (String), "random".to_string())
         (tx_pool_size, (usize), 200_000)
         (tx_pool_min_tx_gas_price, (u64), 1)
+        (tx_pool_max_tx_gas, (u64), DEFAULT_MAX_TRANSACTION_GAS_LIMIT)
+        (tx_pool_max_tx_data_bytes, (usize), DEFAULT_MAX_TRANSACTION_DATA_BYTES)
         (tx_weight_scaling, (u64), 1)
         (tx_weight_exp, (u8), 1)
 
@@ -235,15 +286,43 @@ build_config! {
 
         // General/Unclassified section.
         (account_provider_refresh_time_ms, (u64), 1000)
+        (batch_transfer_activation_epoch, (Option<u64>), None)
+        (call_result_cache_size, (usize), 1000)
+        (chain_statistics_epochs, (usize), 0)
+        (collateral_per_byte, (Option<u64>), None)
+        (collateral_per_byte_activation_epoch, (Option<u64>), None)
         (enable_optimistic_execution, (bool), true)
+        (enable_parallel_execution, (bool), false)
+        (estimate_gas_error_tolerance, (f64), 0.015)
+        (estimate_gas_max_iterations, (usize), 20)
+        (evm_profile_report_period_s, (Option<u64>), None)
         (future_block_buffer_capacity, (usize), 32768)
         (get_logs_filter_max_limit, (Option<usize>), None)
         (get_logs_filter_max_epoch_range, (Option<u64>), None)
         (get_logs_epoch_batch_size, (usize), 32)
+        (index_internal_transfers, (bool), false)
+        (max_code_size, (Option<usize>), None)
         (max_trans_count_received_in_catch_up, (u64), 60_000)
+        (persist_address_tx_index, (bool), false)
+        (persist_log_index, (bool), false)
         (persist_tx_index, (bool), false)
         (print_memory_usage_period_s, (Option<u64>), None)
+        (remote_signer_auth_token, (Option<String>), None)
+        (remote_signer_timeout_ms, (u64), 5000)
+        (remote_signer_url, (Option<String>), None)
+        (shutdown_timeout_s, (u64), 1200)
+        (spec_policy_activation_epoch, (u64), 0)
+        (strict_signature_activation_epoch, (Option<u64>), None)
+        (suicide_enabled, (Option<bool>), None)
         (target_block_gas_limit, (u64), DEFAULT_TARGET_BLOCK_GAS_LIMIT)
+        (threshold_signer_auth_token, (Option<String>), None)
+        (threshold_signer_cosigners, (Option<String>), None)
+        (threshold_signer_threshold, (Option<usize>), None)
+        (threshold_signer_timeout_ms, (u64), 5000)
+        (unused_gas_refund_divisor, (Option<usize>), None)
+        (wasm_activation_epoch, (Option<u64>), None)
+        (watchdog_action, (String), "log".into())
+        (watchdog_stall_timeout_ms, (u64), 300_000)
 
         // TreeGraph Section.
         (candidate_pivot_waiting_timeout_ms, (u64), 10_000)
@@ -264,25 +343,77 @@ build_config! {
                 }
             }
         )
+        (
+            log_format, (LogFormat), LogFormat::Text, |f| {
+                match f {
+                    "text" => Ok(LogFormat::Text),
+                    "json" => Ok(LogFormat::Json),
+                    _ => Err("Invalid log_format".to_owned()),
+                }
+            }
+        )
+        (
+            jsonrpc_api_key_rate_limits, (HashMap<String, usize>), HashMap::new(),
+            to_api_key_rate_limits
+        )
+        (
+            jsonrpc_trusted_proxies, (Vec<IpAddr>), Vec::new(),
+            to_trusted_proxies
+        )
     }
 }
 
+#[derive(Clone)]
 pub struct Configuration {
     pub raw_conf: RawConfiguration,
+    /// The configuration file and the commandline arguments, which are
+    /// loaded again when the configuration is reloaded.
+    pub config_file: Option<String>,
+    pub command_line_args: Vec<(String, String)>,
 }
 
 impl Default for Configuration {
     fn default() -> Self {
         Configuration {
             raw_conf: Default::default(),
+            config_file: None,
+            command_line_args: Vec::new(),
         }
     }
 }
 
 impl Configuration {
     pub fn parse(matches: &clap::ArgMatches) -> Result<Configuration, String> {
-        let mut config = Configuration::default();
-        config.raw_conf = RawConfiguration::parse(matches)?;
+        let config_file = matches.value_of("config").map(Into::into);
+        let mut command_line_args =
+            RawConfiguration::command_line_args(matches);
+        if matches.is_present("dev") {
+            command_line_args.push(("mode".into(), "dev".into()));
+        }
+        Self::load(config_file, command_line_args)
+    }
+
+    /// Load the configuration file again, e.g. after it's edited, with the
+    /// same commandline arguments.
+    pub fn reload(&self) -> Result<Configuration, String> {
+        Self::load(self.config_file.clone(), self.command_line_args.clone())
+    }
+
+    pub(crate) fn load(
+        config_file: Option<String>, command_line_args: Vec<(String, String)>,
+    ) -> Result<Configuration, String> {
+        let mut raw_conf = match &config_file {
+            Some(config_file) => RawConfiguration::from_file(config_file)?,
+            None => RawConfiguration::default(),
+        };
+        for (name, value) in &command_line_args {
+            raw_conf.set(name, value)?;
+        }
+        let mut config = Configuration {
+            raw_conf,
+            config_file,
+            command_line_args,
+        };
 
         if config.is_dev_mode() {
             if config.raw_conf.jsonrpc_ws_port.is_none() {
@@ -295,10 +426,51 @@ impl Configuration {
                 config.raw_conf.jsonrpc_http_port = Some(12537);
             }
         };
+        config.check_rpc_limits()?;
+        // Checked here so that a reload with an invalid strategy fails
+        // before any entry is applied.
+        config
+            .raw_conf
+            .tx_pool_packing_strategy
+            .parse::<PackingStrategy>()?;
 
         Ok(config)
     }
 
+    /// Reject the limits of the public RPCs that some transports cannot
+    /// enforce, rather than leaving the clients on them unlimited.
+    fn check_rpc_limits(&self) -> Result<(), String> {
+        let raw_conf = &self.raw_conf;
+        // The TCP server reads the requests without a size limit.
+        if raw_conf.jsonrpc_max_request_size.is_some()
+            && raw_conf.jsonrpc_tcp_port.is_some()
+        {
+            return Err(
+                "jsonrpc_max_request_size is not supported by the TCP \
+                 RPC, unset jsonrpc_tcp_port"
+                    .into(),
+            );
+        }
+        if raw_conf.jsonrpc_client_rate_limit.is_some() {
+            // Neither server exposes the client addresses, which are only
+            // known from the trusted proxies in HTTP.
+            if raw_conf.jsonrpc_ws_port.is_some() {
+                return Err("jsonrpc_client_rate_limit is not supported by \
+                            the WebSocket RPC, unset jsonrpc_ws_port"
+                    .into());
+            }
+            if (raw_conf.jsonrpc_http_port.is_some()
+                || raw_conf.jsonrpc_explorer_http_port.is_some())
+                && raw_conf.jsonrpc_trusted_proxies.is_empty()
+            {
+                return Err("jsonrpc_client_rate_limit requires \
+                            jsonrpc_trusted_proxies with the HTTP RPC"
+                    .into());
+            }
+        }
+        Ok(())
+    }
+
     fn network_id(&self) -> u64 {
         match self.raw_conf.network_id {
             Some(x) => x,
@@ -319,6 +491,11 @@ impl Configuration {
         network_config.discovery_enabled = self.raw_conf.enable_discovery;
         network_config.boot_nodes = to_bootnodes(&self.raw_conf.bootnodes)
             .map_err(|e| format!("failed to parse bootnodes: {}", e))?;
+        network_config.static_nodes = to_bootnodes(&self.raw_conf.static_nodes)
+            .map_err(|e| format!("failed to parse static_nodes: {}", e))?;
+        network_config.trusted_nodes =
+            to_bootnodes(&self.raw_conf.trusted_nodes)
+                .map_err(|e| format!("failed to parse trusted_nodes: {}", e))?;
         if self.raw_conf.netconf_dir.is_some() {
             network_config.config_path = self.raw_conf.netconf_dir.clone();
         }
@@ -327,25 +504,39 @@ impl Configuration {
                 parse_hex_string(sec_str)
                     .expect("net_key is not a valid secret string")
             });
+        if let Some(addr) = self.raw_conf.listen_address.as_ref() {
+            let ip = addr
+                .parse::<IpAddr>()
+                .map_err(|e| format!("invalid listen_address: {}", e))?;
+            network_config.listen_address =
+                Some(SocketAddr::new(ip, self.raw_conf.tcp_port));
+        }
         if let Some(addr) = self.raw_conf.public_address.clone() {
-            let addr_ip = if let Some(idx) = addr.find(":") {
-                warn!("Public address configuration should not contain port! (val = {}). Content after ':' is ignored.", &addr);
-                (&addr[0..idx]).to_string()
-            } else {
-                addr
-            };
-            let addr_with_port = match self.raw_conf.public_tcp_port {
-                Some(port) => addr_ip + ":" + &port.to_string(),
-                None => addr_ip + ":" + &self.raw_conf.tcp_port.to_string(),
-            };
-            network_config.public_address =
-                match addr_with_port.to_socket_addrs().map(|mut i| i.next()) {
-                    Ok(sock_addr) => sock_addr,
-                    Err(_e) => {
-                        warn!("public_address in config is invalid");
-                        None
+            let port = self
+                .raw_conf
+                .public_tcp_port
+                .unwrap_or(self.raw_conf.tcp_port);
+            // IPv6 addresses contain ':', so try parsing a bare IP first.
+            network_config.public_address = match addr.parse::<IpAddr>() {
+                Ok(ip) => Some(SocketAddr::new(ip, port)),
+                Err(_) => {
+                    let addr_ip = if let Some(idx) = addr.find(":") {
+                        warn!("Public address configuration should not contain port! (val = {}). Content after ':' is ignored.", &addr);
+                        (&addr[0..idx]).to_string()
+                    } else {
+                        addr
+                    };
+                    let addr_with_port = addr_ip + ":" + &port.to_string();
+                    match addr_with_port.to_socket_addrs().map(|mut i| i.next())
+                    {
+                        Ok(sock_addr) => sock_addr,
+                        Err(_e) => {
+                            warn!("public_address in config is invalid");
+                            None
+                        }
                     }
-                };
+                }
+            };
         }
         network_config.node_table_timeout =
             Duration::from_secs(self.raw_conf.node_table_timeout_s);
@@ -353,6 +544,11 @@ impl Configuration {
             Duration::from_secs(self.raw_conf.node_table_promotion_timeout_s);
         network_config.test_mode = self.is_test_mode();
         network_config.subnet_quota = self.raw_conf.subnet_quota;
+        network_config.enable_compression =
+            self.raw_conf.enable_p2p_compression;
+        network_config.enable_encryption = self.raw_conf.enable_p2p_encryption;
+        network_config.require_encryption =
+            self.raw_conf.require_p2p_encryption;
         network_config.session_ip_limit_config =
             self.raw_conf.session_ip_limits.clone().try_into().map_err(
                 |e| format!("failed to parse session ip limit config: {}", e),
@@ -403,19 +599,40 @@ impl Configuration {
         )
     }
 
-    pub fn consensus_config(&self) -> ConsensusConfig {
+    pub fn chain_id_params(&self) -> Result<ChainIdParams, String> {
+        let chain_id = self
+            .raw_conf
+            .chain_id
+            .unwrap_or_else(|| rand::thread_rng().gen());
+        let schedule = match &self.raw_conf.chain_id_schedule {
+            Some(schedule) => to_chain_id_schedule(schedule)?,
+            None => vec![],
+        };
+        ChainIdParams::with_schedule(chain_id, schedule)
+    }
+
+    /// Whether the chain_id is the one of the mainnet at any epoch.
+    fn is_mainnet_chain_id(&self) -> Result<bool, String> {
+        let schedule = match &self.raw_conf.chain_id_schedule {
+            Some(schedule) => to_chain_id_schedule(schedule)?,
+            None => vec![],
+        };
+        Ok(self
+            .raw_conf
+            .chain_id
+            .into_iter()
+            .chain(schedule.iter().map(|transition| transition.chain_id))
+            .any(|chain_id| chain_id as u64 == MAINNET_ID))
+    }
+
+    pub fn consensus_config(&self) -> Result<ConsensusConfig, String> {
         let enable_optimistic_execution = if DEFERRED_STATE_EPOCH_COUNT <= 1 {
             false
         } else {
             self.raw_conf.enable_optimistic_execution
         };
-        ConsensusConfig {
-            chain_id: ChainIdParams {
-                chain_id: self
-                    .raw_conf
-                    .chain_id
-                    .unwrap_or_else(|| rand::thread_rng().gen()),
-            },
+        Ok(ConsensusConfig {
+            chain_id: self.chain_id_params()?,
             inner_conf: ConsensusInnerConfig {
                 adaptive_weight_beta: self.raw_conf.adaptive_weight_beta,
                 heavy_block_difficulty_ratio: self
@@ -455,17 +672,17 @@ impl Configuration {
             referee_bound: self.raw_conf.referee_bound,
             get_logs_epoch_batch_size: self.raw_conf.get_logs_epoch_batch_size,
             get_logs_filter_max_epoch_range: self.raw_conf.get_logs_filter_max_epoch_range
-        }
+        })
     }
 
-    pub fn pow_config(&self) -> ProofOfWorkConfig {
+    pub fn pow_config(&self) -> Result<ProofOfWorkConfig, String> {
         let stratum_secret =
             self.raw_conf.stratum_secret.as_ref().map(|hex_str| {
                 parse_hex_string(hex_str)
                     .expect("Stratum secret should be 64-digit hex string")
             });
 
-        ProofOfWorkConfig::new(
+        let mut pow_config = ProofOfWorkConfig::new(
             self.is_test_or_dev_mode(),
             self.raw_conf.use_octopus_in_test_mode,
             self.raw_conf.mining_type.as_ref().map_or_else(
@@ -483,7 +700,36 @@ impl Configuration {
             self.raw_conf.stratum_listen_address.clone(),
             self.raw_conf.stratum_port,
             stratum_secret,
-        )
+        );
+        pow_config.stratum_share_difficulty =
+            self.raw_conf.stratum_share_difficulty;
+        pow_config.stratum_min_share_difficulty =
+            self.raw_conf.stratum_min_share_difficulty;
+        pow_config.stratum_share_interval_ms =
+            self.raw_conf.stratum_share_interval_ms;
+        pow_config.cpu_miner_threads = self.raw_conf.mining_cpu_threads;
+        let algorithm = match &self.raw_conf.pow_algorithm {
+            Some(algorithm) => algorithm.parse().map_err(|e| {
+                format!("Invalid pow_algorithm {}: {}", algorithm, e)
+            })?,
+            None => pow_config.pow_spec.algorithm,
+        };
+        let schedule = match &self.raw_conf.pow_algorithm_schedule {
+            Some(schedule) => to_pow_algorithm_schedule(schedule)?,
+            None => vec![],
+        };
+        pow_config.pow_spec =
+            PowAlgorithmSpec::with_schedule(algorithm, schedule)?;
+        if pow_config.pow_spec.uses(PowAlgorithmType::Dev)
+            && !self.is_test_or_dev_mode()
+            && self.is_mainnet_chain_id()?
+        {
+            return Err(
+                "pow_algorithm \"dev\" is not allowed on the mainnet chain_id"
+                    .into(),
+            );
+        }
+        Ok(pow_config)
     }
 
     pub fn verification_config(&self) -> VerificationConfig {
@@ -626,6 +872,10 @@ impl Configuration {
                 .raw_conf
                 .max_unprocessed_block_size_mb
                 * 1_000_000,
+            max_buffered_block_body_size: self
+                .raw_conf
+                .max_buffered_block_body_size_mb
+                * 1_000_000,
             sync_expire_block_timeout: Duration::from_secs(
                 self.raw_conf.sync_expire_block_timeout_s,
             ),
@@ -650,6 +900,8 @@ impl Configuration {
     pub fn data_mananger_config(&self) -> DataManagerConfiguration {
         DataManagerConfiguration::new(
             self.raw_conf.persist_tx_index,
+            self.raw_conf.persist_address_tx_index,
+            self.raw_conf.persist_log_index,
             Duration::from_millis(
                 self.raw_conf.tx_cache_index_maintain_timeout_ms,
             ),
@@ -689,41 +941,189 @@ impl Configuration {
                 .metrics_influxdb_password
                 .clone(),
             influxdb_report_node: self.raw_conf.metrics_influxdb_node.clone(),
+            prometheus_listen_addr: self
+                .raw_conf
+                .metrics_prometheus_listen_addr
+                .clone(),
         }
     }
 
     pub fn txpool_config(&self) -> TxPoolConfig {
         TxPoolConfig {
             capacity: self.raw_conf.tx_pool_size,
-            min_tx_price: self.raw_conf.tx_pool_min_tx_gas_price,
-            max_tx_gas: DEFAULT_MAX_TRANSACTION_GAS_LIMIT,
+            admission_limits: self.txpool_admission_limits(),
             tx_weight_scaling: self.raw_conf.tx_weight_scaling,
             tx_weight_exp: self.raw_conf.tx_weight_exp,
             target_block_gas_limit: self.raw_conf.target_block_gas_limit,
+            packing_strategy: self.tx_pool_packing_strategy(),
+        }
+    }
+
+    pub fn tx_pool_packing_strategy(&self) -> PackingStrategy {
+        self.raw_conf
+            .tx_pool_packing_strategy
+            .parse()
+            .expect("checked on load")
+    }
+
+    pub fn txpool_admission_limits(&self) -> TxAdmissionLimits {
+        TxAdmissionLimits {
+            max_tx_data_bytes: self.raw_conf.tx_pool_max_tx_data_bytes,
+            max_tx_gas: self.raw_conf.tx_pool_max_tx_gas,
+            min_tx_gas_price: self.raw_conf.tx_pool_min_tx_gas_price,
         }
     }
 
     pub fn rpc_impl_config(&self) -> RpcImplConfiguration {
         RpcImplConfiguration {
             get_logs_filter_max_limit: self.raw_conf.get_logs_filter_max_limit,
+            call_result_cache_size: self.raw_conf.call_result_cache_size,
+            estimate_gas_error_tolerance: self
+                .raw_conf
+                .estimate_gas_error_tolerance,
+            estimate_gas_max_iterations: self
+                .raw_conf
+                .estimate_gas_max_iterations,
+        }
+    }
+
+    pub fn rpc_middleware_config(&self) -> RpcMiddlewareConfig {
+        RpcMiddlewareConfig {
+            client_rate_limit: self.raw_conf.jsonrpc_client_rate_limit,
+            api_key_rate_limits: self
+                .raw_conf
+                .jsonrpc_api_key_rate_limits
+                .clone(),
+            slow_call_threshold: self
+                .raw_conf
+                .jsonrpc_slow_call_threshold_ms
+                .map(Duration::from_millis),
+        }
+    }
+
+    /// The debug RPCs are not limited, but their slow calls are logged.
+    pub fn debug_rpc_middleware_config(&self) -> RpcMiddlewareConfig {
+        RpcMiddlewareConfig {
+            slow_call_threshold: self
+                .raw_conf
+                .jsonrpc_slow_call_threshold_ms
+                .map(Duration::from_millis),
+            ..Default::default()
         }
     }
 
+    pub fn remote_signer_config(&self) -> Option<RemoteSignerConfig> {
+        self.raw_conf
+            .remote_signer_url
+            .as_ref()
+            .map(|url| RemoteSignerConfig {
+                url: url.clone(),
+                auth_token: self.raw_conf.remote_signer_auth_token.clone(),
+                timeout: Duration::from_millis(
+                    self.raw_conf.remote_signer_timeout_ms,
+                ),
+            })
+    }
+
+    pub fn threshold_signer_config(
+        &self,
+    ) -> Result<Option<ThresholdSignerConfig>, String> {
+        let cosigners: Vec<String> =
+            match &self.raw_conf.threshold_signer_cosigners {
+                Some(cosigners) => cosigners
+                    .split(',')
+                    .filter(|url| !url.is_empty())
+                    .map(Into::into)
+                    .collect(),
+                None => return Ok(None),
+            };
+        let threshold = match self.raw_conf.threshold_signer_threshold {
+            Some(threshold)
+                if threshold >= 1 && threshold <= cosigners.len() =>
+            {
+                threshold
+            }
+            _ => {
+                return Err(format!(
+                    "threshold_signer_threshold must be set between 1 and the \
+                     number of cosigners {}",
+                    cosigners.len()
+                ))
+            }
+        };
+        Ok(Some(ThresholdSignerConfig {
+            cosigners,
+            threshold,
+            auth_token: self.raw_conf.threshold_signer_auth_token.clone(),
+            timeout: Duration::from_millis(
+                self.raw_conf.threshold_signer_timeout_ms,
+            ),
+        }))
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.raw_conf.shutdown_timeout_s)
+    }
+
+    /// The watchdog is disabled with a zero stall timeout.
+    pub fn watchdog_config(&self) -> Result<Option<WatchdogConfig>, String> {
+        if self.raw_conf.watchdog_stall_timeout_ms == 0 {
+            return Ok(None);
+        }
+        let stall_timeout =
+            Duration::from_millis(self.raw_conf.watchdog_stall_timeout_ms);
+        if stall_timeout < MIN_STALL_TIMEOUT {
+            return Err(format!(
+                "watchdog_stall_timeout_ms must be 0 or at least {}",
+                MIN_STALL_TIMEOUT.as_millis()
+            ));
+        }
+        let action = self.raw_conf.watchdog_action.parse().map_err(|e| {
+            format!(
+                "Invalid watchdog_action {}: {}",
+                self.raw_conf.watchdog_action, e
+            )
+        })?;
+        Ok(Some(WatchdogConfig {
+            stall_timeout,
+            action,
+        }))
+    }
+
     pub fn local_http_config(&self) -> HttpConfiguration {
         HttpConfiguration::new(
             Some((127, 0, 0, 1)),
             self.raw_conf.jsonrpc_local_http_port,
             self.raw_conf.jsonrpc_cors.clone(),
             self.raw_conf.jsonrpc_http_keep_alive,
+            None,       /* max_request_size */
+            Vec::new(), /* trusted_proxies */
         )
     }
 
+    pub fn local_ipc_config(&self) -> IpcConfiguration {
+        IpcConfiguration::new(self.raw_conf.jsonrpc_local_ipc_path.clone())
+    }
+
     pub fn http_config(&self) -> HttpConfiguration {
         HttpConfiguration::new(
             None,
             self.raw_conf.jsonrpc_http_port,
             self.raw_conf.jsonrpc_cors.clone(),
             self.raw_conf.jsonrpc_http_keep_alive,
+            self.raw_conf.jsonrpc_max_request_size,
+            self.raw_conf.jsonrpc_trusted_proxies.clone(),
+        )
+    }
+
+    pub fn explorer_http_config(&self) -> HttpConfiguration {
+        HttpConfiguration::new(
+            None,
+            self.raw_conf.jsonrpc_explorer_http_port,
+            self.raw_conf.jsonrpc_cors.clone(),
+            self.raw_conf.jsonrpc_http_keep_alive,
+            self.raw_conf.jsonrpc_max_request_size,
+            self.raw_conf.jsonrpc_trusted_proxies.clone(),
         )
     }
 
@@ -732,13 +1132,20 @@ impl Configuration {
     }
 
     pub fn ws_config(&self) -> WsConfiguration {
-        WsConfiguration::new(None, self.raw_conf.jsonrpc_ws_port)
+        WsConfiguration::new(
+            None,
+            self.raw_conf.jsonrpc_ws_port,
+            self.raw_conf.jsonrpc_max_request_size,
+        )
     }
 
     pub fn execution_config(&self) -> ConsensusExecutionConfiguration {
         ConsensusExecutionConfiguration {
             anticone_penalty_ratio: self.raw_conf.anticone_penalty_ratio,
             base_reward_table_in_ucfx: MINING_REWARD_TABLE_IN_UCFX.to_vec(),
+            parallel_execution: self.raw_conf.enable_parallel_execution,
+            index_internal_transfers: self.raw_conf.persist_address_tx_index
+                && self.raw_conf.index_internal_transfers,
         }
     }
 
@@ -815,6 +1222,87 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
     }
 }
 
+/// Parses the chain_id transitions in the format of
+/// "activation_epoch:chain_id,...".
+pub fn to_chain_id_schedule(
+    schedule: &str,
+) -> Result<Vec<ChainIdTransition>, String> {
+    schedule
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut parts = s.trim().splitn(2, ':');
+            let activation_epoch = parts.next().unwrap_or_default().parse();
+            let chain_id = parts.next().unwrap_or_default().parse();
+            match (activation_epoch, chain_id) {
+                (Ok(activation_epoch), Ok(chain_id)) => Ok(ChainIdTransition {
+                    activation_epoch,
+                    chain_id,
+                }),
+                _ => Err(format!("Invalid chain_id transition: {}", s)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the pow_algorithm transitions in the format of
+/// "activation_height:algorithm,...".
+pub fn to_pow_algorithm_schedule(
+    schedule: &str,
+) -> Result<Vec<PowAlgorithmTransition>, String> {
+    schedule
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut parts = s.trim().splitn(2, ':');
+            let activation_height = parts.next().unwrap_or_default().parse();
+            let algorithm = parts.next().unwrap_or_default().parse();
+            match (activation_height, algorithm) {
+                (Ok(activation_height), Ok(algorithm)) => {
+                    Ok(PowAlgorithmTransition {
+                        activation_height,
+                        algorithm,
+                    })
+                }
+                _ => Err(format!("Invalid pow_algorithm transition: {}", s)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the rate limits of the API keys in the format of "key:limit,...".
+pub fn to_api_key_rate_limits(
+    limits: &str,
+) -> Result<HashMap<String, usize>, String> {
+    limits
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut parts = s.trim().splitn(2, ':');
+            match (parts.next(), parts.next().map(str::parse)) {
+                (Some(key), Some(Ok(limit))) if !key.is_empty() => {
+                    Ok((key.to_string(), limit))
+                }
+                _ => Err(format!("Invalid API key rate limit: {}", s)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the addresses of the trusted reverse proxies in the format of
+/// "ip,...".
+pub fn to_trusted_proxies(proxies: &str) -> Result<Vec<IpAddr>, String> {
+    proxies
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.trim()
+                .parse()
+                .map_err(|_| format!("Invalid trusted proxy address: {}", s))
+        })
+        .collect()
+}
+
 pub fn parse_hex_string<F: FromStr>(hex_str: &str) -> Result<F, F::Err> {
     hex_str.strip_prefix("0x").unwrap_or(hex_str).parse()
 }
@@ -0,0 +1,35 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Roll the database of a stopped node back to an executed epoch. The node
+//! recovers the blocks until the epoch on the next start, and syncs and
+//! executes the later epochs again.
+
+use crate::{common::initialize_data_manager, configuration::Configuration};
+use parking_lot::{Condvar, Mutex};
+use secret_store::SecretStore;
+use std::sync::Arc;
+
+pub fn rollback_database(
+    conf: Configuration, epoch_number: u64,
+) -> Result<String, String> {
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let secret_store = SecretStore::new();
+    let (_, data_man, _) =
+        initialize_data_manager(&conf, &secret_store, exit.clone())?;
+
+    let result = data_man.rollback_to_epoch(epoch_number);
+
+    // Stop the storage usage logger.
+    *exit.0.lock() = true;
+    exit.1.notify_all();
+    let result = result?;
+    Ok(format!(
+        "Rolled back to epoch {} {:?}, removed {} epochs and {} blocks",
+        result.epoch_number,
+        result.pivot_hash,
+        result.removed_epochs,
+        result.removed_blocks
+    ))
+}
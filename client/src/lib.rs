@@ -18,13 +18,20 @@ extern crate tempdir;
 mod config_macro;
 pub mod accounts;
 pub mod archive;
+pub mod block_file;
 pub mod common;
 pub mod configuration;
+pub mod db_rollback;
 pub mod full;
 pub mod light;
+pub mod logging;
+pub mod reload;
+pub mod remote_signer;
 pub mod rpc;
 #[cfg(test)]
 mod tests;
+pub mod threshold_cosigner;
+pub mod threshold_signer;
 
 /// Used in Genesis author to indicate test-net/main-net version.
 /// Increased for every test-net/main-net release with reset.
@@ -8,12 +8,17 @@ use parking_lot::{Condvar, Mutex};
 use secret_store::SecretStore;
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
 use crate::{
-    common::{initialize_common_modules, ClientComponents},
+    common::{
+        close_rpc_servers, initialize_common_modules, ClientComponents,
+        ClientServices,
+    },
     configuration::Configuration,
+    reload,
     rpc::{
         extractor::RpcExtractor, impls::light::RpcImpl,
         setup_debug_rpc_apis_light, setup_public_rpc_apis_light,
@@ -30,6 +35,7 @@ use runtime::Runtime;
 pub struct LightClientExtraComponents {
     pub consensus: Arc<ConsensusGraph>,
     pub debug_rpc_http_server: Option<HttpServer>,
+    pub debug_rpc_ipc_server: Option<IpcServer>,
     pub light: Arc<LightQueryService>,
     pub rpc_http_server: Option<HttpServer>,
     pub rpc_tcp_server: Option<TcpServer>,
@@ -44,6 +50,21 @@ impl MallocSizeOf for LightClientExtraComponents {
     fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize { unimplemented!() }
 }
 
+impl ClientServices for LightClientExtraComponents {
+    fn stop_services(&mut self) {
+        close_rpc_servers(
+            self.debug_rpc_http_server.take(),
+            self.debug_rpc_ipc_server.take(),
+            self.rpc_http_server.take(),
+            self.rpc_tcp_server.take(),
+            self.rpc_ws_server.take(),
+        );
+        self.light.stop_network();
+    }
+
+    fn is_importing_blocks(&self) -> bool { self.light.is_importing_blocks() }
+}
+
 pub struct LightClient {}
 
 impl LightClient {
@@ -65,7 +86,7 @@ impl LightClient {
             sync_graph,
             network,
             common_impl,
-            accounts,
+            signer,
             notifications,
             pubsub,
             runtime,
@@ -79,19 +100,35 @@ impl LightClient {
             notifications,
         ));
         light.register().unwrap();
+        let pubsub = pubsub.with_light_service(light.clone());
 
         let rpc_impl = Arc::new(RpcImpl::new(
             conf.rpc_impl_config(),
             light.clone(),
-            accounts,
+            signer,
         ));
+        reload::register(&rpc_impl, |rpc_impl, conf| {
+            rpc_impl.set_config(conf.rpc_impl_config())
+        });
         let debug_rpc_http_server = super::rpc::start_http(
             conf.local_http_config(),
             setup_debug_rpc_apis_light(
                 common_impl.clone(),
                 rpc_impl.clone(),
                 None,
+                &conf,
+            ),
+        )?;
+
+        let debug_rpc_ipc_server = super::rpc::start_ipc(
+            conf.local_ipc_config(),
+            setup_debug_rpc_apis_light(
+                common_impl.clone(),
+                rpc_impl.clone(),
+                Some(pubsub.clone()),
+                &conf,
             ),
+            RpcExtractor,
         )?;
 
         let rpc_tcp_server = super::rpc::start_tcp(
@@ -101,6 +138,7 @@ impl LightClient {
                     common_impl.clone(),
                     rpc_impl.clone(),
                     Some(pubsub.clone()),
+                    &conf,
                 )
             } else {
                 setup_public_rpc_apis_light(
@@ -120,6 +158,7 @@ impl LightClient {
                     common_impl.clone(),
                     rpc_impl.clone(),
                     Some(pubsub),
+                    &conf,
                 )
             } else {
                 setup_public_rpc_apis_light(
@@ -135,7 +174,7 @@ impl LightClient {
         let rpc_http_server = super::rpc::start_http(
             conf.http_config(),
             if conf.is_test_mode() {
-                setup_debug_rpc_apis_light(common_impl, rpc_impl, None)
+                setup_debug_rpc_apis_light(common_impl, rpc_impl, None, &conf)
             } else {
                 setup_public_rpc_apis_light(common_impl, rpc_impl, None, &conf)
             },
@@ -147,6 +186,7 @@ impl LightClient {
             other_components: LightClientExtraComponents {
                 consensus,
                 debug_rpc_http_server,
+                debug_rpc_ipc_server,
                 light,
                 rpc_http_server,
                 rpc_tcp_server,
@@ -0,0 +1,173 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The logger of the node. Unless a log4rs configuration file is used, the
+//! log levels of the modules can be changed at runtime, e.g. with the
+//! `log_setLevel` local RPC.
+
+use crate::configuration::Configuration;
+use lazy_static::lazy_static;
+use log::LevelFilter;
+use log4rs::{
+    append::{console::ConsoleAppender, file::FileAppender},
+    config::{Appender, Config as LogConfig, Logger, Root},
+    encode::{json::JsonEncoder, pattern::PatternEncoder, Encode},
+    Handle,
+};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+
+const LOG_PATTERN: &str = "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}";
+const LOGGER_NOT_ADJUSTABLE: &str =
+    "the log levels are configured by the log config file";
+
+/// The crates logged at the configured `log_level`. The other crates are
+/// logged at the info level.
+const CRATE_NAMES: &[&str] = &[
+    "blockgen",
+    "cfxcore",
+    "conflux",
+    "db",
+    "keymgr",
+    "network",
+    "txgen",
+    "client",
+    "primitives",
+    "io",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LogFormat {
+    /// Human readable lines.
+    Text,
+    /// One JSON object per line, with the time, module, level, message and
+    /// the context of the message, e.g. the peer id or the block hash.
+    Json,
+}
+
+struct LogSettings {
+    log_file: Option<String>,
+    format: LogFormat,
+    levels: BTreeMap<String, LevelFilter>,
+}
+
+impl LogSettings {
+    fn encoder(&self) -> Box<dyn Encode> {
+        match self.format {
+            LogFormat::Text => Box::new(PatternEncoder::new(LOG_PATTERN)),
+            LogFormat::Json => Box::new(JsonEncoder::new()),
+        }
+    }
+
+    fn build(&self) -> Result<LogConfig, String> {
+        let stdout = match self.format {
+            // Keep the default colored pattern of the console.
+            LogFormat::Text => ConsoleAppender::builder().build(),
+            LogFormat::Json => {
+                ConsoleAppender::builder().encoder(self.encoder()).build()
+            }
+        };
+        let mut conf_builder = LogConfig::builder()
+            .appender(Appender::builder().build("stdout", Box::new(stdout)));
+        let mut root_builder = Root::builder().appender("stdout");
+        if let Some(ref log_file) = self.log_file {
+            let file_appender = FileAppender::builder()
+                .encoder(self.encoder())
+                .build(log_file)
+                .map_err(|e| format!("failed to build log pattern: {:?}", e))?;
+            conf_builder = conf_builder.appender(
+                Appender::builder().build("logfile", Box::new(file_appender)),
+            );
+            root_builder = root_builder.appender("logfile");
+        }
+        for (module, level) in &self.levels {
+            conf_builder =
+                conf_builder.logger(Logger::builder().build(module, *level));
+        }
+        conf_builder
+            .build(root_builder.build(LevelFilter::Info))
+            .map_err(|e| format!("failed to build log config: {:?}", e))
+    }
+}
+
+lazy_static! {
+    /// The handle to reconfigure the logger, if it's not configured with a
+    /// log4rs configuration file.
+    static ref LOGGER: Mutex<Option<(Handle, LogSettings)>> = Mutex::new(None);
+}
+
+/// Initialize the logger.
+///
+/// If `log_conf` is provided, use it for log configuration and ignore
+/// `log_file`, `log_format` and `log_level`. Otherwise, set stdout to INFO
+/// and set all our crate log to `log_level`.
+pub fn initialize(conf: &Configuration) -> Result<(), String> {
+    if let Some(ref log_conf) = conf.raw_conf.log_conf {
+        return log4rs::init_file(log_conf, Default::default()).map_err(|e| {
+            format!("failed to initialize log with log config file: {:?}", e)
+        });
+    }
+
+    let settings = LogSettings {
+        log_file: conf.raw_conf.log_file.clone(),
+        format: conf.raw_conf.log_format,
+        levels: CRATE_NAMES
+            .iter()
+            .map(|name| (name.to_string(), conf.raw_conf.log_level))
+            .collect(),
+    };
+    let handle = log4rs::init_config(settings.build()?).map_err(|e| {
+        format!("failed to initialize log with config: {:?}", e)
+    })?;
+    *LOGGER.lock() = Some((handle, settings));
+    Ok(())
+}
+
+/// The log levels of the modules which are configured explicitly.
+pub fn log_levels() -> Result<BTreeMap<String, LevelFilter>, String> {
+    match &*LOGGER.lock() {
+        Some((_, settings)) => Ok(settings.levels.clone()),
+        None => Err(LOGGER_NOT_ADJUSTABLE.into()),
+    }
+}
+
+/// Set the log level of `module`, which is a crate or a module path like
+/// `cfxcore::sync`, and its submodules.
+pub fn set_log_level(module: &str, level: LevelFilter) -> Result<(), String> {
+    update_log_levels(|levels| {
+        levels.insert(module.into(), level);
+    })
+}
+
+/// Set the log level of all our crates, as `log_level` in the configuration.
+/// The levels set for their submodules are kept.
+pub fn set_crates_log_level(level: LevelFilter) -> Result<(), String> {
+    update_log_levels(|levels| {
+        for name in CRATE_NAMES {
+            levels.insert(name.to_string(), level);
+        }
+    })
+}
+
+fn update_log_levels<F>(update: F) -> Result<(), String>
+where F: FnOnce(&mut BTreeMap<String, LevelFilter>) {
+    let mut logger = LOGGER.lock();
+    let (handle, settings) = match &mut *logger {
+        Some(logger) => logger,
+        None => return Err(LOGGER_NOT_ADJUSTABLE.into()),
+    };
+    let previous = settings.levels.clone();
+    update(&mut settings.levels);
+    match settings.build() {
+        Ok(config) => {
+            handle.set_config(config);
+            Ok(())
+        }
+        Err(e) => {
+            // Keep the settings consistent with the logger in use.
+            settings.levels = previous;
+            Err(e)
+        }
+    }
+}
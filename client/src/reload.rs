@@ -0,0 +1,218 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Reload a subset of the configuration without restarting the node, on
+//! SIGHUP or with the `reload_config` local RPC. The reloadable entries are:
+//! - `log_level`, unless the log levels are configured by `log_conf`.
+//! - `tx_pool_min_tx_gas_price`, `tx_pool_max_tx_gas`,
+//!   `tx_pool_max_tx_data_bytes` and `tx_pool_packing_strategy`. The limits set
+//!   with the `txpool_setAdmissionLimits` local RPC are overridden.
+//! - `max_outgoing_peers` and `max_incoming_peers`.
+//! - `get_logs_filter_max_limit`, `estimate_gas_error_tolerance` and
+//!   `estimate_gas_max_iterations`.
+//!
+//! The changes of the other entries take effect on restart.
+//!
+//! A reload is applied entirely or not at all. The entries are checked when
+//! the configuration is loaded, the log level, which may still fail to apply,
+//! is applied first, and the components apply the rest infallibly.
+
+use crate::{configuration::Configuration, logging};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
+
+type ReloadHook = Box<dyn Fn(&Configuration) + Send>;
+
+struct Reloader {
+    /// The configuration loaded last, with the configuration file and the
+    /// commandline arguments to load it again.
+    conf: Configuration,
+    hooks: Vec<ReloadHook>,
+}
+
+impl Reloader {
+    fn new(conf: &Configuration) -> Self {
+        Reloader {
+            conf: conf.clone(),
+            hooks: Vec::new(),
+        }
+    }
+
+    fn register<T, F>(&mut self, component: &Arc<T>, reload: F)
+    where
+        T: Send + Sync + 'static,
+        F: Fn(&T, &Configuration) + Send + 'static,
+    {
+        let component: Weak<T> = Arc::downgrade(component);
+        self.hooks.push(Box::new(move |conf| {
+            if let Some(component) = component.upgrade() {
+                reload(&component, conf);
+            }
+        }));
+    }
+
+    /// Apply the reloaded configuration. Nothing is applied and the last
+    /// configuration is kept if it fails to load or to apply the log level.
+    fn reload(&mut self) -> Result<(), String> {
+        let conf = self.conf.reload()?;
+        if conf.raw_conf.log_conf.is_none() {
+            logging::set_crates_log_level(conf.raw_conf.log_level)?;
+        }
+        for hook in &self.hooks {
+            hook(&conf);
+        }
+        self.conf = conf;
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref RELOADER: Mutex<Option<Reloader>> = Mutex::new(None);
+}
+
+/// Start to accept reloads of `conf`. The components of a previous client
+/// are unregistered.
+pub fn initialize(conf: &Configuration) {
+    *RELOADER.lock() = Some(Reloader::new(conf));
+    #[cfg(unix)]
+    listen_sighup();
+}
+
+/// Apply the reloaded configuration to `component` with `reload`, as long
+/// as the component is alive. The entries which may be invalid must be
+/// checked when the configuration is loaded, as `reload` cannot fail.
+pub fn register<T, F>(component: &Arc<T>, reload: F)
+where
+    T: Send + Sync + 'static,
+    F: Fn(&T, &Configuration) + Send + 'static,
+{
+    if let Some(reloader) = &mut *RELOADER.lock() {
+        reloader.register(component, reload);
+    }
+}
+
+/// Load the configuration file again and apply the reloadable entries.
+pub fn reload() -> Result<(), String> {
+    RELOADER
+        .lock()
+        .as_mut()
+        .ok_or_else(|| "the node is not started".to_string())?
+        .reload()?;
+    info!("Configuration reloaded");
+    Ok(())
+}
+
+#[cfg(unix)]
+fn listen_sighup() {
+    use signal_hook::{iterator::Signals, SIGHUP};
+    use std::{sync::Once, thread};
+
+    static LISTEN: Once = Once::new();
+    LISTEN.call_once(|| {
+        let signals = match Signals::new(&[SIGHUP]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                warn!("Failed to listen to SIGHUP: {}", e);
+                return;
+            }
+        };
+        thread::Builder::new()
+            .name("reload_config".into())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    if let Err(e) = reload() {
+                        warn!("Failed to reload configuration: {}", e);
+                    }
+                }
+            })
+            .expect("Reload thread spawn error");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reloader;
+    use crate::configuration::Configuration;
+    use cfxcore::transaction_pool::PackingStrategy;
+    use parking_lot::Mutex;
+    use std::{fs, sync::Arc};
+    use tempdir::TempDir;
+
+    fn write_config(
+        path: &str, max_outgoing_peers: usize, packing_strategy: &str,
+    ) {
+        // The log levels of the test are not reloaded.
+        fs::write(
+            path,
+            format!(
+                "log_conf = \"log.yaml\"\nmax_outgoing_peers = {}\n\
+                 max_incoming_peers = 20\n\
+                 tx_pool_packing_strategy = \"{}\"\n",
+                max_outgoing_peers, packing_strategy
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let dir = TempDir::new("conflux-reload-test").unwrap();
+        let path = dir.path().join("conflux.toml");
+        let path = path.to_str().unwrap();
+        write_config(path, 8, "random");
+        let conf = Configuration::load(
+            Some(path.into()),
+            vec![("max_incoming_peers".into(), "30".into())],
+        )
+        .unwrap();
+        assert_eq!(conf.raw_conf.max_outgoing_peers, 8);
+        assert_eq!(conf.raw_conf.max_incoming_peers, 30);
+
+        let mut reloader = Reloader::new(&conf);
+        let peers = Arc::new(Mutex::new((0, 0)));
+        reloader.register(&peers, |peers, conf| {
+            *peers.lock() = (
+                conf.raw_conf.max_outgoing_peers,
+                conf.raw_conf.max_incoming_peers,
+            );
+        });
+
+        // The edited entries are applied, and the commandline arguments
+        // still override the configuration file.
+        write_config(path, 16, "gas_price");
+        reloader.reload().unwrap();
+        assert_eq!(*peers.lock(), (16, 30));
+        assert_eq!(reloader.conf.raw_conf.max_outgoing_peers, 16);
+        assert_eq!(
+            reloader.conf.tx_pool_packing_strategy(),
+            PackingStrategy::GasPrice
+        );
+
+        // An invalid configuration file is not applied.
+        fs::write(path, "max_outgoing_peers = \"many\"\n").unwrap();
+        assert!(reloader.reload().is_err());
+        assert_eq!(*peers.lock(), (16, 30));
+        assert_eq!(reloader.conf.raw_conf.max_outgoing_peers, 16);
+
+        // Neither is a valid entry along with an invalid one, which no
+        // component would be able to apply.
+        write_config(path, 24, "fastest");
+        assert!(reloader.reload().is_err());
+        assert_eq!(*peers.lock(), (16, 30));
+        assert_eq!(
+            reloader.conf.tx_pool_packing_strategy(),
+            PackingStrategy::GasPrice
+        );
+
+        // The dropped components are not reloaded.
+        write_config(path, 24, "random");
+        reloader.reload().unwrap();
+        assert_eq!(*peers.lock(), (24, 30));
+        drop(peers);
+        write_config(path, 32, "random");
+        reloader.reload().unwrap();
+        assert_eq!(reloader.conf.raw_conf.max_outgoing_peers, 32);
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A `TransactionSigner` which forwards the signing requests to a remote
+//! signer, e.g. a service in front of an HSM or a cloud KMS, so that the keys
+//! of the accounts never reside on the node.
+//!
+//! The signer is called with JSON-RPC over HTTP. The request is
+//! `signer_sign(address, message)` and the result is the 65 bytes signature
+//! in hex. If an auth token is configured, it's sent as a bearer token in the
+//! `Authorization` header.
+
+use cfx_types::{Address, H520};
+use cfxcore_accounts::{SignError, TransactionSigner};
+use cfxkey::{verify_address, Message, Password, Signature};
+use jsonrpc_http_server::hyper::{
+    client::HttpConnector,
+    header,
+    rt::{Future, Stream},
+    Body, Client, Request,
+};
+use runtime::{Executor, Runtime};
+use serde_json::{json, Value};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteSignerConfig {
+    /// The HTTP endpoint of the remote signer.
+    pub url: String,
+    /// The bearer token to authenticate to the remote signer.
+    pub auth_token: Option<String>,
+    /// The timeout of a signing request.
+    pub timeout: Duration,
+}
+
+pub struct RemoteSigner {
+    endpoint: SignerEndpoint,
+    /// Runs the requests to the endpoint.
+    _runtime: Runtime,
+}
+
+impl RemoteSigner {
+    pub fn new(config: RemoteSignerConfig) -> Self {
+        let runtime = Runtime::with_thread_count(1);
+        RemoteSigner {
+            endpoint: SignerEndpoint::new(
+                config.url,
+                config.auth_token,
+                config.timeout,
+                runtime.executor(),
+            ),
+            _runtime: runtime,
+        }
+    }
+}
+
+/// The JSON-RPC over HTTP endpoint of a signing service.
+pub struct SignerEndpoint {
+    url: String,
+    auth_token: Option<String>,
+    timeout: Duration,
+    next_request_id: AtomicUsize,
+    client: Client<HttpConnector>,
+    /// The signer is called by the RPC handlers, which already run in a
+    /// runtime and can't block on the requests there, so the requests are
+    /// sent in the runtime of the signer.
+    executor: Executor,
+}
+
+impl SignerEndpoint {
+    pub fn new(
+        url: String, auth_token: Option<String>, timeout: Duration,
+        executor: Executor,
+    ) -> Self
+    {
+        SignerEndpoint {
+            url,
+            auth_token,
+            timeout,
+            next_request_id: AtomicUsize::new(0),
+            client: Client::builder().build_http(),
+            executor,
+        }
+    }
+
+    pub fn url(&self) -> &str { &self.url }
+
+    /// Call `method` of the endpoint and return its result.
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let mut builder = Request::post(self.url.as_str());
+        builder.header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = &self.auth_token {
+            builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = builder
+            .body(Body::from(body.to_string()))
+            .map_err(|e| format!("invalid request: {}", e))?;
+
+        let (sender, receiver) = mpsc::channel();
+        self.executor.spawn(
+            self.client
+                .request(request)
+                .and_then(|response| {
+                    let status = response.status();
+                    response
+                        .into_body()
+                        .concat2()
+                        .map(move |body| (status, body))
+                })
+                .then(move |result| {
+                    sender.send(result).ok();
+                    Ok(())
+                }),
+        );
+
+        let (status, body) = receiver
+            .recv_timeout(self.timeout)
+            .map_err(|_| "request timed out".to_string())?
+            .map_err(|e| format!("request failed: {}", e))?;
+        if !status.is_success() {
+            return Err(format!("request failed with status {}", status));
+        }
+        let mut response: Value = serde_json::from_slice(&body)
+            .map_err(|e| format!("invalid response: {}", e))?;
+        if let Some(error) = response.get("error") {
+            return Err(error.to_string());
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err("invalid response: no result".into()),
+        }
+    }
+}
+
+impl TransactionSigner for RemoteSigner {
+    fn sign(
+        &self, address: Address, _password: Option<Password>, message: Message,
+    ) -> Result<Signature, SignError> {
+        let result = self
+            .endpoint
+            .call("signer_sign", json!([address, message]))
+            .map_err(SignError::Remote)?;
+        let signature: H520 = serde_json::from_value(result).map_err(|e| {
+            SignError::Remote(format!("invalid signature: {}", e))
+        })?;
+        let signature = Signature::from(signature);
+        // Never trust a signature which doesn't belong to the account.
+        match verify_address(&address, &signature, &message) {
+            Ok(true) => Ok(signature),
+            _ => Err(SignError::Remote(format!(
+                "the signature does not belong to {:?}",
+                address
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfx_types::H256;
+    use cfxkey::{sign, Generator, KeyPair, Random};
+    use jsonrpc_core::{IoHandler, Params};
+    use jsonrpc_http_server::ServerBuilder;
+
+    /// A remote signer which signs with `key_pair` whatever the address is.
+    fn start_signer(key_pair: KeyPair) -> jsonrpc_http_server::Server {
+        let mut io = IoHandler::new();
+        io.add_method("signer_sign", move |params: Params| {
+            let (_address, message): (Address, H256) = params.parse()?;
+            let signature = sign(key_pair.secret(), &message).unwrap();
+            Ok(json!(H520::from(signature)))
+        });
+        ServerBuilder::new(io)
+            .start_http(&"127.0.0.1:0".parse().unwrap())
+            .unwrap()
+    }
+
+    fn remote_signer(server: &jsonrpc_http_server::Server) -> RemoteSigner {
+        RemoteSigner::new(RemoteSignerConfig {
+            url: format!("http://{}", server.address()),
+            auth_token: Some("token".into()),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn test_remote_sign() {
+        let key_pair = Random.generate().unwrap();
+        let server = start_signer(key_pair.clone());
+        let signer = remote_signer(&server);
+
+        let message = H256::random();
+        let signature = signer.sign(key_pair.address(), None, message).unwrap();
+        assert!(
+            verify_address(&key_pair.address(), &signature, &message).unwrap()
+        );
+
+        // The signature of another account is rejected.
+        let other = Random.generate().unwrap();
+        assert!(signer.sign(other.address(), None, message).is_err());
+        server.close();
+    }
+
+    #[test]
+    fn test_remote_signer_unavailable() {
+        let signer = RemoteSigner::new(RemoteSignerConfig {
+            url: "http://127.0.0.1:1".into(),
+            auth_token: None,
+            timeout: Duration::from_secs(10),
+        });
+        let key_pair = Random.generate().unwrap();
+        assert!(signer
+            .sign(key_pair.address(), None, H256::random())
+            .is_err());
+    }
+}
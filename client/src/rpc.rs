@@ -7,6 +7,10 @@ use jsonrpc_http_server::{
     AccessControlAllowOrigin, DomainsValidation, Server as HttpServer,
     ServerBuilder as HttpServerBuilder,
 };
+use jsonrpc_ipc_server::{
+    MetaExtractor as IpcMetaExtractor, Server as IpcServer,
+    ServerBuilder as IpcServerBuilder,
+};
 use jsonrpc_tcp_server::{
     MetaExtractor as TpcMetaExtractor, Server as TcpServer,
     ServerBuilder as TcpServerBuilder,
@@ -16,7 +20,7 @@ use jsonrpc_ws_server::{
     ServerBuilder as WsServerBuilder,
 };
 use std::{
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
     sync::Arc,
 };
 
@@ -29,6 +33,7 @@ pub mod impls;
 pub mod informant;
 mod interceptor;
 pub mod metadata;
+mod middleware;
 mod traits;
 mod types;
 
@@ -41,26 +46,39 @@ use self::{
     impls::{
         cfx::{CfxHandler, LocalRpcImpl, RpcImpl, TestRpcImpl},
         common::RpcImpl as CommonImpl,
+        explorer::ExplorerImpl,
         light::{
             CfxHandler as LightCfxHandler, DebugRpcImpl as LightDebugRpcImpl,
             RpcImpl as LightImpl, TestRpcImpl as LightTestRpcImpl,
         },
         pubsub::PubSubClient,
     },
-    traits::{cfx::Cfx, debug::LocalRpc, pubsub::PubSub, test::TestRpc},
+    traits::{
+        cfx::Cfx, debug::LocalRpc, explorer::Explorer, pubsub::PubSub,
+        test::TestRpc,
+    },
 };
 
-pub use self::types::{Block as RpcBlock, Origin};
+pub use self::{
+    middleware::{RpcMiddleware, RpcMiddlewareConfig},
+    types::{set_address_format, Block as RpcBlock, Origin, SendTxRequest},
+};
 use crate::{
     configuration::Configuration,
     rpc::{
         error_codes::request_rejected_too_many_request_error,
+        extractor::RpcExtractor,
+        http_common::MetaExtractor as HttpMetaExtractor,
         interceptor::{RpcInterceptor, RpcProxy},
     },
 };
 pub use metadata::Metadata;
 use throttling::token_bucket::{ThrottleResult, TokenBucketManager};
 
+/// The handler of the RPC requests, with the middleware applied to all the
+/// transports.
+pub type RpcHandler = MetaIoHandler<Metadata, RpcMiddleware>;
+
 #[derive(Debug, PartialEq)]
 pub struct TcpConfiguration {
     pub enabled: bool,
@@ -86,12 +104,18 @@ pub struct HttpConfiguration {
     pub address: SocketAddr,
     pub cors_domains: DomainsValidation<AccessControlAllowOrigin>,
     pub keep_alive: bool,
+    /// The maximum size of a request body in bytes.
+    pub max_request_size: Option<usize>,
+    /// The reverse proxies trusted to set the client address in the
+    /// `X-Forwarded-For` and `X-Real-IP` headers.
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl HttpConfiguration {
     pub fn new(
         ip: Option<(u8, u8, u8, u8)>, port: Option<u16>, cors: Option<String>,
-        keep_alive: bool,
+        keep_alive: bool, max_request_size: Option<usize>,
+        trusted_proxies: Vec<IpAddr>,
     ) -> Self
     {
         let ipv4 = match ip {
@@ -114,6 +138,8 @@ impl HttpConfiguration {
                 },
             },
             keep_alive,
+            max_request_size,
+            trusted_proxies,
         }
     }
 }
@@ -122,10 +148,16 @@ impl HttpConfiguration {
 pub struct WsConfiguration {
     pub enabled: bool,
     pub address: SocketAddr,
+    /// The maximum size of a request message in bytes.
+    pub max_request_size: Option<usize>,
 }
 
 impl WsConfiguration {
-    pub fn new(ip: Option<(u8, u8, u8, u8)>, port: Option<u16>) -> Self {
+    pub fn new(
+        ip: Option<(u8, u8, u8, u8)>, port: Option<u16>,
+        max_request_size: Option<usize>,
+    ) -> Self
+    {
         let ipv4 = match ip {
             Some(ip) => Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
             None => Ipv4Addr::new(0, 0, 0, 0),
@@ -133,6 +165,22 @@ impl WsConfiguration {
         WsConfiguration {
             enabled: port.is_some(),
             address: SocketAddr::V4(SocketAddrV4::new(ipv4, port.unwrap_or(0))),
+            max_request_size,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IpcConfiguration {
+    pub enabled: bool,
+    pub path: String,
+}
+
+impl IpcConfiguration {
+    pub fn new(path: Option<String>) -> Self {
+        IpcConfiguration {
+            enabled: path.is_some(),
+            path: path.unwrap_or_default(),
         }
     }
 }
@@ -140,14 +188,15 @@ impl WsConfiguration {
 pub fn setup_public_rpc_apis(
     common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: Option<PubSubClient>,
     conf: &Configuration,
-) -> MetaIoHandler<Metadata>
+) -> RpcHandler
 {
     let cfx = CfxHandler::new(common, rpc).to_delegate();
     let interceptor =
         ThrottleInterceptor::new(&conf.raw_conf.throttling_conf, "rpc");
+    let middleware = RpcMiddleware::new(conf.rpc_middleware_config());
 
     // extend_with maps each method in RpcImpl object into a RPC handler
-    let mut handler = MetaIoHandler::default();
+    let mut handler = RpcHandler::with_middleware(middleware);
     handler.extend_with(RpcProxy::new(cfx, interceptor));
     if let Some(pubsub) = pubsub {
         handler.extend_with(pubsub.to_delegate());
@@ -158,16 +207,17 @@ pub fn setup_public_rpc_apis(
 pub fn setup_debug_rpc_apis(
     common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: Option<PubSubClient>,
     conf: &Configuration,
-) -> MetaIoHandler<Metadata>
+) -> RpcHandler
 {
     let cfx = CfxHandler::new(common.clone(), rpc.clone()).to_delegate();
     let interceptor =
         ThrottleInterceptor::new(&conf.raw_conf.throttling_conf, "rpc_local");
     let test = TestRpcImpl::new(common.clone(), rpc.clone()).to_delegate();
     let debug = LocalRpcImpl::new(common, rpc).to_delegate();
+    let middleware = RpcMiddleware::new(conf.debug_rpc_middleware_config());
 
     // extend_with maps each method in RpcImpl object into a RPC handler
-    let mut handler = MetaIoHandler::default();
+    let mut handler = RpcHandler::with_middleware(middleware);
     handler.extend_with(RpcProxy::new(cfx, interceptor));
     handler.extend_with(test);
     handler.extend_with(debug);
@@ -177,17 +227,32 @@ pub fn setup_debug_rpc_apis(
     handler
 }
 
+pub fn setup_explorer_rpc_apis(
+    explorer: Arc<ExplorerImpl>, conf: &Configuration,
+) -> RpcHandler {
+    let explorer = explorer.to_delegate();
+    let interceptor =
+        ThrottleInterceptor::new(&conf.raw_conf.throttling_conf, "rpc");
+
+    let middleware = RpcMiddleware::new(conf.rpc_middleware_config());
+
+    let mut handler = RpcHandler::with_middleware(middleware);
+    handler.extend_with(RpcProxy::new(explorer, interceptor));
+    handler
+}
+
 pub fn setup_public_rpc_apis_light(
     common: Arc<CommonImpl>, rpc: Arc<LightImpl>, pubsub: Option<PubSubClient>,
     conf: &Configuration,
-) -> MetaIoHandler<Metadata>
+) -> RpcHandler
 {
     let cfx = LightCfxHandler::new(common, rpc).to_delegate();
     let interceptor =
         ThrottleInterceptor::new(&conf.raw_conf.throttling_conf, "rpc");
+    let middleware = RpcMiddleware::new(conf.rpc_middleware_config());
 
     // extend_with maps each method in RpcImpl object into a RPC handler
-    let mut handler = MetaIoHandler::default();
+    let mut handler = RpcHandler::with_middleware(middleware);
     handler.extend_with(RpcProxy::new(cfx, interceptor));
     if let Some(pubsub) = pubsub {
         handler.extend_with(pubsub.to_delegate());
@@ -197,13 +262,16 @@ pub fn setup_public_rpc_apis_light(
 
 pub fn setup_debug_rpc_apis_light(
     common: Arc<CommonImpl>, rpc: Arc<LightImpl>, pubsub: Option<PubSubClient>,
-) -> MetaIoHandler<Metadata> {
+    conf: &Configuration,
+) -> RpcHandler
+{
     let cfx = LightCfxHandler::new(common.clone(), rpc.clone()).to_delegate();
     let test = LightTestRpcImpl::new(common.clone(), rpc.clone()).to_delegate();
     let debug = LightDebugRpcImpl::new(common, rpc).to_delegate();
+    let middleware = RpcMiddleware::new(conf.debug_rpc_middleware_config());
 
     // extend_with maps each method in RpcImpl object into a RPC handler
-    let mut handler = MetaIoHandler::default();
+    let mut handler = RpcHandler::with_middleware(middleware);
     handler.extend_with(cfx);
     handler.extend_with(test);
     handler.extend_with(debug);
@@ -217,7 +285,7 @@ pub fn start_tcp<H, T>(
     conf: TcpConfiguration, handler: H, extractor: T,
 ) -> Result<Option<TcpServer>, String>
 where
-    H: Into<MetaIoHandler<Metadata>>,
+    H: Into<RpcHandler>,
     T: TpcMetaExtractor<Metadata> + 'static,
 {
     if !conf.enabled {
@@ -234,18 +302,44 @@ where
     }
 }
 
+pub fn start_ipc<H, T>(
+    conf: IpcConfiguration, handler: H, extractor: T,
+) -> Result<Option<IpcServer>, String>
+where
+    H: Into<RpcHandler>,
+    T: IpcMetaExtractor<Metadata> + 'static,
+{
+    if !conf.enabled {
+        return Ok(None);
+    }
+
+    match IpcServerBuilder::with_meta_extractor(handler, extractor)
+        .start(&conf.path)
+    {
+        Ok(server) => Ok(Some(server)),
+        Err(io_error) => {
+            Err(format!("IPC error: {} (path = {})", io_error, conf.path))
+        }
+    }
+}
+
 pub fn start_http(
-    conf: HttpConfiguration, handler: MetaIoHandler<Metadata>,
+    conf: HttpConfiguration, handler: RpcHandler,
 ) -> Result<Option<HttpServer>, String> {
     if !conf.enabled {
         return Ok(None);
     }
 
-    match HttpServerBuilder::new(handler)
-        .keep_alive(conf.keep_alive)
-        .cors(conf.cors_domains.clone())
-        .start_http(&conf.address)
-    {
+    let extractor = HttpMetaExtractor::new(RpcExtractor, conf.trusted_proxies);
+    let mut builder =
+        HttpServerBuilder::with_meta_extractor(handler, extractor)
+            .keep_alive(conf.keep_alive)
+            .cors(conf.cors_domains.clone());
+    if let Some(max_request_size) = conf.max_request_size {
+        builder = builder.max_request_body_size(max_request_size);
+    }
+
+    match builder.start_http(&conf.address) {
         Ok(server) => Ok(Some(server)),
         Err(io_error) => Err(format!(
             "HTTP error: {} (addr = {})",
@@ -258,16 +352,19 @@ pub fn start_ws<H, T>(
     conf: WsConfiguration, handler: H, extractor: T,
 ) -> Result<Option<WsServer>, String>
 where
-    H: Into<MetaIoHandler<Metadata>>,
+    H: Into<RpcHandler>,
     T: WsMetaExtractor<Metadata> + 'static,
 {
     if !conf.enabled {
         return Ok(None);
     }
 
-    match WsServerBuilder::with_meta_extractor(handler, extractor)
-        .start(&conf.address)
-    {
+    let mut builder = WsServerBuilder::with_meta_extractor(handler, extractor);
+    if let Some(max_request_size) = conf.max_request_size {
+        builder = builder.max_payload(max_request_size);
+    }
+
+    match builder.start(&conf.address) {
         Ok(server) => Ok(Some(server)),
         Err(io_error) => {
             Err(format!("WS error: {} (addr = {})", io_error, conf.address))
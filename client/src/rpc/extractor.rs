@@ -18,10 +18,11 @@
 
 use crate::rpc::{http_common::HttpMetaExtractor, Metadata, Origin};
 use cfx_types::H256;
+use jsonrpc_ipc_server as ipc;
 use jsonrpc_pubsub::Session;
 use jsonrpc_tcp_server as tcp;
 use jsonrpc_ws_server as ws;
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 //use ws;
 
 /// Common HTTP & IPC metadata extractor.
@@ -32,7 +33,9 @@ impl HttpMetaExtractor for RpcExtractor {
 
     fn read_metadata(
         &self, origin: Option<String>, user_agent: Option<String>,
-    ) -> Metadata {
+        client_ip: Option<IpAddr>, api_key: Option<String>,
+    ) -> Metadata
+    {
         Metadata {
             origin: Origin::Rpc(format!(
                 "{} / {}",
@@ -40,6 +43,8 @@ impl HttpMetaExtractor for RpcExtractor {
                 user_agent.unwrap_or_else(|| "unknown agent".to_string())
             )),
             session: None,
+            client_ip,
+            api_key,
         }
     }
 }
@@ -49,6 +54,19 @@ impl tcp::MetaExtractor<Metadata> for RpcExtractor {
         Metadata {
             origin: Origin::Tcp(req.peer_addr),
             session: Some(Arc::new(Session::new(req.sender.clone()))),
+            client_ip: Some(req.peer_addr.ip()),
+            api_key: None,
+        }
+    }
+}
+
+impl ipc::MetaExtractor<Metadata> for RpcExtractor {
+    fn extract(&self, req: &ipc::RequestContext) -> Metadata {
+        Metadata {
+            origin: Origin::Ipc(H256::from_low_u64_be(req.session_id)),
+            session: Some(Arc::new(Session::new(req.sender.clone()))),
+            client_ip: None,
+            api_key: None,
         }
     }
 }
@@ -60,6 +78,8 @@ impl ws::MetaExtractor<Metadata> for RpcExtractor {
                 session: H256::from_low_u64_be(req.session_id),
             },
             session: Some(Arc::new(Session::new(req.sender()))),
+            client_ip: None,
+            api_key: None,
         }
     }
 }
@@ -263,11 +283,19 @@ mod tests {
         let extractor = RpcExtractor;
 
         // when
-        let meta1 = extractor.read_metadata(None, None);
-        let meta2 = extractor
-            .read_metadata(None, Some("https://conflux-chain.org".to_owned()));
-        let meta3 = extractor
-            .read_metadata(None, Some("https://conflux-chain.org".to_owned()));
+        let meta1 = extractor.read_metadata(None, None, None, None);
+        let meta2 = extractor.read_metadata(
+            None,
+            Some("https://conflux-chain.org".to_owned()),
+            None,
+            None,
+        );
+        let meta3 = extractor.read_metadata(
+            None,
+            Some("https://conflux-chain.org".to_owned()),
+            None,
+            None,
+        );
 
         // then
         assert_eq!(
@@ -0,0 +1,151 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Cache of the `cfx_call` results.
+//!
+//! The result of a call is determined by the call request and the state of
+//! the epoch, which is identified by the pivot block hash of the epoch. The
+//! cache is cleared when the best epoch changes, since most calls are made
+//! against the latest state. Calls with a large input or output are not
+//! cached, so that the memory used is bounded by the capacity.
+
+use crate::rpc::types::{Bytes, CallRequest};
+use cfx_types::H256;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// The maximal size in bytes of the input or the output of a cached call.
+const MAX_CACHED_CALL_BYTES: usize = 64 * 1024;
+
+#[derive(Default)]
+struct CallResultCacheInner {
+    /// The best epoch number when the results are cached.
+    best_epoch: u64,
+    /// The results keyed by the epoch hash and then the request.
+    results: HashMap<H256, HashMap<CallRequest, Bytes>>,
+    len: usize,
+}
+
+pub struct CallResultCache {
+    /// The maximal number of cached results. The cache is disabled if it is
+    /// 0.
+    capacity: usize,
+    inner: Mutex<CallResultCacheInner>,
+}
+
+impl CallResultCache {
+    pub fn new(capacity: usize) -> Self {
+        CallResultCache {
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    pub fn get(
+        &self, best_epoch: u64, epoch_hash: &H256, request: &CallRequest,
+    ) -> Option<Bytes> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut inner = self.inner.lock();
+        Self::invalidate_on_new_epoch(&mut inner, best_epoch);
+        inner.results.get(epoch_hash)?.get(request).cloned()
+    }
+
+    pub fn insert(
+        &self, best_epoch: u64, epoch_hash: H256, request: CallRequest,
+        result: Bytes,
+    )
+    {
+        let input_len = request.data.as_ref().map_or(0, |data| data.0.len());
+        if self.capacity == 0
+            || input_len > MAX_CACHED_CALL_BYTES
+            || result.0.len() > MAX_CACHED_CALL_BYTES
+        {
+            return;
+        }
+        let mut inner = self.inner.lock();
+        Self::invalidate_on_new_epoch(&mut inner, best_epoch);
+        if inner.len < self.capacity {
+            let previous = inner
+                .results
+                .entry(epoch_hash)
+                .or_default()
+                .insert(request, result);
+            if previous.is_none() {
+                inner.len += 1;
+            }
+        }
+    }
+
+    fn invalidate_on_new_epoch(
+        inner: &mut CallResultCacheInner, best_epoch: u64,
+    ) {
+        if inner.best_epoch != best_epoch {
+            inner.best_epoch = best_epoch;
+            inner.results.clear();
+            inner.len = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CallResultCache, MAX_CACHED_CALL_BYTES};
+    use crate::rpc::types::{Bytes, CallRequest};
+    use cfx_types::{H160, H256};
+
+    fn request(to: u64) -> CallRequest {
+        CallRequest {
+            to: Some(H160::from_low_u64_be(to).into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_call_result_cache() {
+        let cache = CallResultCache::new(2);
+        let epoch_hash = H256::from_low_u64_be(1);
+        let result = Bytes::new(vec![1, 2, 3]);
+        cache.insert(10, epoch_hash, request(1), result.clone());
+        assert_eq!(cache.get(10, &epoch_hash, &request(1)), Some(result));
+        assert_eq!(cache.get(10, &epoch_hash, &request(2)), None);
+        assert_eq!(cache.get(10, &H256::from_low_u64_be(2), &request(1)), None);
+
+        // The cache is full.
+        cache.insert(10, epoch_hash, request(2), Bytes::new(vec![2]));
+        cache.insert(10, epoch_hash, request(3), Bytes::new(vec![3]));
+        assert_eq!(cache.get(10, &epoch_hash, &request(3)), None);
+
+        // Invalidated on a new epoch.
+        assert_eq!(cache.get(11, &epoch_hash, &request(1)), None);
+        cache.insert(11, epoch_hash, request(3), Bytes::new(vec![3]));
+        assert_eq!(
+            cache.get(11, &epoch_hash, &request(3)),
+            Some(Bytes::new(vec![3]))
+        );
+    }
+
+    #[test]
+    fn test_large_call_not_cached() {
+        let cache = CallResultCache::new(2);
+        let epoch_hash = H256::from_low_u64_be(1);
+        let large = Bytes::new(vec![0; MAX_CACHED_CALL_BYTES + 1]);
+        cache.insert(10, epoch_hash, request(1), large.clone());
+        assert_eq!(cache.get(10, &epoch_hash, &request(1)), None);
+
+        let mut large_request = request(2);
+        large_request.data = Some(large);
+        cache.insert(10, epoch_hash, large_request.clone(), Bytes::new(vec![]));
+        assert_eq!(cache.get(10, &epoch_hash, &large_request), None);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let cache = CallResultCache::new(0);
+        let epoch_hash = H256::from_low_u64_be(1);
+        cache.insert(10, epoch_hash, request(1), Bytes::new(vec![1]));
+        assert_eq!(cache.get(10, &epoch_hash, &request(1)), None);
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Estimation of the minimal gas limit with which a transaction succeeds.
+//!
+//! The gas used by a successful execution is not always enough as the gas
+//! limit, e.g. a contract may check `gasleft()` or a sub-call may need more
+//! than 63/64 of the remaining gas. So the gas limit is searched by
+//! executing the transaction repeatedly.
+//!
+//! The transaction is executed against the pending state by default, i.e.
+//! after the transactions of its sender in the transaction pool, since it is
+//! usually sent after them.
+
+use cfx_types::U256;
+use primitives::SignedTransaction;
+use std::sync::Arc;
+
+/// Binary search the minimal gas limit in `(lo, hi]` with which `succeeds`
+/// returns true, given that the execution fails with `lo` and succeeds with
+/// `hi`.
+///
+/// The search stops when the range is within `error_tolerance` of the upper
+/// bound or after `max_iterations` executions, so the result may be slightly
+/// larger than the minimal gas limit, but is never smaller than it.
+pub fn binary_search_gas<F, E>(
+    mut lo: u64, mut hi: u64, error_tolerance: f64, max_iterations: usize,
+    mut succeeds: F,
+) -> Result<u64, E>
+where
+    F: FnMut(u64) -> Result<bool, E>,
+{
+    for _ in 0..max_iterations {
+        if lo + 1 >= hi || ((hi - lo) as f64) < error_tolerance * hi as f64 {
+            break;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if succeeds(mid)? {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(hi)
+}
+
+/// Select the transactions of a sender in the transaction pool which are
+/// executed next, i.e. those with consecutive nonces from `nonce`, the nonce
+/// of the sender in the state. Return them in order of nonce and the nonce
+/// following them.
+pub fn pending_transactions(
+    nonce: U256, mut pooled: Vec<Arc<SignedTransaction>>,
+) -> (Vec<Arc<SignedTransaction>>, U256) {
+    pooled.sort_by_key(|tx| tx.nonce);
+    let mut next_nonce = nonce;
+    let mut pending = Vec::new();
+    for tx in pooled {
+        if tx.nonce > next_nonce {
+            break;
+        } else if tx.nonce == next_nonce {
+            next_nonce += U256::one();
+            pending.push(tx);
+        }
+    }
+    (pending, next_nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary_search_gas, pending_transactions};
+    use cfx_types::Address;
+    use primitives::{SignedTransaction, Transaction};
+    use std::sync::Arc;
+
+    fn search(
+        minimal: u64, error_tolerance: f64, max_iterations: usize,
+    ) -> (u64, usize) {
+        let mut executions = 0;
+        let gas = binary_search_gas::<_, ()>(
+            21_000,
+            1_000_000,
+            error_tolerance,
+            max_iterations,
+            |gas| {
+                executions += 1;
+                Ok(gas >= minimal)
+            },
+        )
+        .unwrap();
+        (gas, executions)
+    }
+
+    #[test]
+    fn test_exact() {
+        assert_eq!(search(21_001, 0.0, 100).0, 21_001);
+        assert_eq!(search(54_321, 0.0, 100).0, 54_321);
+        assert_eq!(search(1_000_000, 0.0, 100).0, 1_000_000);
+    }
+
+    #[test]
+    fn test_error_tolerance() {
+        let (gas, executions) = search(54_321, 0.01, 100);
+        assert!(gas >= 54_321);
+        assert!(gas as f64 <= 54_321.0 * 1.01);
+        assert!(executions < search(54_321, 0.0, 100).1);
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let (gas, executions) = search(54_321, 0.0, 3);
+        assert_eq!(executions, 3);
+        assert!(gas >= 54_321);
+    }
+
+    #[test]
+    fn test_error() {
+        let result: Result<u64, &str> =
+            binary_search_gas(0, 100, 0.0, 10, |_| Err("error"));
+        assert_eq!(result, Err("error"));
+    }
+
+    fn pooled(nonces: &[u64]) -> Vec<Arc<SignedTransaction>> {
+        nonces
+            .iter()
+            .map(|nonce| {
+                Arc::new(
+                    Transaction {
+                        nonce: (*nonce).into(),
+                        ..Default::default()
+                    }
+                    .fake_sign(Address::zero()),
+                )
+            })
+            .collect()
+    }
+
+    fn pending_nonces(nonce: u64, nonces: &[u64]) -> (Vec<u64>, u64) {
+        let (pending, next_nonce) =
+            pending_transactions(nonce.into(), pooled(nonces));
+        (
+            pending.iter().map(|tx| tx.nonce.as_u64()).collect(),
+            next_nonce.as_u64(),
+        )
+    }
+
+    #[test]
+    fn test_pending_transactions() {
+        assert_eq!(pending_nonces(3, &[]), (vec![], 3));
+        assert_eq!(pending_nonces(3, &[5, 3, 4]), (vec![3, 4, 5], 6));
+        // The executed transactions are not pending.
+        assert_eq!(pending_nonces(3, &[1, 2, 3]), (vec![3], 4));
+        // The transactions after a gap are not executed next.
+        assert_eq!(pending_nonces(3, &[3, 4, 6]), (vec![3, 4], 5));
+        assert_eq!(pending_nonces(3, &[4]), (vec![], 3));
+    }
+}
@@ -1,5 +1,11 @@
+mod call_cache;
+mod gas_estimation;
 mod poll_manager;
 mod subscribers;
 //mod subscription_mananger;
 
-pub use self::subscribers::{Id as SubscriberId, Subscribers};
+pub use self::{
+    call_cache::CallResultCache,
+    gas_estimation::{binary_search_gas, pending_transactions},
+    subscribers::{Id as SubscriberId, Subscribers},
+};
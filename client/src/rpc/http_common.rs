@@ -20,6 +20,7 @@
 
 use jsonrpc_core;
 use jsonrpc_http_server::{self as http, hyper};
+use std::net::IpAddr;
 
 /// HTTP RPC server impl-independent metadata extractor
 pub trait HttpMetaExtractor: Send + Sync + 'static {
@@ -28,15 +29,22 @@ pub trait HttpMetaExtractor: Send + Sync + 'static {
     /// Extracts metadata from given params.
     fn read_metadata(
         &self, origin: Option<String>, user_agent: Option<String>,
+        client_ip: Option<IpAddr>, api_key: Option<String>,
     ) -> Self::Metadata;
 }
 
 pub struct MetaExtractor<T> {
     extractor: T,
+    trusted_proxies: Vec<IpAddr>,
 }
 
 impl<T> MetaExtractor<T> {
-    pub fn new(extractor: T) -> Self { MetaExtractor { extractor } }
+    pub fn new(extractor: T, trusted_proxies: Vec<IpAddr>) -> Self {
+        MetaExtractor {
+            extractor,
+            trusted_proxies,
+        }
+    }
 }
 
 impl<M, T> http::MetaExtractor<M> for MetaExtractor<T>
@@ -51,6 +59,88 @@ where
 
         let origin = as_string(req.headers().get("origin"));
         let user_agent = as_string(req.headers().get("user-agent"));
-        self.extractor.read_metadata(origin, user_agent)
+        let client_ip = forwarded_client_ip(
+            as_string(req.headers().get("x-forwarded-for")).as_deref(),
+            as_string(req.headers().get("x-real-ip")).as_deref(),
+            &self.trusted_proxies,
+        );
+        let api_key = as_string(req.headers().get("x-api-key"));
+        self.extractor
+            .read_metadata(origin, user_agent, client_ip, api_key)
+    }
+}
+
+/// The address of the client behind the trusted reverse proxies.
+///
+/// The HTTP server does not expose the socket address of the requests, so the
+/// headers are only read when the proxies are configured, and the port must
+/// then be reachable only through them. Each proxy appends the address it
+/// receives the request from to `X-Forwarded-For`, so the client is the last
+/// address not added by a trusted proxy.
+fn forwarded_client_ip(
+    forwarded_for: Option<&str>, real_ip: Option<&str>,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr>
+{
+    if trusted_proxies.is_empty() {
+        return None;
+    }
+    match forwarded_for {
+        Some(forwarded_for) => {
+            let chain = forwarded_for
+                .split(',')
+                .map(|ip| ip.trim().parse())
+                .collect::<Result<Vec<IpAddr>, _>>()
+                .ok()?;
+            chain
+                .iter()
+                .rev()
+                .find(|ip| !trusted_proxies.contains(ip))
+                .or_else(|| chain.first())
+                .cloned()
+        }
+        None => real_ip.and_then(|ip| ip.trim().parse().ok()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::forwarded_client_ip;
+    use std::net::IpAddr;
+
+    #[test]
+    fn test_forwarded_client_ip() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let proxies = vec![ip("10.0.0.1"), ip("10.0.0.2")];
+
+        // The headers are ignored without the trusted proxies.
+        assert_eq!(forwarded_client_ip(Some("1.2.3.4"), None, &[]), None);
+        assert_eq!(forwarded_client_ip(None, Some("1.2.3.4"), &[]), None);
+
+        assert_eq!(
+            forwarded_client_ip(Some("1.2.3.4"), None, &proxies),
+            Some(ip("1.2.3.4"))
+        );
+        // The addresses given by the client are not trusted.
+        assert_eq!(
+            forwarded_client_ip(
+                Some("5.6.7.8, 1.2.3.4, 10.0.0.2"),
+                None,
+                &proxies
+            ),
+            Some(ip("1.2.3.4"))
+        );
+        assert_eq!(
+            forwarded_client_ip(Some("10.0.0.2, 10.0.0.1"), None, &proxies),
+            Some(ip("10.0.0.2"))
+        );
+        assert_eq!(
+            forwarded_client_ip(Some("1.2.3.4, unknown"), None, &proxies),
+            None
+        );
+        assert_eq!(
+            forwarded_client_ip(None, Some(" 1.2.3.4 "), &proxies),
+            Some(ip("1.2.3.4"))
+        );
     }
 }
@@ -30,9 +30,17 @@ macro_rules! not_supported {
 #[derive(Default)]
 pub struct RpcImplConfiguration {
     pub get_logs_filter_max_limit: Option<usize>,
+    /// The maximal number of cached `cfx_call` results.
+    pub call_result_cache_size: usize,
+    /// The gas estimation stops when the estimated gas limit is within this
+    /// ratio of the minimal gas limit.
+    pub estimate_gas_error_tolerance: f64,
+    /// The maximal number of executions to search the gas limit.
+    pub estimate_gas_max_iterations: usize,
 }
 
 pub mod cfx;
 pub mod common;
+pub mod explorer;
 pub mod light;
 pub mod pubsub;
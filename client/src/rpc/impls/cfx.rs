@@ -2,32 +2,39 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use blockgen::BlockGenerator;
-use cfx_parameters::staking::COLLATERAL_PER_BYTE;
-use cfx_statedb::{StateDbExt, StateDbGetOriginalMethods};
+use blockgen::{BlockGenerator, SubmitWorkError};
+use cfx_parameters::consensus::DEFERRED_STATE_EPOCH_COUNT;
+use cfx_statedb::{StateDb, StateDbExt, StateDbGetOriginalMethods};
 use cfx_types::{
     address_util::AddressUtil, BigEndianHash, H160, H256, H520, U128, U256,
     U512, U64,
 };
 use cfxcore::{
     block_data_manager::BlockExecutionResultWithEpoch,
-    executive::{ExecutionError, ExecutionOutcome, TxDropError},
+    executive::{
+        Executed, ExecutionError, ExecutionOutcome, Executive, TxDropError,
+    },
     rpc_errors::{account_result_to_rpc_result, invalid_params_check},
     state_exposer::STATE_EXPOSER,
+    statistics::{SharedChainStatistics, SharedMemoryAccountant},
+    verification::{compute_receipt_proof, compute_receipts_root},
     vm, ConsensusGraph, ConsensusGraphTrait, PeerInfo, SharedConsensusGraph,
     SharedSynchronizationService, SharedTransactionPool,
 };
-use cfxcore_accounts::AccountProvider;
+use cfxcore_accounts::{KeyFile, TransactionSigner};
+use cfxkey::public_to_address;
 use delegate::delegate;
 use jsonrpc_core::{BoxFuture, Error as JsonRpcError, Result as JsonRpcResult};
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, NodeLists, SessionDetails, UpdateNodeOperation,
 };
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use primitives::{
-    filter::Filter, transaction::Action::Call, Account, SignedTransaction,
-    StorageKey, StorageRoot, StorageValue, TransactionWithSignature,
+    filter::Filter,
+    transaction::Action::{self, Call},
+    Account, BlockHeader, BlockHeaderBuilder, SignedTransaction, StorageKey,
+    StorageRoot, StorageValue, TransactionWithSignature,
 };
 use random_crash::*;
 use rlp::Rlp;
@@ -42,18 +49,25 @@ use crate::{
             call_execution_error, invalid_params,
             request_rejected_in_catch_up_mode,
         },
+        helpers::{binary_search_gas, pending_transactions, CallResultCache},
         impls::{common::RpcImpl as CommonImpl, RpcImplConfiguration},
         traits::{cfx::Cfx, debug::LocalRpc, test::TestRpc},
         types::{
-            sign_call, Account as RpcAccount, BlameInfo, Block as RpcBlock,
+            sign_call, Account as RpcAccount, AccountBatch,
+            AccountTransactions, BlameInfo, Block as RpcBlock,
             BlockHashOrEpochNumber, Bytes, CallRequest,
-            CheckBalanceAgainstTransactionResponse, ConsensusGraphStates,
-            EpochNumber, EstimateGasAndCollateralResponse, Filter as RpcFilter,
-            Log as RpcLog, PackedOrExecuted, Receipt as RpcReceipt,
-            RewardInfo as RpcRewardInfo, SendTxRequest,
-            SponsorInfo as RpcSponsorInfo, Status as RpcStatus,
-            SyncGraphStates, Transaction as RpcTransaction, TxPoolPendingInfo,
-            TxWithPoolInfo,
+            CheckBalanceAgainstTransactionResponse, CollateralTrace,
+            ConsensusGraphStates, CreateAccessListResponse, DecodedTransaction,
+            EpochNumber, EpochStatistics as RpcEpochStatistics,
+            EstimateGasAndCollateralResponse, ExecutionDivergence,
+            ExecutionVariant, Filter as RpcFilter, Log as RpcLog, MemoryUsage,
+            PackedOrExecuted, Receipt as RpcReceipt,
+            ReceiptProof as RpcReceiptProof, RejectedTransaction,
+            RewardInfo as RpcRewardInfo, RpcAddress, SendTxRequest,
+            SenderStatus, SponsorInfo as RpcSponsorInfo, Status as RpcStatus,
+            StorageBatch, StorageSlot, SyncGraphStates, SyncStatus,
+            Transaction as RpcTransaction, TransactionDirection,
+            TxAdmissionLimits, TxPoolPendingInfo, TxWithPoolInfo,
         },
         RpcResult,
     },
@@ -68,15 +82,97 @@ lazy_static! {
         register_timer_with_group("rpc", "rpc:getLogs");
 }
 
+/// The default and maximal numbers of the transactions returned by
+/// `cfx_getAccountTransactions`.
+const DEFAULT_ACCOUNT_TRANSACTIONS_LIMIT: u64 = 100;
+const MAX_ACCOUNT_TRANSACTIONS_LIMIT: u64 = 1000;
+/// The maximum number of the accounts or the storage entries read at once by
+/// `cfx_getAccountBatch` and `cfx_getStorageBatch`.
+const MAX_STATE_BATCH_SIZE: usize = 1000;
+
+/// The receipt of transaction `hash`, with the hash of the pivot block of its
+/// epoch, once the epoch is executed.
+pub fn prepare_receipt(
+    consensus_graph: &ConsensusGraph, hash: H256,
+) -> RpcResult<Option<(H256, RpcReceipt)>> {
+    // Get a consistent view from ConsensusInner
+    let maybe_results =
+        consensus_graph.get_transaction_receipt_and_block_info(&hash);
+    let (
+        BlockExecutionResultWithEpoch(epoch_hash, execution_result),
+        address,
+        maybe_state_root,
+    ) = match maybe_results {
+        None => return Ok(None),
+        Some(result_tuple) => result_tuple,
+    };
+
+    let epoch_block_header = consensus_graph
+        .get_data_manager()
+        .block_header_by_hash(&epoch_hash)
+        // FIXME: server error, client should request another server.
+        .ok_or("Inconsistent state")?;
+    let epoch_number = epoch_block_header.height();
+    if epoch_number > consensus_graph.best_executed_state_epoch_number() {
+        // The receipt is only visible to optimistic execution.
+        return Ok(None);
+    }
+
+    // Operations below will not involve the status of ConsensusInner
+    let block = consensus_graph
+        .get_data_manager()
+        .block_by_hash(&address.block_hash, true)
+        // FIXME: server error, client should request another server.
+        .ok_or("Inconsistent state")?;
+    let transaction = block
+        .transactions
+        .get(address.index)
+        // FIXME: server error, client should request another server.
+        .ok_or("Inconsistent state")?
+        .as_ref()
+        .clone();
+    let receipt = execution_result
+        .block_receipts
+        .receipts
+        .get(address.index)
+        // FIXME: server error, client should request another server.
+        .ok_or("Inconsistent state")?
+        .clone();
+    let prior_gas_used = if address.index == 0 {
+        U256::zero()
+    } else {
+        let prior_receipt = execution_result
+            .block_receipts
+            .receipts
+            .get(address.index - 1)
+            // FIXME: server error, client should request another server.
+            .ok_or("Inconsistent state")?
+            .clone();
+        prior_receipt.accumulated_gas_used
+    };
+    let rpc_receipt = RpcReceipt::new(
+        transaction,
+        receipt,
+        address,
+        prior_gas_used,
+        Some(epoch_number),
+        maybe_state_root,
+    );
+    Ok(Some((epoch_hash, rpc_receipt)))
+}
+
 pub struct RpcImpl {
-    config: RpcImplConfiguration,
+    config: RwLock<RpcImplConfiguration>,
     pub consensus: SharedConsensusGraph,
     sync: SharedSynchronizationService,
     block_gen: Arc<BlockGenerator>,
     tx_pool: SharedTransactionPool,
     maybe_txgen: Option<Arc<TransactionGenerator>>,
     maybe_direct_txgen: Option<Arc<Mutex<DirectTransactionGenerator>>>,
-    accounts: Arc<AccountProvider>,
+    signer: Arc<dyn TransactionSigner>,
+    call_cache: CallResultCache,
+    chain_statistics: Option<SharedChainStatistics>,
+    memory_accountant: Option<SharedMemoryAccountant>,
 }
 
 impl RpcImpl {
@@ -85,9 +181,10 @@ impl RpcImpl {
         block_gen: Arc<BlockGenerator>, tx_pool: SharedTransactionPool,
         maybe_txgen: Option<Arc<TransactionGenerator>>,
         maybe_direct_txgen: Option<Arc<Mutex<DirectTransactionGenerator>>>,
-        config: RpcImplConfiguration, accounts: Arc<AccountProvider>,
+        config: RpcImplConfiguration, signer: Arc<dyn TransactionSigner>,
     ) -> Self
     {
+        let call_cache = CallResultCache::new(config.call_result_cache_size);
         RpcImpl {
             consensus,
             sync,
@@ -95,11 +192,37 @@ impl RpcImpl {
             tx_pool,
             maybe_txgen,
             maybe_direct_txgen,
-            config,
-            accounts,
+            config: RwLock::new(config),
+            signer,
+            call_cache,
+            chain_statistics: None,
+            memory_accountant: None,
         }
     }
 
+    /// Serve `cfx_getChainStatistics` with the statistics collected in
+    /// `chain_statistics`.
+    pub fn with_chain_statistics(
+        mut self, chain_statistics: SharedChainStatistics,
+    ) -> Self {
+        self.chain_statistics = Some(chain_statistics);
+        self
+    }
+
+    /// Serve `memory_usage` with the measurements of `memory_accountant`.
+    pub fn with_memory_accountant(
+        mut self, memory_accountant: SharedMemoryAccountant,
+    ) -> Self {
+        self.memory_accountant = Some(memory_accountant);
+        self
+    }
+
+    /// Change the limits of the RPCs. The new `call_result_cache_size` only
+    /// takes effect on restart.
+    pub fn set_config(&self, config: RpcImplConfiguration) {
+        *self.config.write() = config;
+    }
+
     fn consensus_graph(&self) -> &ConsensusGraph {
         self.consensus
             .as_any()
@@ -108,8 +231,9 @@ impl RpcImpl {
     }
 
     fn code(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<Bytes> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState);
         info!(
             "RPC Request: cfx_getCode address={:?} epoch_num={:?}",
@@ -136,8 +260,9 @@ impl RpcImpl {
     }
 
     fn balance(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<U256> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState).into();
         info!(
             "RPC Request: cfx_getBalance address={:?} epoch_num={:?}",
@@ -152,8 +277,9 @@ impl RpcImpl {
     }
 
     fn admin(
-        &self, address: H160, num: Option<EpochNumber>,
-    ) -> RpcResult<Option<H160>> {
+        &self, address: RpcAddress, num: Option<EpochNumber>,
+    ) -> RpcResult<Option<RpcAddress>> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState).into();
         info!(
             "RPC Request: cfx_getAdmin address={:?} epoch_num={:?}",
@@ -168,8 +294,9 @@ impl RpcImpl {
     }
 
     fn sponsor_info(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<RpcSponsorInfo> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState).into();
         info!(
             "RPC Request: cfx_getSponsorInfo address={:?} epoch_num={:?}",
@@ -186,8 +313,9 @@ impl RpcImpl {
     }
 
     fn staking_balance(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<U256> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState).into();
         info!(
             "RPC Request: cfx_getStakingBalance address={:?} epoch_num={:?}",
@@ -202,8 +330,9 @@ impl RpcImpl {
     }
 
     fn collateral_for_storage(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<U256> {
+        let address: H160 = address.into();
         let epoch_num = num.unwrap_or(EpochNumber::LatestState).into();
         info!(
             "RPC Request: cfx_getCollateralForStorage address={:?} epoch_num={:?}",
@@ -221,8 +350,9 @@ impl RpcImpl {
 
     /// Return account related states of the given account
     fn account(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> RpcResult<RpcAccount> {
+        let address: H160 = address.into();
         let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState).into();
 
         info!(
@@ -233,12 +363,50 @@ impl RpcImpl {
         let state_db =
             self.consensus.get_state_db_by_epoch_number(epoch_num)?;
 
-        Ok(RpcAccount::new(match state_db.get_account(&address)? {
+        Self::read_account(&state_db, &address)
+    }
+
+    fn account_batch(
+        &self, addresses: Vec<RpcAddress>, epoch_num: Option<EpochNumber>,
+    ) -> RpcResult<AccountBatch> {
+        let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState).into();
+
+        info!(
+            "RPC Request: cfx_getAccountBatch count={} epoch_num={:?}",
+            addresses.len(),
+            epoch_num
+        );
+        if addresses.len() > MAX_STATE_BATCH_SIZE {
+            bail!(invalid_params(
+                "addresses",
+                format!("should not exceed {}", MAX_STATE_BATCH_SIZE)
+            ));
+        }
+
+        let (epoch_number, epoch_hash, state_db) = self
+            .consensus
+            .get_pinned_state_db_by_epoch_number(epoch_num)?;
+        let accounts = addresses
+            .into_iter()
+            .map(|address| Self::read_account(&state_db, &address.into()))
+            .collect::<RpcResult<_>>()?;
+
+        Ok(AccountBatch {
+            epoch_number: epoch_number.into(),
+            epoch_hash,
+            accounts,
+        })
+    }
+
+    fn read_account(
+        state_db: &StateDb, address: &H160,
+    ) -> RpcResult<RpcAccount> {
+        Ok(RpcAccount::new(match state_db.get_account(address)? {
             Some(t) => t,
             None => account_result_to_rpc_result(
                 "address",
                 Account::new_empty_with_balance(
-                    &address,
+                    address,
                     &U256::zero(), /* balance */
                     &U256::zero(), /* nonce */
                 ),
@@ -279,9 +447,48 @@ impl RpcImpl {
         self.send_transaction_with_signature(tx)
     }
 
+    fn decode_raw_transaction(
+        &self, raw: Bytes,
+    ) -> RpcResult<DecodedTransaction> {
+        info!(
+            "RPC Request: cfx_decodeRawTransaction len={:?}",
+            raw.0.len()
+        );
+
+        let tx: TransactionWithSignature =
+            Rlp::new(&raw.into_vec()).as_val().map_err(|err| {
+                invalid_params("raw", format!("Error: {:?}", err))
+            })?;
+        // The intrinsic gas checked when the transaction is admitted.
+        let spec = self
+            .tx_pool
+            .admission_spec(self.consensus.best_epoch_number());
+        let intrinsic_gas = Executive::gas_required_for(
+            tx.action == Action::Create,
+            &tx.data,
+            &spec,
+        );
+        let sender = tx
+            .recover_public()
+            .ok()
+            .map(|public| public_to_address(&public));
+
+        // The same checks as `cfx_sendRawTransaction` before the insertion.
+        let verdict = match &tx.action {
+            Call(address) if !address.is_valid_address() => {
+                Err("invalid receiver address".to_string())
+            }
+            _ if sender.is_none() => Err("Cannot recover public key".into()),
+            _ => self.tx_pool.verify_transaction(&tx),
+        };
+        Ok(DecodedTransaction::new(&tx, sender, intrinsic_gas, verdict))
+    }
+
     fn storage_at(
-        &self, address: H160, position: H256, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, position: H256,
+        epoch_num: Option<EpochNumber>,
     ) -> RpcResult<Option<H256>> {
+        let address: H160 = address.into();
         let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState).into();
 
         info!(
@@ -291,7 +498,52 @@ impl RpcImpl {
 
         let state_db =
             self.consensus.get_state_db_by_epoch_number(epoch_num)?;
-        let key = StorageKey::new_storage_key(&address, position.as_ref());
+
+        Self::read_storage(&state_db, &address, &position)
+    }
+
+    fn storage_batch(
+        &self, slots: Vec<StorageSlot>, epoch_num: Option<EpochNumber>,
+    ) -> RpcResult<StorageBatch> {
+        let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState).into();
+
+        info!(
+            "RPC Request: cfx_getStorageBatch count={} epoch_num={:?}",
+            slots.len(),
+            epoch_num
+        );
+        if slots.len() > MAX_STATE_BATCH_SIZE {
+            bail!(invalid_params(
+                "slots",
+                format!("should not exceed {}", MAX_STATE_BATCH_SIZE)
+            ));
+        }
+
+        let (epoch_number, epoch_hash, state_db) = self
+            .consensus
+            .get_pinned_state_db_by_epoch_number(epoch_num)?;
+        let values = slots
+            .into_iter()
+            .map(|slot| {
+                Self::read_storage(
+                    &state_db,
+                    &slot.address.into(),
+                    &slot.position,
+                )
+            })
+            .collect::<RpcResult<_>>()?;
+
+        Ok(StorageBatch {
+            epoch_number: epoch_number.into(),
+            epoch_hash,
+            values,
+        })
+    }
+
+    fn read_storage(
+        state_db: &StateDb, address: &H160, position: &H256,
+    ) -> RpcResult<Option<H256>> {
+        let key = StorageKey::new_storage_key(address, position.as_ref());
 
         Ok(match state_db.get::<StorageValue>(key)? {
             Some(entry) => Some(H256::from_uint(&entry.value).into()),
@@ -362,7 +614,7 @@ impl RpcImpl {
         let epoch_height = consensus_graph.best_epoch_number();
         let chain_id = consensus_graph.best_chain_id();
         let tx = tx
-            .sign_with(epoch_height, chain_id, password, self.accounts.clone())
+            .sign_with(epoch_height, chain_id, password, self.signer.clone())
             .map_err(|e| {
                 invalid_params(
                     "tx",
@@ -393,8 +645,9 @@ impl RpcImpl {
     }
 
     fn storage_root(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> RpcResult<Option<StorageRoot>> {
+        let address: H160 = address.into();
         let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState).into();
 
         info!(
@@ -466,82 +719,150 @@ impl RpcImpl {
     }
 
     fn prepare_receipt(&self, hash: H256) -> RpcResult<Option<RpcReceipt>> {
-        // Get a consistent view from ConsensusInner
-        let consensus_graph = self.consensus_graph();
+        Ok(prepare_receipt(self.consensus_graph(), hash)?
+            .map(|(_, receipt)| receipt))
+    }
 
-        let maybe_results =
-            consensus_graph.get_transaction_receipt_and_block_info(&hash);
-        let (
-            BlockExecutionResultWithEpoch(epoch_hash, execution_result),
-            address,
-            maybe_state_root,
-        ) = match maybe_results {
-            None => return Ok(None),
-            Some(result_tuple) => result_tuple,
-        };
+    fn transaction_receipt(
+        &self, tx_hash: H256,
+    ) -> RpcResult<Option<RpcReceipt>> {
+        let hash: H256 = tx_hash.into();
+        info!("RPC Request: cfx_getTransactionReceipt({:?})", hash);
+        self.prepare_receipt(hash)
+    }
 
-        let epoch_block_header = self
-            .consensus
-            .get_data_manager()
+    fn transaction_receipt_proof(
+        &self, tx_hash: H256,
+    ) -> RpcResult<Option<RpcReceiptProof>> {
+        info!("RPC Request: cfx_getTransactionReceiptProof({:?})", tx_hash);
+        let consensus_graph = self.consensus_graph();
+        let (BlockExecutionResultWithEpoch(epoch_hash, _), address, _) =
+            match consensus_graph
+                .get_transaction_receipt_and_block_info(&tx_hash)
+            {
+                None => return Ok(None),
+                Some(result_tuple) => result_tuple,
+            };
+
+        let data_man = consensus_graph.get_data_manager();
+        let epoch_number = data_man
             .block_header_by_hash(&epoch_hash)
             // FIXME: server error, client should request another server.
-            .ok_or("Inconsistent state")?;
-        let epoch_number = epoch_block_header.height();
+            .ok_or("Inconsistent state")?
+            .height();
         if epoch_number > consensus_graph.best_executed_state_epoch_number() {
             // The receipt is only visible to optimistic execution.
             return Ok(None);
         }
 
-        // Operations below will not involve the status of ConsensusInner
-        let block = self
-            .consensus
-            .get_data_manager()
-            .block_by_hash(&address.block_hash, true)
-            // FIXME: server error, client should request another server.
+        let epoch_hashes = consensus_graph.get_block_hashes_by_epoch(
+            EpochNumber::Num(epoch_number.into()).into(),
+        )?;
+        if epoch_hashes.last() != Some(&epoch_hash) {
+            // The pivot chain has changed since the receipt is retrieved.
+            return Ok(None);
+        }
+        let block_index_in_epoch = epoch_hashes
+            .iter()
+            .position(|hash| *hash == address.block_hash)
             .ok_or("Inconsistent state")?;
-        let transaction = block
-            .transactions
-            .get(address.index)
-            // FIXME: server error, client should request another server.
-            .ok_or("Inconsistent state")?
-            .as_ref()
-            .clone();
-        let receipt = execution_result
-            .block_receipts
-            .receipts
-            .get(address.index)
-            // FIXME: server error, client should request another server.
-            .ok_or("Inconsistent state")?
-            .clone();
-        let prior_gas_used = if address.index == 0 {
-            U256::zero()
-        } else {
-            let prior_receipt = execution_result
-                .block_receipts
-                .receipts
-                .get(address.index - 1)
-                // FIXME: server error, client should request another server.
-                .ok_or("Inconsistent state")?
-                .clone();
-            prior_receipt.accumulated_gas_used
-        };
-        let rpc_receipt = RpcReceipt::new(
-            transaction,
-            receipt,
-            address,
-            prior_gas_used,
-            Some(epoch_number),
-            maybe_state_root,
-        );
-        Ok(Some(rpc_receipt))
+        let epoch_receipts = epoch_hashes
+            .iter()
+            .map(|hash| {
+                data_man
+                    .block_execution_result_by_hash_with_epoch(
+                        hash,
+                        &epoch_hash,
+                        false, /* update_pivot_assumption */
+                        false, /* update_cache */
+                    )
+                    .map(|result| result.block_receipts)
+                    .ok_or("Inconsistent state")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proof = compute_receipt_proof(
+            &epoch_receipts,
+            block_index_in_epoch,
+            address.index,
+        )
+        .ok_or("Inconsistent state")?;
+        let receipts_root = compute_receipts_root(&epoch_receipts);
+
+        let (witness_header, witness_receipts_roots) =
+            match self.receipts_root_witness(epoch_number) {
+                Some(witness) => witness,
+                // The receipts root is not committed on the pivot chain yet.
+                None => return Ok(None),
+            };
+        let witness_height = witness_header.height();
+        let index = (witness_height - epoch_number - DEFERRED_STATE_EPOCH_COUNT)
+            as usize;
+        if witness_receipts_roots.get(index) != Some(&receipts_root) {
+            // The pivot chain has changed since the receipts are retrieved.
+            return Ok(None);
+        }
+
+        Ok(Some(RpcReceiptProof {
+            transaction_hash: tx_hash,
+            block_hash: address.block_hash,
+            epoch_number: epoch_number.into(),
+            epoch_hash,
+            receipts_root,
+            witness_header: Bytes::new(rlp::encode(&*witness_header)),
+            witness_receipts_roots,
+            proof: Bytes::new(rlp::encode(&proof)),
+        }))
     }
 
-    fn transaction_receipt(
-        &self, tx_hash: H256,
-    ) -> RpcResult<Option<RpcReceipt>> {
-        let hash: H256 = tx_hash.into();
-        info!("RPC Request: cfx_getTransactionReceipt({:?})", hash);
-        self.prepare_receipt(hash)
+    /// The pivot header committing the receipts root of `epoch_number`, with
+    /// the correct receipts roots from its height backwards. It's the header
+    /// `DEFERRED_STATE_EPOCH_COUNT` epochs later if its root is correct, or
+    /// else the first later header which blames it with the correct roots.
+    fn receipts_root_witness(
+        &self, epoch_number: u64,
+    ) -> Option<(Arc<BlockHeader>, Vec<H256>)> {
+        let consensus_graph = self.consensus_graph();
+        let data_man = consensus_graph.get_data_manager();
+        let pivot_hash = |height: u64| {
+            consensus_graph
+                .get_hash_from_epoch_number(
+                    EpochNumber::Num(height.into()).into(),
+                )
+                .ok()
+        };
+        // The correct receipts root in the pivot header at `height`.
+        let receipts_root_at = |height: u64| {
+            pivot_hash(height.saturating_sub(DEFERRED_STATE_EPOCH_COUNT))
+                .and_then(|hash| {
+                    data_man.get_epoch_execution_commitment_with_db(&hash)
+                })
+                .map(|commitment| commitment.receipts_root)
+        };
+
+        let height = epoch_number + DEFERRED_STATE_EPOCH_COUNT;
+        for witness in height..=consensus_graph.best_epoch_number() {
+            let header = pivot_hash(witness)
+                .and_then(|hash| data_man.block_header_by_hash(&hash))?;
+            let first_blamed = witness.saturating_sub(header.blame() as u64);
+            if first_blamed > height {
+                continue;
+            }
+            let roots = (first_blamed..=witness)
+                .rev()
+                .map(&receipts_root_at)
+                .collect::<Option<Vec<_>>>()?;
+            let committed_root = match header.blame() {
+                0 => roots[0],
+                _ => BlockHeaderBuilder::compute_blame_state_root_vec_root(
+                    roots.clone(),
+                ),
+            };
+            if committed_root == *header.deferred_receipts_root() {
+                return Some((header, roots));
+            }
+        }
+        None
     }
 
     fn generate_empty_blocks(&self, num_blocks: usize) -> RpcResult<Vec<H256>> {
@@ -721,7 +1042,8 @@ impl RpcImpl {
         // If max_limit is set, the value in `filter` will be modified to
         // satisfy this limitation to avoid loading too many blocks
         // TODO Should the response indicate that the filter is modified?
-        if let Some(max_limit) = self.config.get_logs_filter_max_limit {
+        let max_limit = self.config.read().get_logs_filter_max_limit;
+        if let Some(max_limit) = max_limit {
             if filter.limit.is_none() || filter.limit.unwrap() > max_limit {
                 filter.limit = Some(max_limit);
             }
@@ -766,10 +1088,76 @@ impl RpcImpl {
         Ok(ret)
     }
 
+    fn work(&self) -> RpcResult<(H256, H256, U64)> {
+        info!("RPC Request: cfx_getWork");
+        let remote_work = self.block_gen.remote_work();
+        if !remote_work.is_mining() {
+            bail!(invalid_params("work", "Remote mining is not enabled"));
+        }
+        match remote_work.work() {
+            Some(problem) => Ok((
+                problem.block_hash,
+                BigEndianHash::from_uint(&problem.boundary),
+                problem.block_height.into(),
+            )),
+            None => bail!(invalid_params("work", "No work is available yet")),
+        }
+    }
+
+    fn submit_work(&self, nonce: U256, pow_hash: H256) -> RpcResult<bool> {
+        info!(
+            "RPC Request: cfx_submitWork nonce={:?} pow_hash={:?}",
+            nonce, pow_hash
+        );
+        match self.block_gen.remote_work().submit(nonce, pow_hash) {
+            Ok(()) => Ok(true),
+            Err(SubmitWorkError::NotMining) => {
+                bail!(invalid_params("work", "Remote mining is not enabled"))
+            }
+            Err(e) => {
+                // Like the other miner softwares expect, a stale work or an
+                // invalid nonce is rejected with `false`.
+                debug!("Submitted work is rejected: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    fn submit_hashrate(&self, hashrate: U256, id: H256) -> RpcResult<bool> {
+        info!(
+            "RPC Request: cfx_submitHashrate hashrate={:?} id={:?}",
+            hashrate, id
+        );
+        let remote_work = self.block_gen.remote_work();
+        remote_work.submit_hashrate(hashrate, id);
+        debug!(
+            "Reported hashrate of remote miners: {}",
+            remote_work.hashrate()
+        );
+        Ok(true)
+    }
+
     fn call(
         &self, request: CallRequest, epoch: Option<EpochNumber>,
     ) -> RpcResult<Bytes> {
-        match self.exec_transaction(request, epoch)? {
+        let consensus_graph = self.consensus_graph();
+        let best_epoch = consensus_graph.best_epoch_number();
+        // The state of an epoch is identified by its pivot block hash.
+        let epoch_hash = consensus_graph
+            .get_block_hashes_by_epoch(
+                epoch.clone().unwrap_or(EpochNumber::LatestState).into(),
+            )
+            .ok()
+            .and_then(|hashes| hashes.last().cloned());
+        if let Some(epoch_hash) = &epoch_hash {
+            if let Some(result) =
+                self.call_cache.get(best_epoch, epoch_hash, &request)
+            {
+                return Ok(result);
+            }
+        }
+
+        match self.exec_transaction(request.clone(), epoch)? {
             ExecutionOutcome::NotExecutedDrop(TxDropError::OldNonce(expected, got)) => {
                 bail!(call_execution_error(
                     "Transaction can not be executed".into(),
@@ -782,6 +1170,12 @@ impl RpcImpl {
                     format! {"invalid recipient address {:?}", recipient}.into_bytes()
                 ))
             }
+            ExecutionOutcome::NotExecutedDrop(TxDropError::ChainIdMismatch(expected, got)) => {
+                bail!(call_execution_error(
+                    "Transaction can not be executed".into(),
+                    format! {"chain_id mismatch expected {:?} got {:?}", expected, got}.into_bytes()
+                ))
+            }
             ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
                 bail!(call_execution_error(
                     "Transaction can not be executed".into(),
@@ -801,14 +1195,56 @@ impl RpcImpl {
                     format! {"{:?}", e}.into_bytes()
                 ))
             }
-            ExecutionOutcome::Finished(executed) => Ok(executed.output.into()),
+            ExecutionOutcome::Finished(executed) => {
+                let result: Bytes = executed.output.into();
+                if let Some(epoch_hash) = epoch_hash {
+                    self.call_cache.insert(
+                        best_epoch,
+                        epoch_hash,
+                        request,
+                        result.clone(),
+                    );
+                }
+                Ok(result)
+            }
         }
     }
 
     fn estimate_gas_and_collateral(
-        &self, request: CallRequest, epoch: Option<EpochNumber>,
+        &self, mut request: CallRequest, epoch: Option<EpochNumber>,
     ) -> RpcResult<EstimateGasAndCollateralResponse> {
-        let executed = match self.exec_transaction(request, epoch)? {
+        let consensus_graph = self.consensus_graph();
+        // Estimate against the pending state unless the epoch is specified.
+        let (epoch, pending) = match epoch {
+            Some(epoch) => (epoch, Vec::new()),
+            None => {
+                let pending = match request.from {
+                    Some(ref from) => {
+                        let from: H160 = from.clone().into();
+                        let (nonce, _) =
+                            self.tx_pool.get_state_account_info(&from)?;
+                        let (_, pooled_txs) = self.tx_pool.content(Some(from));
+                        let (pending, next_nonce) =
+                            pending_transactions(nonce, pooled_txs);
+                        request.nonce.get_or_insert(next_nonce);
+                        pending
+                    }
+                    None => Vec::new(),
+                };
+                (EpochNumber::LatestState, pending)
+            }
+        };
+
+        let best_epoch_height = consensus_graph.best_epoch_number();
+        let chain_id = consensus_graph.best_chain_id();
+        let signed_tx = sign_call(best_epoch_height, chain_id, request);
+        trace!("estimate tx {:?}, pending txs {:?}", signed_tx, pending);
+        let outcome = consensus_graph.call_virtual(
+            &signed_tx,
+            epoch.clone().into(),
+            &pending,
+        )?;
+        let (executed, succeeded) = match outcome {
             ExecutionOutcome::NotExecutedDrop(TxDropError::OldNonce(expected, got)) => {
                 bail!(call_execution_error(
                     "Can not estimate: transaction can not be executed".into(),
@@ -821,6 +1257,12 @@ impl RpcImpl {
                     format! {"invalid recipient address {:?}", recipient}.into_bytes()
                 ))
             }
+            ExecutionOutcome::NotExecutedDrop(TxDropError::ChainIdMismatch(expected, got)) => {
+                bail!(call_execution_error(
+                    "Can not estimate: transaction can not be executed".into(),
+                    format! {"chain_id mismatch expected {:?} got {:?}", expected, got}.into_bytes()
+                ))
+            }
             ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
                 bail!(call_execution_error(
                     "Can not estimate: transaction can not be executed".into(),
@@ -830,7 +1272,7 @@ impl RpcImpl {
             ExecutionOutcome::ExecutionErrorBumpNonce(
                 ExecutionError::VmError(vm::Error::Reverted),
                 executed,
-            ) => executed,
+            ) => (executed, false),
             ExecutionOutcome::ExecutionErrorBumpNonce(e, _) => {
                 bail!(call_execution_error(
                     format! {"Can not estimate: transaction execution failed, \
@@ -838,30 +1280,162 @@ impl RpcImpl {
                     format! {"{:?}", e}.into_bytes()
                 ))
             }
-            ExecutionOutcome::Finished(executed) => executed,
+            ExecutionOutcome::Finished(executed) => (executed, true),
+        };
+        // A reverted transaction is estimated by the gas it used.
+        let (gas, executed) = if succeeded {
+            self.estimate_minimal_gas(&signed_tx, epoch, &pending, executed)?
+        } else {
+            (executed.gas_used, executed)
         };
         let mut storage_collateralized = 0;
         for storage_change in &executed.storage_collateralized {
             storage_collateralized += storage_change.amount;
         }
         let response = EstimateGasAndCollateralResponse {
-            gas_used: executed.gas_used.into(),
+            gas_used: gas.into(),
             storage_collateralized: storage_collateralized.into(),
         };
         Ok(response)
     }
 
+    /// Search the minimal gas limit with which `tx` succeeds after the
+    /// `pending` transactions, given that it succeeds with its own gas limit
+    /// and `executed` is the result. Return the gas limit and the result of
+    /// the execution with it.
+    fn estimate_minimal_gas(
+        &self, tx: &SignedTransaction, epoch: EpochNumber,
+        pending: &[Arc<SignedTransaction>], executed: Executed,
+    ) -> RpcResult<(U256, Executed)>
+    {
+        let consensus_graph = self.consensus_graph();
+        // The gas limit of a call request is capped, so it fits in u64.
+        // The transaction is assumed to fail with less gas than it used.
+        let lo = executed.gas_used.as_u64().saturating_sub(1);
+        let hi = tx.gas.as_u64();
+        let mut estimated = executed;
+        let (error_tolerance, max_iterations) = {
+            let config = self.config.read();
+            (
+                config.estimate_gas_error_tolerance,
+                config.estimate_gas_max_iterations,
+            )
+        };
+        let gas = binary_search_gas(
+            lo,
+            hi,
+            error_tolerance,
+            max_iterations,
+            |gas| {
+                let mut unsigned = tx.transaction.transaction.unsigned.clone();
+                unsigned.gas = gas.into();
+                let trial = unsigned.fake_sign(tx.sender());
+                let outcome = consensus_graph.call_virtual(
+                    &trial,
+                    epoch.clone().into(),
+                    pending,
+                )?;
+                match outcome {
+                    ExecutionOutcome::Finished(executed) => {
+                        estimated = executed;
+                        Ok(true)
+                    }
+                    _ => Ok(false),
+                }
+            },
+        )?;
+        debug!(
+            "estimate gas: gas_used={}, gas_limit={}",
+            estimated.gas_used, gas
+        );
+        Ok((gas.into(), estimated))
+    }
+
+    fn create_access_list(
+        &self, request: CallRequest, epoch: Option<EpochNumber>,
+    ) -> RpcResult<CreateAccessListResponse> {
+        let consensus_graph = self.consensus_graph();
+        let epoch = epoch.unwrap_or(EpochNumber::LatestState);
+
+        let best_epoch_height = consensus_graph.best_epoch_number();
+        let chain_id = consensus_graph.best_chain_id();
+        let signed_tx = sign_call(best_epoch_height, chain_id, request);
+        trace!("create access list for tx {:?}", signed_tx);
+        let (outcome, access_list) =
+            consensus_graph.create_access_list(&signed_tx, epoch.into())?;
+        // The accounts accessed before a failure are still returned.
+        let executed = match outcome {
+            ExecutionOutcome::NotExecutedDrop(e) => {
+                bail!(call_execution_error(
+                    "Can not create access list: transaction can not be executed".into(),
+                    format! {"{:?}", e}.into_bytes()
+                ))
+            }
+            ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                bail!(call_execution_error(
+                    "Can not create access list: transaction can not be executed".into(),
+                    format! {"{:?}", e}.into_bytes()
+                ))
+            }
+            ExecutionOutcome::ExecutionErrorBumpNonce(_, executed) => executed,
+            ExecutionOutcome::Finished(executed) => executed,
+        };
+        Ok(CreateAccessListResponse {
+            access_list: access_list.into_iter().map(Into::into).collect(),
+            gas_used: executed.gas_used.into(),
+        })
+    }
+
+    fn trace_collateral(
+        &self, request: CallRequest, epoch: Option<EpochNumber>,
+    ) -> RpcResult<Vec<CollateralTrace>> {
+        let consensus_graph = self.consensus_graph();
+        let epoch = epoch.unwrap_or(EpochNumber::LatestState);
+
+        let best_epoch_height = consensus_graph.best_epoch_number();
+        let chain_id = consensus_graph.best_chain_id();
+        let signed_tx = sign_call(best_epoch_height, chain_id, request);
+        trace!("trace collateral for tx {:?}", signed_tx);
+        let (outcome, traces) =
+            consensus_graph.trace_collateral(&signed_tx, epoch.into())?;
+        // The changes of a failed execution are all discarded.
+        match outcome {
+            ExecutionOutcome::NotExecutedDrop(e) => {
+                bail!(call_execution_error(
+                    "Can not trace collateral: transaction can not be executed".into(),
+                    format! {"{:?}", e}.into_bytes()
+                ))
+            }
+            ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                bail!(call_execution_error(
+                    "Can not trace collateral: transaction can not be executed".into(),
+                    format! {"{:?}", e}.into_bytes()
+                ))
+            }
+            ExecutionOutcome::ExecutionErrorBumpNonce(..)
+            | ExecutionOutcome::Finished(_) => {}
+        }
+        Ok(traces.into_iter().map(Into::into).collect())
+    }
+
     fn check_balance_against_transaction(
-        &self, account_addr: H160, contract_addr: H160, gas_limit: U256,
-        gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>,
+        &self, account_addr: RpcAddress, contract_addr: RpcAddress,
+        gas_limit: U256, gas_price: U256, storage_limit: U256,
+        epoch: Option<EpochNumber>,
     ) -> RpcResult<CheckBalanceAgainstTransactionResponse>
     {
+        let account_addr: H160 = account_addr.into();
+        let contract_addr: H160 = contract_addr.into();
         let epoch = epoch.unwrap_or(EpochNumber::LatestState).into();
         if storage_limit > U256::from(std::u64::MAX) {
             bail!(JsonRpcError::invalid_params(format!("storage_limit has to be within the range of u64 but {} supplied!", storage_limit)));
         }
 
-        let state = self.consensus.get_state_by_epoch_number(epoch)?;
+        let state = self.consensus.get_state_by_epoch_number(epoch.clone())?;
+        let spec = self.tx_pool.machine().spec(invalid_params_check(
+            "epoch",
+            self.consensus.get_height_from_epoch_number(epoch),
+        )?);
         let gas_cost = gas_limit.full_mul(gas_price);
         let mut gas_sponsored = false;
         let mut storage_sponsored = false;
@@ -879,7 +1453,7 @@ impl RpcImpl {
         };
         let will_pay_tx_fee = !gas_sponsored || gas_sponsor_balance < gas_cost;
 
-        let storage_limit_in_drip = storage_limit * *COLLATERAL_PER_BYTE;
+        let storage_limit_in_drip = storage_limit * spec.collateral_per_byte;
         let storage_sponsor_balance = if storage_sponsored {
             state.sponsor_balance_for_collateral(&contract_addr)?
         } else {
@@ -900,6 +1474,90 @@ impl RpcImpl {
         })
     }
 
+    fn check_sender_status(
+        &self, address: RpcAddress,
+    ) -> RpcResult<SenderStatus> {
+        let address: H160 = address.into();
+        info!("RPC Request: cfx_checkSenderStatus address={:?}", address);
+
+        let (nonce, balance) = self.tx_pool.get_state_account_info(&address)?;
+        let (_, pooled_txs) = self.tx_pool.content(Some(address));
+        let pooled_nonces = pooled_txs.iter().map(|tx| tx.nonce).collect();
+        let last_rejected_transaction = self
+            .tx_pool
+            .get_rejected_transaction(&address)
+            .map(|tx| RejectedTransaction {
+                hash: tx.hash,
+                nonce: tx.nonce,
+                reason: tx.reason,
+            });
+
+        Ok(SenderStatus::new(
+            nonce,
+            balance,
+            pooled_nonces,
+            last_rejected_transaction,
+        ))
+    }
+
+    fn chain_statistics(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> RpcResult<Vec<RpcEpochStatistics>> {
+        info!(
+            "RPC Request: cfx_getChainStatistics from_epoch={} to_epoch={}",
+            from_epoch, to_epoch
+        );
+        let chain_statistics =
+            self.chain_statistics.as_ref().ok_or_else(|| {
+                JsonRpcError::invalid_params(
+                    "The chain statistics are not collected, set \
+                     chain_statistics_epochs to collect them",
+                )
+            })?;
+        Ok(chain_statistics
+            .range(from_epoch.as_u64(), to_epoch.as_u64())
+            .into_iter()
+            .map(RpcEpochStatistics::from)
+            .collect())
+    }
+
+    fn account_transactions(
+        &self, address: RpcAddress, direction: TransactionDirection,
+        offset: Option<U64>, limit: Option<U64>,
+    ) -> RpcResult<AccountTransactions>
+    {
+        let address: H160 = address.into();
+        let offset = offset.map_or(0, |offset| offset.as_u64());
+        let limit = limit
+            .map_or(DEFAULT_ACCOUNT_TRANSACTIONS_LIMIT, |limit| limit.as_u64());
+        info!(
+            "RPC Request: cfx_getAccountTransactions address={:?} \
+             direction={:?} offset={} limit={}",
+            address, direction, offset, limit
+        );
+        if limit > MAX_ACCOUNT_TRANSACTIONS_LIMIT {
+            bail!(invalid_params(
+                "limit",
+                format!("should not exceed {}", MAX_ACCOUNT_TRANSACTIONS_LIMIT)
+            ));
+        }
+
+        let (total, transactions) = self
+            .consensus
+            .get_data_manager()
+            .address_transactions(&address, direction.into(), offset, limit)
+            .ok_or_else(|| {
+                JsonRpcError::invalid_params(
+                    "The transactions are not indexed by address, set \
+                     persist_address_tx_index to serve them",
+                )
+            })?;
+        Ok(AccountTransactions {
+            total: total.into(),
+            transactions,
+        })
+    }
+
     fn exec_transaction(
         &self, request: CallRequest, epoch: Option<EpochNumber>,
     ) -> RpcResult<ExecutionOutcome> {
@@ -910,13 +1568,17 @@ impl RpcImpl {
         let chain_id = consensus_graph.best_chain_id();
         let signed_tx = sign_call(best_epoch_height, chain_id, request);
         trace!("call tx {:?}", signed_tx);
-        consensus_graph.call_virtual(&signed_tx, epoch.into())
+        consensus_graph.call_virtual(&signed_tx, epoch.into(), &[])
     }
 
     fn current_sync_phase(&self) -> RpcResult<String> {
         Ok(self.sync.current_sync_phase().name().into())
     }
 
+    fn sync_status(&self) -> RpcResult<SyncStatus> {
+        Ok(SyncStatus::new(self.sync.sync_status()))
+    }
+
     /// Return the pivot chain block hashes in `height_range` (inclusive) and
     /// their subtree weight. If it's none, return all pivot chain from
     /// `cur_era_genesis` to chain tip.
@@ -949,6 +1611,24 @@ impl RpcImpl {
         ))
     }
 
+    fn differential_execution(
+        &self, start_epoch: u64, end_epoch: u64, first: ExecutionVariant,
+        second: ExecutionVariant,
+    ) -> RpcResult<Vec<ExecutionDivergence>>
+    {
+        info!(
+            "RPC Request: test_differentialExecution start_epoch={} \
+             end_epoch={} first={:?} second={:?}",
+            start_epoch, end_epoch, first, second
+        );
+        let divergences = self.consensus_graph().differential_execute(
+            start_epoch,
+            end_epoch,
+            &[first.into(), second.into()],
+        )?;
+        Ok(divergences.into_iter().map(Into::into).collect())
+    }
+
     fn expire_block_gc(&self, timeout: u64) -> RpcResult<()> {
         self.sync.expire_block_gc(timeout);
         Ok(())
@@ -965,6 +1645,34 @@ impl RpcImpl {
         Ok(SyncGraphStates::new(sync_graph_states))
     }
 
+    pub fn memory_usage(&self) -> RpcResult<MemoryUsage> {
+        let accountant = self.memory_accountant.as_ref().ok_or_else(|| {
+            JsonRpcError::invalid_params(
+                "The memory usage is not measured, set \
+                 print_memory_usage_period_s to measure it",
+            )
+        })?;
+        let usage = accountant.latest().ok_or_else(|| {
+            JsonRpcError::invalid_params("The memory usage is not measured yet")
+        })?;
+        Ok(usage.into())
+    }
+
+    /// Return the block to mine next with the transactions selected by the
+    /// packing strategy, without mining it.
+    pub fn txpool_block_template(&self) -> RpcResult<RpcBlock> {
+        info!("RPC Request: txpool_blockTemplate()");
+        let block = self.block_gen.assemble_block_template();
+        let consensus_graph = self.consensus_graph();
+        let inner = &*consensus_graph.inner.read();
+        Ok(RpcBlock::new(
+            &block,
+            inner,
+            consensus_graph.get_data_manager(),
+            true, /* include_txs */
+        ))
+    }
+
     /// Return (block_info.status, state_valid)
     /// Return Error if either field is missing
     pub fn get_block_status(&self, block_hash: H256) -> RpcResult<(u8, bool)> {
@@ -1027,42 +1735,59 @@ impl Cfx for CfxHandler {
             fn skipped_blocks_by_epoch(&self, num: EpochNumber) -> JsonRpcResult<Vec<H256>>;
             fn epoch_number(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<U256>;
             fn gas_price(&self) -> JsonRpcResult<U256>;
-            fn next_nonce(&self, address: H160, num: Option<BlockHashOrEpochNumber>)
+            fn next_nonce(&self, address: RpcAddress, num: Option<BlockHashOrEpochNumber>)
                 -> JsonRpcResult<U256>;
             fn get_status(&self) -> JsonRpcResult<RpcStatus>;
             fn get_client_version(&self) -> JsonRpcResult<String>;
         }
 
         to self.rpc_impl {
-            fn code(&self, addr: H160, epoch_number: Option<EpochNumber>) -> BoxFuture<Bytes>;
-            fn account(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<RpcAccount>;
+            fn code(&self, addr: RpcAddress, epoch_number: Option<EpochNumber>) -> BoxFuture<Bytes>;
+            fn account(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<RpcAccount>;
+            fn account_batch(&self, addresses: Vec<RpcAddress>, num: Option<EpochNumber>) -> JsonRpcResult<AccountBatch>;
             fn interest_rate(&self, num: Option<EpochNumber>) -> JsonRpcResult<U256>;
             fn accumulate_interest_rate(&self, num: Option<EpochNumber>) -> JsonRpcResult<U256>;
-            fn admin(&self, address: H160, num: Option<EpochNumber>)
-                -> BoxFuture<Option<H160>>;
-            fn sponsor_info(&self, address: H160, num: Option<EpochNumber>)
+            fn admin(&self, address: RpcAddress, num: Option<EpochNumber>)
+                -> BoxFuture<Option<RpcAddress>>;
+            fn sponsor_info(&self, address: RpcAddress, num: Option<EpochNumber>)
                 -> BoxFuture<RpcSponsorInfo>;
-            fn balance(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<U256>;
-            fn staking_balance(&self, address: H160, num: Option<EpochNumber>)
+            fn balance(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<U256>;
+            fn staking_balance(&self, address: RpcAddress, num: Option<EpochNumber>)
                 -> BoxFuture<U256>;
-            fn collateral_for_storage(&self, address: H160, num: Option<EpochNumber>)
+            fn collateral_for_storage(&self, address: RpcAddress, num: Option<EpochNumber>)
                 -> BoxFuture<U256>;
             fn call(&self, request: CallRequest, epoch: Option<EpochNumber>)
                 -> JsonRpcResult<Bytes>;
             fn estimate_gas_and_collateral(
                 &self, request: CallRequest, epoch_number: Option<EpochNumber>)
                 -> JsonRpcResult<EstimateGasAndCollateralResponse>;
+            fn create_access_list(
+                &self, request: CallRequest, epoch_number: Option<EpochNumber>)
+                -> JsonRpcResult<CreateAccessListResponse>;
+            fn trace_collateral(
+                &self, request: CallRequest, epoch_number: Option<EpochNumber>)
+                -> JsonRpcResult<Vec<CollateralTrace>>;
             fn check_balance_against_transaction(
-                &self, account_addr: H160, contract_addr: H160, gas_limit: U256, gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>,
+                &self, account_addr: RpcAddress, contract_addr: RpcAddress, gas_limit: U256, gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>,
             ) -> JsonRpcResult<CheckBalanceAgainstTransactionResponse>;
+            fn check_sender_status(&self, address: RpcAddress) -> JsonRpcResult<SenderStatus>;
+            fn account_transactions(&self, address: RpcAddress, direction: TransactionDirection, offset: Option<U64>, limit: Option<U64>) -> JsonRpcResult<AccountTransactions>;
+            fn chain_statistics(&self, from_epoch: U64, to_epoch: U64) -> JsonRpcResult<Vec<RpcEpochStatistics>>;
             fn get_logs(&self, filter: RpcFilter) -> BoxFuture<Vec<RpcLog>>;
             fn get_block_reward_info(&self, num: EpochNumber) -> JsonRpcResult<Vec<RpcRewardInfo>>;
+            fn work(&self) -> JsonRpcResult<(H256, H256, U64)>;
+            fn submit_work(&self, nonce: U256, pow_hash: H256) -> JsonRpcResult<bool>;
+            fn submit_hashrate(&self, hashrate: U256, id: H256) -> JsonRpcResult<bool>;
             fn send_raw_transaction(&self, raw: Bytes) -> JsonRpcResult<H256>;
-            fn storage_at(&self, addr: H160, pos: H256, epoch_number: Option<EpochNumber>)
+            fn decode_raw_transaction(&self, raw: Bytes) -> JsonRpcResult<DecodedTransaction>;
+            fn storage_at(&self, addr: RpcAddress, pos: H256, epoch_number: Option<EpochNumber>)
                 -> BoxFuture<Option<H256>>;
+            fn storage_batch(&self, slots: Vec<StorageSlot>, epoch_number: Option<EpochNumber>)
+                -> JsonRpcResult<StorageBatch>;
             fn transaction_by_hash(&self, hash: H256) -> BoxFuture<Option<RpcTransaction>>;
             fn transaction_receipt(&self, tx_hash: H256) -> BoxFuture<Option<RpcReceipt>>;
-            fn storage_root(&self, address: H160, epoch_num: Option<EpochNumber>) -> BoxFuture<Option<StorageRoot>>;
+            fn transaction_receipt_proof(&self, tx_hash: H256) -> JsonRpcResult<Option<RpcReceiptProof>>;
+            fn storage_root(&self, address: RpcAddress, epoch_num: Option<EpochNumber>) -> BoxFuture<Option<StorageRoot>>;
         }
     }
 }
@@ -1107,6 +1832,9 @@ impl TestRpc for TestRpcImpl {
                 -> JsonRpcResult<H256>;
             fn get_pivot_chain_and_weight(&self, height_range: Option<(u64, u64)>) -> JsonRpcResult<Vec<(H256, U256)>>;
             fn get_executed_info(&self, block_hash: H256) -> JsonRpcResult<(H256, H256)> ;
+            fn differential_execution(
+                &self, start_epoch: u64, end_epoch: u64, first: ExecutionVariant, second: ExecutionVariant)
+                -> JsonRpcResult<Vec<ExecutionDivergence>>;
             fn generate_fixed_block(
                 &self, parent_hash: H256, referee: Vec<H256>, num_txs: usize, adaptive: bool, difficulty: Option<u64>)
                 -> JsonRpcResult<H256>;
@@ -1139,35 +1867,56 @@ impl LocalRpcImpl {
 impl LocalRpc for LocalRpcImpl {
     delegate! {
         to self.common {
+            fn txpool_admission_limits(&self) -> JsonRpcResult<TxAdmissionLimits>;
+            fn txpool_set_admission_limits(&self, limits: TxAdmissionLimits) -> JsonRpcResult<TxAdmissionLimits>;
             fn clear_tx_pool(&self) -> JsonRpcResult<()>;
             fn net_node(&self, id: NodeId) -> JsonRpcResult<Option<(String, Node)>>;
             fn net_disconnect_node(&self, id: NodeId, op: Option<UpdateNodeOperation>)
                 -> JsonRpcResult<bool>;
             fn net_sessions(&self, node_id: Option<NodeId>) -> JsonRpcResult<Vec<SessionDetails>>;
             fn net_throttling(&self) -> JsonRpcResult<throttling::Service>;
+            fn net_add_static_node(&self, id: NodeId, address: SocketAddr) -> JsonRpcResult<()>;
+            fn net_remove_static_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn net_add_trusted_node(&self, id: NodeId, address: SocketAddr) -> JsonRpcResult<()>;
+            fn net_remove_trusted_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn net_ban_node(&self, id: NodeId) -> JsonRpcResult<()>;
+            fn net_unban_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+            fn net_node_lists(&self) -> JsonRpcResult<Option<NodeLists>>;
+            fn log_levels(&self) -> JsonRpcResult<BTreeMap<String, String>>;
+            fn log_set_level(&self, module: String, level: String) -> JsonRpcResult<bool>;
+            fn reload_config(&self) -> JsonRpcResult<bool>;
             fn tx_inspect(&self, hash: H256) -> JsonRpcResult<TxWithPoolInfo>;
-            fn txpool_content(&self, address: Option<H160>) -> JsonRpcResult<
+            fn txpool_content(&self, address: Option<RpcAddress>) -> JsonRpcResult<
                 BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<RpcTransaction>>>>>;
-            fn txs_from_pool(&self, address: Option<H160>) -> JsonRpcResult<Vec<RpcTransaction>>;
-            fn txpool_inspect(&self, address: Option<H160>) -> JsonRpcResult<
+            fn txs_from_pool(&self, address: Option<RpcAddress>) -> JsonRpcResult<Vec<RpcTransaction>>;
+            fn txpool_inspect(&self, address: Option<RpcAddress>) -> JsonRpcResult<
                 BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<String>>>>>;
             fn txpool_status(&self) -> JsonRpcResult<BTreeMap<String, usize>>;
-            fn accounts(&self) -> JsonRpcResult<Vec<H160>>;
-            fn new_account(&self, password: String) -> JsonRpcResult<H160>;
+            fn accounts(&self) -> JsonRpcResult<Vec<RpcAddress>>;
+            fn new_account(&self, password: String) -> JsonRpcResult<RpcAddress>;
+            fn import_raw_key(&self, secret: H256, password: String) -> JsonRpcResult<RpcAddress>;
+            fn export_account(&self, address: RpcAddress, password: String) -> JsonRpcResult<KeyFile>;
+            fn change_password(
+                &self, address: RpcAddress, password: String, new_password: String)
+                -> JsonRpcResult<bool>;
+            fn remove_account(&self, address: RpcAddress, password: String) -> JsonRpcResult<bool>;
             fn unlock_account(
-                &self, address: H160, password: String, duration: Option<U128>)
+                &self, address: RpcAddress, password: String, duration: Option<U128>)
                 -> JsonRpcResult<bool>;
-            fn lock_account(&self, address: H160) -> JsonRpcResult<bool>;
-            fn sign(&self, data: Bytes, address: H160, password: Option<String>)
+            fn lock_account(&self, address: RpcAddress) -> JsonRpcResult<bool>;
+            fn sign(&self, data: Bytes, address: RpcAddress, password: Option<String>)
                 -> JsonRpcResult<H520>;
-            fn tx_inspect_pending(&self, address: H160) -> JsonRpcResult<TxPoolPendingInfo>;
+            fn tx_inspect_pending(&self, address: RpcAddress) -> JsonRpcResult<TxPoolPendingInfo>;
 
         }
 
         to self.rpc_impl {
             fn current_sync_phase(&self) -> JsonRpcResult<String>;
+            fn sync_status(&self) -> JsonRpcResult<SyncStatus>;
             fn consensus_graph_state(&self) -> JsonRpcResult<ConsensusGraphStates>;
             fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
+            fn memory_usage(&self) -> JsonRpcResult<MemoryUsage>;
+            fn txpool_block_template(&self) -> JsonRpcResult<RpcBlock>;
             fn send_transaction(
                 &self, tx: SendTxRequest, password: Option<String>) -> BoxFuture<H256>;
             fn sign_transaction(&self, tx: SendTxRequest, password: Option<String>) -> JsonRpcResult<String>;
@@ -2,13 +2,16 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::{
-    types::{
-        Block as RpcBlock, BlockHashOrEpochNumber, Bytes, EpochNumber,
-        Status as RpcStatus, Transaction as RpcTransaction, TxPoolPendingInfo,
-        TxWithPoolInfo,
+use crate::{
+    logging, reload,
+    rpc::{
+        types::{
+            Block as RpcBlock, BlockHashOrEpochNumber, Bytes, EpochNumber,
+            RpcAddress, Status as RpcStatus, Transaction as RpcTransaction,
+            TxAdmissionLimits, TxPoolPendingInfo, TxWithPoolInfo,
+        },
+        RpcResult,
     },
-    RpcResult,
 };
 use bigdecimal::BigDecimal;
 use cfx_parameters::consensus::ONE_CFX_IN_DRIP;
@@ -17,8 +20,8 @@ use cfxcore::{
     BlockDataManager, ConsensusGraph, ConsensusGraphTrait, PeerInfo,
     SharedConsensusGraph, SharedTransactionPool,
 };
-use cfxcore_accounts::AccountProvider;
-use cfxkey::Password;
+use cfxcore_accounts::{AccountProvider, KeyFile, TransactionSigner};
+use cfxkey::{Password, Secret};
 use clap::crate_version;
 use jsonrpc_core::{
     Error as RpcError, Result as JsonRpcResult, Value as RpcValue,
@@ -27,7 +30,7 @@ use keccak_hash::keccak;
 use network::{
     node_table::{Node, NodeEndpoint, NodeEntry, NodeId},
     throttling::{self, THROTTLING_SERVICE},
-    NetworkService, SessionDetails, UpdateNodeOperation,
+    NetworkService, NodeLists, SessionDetails, UpdateNodeOperation,
 };
 use num_bigint::{BigInt, ToBigInt};
 use parking_lot::{Condvar, Mutex};
@@ -47,7 +50,7 @@ where F: Fn(Arc<SignedTransaction>) -> T {
         BTreeMap::new();
 
     for tx in txs {
-        let addr = format!("{:?}", tx.sender());
+        let addr = RpcAddress::from(tx.sender()).to_string();
         let addr_entry: &mut BTreeMap<usize, Vec<T>> =
             addr_grouped_txs.entry(addr).or_insert(BTreeMap::new());
 
@@ -61,6 +64,25 @@ where F: Fn(Arc<SignedTransaction>) -> T {
     addr_grouped_txs
 }
 
+/// Scale the confirmation risk of a block in [0, 1] to [0, 2^256 - 1].
+pub fn scale_confirmation_risk(risk: f64) -> U256 {
+    let risk: BigDecimal = risk.into();
+    let scale = BigInt::parse_bytes(
+        b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+        16,
+    )
+    .expect("failed to unwrap U256::max into bigInt");
+
+    //TODO: there's a precision problem here, it should be fine under a
+    // (2^256 - 1) scale
+    let scaled_risk: BigInt = (risk * scale)
+        .to_bigint()
+        .expect("failed to convert scaled risk to bigInt");
+    let (sign, big_endian_bytes) = scaled_risk.to_bytes_be();
+    assert_ne!(sign, num_bigint::Sign::Minus);
+    U256::from(big_endian_bytes.as_slice())
+}
+
 pub struct RpcImpl {
     exit: Arc<(Mutex<bool>, Condvar)>,
     consensus: SharedConsensusGraph,
@@ -68,13 +90,14 @@ pub struct RpcImpl {
     network: Arc<NetworkService>,
     tx_pool: SharedTransactionPool,
     accounts: Arc<AccountProvider>,
+    signer: Arc<dyn TransactionSigner>,
 }
 
 impl RpcImpl {
     pub fn new(
         exit: Arc<(Mutex<bool>, Condvar)>, consensus: SharedConsensusGraph,
         network: Arc<NetworkService>, tx_pool: SharedTransactionPool,
-        accounts: Arc<AccountProvider>,
+        accounts: Arc<AccountProvider>, signer: Arc<dyn TransactionSigner>,
     ) -> Self
     {
         let data_man = consensus.get_data_manager().clone();
@@ -86,6 +109,7 @@ impl RpcImpl {
             network,
             tx_pool,
             accounts,
+            signer,
         }
     }
 
@@ -156,22 +180,7 @@ impl RpcImpl {
         let result = consensus_graph
             .confirmation_meter
             .confirmation_risk_by_hash(inner, block_hash.into());
-        if result.is_none() {
-            Ok(None)
-        } else {
-            let risk: BigDecimal = result.unwrap().into();
-            let scale = BigInt::parse_bytes(b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", 16).expect("failed to unwrap U256::max into bigInt");
-
-            //TODO: there's a precision problem here, it should be fine under a
-            // (2^256 - 1) scale
-            let scaled_risk: BigInt = (risk * scale)
-                .to_bigint()
-                .expect("failed to convert scaled risk to bigInt");
-            let (sign, big_endian_bytes) = scaled_risk.to_bytes_be();
-            assert_ne!(sign, num_bigint::Sign::Minus);
-            let rpc_result = U256::from(big_endian_bytes.as_slice());
-            Ok(Some(rpc_result.into()))
-        }
+        Ok(result.map(scale_confirmation_risk))
     }
 
     pub fn block_by_hash(
@@ -247,8 +256,9 @@ impl RpcImpl {
     }
 
     pub fn next_nonce(
-        &self, address: H160, num: Option<BlockHashOrEpochNumber>,
+        &self, address: RpcAddress, num: Option<BlockHashOrEpochNumber>,
     ) -> RpcResult<U256> {
+        let address: H160 = address.into();
         let consensus_graph = self.consensus_graph();
         let num = num.unwrap_or(BlockHashOrEpochNumber::EpochNumber(
             EpochNumber::LatestState,
@@ -447,6 +457,19 @@ impl RpcImpl {
 
 // Debug RPC implementation
 impl RpcImpl {
+    pub fn txpool_admission_limits(&self) -> JsonRpcResult<TxAdmissionLimits> {
+        Ok(self.tx_pool.admission_limits().into())
+    }
+
+    pub fn txpool_set_admission_limits(
+        &self, limits: TxAdmissionLimits,
+    ) -> JsonRpcResult<TxAdmissionLimits> {
+        info!("RPC Request: txpool_setAdmissionLimits({:?})", limits);
+        let admission_limits = limits.apply_to(self.tx_pool.admission_limits());
+        self.tx_pool.set_admission_limits(admission_limits);
+        Ok(admission_limits.into())
+    }
+
     pub fn clear_tx_pool(&self) -> JsonRpcResult<()> {
         self.tx_pool.clear_tx_pool();
         Ok(())
@@ -482,10 +505,100 @@ impl RpcImpl {
         }
     }
 
+    pub fn net_add_static_node(
+        &self, id: NodeId, address: SocketAddr,
+    ) -> JsonRpcResult<()> {
+        let node = NodeEntry {
+            id,
+            endpoint: NodeEndpoint {
+                address,
+                udp_port: address.port(),
+            },
+        };
+        info!("RPC Request: net_add_static_node({:?})", node);
+        self.network
+            .add_static_node(node)
+            .map_err(|e| RpcError::invalid_params(format!("{}", e)))
+    }
+
+    pub fn net_remove_static_node(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: net_remove_static_node({:?})", id);
+        self.network
+            .remove_static_node(&id)
+            .map_err(|_| RpcError::internal_error())
+    }
+
+    pub fn net_add_trusted_node(
+        &self, id: NodeId, address: SocketAddr,
+    ) -> JsonRpcResult<()> {
+        let node = NodeEntry {
+            id,
+            endpoint: NodeEndpoint {
+                address,
+                udp_port: address.port(),
+            },
+        };
+        info!("RPC Request: net_add_trusted_node({:?})", node);
+        self.network
+            .add_trusted_node(node)
+            .map_err(|e| RpcError::invalid_params(format!("{}", e)))
+    }
+
+    pub fn net_remove_trusted_node(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: net_remove_trusted_node({:?})", id);
+        self.network
+            .remove_trusted_node(&id)
+            .map_err(|_| RpcError::internal_error())
+    }
+
+    pub fn net_ban_node(&self, id: NodeId) -> JsonRpcResult<()> {
+        info!("RPC Request: net_ban_node({:?})", id);
+        self.network
+            .ban_node(&id)
+            .map_err(|_| RpcError::internal_error())
+    }
+
+    pub fn net_unban_node(&self, id: NodeId) -> JsonRpcResult<bool> {
+        info!("RPC Request: net_unban_node({:?})", id);
+        self.network
+            .unban_node(&id)
+            .map_err(|_| RpcError::internal_error())
+    }
+
+    pub fn net_node_lists(&self) -> JsonRpcResult<Option<NodeLists>> {
+        Ok(self.network.get_node_lists())
+    }
+
     pub fn net_throttling(&self) -> JsonRpcResult<throttling::Service> {
         Ok(THROTTLING_SERVICE.read().clone())
     }
 
+    pub fn log_levels(&self) -> JsonRpcResult<BTreeMap<String, String>> {
+        let levels = logging::log_levels().map_err(RpcError::invalid_params)?;
+        Ok(levels
+            .into_iter()
+            .map(|(module, level)| (module, level.to_string().to_lowercase()))
+            .collect())
+    }
+
+    pub fn log_set_level(
+        &self, module: String, level: String,
+    ) -> JsonRpcResult<bool> {
+        info!("RPC Request: log_setLevel({}, {})", module, level);
+        let level = level.parse().map_err(|_| {
+            RpcError::invalid_params(format!("Invalid log level {}", level))
+        })?;
+        logging::set_log_level(&module, level)
+            .map_err(RpcError::invalid_params)?;
+        Ok(true)
+    }
+
+    pub fn reload_config(&self) -> JsonRpcResult<bool> {
+        info!("RPC Request: reload_config()");
+        reload::reload().map_err(RpcError::invalid_params)?;
+        Ok(true)
+    }
+
     pub fn tx_inspect(&self, hash: H256) -> JsonRpcResult<TxWithPoolInfo> {
         let mut ret = TxWithPoolInfo::default();
         let hash: H256 = hash.into();
@@ -518,8 +631,9 @@ impl RpcImpl {
     }
 
     pub fn txs_from_pool(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<Vec<RpcTransaction>> {
+        let address: Option<H160> = address.map(Into::into);
         let (ready_txs, deferred_txs) = self.tx_pool.content(address);
         let converter = |tx: &Arc<SignedTransaction>| -> RpcTransaction {
             RpcTransaction::from_signed(&tx, None)
@@ -533,13 +647,14 @@ impl RpcImpl {
     }
 
     pub fn txpool_content(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<
         BTreeMap<
             String,
             BTreeMap<String, BTreeMap<usize, Vec<RpcTransaction>>>,
         >,
     > {
+        let address: Option<H160> = address.map(Into::into);
         let (ready_txs, deferred_txs) = self.tx_pool.content(address);
         let converter = |tx: Arc<SignedTransaction>| -> RpcTransaction {
             RpcTransaction::from_signed(&tx, None)
@@ -556,15 +671,16 @@ impl RpcImpl {
     }
 
     pub fn txpool_inspect(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<
         BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<String>>>>,
     > {
+        let address: Option<H160> = address.map(Into::into);
         let (ready_txs, deferred_txs) = self.tx_pool.content(address);
         let converter = |tx: Arc<SignedTransaction>| -> String {
             let to = match tx.action {
                 Action::Create => "<Create contract>".into(),
-                Action::Call(addr) => format!("{:?}", addr),
+                Action::Call(addr) => RpcAddress::from(addr).to_string(),
             };
 
             format!(
@@ -596,15 +712,18 @@ impl RpcImpl {
         Ok(ret)
     }
 
-    pub fn accounts(&self) -> JsonRpcResult<Vec<H160>> {
+    pub fn accounts(&self) -> JsonRpcResult<Vec<RpcAddress>> {
         let accounts: Vec<Address> = self.accounts.accounts().map_err(|e| {
             warn!("Could not fetch accounts. With error {:?}", e);
             RpcError::internal_error()
         })?;
-        Ok(accounts.into_iter().map(Into::into).collect::<Vec<H160>>())
+        Ok(accounts
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<RpcAddress>>())
     }
 
-    pub fn new_account(&self, password: String) -> JsonRpcResult<H160> {
+    pub fn new_account(&self, password: String) -> JsonRpcResult<RpcAddress> {
         let address: Address = self
             .accounts
             .new_account(&password.into())
@@ -616,8 +735,65 @@ impl RpcImpl {
         Ok(address.into())
     }
 
+    pub fn import_raw_key(
+        &self, secret: H256, password: String,
+    ) -> JsonRpcResult<RpcAddress> {
+        let secret = Secret::from_unsafe_slice(secret.as_bytes())
+            .map_err(|_| RpcError::invalid_params("invalid secret"))?;
+        let address: Address = self
+            .accounts
+            .insert_account(secret, &password.into())
+            .map_err(|e| {
+                warn!("Could not import account. With error {:?}", e);
+                RpcError::internal_error()
+            })?;
+        Ok(address.into())
+    }
+
+    pub fn export_account(
+        &self, address: RpcAddress, password: String,
+    ) -> JsonRpcResult<KeyFile> {
+        self.accounts
+            .export_account(&address.into(), password.into())
+            .map_err(|e| {
+                warn!("Could not export account. With error {:?}", e);
+                RpcError::internal_error()
+            })
+    }
+
+    pub fn change_password(
+        &self, address: RpcAddress, password: String, new_password: String,
+    ) -> JsonRpcResult<bool> {
+        match self.accounts.change_password(
+            &address.into(),
+            password.into(),
+            new_password.into(),
+        ) {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                warn!("Unable to change the password. With error {:?}", err);
+                Err(RpcError::internal_error())
+            }
+        }
+    }
+
+    pub fn remove_account(
+        &self, address: RpcAddress, password: String,
+    ) -> JsonRpcResult<bool> {
+        match self
+            .accounts
+            .kill_account(&address.into(), &password.into())
+        {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                warn!("Unable to remove the account. With error {:?}", err);
+                Err(RpcError::internal_error())
+            }
+        }
+    }
+
     pub fn unlock_account(
-        &self, address: H160, password: String, duration: Option<U128>,
+        &self, address: RpcAddress, password: String, duration: Option<U128>,
     ) -> JsonRpcResult<bool> {
         let account: Address = address.into();
         let store = self.accounts.clone();
@@ -660,7 +836,7 @@ impl RpcImpl {
         }
     }
 
-    pub fn lock_account(&self, address: H160) -> JsonRpcResult<bool> {
+    pub fn lock_account(&self, address: RpcAddress) -> JsonRpcResult<bool> {
         match self.accounts.lock_account(address.into()) {
             Ok(_) => Ok(true),
             Err(err) => {
@@ -671,12 +847,12 @@ impl RpcImpl {
     }
 
     pub fn sign(
-        &self, data: Bytes, address: H160, password: Option<String>,
+        &self, data: Bytes, address: RpcAddress, password: Option<String>,
     ) -> JsonRpcResult<H520> {
         let message = eth_data_hash(data.0);
         let password = password.map(Password::from);
         let signature =
-            match self.accounts.sign(address.into(), password, message) {
+            match self.signer.sign(address.into(), password, message) {
                 Ok(signature) => signature,
                 Err(err) => {
                     warn!("Unable to sign the message. With error {:?}", err);
@@ -696,8 +872,9 @@ impl RpcImpl {
     }
 
     pub fn tx_inspect_pending(
-        &self, address: H160,
+        &self, address: RpcAddress,
     ) -> JsonRpcResult<TxPoolPendingInfo> {
+        let address: H160 = address.into();
         let mut ret = TxPoolPendingInfo::default();
         let (deferred_txs, _) = self.tx_pool.content(Some(address));
         let mut max_nonce: U256 = U256::from(0);
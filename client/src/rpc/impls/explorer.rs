@@ -0,0 +1,575 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::{
+    error_codes::invalid_params,
+    traits::explorer::Explorer,
+    types::{
+        AccountBalance, ContractCreation, EpochTransactionCount, RpcAddress,
+        TokenTransfer, TRANSFER_EVENT_TOPIC,
+    },
+    RpcResult,
+};
+use cfx_statedb::StateDbExt;
+use cfx_types::{Address, U64};
+use cfxcore::{
+    executive::contract_address, vm::CreateContractAddress, ConsensusGraph,
+    ConsensusGraphTrait, SharedConsensusGraph,
+};
+use jsonrpc_core::Result as JsonRpcResult;
+use parking_lot::Mutex;
+use primitives::{
+    filter::Filter, receipt::TRANSACTION_OUTCOME_SUCCESS, transaction::Action,
+    Block, BlockReceipts, EpochNumber, SignedTransaction,
+};
+use std::{collections::HashSet, sync::Arc, thread, time::Duration};
+
+/// The default and maximal numbers of the accounts returned by
+/// `explorer_getRichestAccounts`.
+const DEFAULT_RICHEST_ACCOUNTS_LIMIT: u64 = 100;
+const MAX_RICHEST_ACCOUNTS_LIMIT: u64 = 1000;
+
+/// The maximal number of epochs in a query.
+const MAX_EPOCH_RANGE: u64 = 1000;
+
+/// The maximal number of token transfers returned.
+const MAX_TOKEN_TRANSFERS: usize = 10000;
+
+/// The maximal number of epochs scanned before their accounts are known.
+const SCAN_BATCH_EPOCHS: u64 = 1000;
+
+/// The interval of the scans once all the executed epochs are scanned.
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The accounts discovered in the executed epochs before `next_epoch`.
+struct KnownAccounts {
+    next_epoch: u64,
+    addresses: HashSet<Address>,
+}
+
+impl KnownAccounts {
+    fn new(next_epoch: u64) -> Self {
+        KnownAccounts {
+            next_epoch,
+            addresses: HashSet::new(),
+        }
+    }
+
+    /// Add the accounts of the epochs from `next_epoch` to `to_epoch`, with
+    /// the blocks loaded by `load_blocks`. The epochs failing to load, e.g.
+    /// with their receipts pruned, are skipped.
+    fn scan<F>(&mut self, to_epoch: u64, load_blocks: F)
+    where F: Fn(u64) -> RpcResult<Vec<(Arc<Block>, Arc<BlockReceipts>)>> {
+        while self.next_epoch <= to_epoch {
+            match load_blocks(self.next_epoch) {
+                Ok(blocks) => {
+                    for (block, receipts) in blocks {
+                        self.add_block(&block, &receipts);
+                    }
+                }
+                Err(e) => warn!(
+                    "Skip epoch {} unavailable to the explorer: {}",
+                    self.next_epoch, e
+                ),
+            }
+            self.next_epoch += 1;
+        }
+    }
+
+    fn add_block(&mut self, block: &Block, receipts: &BlockReceipts) {
+        self.addresses.insert(*block.block_header.author());
+        for (tx, receipt) in block.transactions.iter().zip(&receipts.receipts) {
+            self.addresses.insert(tx.sender);
+            if let Action::Call(to) = &tx.action {
+                self.addresses.insert(*to);
+            }
+            if let Some(created) = created_contract(tx, receipt.outcome_status)
+            {
+                self.addresses.insert(created);
+            }
+        }
+    }
+
+    /// Merge the accounts scanned from `next_epoch`, or from a later epoch
+    /// if the epochs in between are unavailable.
+    fn merge(&mut self, scanned: KnownAccounts) {
+        self.addresses.extend(scanned.addresses);
+        self.next_epoch = self.next_epoch.max(scanned.next_epoch);
+    }
+}
+
+/// The explorer API. As the state can't be iterated, the richest accounts are
+/// found among the accounts seen in the available executed epochs, which are
+/// scanned in the background once `start_scanner` is called. It's meant for
+/// the small deployments, where the chain is short enough to be scanned.
+pub struct ExplorerImpl {
+    consensus: SharedConsensusGraph,
+    known_accounts: Arc<Mutex<KnownAccounts>>,
+}
+
+impl ExplorerImpl {
+    pub fn new(
+        consensus: SharedConsensusGraph, genesis_accounts: Vec<Address>,
+    ) -> Self {
+        ExplorerImpl {
+            consensus,
+            known_accounts: Arc::new(Mutex::new(KnownAccounts {
+                next_epoch: 0,
+                addresses: genesis_accounts.into_iter().collect(),
+            })),
+        }
+    }
+
+    /// Scan the executed epochs for the known accounts in a thread, which
+    /// exits once the explorer or the consensus graph is dropped.
+    pub fn start_scanner(&self) {
+        let consensus = Arc::downgrade(&self.consensus);
+        let known_accounts = Arc::downgrade(&self.known_accounts);
+        thread::Builder::new()
+            .name("explorer_scanner".into())
+            .spawn(move || loop {
+                // Do not keep them alive while sleeping.
+                let more = match (consensus.upgrade(), known_accounts.upgrade())
+                {
+                    (Some(consensus), Some(known_accounts)) => {
+                        scan_known_accounts(&consensus, &known_accounts)
+                    }
+                    _ => return,
+                };
+                if !more {
+                    thread::sleep(SCAN_INTERVAL);
+                }
+            })
+            .expect("Explorer scanner thread spawn error");
+    }
+
+    fn consensus_graph(&self) -> &ConsensusGraph {
+        consensus_graph(&self.consensus)
+    }
+
+    fn check_epoch_range(
+        &self, from_epoch: U64, to_epoch: U64, max_epoch: u64,
+    ) -> RpcResult<(u64, u64)> {
+        let (from, to) = (from_epoch.as_u64(), to_epoch.as_u64());
+        if from > to {
+            bail!(invalid_params(
+                "from_epoch",
+                format!("from_epoch {} is larger than to_epoch {}", from, to)
+            ));
+        }
+        if to - from >= MAX_EPOCH_RANGE {
+            bail!(invalid_params(
+                "to_epoch",
+                format!("At most {} epochs can be queried", MAX_EPOCH_RANGE)
+            ));
+        }
+        if to > max_epoch {
+            bail!(invalid_params(
+                "to_epoch",
+                format!("Epoch {} is not available yet, max {}", to, max_epoch)
+            ));
+        }
+        Ok((from, to))
+    }
+
+    fn executed_blocks(
+        &self, epoch: u64,
+    ) -> RpcResult<Vec<(Arc<Block>, Arc<BlockReceipts>)>> {
+        executed_blocks(&self.consensus, epoch)
+    }
+
+    fn get_richest_accounts(
+        &self, limit: Option<U64>,
+    ) -> RpcResult<Vec<AccountBalance>> {
+        let limit =
+            limit.map_or(DEFAULT_RICHEST_ACCOUNTS_LIMIT, |l| l.as_u64());
+        if limit > MAX_RICHEST_ACCOUNTS_LIMIT {
+            bail!(invalid_params(
+                "limit",
+                format!(
+                    "limit should be at most {}",
+                    MAX_RICHEST_ACCOUNTS_LIMIT
+                )
+            ));
+        }
+        info!("RPC Request: explorer_getRichestAccounts limit={}", limit);
+
+        let known = self.known_accounts.lock().addresses.clone();
+
+        let state_db = self
+            .consensus
+            .get_state_db_by_epoch_number(EpochNumber::LatestState)?;
+        let mut balances = Vec::with_capacity(known.len());
+        for address in &known {
+            if let Some(account) = state_db.get_account(address)? {
+                balances.push((account.balance, *address));
+            }
+        }
+        balances.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(balances
+            .into_iter()
+            .take(limit as usize)
+            .map(|(balance, address)| AccountBalance {
+                address: address.into(),
+                balance,
+            })
+            .collect())
+    }
+
+    fn get_epoch_transaction_counts(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> RpcResult<Vec<EpochTransactionCount>> {
+        info!(
+            "RPC Request: explorer_getEpochTransactionCounts from={} to={}",
+            from_epoch, to_epoch
+        );
+        let (from, to) = self.check_epoch_range(
+            from_epoch,
+            to_epoch,
+            self.consensus.best_epoch_number(),
+        )?;
+        let data_man = self.consensus.get_data_manager();
+
+        let mut counts = Vec::new();
+        for epoch in from..=to {
+            let hashes = self
+                .consensus
+                .get_block_hashes_by_epoch(EpochNumber::Number(epoch))?;
+            let mut transaction_count = 0;
+            for hash in &hashes {
+                let block =
+                    data_man.block_by_hash(hash, false).ok_or_else(|| {
+                        format!("Block {:?} is not available", hash)
+                    })?;
+                transaction_count += block.transactions.len() as u64;
+            }
+            counts.push(EpochTransactionCount {
+                epoch_number: epoch.into(),
+                block_count: (hashes.len() as u64).into(),
+                transaction_count: transaction_count.into(),
+            });
+        }
+        Ok(counts)
+    }
+
+    fn get_contract_creations(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> RpcResult<Vec<ContractCreation>> {
+        info!(
+            "RPC Request: explorer_getContractCreations from={} to={}",
+            from_epoch, to_epoch
+        );
+        let (from, to) = self.check_epoch_range(
+            from_epoch,
+            to_epoch,
+            self.consensus_graph().best_executed_state_epoch_number(),
+        )?;
+
+        let mut creations = Vec::new();
+        for epoch in from..=to {
+            for (block, receipts) in self.executed_blocks(epoch)? {
+                for (tx, receipt) in
+                    block.transactions.iter().zip(&receipts.receipts)
+                {
+                    if let Some(created) =
+                        created_contract(tx, receipt.outcome_status)
+                    {
+                        creations.push(ContractCreation {
+                            address: created.into(),
+                            creator: tx.sender.into(),
+                            transaction_hash: tx.hash(),
+                            block_hash: block.hash(),
+                            epoch_number: epoch.into(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(creations)
+    }
+
+    fn get_token_transfers(
+        &self, from_epoch: U64, to_epoch: U64, token: Option<RpcAddress>,
+    ) -> RpcResult<Vec<TokenTransfer>> {
+        info!(
+            "RPC Request: explorer_getTokenTransfers from={} to={} token={:?}",
+            from_epoch, to_epoch, token
+        );
+        let (from, to) = self.check_epoch_range(
+            from_epoch,
+            to_epoch,
+            self.consensus_graph().best_executed_state_epoch_number(),
+        )?;
+
+        // One more log is queried to find whether the transfers would be
+        // truncated.
+        let filter = Filter {
+            from_epoch: EpochNumber::Number(from),
+            to_epoch: EpochNumber::Number(to),
+            address: token.map(|token| vec![token.into()]),
+            topics: vec![Some(vec![*TRANSFER_EVENT_TOPIC]), None, None, None],
+            limit: Some(MAX_TOKEN_TRANSFERS + 1),
+            ..Default::default()
+        };
+        let logs = self.consensus_graph().logs(filter)?;
+        if logs.len() > MAX_TOKEN_TRANSFERS {
+            bail!(invalid_params(
+                "to_epoch",
+                format!(
+                    "More than {} token transfers in the epochs, query fewer epochs",
+                    MAX_TOKEN_TRANSFERS
+                )
+            ));
+        }
+        Ok(logs.iter().filter_map(TokenTransfer::decode).collect())
+    }
+}
+
+fn consensus_graph(consensus: &SharedConsensusGraph) -> &ConsensusGraph {
+    consensus
+        .as_any()
+        .downcast_ref::<ConsensusGraph>()
+        .expect("downcast should succeed")
+}
+
+/// The blocks of an executed epoch with their receipts.
+fn executed_blocks(
+    consensus: &SharedConsensusGraph, epoch: u64,
+) -> RpcResult<Vec<(Arc<Block>, Arc<BlockReceipts>)>> {
+    let data_man = consensus.get_data_manager();
+    let hashes =
+        consensus.get_block_hashes_by_epoch(EpochNumber::Number(epoch))?;
+    let pivot = hashes.last().expect("pivot block always exist");
+    let mut blocks = Vec::with_capacity(hashes.len());
+    for hash in &hashes {
+        let block = data_man
+            .block_by_hash(hash, false)
+            .ok_or_else(|| format!("Block {:?} is not available", hash))?;
+        let receipts = data_man
+            .block_execution_result_by_hash_with_epoch(
+                hash, pivot, false, /* update_pivot_assumption */
+                false, /* update_cache */
+            )
+            .ok_or_else(|| format!("Block {:?} is not executed", hash))?
+            .block_receipts;
+        blocks.push((block, receipts));
+    }
+    Ok(blocks)
+}
+
+/// Add the accounts of a batch of the executed epochs not scanned yet, without
+/// holding the lock of the known accounts. The epochs before the earliest
+/// available one are skipped as they may be pruned. Returns whether more
+/// executed epochs are left to scan.
+fn scan_known_accounts(
+    consensus: &SharedConsensusGraph, known_accounts: &Mutex<KnownAccounts>,
+) -> bool {
+    let graph = consensus_graph(consensus);
+    let best_executed = graph.best_executed_state_epoch_number();
+    let next_epoch = known_accounts.lock().next_epoch;
+    let mut scanned =
+        KnownAccounts::new(next_epoch.max(graph.earliest_epoch_available()));
+    let to_epoch =
+        best_executed.min(scanned.next_epoch + SCAN_BATCH_EPOCHS - 1);
+    scanned.scan(to_epoch, |epoch| executed_blocks(consensus, epoch));
+
+    known_accounts.lock().merge(scanned);
+    to_epoch < best_executed
+}
+
+/// The address of the contract created by `tx`, if it succeeds.
+fn created_contract(
+    tx: &SignedTransaction, outcome_status: u8,
+) -> Option<Address> {
+    if tx.action != Action::Create
+        || outcome_status != TRANSACTION_OUTCOME_SUCCESS
+    {
+        return None;
+    }
+    let (address, _) = contract_address(
+        CreateContractAddress::FromSenderNonceAndCodeHash,
+        &tx.sender,
+        &tx.nonce,
+        &tx.data,
+    );
+    Some(address)
+}
+
+impl Explorer for ExplorerImpl {
+    fn richest_accounts(
+        &self, limit: Option<U64>,
+    ) -> JsonRpcResult<Vec<AccountBalance>> {
+        self.get_richest_accounts(limit).into()
+    }
+
+    fn epoch_transaction_counts(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> JsonRpcResult<Vec<EpochTransactionCount>> {
+        self.get_epoch_transaction_counts(from_epoch, to_epoch)
+            .into()
+    }
+
+    fn contract_creations(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> JsonRpcResult<Vec<ContractCreation>> {
+        self.get_contract_creations(from_epoch, to_epoch).into()
+    }
+
+    fn token_transfers(
+        &self, from_epoch: U64, to_epoch: U64, token: Option<RpcAddress>,
+    ) -> JsonRpcResult<Vec<TokenTransfer>> {
+        self.get_token_transfers(from_epoch, to_epoch, token).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{created_contract, KnownAccounts};
+    use crate::rpc::RpcResult;
+    use cfx_types::{Address, U256};
+    use primitives::{
+        receipt::{
+            Receipt, TRANSACTION_OUTCOME_EXCEPTION_WITH_NONCE_BUMPING,
+            TRANSACTION_OUTCOME_SUCCESS,
+        },
+        transaction::Action,
+        Block, BlockHeaderBuilder, BlockReceipts, Transaction,
+    };
+    use std::{collections::HashSet, sync::Arc};
+
+    fn address(n: u64) -> Address { Address::from_low_u64_be(n) }
+
+    /// A block by `author` with the transactions sent by the senders with the
+    /// actions, and their outcomes.
+    fn block(
+        author: u64, transactions: &[(u64, Action, u8)],
+    ) -> (Arc<Block>, Arc<BlockReceipts>) {
+        let header = BlockHeaderBuilder::new()
+            .with_author(address(author))
+            .build();
+        let txs = transactions
+            .iter()
+            .map(|(from, action, _)| {
+                Arc::new(
+                    Transaction {
+                        action: action.clone(),
+                        ..Default::default()
+                    }
+                    .fake_sign(address(*from)),
+                )
+            })
+            .collect();
+        let receipts = transactions
+            .iter()
+            .map(|(_, _, outcome)| {
+                Receipt::new(
+                    *outcome,
+                    U256::zero(),
+                    U256::zero(),
+                    false,
+                    vec![],
+                    false,
+                    vec![],
+                    vec![],
+                )
+            })
+            .collect();
+        (
+            Arc::new(Block::new(header, txs)),
+            Arc::new(BlockReceipts {
+                receipts,
+                secondary_reward: U256::zero(),
+                tx_execution_error_messages: vec![],
+            }),
+        )
+    }
+
+    /// Epoch `n` has a block by `n` with a transfer from `n + 100` to
+    /// `n + 200`.
+    fn load_epoch(
+        epoch: u64,
+    ) -> RpcResult<Vec<(Arc<Block>, Arc<BlockReceipts>)>> {
+        Ok(vec![block(
+            epoch,
+            &[(
+                epoch + 100,
+                Action::Call(address(epoch + 200)),
+                TRANSACTION_OUTCOME_SUCCESS,
+            )],
+        )])
+    }
+
+    fn addresses(ns: &[u64]) -> HashSet<Address> {
+        ns.iter().map(|n| address(*n)).collect()
+    }
+
+    #[test]
+    fn test_scan_block_accounts() {
+        let (creations, receipts) = block(
+            1,
+            &[
+                (2, Action::Create, TRANSACTION_OUTCOME_SUCCESS),
+                (
+                    3,
+                    Action::Create,
+                    TRANSACTION_OUTCOME_EXCEPTION_WITH_NONCE_BUMPING,
+                ),
+            ],
+        );
+        let contract = created_contract(
+            &creations.transactions[0],
+            receipts.receipts[0].outcome_status,
+        )
+        .unwrap();
+        assert!(created_contract(
+            &creations.transactions[1],
+            receipts.receipts[1].outcome_status
+        )
+        .is_none());
+
+        let mut known = KnownAccounts::new(0);
+        known.scan(0, |_| Ok(vec![(creations.clone(), receipts.clone())]));
+        assert_eq!(known.next_epoch, 1);
+        let mut expected = addresses(&[1, 2, 3]);
+        expected.insert(contract);
+        assert_eq!(known.addresses, expected);
+    }
+
+    #[test]
+    fn test_scan_skips_unavailable_epoch() {
+        let mut known = KnownAccounts::new(0);
+        known.scan(3, |epoch| match epoch {
+            2 => Err("Block is not executed".into()),
+            _ => load_epoch(epoch),
+        });
+        // the scan goes on after the unavailable epoch
+        assert_eq!(known.next_epoch, 4);
+        assert_eq!(
+            known.addresses,
+            addresses(&[0, 100, 200, 1, 101, 201, 3, 103, 203])
+        );
+
+        // nothing is scanned before a new epoch is executed
+        known.scan(3, |_| panic!("no epoch to scan"));
+        assert_eq!(known.next_epoch, 4);
+    }
+
+    #[test]
+    fn test_merge_overlapping_scans() {
+        let mut known = KnownAccounts::new(0);
+        known.scan(1, load_epoch);
+
+        // two scans from epoch 2, finishing in any order
+        let mut first = KnownAccounts::new(known.next_epoch);
+        first.scan(3, load_epoch);
+        let mut second = KnownAccounts::new(known.next_epoch);
+        second.scan(2, load_epoch);
+
+        known.merge(first);
+        assert_eq!(known.next_epoch, 4);
+        known.merge(second);
+        assert_eq!(known.next_epoch, 4);
+        assert_eq!(known.addresses.len(), 12);
+    }
+}
@@ -7,15 +7,16 @@ use cfxcore::{
     rpc_errors::{account_result_to_rpc_result, invalid_params_check},
     LightQueryService, PeerInfo,
 };
-use cfxcore_accounts::AccountProvider;
+use cfxcore_accounts::{KeyFile, TransactionSigner};
 use delegate::delegate;
 use futures::future::{FutureExt, TryFutureExt};
 use futures01;
 use jsonrpc_core::{BoxFuture, Error as RpcError, Result as RpcResult};
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, NodeLists, SessionDetails, UpdateNodeOperation,
 };
+use parking_lot::RwLock;
 use primitives::{Account, StorageRoot, TransactionWithSignature};
 use rlp::Encodable;
 use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
@@ -27,14 +28,20 @@ use crate::{
         impls::{common::RpcImpl as CommonImpl, RpcImplConfiguration},
         traits::{cfx::Cfx, debug::LocalRpc, test::TestRpc},
         types::{
-            Account as RpcAccount, BlameInfo, Block as RpcBlock,
-            BlockHashOrEpochNumber, Bytes, CallRequest,
-            CheckBalanceAgainstTransactionResponse, ConsensusGraphStates,
-            EpochNumber, EstimateGasAndCollateralResponse, Filter as RpcFilter,
-            Log as RpcLog, Receipt as RpcReceipt, RewardInfo as RpcRewardInfo,
-            SendTxRequest, SponsorInfo as RpcSponsorInfo, Status as RpcStatus,
-            SyncGraphStates, Transaction as RpcTransaction, TxPoolPendingInfo,
-            TxWithPoolInfo,
+            Account as RpcAccount, AccountBatch, AccountTransactions,
+            BlameInfo, Block as RpcBlock, BlockHashOrEpochNumber, Bytes,
+            CallRequest, CheckBalanceAgainstTransactionResponse,
+            CollateralTrace, ConsensusGraphStates, CreateAccessListResponse,
+            DecodedTransaction, EpochNumber,
+            EpochStatistics as RpcEpochStatistics,
+            EstimateGasAndCollateralResponse, ExecutionDivergence,
+            ExecutionVariant, Filter as RpcFilter, Log as RpcLog, MemoryUsage,
+            Receipt as RpcReceipt, ReceiptProof as RpcReceiptProof,
+            RewardInfo as RpcRewardInfo, RpcAddress, SendTxRequest,
+            SenderStatus, SponsorInfo as RpcSponsorInfo, Status as RpcStatus,
+            StorageBatch, StorageSlot, SyncGraphStates, SyncStatus,
+            Transaction as RpcTransaction, TransactionDirection,
+            TxAdmissionLimits, TxPoolPendingInfo, TxWithPoolInfo,
         },
         RpcBoxFuture,
     },
@@ -42,29 +49,34 @@ use crate::{
 
 pub struct RpcImpl {
     // configuration parameters
-    config: RpcImplConfiguration,
+    config: RwLock<RpcImplConfiguration>,
 
     // helper API for retrieving verified information from peers
     light: Arc<LightQueryService>,
 
-    accounts: Arc<AccountProvider>,
+    signer: Arc<dyn TransactionSigner>,
 }
 
 impl RpcImpl {
     pub fn new(
         config: RpcImplConfiguration, light: Arc<LightQueryService>,
-        accounts: Arc<AccountProvider>,
+        signer: Arc<dyn TransactionSigner>,
     ) -> Self
     {
         RpcImpl {
-            config,
+            config: RwLock::new(config),
             light,
-            accounts,
+            signer,
         }
     }
 
+    /// Change the limits of the RPCs.
+    pub fn set_config(&self, config: RpcImplConfiguration) {
+        *self.config.write() = config;
+    }
+
     fn account(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcBoxFuture<RpcAccount> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
@@ -98,7 +110,7 @@ impl RpcImpl {
     }
 
     fn balance(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcBoxFuture<U256> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
@@ -126,8 +138,8 @@ impl RpcImpl {
     }
 
     fn admin(
-        &self, address: H160, num: Option<EpochNumber>,
-    ) -> RpcBoxFuture<Option<H160>> {
+        &self, address: RpcAddress, num: Option<EpochNumber>,
+    ) -> RpcBoxFuture<Option<RpcAddress>> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
 
@@ -152,7 +164,7 @@ impl RpcImpl {
     }
 
     fn sponsor_info(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcBoxFuture<RpcSponsorInfo> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
@@ -180,7 +192,7 @@ impl RpcImpl {
     }
 
     fn staking_balance(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcBoxFuture<U256> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
@@ -208,7 +220,7 @@ impl RpcImpl {
     }
 
     fn collateral_for_storage(
-        &self, address: H160, num: Option<EpochNumber>,
+        &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcBoxFuture<U256> {
         let address: H160 = address.into();
         let epoch = num.unwrap_or(EpochNumber::LatestState).into();
@@ -252,7 +264,7 @@ impl RpcImpl {
     }
 
     fn code(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> RpcBoxFuture<Bytes> {
         let address: H160 = address.into();
         let epoch = epoch_num.unwrap_or(EpochNumber::LatestState).into();
@@ -301,7 +313,8 @@ impl RpcImpl {
         // If max_limit is set, the value in `filter` will be modified to
         // satisfy this limitation to avoid loading too many blocks
         // TODO Should the response indicate that the filter is modified?
-        if let Some(max_limit) = self.config.get_logs_filter_max_limit {
+        let max_limit = self.config.read().get_logs_filter_max_limit;
+        if let Some(max_limit) = max_limit {
             if filter.limit.is_none() || filter.limit.unwrap() > max_limit {
                 filter.limit = Some(max_limit);
             }
@@ -358,7 +371,7 @@ impl RpcImpl {
 
         // clone `self.light` to avoid lifetime issues due to capturing `self`
         let light = self.light.clone();
-        let accounts = self.accounts.clone();
+        let signer = self.signer.clone();
 
         let fut = async move {
             if tx.nonce.is_none() {
@@ -387,7 +400,7 @@ impl RpcImpl {
                 RpcError::invalid_params(format!("the light client cannot retrieve/verify the latest chain_id."))
             })?;
             let tx = tx
-                .sign_with(epoch_height, chain_id, password, accounts)
+                .sign_with(epoch_height, chain_id, password, signer)
                 .map_err(|e| {
                 RpcError::invalid_params(format!(
                     "failed to send transaction: {:?}",
@@ -402,8 +415,9 @@ impl RpcImpl {
     }
 
     fn storage_root(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> RpcBoxFuture<Option<StorageRoot>> {
+        let address: H160 = address.into();
         let epoch_num = epoch_num.unwrap_or(EpochNumber::LatestState);
 
         info!(
@@ -427,7 +441,8 @@ impl RpcImpl {
     }
 
     fn storage_at(
-        &self, address: H160, position: H256, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, position: H256,
+        epoch_num: Option<EpochNumber>,
     ) -> BoxFuture<Option<H256>> {
         let address: H160 = address.into();
         let position: H256 = position.into();
@@ -538,7 +553,7 @@ impl Cfx for CfxHandler {
             fn blocks_by_epoch(&self, num: EpochNumber) -> RpcResult<Vec<H256>>;
             fn epoch_number(&self, epoch_num: Option<EpochNumber>) -> RpcResult<U256>;
             fn gas_price(&self) -> RpcResult<U256>;
-            fn next_nonce(&self, address: H160, num: Option<BlockHashOrEpochNumber>) -> RpcResult<U256>;
+            fn next_nonce(&self, address: RpcAddress, num: Option<BlockHashOrEpochNumber>) -> RpcResult<U256>;
             fn skipped_blocks_by_epoch(&self, num: EpochNumber) -> RpcResult<Vec<H256>>;
             fn confirmation_risk_by_hash(&self, block_hash: H256) -> RpcResult<Option<U256>>;
             fn get_status(&self) -> RpcResult<RpcStatus>;
@@ -546,19 +561,19 @@ impl Cfx for CfxHandler {
         }
 
         to self.rpc_impl {
-            fn account(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<RpcAccount>;
-            fn admin(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<Option<H160>>;
-            fn balance(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<U256>;
+            fn account(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<RpcAccount>;
+            fn admin(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<Option<RpcAddress>>;
+            fn balance(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<U256>;
             fn call(&self, request: CallRequest, epoch: Option<EpochNumber>) -> RpcResult<Bytes>;
-            fn code(&self, address: H160, epoch_num: Option<EpochNumber>) -> BoxFuture<Bytes>;
-            fn collateral_for_storage(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<U256>;
+            fn code(&self, address: RpcAddress, epoch_num: Option<EpochNumber>) -> BoxFuture<Bytes>;
+            fn collateral_for_storage(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<U256>;
             fn estimate_gas_and_collateral(&self, request: CallRequest, epoch_num: Option<EpochNumber>) -> RpcResult<EstimateGasAndCollateralResponse>;
             fn get_logs(&self, filter: RpcFilter) -> BoxFuture<Vec<RpcLog>>;
             fn send_raw_transaction(&self, raw: Bytes) -> RpcResult<H256>;
-            fn sponsor_info(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<RpcSponsorInfo>;
-            fn staking_balance(&self, address: H160, num: Option<EpochNumber>) -> BoxFuture<U256>;
-            fn storage_at(&self, addr: H160, pos: H256, epoch_number: Option<EpochNumber>) -> BoxFuture<Option<H256>>;
-            fn storage_root(&self, address: H160, epoch_num: Option<EpochNumber>) -> BoxFuture<Option<StorageRoot>>;
+            fn sponsor_info(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<RpcSponsorInfo>;
+            fn staking_balance(&self, address: RpcAddress, num: Option<EpochNumber>) -> BoxFuture<U256>;
+            fn storage_at(&self, addr: RpcAddress, pos: H256, epoch_number: Option<EpochNumber>) -> BoxFuture<Option<H256>>;
+            fn storage_root(&self, address: RpcAddress, epoch_num: Option<EpochNumber>) -> BoxFuture<Option<StorageRoot>>;
             fn transaction_by_hash(&self, hash: H256) -> BoxFuture<Option<RpcTransaction>>;
             fn transaction_receipt(&self, tx_hash: H256) -> BoxFuture<Option<RpcReceipt>>;
         }
@@ -567,8 +582,20 @@ impl Cfx for CfxHandler {
     not_supported! {
         fn accumulate_interest_rate(&self, num: Option<EpochNumber>) -> RpcResult<U256>;
         fn interest_rate(&self, num: Option<EpochNumber>) -> RpcResult<U256>;
-        fn check_balance_against_transaction(&self, account_addr: H160, contract_addr: H160, gas_limit: U256, gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>) -> RpcResult<CheckBalanceAgainstTransactionResponse>;
+        fn check_balance_against_transaction(&self, account_addr: RpcAddress, contract_addr: RpcAddress, gas_limit: U256, gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>) -> RpcResult<CheckBalanceAgainstTransactionResponse>;
+        fn check_sender_status(&self, address: RpcAddress) -> RpcResult<SenderStatus>;
+        fn account_transactions(&self, address: RpcAddress, direction: TransactionDirection, offset: Option<U64>, limit: Option<U64>) -> RpcResult<AccountTransactions>;
+        fn chain_statistics(&self, from_epoch: U64, to_epoch: U64) -> RpcResult<Vec<RpcEpochStatistics>>;
+        fn create_access_list(&self, request: CallRequest, epoch_num: Option<EpochNumber>) -> RpcResult<CreateAccessListResponse>;
+        fn trace_collateral(&self, request: CallRequest, epoch_num: Option<EpochNumber>) -> RpcResult<Vec<CollateralTrace>>;
         fn get_block_reward_info(&self, num: EpochNumber) -> RpcResult<Vec<RpcRewardInfo>>;
+        fn work(&self) -> RpcResult<(H256, H256, U64)>;
+        fn submit_work(&self, nonce: U256, pow_hash: H256) -> RpcResult<bool>;
+        fn submit_hashrate(&self, hashrate: U256, id: H256) -> RpcResult<bool>;
+        fn transaction_receipt_proof(&self, tx_hash: H256) -> RpcResult<Option<RpcReceiptProof>>;
+        fn account_batch(&self, addresses: Vec<RpcAddress>, num: Option<EpochNumber>) -> RpcResult<AccountBatch>;
+        fn storage_batch(&self, slots: Vec<StorageSlot>, epoch_number: Option<EpochNumber>) -> RpcResult<StorageBatch>;
+        fn decode_raw_transaction(&self, raw: Bytes) -> RpcResult<DecodedTransaction>;
     }
 }
 
@@ -614,6 +641,7 @@ impl TestRpc for TestRpcImpl {
         fn generate_empty_blocks(&self, num_blocks: usize) -> RpcResult<Vec<H256>>;
         fn get_pivot_chain_and_weight(&self, height_range: Option<(u64, u64)>) -> RpcResult<Vec<(H256, U256)>>;
         fn get_executed_info(&self, block_hash: H256) -> RpcResult<(H256, H256)> ;
+        fn differential_execution(&self, start_epoch: u64, end_epoch: u64, first: ExecutionVariant, second: ExecutionVariant) -> RpcResult<Vec<ExecutionDivergence>>;
         fn send_usable_genesis_accounts(&self, account_start_index: usize) -> RpcResult<Bytes>;
         fn get_block_status(&self, block_hash: H256) -> RpcResult<(u8, bool)>;
         fn set_db_crash(&self, crash_probability: f64, crash_exit_code: i32) -> RpcResult<()>;
@@ -639,17 +667,31 @@ impl LocalRpc for DebugRpcImpl {
             fn net_disconnect_node(&self, id: NodeId, op: Option<UpdateNodeOperation>) -> RpcResult<bool>;
             fn net_sessions(&self, node_id: Option<NodeId>) -> RpcResult<Vec<SessionDetails>>;
             fn net_throttling(&self) -> RpcResult<throttling::Service>;
+            fn net_add_static_node(&self, id: NodeId, address: SocketAddr) -> RpcResult<()>;
+            fn net_remove_static_node(&self, id: NodeId) -> RpcResult<bool>;
+            fn net_add_trusted_node(&self, id: NodeId, address: SocketAddr) -> RpcResult<()>;
+            fn net_remove_trusted_node(&self, id: NodeId) -> RpcResult<bool>;
+            fn net_ban_node(&self, id: NodeId) -> RpcResult<()>;
+            fn net_unban_node(&self, id: NodeId) -> RpcResult<bool>;
+            fn net_node_lists(&self) -> RpcResult<Option<NodeLists>>;
+            fn log_levels(&self) -> RpcResult<BTreeMap<String, String>>;
+            fn log_set_level(&self, module: String, level: String) -> RpcResult<bool>;
+            fn reload_config(&self) -> RpcResult<bool>;
             fn tx_inspect(&self, hash: H256) -> RpcResult<TxWithPoolInfo>;
-            fn txpool_content(&self, address: Option<H160>) -> RpcResult<BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<RpcTransaction>>>>>;
-            fn txs_from_pool(&self, address: Option<H160>) -> RpcResult<Vec<RpcTransaction>>;
-            fn txpool_inspect(&self, address: Option<H160>) -> RpcResult<BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<String>>>>>;
+            fn txpool_content(&self, address: Option<RpcAddress>) -> RpcResult<BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<RpcTransaction>>>>>;
+            fn txs_from_pool(&self, address: Option<RpcAddress>) -> RpcResult<Vec<RpcTransaction>>;
+            fn txpool_inspect(&self, address: Option<RpcAddress>) -> RpcResult<BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<String>>>>>;
             fn txpool_status(&self) -> RpcResult<BTreeMap<String, usize>>;
-            fn accounts(&self) -> RpcResult<Vec<H160>>;
-            fn new_account(&self, password: String) -> RpcResult<H160>;
-            fn unlock_account(&self, address: H160, password: String, duration: Option<U128>) -> RpcResult<bool>;
-            fn lock_account(&self, address: H160) -> RpcResult<bool>;
-            fn sign(&self, data: Bytes, address: H160, password: Option<String>) -> RpcResult<H520>;
-            fn tx_inspect_pending(&self, address: H160) -> RpcResult<TxPoolPendingInfo>;
+            fn accounts(&self) -> RpcResult<Vec<RpcAddress>>;
+            fn new_account(&self, password: String) -> RpcResult<RpcAddress>;
+            fn import_raw_key(&self, secret: H256, password: String) -> RpcResult<RpcAddress>;
+            fn export_account(&self, address: RpcAddress, password: String) -> RpcResult<KeyFile>;
+            fn change_password(&self, address: RpcAddress, password: String, new_password: String) -> RpcResult<bool>;
+            fn remove_account(&self, address: RpcAddress, password: String) -> RpcResult<bool>;
+            fn unlock_account(&self, address: RpcAddress, password: String, duration: Option<U128>) -> RpcResult<bool>;
+            fn lock_account(&self, address: RpcAddress) -> RpcResult<bool>;
+            fn sign(&self, data: Bytes, address: RpcAddress, password: Option<String>) -> RpcResult<H520>;
+            fn tx_inspect_pending(&self, address: RpcAddress) -> RpcResult<TxPoolPendingInfo>;
         }
 
         to self.rpc_impl {
@@ -660,7 +702,12 @@ impl LocalRpc for DebugRpcImpl {
 
     not_supported! {
         fn current_sync_phase(&self) -> RpcResult<String>;
+        fn sync_status(&self) -> RpcResult<SyncStatus>;
         fn consensus_graph_state(&self) -> RpcResult<ConsensusGraphStates>;
         fn sync_graph_state(&self) -> RpcResult<SyncGraphStates>;
+        fn memory_usage(&self) -> RpcResult<MemoryUsage>;
+        fn txpool_block_template(&self) -> RpcResult<RpcBlock>;
+        fn txpool_admission_limits(&self) -> RpcResult<TxAdmissionLimits>;
+        fn txpool_set_admission_limits(&self, limits: TxAdmissionLimits) -> RpcResult<TxAdmissionLimits>;
     }
 }
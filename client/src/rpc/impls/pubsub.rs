@@ -3,6 +3,7 @@
 use crate::rpc::{
     error_codes,
     helpers::{SubscriberId, Subscribers},
+    impls::{cfx::prepare_receipt, common::scale_confirmation_risk},
     metadata::Metadata,
     traits::PubSub,
     types::{pubsub, Header as RpcHeader, Log as RpcLog},
@@ -10,7 +11,8 @@ use crate::rpc::{
 use cfx_types::{H160, H256, H520, U128, U256, U64};
 use cfxcore::{
     block_data_manager::BlockExecutionResult, channel::Channel,
-    BlockDataManager, Notifications, SharedConsensusGraph,
+    trace::EpochInternalTransfers, BlockDataManager, ConsensusGraph,
+    LightQueryService, Notifications, SharedConsensusGraph,
     SynchronizationGraph,
 };
 use futures::{
@@ -30,7 +32,7 @@ use parking_lot::RwLock;
 use primitives::{
     filter::Filter,
     log_entry::{LocalizedLogEntry, LogEntry},
-    BlockHeader, BlockReceipts,
+    Action, BlockHeader, BlockReceipts, EpochNumber,
 };
 use runtime::Executor;
 use std::{
@@ -42,6 +44,10 @@ use tokio_timer::sleep;
 
 type Client = Sink<pubsub::Result>;
 
+/// The confirmation risk below which the block of a subscribed transaction
+/// is considered confirmed, if the subscriber gives no threshold.
+const DEFAULT_CONFIRMATION_RISK: f64 = 1e-8;
+
 /// Cfx PubSub implementation.
 #[derive(Clone)]
 pub struct PubSubClient {
@@ -49,7 +55,13 @@ pub struct PubSubClient {
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     epochs_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, Filter)>>>,
+    watch_subscribers: Arc<RwLock<Subscribers<(Client, Vec<H160>)>>>,
+    internal_transfers_subscribers:
+        Arc<RwLock<Subscribers<(Client, Option<Vec<H160>>)>>>,
+    receipt_subscribers: Arc<RwLock<Subscribers<(Client, (H256, U256))>>>,
     epochs_ordered: Arc<Channel<(u64, Vec<H256>)>>,
+    internal_transfers: Arc<Channel<Arc<EpochInternalTransfers>>>,
+    light: Option<Arc<LightQueryService>>,
 }
 
 impl PubSubClient {
@@ -91,10 +103,24 @@ impl PubSubClient {
             heads_subscribers,
             epochs_subscribers,
             logs_subscribers,
+            watch_subscribers: Arc::new(RwLock::new(Subscribers::default())),
+            internal_transfers_subscribers: Arc::new(RwLock::new(
+                Subscribers::default(),
+            )),
+            receipt_subscribers: Arc::new(RwLock::new(Subscribers::default())),
             epochs_ordered: notifications.epochs_ordered.clone(),
+            internal_transfers: notifications.internal_transfers.clone(),
+            light: None,
         }
     }
 
+    /// Serve `watch` subscriptions with the verified state and receipts
+    /// retrieved by `light`.
+    pub fn with_light_service(mut self, light: Arc<LightQueryService>) -> Self {
+        self.light = Some(light);
+        self
+    }
+
     /// Returns a chain notification handler.
     pub fn handler(&self) -> Weak<ChainNotificationHandler> {
         Arc::downgrade(&self.handler)
@@ -188,6 +214,459 @@ impl PubSubClient {
         let fut = fut.unit_error().boxed().compat();
         self.handler.executor.spawn(fut);
     }
+
+    // Start an async loop that continuously receives epoch notifications and
+    // publishes the verified changes of the accounts watched by subscriber
+    // `id`. The epochs are processed once they are verifiable, i.e. a few
+    // epochs behind the latest one. The loop terminates when subscriber `id`
+    // unsubscribes.
+    fn start_watch_loop(
+        &self, id: SubscriberId, light: Arc<LightQueryService>,
+    ) {
+        trace!("start_watch_loop({:?})", id);
+
+        // clone everything we use in our async loop
+        let subscribers = self.watch_subscribers.clone();
+        let epochs_ordered = self.epochs_ordered.clone();
+
+        // subscribe to the `epochs_ordered` channel
+        let mut receiver = epochs_ordered.subscribe();
+
+        // loop asynchronously
+        let fut = async move {
+            let mut watcher = AccountWatcher::new(light);
+
+            while let Some(epoch) = receiver.recv().await {
+                trace!("watch_loop({:?}): {:?}", id, epoch);
+
+                // retrieve subscriber
+                let (sub, addresses) = match subscribers.read().get(&id) {
+                    Some(sub) => sub.clone(),
+                    None => {
+                        // unsubscribed, terminate loop
+                        epochs_ordered.unsubscribe(receiver.id);
+                        return;
+                    }
+                };
+
+                watcher.notify(&sub, &addresses, epoch.0).await;
+            }
+        };
+
+        // run futures@0.3 future on tokio@0.1 executor
+        let fut = fut.unit_error().boxed().compat();
+        self.handler.executor.spawn(fut);
+    }
+
+    // Start an async loop that continuously receives the internal transfers
+    // of the executed pivot epochs and publishes the ones from or to the
+    // addresses of subscriber `id`, or all of them if no address is given.
+    // The loop terminates when subscriber `id` unsubscribes.
+    fn start_internal_transfers_loop(&self, id: SubscriberId) {
+        trace!("start_internal_transfers_loop({:?})", id);
+
+        // clone everything we use in our async loop
+        let subscribers = self.internal_transfers_subscribers.clone();
+        let internal_transfers = self.internal_transfers.clone();
+        let handler = self.handler.clone();
+
+        // subscribe to the `internal_transfers` channel, which also enables
+        // the tracing of the internal transfers during execution
+        let mut receiver = internal_transfers.subscribe();
+
+        // loop asynchronously
+        let fut = async move {
+            let mut last_epoch = 0;
+
+            while let Some(epoch) = receiver.recv().await {
+                trace!(
+                    "internal_transfers_loop({:?}): {:?}",
+                    id,
+                    epoch.epoch_number
+                );
+
+                // retrieve subscriber
+                let (sub, addresses) = match subscribers.read().get(&id) {
+                    Some(sub) => sub.clone(),
+                    None => {
+                        // unsubscribed, terminate loop
+                        internal_transfers.unsubscribe(receiver.id);
+                        return;
+                    }
+                };
+
+                // publish pivot chain reorg if necessary
+                if epoch.epoch_number <= last_epoch {
+                    debug!(
+                        "pivot chain reorg: {} -> {}",
+                        last_epoch, epoch.epoch_number
+                    );
+                    assert!(
+                        epoch.epoch_number > 0,
+                        "Unexpected epoch number received."
+                    );
+                    handler.notify_revert(&sub, epoch.epoch_number - 1).await;
+                }
+
+                last_epoch = epoch.epoch_number;
+
+                // publish matching transfers
+                for tx in &epoch.transactions {
+                    for transfer in &tx.transfers {
+                        if let Some(addresses) = &addresses {
+                            if !addresses.contains(&transfer.from)
+                                && !addresses.contains(&transfer.to)
+                            {
+                                continue;
+                            }
+                        }
+
+                        let result = pubsub::Result::InternalTransfer {
+                            epoch_number: epoch.epoch_number.into(),
+                            block_hash: tx.block_hash,
+                            transaction_hash: tx.transaction_hash,
+                            from: transfer.from.into(),
+                            to: transfer.to.into(),
+                            value: transfer.value,
+                        };
+                        ChainNotificationHandler::notify_async(&sub, result)
+                            .await;
+                    }
+                }
+            }
+        };
+
+        // run futures@0.3 future on tokio@0.1 executor
+        let fut = fut.unit_error().boxed().compat();
+        self.handler.executor.spawn(fut);
+    }
+
+    // Start an async loop that publishes the receipt of the transaction of
+    // subscriber `id` once its epoch is executed, and again if it changes
+    // after a pivot chain reorg, until the block of the transaction is
+    // confirmed. The loop terminates when the transaction is confirmed or
+    // subscriber `id` unsubscribes.
+    fn start_receipt_loop(&self, id: SubscriberId) {
+        trace!("start_receipt_loop({:?})", id);
+
+        // clone everything we use in our async loop
+        let subscribers = self.receipt_subscribers.clone();
+        let epochs_ordered = self.epochs_ordered.clone();
+        let handler = self.handler.clone();
+
+        // subscribe to the `epochs_ordered` channel
+        let mut receiver = epochs_ordered.subscribe();
+
+        // loop asynchronously
+        let fut = async move {
+            // the epoch and the block of the receipt published
+            let mut published = None;
+
+            loop {
+                // retrieve subscriber, terminate loop if unsubscribed
+                let (sub, (tx, threshold)) = match subscribers.read().get(&id) {
+                    Some(sub) => sub.clone(),
+                    None => break,
+                };
+
+                if handler
+                    .notify_receipt(&sub, tx, threshold, &mut published)
+                    .await
+                {
+                    break;
+                }
+
+                // check again on the next epoch
+                match receiver.recv().await {
+                    Some(epoch) => {
+                        trace!("receipt_loop({:?}): {:?}", id, epoch)
+                    }
+                    None => return,
+                }
+            }
+
+            epochs_ordered.unsubscribe(receiver.id);
+        };
+
+        // run futures@0.3 future on tokio@0.1 executor
+        let fut = fut.unit_error().boxed().compat();
+        self.handler.executor.spawn(fut);
+    }
+}
+
+/// Publishes the changes of watched accounts on a light node: balance changes
+/// read from verified states, the transactions sent from or to the accounts
+/// in the epochs where their balance or nonce changes, and the logs emitted
+/// by the accounts or with them as the first or second indexed argument.
+struct AccountWatcher {
+    light: Arc<LightQueryService>,
+
+    state: WatchedState,
+}
+
+/// The epochs processed by an `AccountWatcher`.
+#[derive(Default)]
+struct WatchedState {
+    // the last epoch processed
+    last_epoch: Option<u64>,
+
+    // the balance and nonce of each account in `last_epoch`
+    accounts: BTreeMap<H160, (U256, U256)>,
+
+    // whether `accounts` should be loaded again after a pivot chain reorg
+    reload_accounts: bool,
+}
+
+impl WatchedState {
+    // revert the processed epochs from pivot epoch `epoch` on, returning the
+    // epoch reverted to if any
+    fn revert(&mut self, epoch: u64) -> Option<u64> {
+        match self.last_epoch {
+            Some(last_epoch) if epoch <= last_epoch => {
+                debug!("pivot chain reorg: {} -> {}", last_epoch, epoch);
+                assert!(epoch > 0, "Unexpected epoch number received.");
+                self.last_epoch = Some(epoch - 1);
+                self.accounts.clear();
+                self.reload_accounts = true;
+                Some(epoch - 1)
+            }
+            _ => None,
+        }
+    }
+
+    // the balance changes and the changed accounts among the new `states`,
+    // without updating the states
+    fn changes(
+        &self, states: &[(H160, (U256, U256))],
+    ) -> (Vec<(H160, U256)>, Vec<H160>) {
+        let mut balance_changes = vec![];
+        let mut changed = vec![];
+
+        for (address, state) in states {
+            // the first state of an account is not a change
+            let (balance, _) = match self.accounts.get(address) {
+                Some(prev) if prev != state => prev,
+                _ => continue,
+            };
+
+            if *balance != state.0 {
+                balance_changes.push((*address, state.0));
+            }
+
+            changed.push(*address);
+        }
+
+        (balance_changes, changed)
+    }
+
+    fn commit(&mut self, states: Vec<(H160, (U256, U256))>) {
+        self.accounts.extend(states);
+    }
+}
+
+impl AccountWatcher {
+    fn new(light: Arc<LightQueryService>) -> Self {
+        AccountWatcher {
+            light,
+            state: Default::default(),
+        }
+    }
+
+    // process the epochs verifiable after pivot epoch `epoch` is received
+    async fn notify(
+        &mut self, subscriber: &Client, addresses: &[H160], epoch: u64,
+    ) {
+        // publish pivot chain reorg if necessary
+        if let Some(revert_to) = self.state.revert(epoch) {
+            ChainNotificationHandler::notify_async(
+                subscriber,
+                pubsub::Result::ChainReorg {
+                    revert_to: revert_to.into(),
+                },
+            )
+            .await;
+        }
+
+        let latest = match self.light.get_latest_verifiable_epoch_number() {
+            Ok(latest) => latest,
+            Err(e) => return trace!("No verifiable epoch yet: {}", e),
+        };
+
+        // the changes after a reorg are relative to the states in the epoch
+        // reverted to; on failure, retry on the next notification
+        if let (true, Some(last_epoch)) =
+            (self.state.reload_accounts, self.state.last_epoch)
+        {
+            if let Err(e) = self.load_accounts(addresses, last_epoch).await {
+                return warn!("Unable to load accounts: {}", e);
+            }
+        }
+
+        // start with the latest verifiable epoch on the first notification
+        let from = self.state.last_epoch.map_or(latest, |last| last + 1);
+
+        for epoch in from..=latest {
+            // on failure, retry the epoch on the next notification
+            if let Err(e) =
+                self.notify_epoch(subscriber, addresses, epoch).await
+            {
+                return warn!("Unable to process epoch {}: {}", epoch, e);
+            }
+            self.state.last_epoch = Some(epoch);
+        }
+    }
+
+    async fn get_account_state(
+        &self, address: H160, epoch: u64,
+    ) -> std::result::Result<(U256, U256), String> {
+        let account = self
+            .light
+            .get_account(EpochNumber::Number(epoch), address)
+            .await
+            .map_err(|e| format!("{}", e))?;
+
+        Ok(account
+            .map_or((U256::zero(), U256::zero()), |a| (a.balance, a.nonce)))
+    }
+
+    async fn load_accounts(
+        &mut self, addresses: &[H160], epoch: u64,
+    ) -> std::result::Result<(), String> {
+        for address in addresses {
+            let state = self.get_account_state(*address, epoch).await?;
+            self.state.accounts.insert(*address, state);
+        }
+
+        self.state.reload_accounts = false;
+        Ok(())
+    }
+
+    // publish the changes of the accounts in `epoch`; the states of the
+    // accounts are only updated once all the notifications are retrieved, so
+    // that a failed epoch is processed again from the same states
+    async fn notify_epoch(
+        &mut self, subscriber: &Client, addresses: &[H160], epoch: u64,
+    ) -> std::result::Result<(), String> {
+        trace!("notify_epoch({:?})", epoch);
+
+        let mut staged = vec![];
+
+        for address in addresses {
+            let state = self.get_account_state(*address, epoch).await?;
+            staged.push((*address, state));
+        }
+
+        let (balance_changes, changed) = self.state.changes(&staged);
+
+        let mut notifications: Vec<_> = balance_changes
+            .into_iter()
+            .map(|(address, balance)| pubsub::Result::BalanceChange {
+                address: address.into(),
+                epoch_number: epoch.into(),
+                balance,
+            })
+            .collect();
+
+        if !changed.is_empty() {
+            notifications.extend(self.transactions(&changed, epoch).await?);
+        }
+
+        notifications.extend(self.logs(addresses, epoch).await?);
+
+        for notification in notifications {
+            ChainNotificationHandler::notify_async(subscriber, notification)
+                .await;
+        }
+
+        self.state.commit(staged);
+        Ok(())
+    }
+
+    async fn transactions(
+        &self, addresses: &[H160], epoch: u64,
+    ) -> std::result::Result<Vec<pubsub::Result>, String> {
+        let blocks = self
+            .light
+            .get_epoch_txs(epoch)
+            .await
+            .map_err(|e| format!("{}", e))?;
+
+        let mut notifications = vec![];
+
+        for (block_hash, txs) in blocks {
+            for tx in txs {
+                let from = tx.sender();
+                let to = match tx.action {
+                    Action::Call(to) => Some(to),
+                    Action::Create => None,
+                };
+
+                for address in addresses {
+                    if from != *address && to != Some(*address) {
+                        continue;
+                    }
+
+                    notifications.push(pubsub::Result::AccountTransaction {
+                        address: (*address).into(),
+                        epoch_number: epoch.into(),
+                        block_hash,
+                        transaction_hash: tx.hash(),
+                        from: from.into(),
+                        to: to.map(Into::into),
+                        value: tx.value,
+                    });
+                }
+            }
+        }
+
+        Ok(notifications)
+    }
+
+    async fn logs(
+        &self, addresses: &[H160], epoch: u64,
+    ) -> std::result::Result<Vec<pubsub::Result>, String> {
+        let topics: Vec<H256> =
+            addresses.iter().cloned().map(H256::from).collect();
+
+        // logs emitted by the accounts, and logs with the accounts as the
+        // first or second indexed argument, e.g. token transfers
+        let mut filters = vec![Filter {
+            from_epoch: EpochNumber::Number(epoch),
+            to_epoch: EpochNumber::Number(epoch),
+            address: Some(addresses.to_vec()),
+            ..Default::default()
+        }];
+
+        for index in 1..3 {
+            let mut filter = Filter {
+                from_epoch: EpochNumber::Number(epoch),
+                to_epoch: EpochNumber::Number(epoch),
+                ..Default::default()
+            };
+            filter.topics[index] = Some(topics.clone());
+            filters.push(filter);
+        }
+
+        // deduplicate and order logs by their index in the epoch
+        let mut logs = BTreeMap::new();
+
+        for filter in filters {
+            let matching = self
+                .light
+                .get_logs(filter)
+                .await
+                .map_err(|e| format!("{}", e))?;
+
+            for log in matching {
+                logs.insert(log.log_index, log);
+            }
+        }
+
+        Ok(logs
+            .into_iter()
+            .map(|(_, log)| pubsub::Result::Log(RpcLog::from(log)))
+            .collect())
+    }
 }
 
 /// PubSub notification handler.
@@ -305,6 +784,74 @@ impl ChainNotificationHandler {
         }
     }
 
+    // publish the receipt of transaction `hash` if it's available and differs
+    // from the one `published`, identified by the hashes of its epoch and its
+    // block, and then its confirmation if the risk of its block is below
+    // `threshold`. Returns whether it's confirmed.
+    async fn notify_receipt(
+        &self, subscriber: &Client, hash: H256, threshold: U256,
+        published: &mut Option<(H256, H256)>,
+    ) -> bool
+    {
+        trace!("notify_receipt({:?})", hash);
+
+        let consensus_graph = self
+            .consensus
+            .as_any()
+            .downcast_ref::<ConsensusGraph>()
+            .expect("downcast should succeed");
+
+        let (epoch_hash, receipt) = match prepare_receipt(consensus_graph, hash)
+        {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                // not executed yet, or reverted by a pivot chain reorg
+                *published = None;
+                return false;
+            }
+            Err(e) => {
+                warn!("Unable to retrieve receipt of {:?}: {}", hash, e);
+                return false;
+            }
+        };
+
+        let block_hash = receipt.block_hash;
+        let epoch_number = receipt.epoch_number.unwrap_or_default();
+
+        // The transaction is executed again in the same block when the pivot
+        // chain changes, with a different receipt.
+        if *published != Some((epoch_hash, block_hash)) {
+            *published = Some((epoch_hash, block_hash));
+            Self::notify_async(subscriber, pubsub::Result::Receipt(receipt))
+                .await;
+        }
+
+        let risk = consensus_graph
+            .confirmation_meter
+            .confirmation_risk_by_hash(
+                &*consensus_graph.inner.read(),
+                block_hash,
+            )
+            .map(scale_confirmation_risk);
+
+        match risk {
+            Some(risk) if risk <= threshold => {
+                Self::notify_async(
+                    subscriber,
+                    pubsub::Result::TransactionConfirmed {
+                        transaction_hash: hash,
+                        block_hash,
+                        epoch_number: epoch_number.as_u64().into(),
+                        confirmation_risk: risk,
+                    },
+                )
+                .await;
+                true
+            }
+            _ => false,
+        }
+    }
+
     // attempt to retrieve block receipts from BlockDataManager
     // on failure, wait and retry a few times, then fail
     // NOTE: we do this because we might get epoch notifications
@@ -446,6 +993,95 @@ impl PubSub for PubSubClient {
                 "logs",
                 "Expected filter parameter.",
             ),
+            // --------- watch ---------
+            (pubsub::Kind::Watch, Some(pubsub::Params::Watch(params))) => {
+                match &self.light {
+                    None => error_codes::unimplemented(Some(
+                        "Watch subscriptions are served by light nodes.".into(),
+                    )),
+                    Some(light) => {
+                        let addresses = params
+                            .addresses
+                            .into_iter()
+                            .map(Into::into)
+                            .collect();
+                        let id = self
+                            .watch_subscribers
+                            .write()
+                            .push(subscriber, addresses);
+
+                        self.start_watch_loop(id, light.clone());
+                        return;
+                    }
+                }
+            }
+            (pubsub::Kind::Watch, _) => error_codes::invalid_params(
+                "watch",
+                "Expected watched addresses.",
+            ),
+            // --------- internalTransfers ---------
+            (pubsub::Kind::InternalTransfers, None) if self.light.is_none() => {
+                let id = self
+                    .internal_transfers_subscribers
+                    .write()
+                    .push(subscriber, None);
+
+                self.start_internal_transfers_loop(id);
+                return;
+            }
+            (
+                pubsub::Kind::InternalTransfers,
+                Some(pubsub::Params::Watch(params)),
+            ) if self.light.is_none() => {
+                let addresses =
+                    params.addresses.into_iter().map(Into::into).collect();
+                let id = self
+                    .internal_transfers_subscribers
+                    .write()
+                    .push(subscriber, Some(addresses));
+
+                self.start_internal_transfers_loop(id);
+                return;
+            }
+            (pubsub::Kind::InternalTransfers, _) if self.light.is_some() => {
+                error_codes::unimplemented(Some(
+                    "Internal transfers are only traced by full nodes.".into(),
+                ))
+            }
+            (pubsub::Kind::InternalTransfers, _) => {
+                error_codes::invalid_params(
+                    "internalTransfers",
+                    "Expected no parameters or the addresses to filter.",
+                )
+            }
+            // --------- transactionReceipt ---------
+            (
+                pubsub::Kind::TransactionReceipt,
+                Some(pubsub::Params::Receipt(params)),
+            ) if self.light.is_none() => {
+                let threshold = params.confirmation_risk.unwrap_or_else(|| {
+                    scale_confirmation_risk(DEFAULT_CONFIRMATION_RISK)
+                });
+                let id = self
+                    .receipt_subscribers
+                    .write()
+                    .push(subscriber, (params.transaction_hash, threshold));
+
+                self.start_receipt_loop(id);
+                return;
+            }
+            (pubsub::Kind::TransactionReceipt, _) if self.light.is_some() => {
+                error_codes::unimplemented(Some(
+                    "Transaction receipts are only served by full nodes."
+                        .into(),
+                ))
+            }
+            (pubsub::Kind::TransactionReceipt, _) => {
+                error_codes::invalid_params(
+                    "transactionReceipt",
+                    "Expected the transaction hash.",
+                )
+            }
             _ => error_codes::unimplemented(None),
         };
 
@@ -458,7 +1094,77 @@ impl PubSub for PubSubClient {
         let res0 = self.heads_subscribers.write().remove(&id).is_some();
         let res1 = self.epochs_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
+        let res3 = self.watch_subscribers.write().remove(&id).is_some();
+        let res4 = self
+            .internal_transfers_subscribers
+            .write()
+            .remove(&id)
+            .is_some();
+        let res5 = self.receipt_subscribers.write().remove(&id).is_some();
+
+        Ok(res0 || res1 || res2 || res3 || res4 || res5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchedState;
+    use cfx_types::{H160, U256};
+
+    #[test]
+    fn test_revert_watched_state() {
+        let mut state = WatchedState::default();
+
+        // nothing is reverted before the first epoch is processed
+        assert_eq!(state.revert(5), None);
+        assert!(!state.reload_accounts);
+
+        state.last_epoch = Some(10);
+        state
+            .accounts
+            .insert(H160::from_low_u64_be(1), (U256::from(100), U256::one()));
+
+        // a new pivot epoch does not revert anything
+        assert_eq!(state.revert(11), None);
+        assert_eq!(state.last_epoch, Some(10));
+        assert_eq!(state.accounts.len(), 1);
+        assert!(!state.reload_accounts);
+
+        // a reorg drops the account states of the reverted epochs
+        assert_eq!(state.revert(8), Some(7));
+        assert_eq!(state.last_epoch, Some(7));
+        assert!(state.accounts.is_empty());
+        assert!(state.reload_accounts);
+
+        // a reorg of an already reverted epoch is published again
+        assert_eq!(state.revert(7), Some(6));
+        assert_eq!(state.last_epoch, Some(6));
+        assert_eq!(state.revert(8), None);
+    }
 
-        Ok(res0 || res1 || res2)
+    #[test]
+    fn test_watched_state_changes() {
+        let mut state = WatchedState::default();
+        let (a, b) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+
+        // the first states are not changes
+        let states = vec![(a, (U256::from(100), U256::zero()))];
+        assert_eq!(state.changes(&states), (vec![], vec![]));
+        state.commit(states);
+
+        // a new nonce changes the account without changing the balance
+        let states = vec![
+            (a, (U256::from(100), U256::one())),
+            (b, (U256::from(5), U256::zero())),
+        ];
+        assert_eq!(state.changes(&states), (vec![], vec![a]));
+
+        // the states are unchanged until committed, so that a failed epoch
+        // yields the same changes again
+        let states = vec![(a, (U256::from(90), U256::one()))];
+        assert_eq!(state.changes(&states), (vec![(a, 90.into())], vec![a]));
+        assert_eq!(state.changes(&states), (vec![(a, 90.into())], vec![a]));
+        state.commit(states.clone());
+        assert_eq!(state.changes(&states), (vec![], vec![]));
     }
 }
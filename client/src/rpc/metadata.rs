@@ -18,7 +18,7 @@
 use super::types::Origin;
 use jsonrpc_core;
 use jsonrpc_pubsub::{PubSubMetadata, Session};
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 /// RPC methods metadata.
 #[derive(Clone, Default, Debug)]
@@ -27,6 +27,10 @@ pub struct Metadata {
     pub origin: Origin,
     /// Request PubSub Session
     pub session: Option<Arc<Session>>,
+    /// Client IP address, if known
+    pub client_ip: Option<IpAddr>,
+    /// API key given by the client
+    pub api_key: Option<String>,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -0,0 +1,281 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The middleware of the RPC requests on all the transports, which limits the
+//! call rate of the clients and logs the slow calls. The request size is
+//! limited by the transports.
+
+use crate::rpc::{
+    error_codes::request_rejected_too_many_request_error, Metadata, Origin,
+};
+use cfxcore::watchdog::{Heartbeat, WATCHDOG};
+use jsonrpc_core::{
+    futures::{
+        future::{self, Either},
+        Future,
+    },
+    Call, Error, FutureOutput, FutureResponse, Middleware, Output, Params,
+};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use throttling::time_window_bucket::TimeWindowBucket;
+
+/// The interval in which the calls of a client are counted.
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(1);
+/// The maximum length of the parameters in the slow call logs.
+const MAX_PARAMS_SUMMARY_LEN: usize = 256;
+
+lazy_static! {
+    static ref RPC_HEARTBEAT: Arc<Heartbeat> = WATCHDOG.logged_heartbeat("rpc");
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RpcMiddlewareConfig {
+    /// The maximum number of calls per second of a client, identified by its
+    /// IP address. The clients without a known address, i.e. the local IPC
+    /// ones, are not limited, and the configuration rejects the limit with the
+    /// transports not exposing the addresses.
+    pub client_rate_limit: Option<usize>,
+    /// The maximum number of calls per second with each API key. The calls
+    /// with an unknown API key are limited as those without one.
+    pub api_key_rate_limits: HashMap<String, usize>,
+    /// The calls taking at least this long are logged.
+    pub slow_call_threshold: Option<Duration>,
+}
+
+pub struct RpcMiddleware {
+    config: RpcMiddlewareConfig,
+    clients: Option<Mutex<TimeWindowBucket<IpAddr>>>,
+    api_keys: HashMap<String, Mutex<TimeWindowBucket<()>>>,
+}
+
+impl RpcMiddleware {
+    pub fn new(config: RpcMiddlewareConfig) -> Self {
+        let clients = config.client_rate_limit.map(|limit| {
+            Mutex::new(TimeWindowBucket::new(RATE_LIMIT_INTERVAL, limit))
+        });
+        let api_keys = config
+            .api_key_rate_limits
+            .iter()
+            .map(|(key, limit)| {
+                let bucket = TimeWindowBucket::new(RATE_LIMIT_INTERVAL, *limit);
+                (key.clone(), Mutex::new(bucket))
+            })
+            .collect();
+        RpcMiddleware {
+            config,
+            clients,
+            api_keys,
+        }
+    }
+
+    fn throttle(&self, meta: &Metadata) -> Result<(), Error> {
+        if let Some(bucket) =
+            meta.api_key.as_ref().and_then(|key| self.api_keys.get(key))
+        {
+            return if bucket.lock().try_acquire(()) {
+                Ok(())
+            } else {
+                Err(request_rejected_too_many_request_error(Some(
+                    "rate limit of the API key exceeded".into(),
+                )))
+            };
+        }
+        if let (Some(bucket), Some(client)) = (&self.clients, meta.client_ip) {
+            if !bucket.lock().try_acquire(client) {
+                debug!("RPC calls of client {} throttled", client);
+                return Err(request_rejected_too_many_request_error(Some(
+                    "rate limit of the client exceeded".into(),
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Middleware<Metadata> for RpcMiddleware {
+    type CallFuture = FutureOutput;
+    type Future = FutureResponse;
+
+    fn on_call<F, X>(
+        &self, call: Call, meta: Metadata, next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, Metadata) -> X + Send + Sync,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let (method, params) = match &call {
+            Call::MethodCall(call) => {
+                (call.method.clone(), call.params.clone())
+            }
+            Call::Notification(notification) => {
+                (notification.method.clone(), notification.params.clone())
+            }
+            Call::Invalid { .. } => return Either::B(next(call, meta)),
+        };
+
+        if let Err(error) = self.throttle(&meta) {
+            let output = match call {
+                Call::MethodCall(call) => {
+                    Some(Output::from(Err(error), call.id, call.jsonrpc))
+                }
+                _ => None,
+            };
+            return Either::A(Box::new(future::ok(output)));
+        }
+
+        // The call is a task of the RPC executor until its output is ready.
+        let task = RPC_HEARTBEAT.task(method.clone());
+        let slow_call_log = self
+            .config
+            .slow_call_threshold
+            .map(|threshold| (threshold, client_id(&meta), Instant::now()));
+        Either::A(Box::new(next(call, meta).map(move |output| {
+            drop(task);
+            if let Some((threshold, client, start)) = slow_call_log {
+                let elapsed = start.elapsed();
+                if elapsed >= threshold {
+                    info!(
+                        "Slow RPC call: method={} duration_ms={} client={} params={}",
+                        method,
+                        elapsed.as_millis(),
+                        client,
+                        params_summary(&params)
+                    );
+                }
+            }
+            output
+        })))
+    }
+}
+
+/// The identity of the client in the slow call logs.
+fn client_id(meta: &Metadata) -> String {
+    match (&meta.client_ip, &meta.origin) {
+        (Some(ip), _) => ip.to_string(),
+        (None, Origin::Ws { session }) => format!("ws:{:?}", session),
+        _ => "unknown".into(),
+    }
+}
+
+fn params_summary(params: &Params) -> String {
+    let mut summary =
+        serde_json::to_string(params).unwrap_or_else(|e| e.to_string());
+    if summary.len() > MAX_PARAMS_SUMMARY_LEN {
+        let mut end = MAX_PARAMS_SUMMARY_LEN;
+        while !summary.is_char_boundary(end) {
+            end -= 1;
+        }
+        summary.truncate(end);
+        summary.push_str("...");
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{params_summary, RpcMiddleware, RpcMiddlewareConfig};
+    use crate::rpc::Metadata;
+    use jsonrpc_core::{MetaIoHandler, Params, Result as RpcResult};
+    use jsonrpc_derive::rpc;
+    use serde_json::Value;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[rpc]
+    pub trait Foo {
+        #[rpc(name = "cfx_balance")]
+        fn balance(&self, _id: usize) -> RpcResult<usize>;
+    }
+
+    struct FooImpl;
+
+    impl Foo for FooImpl {
+        fn balance(&self, id: usize) -> RpcResult<usize> { Ok(id) }
+    }
+
+    const REQUEST: &str = r#"{"jsonrpc": "2.0", "method": "cfx_balance", "params": [8], "id": 1}"#;
+    const RESPONSE: &str = r#"{"jsonrpc":"2.0","result":8,"id":1}"#;
+
+    fn handler(
+        config: RpcMiddlewareConfig,
+    ) -> MetaIoHandler<Metadata, RpcMiddleware> {
+        let mut handler =
+            MetaIoHandler::with_middleware(RpcMiddleware::new(config));
+        handler.extend_with(FooImpl.to_delegate());
+        handler
+    }
+
+    fn client(last_byte: u8, api_key: Option<&str>) -> Metadata {
+        Metadata {
+            client_ip: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last_byte))),
+            api_key: api_key.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    fn is_throttled(response: Option<String>) -> bool {
+        response.map_or(false, |r| r.contains("-32072"))
+    }
+
+    #[test]
+    fn test_client_rate_limit() {
+        let handler = handler(RpcMiddlewareConfig {
+            client_rate_limit: Some(2),
+            api_key_rate_limits: vec![("key".to_string(), 3)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        });
+
+        for _ in 0..2 {
+            assert_eq!(
+                handler.handle_request_sync(REQUEST, client(1, None)),
+                Some(RESPONSE.to_string())
+            );
+        }
+        assert!(is_throttled(
+            handler.handle_request_sync(REQUEST, client(1, None))
+        ));
+        // An unknown API key is limited by the client address.
+        assert!(is_throttled(
+            handler.handle_request_sync(REQUEST, client(1, Some("unknown")))
+        ));
+        assert_eq!(
+            handler.handle_request_sync(REQUEST, client(2, None)),
+            Some(RESPONSE.to_string())
+        );
+        // The local IPC clients without an address are not limited.
+        for _ in 0..3 {
+            assert_eq!(
+                handler.handle_request_sync(REQUEST, Metadata::default()),
+                Some(RESPONSE.to_string())
+            );
+        }
+
+        for _ in 0..3 {
+            assert_eq!(
+                handler.handle_request_sync(REQUEST, client(1, Some("key"))),
+                Some(RESPONSE.to_string())
+            );
+        }
+        assert!(is_throttled(
+            handler.handle_request_sync(REQUEST, client(2, Some("key")))
+        ));
+    }
+
+    #[test]
+    fn test_params_summary() {
+        let params = Params::Array(vec![Value::String("a".repeat(1000))]);
+        let summary = params_summary(&params);
+        assert_eq!(summary.len(), super::MAX_PARAMS_SUMMARY_LEN + 3);
+        assert!(summary.ends_with("..."));
+        assert_eq!(params_summary(&Params::None), "null");
+    }
+}
@@ -3,14 +3,16 @@
 // See http://www.gnu.org/licenses/
 
 use super::super::types::{
-    Account as RpcAccount, Block, Bytes, CallRequest,
-    CheckBalanceAgainstTransactionResponse, EpochNumber,
+    Account as RpcAccount, AccountBatch, AccountTransactions, Block, Bytes,
+    CallRequest, CheckBalanceAgainstTransactionResponse, CollateralTrace,
+    CreateAccessListResponse, DecodedTransaction, EpochNumber, EpochStatistics,
     EstimateGasAndCollateralResponse, Filter as RpcFilter, Log as RpcLog,
-    Receipt as RpcReceipt, RewardInfo as RpcRewardInfo,
-    SponsorInfo as RpcSponsorInfo, Status as RpcStatus, Transaction,
+    Receipt as RpcReceipt, ReceiptProof, RewardInfo as RpcRewardInfo,
+    SenderStatus, SponsorInfo as RpcSponsorInfo, Status as RpcStatus,
+    StorageBatch, StorageSlot, Transaction, TransactionDirection,
 };
-use crate::rpc::types::BlockHashOrEpochNumber;
-use cfx_types::{H160, H256, U256, U64};
+use crate::rpc::types::{BlockHashOrEpochNumber, RpcAddress};
+use cfx_types::{H256, U256, U64};
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
 use jsonrpc_derive::rpc;
 use primitives::StorageRoot;
@@ -34,6 +36,20 @@ pub trait Cfx {
     //        #[rpc(name = "cfx_mining")]
     //        fn is_mining(&self) -> JsonRpcResult<bool>;
 
+    /// Returns the current work package: the problem hash, the boundary and
+    /// the block height.
+    #[rpc(name = "cfx_getWork")]
+    fn work(&self) -> JsonRpcResult<(H256, H256, U64)>;
+
+    /// Submits the nonce solving the work of the problem hash. Returns false
+    /// if the work is stale or the nonce is invalid.
+    #[rpc(name = "cfx_submitWork")]
+    fn submit_work(&self, nonce: U256, pow_hash: H256) -> JsonRpcResult<bool>;
+
+    /// Reports the hashrate of the miner `id`.
+    #[rpc(name = "cfx_submitHashrate")]
+    fn submit_hashrate(&self, hashrate: U256, id: H256) -> JsonRpcResult<bool>;
+
     /// Returns current gas price.
     #[rpc(name = "cfx_gasPrice")]
     fn gas_price(&self) -> JsonRpcResult<U256>;
@@ -47,48 +63,55 @@ pub trait Cfx {
     /// Returns balance of the given account.
     #[rpc(name = "cfx_getBalance")]
     fn balance(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<U256>;
 
     /// Returns admin of the given contract
     #[rpc(name = "cfx_getAdmin")]
     fn admin(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
-    ) -> BoxFuture<Option<H160>>;
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
+    ) -> BoxFuture<Option<RpcAddress>>;
 
     /// Returns sponsor information of the given contract
     #[rpc(name = "cfx_getSponsorInfo")]
     fn sponsor_info(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<RpcSponsorInfo>;
 
     /// Returns balance of the given account.
     #[rpc(name = "cfx_getStakingBalance")]
     fn staking_balance(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<U256>;
 
     /// Returns balance of the given account.
     #[rpc(name = "cfx_getCollateralForStorage")]
     fn collateral_for_storage(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<U256>;
 
     /// Returns the code at given address at given time (epoch number).
     #[rpc(name = "cfx_getCode")]
     fn code(
-        &self, addr: H160, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<Bytes>;
 
     /// Returns storage entries from a given contract.
     #[rpc(name = "cfx_getStorageAt")]
     fn storage_at(
-        &self, addr: H160, pos: H256, epoch_number: Option<EpochNumber>,
+        &self, addr: RpcAddress, pos: H256, epoch_number: Option<EpochNumber>,
     ) -> BoxFuture<Option<H256>>;
 
+    /// Returns the storage entries of the contracts, all read from the state
+    /// of one epoch.
+    #[rpc(name = "cfx_getStorageBatch")]
+    fn storage_batch(
+        &self, slots: Vec<StorageSlot>, epoch_number: Option<EpochNumber>,
+    ) -> JsonRpcResult<StorageBatch>;
+
     #[rpc(name = "cfx_getStorageRoot")]
     fn storage_root(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> BoxFuture<Option<StorageRoot>>;
 
     /// Returns block with given hash.
@@ -117,7 +140,7 @@ pub trait Cfx {
     /// given address at given time (epoch number).
     #[rpc(name = "cfx_getNextNonce")]
     fn next_nonce(
-        &self, addr: H160, epoch_number: Option<BlockHashOrEpochNumber>,
+        &self, addr: RpcAddress, epoch_number: Option<BlockHashOrEpochNumber>,
     ) -> JsonRpcResult<U256>;
 
     //        /// Returns the number of transactions in a block with given hash.
@@ -134,6 +157,13 @@ pub trait Cfx {
     #[rpc(name = "cfx_sendRawTransaction")]
     fn send_raw_transaction(&self, raw_tx: Bytes) -> JsonRpcResult<H256>;
 
+    /// Decodes a raw transaction and checks it as the transaction pool would,
+    /// without submitting it.
+    #[rpc(name = "cfx_decodeRawTransaction")]
+    fn decode_raw_transaction(
+        &self, raw: Bytes,
+    ) -> JsonRpcResult<DecodedTransaction>;
+
     //        /// @alias of `cfx_sendRawTransaction`.
     //        #[rpc(name = "cfx_submitTransaction")]
     //        fn submit_transaction(&self, Bytes) -> JsonRpcResult<H256>;
@@ -154,19 +184,55 @@ pub trait Cfx {
         &self, tx_hash: H256,
     ) -> BoxFuture<Option<Transaction>>;
 
-    /// Return estimated gas and collateral usage.
+    /// Return estimated gas and collateral usage. Without `epoch_number`, the
+    /// transaction is estimated against the pending state, i.e. after the
+    /// transactions of its sender in the transaction pool.
     #[rpc(name = "cfx_estimateGasAndCollateral")]
     fn estimate_gas_and_collateral(
         &self, request: CallRequest, epoch_number: Option<EpochNumber>,
     ) -> JsonRpcResult<EstimateGasAndCollateralResponse>;
 
+    /// Return the accounts and storage keys accessed by the transaction,
+    /// which can be loaded in advance when the transaction is executed.
+    #[rpc(name = "cfx_createAccessList")]
+    fn create_access_list(
+        &self, request: CallRequest, epoch_number: Option<EpochNumber>,
+    ) -> JsonRpcResult<CreateAccessListResponse>;
+
+    /// Return the storage collateral charged and refunded by each frame of
+    /// the transaction.
+    #[rpc(name = "cfx_traceCollateral")]
+    fn trace_collateral(
+        &self, request: CallRequest, epoch_number: Option<EpochNumber>,
+    ) -> JsonRpcResult<Vec<CollateralTrace>>;
+
     /// Check if user balance is enough for the transaction.
     #[rpc(name = "cfx_checkBalanceAgainstTransaction")]
     fn check_balance_against_transaction(
-        &self, account_addr: H160, contract_addr: H160, gas_limit: U256,
-        gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>,
+        &self, account_addr: RpcAddress, contract_addr: RpcAddress,
+        gas_limit: U256, gas_price: U256, storage_limit: U256,
+        epoch: Option<EpochNumber>,
     ) -> JsonRpcResult<CheckBalanceAgainstTransactionResponse>;
 
+    /// Reports why the transactions of the sender are pending: the nonce in
+    /// the latest executed state, the nonces of the transactions in the pool
+    /// with the gaps among them, and the reason the pool refused the latest
+    /// rejected transaction of the sender.
+    #[rpc(name = "cfx_checkSenderStatus")]
+    fn check_sender_status(
+        &self, address: RpcAddress,
+    ) -> JsonRpcResult<SenderStatus>;
+
+    /// Returns the hashes of the transactions sent or received by the
+    /// account, in the order of execution, from the `offset`-th (0 by
+    /// default) to at most `limit` (100 by default) of them. It is only
+    /// served if `persist_address_tx_index` is set.
+    #[rpc(name = "cfx_getAccountTransactions")]
+    fn account_transactions(
+        &self, address: RpcAddress, direction: TransactionDirection,
+        offset: Option<U64>, limit: Option<U64>,
+    ) -> JsonRpcResult<AccountTransactions>;
+
     #[rpc(name = "cfx_getBlocksByEpoch")]
     fn blocks_by_epoch(
         &self, epoch_number: EpochNumber,
@@ -182,12 +248,26 @@ pub trait Cfx {
         &self, tx_hash: H256,
     ) -> BoxFuture<Option<RpcReceipt>>;
 
+    /// Returns the proof of the receipt of the transaction under the receipts
+    /// root of its epoch, with the pivot block header committing the root,
+    /// once the root is committed on the pivot chain.
+    #[rpc(name = "cfx_getTransactionReceiptProof")]
+    fn transaction_receipt_proof(
+        &self, tx_hash: H256,
+    ) -> JsonRpcResult<Option<ReceiptProof>>;
+
     /// Return account related states of the given account
     #[rpc(name = "cfx_getAccount")]
     fn account(
-        &self, address: H160, epoch_num: Option<EpochNumber>,
+        &self, address: RpcAddress, epoch_num: Option<EpochNumber>,
     ) -> BoxFuture<RpcAccount>;
 
+    /// Returns the accounts, all read from the state of one epoch.
+    #[rpc(name = "cfx_getAccountBatch")]
+    fn account_batch(
+        &self, addresses: Vec<RpcAddress>, epoch_num: Option<EpochNumber>,
+    ) -> JsonRpcResult<AccountBatch>;
+
     /// Returns interest rate of the given epoch
     #[rpc(name = "cfx_getInterestRate")]
     fn interest_rate(
@@ -208,6 +288,14 @@ pub trait Cfx {
     #[rpc(name = "cfx_getStatus")]
     fn get_status(&self) -> JsonRpcResult<RpcStatus>;
 
+    /// Returns the statistics of the executed epochs in
+    /// `[from_epoch, to_epoch]` among the latest `chain_statistics_epochs`
+    /// ones, which are kept by the node.
+    #[rpc(name = "cfx_getChainStatistics")]
+    fn chain_statistics(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> JsonRpcResult<Vec<EpochStatistics>>;
+
     /// Returns block reward information in an epoch
     #[rpc(name = "cfx_getBlockRewardInfo")]
     fn get_block_reward_info(
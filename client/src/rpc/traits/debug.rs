@@ -3,18 +3,20 @@
 // See http://www.gnu.org/licenses/
 
 use super::super::types::{
-    Bytes as RpcBytes, ConsensusGraphStates, SyncGraphStates,
-    Transaction as RpcTransaction, TxPoolPendingInfo, TxWithPoolInfo,
+    Block as RpcBlock, Bytes as RpcBytes, ConsensusGraphStates, MemoryUsage,
+    SyncGraphStates, SyncStatus, Transaction as RpcTransaction,
+    TxAdmissionLimits, TxPoolPendingInfo, TxWithPoolInfo,
 };
-use crate::rpc::types::SendTxRequest;
-use cfx_types::{H160, H256, H520, U128};
+use crate::rpc::types::{RpcAddress, SendTxRequest};
+use cfx_types::{H256, H520, U128};
+use cfxcore_accounts::KeyFile;
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
 use jsonrpc_derive::rpc;
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, NodeLists, SessionDetails, UpdateNodeOperation,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, net::SocketAddr};
 
 #[rpc(server)]
 pub trait LocalRpc {
@@ -23,7 +25,7 @@ pub trait LocalRpc {
 
     #[rpc(name = "tx_inspect_pending")]
     fn tx_inspect_pending(
-        &self, address: H160,
+        &self, address: RpcAddress,
     ) -> JsonRpcResult<TxPoolPendingInfo>;
 
     #[rpc(name = "tx_inspect")]
@@ -31,14 +33,14 @@ pub trait LocalRpc {
 
     #[rpc(name = "txpool_inspect")]
     fn txpool_inspect(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<
         BTreeMap<String, BTreeMap<String, BTreeMap<usize, Vec<String>>>>,
     >;
 
     #[rpc(name = "txpool_content")]
     fn txpool_content(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<
         BTreeMap<
             String,
@@ -46,11 +48,27 @@ pub trait LocalRpc {
         >,
     >;
 
+    /// Returns the block to mine next with the transactions selected by the
+    /// packing strategy, without mining it.
+    #[rpc(name = "txpool_blockTemplate")]
+    fn txpool_block_template(&self) -> JsonRpcResult<RpcBlock>;
+
     #[rpc(name = "getTransactionsFromPool")]
     fn txs_from_pool(
-        &self, address: Option<H160>,
+        &self, address: Option<RpcAddress>,
     ) -> JsonRpcResult<Vec<RpcTransaction>>;
 
+    /// Returns the limits of the transactions admitted into the pool.
+    #[rpc(name = "txpool_admissionLimits")]
+    fn txpool_admission_limits(&self) -> JsonRpcResult<TxAdmissionLimits>;
+
+    /// Change the given limits of the transactions admitted into the pool
+    /// until the configuration is reloaded, and returns all the limits.
+    #[rpc(name = "txpool_setAdmissionLimits")]
+    fn txpool_set_admission_limits(
+        &self, limits: TxAdmissionLimits,
+    ) -> JsonRpcResult<TxAdmissionLimits>;
+
     #[rpc(name = "clear_tx_pool")]
     fn clear_tx_pool(&self) -> JsonRpcResult<()>;
 
@@ -72,15 +90,64 @@ pub trait LocalRpc {
         &self, node_id: Option<NodeId>,
     ) -> JsonRpcResult<Vec<SessionDetails>>;
 
+    #[rpc(name = "net_add_static_node")]
+    fn net_add_static_node(
+        &self, id: NodeId, address: SocketAddr,
+    ) -> JsonRpcResult<()>;
+
+    #[rpc(name = "net_remove_static_node")]
+    fn net_remove_static_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    #[rpc(name = "net_add_trusted_node")]
+    fn net_add_trusted_node(
+        &self, id: NodeId, address: SocketAddr,
+    ) -> JsonRpcResult<()>;
+
+    #[rpc(name = "net_remove_trusted_node")]
+    fn net_remove_trusted_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    #[rpc(name = "net_ban_node")]
+    fn net_ban_node(&self, id: NodeId) -> JsonRpcResult<()>;
+
+    #[rpc(name = "net_unban_node")]
+    fn net_unban_node(&self, id: NodeId) -> JsonRpcResult<bool>;
+
+    #[rpc(name = "net_node_lists")]
+    fn net_node_lists(&self) -> JsonRpcResult<Option<NodeLists>>;
+
+    /// Returns the log levels of the modules which are set explicitly.
+    #[rpc(name = "log_levels")]
+    fn log_levels(&self) -> JsonRpcResult<BTreeMap<String, String>>;
+
+    /// Set the log level of a module and its submodules, e.g. `cfxcore::sync`.
+    #[rpc(name = "log_setLevel")]
+    fn log_set_level(
+        &self, module: String, level: String,
+    ) -> JsonRpcResult<bool>;
+
+    /// Reload the reloadable entries of the configuration file, e.g. the log
+    /// level, the transaction pool policy and the peer limits.
+    #[rpc(name = "reload_config")]
+    fn reload_config(&self) -> JsonRpcResult<bool>;
+
     #[rpc(name = "current_sync_phase")]
     fn current_sync_phase(&self) -> JsonRpcResult<String>;
 
+    #[rpc(name = "sync_status")]
+    fn sync_status(&self) -> JsonRpcResult<SyncStatus>;
+
     #[rpc(name = "consensus_graph_state")]
     fn consensus_graph_state(&self) -> JsonRpcResult<ConsensusGraphStates>;
 
     #[rpc(name = "sync_graph_state")]
     fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
 
+    /// Returns the latest measurement of the heap memory used by the
+    /// components of the node, which is taken every
+    /// `print_memory_usage_period_s` seconds.
+    #[rpc(name = "memory_usage")]
+    fn memory_usage(&self) -> JsonRpcResult<MemoryUsage>;
+
     #[rpc(name = "cfx_sendTransaction")]
     fn send_transaction(
         &self, tx: SendTxRequest, password: Option<String>,
@@ -88,25 +155,49 @@ pub trait LocalRpc {
 
     /// Returns accounts list.
     #[rpc(name = "accounts")]
-    fn accounts(&self) -> JsonRpcResult<Vec<H160>>;
+    fn accounts(&self) -> JsonRpcResult<Vec<RpcAddress>>;
 
     /// Create a new account
     #[rpc(name = "new_account")]
-    fn new_account(&self, password: String) -> JsonRpcResult<H160>;
+    fn new_account(&self, password: String) -> JsonRpcResult<RpcAddress>;
+
+    /// Import an account from the raw secret key
+    #[rpc(name = "import_raw_key")]
+    fn import_raw_key(
+        &self, secret: H256, password: String,
+    ) -> JsonRpcResult<RpcAddress>;
+
+    /// Export an account in the keystore file format
+    #[rpc(name = "export_account")]
+    fn export_account(
+        &self, address: RpcAddress, password: String,
+    ) -> JsonRpcResult<KeyFile>;
+
+    /// Change the password of an account
+    #[rpc(name = "change_password")]
+    fn change_password(
+        &self, address: RpcAddress, password: String, new_password: String,
+    ) -> JsonRpcResult<bool>;
+
+    /// Permanently remove an account
+    #[rpc(name = "remove_account")]
+    fn remove_account(
+        &self, address: RpcAddress, password: String,
+    ) -> JsonRpcResult<bool>;
 
     /// Unlock an account
     #[rpc(name = "unlock_account")]
     fn unlock_account(
-        &self, address: H160, password: String, duration: Option<U128>,
+        &self, address: RpcAddress, password: String, duration: Option<U128>,
     ) -> JsonRpcResult<bool>;
 
     /// Lock an account
     #[rpc(name = "lock_account")]
-    fn lock_account(&self, address: H160) -> JsonRpcResult<bool>;
+    fn lock_account(&self, address: RpcAddress) -> JsonRpcResult<bool>;
 
     #[rpc(name = "sign")]
     fn sign(
-        &self, data: RpcBytes, address: H160, password: Option<String>,
+        &self, data: RpcBytes, address: RpcAddress, password: Option<String>,
     ) -> JsonRpcResult<H520>;
 
     #[rpc(name = "cfx_signTransaction")]
@@ -0,0 +1,46 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::super::types::{
+    AccountBalance, ContractCreation, EpochTransactionCount, RpcAddress,
+    TokenTransfer,
+};
+use cfx_types::U64;
+use jsonrpc_core::Result as JsonRpcResult;
+use jsonrpc_derive::rpc;
+
+/// The aggregates for a block explorer, served from the data of the node.
+/// The epoch ranges are inclusive.
+#[rpc(server)]
+pub trait Explorer {
+    /// Returns the accounts with the largest balances in the latest state,
+    /// among the senders, receivers and contracts of the executed
+    /// transactions and the block authors. The epochs are scanned in the
+    /// background, so the latest ones may be missing.
+    #[rpc(name = "explorer_getRichestAccounts")]
+    fn richest_accounts(
+        &self, limit: Option<U64>,
+    ) -> JsonRpcResult<Vec<AccountBalance>>;
+
+    /// Returns the numbers of blocks and transactions in each epoch.
+    #[rpc(name = "explorer_getEpochTransactionCounts")]
+    fn epoch_transaction_counts(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> JsonRpcResult<Vec<EpochTransactionCount>>;
+
+    /// Returns the contracts created by the transactions in the executed
+    /// epochs.
+    #[rpc(name = "explorer_getContractCreations")]
+    fn contract_creations(
+        &self, from_epoch: U64, to_epoch: U64,
+    ) -> JsonRpcResult<Vec<ContractCreation>>;
+
+    /// Returns the decoded `Transfer` events of the ERC-20 and ERC-721
+    /// tokens, optionally of the `token` contract only. Fails if there are
+    /// too many transfers to return, instead of truncating them.
+    #[rpc(name = "explorer_getTokenTransfers")]
+    fn token_transfers(
+        &self, from_epoch: U64, to_epoch: U64, token: Option<RpcAddress>,
+    ) -> JsonRpcResult<Vec<TokenTransfer>>;
+}
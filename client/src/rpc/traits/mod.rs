@@ -4,10 +4,12 @@
 
 pub use cfx::Cfx;
 pub use debug::LocalRpc;
+pub use explorer::Explorer;
 pub use pubsub::PubSub;
 pub use test::TestRpc;
 
 pub mod cfx;
 pub mod debug;
+pub mod explorer;
 pub mod pubsub;
 pub mod test;
@@ -2,7 +2,9 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use super::super::types::{BlameInfo, Block, Bytes};
+use super::super::types::{
+    BlameInfo, Block, Bytes, ExecutionDivergence, ExecutionVariant,
+};
 use cfx_types::{H256, U256};
 use cfxcore::PeerInfo;
 use jsonrpc_core::Result as RpcResult;
@@ -98,6 +100,14 @@ pub trait TestRpc {
 
     #[rpc(name = "getExecutedInfo")]
     fn get_executed_info(&self, block_hash: H256) -> RpcResult<(H256, H256)>;
+    /// Re-executes the epochs in `[start_epoch, end_epoch]` with both
+    /// variants, and returns the epochs whose results differ.
+    #[rpc(name = "test_differentialExecution")]
+    fn differential_execution(
+        &self, start_epoch: u64, end_epoch: u64, first: ExecutionVariant,
+        second: ExecutionVariant,
+    ) -> RpcResult<Vec<ExecutionDivergence>>;
+
     #[rpc(name = "test_sendUsableGenesisAccounts")]
     fn send_usable_genesis_accounts(
         &self, account_start_index: usize,
@@ -3,45 +3,70 @@
 // See http://www.gnu.org/licenses/
 
 mod account;
+mod address;
+mod admission_limits;
 mod blame_info;
 mod block;
 mod bytes;
 mod call_request;
+mod chain_statistics;
 mod consensus_graph_states;
+mod differential;
 mod epoch_number;
+mod explorer;
 mod filter;
 mod index;
 mod log;
+mod memory_usage;
 mod provenance;
 mod receipt;
+mod receipt_proof;
 mod reward_info;
+mod state_batch;
 mod status;
 mod sync_graph_states;
+mod sync_status;
+mod trace;
 mod transaction;
 
 pub mod pubsub;
 
 pub use self::{
     account::{Account, SponsorInfo},
+    address::{set_address_format, RpcAddress},
+    admission_limits::TxAdmissionLimits,
     blame_info::BlameInfo,
     block::{Block, BlockTransactions, Header},
     bytes::Bytes,
     call_request::{
-        sign_call, CallRequest, CheckBalanceAgainstTransactionResponse,
+        sign_call, AccessListItem, CallRequest,
+        CheckBalanceAgainstTransactionResponse, CreateAccessListResponse,
         EstimateGasAndCollateralResponse,
     },
+    chain_statistics::EpochStatistics,
     consensus_graph_states::ConsensusGraphStates,
+    differential::{ExecutionDivergence, ExecutionVariant, ReceiptMismatch},
     epoch_number::{BlockHashOrEpochNumber, EpochNumber},
+    explorer::{
+        AccountBalance, ContractCreation, EpochTransactionCount, TokenTransfer,
+        TRANSFER_EVENT_TOPIC,
+    },
     filter::Filter,
     index::Index,
     log::Log,
+    memory_usage::MemoryUsage,
     provenance::Origin,
     receipt::Receipt,
+    receipt_proof::ReceiptProof,
     reward_info::RewardInfo,
+    state_batch::{AccountBatch, StorageBatch, StorageSlot},
     status::Status,
     sync_graph_states::SyncGraphStates,
+    sync_status::SyncStatus,
+    trace::{CollateralTrace, StorageChange},
     transaction::{
-        PackedOrExecuted, SendTxRequest, Transaction, TxPoolPendingInfo,
-        TxWithPoolInfo,
+        AccountTransactions, DecodedTransaction, PackedOrExecuted,
+        RejectedTransaction, SendTxRequest, SenderStatus, Transaction,
+        TransactionDirection, TxPoolPendingInfo, TxWithPoolInfo,
     },
 };
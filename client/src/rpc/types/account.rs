@@ -2,7 +2,8 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use cfx_types::{H160, H256, U256};
+use crate::rpc::types::RpcAddress;
+use cfx_types::{H256, U256};
 use primitives::{
     Account as PrimitiveAccount, SponsorInfo as PrimitiveSponsorInfo,
 };
@@ -16,7 +17,7 @@ pub struct Account {
     pub staking_balance: U256,
     pub collateral_for_storage: U256,
     pub accumulated_interest_return: U256,
-    pub admin: H160,
+    pub admin: RpcAddress,
 }
 
 impl Account {
@@ -38,8 +39,8 @@ impl Account {
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SponsorInfo {
-    pub sponsor_for_gas: H160,
-    pub sponsor_for_collateral: H160,
+    pub sponsor_for_gas: RpcAddress,
+    pub sponsor_for_collateral: RpcAddress,
     pub sponsor_gas_bound: U256,
     pub sponsor_balance_for_gas: U256,
     pub sponsor_balance_for_collateral: U256,
@@ -80,7 +81,7 @@ mod tests {
         let account = Account::new(pri_account);
         let account_info = serde_json::to_string(&account).unwrap();
         assert_eq!(account_info,
-                   "{\"balance\":\"0x0\",\"nonce\":\"0x0\",\"codeHash\":\"0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\",\"stakingBalance\":\"0x0\",\"collateralForStorage\":\"0x0\",\"accumulatedInterestReturn\":\"0x0\",\"admin\":\"0x0000000000000000000000000000000000000000\"}");
+                   "{\"balance\":\"0x0\",\"nonce\":\"0x0\",\"codeHash\":\"0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\",\"stakingBalance\":\"0x0\",\"collateralForStorage\":\"0x0\",\"accumulatedInterestReturn\":\"0x0\",\"admin\":\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa6f0vrcsw\"}");
     }
     #[test]
     fn test_account_serialize() {
@@ -91,10 +92,10 @@ mod tests {
             staking_balance: U256::one(),
             collateral_for_storage: U256::one(),
             accumulated_interest_return: U256::one(),
-            admin: H160([0xff; 20]),
+            admin: H160([0xff; 20]).into(),
         };
         let serialize = serde_json::to_string(&account).unwrap();
-        assert_eq!(serialize,"{\"balance\":\"0x1\",\"nonce\":\"0x1\",\"codeHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"stakingBalance\":\"0x1\",\"collateralForStorage\":\"0x1\",\"accumulatedInterestReturn\":\"0x1\",\"admin\":\"0xffffffffffffffffffffffffffffffffffffffff\"}");
+        assert_eq!(serialize,"{\"balance\":\"0x1\",\"nonce\":\"0x1\",\"codeHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"stakingBalance\":\"0x1\",\"collateralForStorage\":\"0x1\",\"accumulatedInterestReturn\":\"0x1\",\"admin\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\"}");
     }
     #[test]
     fn test_account_deserialize() {
@@ -105,9 +106,9 @@ mod tests {
             staking_balance: U256::one(),
             collateral_for_storage: U256::one(),
             accumulated_interest_return: U256::one(),
-            admin: H160([0xff; 20]),
+            admin: H160([0xff; 20]).into(),
         };
-        let serialize = "{\"balance\":\"0x1\",\"nonce\":\"0x1\",\"codeHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"stakingBalance\":\"0x1\",\"collateralForStorage\":\"0x1\",\"accumulatedInterestReturn\":\"0x1\",\"admin\":\"0xffffffffffffffffffffffffffffffffffffffff\"}";
+        let serialize = "{\"balance\":\"0x1\",\"nonce\":\"0x1\",\"codeHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"stakingBalance\":\"0x1\",\"collateralForStorage\":\"0x1\",\"accumulatedInterestReturn\":\"0x1\",\"admin\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\"}";
         let deserialize: Account = serde_json::from_str(serialize).unwrap();
         assert_eq!(deserialize, account);
     }
@@ -123,6 +124,6 @@ mod tests {
         let sponsor_info = SponsorInfo::new(pri_sponsor_info);
         let sponsor_info_new = serde_json::to_string(&sponsor_info).unwrap();
         assert_eq!(sponsor_info_new,
-        r#"{"sponsorForGas":"0xffffffffffffffffffffffffffffffffffffffff","sponsorForCollateral":"0xffffffffffffffffffffffffffffffffffffffff","sponsorGasBound":"0x1","sponsorBalanceForGas":"0x1","sponsorBalanceForCollateral":"0x1"}"#);
+        r#"{"sponsorForGas":"cfxtest:ad99999999999999999999999999999996bw9gccab","sponsorForCollateral":"cfxtest:ad99999999999999999999999999999996bw9gccab","sponsorGasBound":"0x1","sponsorBalanceForGas":"0x1","sponsorBalanceForCollateral":"0x1"}"#);
     }
 }
@@ -0,0 +1,151 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{base32_address, H160};
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+static NETWORK_ID: AtomicU64 = AtomicU64::new(base32_address::TESTNET_ID);
+static HEX_ADDRESS: AtomicBool = AtomicBool::new(false);
+
+/// Set the network of the base32 addresses in the RPCs, and whether the RPCs
+/// return the legacy hex addresses instead.
+pub fn set_address_format(network_id: u64, hex_address: bool) {
+    NETWORK_ID.store(network_id, Ordering::Relaxed);
+    HEX_ADDRESS.store(hex_address, Ordering::Relaxed);
+}
+
+/// Represents an address, in the CIP-37 base32 format of the node's network,
+/// e.g. `cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p`. Hex addresses are
+/// only accepted and returned if `jsonrpc_hex_address` is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RpcAddress(pub H160);
+
+impl From<H160> for RpcAddress {
+    fn from(address: H160) -> Self { RpcAddress(address) }
+}
+
+impl From<RpcAddress> for H160 {
+    fn from(address: RpcAddress) -> Self { address.0 }
+}
+
+impl fmt::Display for RpcAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if HEX_ADDRESS.load(Ordering::Relaxed) {
+            write!(f, "{:?}", self.0)
+        } else {
+            let network_id = NETWORK_ID.load(Ordering::Relaxed);
+            let encoded = base32_address::encode(&self.0, network_id, false);
+            write!(f, "{}", encoded)
+        }
+    }
+}
+
+impl Serialize for RpcAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'a> Deserialize<'a> for RpcAddress {
+    fn deserialize<D>(deserializer: D) -> Result<RpcAddress, D::Error>
+    where D: Deserializer<'a> {
+        deserializer.deserialize_str(RpcAddressVisitor)
+    }
+}
+
+struct RpcAddressVisitor;
+
+impl<'a> Visitor<'a> for RpcAddressVisitor {
+    type Value = RpcAddress;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a base32 address")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where E: Error {
+        let network_id = NETWORK_ID.load(Ordering::Relaxed);
+
+        if value.starts_with("0x") {
+            let address = H160::from_str(&value[2..]).map_err(|e| {
+                E::custom(format!("Invalid hex address {}: {:?}", value, e))
+            })?;
+            if !HEX_ADDRESS.load(Ordering::Relaxed) {
+                return Err(E::custom(format!(
+                    "Hex address {} is not accepted, use its base32 address {}",
+                    value,
+                    base32_address::encode(&address, network_id, false)
+                )));
+            }
+            return Ok(RpcAddress(address));
+        }
+
+        match base32_address::decode(value) {
+            Ok((address, id)) if id == network_id => Ok(RpcAddress(address)),
+            Ok((_, id)) => Err(E::custom(format!(
+                "Address {} is on network {}, expected {}",
+                value,
+                base32_address::network_prefix(id),
+                base32_address::network_prefix(network_id)
+            ))),
+            Err(e) => Err(E::custom(format!(
+                "Invalid base32 address {}: {}",
+                value, e
+            ))),
+        }
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+    where E: Error {
+        self.visit_str(value.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RpcAddress;
+    use cfx_types::H160;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_deserialize() {
+        let address =
+            H160::from_str("106d49f8505410eb4e671d51f7d96d2c87807b09").unwrap();
+        let base32 = "\"cfxtest:aajg4wt2mbmbb44sp6szd783ry0jtad5bemzfdf83g\"";
+        let verbose =
+            "\"CFXTEST:TYPE.USER:AAJG4WT2MBMBB44SP6SZD783RY0JTAD5BEMZFDF83G\"";
+
+        assert_eq!(
+            serde_json::from_str::<RpcAddress>(base32).unwrap(),
+            RpcAddress(address)
+        );
+        assert_eq!(
+            serde_json::from_str::<RpcAddress>(verbose).unwrap(),
+            RpcAddress(address)
+        );
+        assert_eq!(
+            serde_json::to_string(&RpcAddress(address)).unwrap(),
+            base32
+        );
+
+        // hex addresses, mistyped addresses and the addresses of other
+        // networks are rejected
+        for rejected in &[
+            "\"0x106d49f8505410eb4e671d51f7d96d2c87807b09\"",
+            "\"cfxtest:aajg4wt2mbmbb44sp6szd783ry0jtad5bemzfdf83h\"",
+            "\"cfx:aajg4wt2mbmbb44sp6szd783ry0jtad5bea80xdy7p\"",
+        ] {
+            assert!(serde_json::from_str::<RpcAddress>(rejected).is_err());
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::U64;
+use cfxcore::transaction_pool::TxAdmissionLimits as PrimitiveTxAdmissionLimits;
+
+/// The limits of the transactions admitted into the transaction pool. The
+/// absent limits are kept unchanged when the limits are set.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct TxAdmissionLimits {
+    pub max_tx_data_bytes: Option<U64>,
+    pub max_tx_gas: Option<U64>,
+    pub min_tx_gas_price: Option<U64>,
+}
+
+impl TxAdmissionLimits {
+    /// Returns `limits` with the limits given in `self` replaced.
+    pub fn apply_to(
+        &self, mut limits: PrimitiveTxAdmissionLimits,
+    ) -> PrimitiveTxAdmissionLimits {
+        if let Some(max_tx_data_bytes) = self.max_tx_data_bytes {
+            limits.max_tx_data_bytes = max_tx_data_bytes.as_usize();
+        }
+        if let Some(max_tx_gas) = self.max_tx_gas {
+            limits.max_tx_gas = max_tx_gas.as_u64();
+        }
+        if let Some(min_tx_gas_price) = self.min_tx_gas_price {
+            limits.min_tx_gas_price = min_tx_gas_price.as_u64();
+        }
+        limits
+    }
+}
+
+impl From<PrimitiveTxAdmissionLimits> for TxAdmissionLimits {
+    fn from(limits: PrimitiveTxAdmissionLimits) -> Self {
+        TxAdmissionLimits {
+            max_tx_data_bytes: Some(limits.max_tx_data_bytes.into()),
+            max_tx_gas: Some(limits.max_tx_gas.into()),
+            min_tx_gas_price: Some(limits.min_tx_gas_price.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PrimitiveTxAdmissionLimits, TxAdmissionLimits};
+
+    #[test]
+    fn test_apply_to() {
+        let limits: TxAdmissionLimits =
+            serde_json::from_str(r#"{"maxTxGas":"0x64"}"#).unwrap();
+        let applied = limits.apply_to(PrimitiveTxAdmissionLimits::default());
+        assert_eq!(applied.max_tx_gas, 100);
+        assert_eq!(
+            applied.max_tx_data_bytes,
+            PrimitiveTxAdmissionLimits::default().max_tx_data_bytes
+        );
+        assert_eq!(
+            serde_json::to_string(&TxAdmissionLimits::from(applied)).unwrap(),
+            r#"{"maxTxDataBytes":"0x32000","maxTxGas":"0x64","minTxGasPrice":"0x1"}"#
+        );
+
+        assert!(serde_json::from_str::<TxAdmissionLimits>(
+            r#"{"maxGas":"0x64"}"#
+        )
+        .is_err());
+    }
+}
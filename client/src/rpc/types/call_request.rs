@@ -2,22 +2,25 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::types::Bytes;
-use cfx_types::{address_util::AddressUtil, Address, H160, U256, U64};
+use crate::rpc::types::{Bytes, RpcAddress};
+use cfx_types::{address_util::AddressUtil, Address, U256, U64};
 use primitives::{
-    transaction::Action, SignedTransaction, Transaction as PrimitiveTransaction,
+    transaction::Action, AccessListItem as PrimitiveAccessListItem,
+    SignedTransaction, Transaction as PrimitiveTransaction,
 };
 use std::cmp::min;
 
 // use serde_json::de::ParserNumber::U64;
 
-#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize,
+)]
 #[serde(rename_all = "camelCase")]
 pub struct CallRequest {
     /// From
-    pub from: Option<H160>,
+    pub from: Option<RpcAddress>,
     /// To
-    pub to: Option<H160>,
+    pub to: Option<RpcAddress>,
     /// Gas Price
     pub gas_price: Option<U256>,
     /// Gas
@@ -35,12 +38,42 @@ pub struct CallRequest {
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EstimateGasAndCollateralResponse {
-    /// The amount of gas used in the execution.
+    /// The minimal gas limit with which the execution succeeds, within the
+    /// configured error tolerance.
     pub gas_used: U256,
     /// The number of bytes collateralized in the execution.
     pub storage_collateralized: U256,
 }
 
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: RpcAddress,
+    pub storage_keys: Vec<Bytes>,
+}
+
+impl From<PrimitiveAccessListItem> for AccessListItem {
+    fn from(item: PrimitiveAccessListItem) -> Self {
+        AccessListItem {
+            address: item.address.into(),
+            storage_keys: item
+                .storage_keys
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccessListResponse {
+    /// The accounts and storage keys accessed in the execution.
+    pub access_list: Vec<AccessListItem>,
+    /// The amount of gas used in the execution.
+    pub gas_used: U256,
+}
+
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckBalanceAgainstTransactionResponse {
@@ -57,7 +90,7 @@ pub fn sign_call(
 ) -> SignedTransaction {
     let max_gas = U256::from(500_000_000);
     let gas = min(request.gas.unwrap_or(max_gas), max_gas);
-    let from = request.from.unwrap_or_else(|| {
+    let from = request.from.map(Into::into).unwrap_or_else(|| {
         let mut address = Address::random();
         address.set_user_account_type_bits();
         address
@@ -65,7 +98,9 @@ pub fn sign_call(
 
     PrimitiveTransaction {
         nonce: request.nonce.unwrap_or_default(),
-        action: request.to.map_or(Action::Create, Action::Call),
+        action: request
+            .to
+            .map_or(Action::Create, |to| Action::Call(to.into())),
         gas,
         gas_price: request.gas_price.unwrap_or_default(),
         value: request.value.unwrap_or_default(),
@@ -99,8 +134,8 @@ mod tests {
     #[test]
     fn call_request_deserialize() {
         let s = r#"{
-            "from":"0x0000000000000000000000000000000000000001",
-            "to":"0x0000000000000000000000000000000000000002",
+            "from":"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeckkywsu0",
+            "to":"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaajk7b54454",
             "gasPrice":"0x1",
             "gas":"0x2",
             "value":"0x3",
@@ -113,8 +148,8 @@ mod tests {
         assert_eq!(
             deserialized,
             CallRequest {
-                from: Some(H160::from_low_u64_be(1)),
-                to: Some(H160::from_low_u64_be(2)),
+                from: Some(H160::from_low_u64_be(1).into()),
+                to: Some(H160::from_low_u64_be(2).into()),
                 gas_price: Some(U256::from(1)),
                 gas: Some(U256::from(2)),
                 value: Some(U256::from(3)),
@@ -128,8 +163,8 @@ mod tests {
     #[test]
     fn call_request_deserialize2() {
         let s = r#"{
-            "from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
-            "to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+            "from": "cfxtest:ac5a7ds0dvsxftyanc727snuu6husj3vmy599ffkn6",
+            "to": "cfxtest:admg7ds0tvsxftyanc727snuu6huskcfp68244074b",
             "gas": "0x76c0",
             "gasPrice": "0x9184e72a000",
             "value": "0x9184e72a",
@@ -139,8 +174,8 @@ mod tests {
         let deserialized: CallRequest = serde_json::from_str(s).unwrap();
 
         assert_eq!(deserialized, CallRequest {
-            from: Some(H160::from_str("b60e8dd61c5d32be8058bb8eb970870f07233155").unwrap()),
-            to: Some(H160::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+            from: Some(H160::from_str("b60e8dd61c5d32be8058bb8eb970870f07233155").unwrap().into()),
+            to: Some(H160::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap().into()),
             gas_price: Some(U256::from_str("9184e72a000").unwrap()),
             gas: Some(U256::from_str("76c0").unwrap()),
             value: Some(U256::from_str("9184e72a").unwrap()),
@@ -152,13 +187,14 @@ mod tests {
 
     #[test]
     fn call_request_deserialize_empty() {
-        let s = r#"{"from":"0x0000000000000000000000000000000000000001"}"#;
+        let s =
+            r#"{"from":"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeckkywsu0"}"#;
         let deserialized: CallRequest = serde_json::from_str(s).unwrap();
 
         assert_eq!(
             deserialized,
             CallRequest {
-                from: Some(H160::from_low_u64_be(1)),
+                from: Some(H160::from_low_u64_be(1).into()),
                 to: None,
                 gas_price: None,
                 gas: None,
@@ -172,8 +208,8 @@ mod tests {
     #[test]
     fn test_sign_call() {
         let call_request = CallRequest {
-            from: Some(H160::from_low_u64_be(1)),
-            to: Some(H160::from_low_u64_be(2)),
+            from: Some(H160::from_low_u64_be(1).into()),
+            to: Some(H160::from_low_u64_be(2).into()),
             gas_price: Some(U256::from(1)),
             gas: Some(U256::from(2)),
             value: Some(U256::from(3)),
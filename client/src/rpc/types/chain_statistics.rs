@@ -0,0 +1,42 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H256, U256, U64};
+use cfxcore::statistics::EpochStatistics as PrimitiveEpochStatistics;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochStatistics {
+    pub epoch_number: U64,
+    pub pivot_hash: H256,
+    /// The timestamp of the pivot block.
+    pub timestamp: U64,
+    pub block_count: U64,
+    pub transaction_count: U64,
+    pub gas_used: U256,
+    /// The seconds since the pivot block of the previous epoch.
+    pub block_interval: U64,
+    /// The number of unpacked transactions in the pool when the epoch is
+    /// executed, which is null for the epochs backfilled.
+    pub pool_size: Option<U64>,
+    /// The number of peers when the epoch is executed, which is null for the
+    /// epochs backfilled.
+    pub peer_count: Option<U64>,
+}
+
+impl From<PrimitiveEpochStatistics> for EpochStatistics {
+    fn from(stats: PrimitiveEpochStatistics) -> Self {
+        EpochStatistics {
+            epoch_number: stats.epoch_number.into(),
+            pivot_hash: stats.pivot_hash,
+            timestamp: stats.timestamp.into(),
+            block_count: stats.block_count.into(),
+            transaction_count: stats.transaction_count.into(),
+            gas_used: stats.gas_used,
+            block_interval: stats.block_interval.into(),
+            pool_size: stats.pool_size.map(Into::into),
+            peer_count: stats.peer_count.map(Into::into),
+        }
+    }
+}
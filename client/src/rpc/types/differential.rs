@@ -0,0 +1,76 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H256, U64};
+use cfxcore::consensus::consensus_inner::consensus_executor::{
+    ExecutionDivergence as PrimitiveExecutionDivergence,
+    ExecutionVariant as PrimitiveExecutionVariant,
+};
+use primitives::TransactionIndex;
+
+/// An interpreter configuration to re-execute the epochs with.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionVariant {
+    /// Execute the transactions in a block optimistically in parallel.
+    #[serde(default)]
+    pub parallel_execution: bool,
+    /// Execute with the spec activated at this epoch instead of the one of
+    /// the executed epoch.
+    pub spec_epoch: Option<U64>,
+}
+
+impl Into<PrimitiveExecutionVariant> for ExecutionVariant {
+    fn into(self) -> PrimitiveExecutionVariant {
+        PrimitiveExecutionVariant {
+            parallel_execution: self.parallel_execution,
+            spec_epoch_height: self.spec_epoch.map(|epoch| epoch.as_u64()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptMismatch {
+    pub block_hash: H256,
+    pub index: U64,
+}
+
+impl From<TransactionIndex> for ReceiptMismatch {
+    fn from(index: TransactionIndex) -> Self {
+        ReceiptMismatch {
+            block_hash: index.block_hash,
+            index: index.index.into(),
+        }
+    }
+}
+
+/// The differences found by executing an epoch with two variants.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionDivergence {
+    pub epoch_hash: H256,
+    /// The state roots after executing the transactions with each variant.
+    pub state_roots: Vec<H256>,
+    /// The transactions whose receipts differ.
+    pub receipt_mismatches: Vec<ReceiptMismatch>,
+}
+
+impl From<PrimitiveExecutionDivergence> for ExecutionDivergence {
+    fn from(divergence: PrimitiveExecutionDivergence) -> Self {
+        ExecutionDivergence {
+            epoch_hash: divergence.epoch_hash,
+            state_roots: divergence
+                .state_roots
+                .iter()
+                .map(|root| root.state_root.compute_state_root_hash())
+                .collect(),
+            receipt_mismatches: divergence
+                .receipt_mismatches
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::RpcAddress;
+use cfx_types::{Address, H256, U256, U64};
+use keccak_hash::keccak;
+use lazy_static::lazy_static;
+use primitives::log_entry::LocalizedLogEntry;
+
+lazy_static! {
+    /// The topic of `Transfer(address,address,uint256)`, which is emitted by
+    /// both the ERC-20 and the ERC-721 tokens.
+    pub static ref TRANSFER_EVENT_TOPIC: H256 =
+        keccak("Transfer(address,address,uint256)");
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalance {
+    pub address: RpcAddress,
+    pub balance: U256,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochTransactionCount {
+    pub epoch_number: U64,
+    /// The number of blocks in the epoch.
+    pub block_count: U64,
+    /// The number of transactions packed in the blocks of the epoch,
+    /// including the ones which are not executed, e.g. the duplicates.
+    pub transaction_count: U64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractCreation {
+    /// The address of the created contract.
+    pub address: RpcAddress,
+    pub creator: RpcAddress,
+    pub transaction_hash: H256,
+    pub block_hash: H256,
+    pub epoch_number: U64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenTransfer {
+    /// The address of the token contract.
+    pub token: RpcAddress,
+    pub from: RpcAddress,
+    pub to: RpcAddress,
+    /// The amount transferred, for an ERC-20 token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// The id of the token transferred, for an ERC-721 token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<U256>,
+    pub transaction_hash: H256,
+    pub block_hash: H256,
+    pub epoch_number: U64,
+    /// The position of the log in the epoch.
+    pub log_index: U64,
+}
+
+impl TokenTransfer {
+    /// Decode the `Transfer` event of an ERC-20 token, whose amount is in the
+    /// data, or of an ERC-721 token, whose token id is the third indexed
+    /// topic. Returns `None` for the other logs.
+    pub fn decode(log: &LocalizedLogEntry) -> Option<TokenTransfer> {
+        let topics = &log.entry.topics;
+        if topics.first() != Some(&*TRANSFER_EVENT_TOPIC) {
+            return None;
+        }
+        let (value, token_id) = match (topics.len(), log.entry.data.len()) {
+            (3, 32) => (Some(U256::from(&log.entry.data[..])), None),
+            (4, 0) => (None, Some(U256::from(topics[3].as_bytes()))),
+            _ => return None,
+        };
+        Some(TokenTransfer {
+            token: log.entry.address.into(),
+            from: topic_to_address(&topics[1])?.into(),
+            to: topic_to_address(&topics[2])?.into(),
+            value,
+            token_id,
+            transaction_hash: log.transaction_hash,
+            block_hash: log.block_hash,
+            epoch_number: log.epoch_number.into(),
+            log_index: (log.log_index as u64).into(),
+        })
+    }
+}
+
+/// The address in an indexed topic, which is left padded with zeros.
+fn topic_to_address(topic: &H256) -> Option<Address> {
+    if topic[..12].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(Address::from_slice(&topic[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::log_entry::LogEntry;
+
+    fn transfer_log(topics: Vec<H256>, data: Vec<u8>) -> LocalizedLogEntry {
+        LocalizedLogEntry {
+            entry: LogEntry {
+                address: Address::from_low_u64_be(1),
+                topics,
+                data,
+            },
+            block_hash: H256::from_low_u64_be(2),
+            epoch_number: 3,
+            transaction_hash: H256::from_low_u64_be(4),
+            transaction_index: 0,
+            log_index: 5,
+            transaction_log_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_token_transfer() {
+        let from = H256::from_low_u64_be(6);
+        let to = H256::from_low_u64_be(7);
+        let amount = H256::from_low_u64_be(8);
+
+        let erc20 = TokenTransfer::decode(&transfer_log(
+            vec![*TRANSFER_EVENT_TOPIC, from, to],
+            amount.as_bytes().to_vec(),
+        ))
+        .unwrap();
+        assert_eq!(erc20.token, Address::from_low_u64_be(1).into());
+        assert_eq!(erc20.from, Address::from_low_u64_be(6).into());
+        assert_eq!(erc20.to, Address::from_low_u64_be(7).into());
+        assert_eq!(erc20.value, Some(8.into()));
+        assert_eq!(erc20.token_id, None);
+        assert_eq!(erc20.epoch_number, 3.into());
+        assert_eq!(erc20.log_index, 5.into());
+
+        let erc721 = TokenTransfer::decode(&transfer_log(
+            vec![*TRANSFER_EVENT_TOPIC, from, to, amount],
+            vec![],
+        ))
+        .unwrap();
+        assert_eq!(erc721.value, None);
+        assert_eq!(erc721.token_id, Some(8.into()));
+
+        // Other events, malformed transfers and the topics which are not
+        // addresses are ignored.
+        for (topics, data) in vec![
+            (vec![H256::from_low_u64_be(9), from, to], amount.0.to_vec()),
+            (vec![*TRANSFER_EVENT_TOPIC, from, to], vec![]),
+            (
+                vec![*TRANSFER_EVENT_TOPIC, from, to, amount],
+                amount.0.to_vec(),
+            ),
+            (
+                vec![*TRANSFER_EVENT_TOPIC, H256::repeat_byte(1), to],
+                amount.0.to_vec(),
+            ),
+        ] {
+            assert!(
+                TokenTransfer::decode(&transfer_log(topics, data)).is_none()
+            );
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use super::EpochNumber;
+use super::{EpochNumber, RpcAddress};
 use cfx_types::{H160, H256, U64};
 use jsonrpc_core::Error as RpcError;
 use primitives::filter::Filter as PrimitiveFilter;
@@ -87,7 +87,7 @@ pub struct Filter {
     ///
     /// If None, match all.
     /// If specified, log must be produced by one of these addresses.
-    pub address: Option<VariadicValue<H160>>,
+    pub address: Option<VariadicValue<RpcAddress>>,
 
     /// Search topics.
     ///
@@ -159,7 +159,11 @@ impl Filter {
         };
 
         // address, limit
-        let address = self.address.and_then(Into::into);
+        let address = self.address.and_then(Into::into).map(
+            |addresses: Vec<RpcAddress>| {
+                addresses.into_iter().map(H160::from).collect()
+            },
+        );
         let limit = self.limit.map(|x| x.as_u64() as usize);
 
         Ok(PrimitiveFilter {
@@ -262,8 +266,8 @@ mod tests {
                 H256::from_str("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347").unwrap()
             ]),
             address: Some(VariadicValue::Multiple(vec![
-                Address::from_str("0000000000000000000000000000000000000000").unwrap(),
-                Address::from_str("0000000000000000000000000000000000000001").unwrap()
+                Address::from_str("0000000000000000000000000000000000000000").unwrap().into(),
+                Address::from_str("0000000000000000000000000000000000000001").unwrap().into()
             ])),
             topics: Some(vec![
                 VariadicValue::Single(H256::from_str("d397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5").unwrap()),
@@ -283,7 +287,7 @@ mod tests {
              \"fromEpoch\":\"0x3e8\",\
              \"toEpoch\":\"latest_state\",\
              \"blockHashes\":[\"0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\",\"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347\"],\
-             \"address\":[\"0x0000000000000000000000000000000000000000\",\"0x0000000000000000000000000000000000000001\"],\
+             \"address\":[\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa6f0vrcsw\",\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeckkywsu0\"],\
              \"topics\":[\
                 \"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\
                 [\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\"]\
@@ -314,7 +318,7 @@ mod tests {
              \"fromEpoch\":\"0x3e8\",\
              \"toEpoch\":\"latest_state\",\
              \"blockHashes\":[\"0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\",\"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347\"],\
-             \"address\":[\"0x0000000000000000000000000000000000000000\",\"0x0000000000000000000000000000000000000001\"],\
+             \"address\":[\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa6f0vrcsw\",\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeckkywsu0\"],\
              \"topics\":[\
                 \"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\
                 [\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\"]\
@@ -330,8 +334,8 @@ mod tests {
                 H256::from_str("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347").unwrap()
             ]),
             address: Some(VariadicValue::Multiple(vec![
-                H160::from_str("0000000000000000000000000000000000000000").unwrap(),
-                H160::from_str("0000000000000000000000000000000000000001").unwrap()
+                H160::from_str("0000000000000000000000000000000000000000").unwrap().into(),
+                H160::from_str("0000000000000000000000000000000000000001").unwrap().into()
             ])),
             topics: Some(vec![
                 VariadicValue::Single(H256::from_str("d397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5").unwrap()),
@@ -358,8 +362,8 @@ mod tests {
                 H256::from_str("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347").unwrap()
             ]),
             address: Some(VariadicValue::Multiple(vec![
-                H160::from_str("0000000000000000000000000000000000000000").unwrap(),
-                H160::from_str("0000000000000000000000000000000000000001").unwrap()
+                H160::from_str("0000000000000000000000000000000000000000").unwrap().into(),
+                H160::from_str("0000000000000000000000000000000000000001").unwrap().into()
             ])),
             topics: Some(vec![
                 VariadicValue::Single(H256::from_str("d397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5").unwrap()),
@@ -0,0 +1,75 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::U64;
+use cfxcore::statistics::MemoryUsage as PrimitiveMemoryUsage;
+
+/// The heap usage of the components of a node in bytes.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// The unix timestamp of the measurement.
+    pub timestamp: U64,
+    pub secret_store: U64,
+    /// The caches of the db manager, which are also included in
+    /// `dataManager`.
+    pub data_manager_db_cache: U64,
+    pub storage_manager: U64,
+    pub data_manager: U64,
+    pub tx_pool: U64,
+    pub consensus_graph: U64,
+    pub sync_graph: U64,
+    pub sync_service: U64,
+    /// The milliseconds taken by the measurement.
+    pub elapsed: U64,
+}
+
+impl From<PrimitiveMemoryUsage> for MemoryUsage {
+    fn from(usage: PrimitiveMemoryUsage) -> Self {
+        MemoryUsage {
+            timestamp: usage.timestamp.into(),
+            secret_store: usage.secret_store.into(),
+            data_manager_db_cache: usage.data_manager_db_cache.into(),
+            storage_manager: usage.storage_manager.into(),
+            data_manager: usage.data_manager.into(),
+            tx_pool: usage.tx_pool.into(),
+            consensus_graph: usage.consensus_graph.into(),
+            sync_graph: usage.sync_graph.into(),
+            sync_service: usage.sync_service.into(),
+            elapsed: (usage.elapsed.as_millis() as u64).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryUsage;
+    use cfxcore::statistics::MemoryUsage as PrimitiveMemoryUsage;
+    use std::time::Duration;
+
+    #[test]
+    fn test_memory_usage() {
+        let usage: MemoryUsage = PrimitiveMemoryUsage {
+            timestamp: 1600000000,
+            secret_store: 1,
+            data_manager_db_cache: 2,
+            storage_manager: 3,
+            data_manager: 4,
+            tx_pool: 5,
+            consensus_graph: 6,
+            sync_graph: 7,
+            sync_service: 8,
+            elapsed: Duration::from_micros(12_345),
+        }
+        .into();
+        assert_eq!(
+            serde_json::to_string(&usage).unwrap(),
+            "{\"timestamp\":\"0x5f5e1000\",\"secretStore\":\"0x1\",\
+             \"dataManagerDbCache\":\"0x2\",\"storageManager\":\"0x3\",\
+             \"dataManager\":\"0x4\",\"txPool\":\"0x5\",\
+             \"consensusGraph\":\"0x6\",\"syncGraph\":\"0x7\",\
+             \"syncService\":\"0x8\",\"elapsed\":\"0xc\"}"
+        );
+    }
+}
@@ -28,6 +28,8 @@ pub enum Origin {
     Rpc(String),
     /// TCP server (includes peer address)
     Tcp(SocketAddr),
+    /// IPC server (includes session id)
+    Ipc(H256),
     /// WS server
     Ws {
         /// Session id
@@ -53,6 +55,7 @@ impl fmt::Display for Origin {
         match *self {
             Origin::Rpc(ref origin) => write!(f, "{} via RPC", origin),
             Origin::Tcp(ref address) => write!(f, "TCP (address: {})", address),
+            Origin::Ipc(ref session) => write!(f, "IPC (session: {})", session),
             Origin::Ws { ref session } => {
                 write!(f, "WebSocket (session: {})", session)
             }
@@ -87,6 +90,7 @@ mod tests {
             session: H256::from_low_u64_be(5),
         };
         let o7 = Origin::CApi;
+        let o8 = Origin::Ipc(H256::from_low_u64_be(1));
         let res1 = serde_json::to_string(&o1).unwrap();
         let res2 = serde_json::to_string(&o2).unwrap();
         let res3 = serde_json::to_string(&o3).unwrap();
@@ -94,6 +98,7 @@ mod tests {
         let res5 = serde_json::to_string(&o5).unwrap();
         let res6 = serde_json::to_string(&o6).unwrap();
         let res7 = serde_json::to_string(&o7).unwrap();
+        let res8 = serde_json::to_string(&o8).unwrap();
         assert_eq!(res1, r#""unknown""#);
         assert_eq!(res2, r#"{"rpc":"test service"}"#);
         assert_eq!(res3, r#"{"tcp":"127.0.0.1:8080"}"#);
@@ -103,6 +108,10 @@ mod tests {
         assert_eq!(res6,
         r#"{"ws":{"session":"0x0000000000000000000000000000000000000000000000000000000000000005"}}"#);
         assert_eq!(res7, r#""c-api""#);
+        assert_eq!(
+            res8,
+            r#"{"ipc":"0x0000000000000000000000000000000000000000000000000000000000000001"}"#
+        );
     }
     #[test]
     fn test_origin_deserialize() {
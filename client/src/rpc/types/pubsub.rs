@@ -20,7 +20,7 @@
 
 //! Pub-Sub types.
 
-use super::{Filter, Header, Log};
+use super::{Filter, Header, Log, Receipt, RpcAddress};
 use cfx_types::{H256, U256};
 use serde::{de::Error, Deserialize, Deserializer, Serialize};
 use serde_json::{from_value, Value};
@@ -51,6 +51,50 @@ pub enum Result {
     /// Chain reorg
     #[serde(rename_all = "camelCase")]
     ChainReorg { revert_to: U256 },
+
+    /// Balance change of a watched account
+    #[serde(rename_all = "camelCase")]
+    BalanceChange {
+        address: RpcAddress,
+        epoch_number: U256,
+        balance: U256,
+    },
+
+    /// Transaction sent from or to a watched account
+    #[serde(rename_all = "camelCase")]
+    AccountTransaction {
+        address: RpcAddress,
+        epoch_number: U256,
+        block_hash: H256,
+        transaction_hash: H256,
+        from: RpcAddress,
+        to: Option<RpcAddress>,
+        value: U256,
+    },
+
+    /// Value transferred by an internal call of an executed transaction
+    #[serde(rename_all = "camelCase")]
+    InternalTransfer {
+        epoch_number: U256,
+        block_hash: H256,
+        transaction_hash: H256,
+        from: RpcAddress,
+        to: RpcAddress,
+        value: U256,
+    },
+
+    /// Receipt of a subscribed transaction
+    Receipt(Receipt),
+
+    /// A subscribed transaction whose block is confirmed with a risk below
+    /// the threshold
+    #[serde(rename_all = "camelCase")]
+    TransactionConfirmed {
+        transaction_hash: H256,
+        block_hash: H256,
+        epoch_number: U256,
+        confirmation_risk: U256,
+    },
 }
 
 /// Subscription kind.
@@ -68,6 +112,12 @@ pub enum Kind {
     Syncing,
     /// Epoch
     Epochs,
+    /// Watched accounts subscription, served by light nodes.
+    Watch,
+    /// Internal transfers subscription, served by full nodes.
+    InternalTransfers,
+    /// Transaction receipt subscription, served by full nodes.
+    TransactionReceipt,
 }
 
 /// Subscription kind.
@@ -77,6 +127,27 @@ pub enum Params {
     None,
     /// Log parameters.
     Logs(Filter),
+    /// Watch parameters, also the addresses filtering internal transfers.
+    Watch(WatchParams),
+    /// Transaction receipt parameters.
+    Receipt(ReceiptParams),
+}
+
+/// The accounts watched by a `watch` subscription.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchParams {
+    pub addresses: Vec<RpcAddress>,
+}
+
+/// The transaction of a `transactionReceipt` subscription, and the
+/// confirmation risk below which its block is considered confirmed, in the
+/// scale of `cfx_getConfirmationRiskByHash`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct ReceiptParams {
+    pub transaction_hash: H256,
+    pub confirmation_risk: Option<U256>,
 }
 
 impl Default for Params {
@@ -94,6 +165,18 @@ impl<'a> Deserialize<'a> for Params {
             return Ok(Params::None);
         }
 
+        if v.get("transactionHash").is_some() {
+            return from_value(v).map(Params::Receipt).map_err(|e| {
+                D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e))
+            });
+        }
+
+        if v.get("addresses").is_some() {
+            return from_value(v).map(Params::Watch).map_err(|e| {
+                D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e))
+            });
+        }
+
         from_value(v).map(Params::Logs).map_err(|e| {
             D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e))
         })
@@ -103,7 +186,10 @@ impl<'a> Deserialize<'a> for Params {
 #[cfg(test)]
 mod tests {
     use super::Result;
-    use crate::rpc::types::{pubsub::Params, Header, Log};
+    use crate::rpc::types::{
+        pubsub::{Params, ReceiptParams, WatchParams},
+        Header, Log, RpcAddress,
+    };
     use cfx_types::{H160, H256, U256};
 
     #[test]
@@ -112,7 +198,7 @@ mod tests {
             hash: H256::default(),
             parent_hash: H256::default(),
             height: 0.into(),
-            miner: H160::default(),
+            miner: RpcAddress::default(),
             deferred_state_root: Default::default(),
             deferred_receipts_root: H256::default(),
             deferred_logs_bloom_hash: cfx_types::KECCAK_EMPTY_BLOOM.into(),
@@ -128,7 +214,7 @@ mod tests {
             nonce: 0.into(),
         };
         let log = Log {
-            address: H160([0xff; 20]),
+            address: H160([0xff; 20]).into(),
             topics: vec![H256([0xff; 32])],
             data: vec![].into(),
             block_hash: Some(H256([0xff; 32])),
@@ -153,16 +239,16 @@ mod tests {
         let se3 = serde_json::to_string(&r3).unwrap();
         let se4 = serde_json::to_string(&r4).unwrap();
         let se5 = serde_json::to_string(&r5).unwrap();
-        assert_eq!(se1,"{\"hash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"height\":\"0x0\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"deferredStateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredReceiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredLogsBloomHash\":\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"blame\":0,\"transactionsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"epochNumber\":null,\"gasLimit\":\"0x0\",\"timestamp\":\"0x0\",\"difficulty\":\"0x0\",\"powQuality\":null,\"refereeHashes\":[],\"adaptive\":false,\"nonce\":\"0x0\"}");
-        assert_eq!(se2,"{\"address\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"topics\":[\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\"],\"data\":\"0x\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":\"0x1\",\"transactionHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"transactionIndex\":\"0x0\",\"logIndex\":\"0x1\",\"transactionLogIndex\":\"0x1\"}");
+        assert_eq!(se1,"{\"hash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"height\":\"0x0\",\"miner\":\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa6f0vrcsw\",\"deferredStateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredReceiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredLogsBloomHash\":\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"blame\":0,\"transactionsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"epochNumber\":null,\"gasLimit\":\"0x0\",\"timestamp\":\"0x0\",\"difficulty\":\"0x0\",\"powQuality\":null,\"refereeHashes\":[],\"adaptive\":false,\"nonce\":\"0x0\"}");
+        assert_eq!(se2,"{\"address\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"topics\":[\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\"],\"data\":\"0x\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":\"0x1\",\"transactionHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"transactionIndex\":\"0x0\",\"logIndex\":\"0x1\",\"transactionLogIndex\":\"0x1\"}");
         assert_eq!(se3,"\"0x0000000000000000000000000000000000000000000000000000000000000000\"");
         assert_eq!(se4, "{\"epochNumber\":\"0x1\",\"epochHashesOrdered\":[]}");
         assert_eq!(se5, "{\"revertTo\":\"0x1\"}");
     }
     #[test]
     fn test_result_deserialize() {
-        let se1 = "{\"hash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"height\":\"0x0\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"deferredStateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredReceiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredLogsBloomHash\":\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"blame\":0,\"transactionsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"epochNumber\":null,\"gasLimit\":\"0x0\",\"timestamp\":\"0x0\",\"difficulty\":\"0x0\",\"powQuality\":null,\"refereeHashes\":[],\"adaptive\":false,\"nonce\":\"0x0\"}";
-        let se2 ="{\"address\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"topics\":[\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\"],\"data\":\"0x\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":\"0x1\",\"transactionHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"transactionIndex\":\"0x0\",\"logIndex\":\"0x1\",\"transactionLogIndex\":\"0x1\"}";
+        let se1 = "{\"hash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"height\":\"0x0\",\"miner\":\"cfxtest:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa6f0vrcsw\",\"deferredStateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredReceiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"deferredLogsBloomHash\":\"0xd397b3b043d87fcd6fad1291ff0bfd16401c274896d8c63a923727f077b8e0b5\",\"blame\":0,\"transactionsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"epochNumber\":null,\"gasLimit\":\"0x0\",\"timestamp\":\"0x0\",\"difficulty\":\"0x0\",\"powQuality\":null,\"refereeHashes\":[],\"adaptive\":false,\"nonce\":\"0x0\"}";
+        let se2 ="{\"address\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"topics\":[\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\"],\"data\":\"0x\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":\"0x1\",\"transactionHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"transactionIndex\":\"0x0\",\"logIndex\":\"0x1\",\"transactionLogIndex\":\"0x1\"}";
         let se3 = "\"0x0000000000000000000000000000000000000000000000000000000000000000\"";
         let se4 = "{\"epochNumber\":\"0x1\",\"epochHashesOrdered\":[]}";
         let se5 = "{\"revertTo\":\"0x1\"}";
@@ -175,7 +261,7 @@ mod tests {
             hash: H256::default(),
             parent_hash: H256::default(),
             height: 0.into(),
-            miner: H160::default(),
+            miner: RpcAddress::default(),
             deferred_state_root: Default::default(),
             deferred_receipts_root: H256::default(),
             deferred_logs_bloom_hash: cfx_types::KECCAK_EMPTY_BLOOM.into(),
@@ -191,7 +277,7 @@ mod tests {
             nonce: 0.into(),
         };
         let log = Log {
-            address: H160([0xff; 20]),
+            address: H160([0xff; 20]).into(),
             topics: vec![H256([0xff; 32])],
             data: vec![].into(),
             block_hash: Some(H256([0xff; 32])),
@@ -222,6 +308,42 @@ mod tests {
         let default = Params::default();
         assert_eq!(default, Params::None);
     }
+    #[test]
+    fn test_params_deserialize() {
+        let watch = serde_json::from_str::<Params>(
+            r#"{"addresses":["cfxtest:ad99999999999999999999999999999996bw9gccab"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            watch,
+            Params::Watch(WatchParams {
+                addresses: vec![H160([0xff; 20]).into()],
+            })
+        );
+        assert!(
+            serde_json::from_str::<Params>(r#"{"addresses":"0x1"}"#).is_err()
+        );
+        assert!(matches!(
+            serde_json::from_str::<Params>(r#"{}"#).unwrap(),
+            Params::Logs(_)
+        ));
+
+        let receipt = serde_json::from_str::<Params>(
+            r#"{"transactionHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","confirmationRisk":"0x10"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            receipt,
+            Params::Receipt(ReceiptParams {
+                transaction_hash: H256([0xff; 32]),
+                confirmation_risk: Some(16.into()),
+            })
+        );
+        assert!(serde_json::from_str::<Params>(
+            r#"{"transactionHash":"0x1","limit":"0x1"}"#
+        )
+        .is_err());
+    }
 }
 
 //#[cfg(test)]
@@ -2,8 +2,8 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::types::Log;
-use cfx_types::{Address, Bloom, H256, U256, U64};
+use crate::rpc::types::{Log, RpcAddress};
+use cfx_types::{Bloom, H256, U256, U64};
 use cfxcore::{executive::contract_address, vm::CreateContractAddress};
 use primitives::{
     receipt::Receipt as PrimitiveReceipt, transaction::Action,
@@ -11,7 +11,7 @@ use primitives::{
 };
 use serde_derive::Serialize;
 
-#[derive(Debug, Serialize, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Receipt {
     /// Transaction hash.
@@ -23,16 +23,16 @@ pub struct Receipt {
     /// epoch number where this transaction was in.
     pub epoch_number: Option<U64>,
     /// address of the sender.
-    pub from: Address,
+    pub from: RpcAddress,
     /// address of the receiver, null when it's a contract creation
     /// transaction.
-    pub to: Option<Address>,
+    pub to: Option<RpcAddress>,
     /// The gas used in the execution of the transaction.
     pub gas_used: U256,
     /// The gas fee charged in the execution of the transaction.
     pub gas_fee: U256,
     /// Address of contracts created during execution of transaction.
-    pub contract_created: Option<Address>,
+    pub contract_created: Option<RpcAddress>,
     /// Array of log objects, which this transaction generated.
     pub logs: Vec<Log>,
     /// Bloom filter for light clients to quickly retrieve related logs.
@@ -66,13 +66,13 @@ impl Receipt {
             block_hash: transaction_index.block_hash.into(),
             gas_used: (receipt.accumulated_gas_used - prior_gas_used).into(),
             gas_fee: receipt.gas_fee.into(),
-            from: transaction.sender,
+            from: transaction.sender.into(),
             to: match transaction.action {
                 Action::Create => None,
-                Action::Call(ref address) => Some(address.clone()),
+                Action::Call(ref address) => Some(address.clone().into()),
             },
             outcome_status: U64::from(receipt.outcome_status),
-            contract_created: address,
+            contract_created: address.map(Into::into),
             logs: receipt.logs.into_iter().map(Log::from).collect(),
             logs_bloom: receipt.log_bloom,
             state_root: maybe_state_root
@@ -100,7 +100,7 @@ mod tests {
             index: U64::one(),
             block_hash: H256([0xff; 32]),
             epoch_number: None,
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             gas_used: U256::one(),
             gas_fee: U256::one(),
@@ -111,11 +111,11 @@ mod tests {
             outcome_status: U64::one(),
         };
         let serialize = serde_json::to_string(&receipt).unwrap();
-        assert_eq!(serialize,"{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x1\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"to\":null,\"gasUsed\":\"0x1\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"outcomeStatus\":\"0x1\"}");
+        assert_eq!(serialize,"{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x1\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"to\":null,\"gasUsed\":\"0x1\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"outcomeStatus\":\"0x1\"}");
     }
     #[test]
     fn test_receipt_deserialize() {
-        let serialize = "{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x1\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"to\":null,\"gasUsed\":\"0x1\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"outcomeStatus\":\"0x1\"}";
+        let serialize = "{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x1\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"to\":null,\"gasUsed\":\"0x1\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"outcomeStatus\":\"0x1\"}";
         let deserialize: Receipt = serde_json::from_str(serialize).unwrap();
         let bloom: [u8; 256] = [0; 256];
         let receipt = Receipt {
@@ -123,7 +123,7 @@ mod tests {
             index: U64::one(),
             block_hash: H256([0xff; 32]),
             epoch_number: None,
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             gas_used: U256::one(),
             gas_fee: U256::one(),
@@ -177,7 +177,7 @@ mod tests {
         );
         let receipt_info = serde_json::to_string(&receipt).unwrap();
         assert_eq!(receipt_info,
-        r#"{"transactionHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","index":"0x0","blockHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","epochNumber":null,"from":"0xffffffffffffffffffffffffffffffffffffffff","to":null,"gasUsed":"0x0","gasFee":"0x1","contractCreated":"0x8c2152e51c66962b151a4262b950c1a14bbcdee5","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","stateRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","outcomeStatus":"0x0"}"#);
+        r#"{"transactionHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","index":"0x0","blockHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","epochNumber":null,"from":"cfxtest:ad99999999999999999999999999999996bw9gccab","to":null,"gasUsed":"0x0","gasFee":"0x1","contractCreated":"cfxtest:acgccy1fdvxkpm2zdkbgfsmu2guy1tg86ytzn65kcc","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","stateRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","outcomeStatus":"0x0"}"#);
     }
     #[test]
     fn test_receipt_new_one() {
@@ -221,6 +221,6 @@ mod tests {
         );
         let receipt_info = serde_json::to_string(&receipt).unwrap();
         assert_eq!(receipt_info,
-                   "{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x0\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"to\":null,\"gasUsed\":\"0x0\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"outcomeStatus\":\"0x1\"}");
+                   "{\"transactionHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"index\":\"0x0\",\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"epochNumber\":null,\"from\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"to\":null,\"gasUsed\":\"0x0\",\"gasFee\":\"0x1\",\"contractCreated\":null,\"logs\":[],\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"stateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"outcomeStatus\":\"0x1\"}");
     }
 }
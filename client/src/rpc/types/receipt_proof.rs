@@ -0,0 +1,32 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::Bytes;
+use cfx_types::{H256, U64};
+
+/// The proof of the inclusion of the receipt of a transaction under the
+/// receipts root of its epoch.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptProof {
+    pub transaction_hash: H256,
+    pub block_hash: H256,
+    pub epoch_number: U64,
+    /// The hash of the pivot block of the epoch.
+    pub epoch_hash: H256,
+    /// The receipts root of the epoch.
+    pub receipts_root: H256,
+    /// The RLP encoded pivot block header committing the receipts root. It's
+    /// the pivot block `DEFERRED_STATE_EPOCH_COUNT` (5) epochs later, or the
+    /// first pivot block after it which blames it.
+    pub witness_header: Bytes,
+    /// The receipts roots committed by the witness header, from its height
+    /// backwards. The `deferredReceiptsRoot` of the witness header is the only
+    /// root if it blames no block, or else the root of the blame vector of
+    /// the roots. The receipts root of the epoch is at the index of the number
+    /// of the witness header minus the epoch number minus 5.
+    pub witness_receipts_roots: Vec<H256>,
+    /// The RLP encoded `ReceiptProof`, which contains the receipt.
+    pub proof: Bytes,
+}
@@ -1,3 +1,4 @@
+use crate::rpc::types::RpcAddress;
 use cfx_types::{H160, H256, U256};
 use cfxcore::block_data_manager::BlockRewardResult;
 
@@ -5,7 +6,7 @@ use cfxcore::block_data_manager::BlockRewardResult;
 #[serde(rename_all = "camelCase")]
 pub struct RewardInfo {
     block_hash: H256,
-    author: H160,
+    author: RpcAddress,
     total_reward: U256,
     base_reward: U256,
     tx_fee: U256,
@@ -35,21 +36,21 @@ mod tests {
     fn test_reward_info_serialize() {
         let reward_info = RewardInfo {
             block_hash: H256([0xff; 32]),
-            author: H160([0xff; 20]),
+            author: H160([0xff; 20]).into(),
             total_reward: U256::one(),
             base_reward: U256::one(),
             tx_fee: U256::one(),
         };
         let serialize = serde_json::to_string(&reward_info).unwrap();
-        assert_eq!(serialize,"{\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"author\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"totalReward\":\"0x1\",\"baseReward\":\"0x1\",\"txFee\":\"0x1\"}");
+        assert_eq!(serialize,"{\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"author\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"totalReward\":\"0x1\",\"baseReward\":\"0x1\",\"txFee\":\"0x1\"}");
     }
     #[test]
     fn test_reward_info_deserialize() {
-        let serialize = "{\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"author\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"totalReward\":\"0x1\",\"baseReward\":\"0x1\",\"txFee\":\"0x1\"}";
+        let serialize = "{\"blockHash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"author\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"totalReward\":\"0x1\",\"baseReward\":\"0x1\",\"txFee\":\"0x1\"}";
         let deserialize: RewardInfo = serde_json::from_str(serialize).unwrap();
         let reward_info = RewardInfo {
             block_hash: H256([0xff; 32]),
-            author: H160([0xff; 20]),
+            author: H160([0xff; 20]).into(),
             total_reward: U256::one(),
             base_reward: U256::one(),
             tx_fee: U256::one(),
@@ -62,6 +63,6 @@ mod tests {
         let info = RewardInfo::new(H256([0xff; 32]), H160([0xff; 20]), result);
         let reward_info = serde_json::to_string(&info).unwrap();
         assert_eq!(reward_info,
-        r#"{"blockHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","author":"0xffffffffffffffffffffffffffffffffffffffff","totalReward":"0x0","baseReward":"0x0","txFee":"0x0"}"#);
+        r#"{"blockHash":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","author":"cfxtest:ad99999999999999999999999999999996bw9gccab","totalReward":"0x0","baseReward":"0x0","txFee":"0x0"}"#);
     }
 }
@@ -0,0 +1,33 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::{Account, RpcAddress};
+use cfx_types::{H256, U64};
+
+/// The accounts read from the state of one epoch, in the requested order.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBatch {
+    pub epoch_number: U64,
+    pub epoch_hash: H256,
+    pub accounts: Vec<Account>,
+}
+
+/// A storage entry to read.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct StorageSlot {
+    pub address: RpcAddress,
+    pub position: H256,
+}
+
+/// The storage values read from the state of one epoch, in the requested
+/// order. The value is null if the entry doesn't exist.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageBatch {
+    pub epoch_number: U64,
+    pub epoch_hash: H256,
+    pub values: Vec<Option<H256>>,
+}
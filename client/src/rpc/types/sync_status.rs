@@ -0,0 +1,65 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H512, U64};
+use cfxcore::sync::SyncStatus as PrimitiveSyncStatus;
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSyncContribution {
+    pub node_id: H512,
+    pub headers: U64,
+    pub blocks: U64,
+    pub chunks: U64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The progress of synchronization.
+pub struct SyncStatus {
+    pub phase: String,
+    pub catch_up_mode: bool,
+    pub best_epoch: U64,
+    /// The median epoch of peers, which is the target to catch up with.
+    pub target_epoch: Option<U64>,
+    pub headers_received: U64,
+    pub blocks_received: U64,
+    pub state_chunks_restored: Option<U64>,
+    pub state_chunks_total: Option<U64>,
+    /// The estimated seconds to finish the current phase.
+    pub eta_seconds: Option<U64>,
+    pub peers: Vec<PeerSyncContribution>,
+}
+
+impl SyncStatus {
+    pub fn new(status: PrimitiveSyncStatus) -> Self {
+        let peers = status
+            .peers
+            .into_iter()
+            .map(|(node_id, contribution)| PeerSyncContribution {
+                node_id,
+                headers: contribution.headers.into(),
+                blocks: contribution.blocks.into(),
+                chunks: contribution.chunks.into(),
+            })
+            .collect();
+
+        SyncStatus {
+            phase: status.phase.into(),
+            catch_up_mode: status.catch_up_mode,
+            best_epoch: status.best_epoch.into(),
+            target_epoch: status.target_epoch.map(Into::into),
+            headers_received: status.headers_received.into(),
+            blocks_received: status.blocks_received.into(),
+            state_chunks_restored: status
+                .state_chunks
+                .map(|(restored, _)| restored.into()),
+            state_chunks_total: status
+                .state_chunks
+                .map(|(_, total)| total.into()),
+            eta_seconds: status.eta.map(|eta| eta.as_secs().into()),
+            peers,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::RpcAddress;
+use cfx_types::U64;
+use cfxcore::trace::CollateralTrace as PrimitiveCollateralTrace;
+use primitives::receipt::StorageChange as PrimitiveStorageChange;
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageChange {
+    /// The storage owner.
+    pub address: RpcAddress,
+    /// The number of bytes.
+    pub amount: U64,
+}
+
+impl From<PrimitiveStorageChange> for StorageChange {
+    fn from(change: PrimitiveStorageChange) -> Self {
+        StorageChange {
+            address: change.address.into(),
+            amount: change.amount.into(),
+        }
+    }
+}
+
+/// The storage collateral charged and refunded by a frame, not including
+/// its sub-calls.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CollateralTrace {
+    /// The address called or created by the frame.
+    pub address: RpcAddress,
+    /// The depth of the frame, which is 0 for the transaction itself.
+    pub depth: U64,
+    pub storage_collateralized: Vec<StorageChange>,
+    pub storage_released: Vec<StorageChange>,
+}
+
+impl From<PrimitiveCollateralTrace> for CollateralTrace {
+    fn from(trace: PrimitiveCollateralTrace) -> Self {
+        CollateralTrace {
+            address: trace.address.into(),
+            depth: trace.depth.into(),
+            storage_collateralized: trace
+                .storage_collateralized
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            storage_released: trace
+                .storage_released
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
@@ -2,10 +2,10 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::types::{receipt::Receipt, Bytes};
+use crate::rpc::types::{receipt::Receipt, Bytes, RpcAddress};
 use cfx_types::{H160, H256, U256, U64};
-use cfxcore_accounts::AccountProvider;
-use cfxkey::{Error, Password};
+use cfxcore_accounts::TransactionSigner;
+use cfxkey::{sign, Error, KeyPair, Password, Secret};
 use primitives::{
     transaction::Action, SignedTransaction,
     Transaction as PrimitiveTransaction, TransactionIndex,
@@ -20,12 +20,12 @@ pub struct Transaction {
     pub nonce: U256,
     pub block_hash: Option<H256>,
     pub transaction_index: Option<U64>,
-    pub from: H160,
-    pub to: Option<H160>,
+    pub from: RpcAddress,
+    pub to: Option<RpcAddress>,
     pub value: U256,
     pub gas_price: U256,
     pub gas: U256,
-    pub contract_created: Option<H160>,
+    pub contract_created: Option<RpcAddress>,
     pub data: Bytes,
     pub storage_limit: U256,
     pub epoch_height: U256,
@@ -64,7 +64,7 @@ impl Transaction {
                 block_hash = Some(receipt.block_hash);
                 transaction_index = Some(receipt.index.into());
                 if let Some(ref address) = receipt.contract_created {
-                    contract_created = Some(address.clone().into());
+                    contract_created = Some(*address);
                 }
                 status = Some(receipt.outcome_status);
             }
@@ -126,8 +126,8 @@ impl Transaction {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendTxRequest {
-    pub from: H160,
-    pub to: Option<H160>,
+    pub from: RpcAddress,
+    pub to: Option<RpcAddress>,
     pub gas: U256,
     pub gas_price: U256,
     pub value: U256,
@@ -141,7 +141,7 @@ pub struct SendTxRequest {
 impl SendTxRequest {
     pub fn sign_with(
         self, best_epoch_height: u64, chain_id: u32, password: Option<String>,
-        accounts: Arc<AccountProvider>,
+        signer: Arc<dyn TransactionSigner>,
     ) -> Result<TransactionWithSignature, String>
     {
         let tx = PrimitiveTransaction {
@@ -166,12 +166,61 @@ impl SendTxRequest {
         };
 
         let password = password.map(Password::from);
-        let sig = accounts
+        let sig = signer
             .sign(self.from.into(), password, tx.hash())
             .map_err(|e| format!("failed to sign transaction: {:?}", e))?;
 
         Ok(tx.with_signature(sig))
     }
+
+    /// The unsigned transaction to sign offline. The nonce, the epoch height
+    /// and the chain id must be provided, because there is no node to fill
+    /// them in.
+    pub fn into_offline_transaction(
+        self,
+    ) -> Result<PrimitiveTransaction, String> {
+        let nonce = self.nonce.ok_or("nonce is required to sign offline")?;
+        let epoch_height = self
+            .epoch_height
+            .ok_or("epochHeight is required to sign offline")?;
+        let chain_id =
+            self.chain_id.ok_or("chainId is required to sign offline")?;
+        Ok(PrimitiveTransaction {
+            nonce: nonce.into(),
+            gas_price: self.gas_price.into(),
+            gas: self.gas.into(),
+            action: match self.to {
+                None => Action::Create,
+                Some(address) => Action::Call(address.into()),
+            },
+            value: self.value.into(),
+            storage_limit: self
+                .storage_limit
+                .unwrap_or(std::u64::MAX.into())
+                .as_usize() as u64,
+            epoch_height: epoch_height.as_usize() as u64,
+            chain_id: chain_id.as_u32(),
+            data: self.data.unwrap_or(Bytes::new(vec![])).into(),
+        })
+    }
+
+    /// Sign the transaction offline with the `secret` of the sender.
+    pub fn sign_offline(
+        self, secret: &Secret,
+    ) -> Result<TransactionWithSignature, String> {
+        let key_pair = KeyPair::from_secret(secret.clone())
+            .map_err(|e| format!("invalid secret: {}", e))?;
+        if key_pair.address() != H160::from(self.from) {
+            return Err(format!(
+                "the secret does not belong to the sender {}",
+                self.from
+            ));
+        }
+        let tx = self.into_offline_transaction()?;
+        let sig = sign(secret, &tx.hash())
+            .map_err(|e| format!("failed to sign transaction: {:?}", e))?;
+        Ok(tx.with_signature(sig))
+    }
 }
 
 #[derive(Default, Serialize)]
@@ -193,15 +242,177 @@ pub struct TxPoolPendingInfo {
     pub max_nonce: U256,
 }
 
+/// Why the transactions of a sender are pending, returned by
+/// `cfx_checkSenderStatus`.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SenderStatus {
+    /// The nonce in the latest executed state, i.e. the nonce of the next
+    /// transaction to be executed.
+    pub nonce: U256,
+    /// The balance in the latest executed state.
+    pub balance: U256,
+    /// The number of transactions of the sender in the pool.
+    pub pending_count: usize,
+    pub lowest_pooled_nonce: Option<U256>,
+    pub highest_pooled_nonce: Option<U256>,
+    /// The nonces without a transaction in the pool, from `nonce` to the
+    /// highest pooled nonce. The transactions after a gap can not be packed.
+    pub nonce_gaps: Vec<NonceGap>,
+    /// The latest transaction of the sender refused by the pool.
+    pub last_rejected_transaction: Option<RejectedTransaction>,
+}
+
+/// The missing nonces from `from` to `to`, inclusive.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NonceGap {
+    pub from: U256,
+    pub to: U256,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RejectedTransaction {
+    pub hash: H256,
+    pub nonce: U256,
+    pub reason: String,
+}
+
+impl SenderStatus {
+    pub fn new(
+        nonce: U256, balance: U256, mut pooled_nonces: Vec<U256>,
+        last_rejected_transaction: Option<RejectedTransaction>,
+    ) -> Self
+    {
+        pooled_nonces.sort();
+        let pending_count = pooled_nonces.len();
+        pooled_nonces.dedup();
+
+        let mut nonce_gaps = Vec::new();
+        let mut next_nonce = nonce;
+        for pooled_nonce in &pooled_nonces {
+            if *pooled_nonce < next_nonce {
+                continue;
+            }
+            if *pooled_nonce > next_nonce {
+                nonce_gaps.push(NonceGap {
+                    from: next_nonce,
+                    to: *pooled_nonce - 1,
+                });
+            }
+            next_nonce = *pooled_nonce + 1;
+        }
+
+        SenderStatus {
+            nonce,
+            balance,
+            pending_count,
+            lowest_pooled_nonce: pooled_nonces.first().cloned(),
+            highest_pooled_nonce: pooled_nonces.last().cloned(),
+            nonce_gaps,
+            last_rejected_transaction,
+        }
+    }
+}
+
+/// Whether the transactions sent or received by an account are returned by
+/// `cfx_getAccountTransactions`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionDirection {
+    Sent,
+    /// The calls to the account and the transactions transferring value to
+    /// it in their internal calls, if `index_internal_transfers` is set.
+    Received,
+}
+
+impl From<TransactionDirection>
+    for cfxcore::block_data_manager::TransactionDirection
+{
+    fn from(direction: TransactionDirection) -> Self {
+        match direction {
+            TransactionDirection::Sent => Self::Sent,
+            TransactionDirection::Received => Self::Received,
+        }
+    }
+}
+
+/// A page of the transactions of an account, in the order of execution.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AccountTransactions {
+    /// The number of the transactions of the account in the direction.
+    pub total: U64,
+    pub transactions: Vec<H256>,
+}
+
+/// A raw transaction decoded by the node without submitting it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedTransaction {
+    pub hash: H256,
+    pub nonce: U256,
+    /// The sender recovered from the signature, `None` if the signature is
+    /// invalid.
+    pub from: Option<RpcAddress>,
+    pub to: Option<RpcAddress>,
+    pub value: U256,
+    pub gas_price: U256,
+    pub gas: U256,
+    pub data: Bytes,
+    pub storage_limit: U256,
+    pub epoch_height: U256,
+    pub chain_id: U256,
+    pub v: U256,
+    pub r: U256,
+    pub s: U256,
+    /// The gas charged for the action and the data before the execution.
+    pub intrinsic_gas: U256,
+    /// Whether the transaction would be accepted into the pool now.
+    pub valid: bool,
+    /// The reason the transaction would be rejected.
+    pub error: Option<String>,
+}
+
+impl DecodedTransaction {
+    pub fn new(
+        t: &TransactionWithSignature, sender: Option<H160>, intrinsic_gas: u64,
+        verdict: Result<(), String>,
+    ) -> Self
+    {
+        DecodedTransaction {
+            hash: t.hash(),
+            nonce: t.nonce,
+            from: sender.map(Into::into),
+            to: match t.action {
+                Action::Create => None,
+                Action::Call(ref address) => Some(address.clone().into()),
+            },
+            value: t.value,
+            gas_price: t.gas_price,
+            gas: t.gas,
+            data: t.data.clone().into(),
+            storage_limit: t.storage_limit.into(),
+            epoch_height: t.epoch_height.into(),
+            chain_id: t.chain_id.into(),
+            v: t.transaction.v.into(),
+            r: t.transaction.r,
+            s: t.transaction.s,
+            intrinsic_gas: intrinsic_gas.into(),
+            valid: verdict.is_ok(),
+            error: verdict.err(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::rpc::types::{
-        Bytes, PackedOrExecuted, Receipt, SendTxRequest, Transaction,
-        TxPoolPendingInfo, TxWithPoolInfo,
+        transaction::NonceGap, Bytes, DecodedTransaction, PackedOrExecuted,
+        Receipt, SendTxRequest, SenderStatus, Transaction, TxPoolPendingInfo,
+        TxWithPoolInfo,
     };
     use cfx_types::{Bloom, H160, H256, U256, U64};
     use cfxcore_accounts::AccountProvider;
-    use cfxkey::Secret;
+    use cfxkey::{KeyPair, Secret};
     use primitives::{
         transaction::Action, SignedTransaction,
         Transaction as PrimitiveTransaction, TransactionIndex,
@@ -217,7 +428,7 @@ mod tests {
             nonce: U256::one(),
             block_hash: None,
             transaction_index: None,
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             value: U256::one(),
             gas_price: U256::one(),
@@ -234,18 +445,18 @@ mod tests {
         };
         let serialize = serde_json::to_string(&transaction).unwrap();
         assert_eq!(serialize,
-                   "{\"hash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"nonce\":\"0x1\",\"blockHash\":null,\"transactionIndex\":null,\"from\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"to\":null,\"value\":\"0x1\",\"gasPrice\":\"0x1\",\"gas\":\"0x1\",\"contractCreated\":null,\"data\":\"0x\",\"storageLimit\":\"0x1\",\"epochHeight\":\"0x1\",\"chainId\":\"0x1\",\"status\":null,\"v\":\"0x1\",\"r\":\"0x1\",\"s\":\"0x1\"}");
+                   "{\"hash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"nonce\":\"0x1\",\"blockHash\":null,\"transactionIndex\":null,\"from\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"to\":null,\"value\":\"0x1\",\"gasPrice\":\"0x1\",\"gas\":\"0x1\",\"contractCreated\":null,\"data\":\"0x\",\"storageLimit\":\"0x1\",\"epochHeight\":\"0x1\",\"chainId\":\"0x1\",\"status\":null,\"v\":\"0x1\",\"r\":\"0x1\",\"s\":\"0x1\"}");
     }
     #[test]
     fn test_transaction_deserialize() {
-        let serialize = "{\"hash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"nonce\":\"0x1\",\"blockHash\":null,\"transactionIndex\":null,\"from\":\"0xffffffffffffffffffffffffffffffffffffffff\",\"to\":null,\"value\":\"0x1\",\"gasPrice\":\"0x1\",\"gas\":\"0x1\",\"contractCreated\":null,\"data\":\"0x\",\"storageLimit\":\"0x1\",\"epochHeight\":\"0x1\",\"chainId\":\"0x1\",\"status\":null,\"v\":\"0x1\",\"r\":\"0x1\",\"s\":\"0x1\"}";
+        let serialize = "{\"hash\":\"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\",\"nonce\":\"0x1\",\"blockHash\":null,\"transactionIndex\":null,\"from\":\"cfxtest:ad99999999999999999999999999999996bw9gccab\",\"to\":null,\"value\":\"0x1\",\"gasPrice\":\"0x1\",\"gas\":\"0x1\",\"contractCreated\":null,\"data\":\"0x\",\"storageLimit\":\"0x1\",\"epochHeight\":\"0x1\",\"chainId\":\"0x1\",\"status\":null,\"v\":\"0x1\",\"r\":\"0x1\",\"s\":\"0x1\"}";
         let deserialize: Transaction = serde_json::from_str(serialize).unwrap();
         let transaction = Transaction {
             hash: H256([0xff; 32]),
             nonce: U256::one(),
             block_hash: None,
             transaction_index: None,
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             value: U256::one(),
             gas_price: U256::one(),
@@ -295,7 +506,7 @@ mod tests {
         let t: Transaction = Transaction::from_signed(&sign_transaction, None);
         let transaction_from_signed = serde_json::to_string(&t).unwrap();
         assert_eq!(transaction_from_signed,
-                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":null,"transactionIndex":null,"from":"0xffffffffffffffffffffffffffffffffffffffff","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":null,"v":"0x0","r":"0x1","s":"0x1"}"#);
+                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":null,"transactionIndex":null,"from":"cfxtest:ad99999999999999999999999999999996bw9gccab","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":null,"v":"0x0","r":"0x1","s":"0x1"}"#);
     }
 
     #[test]
@@ -333,7 +544,7 @@ mod tests {
             transaction_hash: H256::default(),
             block_hash: H256::default(),
             epoch_number: None,
-            from: H160::default(),
+            from: H160::default().into(),
             to: None,
             gas_used: U256::default(),
             index: U64::default(),
@@ -348,7 +559,7 @@ mod tests {
             Transaction::from_signed(&sign_transaction, receipt);
         let transaction_from_signed = serde_json::to_string(&t).unwrap();
         assert_eq!(transaction_from_signed,
-                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0","from":"0xffffffffffffffffffffffffffffffffffffffff","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":"0x0","v":"0x0","r":"0x1","s":"0x1"}"#);
+                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0","from":"cfxtest:ad99999999999999999999999999999996bw9gccab","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":"0x0","v":"0x0","r":"0x1","s":"0x1"}"#);
     }
 
     #[test]
@@ -390,7 +601,7 @@ mod tests {
             Transaction::from_signed(&sign_transaction, transaction_index);
         let transaction_from_signed = serde_json::to_string(&t).unwrap();
         assert_eq!(transaction_from_signed,
-                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0","from":"0xffffffffffffffffffffffffffffffffffffffff","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":null,"v":"0x0","r":"0x1","s":"0x1"}"#);
+                   r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0","from":"cfxtest:ad99999999999999999999999999999996bw9gccab","to":null,"value":"0x0","gasPrice":"0x0","gas":"0x0","contractCreated":null,"data":"0x","storageLimit":"0x0","epochHeight":"0x0","chainId":"0x0","status":null,"v":"0x0","r":"0x1","s":"0x1"}"#);
     }
 
     #[test]
@@ -401,8 +612,8 @@ mod tests {
             nonce: U256::one(),
             block_hash: None,
             transaction_index: None,
-            from: H160([0xff; 20]),
-            to: Some(H160([0xff; 20])),
+            from: H160([0xff; 20]).into(),
+            to: Some(H160([0xff; 20]).into()),
             value: U256::one(),
             gas_price: U256::one(),
             gas: U256::one(),
@@ -427,7 +638,7 @@ mod tests {
     #[test]
     fn test_send_tx_request_sign_with_error() {
         let request = SendTxRequest {
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             gas: U256::one(),
             gas_price: U256::one(),
@@ -448,7 +659,7 @@ mod tests {
         );
         assert_eq!(x.is_err(), true);
         let request2 = SendTxRequest {
-            from: H160([0xff; 20]),
+            from: H160([0xff; 20]).into(),
             to: None,
             gas: U256::one(),
             gas_price: U256::one(),
@@ -480,7 +691,7 @@ mod tests {
             AccountProvider::insert_account(&ap3, secret, &"password".into())
                 .unwrap();
         let request3 = SendTxRequest {
-            from: address,
+            from: address.into(),
             to: None,
             gas: U256::one(),
             gas_price: U256::one(),
@@ -503,6 +714,50 @@ mod tests {
         assert_eq!(info,
                    "{\"Ok\":{\"transaction\":{\"unsigned\":{\"nonce\":\"0x1\",\"gasPrice\":\"0x1\",\"gas\":\"0x1\",\"action\":\"Create\",\"value\":\"0x1\",\"storageLimit\":1,\"epochHeight\":1,\"chainId\":1,\"data\":[]},\"v\":0,\"r\":\"0x48c606475f4a90b89697105a246b0b95009ffe596b468de00bc3f6289cc884ff\",\"s\":\"0x19f38170561228fc31613626d9b8fdde18796ba8ae9c3c5470764260cbb525cf\"}}}");
     }
+    #[test]
+    fn test_send_tx_request_sign_offline() {
+        let secret = Secret::from_str(
+            "a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65",
+        )
+        .unwrap();
+        let address = KeyPair::from_secret(secret.clone()).unwrap().address();
+        let request = |from: H160, nonce| SendTxRequest {
+            from: from.into(),
+            to: None,
+            gas: U256::one(),
+            gas_price: U256::one(),
+            value: U256::one(),
+            data: Some(Bytes(vec![])),
+            nonce,
+            storage_limit: Some(U256::one()),
+            chain_id: Some(U256::one()),
+            epoch_height: Some(U256::one()),
+        };
+        assert!(request(address, None).sign_offline(&secret).is_err());
+        assert!(request(H160([0xff; 20]), Some(U256::one()))
+            .sign_offline(&secret)
+            .is_err());
+
+        // The same signature as signing with the account provider.
+        let tx = request(address, Some(U256::one()))
+            .sign_offline(&secret)
+            .unwrap();
+        assert_eq!(
+            tx.r,
+            U256::from_str(
+                "48c606475f4a90b89697105a246b0b95009ffe596b468de00bc3f6289cc884ff"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            tx.s,
+            U256::from_str(
+                "19f38170561228fc31613626d9b8fdde18796ba8ae9c3c5470764260cbb525cf"
+            )
+            .unwrap()
+        );
+    }
+
     #[test]
     fn test_tx_pool_info() {
         let tx_pool_info = TxWithPoolInfo {
@@ -531,4 +786,83 @@ mod tests {
             "{\"pending_count\":0,\"min_nonce\":\"0x0\",\"max_nonce\":\"0x0\"}"
         );
     }
+
+    #[test]
+    fn test_sender_status() {
+        let nonces = |nonces: &[u64]| {
+            nonces.iter().map(|n| U256::from(*n)).collect::<Vec<_>>()
+        };
+        let status =
+            SenderStatus::new(3.into(), 0.into(), nonces(&[9, 2, 3, 6]), None);
+        assert_eq!(status.pending_count, 4);
+        assert_eq!(status.lowest_pooled_nonce, Some(2.into()));
+        assert_eq!(status.highest_pooled_nonce, Some(9.into()));
+        assert_eq!(
+            status.nonce_gaps,
+            vec![
+                NonceGap {
+                    from: 4.into(),
+                    to: 5.into(),
+                },
+                NonceGap {
+                    from: 7.into(),
+                    to: 8.into(),
+                },
+            ]
+        );
+
+        let status =
+            SenderStatus::new(3.into(), 0.into(), nonces(&[4, 3, 3]), None);
+        assert_eq!(status.pending_count, 3);
+        assert!(status.nonce_gaps.is_empty());
+        assert_eq!(
+            serde_json::to_string(&status).unwrap(),
+            "{\"nonce\":\"0x3\",\"balance\":\"0x0\",\"pendingCount\":3,\
+             \"lowestPooledNonce\":\"0x3\",\"highestPooledNonce\":\"0x4\",\
+             \"nonceGaps\":[],\"lastRejectedTransaction\":null}"
+        );
+    }
+
+    #[test]
+    fn test_decoded_transaction() {
+        let keypair = KeyPair::from_secret(
+            Secret::from_str(
+                "a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let signed = PrimitiveTransaction {
+            nonce: 1.into(),
+            gas_price: 1.into(),
+            gas: 21000.into(),
+            action: Action::Call(H160::from_low_u64_be(1)),
+            value: 2.into(),
+            storage_limit: 0,
+            epoch_height: 0,
+            chain_id: 1,
+            data: vec![1, 2],
+        }
+        .sign(keypair.secret());
+
+        let decoded = DecodedTransaction::new(
+            &signed.transaction,
+            Some(keypair.address()),
+            21136,
+            Err("gas too low".into()),
+        );
+        assert_eq!(decoded.hash, signed.hash());
+        assert_eq!(decoded.from, Some(keypair.address().into()));
+        assert_eq!(decoded.to, Some(H160::from_low_u64_be(1).into()));
+        assert_eq!(decoded.data, Bytes::new(vec![1, 2]));
+        assert_eq!(decoded.intrinsic_gas, 21136.into());
+        assert!(!decoded.valid);
+        assert_eq!(decoded.error, Some("gas too low".to_string()));
+
+        let decoded =
+            DecodedTransaction::new(&signed.transaction, None, 21136, Ok(()));
+        assert_eq!(decoded.from, None);
+        assert!(decoded.valid);
+        assert_eq!(decoded.error, None);
+    }
 }
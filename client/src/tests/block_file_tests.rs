@@ -0,0 +1,125 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+extern crate tempdir;
+
+use self::tempdir::TempDir;
+use crate::{
+    archive::ArchiveClient,
+    block_file::{export_blocks, import_blocks, read_rlp_item},
+    common::{client_methods, initialize_data_manager},
+    configuration::Configuration,
+    rpc::RpcBlock,
+};
+use cfxcore::ConsensusGraphTrait;
+use parking_lot::{Condvar, Mutex};
+use primitives::Block;
+use rlp::Rlp;
+use secret_store::SecretStore;
+use std::{
+    fs::File,
+    io::BufReader,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+fn test_conf(dir: &TempDir, port_offset: u16) -> Configuration {
+    let mut conf = Configuration::default();
+    conf.raw_conf.mode = Some("test".to_owned());
+    conf.raw_conf.conflux_data_dir =
+        dir.path().to_str().unwrap().to_string() + "/";
+    conf.raw_conf.block_db_dir = dir
+        .path()
+        .join("db")
+        .into_os_string()
+        .into_string()
+        .unwrap();
+    conf.raw_conf.netconf_dir = Some(
+        dir.path()
+            .join("config")
+            .into_os_string()
+            .into_string()
+            .unwrap(),
+    );
+    conf.raw_conf.tcp_port = 13002 + port_offset;
+    conf.raw_conf.jsonrpc_http_port = Some(18002 + port_offset);
+    conf
+}
+
+/// Load the blocks of a test chain into the node at `dir`.
+fn load_chain(dir: &TempDir) {
+    let conf = test_conf(dir, 0);
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_timeout = conf.shutdown_timeout();
+    let handle = ArchiveClient::start(conf, exit).unwrap();
+    // make sure db recovery has completed
+    thread::sleep(Duration::from_secs(7));
+
+    let file = File::open("../tests/blockchain_tests/general_2.json").unwrap();
+    let rpc_blocks: Vec<RpcBlock> =
+        serde_json::from_reader(BufReader::new(file)).unwrap();
+    let mut last_hash = None;
+    for rpc_block in rpc_blocks.into_iter().skip(1) {
+        let block = rpc_block.into_primitive().unwrap();
+        last_hash = Some(block.hash());
+        handle.other_components.sync.on_mined_block(block).ok();
+    }
+
+    let last_hash = last_hash.unwrap();
+    let instant = Instant::now();
+    while instant.elapsed() < Duration::from_secs(60)
+        && handle
+            .other_components
+            .consensus
+            .get_block_epoch_number(&last_hash)
+            .is_none()
+    {
+        thread::sleep(Duration::from_secs(1));
+    }
+    client_methods::shutdown(handle, shutdown_timeout);
+}
+
+#[test]
+fn test_export_import_roundtrip() {
+    let source_dir = TempDir::new("conflux-test").unwrap();
+    load_chain(&source_dir);
+
+    let file_dir = TempDir::new("conflux-test").unwrap();
+    let path = file_dir.path().join("blocks.rlp");
+    let path = path.to_str().unwrap();
+    export_blocks(test_conf(&source_dir, 0), 1, None, path).unwrap();
+
+    let mut blocks = Vec::new();
+    let mut reader = BufReader::new(File::open(path).unwrap());
+    while let Some(bytes) = read_rlp_item(&mut reader, usize::MAX).unwrap() {
+        let block: Block = Rlp::new(&bytes).as_val().unwrap();
+        blocks.push(block.hash());
+    }
+    assert!(!blocks.is_empty());
+
+    let target_dir = TempDir::new("conflux-test").unwrap();
+    assert_eq!(
+        import_blocks(test_conf(&target_dir, 1), path).unwrap(),
+        format!("Imported {} blocks, 0 blocks already exist", blocks.len())
+    );
+    // The blocks are already imported at the second time.
+    assert_eq!(
+        import_blocks(test_conf(&target_dir, 1), path).unwrap(),
+        format!("Imported 0 blocks, {} blocks already exist", blocks.len())
+    );
+
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let (_, data_man, _) = initialize_data_manager(
+        &test_conf(&target_dir, 1),
+        &SecretStore::new(),
+        exit.clone(),
+    )
+    .unwrap();
+    for hash in &blocks {
+        assert!(data_man.block_by_hash(hash, false).is_some());
+    }
+    *exit.0.lock() = true;
+    exit.1.notify_all();
+}
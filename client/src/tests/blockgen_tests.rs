@@ -58,13 +58,15 @@ fn test_mining_10_epochs_inner(
     );
 }
 
-#[test]
-fn test_mining_10_epochs() {
+/// The configuration of an archive node mining on CPU in test mode, with
+/// its data in `tmp_dir`.
+fn cpu_mining_conf(
+    tmp_dir: &TempDir, tcp_port: u16, jsonrpc_http_port: u16,
+) -> Configuration {
     let mut conf = Configuration::default();
     conf.raw_conf.mode = Some("test".to_owned());
     conf.raw_conf.initial_difficulty = Some(10_000);
 
-    let tmp_dir = TempDir::new("conflux-test").unwrap();
     conf.raw_conf.conflux_data_dir =
         tmp_dir.path().to_str().unwrap().to_string() + "/";
     conf.raw_conf.block_db_dir = tmp_dir
@@ -81,16 +83,52 @@ fn test_mining_10_epochs() {
             .into_string()
             .unwrap(),
     );
-    conf.raw_conf.tcp_port = 13001;
-    conf.raw_conf.jsonrpc_http_port = Some(18001);
+    conf.raw_conf.tcp_port = tcp_port;
+    conf.raw_conf.jsonrpc_http_port = Some(jsonrpc_http_port);
     conf.raw_conf.mining_author =
         Some("1aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".into());
     conf.raw_conf.mining_type = Some("cpu".into());
+    conf
+}
+
+#[test]
+fn test_mining_10_epochs() {
+    let tmp_dir = TempDir::new("conflux-test").unwrap();
+    let conf = cpu_mining_conf(&tmp_dir, 13001, 18001);
 
     let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_timeout = conf.shutdown_timeout();
     let handle = ArchiveClient::start(conf, exit).unwrap();
 
     test_mining_10_epochs_inner(&handle);
 
-    client_methods::shutdown(handle);
+    client_methods::shutdown(handle, shutdown_timeout);
+}
+
+#[test]
+fn test_mining_serves_remote_miners() {
+    let tmp_dir = TempDir::new("conflux-test").unwrap();
+    let conf = cpu_mining_conf(&tmp_dir, 13004, 18004);
+
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_timeout = conf.shutdown_timeout();
+    let handle = ArchiveClient::start(conf, exit).unwrap();
+
+    let bgen = handle.blockgen.clone().unwrap();
+    thread::spawn(move || {
+        BlockGenerator::start_mining(bgen, 0);
+    });
+
+    // The remote miners are served along with the CPU miner.
+    let remote_work = handle.blockgen.as_ref().unwrap().remote_work();
+    let max_timeout = Duration::from_secs(60);
+    let instant = Instant::now();
+    while remote_work.work().is_none() && instant.elapsed() < max_timeout {
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert!(remote_work.is_mining());
+    assert!(remote_work.work().is_some());
+    handle.blockgen.as_ref().unwrap().stop();
+
+    client_methods::shutdown(handle, shutdown_timeout);
 }
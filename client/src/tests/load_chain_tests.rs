@@ -75,6 +75,7 @@ fn test_load_chain() {
     conf.raw_conf.jsonrpc_http_port = Some(18000);
 
     let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_timeout = conf.shutdown_timeout();
     let handle = ArchiveClient::start(conf, exit).unwrap();
 
     let chain_path = "../tests/blockchain_tests/general_2.json";
@@ -132,5 +133,5 @@ fn test_load_chain() {
         handle.other_components.consensus.best_block_hash()
     );
 
-    client_methods::shutdown(handle);
+    client_methods::shutdown(handle, shutdown_timeout);
 }
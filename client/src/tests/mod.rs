@@ -2,6 +2,8 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
+#[cfg(test)]
+mod block_file_tests;
 #[cfg(test)]
 mod blockgen_tests;
 #[cfg(test)]
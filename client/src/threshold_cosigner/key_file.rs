@@ -0,0 +1,112 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The key file of a cosigner, with its key and its shares of the generated
+//! keys encrypted with a password as the keys of the accounts are.
+
+use super::KeyShare;
+use cfx_types::{Public, H256};
+use cfxkey::{Generator, Password, Random, Secret};
+use cfxstore::Crypto;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize)]
+struct Contents {
+    secret: H256,
+    shares: Vec<KeyShare>,
+}
+
+pub struct KeyFile {
+    path: PathBuf,
+    password: Password,
+    /// The iterations of the key derivation from the password.
+    iterations: u32,
+}
+
+impl KeyFile {
+    pub fn new<P: AsRef<Path>>(
+        path: P, password: Password, iterations: u32,
+    ) -> Self {
+        KeyFile {
+            path: path.as_ref().to_path_buf(),
+            password,
+            iterations,
+        }
+    }
+
+    /// Create the key file with a new key of the cosigner without shares, and
+    /// return its public key to configure the `peers`.
+    pub fn create(&self) -> Result<Public, String> {
+        if self.path.exists() {
+            return Err(format!("{} exists", self.path.display()));
+        }
+        let key_pair = Random.generate().map_err(|e| e.to_string())?;
+        self.save(key_pair.secret(), &[])?;
+        Ok(*key_pair.public())
+    }
+
+    pub fn load(&self) -> Result<(Secret, Vec<KeyShare>), String> {
+        let crypto: Crypto = fs::read_to_string(&self.path)
+            .map_err(|e| format!("failed to read the key file: {}", e))?
+            .parse()
+            .map_err(|e| format!("invalid key file: {:?}", e))?;
+        let plain = crypto
+            .decrypt(&self.password)
+            .map_err(|e| format!("failed to decrypt the key file: {}", e))?;
+        let contents: Contents = serde_json::from_slice(&plain)
+            .map_err(|e| format!("invalid key file: {}", e))?;
+        let secret = Secret::from_unsafe_slice(contents.secret.as_bytes())
+            .map_err(|e| format!("invalid key file: {}", e))?;
+        Ok((secret, contents.shares))
+    }
+
+    /// Replace the key file with the key and the shares, so that a crash
+    /// leaves either the old or the new file.
+    pub fn save(
+        &self, secret: &Secret, shares: &[KeyShare],
+    ) -> Result<(), String> {
+        let contents = Contents {
+            secret: H256::from_slice(secret.as_bytes()),
+            shares: shares.to_vec(),
+        };
+        let plain = serde_json::to_vec(&contents).map_err(|e| e.to_string())?;
+        let crypto =
+            Crypto::with_plain(&plain, &self.password, self.iterations)
+                .map_err(|e| {
+                    format!("failed to encrypt the key file: {:?}", e)
+                })?;
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, String::from(crypto))
+            .and_then(|_| fs::rename(&temp, &self.path))
+            .map_err(|e| format!("failed to write the key file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_key_file() {
+        let dir = TempDir::new("cosigner").unwrap();
+        let path = dir.path().join("key.json");
+        let key_file = KeyFile::new(&path, "password".into(), 16);
+        let public = key_file.create().unwrap();
+        assert!(key_file.create().is_err());
+
+        let (secret, shares) = key_file.load().unwrap();
+        assert_eq!(
+            cfxkey::KeyPair::from_secret(secret).unwrap().public(),
+            &public
+        );
+        assert!(shares.is_empty());
+
+        let wrong = KeyFile::new(&path, "wrong".into(), 16);
+        assert!(wrong.load().is_err());
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The distributed generation of a key. In round 0 every cosigner commits to
+//! its polynomial and sends its Paillier key with the proofs that it's well
+//! formed, in round 1 it reveals the polynomial with the shares dealt to the
+//! others and the proofs that the factors of its Paillier modulus are not
+//! small, and in round 2 it checks the shares against the polynomials and sums
+//! them up.
+
+use super::{
+    base_mul, paillier, point_add, point_mul,
+    proofs::{Context, FactorProof, ModulusProof, ParametersProof},
+    random_scalar, ORDER,
+};
+use cfx_types::{Address, Public, H256};
+use cfxkey::{crypto::ecies, public_to_address, Secret};
+use keccak_hash::keccak;
+use num_bigint::BigUint;
+use rustc_hex::{FromHex, ToHex};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// The share of a cosigner of a generated key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub address: Address,
+    pub threshold: usize,
+    /// The index of the cosigner.
+    pub(super) index: usize,
+    pub(super) public: Public,
+    pub(super) share: H256,
+    pub(super) paillier: paillier::SecretKey,
+    /// The Paillier keys of all the cosigners, ordered by their indexes.
+    pub(super) paillier_keys: Vec<paillier::PublicKey>,
+    /// The shares of all the cosigners multiplied by `G`, ordered by their
+    /// indexes.
+    pub(super) public_shares: Vec<Public>,
+}
+
+/// The message of a cosigner in round 0.
+#[derive(Serialize, Deserialize)]
+pub struct Commitment {
+    /// The commitment to the polynomial.
+    commitment: H256,
+    paillier: paillier::PublicKey,
+    modulus_proof: ModulusProof,
+    parameters_proof: ParametersProof,
+}
+
+/// The message of a cosigner in round 1.
+#[derive(Serialize, Deserialize)]
+pub struct Reveal {
+    /// The random bytes hiding the coefficients in the commitment.
+    salt: H256,
+    /// The coefficients of the polynomial multiplied by `G`.
+    coefficients: Vec<Public>,
+    /// The polynomial at the indexes of the cosigners, encrypted to them. The
+    /// one of the cosigner itself is empty.
+    shares: Vec<String>,
+    /// The proofs of the Paillier modulus with the parameters of each other
+    /// cosigner.
+    factor_proofs: BTreeMap<usize, FactorProof>,
+}
+
+pub struct KeyGen {
+    session: H256,
+    index: usize,
+    threshold: usize,
+    /// The coefficients of the polynomial, whose constant term is the part of
+    /// the key dealt by the cosigner.
+    polynomial: Vec<BigUint>,
+    salt: H256,
+    paillier: paillier::SecretKey,
+    /// The commitments of all the cosigners in round 0.
+    commitments: BTreeMap<usize, H256>,
+    paillier_keys: Vec<paillier::PublicKey>,
+}
+
+impl KeyGen {
+    pub fn start(
+        session: H256, index: usize, cosigners: usize, threshold: usize,
+    ) -> Result<(KeyGen, Commitment), String> {
+        if threshold == 0 || threshold > cosigners {
+            return Err(format!(
+                "invalid threshold {} of {} cosigners",
+                threshold, cosigners
+            ));
+        }
+        let keygen = KeyGen {
+            session,
+            index,
+            threshold,
+            polynomial: (0..threshold).map(|_| random_scalar()).collect(),
+            salt: H256::from(rand::random::<[u8; 32]>()),
+            paillier: paillier::SecretKey::generate(),
+            commitments: BTreeMap::new(),
+            paillier_keys: Vec::new(),
+        };
+        let context = keygen.context(index, None);
+        let commitment = Commitment {
+            commitment: commitment(&keygen.salt, &keygen.coefficients()?),
+            paillier: keygen.paillier.public().clone(),
+            modulus_proof: ModulusProof::new(&context, &keygen.paillier),
+            parameters_proof: ParametersProof::new(&context, &keygen.paillier),
+        };
+        Ok((keygen, commitment))
+    }
+
+    pub fn reveal(
+        &mut self, commitments: BTreeMap<usize, Commitment>, peers: &[Public],
+    ) -> Result<Reveal, String> {
+        if commitments.len() != peers.len() {
+            return Err("all the cosigners should generate the key".into());
+        }
+        for (j, commitment) in &commitments {
+            let context = self.context(*j, None);
+            if *j != self.index
+                && !(commitment
+                    .modulus_proof
+                    .verify(&context, &commitment.paillier)
+                    && commitment
+                        .parameters_proof
+                        .verify(&context, &commitment.paillier))
+            {
+                return Err(format!("invalid Paillier key of cosigner {}", j));
+            }
+        }
+        let mut factor_proofs = BTreeMap::new();
+        for (j, commitment) in &commitments {
+            if *j != self.index {
+                let context = self.context(self.index, Some(*j));
+                let proof = FactorProof::new(
+                    &context,
+                    &self.paillier,
+                    &commitment.paillier,
+                )?;
+                factor_proofs.insert(*j, proof);
+            }
+        }
+
+        let mut shares = Vec::with_capacity(peers.len());
+        for (j, peer) in peers.iter().enumerate() {
+            if j == self.index {
+                shares.push(String::new());
+                continue;
+            }
+            let share = to_bytes(&evaluate(&self.polynomial, j));
+            let encrypted =
+                ecies::encrypt(peer, &[], &share).map_err(|e| e.to_string())?;
+            shares.push(encrypted.to_hex());
+        }
+        for (j, commitment) in commitments {
+            self.commitments.insert(j, commitment.commitment);
+            self.paillier_keys.push(commitment.paillier);
+        }
+        Ok(Reveal {
+            salt: self.salt,
+            coefficients: self.coefficients()?,
+            shares,
+            factor_proofs,
+        })
+    }
+
+    pub fn finish(
+        self, reveals: BTreeMap<usize, Reveal>, secret: &Secret,
+    ) -> Result<KeyShare, String> {
+        if !reveals.keys().eq(self.commitments.keys()) {
+            return Err("the cosigners changed in the key generation".into());
+        }
+        let mut share = BigUint::from(0u32);
+        let mut public: Option<Public> = None;
+        let mut public_shares: Vec<Option<Public>> =
+            vec![None; self.commitments.len()];
+        for (j, reveal) in reveals {
+            if commitment(&reveal.salt, &reveal.coefficients)
+                != self.commitments[&j]
+            {
+                return Err(format!("cosigner {} broke its commitment", j));
+            }
+            if reveal.coefficients.len() != self.threshold
+                || reveal.shares.len() != self.commitments.len()
+            {
+                return Err(format!("invalid polynomial of cosigner {}", j));
+            }
+            if j != self.index {
+                let valid = reveal.factor_proofs.get(&self.index).map_or(
+                    false,
+                    |proof| {
+                        proof.verify(
+                            &self.context(j, Some(self.index)),
+                            &self.paillier_keys[j],
+                            self.paillier.public(),
+                        )
+                    },
+                );
+                if !valid {
+                    return Err(format!(
+                        "invalid Paillier key of cosigner {}",
+                        j
+                    ));
+                }
+            }
+
+            let dealt = if j == self.index {
+                evaluate(&self.polynomial, self.index)
+            } else {
+                let encrypted = reveal.shares[self.index]
+                    .from_hex()
+                    .map_err(|e| e.to_string())?;
+                let plain = ecies::decrypt(secret, &[], &encrypted)
+                    .map_err(|e| e.to_string())?;
+                BigUint::from_bytes_be(&plain)
+            };
+            // Feldman's check of the share against the polynomial.
+            if dealt >= *ORDER
+                || base_mul(&dealt)?
+                    != evaluate_points(&reveal.coefficients, self.index)?
+            {
+                return Err(format!("invalid share from cosigner {}", j));
+            }
+
+            share = (share + dealt) % &*ORDER;
+            public = Some(match public {
+                None => reveal.coefficients[0],
+                Some(sum) => point_add(&sum, &reveal.coefficients[0])?,
+            });
+            for (m, public_share) in public_shares.iter_mut().enumerate() {
+                let dealt = evaluate_points(&reveal.coefficients, m)?;
+                *public_share = Some(match public_share {
+                    None => dealt,
+                    Some(sum) => point_add(sum, &dealt)?,
+                });
+            }
+        }
+        let public = public.expect("the cosigner itself revealed; qed");
+
+        Ok(KeyShare {
+            address: public_to_address(&public),
+            threshold: self.threshold,
+            index: self.index,
+            public,
+            share: H256::from(to_bytes(&share)),
+            paillier: self.paillier,
+            paillier_keys: self.paillier_keys,
+            public_shares: public_shares
+                .into_iter()
+                .map(|share| share.expect("all the cosigners revealed; qed"))
+                .collect(),
+        })
+    }
+
+    fn coefficients(&self) -> Result<Vec<Public>, String> {
+        self.polynomial.iter().map(base_mul).collect()
+    }
+
+    fn context(&self, prover: usize, verifier: Option<usize>) -> Context {
+        Context {
+            session: self.session,
+            prover,
+            verifier,
+        }
+    }
+}
+
+fn commitment(salt: &H256, coefficients: &[Public]) -> H256 {
+    keccak(json!([salt, coefficients]).to_string())
+}
+
+fn to_bytes(scalar: &BigUint) -> [u8; 32] { super::to_h256(scalar).0 }
+
+/// The polynomial at the cosigner `index`, whose `x` is `index + 1`.
+fn evaluate(polynomial: &[BigUint], index: usize) -> BigUint {
+    let x = BigUint::from(index as u64 + 1);
+    polynomial
+        .iter()
+        .rev()
+        .fold(BigUint::from(0u32), |acc, c| (acc * &x + c) % &*ORDER)
+}
+
+/// The polynomial multiplied by `G` at the cosigner `index`.
+fn evaluate_points(
+    coefficients: &[Public], index: usize,
+) -> Result<Public, String> {
+    let x = BigUint::from(index as u64 + 1);
+    let mut power = BigUint::from(1u32);
+    let mut result = coefficients[0];
+    for coefficient in &coefficients[1..] {
+        power = power * &x % &*ORDER;
+        result = point_add(&result, &point_mul(coefficient, &power)?)?;
+    }
+    Ok(result)
+}
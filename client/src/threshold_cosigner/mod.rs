@@ -0,0 +1,624 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The cosigner of the `ThresholdSigner`, which holds a share of each key and
+//! signs with `threshold` of the others without ever knowing the key.
+//!
+//! The keys are generated by all the `n` cosigners together. Each deals a
+//! random polynomial of degree `threshold - 1` with Feldman's verifiable
+//! secret sharing, and its share of the key is the sum of all the polynomials
+//! at its index. No cosigner learns the key, which is the sum of the constant
+//! terms, and any `threshold` of the shares interpolate it.
+//!
+//! The signing follows GG18 ("Fast Multiparty Threshold ECDSA with Fast
+//! Trustless Setup", Gennaro and Goldfeder). Each cosigner in a session picks
+//! `k_i` and `gamma_i`, turns its parts of `k * gamma` and `k * x` into
+//! additive shares with the other cosigners by the Paillier based MtA
+//! (multiplicative to additive) conversion, reveals `delta_i` and
+//! `k_i * Gamma` to compute `R = k^-1 * G`, and returns its share
+//! `s_i = m * k_i + r * sigma_i` of `s`.
+//!
+//! The cosigners sign their messages with their keys in `peers`, and the
+//! shares they deal to each other are encrypted to these keys, so the node
+//! relaying the messages learns nothing about the keys. The Paillier keys and
+//! the messages of the MtA conversions come with the zero knowledge proofs of
+//! `proofs`, so the keys are safe from any `threshold - 1` cosigners, even the
+//! ones deviating from the protocol.
+//!
+//! The cosigner serves JSON-RPC over HTTP to the nodes holding one of the
+//! `auth_tokens`, sent as a bearer token in the `Authorization` header. A
+//! session continues only with the node which started it.
+//! - `threshold_keygen_start(session, threshold)` and
+//!   `threshold_keygen_round(session, round, parties, messages)` generate a
+//!   key, and return `{"address": ...}` when it's generated.
+//! - `threshold_start(session, address, message)` and
+//!   `threshold_round(session, round, parties, messages)` sign as described
+//!   in `threshold_signer`.
+//!
+//! The messages carry the indexes of their cosigners, so the `parties` of the
+//! node are not used.
+//!
+//! A cosigner opened from a `KeyFile` saves its shares there as soon as a key
+//! is generated, and the key is only used once it's saved.
+
+mod key_file;
+mod keygen;
+mod paillier;
+mod proofs;
+mod signing;
+
+pub use self::{key_file::KeyFile, keygen::KeyShare};
+
+use self::{keygen::KeyGen, signing::Signing};
+use cfx_types::{Address, Public, H256, H520};
+use cfxkey::{
+    math::{curve_order, generation_point, public_add, public_mul_secret},
+    sign, verify_public, Generator, KeyPair, Random, Secret, Signature,
+};
+use jsonrpc_core::{Error as RpcError, MetaIoHandler, Metadata, Params, Value};
+use jsonrpc_http_server::{hyper, Server, ServerBuilder};
+use keccak_hash::keccak;
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use parking_lot::{Mutex, RwLock};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// The sessions which don't complete in time are dropped.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(600);
+
+lazy_static! {
+    /// The order of the curve, i.e. the modulus of the scalars.
+    static ref ORDER: BigUint = {
+        let mut bytes = [0u8; 32];
+        curve_order().to_big_endian(&mut bytes);
+        BigUint::from_bytes_be(&bytes)
+    };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CosignerConfig {
+    /// The index of the cosigner in `peers`.
+    pub index: usize,
+    /// The key to sign the messages of the cosigner and to decrypt the shares
+    /// dealt to it.
+    pub secret: Secret,
+    /// The public keys of all the cosigners, ordered by their indexes.
+    pub peers: Vec<Public>,
+    /// The bearer tokens of the nodes allowed to run the sessions.
+    pub auth_tokens: Vec<String>,
+}
+
+/// The node authorized by the bearer token of a request, as the index of its
+/// token in `auth_tokens`.
+#[derive(Clone, Copy, Default)]
+struct Coordinator(Option<usize>);
+
+impl Metadata for Coordinator {}
+
+impl Coordinator {
+    fn authorized(self) -> Result<usize, RpcError> {
+        self.0
+            .ok_or_else(|| rpc_error("the node is not authorized".into()))
+    }
+}
+
+/// A message of a cosigner in a round, signed with its key.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    index: usize,
+    payload: Value,
+    signature: H520,
+}
+
+/// The sessions with the time they started and the coordinator which started
+/// them.
+type Sessions<T> = Mutex<HashMap<H256, (Instant, usize, T)>>;
+
+pub struct ThresholdCosigner {
+    config: CosignerConfig,
+    keys: RwLock<HashMap<Address, KeyShare>>,
+    keygen_sessions: Sessions<KeyGen>,
+    signing_sessions: Sessions<Signing>,
+    /// Where the generated keys are saved, if anywhere.
+    key_file: Option<KeyFile>,
+}
+
+impl ThresholdCosigner {
+    pub fn new(config: CosignerConfig, keys: Vec<KeyShare>) -> Self {
+        ThresholdCosigner {
+            config,
+            keys: RwLock::new(
+                keys.into_iter().map(|key| (key.address, key)).collect(),
+            ),
+            keygen_sessions: Default::default(),
+            signing_sessions: Default::default(),
+            key_file: None,
+        }
+    }
+
+    /// Open the cosigner `index` of `peers` with its key and shares in the
+    /// `key_file`, where it also saves the keys it generates.
+    pub fn from_key_file(
+        index: usize, peers: Vec<Public>, auth_tokens: Vec<String>,
+        key_file: KeyFile,
+    ) -> Result<Self, String>
+    {
+        let (secret, keys) = key_file.load()?;
+        let public = *KeyPair::from_secret(secret.clone())
+            .map_err(|e| e.to_string())?
+            .public();
+        if peers.get(index) != Some(&public) {
+            return Err(format!(
+                "the key file doesn't hold the key of cosigner {}",
+                index
+            ));
+        }
+        let config = CosignerConfig {
+            index,
+            secret,
+            peers,
+            auth_tokens,
+        };
+        Ok(ThresholdCosigner {
+            key_file: Some(key_file),
+            ..ThresholdCosigner::new(config, keys)
+        })
+    }
+
+    /// Serve the cosigner over HTTP, which requires at least one auth token.
+    pub fn start_http(
+        self: Arc<Self>, address: &SocketAddr,
+    ) -> io::Result<Server> {
+        if self.config.auth_tokens.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no auth token of the nodes",
+            ));
+        }
+        let auth_tokens = self.config.auth_tokens.clone();
+        ServerBuilder::new(self.io_handler())
+            .with_meta_extractor(
+                move |request: &hyper::Request<hyper::Body>| {
+                    let token = request
+                        .headers()
+                        .get(hyper::header::AUTHORIZATION)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.strip_prefix("Bearer "));
+                    Coordinator(token.and_then(|token| {
+                        auth_tokens.iter().position(|expected| {
+                            constant_time_eq(
+                                expected.as_bytes(),
+                                token.as_bytes(),
+                            )
+                        })
+                    }))
+                },
+            )
+            .start_http(address)
+    }
+
+    fn io_handler(self: Arc<Self>) -> MetaIoHandler<Coordinator> {
+        let mut io = MetaIoHandler::default();
+        let cosigner = self.clone();
+        io.add_method_with_meta(
+            "threshold_keygen_start",
+            move |params: Params, coordinator: Coordinator| {
+                let coordinator = coordinator.authorized()?;
+                let (session, threshold): (H256, usize) = params.parse()?;
+                cosigner
+                    .keygen_start(coordinator, session, threshold)
+                    .map_err(rpc_error)
+            },
+        );
+        let cosigner = self.clone();
+        io.add_method_with_meta(
+            "threshold_keygen_round",
+            move |params: Params, coordinator: Coordinator| {
+                let coordinator = coordinator.authorized()?;
+                let (session, round, _parties, messages): (
+                    H256,
+                    usize,
+                    Vec<usize>,
+                    Vec<Value>,
+                ) = params.parse()?;
+                cosigner
+                    .keygen_round(coordinator, session, round, messages)
+                    .map_err(rpc_error)
+            },
+        );
+        let cosigner = self.clone();
+        io.add_method_with_meta(
+            "threshold_start",
+            move |params: Params, coordinator: Coordinator| {
+                let coordinator = coordinator.authorized()?;
+                let (session, address, message): (H256, Address, H256) =
+                    params.parse()?;
+                cosigner
+                    .signing_start(coordinator, session, address, message)
+                    .map_err(rpc_error)
+            },
+        );
+        let cosigner = self;
+        io.add_method_with_meta(
+            "threshold_round",
+            move |params: Params, coordinator: Coordinator| {
+                let coordinator = coordinator.authorized()?;
+                let (session, round, _parties, messages): (
+                    H256,
+                    usize,
+                    Vec<usize>,
+                    Vec<Value>,
+                ) = params.parse()?;
+                cosigner
+                    .signing_round(coordinator, session, round, messages)
+                    .map_err(rpc_error)
+            },
+        );
+        io
+    }
+
+    fn keygen_start(
+        &self, coordinator: usize, session: H256, threshold: usize,
+    ) -> Result<Value, String> {
+        let (keygen, commitment) = KeyGen::start(
+            session,
+            self.config.index,
+            self.config.peers.len(),
+            threshold,
+        )?;
+        let message = self.seal(&session, 0, &commitment)?;
+        insert_session(&self.keygen_sessions, session, coordinator, keygen)?;
+        Ok(json!({ "message": message }))
+    }
+
+    fn keygen_round(
+        &self, coordinator: usize, session: H256, round: usize,
+        messages: Vec<Value>,
+    ) -> Result<Value, String>
+    {
+        let (started, mut keygen) =
+            take_session(&self.keygen_sessions, &session, coordinator)?;
+        match round {
+            1 => {
+                let commitments = self.open(&session, 0, messages)?;
+                let reveal = keygen.reveal(commitments, &self.config.peers)?;
+                let message = self.seal(&session, 1, &reveal)?;
+                self.keygen_sessions
+                    .lock()
+                    .insert(session, (started, coordinator, keygen));
+                Ok(json!({ "message": message }))
+            }
+            2 => {
+                let reveals = self.open(&session, 1, messages)?;
+                let key = keygen.finish(reveals, &self.config.secret)?;
+                let address = key.address;
+                let mut keys = self.keys.write();
+                keys.insert(address, key);
+                if let Some(key_file) = &self.key_file {
+                    let shares: Vec<_> = keys.values().cloned().collect();
+                    if let Err(e) = key_file.save(&self.config.secret, &shares)
+                    {
+                        keys.remove(&address);
+                        return Err(e);
+                    }
+                }
+                Ok(json!({ "address": address }))
+            }
+            _ => Err(format!("unexpected round {}", round)),
+        }
+    }
+
+    fn signing_start(
+        &self, coordinator: usize, session: H256, address: Address,
+        message: H256,
+    ) -> Result<Value, String>
+    {
+        let key =
+            self.keys.read().get(&address).cloned().ok_or_else(|| {
+                format!("no share of the key of {:?}", address)
+            })?;
+        if key.index != self.config.index {
+            return Err("the share belongs to another cosigner".into());
+        }
+        let (signing, encryption) = Signing::start(session, key, &message)?;
+        let message = self.seal(&session, 0, &encryption)?;
+        insert_session(&self.signing_sessions, session, coordinator, signing)?;
+        Ok(json!({ "message": message }))
+    }
+
+    fn signing_round(
+        &self, coordinator: usize, session: H256, round: usize,
+        messages: Vec<Value>,
+    ) -> Result<Value, String>
+    {
+        let (started, mut signing) =
+            take_session(&self.signing_sessions, &session, coordinator)?;
+        let message = match round {
+            1 => {
+                let conversions =
+                    signing.convert(self.open(&session, 0, messages)?)?;
+                self.seal(&session, 1, &conversions)?
+            }
+            2 => {
+                let reveal =
+                    signing.reveal(self.open(&session, 1, messages)?)?;
+                self.seal(&session, 2, &reveal)?
+            }
+            3 => {
+                let (r, s) =
+                    signing.finish(self.open(&session, 2, messages)?)?;
+                return Ok(json!({ "r": r, "s": s }));
+            }
+            _ => return Err(format!("unexpected round {}", round)),
+        };
+        self.signing_sessions
+            .lock()
+            .insert(session, (started, coordinator, signing));
+        Ok(json!({ "message": message }))
+    }
+
+    /// Sign the `payload` of the cosigner in `round` of `session`.
+    fn seal<T: Serialize>(
+        &self, session: &H256, round: usize, payload: &T,
+    ) -> Result<Value, String> {
+        let payload =
+            serde_json::to_value(payload).map_err(|e| e.to_string())?;
+        let digest = digest(session, round, self.config.index, &payload);
+        let signature =
+            sign(&self.config.secret, &digest).map_err(|e| e.to_string())?;
+        serde_json::to_value(Envelope {
+            index: self.config.index,
+            payload,
+            signature: signature.into(),
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    /// Verify the `messages` of `round` of `session`, and return their
+    /// payloads by the indexes of their cosigners.
+    fn open<T: DeserializeOwned>(
+        &self, session: &H256, round: usize, messages: Vec<Value>,
+    ) -> Result<BTreeMap<usize, T>, String> {
+        let mut payloads = BTreeMap::new();
+        for message in messages {
+            let envelope: Envelope = serde_json::from_value(message)
+                .map_err(|e| format!("invalid message: {}", e))?;
+            let index = envelope.index;
+            let peer = self
+                .config
+                .peers
+                .get(index)
+                .ok_or_else(|| format!("unknown cosigner {}", index))?;
+            let digest = digest(session, round, index, &envelope.payload);
+            let signature = Signature::from(envelope.signature);
+            if !verify_public(peer, &signature, &digest).unwrap_or(false) {
+                return Err(format!("invalid signature of cosigner {}", index));
+            }
+            let payload = serde_json::from_value(envelope.payload)
+                .map_err(|e| format!("invalid message: {}", e))?;
+            if payloads.insert(index, payload).is_some() {
+                return Err(format!(
+                    "duplicate messages of cosigner {}",
+                    index
+                ));
+            }
+        }
+        if !payloads.contains_key(&self.config.index) {
+            return Err("the cosigner is not in the session".into());
+        }
+        Ok(payloads)
+    }
+}
+
+fn digest(session: &H256, round: usize, index: usize, payload: &Value) -> H256 {
+    keccak(json!([session, round, index, payload]).to_string())
+}
+
+fn insert_session<T>(
+    sessions: &Sessions<T>, session: H256, coordinator: usize, state: T,
+) -> Result<(), String> {
+    let mut sessions = sessions.lock();
+    let now = Instant::now();
+    sessions.retain(|_, (started, _, _)| {
+        now.duration_since(*started) < SESSION_TIMEOUT
+    });
+    if sessions.contains_key(&session) {
+        return Err(format!("session {:?} exists", session));
+    }
+    sessions.insert(session, (now, coordinator, state));
+    Ok(())
+}
+
+/// Take the state of the `session` started by the `coordinator`. The
+/// sessions of the other coordinators are left untouched.
+fn take_session<T>(
+    sessions: &Sessions<T>, session: &H256, coordinator: usize,
+) -> Result<(Instant, T), String> {
+    let mut sessions = sessions.lock();
+    match sessions.get(session) {
+        Some((_, owner, _)) if *owner == coordinator => {
+            let (started, _, state) =
+                sessions.remove(session).expect("the session exists; qed");
+            Ok((started, state))
+        }
+        _ => Err(format!("unknown session {:?}", session)),
+    }
+}
+
+/// Compare the secrets in a time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn rpc_error(message: String) -> RpcError {
+    let mut error = RpcError::internal_error();
+    error.message = message;
+    error
+}
+
+fn random_scalar() -> BigUint {
+    let key_pair = Random.generate().expect("OsRng never fails; qed");
+    BigUint::from_bytes_be(key_pair.secret().as_bytes())
+}
+
+/// The scalar `value mod q` in 32 bytes.
+fn to_h256(value: &BigUint) -> H256 {
+    let bytes = (value % &*ORDER).to_bytes_be();
+    let mut result = H256::zero();
+    result.as_bytes_mut()[32 - bytes.len()..].copy_from_slice(&bytes);
+    result
+}
+
+/// `scalar * G`.
+fn base_mul(scalar: &BigUint) -> Result<Public, String> {
+    point_mul(&generation_point(), scalar)
+}
+
+fn point_mul(point: &Public, scalar: &BigUint) -> Result<Public, String> {
+    let mut result = *point;
+    public_mul_secret(&mut result, &Secret::from(to_h256(scalar)))
+        .map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+fn point_add(a: &Public, b: &Public) -> Result<Public, String> {
+    let mut result = *a;
+    public_add(&mut result, b).map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfxkey::{public_to_address, recover};
+
+    fn call(
+        io: &MetaIoHandler<Coordinator>, coordinator: Option<usize>,
+        method: &str, params: Value,
+    ) -> Result<Value, Value>
+    {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = io
+            .handle_request_sync(&request.to_string(), Coordinator(coordinator))
+            .unwrap();
+        let mut response: Value = serde_json::from_str(&response).unwrap();
+        match response.get("error") {
+            Some(error) => Err(error.clone()),
+            None => Ok(response["result"].take()),
+        }
+    }
+
+    #[test]
+    fn test_sessions_of_coordinators() {
+        let key_pair = Random.generate().unwrap();
+        let config = CosignerConfig {
+            index: 0,
+            secret: key_pair.secret().clone(),
+            peers: vec![*key_pair.public()],
+            auth_tokens: vec!["first".into(), "second".into()],
+        };
+        let io = Arc::new(ThresholdCosigner::new(config, vec![])).io_handler();
+
+        // The calls without a valid token are rejected.
+        let session = H256::random();
+        let params = json!([session, 1]);
+        assert!(
+            call(&io, None, "threshold_keygen_start", params.clone()).is_err()
+        );
+
+        // A single cosigner generates a key of threshold 1, but only with the
+        // node which started the session.
+        let round = |coordinator, round, message: &Value| {
+            let params = json!([session, round, [0], [message]]);
+            call(&io, Some(coordinator), "threshold_keygen_round", params)
+        };
+        let output = call(&io, Some(0), "threshold_keygen_start", params);
+        let commitment = output.unwrap()["message"].take();
+        assert!(round(1, 1, &commitment).is_err());
+        let reveal = round(0, 1, &commitment).unwrap()["message"].take();
+        assert!(round(1, 2, &reveal).is_err());
+        let output = round(0, 2, &reveal).unwrap();
+        let address: Address =
+            serde_json::from_value(output["address"].clone()).unwrap();
+
+        // And so it signs.
+        let session = H256::random();
+        let message = H256::random();
+        let params = json!([session, address, message]);
+        let mut output = call(&io, Some(1), "threshold_start", params).unwrap();
+        for round in 1..4 {
+            let params = json!([session, round, [0], [output["message"]]]);
+            assert!(
+                call(&io, Some(0), "threshold_round", params.clone()).is_err()
+            );
+            output = call(&io, Some(1), "threshold_round", params).unwrap();
+        }
+        let r: H256 = serde_json::from_value(output["r"].clone()).unwrap();
+        let s: H256 = serde_json::from_value(output["s"].clone()).unwrap();
+        // The signatures have the lower `s`.
+        let mut s = BigUint::from_bytes_be(s.as_bytes());
+        if s > &*ORDER >> 1usize {
+            s = &*ORDER - s;
+        }
+        let s = to_h256(&s);
+        let signed = (0..2).any(|v| {
+            let signature = Signature::from_rsv(&r, &s, v);
+            recover(&signature, &message)
+                .map_or(false, |public| public_to_address(&public) == address)
+        });
+        assert!(signed);
+    }
+
+    #[test]
+    fn test_keys_in_key_file() {
+        let dir = tempdir::TempDir::new("cosigner").unwrap();
+        let key_file =
+            || KeyFile::new(dir.path().join("key.json"), "password".into(), 16);
+        let public = key_file().create().unwrap();
+        let open = || {
+            let tokens = vec!["token".into()];
+            ThresholdCosigner::from_key_file(
+                0,
+                vec![public],
+                tokens,
+                key_file(),
+            )
+        };
+        let other =
+            ThresholdCosigner::from_key_file(1, vec![], vec![], key_file());
+        assert!(other.is_err());
+        let io = Arc::new(open().unwrap()).io_handler();
+
+        let session = H256::random();
+        let params = json!([session, 1]);
+        let mut output =
+            call(&io, Some(0), "threshold_keygen_start", params).unwrap();
+        for round in 1..3 {
+            let params = json!([session, round, [0], [output["message"]]]);
+            output =
+                call(&io, Some(0), "threshold_keygen_round", params).unwrap();
+        }
+        let address: Address =
+            serde_json::from_value(output["address"].clone()).unwrap();
+
+        // The cosigner opened again signs with the generated key.
+        let io = Arc::new(open().unwrap()).io_handler();
+        let params = json!([H256::random(), address, H256::random()]);
+        assert!(call(&io, Some(0), "threshold_start", params).is_ok());
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The Paillier cryptosystem, whose additive homomorphism lets two cosigners
+//! turn the product of their secrets into additive shares without revealing
+//! the secrets. The modulus of a cosigner also carries its ring-Pedersen
+//! parameters, with which the others commit to their secrets in the proofs
+//! they send to the cosigner.
+
+use num_bigint::{BigInt, BigUint, Sign};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The bits of the modulus.
+pub const MODULUS_BITS: usize = 2048;
+
+/// The rounds of the Miller-Rabin test of the primes.
+const MILLER_RABIN_ROUNDS: usize = 32;
+
+/// The primes used to sieve the candidates before the Miller-Rabin test.
+const SIEVE_LIMIT: u32 = 2000;
+
+/// The public key, with which a cosigner receives the ciphertexts from the
+/// others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKey {
+    n: BigUint,
+    /// `n^2`, the modulus of the ciphertexts.
+    nn: BigUint,
+    /// The ring-Pedersen parameters, where `t` is a random square and
+    /// `s = t^lambda mod n`.
+    s: BigUint,
+    t: BigUint,
+}
+
+#[derive(Clone)]
+pub struct SecretKey {
+    public: PublicKey,
+    p: BigUint,
+    q: BigUint,
+    /// `(p - 1)(q - 1)`.
+    phi: BigUint,
+    /// `phi^-1 mod n`.
+    mu: BigUint,
+    /// The discrete logarithm of `s` to the base `t`.
+    lambda: BigUint,
+}
+
+impl PublicKey {
+    fn new(n: BigUint, s: BigUint, t: BigUint) -> Self {
+        let nn = &n * &n;
+        PublicKey { n, nn, s, t }
+    }
+
+    pub fn n(&self) -> &BigUint { &self.n }
+
+    pub fn nn(&self) -> &BigUint { &self.nn }
+
+    pub fn s(&self) -> &BigUint { &self.s }
+
+    pub fn t(&self) -> &BigUint { &self.t }
+
+    /// A random nonce of the encryption.
+    pub fn random_nonce(&self) -> BigUint {
+        loop {
+            let r = random_below(&self.n);
+            if r != BigUint::from(0u32) {
+                return r;
+            }
+        }
+    }
+
+    /// Encrypt `m` with the nonce `r`.
+    pub fn encrypt_with(&self, m: &BigUint, r: &BigUint) -> BigUint {
+        // With the generator `n + 1`, `g^m = 1 + mn (mod n^2)`.
+        let gm = (BigUint::from(1u32) + m % &self.n * &self.n) % &self.nn;
+        gm * r.modpow(&self.n, &self.nn) % &self.nn
+    }
+
+    /// Encrypt the signed `m` with the nonce `r`, as `m mod n`.
+    pub fn encrypt_signed(&self, m: &BigInt, r: &BigUint) -> BigUint {
+        self.encrypt_with(&modulo(m, &self.n), r)
+    }
+
+    /// The ciphertext of the sum of the plaintexts of `a` and `b`.
+    pub fn add(&self, a: &BigUint, b: &BigUint) -> BigUint { a * b % &self.nn }
+
+    /// The ciphertext of the plaintext of `c` multiplied by `k`.
+    pub fn mul(&self, c: &BigUint, k: &BigUint) -> BigUint {
+        c.modpow(k, &self.nn)
+    }
+
+    pub fn is_ciphertext(&self, c: &BigUint) -> bool {
+        *c != BigUint::from(0u32) && *c < self.nn
+    }
+}
+
+impl SecretKey {
+    pub fn generate() -> Self {
+        loop {
+            let p = random_prime(MODULUS_BITS / 2);
+            let q = random_prime(MODULUS_BITS / 2);
+            if p == q {
+                continue;
+            }
+            let n = &p * &q;
+            let r = random_below(&n);
+            let t = &r * &r % &n;
+            let lambda = random_below(&((&p - 1u32) * (&q - 1u32)));
+            return Self::from_parts(p, q, t, lambda);
+        }
+    }
+
+    fn from_parts(p: BigUint, q: BigUint, t: BigUint, lambda: BigUint) -> Self {
+        let one = BigUint::from(1u32);
+        let n = &p * &q;
+        let phi = (&p - &one) * (&q - &one);
+        // `phi` is coprime to `n` for the primes of the same length, so
+        // `phi^-1 = phi^(phi - 1) (mod n)` by Euler's theorem.
+        let mu = phi.modpow(&(&phi - &one), &n);
+        let s = t.modpow(&lambda, &n);
+        SecretKey {
+            public: PublicKey::new(n, s, t),
+            p,
+            q,
+            phi,
+            mu,
+            lambda,
+        }
+    }
+
+    pub fn public(&self) -> &PublicKey { &self.public }
+
+    pub fn p(&self) -> &BigUint { &self.p }
+
+    pub fn q(&self) -> &BigUint { &self.q }
+
+    pub fn phi(&self) -> &BigUint { &self.phi }
+
+    pub fn lambda(&self) -> &BigUint { &self.lambda }
+
+    pub fn decrypt(&self, c: &BigUint) -> Result<BigUint, String> {
+        if !self.public.is_ciphertext(c) {
+            return Err("invalid ciphertext".into());
+        }
+        let u = c.modpow(&self.phi, &self.public.nn);
+        if u == BigUint::from(0u32) {
+            return Err("invalid ciphertext".into());
+        }
+        // `L(u) = (u - 1) / n`.
+        let l = (u - BigUint::from(1u32)) / &self.public.n;
+        Ok(l * &self.mu % &self.public.n)
+    }
+
+    /// Decrypt to a number in `(-n / 2, n / 2]`, so that the negative
+    /// plaintexts keep their sign.
+    pub fn decrypt_signed(&self, c: &BigUint) -> Result<BigInt, String> {
+        let m = self.decrypt(c)?;
+        if m > &self.public.n >> 1usize {
+            Ok(int(&m) - int(&self.public.n))
+        } else {
+            Ok(int(&m))
+        }
+    }
+}
+
+/// A uniformly random number in `[0, bound)`.
+pub fn random_below(bound: &BigUint) -> BigUint {
+    // The extra bytes make the bias of the reduction negligible.
+    let mut bytes = vec![0u8; (bound.bits() + 7) / 8 + 16];
+    OsRng.fill_bytes(&mut bytes);
+    BigUint::from_bytes_be(&bytes) % bound
+}
+
+/// `a` as a signed number.
+pub fn int(a: &BigUint) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, a.clone())
+}
+
+/// `a mod m` in `[0, m)`.
+pub fn modulo(a: &BigInt, m: &BigUint) -> BigUint {
+    let remainder = a % int(m);
+    let remainder = if remainder.sign() == Sign::Minus {
+        remainder + int(m)
+    } else {
+        remainder
+    };
+    remainder
+        .to_biguint()
+        .expect("the remainder is not negative; qed")
+}
+
+/// A random prime of exactly `bits` bits and congruent to 3 mod 4, whose two
+/// top bits are set so that the product of two such primes has exactly
+/// `2 * bits` bits. The modulus of two such primes is a Blum integer, as the
+/// modulus proof requires.
+fn random_prime(bits: usize) -> BigUint {
+    let small_primes = small_primes();
+    let mut bytes = vec![0u8; bits / 8];
+    loop {
+        OsRng.fill_bytes(&mut bytes);
+        bytes[0] |= 0xc0;
+        bytes[bits / 8 - 1] |= 3;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if is_probable_prime(&candidate, &small_primes) {
+            return candidate;
+        }
+    }
+}
+
+fn small_primes() -> Vec<BigUint> {
+    (3..SIEVE_LIMIT)
+        .step_by(2)
+        .filter(|n| {
+            (3..*n)
+                .step_by(2)
+                .take_while(|d| d * d <= *n)
+                .all(|d| n % d != 0)
+        })
+        .map(BigUint::from)
+        .collect()
+}
+
+/// Whether the odd `n` larger than the sieved primes is probably a prime.
+pub fn is_prime(n: &BigUint) -> bool { is_probable_prime(n, &small_primes()) }
+
+/// The Miller-Rabin test of an odd `n` larger than the `small_primes`.
+fn is_probable_prime(n: &BigUint, small_primes: &[BigUint]) -> bool {
+    let zero = BigUint::from(0u32);
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+    if small_primes.iter().any(|p| n % p == zero) {
+        return false;
+    }
+
+    let n_minus_one = n - &one;
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while &d % &two == zero {
+        d >>= 1usize;
+        s += 1;
+    }
+    'witness: for _ in 0..MILLER_RABIN_ROUNDS {
+        let a = random_below(&(n - BigUint::from(3u32))) + &two;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+pub fn to_hex(n: &BigUint) -> String { n.to_str_radix(16) }
+
+pub fn from_hex(s: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(s.as_bytes(), 16)
+        .ok_or_else(|| format!("invalid number {}", s))
+}
+
+/// The serialization of a `BigUint` as a hex string, for `#[serde(with)]`.
+pub mod hex {
+    use num_bigint::BigUint;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(n: &BigUint, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&super::to_hex(n))
+    }
+
+    pub fn deserialize<'a, D>(deserializer: D) -> Result<BigUint, D::Error>
+    where D: Deserializer<'a> {
+        super::from_hex(&String::deserialize(deserializer)?)
+            .map_err(Error::custom)
+    }
+}
+
+/// The serialization of a `BigInt` as a hex string, for `#[serde(with)]`.
+pub mod signed_hex {
+    use num_bigint::BigInt;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(n: &BigInt, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&n.to_str_radix(16))
+    }
+
+    pub fn deserialize<'a, D>(deserializer: D) -> Result<BigInt, D::Error>
+    where D: Deserializer<'a> {
+        let s = String::deserialize(deserializer)?;
+        BigInt::parse_bytes(s.as_bytes(), 16)
+            .ok_or_else(|| Error::custom(format!("invalid number {}", s)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Modulus {
+    #[serde(with = "hex")]
+    n: BigUint,
+    #[serde(with = "hex")]
+    s: BigUint,
+    #[serde(with = "hex")]
+    t: BigUint,
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        Modulus {
+            n: self.n.clone(),
+            s: self.s.clone(),
+            t: self.t.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'a> Deserialize<'a> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<PublicKey, D::Error>
+    where D: Deserializer<'a> {
+        let Modulus { n, s, t } = Modulus::deserialize(deserializer)?;
+        // A smaller modulus would overflow in the conversion of the products.
+        let even = &n % BigUint::from(2u32) == BigUint::from(0u32);
+        if n.bits() < MODULUS_BITS || even {
+            return Err(Error::custom("invalid Paillier modulus"));
+        }
+        let zero = BigUint::from(0u32);
+        if s == zero || s >= n || t == zero || t >= n {
+            return Err(Error::custom("invalid ring-Pedersen parameters"));
+        }
+        Ok(PublicKey::new(n, s, t))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Parts {
+    #[serde(with = "hex")]
+    p: BigUint,
+    #[serde(with = "hex")]
+    q: BigUint,
+    #[serde(with = "hex")]
+    t: BigUint,
+    #[serde(with = "hex")]
+    lambda: BigUint,
+}
+
+impl Serialize for SecretKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        Parts {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            t: self.public.t.clone(),
+            lambda: self.lambda.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'a> Deserialize<'a> for SecretKey {
+    fn deserialize<D>(deserializer: D) -> Result<SecretKey, D::Error>
+    where D: Deserializer<'a> {
+        let Parts { p, q, t, lambda } = Parts::deserialize(deserializer)?;
+        Ok(SecretKey::from_parts(p, q, t, lambda))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_homomorphism() {
+        let key = SecretKey::generate();
+        let public = key.public();
+        let a = BigUint::from(1234567u32);
+        let b = random_below(&(BigUint::from(1u32) << 600usize));
+        let k = BigUint::from(89u32);
+
+        let encrypt = |m| public.encrypt_with(m, &public.random_nonce());
+        let sum = public.add(&encrypt(&a), &encrypt(&b));
+        assert_eq!(key.decrypt(&sum).unwrap(), &a + &b);
+        let product = public.mul(&encrypt(&a), &k);
+        assert_eq!(key.decrypt(&product).unwrap(), &a * &k);
+
+        let serialized = serde_json::to_string(&key).unwrap();
+        let restored: SecretKey = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored.decrypt(&sum).unwrap(), &a + &b);
+        assert_eq!(restored.public(), public);
+
+        // The negative plaintexts are decrypted with their signs.
+        let minus = BigInt::from(-1234567i64);
+        let c = public.encrypt_signed(&minus, &public.random_nonce());
+        assert_eq!(key.decrypt_signed(&c).unwrap(), minus);
+    }
+}
@@ -0,0 +1,988 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The zero knowledge proofs which keep a cosigner deviating from the protocol
+//! from learning the secrets of the others, as in CGGMP21 ("UC Non-Interactive,
+//! Proactive, Threshold ECDSA with Identifiable Aborts", Canetti, Gennaro,
+//! Goldfeder, Makriyannis and Peled):
+//! - `ModulusProof` proves that a Paillier modulus is the product of two primes
+//!   congruent to 3 mod 4, and `FactorProof` that neither of them is small.
+//! - `ParametersProof` proves that `s` is in the group generated by `t`, so
+//!   that the ring-Pedersen commitments in the parameters hide the secrets.
+//! - `EncryptionProof` proves that a ciphertext encrypts a number of at most
+//!   `L + EPSILON` bits, e.g. `k_i`.
+//! - `AffineProof` proves that an MtA conversion `D = C^x * Enc(y)` is formed
+//!   with the discrete logarithm `x` of `X` and with `x` and `y` in range.
+//! - `LogProof` proves that `X = x * g` for the `x` encrypted in a ciphertext.
+//!
+//! Without the range proofs, a `k_i` of 1000 bits would make the MtA
+//! conversions of the others leak their secrets modulo `k_i`.
+//!
+//! The proofs are made non-interactive by the Fiat-Shamir transform, whose
+//! challenges are bound to the session and to the cosigners.
+
+use super::{
+    base_mul,
+    paillier::{self, hex, int, modulo, signed_hex, PublicKey, SecretKey},
+    point_add, point_mul, ORDER,
+};
+use cfx_types::{Public, H256};
+use keccak_hash::keccak;
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// `ℓ`, the bits of the secrets, i.e. of the order of the curve.
+const L: usize = 256;
+/// `ℓ'`, the bits of the masks of the MtA conversions.
+pub const L_PRIME: usize = 5 * L;
+/// `ε`, the slack of the proved ranges.
+const EPSILON: usize = 2 * L;
+/// The repetitions of the proofs with binary challenges.
+const REPETITIONS: usize = 80;
+
+/// The session and the cosigners a proof is bound to.
+#[derive(Clone, Copy)]
+pub struct Context {
+    pub session: H256,
+    pub prover: usize,
+    /// The cosigner whose parameters the proof uses, or `None` for the proofs
+    /// to all the cosigners.
+    pub verifier: Option<usize>,
+}
+
+/// `Π^mod`, the proof that the modulus is a Blum integer without a square
+/// factor.
+#[derive(Serialize, Deserialize)]
+pub struct ModulusProof {
+    /// A number whose Jacobi symbol is -1.
+    #[serde(with = "hex")]
+    w: BigUint,
+    rounds: Vec<ModulusRound>,
+}
+
+/// The fourth root `x` of `(-1)^a * w^b * y` and the `n`-th root `z` of `y`,
+/// for a challenge `y`.
+#[derive(Serialize, Deserialize)]
+struct ModulusRound {
+    #[serde(with = "hex")]
+    x: BigUint,
+    a: bool,
+    b: bool,
+    #[serde(with = "hex")]
+    z: BigUint,
+}
+
+impl ModulusProof {
+    pub fn new(context: &Context, key: &SecretKey) -> Self {
+        let (p, q, n) = (key.p(), key.q(), key.public().n());
+        let w = loop {
+            let w = paillier::random_below(n);
+            if is_residue(&w, p) != is_residue(&w, q) {
+                break w;
+            }
+        };
+        let challenges = Transcript::new("modulus", context)
+            .number(n)
+            .number(&w)
+            .residues(n, REPETITIONS);
+        let n_inverse =
+            inverse(n, key.phi()).expect("n is coprime to phi; qed");
+        let rounds = challenges
+            .iter()
+            .map(|y| {
+                // Exactly one of `y`, `-y`, `wy` and `-wy` is a square modulo
+                // both the primes.
+                let (a, b, y_) = [(false, false), (true, false), (false, true)]
+                    .iter()
+                    .map(|(a, b)| (*a, *b, twist(y, *a, *b, &w, n)))
+                    .find(|(_, _, y_)| is_residue(y_, p) && is_residue(y_, q))
+                    .unwrap_or_else(|| {
+                        (true, true, twist(y, true, true, &w, n))
+                    });
+                let x = crt(&fourth_root(&y_, p), &fourth_root(&y_, q), p, q);
+                let z = crt(
+                    &y.modpow(&(&n_inverse % (p - 1u32)), p),
+                    &y.modpow(&(&n_inverse % (q - 1u32)), q),
+                    p,
+                    q,
+                );
+                ModulusRound { x, a, b, z }
+            })
+            .collect();
+        ModulusProof { w, rounds }
+    }
+
+    pub fn verify(&self, context: &Context, key: &PublicKey) -> bool {
+        let n = key.n();
+        // A prime modulus would have the roots as well.
+        if self.rounds.len() != REPETITIONS
+            || self.w >= *n
+            || paillier::is_prime(n)
+        {
+            return false;
+        }
+        let challenges = Transcript::new("modulus", context)
+            .number(n)
+            .number(&self.w)
+            .residues(n, REPETITIONS);
+        let four = BigUint::from(4u32);
+        challenges.iter().zip(&self.rounds).all(|(y, round)| {
+            round.z.modpow(n, n) == *y
+                && round.x.modpow(&four, n)
+                    == twist(y, round.a, round.b, &self.w, n)
+        })
+    }
+}
+
+/// `Π^prm`, the proof that `s` is in the group generated by `t`.
+#[derive(Serialize, Deserialize)]
+pub struct ParametersProof {
+    rounds: Vec<ParametersRound>,
+}
+
+/// The commitment `a = t^alpha` and the response `z = alpha + e * lambda`.
+#[derive(Serialize, Deserialize)]
+struct ParametersRound {
+    #[serde(with = "hex")]
+    a: BigUint,
+    #[serde(with = "hex")]
+    z: BigUint,
+}
+
+impl ParametersProof {
+    pub fn new(context: &Context, key: &SecretKey) -> Self {
+        let public = key.public();
+        let (p, q) = (key.p(), key.q());
+        let alphas: Vec<_> = (0..REPETITIONS)
+            .map(|_| paillier::random_below(key.phi()))
+            .collect();
+        let commitments: Vec<_> = alphas
+            .iter()
+            .map(|alpha| {
+                crt(
+                    &public.t().modpow(&(alpha % (p - 1u32)), p),
+                    &public.t().modpow(&(alpha % (q - 1u32)), q),
+                    p,
+                    q,
+                )
+            })
+            .collect();
+        let challenges = parameters_challenges(context, public, &commitments);
+        let rounds = alphas
+            .into_iter()
+            .zip(commitments)
+            .zip(challenges)
+            .map(|((alpha, a), e)| {
+                let z = if e {
+                    (alpha + key.lambda()) % key.phi()
+                } else {
+                    alpha
+                };
+                ParametersRound { a, z }
+            })
+            .collect();
+        ParametersProof { rounds }
+    }
+
+    pub fn verify(&self, context: &Context, key: &PublicKey) -> bool {
+        if self.rounds.len() != REPETITIONS {
+            return false;
+        }
+        let n = key.n();
+        let commitments: Vec<_> =
+            self.rounds.iter().map(|round| round.a.clone()).collect();
+        let challenges = parameters_challenges(context, key, &commitments);
+        self.rounds.iter().zip(challenges).all(|(round, e)| {
+            let expected = if e {
+                &round.a * key.s() % n
+            } else {
+                round.a.clone()
+            };
+            is_unit(&round.a, n) && key.t().modpow(&round.z, n) == expected
+        })
+    }
+}
+
+fn parameters_challenges(
+    context: &Context, key: &PublicKey, commitments: &[BigUint],
+) -> Vec<bool> {
+    commitments
+        .iter()
+        .fold(
+            Transcript::new("parameters", context)
+                .number(key.n())
+                .number(key.s())
+                .number(key.t()),
+            |transcript, a| transcript.number(a),
+        )
+        .bits(REPETITIONS)
+}
+
+/// `Π^fac`, the proof that the factors of the modulus of the prover have at
+/// least `L` bits, with the parameters of the verifier.
+#[derive(Serialize, Deserialize)]
+pub struct FactorProof {
+    #[serde(with = "hex")]
+    p: BigUint,
+    #[serde(with = "hex")]
+    q: BigUint,
+    #[serde(with = "hex")]
+    a: BigUint,
+    #[serde(with = "hex")]
+    b: BigUint,
+    #[serde(with = "hex")]
+    t: BigUint,
+    #[serde(with = "signed_hex")]
+    sigma: BigInt,
+    #[serde(with = "signed_hex")]
+    z1: BigInt,
+    #[serde(with = "signed_hex")]
+    z2: BigInt,
+    #[serde(with = "signed_hex")]
+    w1: BigInt,
+    #[serde(with = "signed_hex")]
+    w2: BigInt,
+    #[serde(with = "signed_hex")]
+    v: BigInt,
+}
+
+impl FactorProof {
+    pub fn new(
+        context: &Context, key: &SecretKey, verifier: &PublicKey,
+    ) -> Result<Self, String> {
+        let n0 = key.public().n();
+        let n = verifier.n();
+        let sqrt_n0 = n0.sqrt();
+        let alpha = random_signed(L + EPSILON, &sqrt_n0);
+        let beta = random_signed(L + EPSILON, &sqrt_n0);
+        let mu = random_signed(L, n);
+        let nu = random_signed(L, n);
+        let sigma = random_signed(L, &(n0 * n));
+        let r = random_signed(L + EPSILON, &(n0 * n));
+        let x = random_signed(L + EPSILON, n);
+        let y = random_signed(L + EPSILON, n);
+        let (p, q) = (int(key.p()), int(key.q()));
+
+        let p_ = commit(verifier, &p, &mu)?;
+        let q_ = commit(verifier, &q, &nu)?;
+        let a = commit(verifier, &alpha, &x)?;
+        let b = commit(verifier, &beta, &y)?;
+        let t = pow(&q_, &alpha, n)? * pow(verifier.t(), &r, n)? % n;
+        let e = factor_challenge(
+            context,
+            key.public(),
+            verifier,
+            &[&p_, &q_, &a, &b, &t],
+            &sigma,
+        );
+
+        let sigma_ = &sigma - &nu * &p;
+        Ok(FactorProof {
+            z1: &alpha + &e * &p,
+            z2: &beta + &e * &q,
+            w1: &x + &e * &mu,
+            w2: &y + &e * &nu,
+            v: &r + &e * &sigma_,
+            p: p_,
+            q: q_,
+            a,
+            b,
+            t,
+            sigma,
+        })
+    }
+
+    pub fn verify(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+    ) -> bool {
+        self.check(context, key, verifier).unwrap_or(false)
+    }
+
+    fn check(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+    ) -> Result<bool, String> {
+        let n0 = key.n();
+        let n = verifier.n();
+        let sqrt_n0 = n0.sqrt();
+        if !in_range(&self.z1, L + EPSILON, &sqrt_n0)
+            || !in_range(&self.z2, L + EPSILON, &sqrt_n0)
+            || [&self.p, &self.q, &self.a, &self.b, &self.t]
+                .iter()
+                .any(|c| !is_unit(c, n))
+        {
+            return Ok(false);
+        }
+        let e = factor_challenge(
+            context,
+            key,
+            verifier,
+            &[&self.p, &self.q, &self.a, &self.b, &self.t],
+            &self.sigma,
+        );
+        let r = commit(verifier, &int(n0), &self.sigma)?;
+        Ok(commit(verifier, &self.z1, &self.w1)?
+            == &self.a * pow(&self.p, &e, n)? % n
+            && commit(verifier, &self.z2, &self.w2)?
+                == &self.b * pow(&self.q, &e, n)? % n
+            && pow(&self.q, &self.z1, n)? * pow(verifier.t(), &self.v, n)? % n
+                == &self.t * pow(&r, &e, n)? % n)
+    }
+}
+
+fn factor_challenge(
+    context: &Context, key: &PublicKey, verifier: &PublicKey,
+    commitments: &[&BigUint], sigma: &BigInt,
+) -> BigInt
+{
+    commitments
+        .iter()
+        .fold(
+            Transcript::new("factor", context)
+                .number(key.n())
+                .parameters(verifier),
+            |transcript, c| transcript.number(c),
+        )
+        .signed(sigma)
+        .challenge()
+}
+
+/// `Π^enc`, the proof that the ciphertext `k` under the key of the prover
+/// encrypts a number of at most `L + EPSILON` bits, with the parameters of the
+/// verifier.
+#[derive(Serialize, Deserialize)]
+pub struct EncryptionProof {
+    #[serde(with = "hex")]
+    s: BigUint,
+    #[serde(with = "hex")]
+    a: BigUint,
+    #[serde(with = "hex")]
+    c: BigUint,
+    #[serde(with = "signed_hex")]
+    z1: BigInt,
+    #[serde(with = "hex")]
+    z2: BigUint,
+    #[serde(with = "signed_hex")]
+    z3: BigInt,
+}
+
+impl EncryptionProof {
+    /// Prove that `k` is encrypted to `ciphertext` with the nonce `rho`.
+    pub fn new(
+        context: &Context, key: &PublicKey, verifier: &PublicKey,
+        ciphertext: &BigUint, k: &BigUint, rho: &BigUint,
+    ) -> Result<Self, String>
+    {
+        let n = verifier.n();
+        let alpha = random_signed(L + EPSILON, &BigUint::from(1u32));
+        let mu = random_signed(L, n);
+        let r = key.random_nonce();
+        let gamma = random_signed(L + EPSILON, n);
+
+        let s = commit(verifier, &int(k), &mu)?;
+        let a = key.encrypt_signed(&alpha, &r);
+        let c = commit(verifier, &alpha, &gamma)?;
+        let e = Transcript::new("encryption", context)
+            .number(key.n())
+            .parameters(verifier)
+            .number(ciphertext)
+            .number(&s)
+            .number(&a)
+            .number(&c)
+            .challenge();
+        Ok(EncryptionProof {
+            z1: &alpha + &e * int(k),
+            z2: r * pow(rho, &e, key.n())? % key.n(),
+            z3: &gamma + &e * &mu,
+            s,
+            a,
+            c,
+        })
+    }
+
+    pub fn verify(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+        ciphertext: &BigUint,
+    ) -> bool
+    {
+        self.check(context, key, verifier, ciphertext)
+            .unwrap_or(false)
+    }
+
+    fn check(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+        ciphertext: &BigUint,
+    ) -> Result<bool, String>
+    {
+        let n = verifier.n();
+        if !in_range(&self.z1, L + EPSILON, &BigUint::from(1u32))
+            || !is_unit(&self.s, n)
+            || !is_unit(&self.c, n)
+            || !is_unit(&self.a, key.nn())
+            || !is_unit(&self.z2, key.n())
+        {
+            return Ok(false);
+        }
+        let e = Transcript::new("encryption", context)
+            .number(key.n())
+            .parameters(verifier)
+            .number(ciphertext)
+            .number(&self.s)
+            .number(&self.a)
+            .number(&self.c)
+            .challenge();
+        Ok(key.encrypt_signed(&self.z1, &self.z2)
+            == &self.a * pow(ciphertext, &e, key.nn())? % key.nn()
+            && commit(verifier, &self.z1, &self.z3)?
+                == &self.c * pow(&self.s, &e, n)? % n)
+    }
+}
+
+/// `Π^aff-g`, the proof that `d = c^x * Enc(y; rho)` under the key of the
+/// verifier, with `X = x * G`, `x` of at most `L + EPSILON` bits and `y` of at
+/// most `L_PRIME + EPSILON` bits. Unlike in CGGMP21, the encryption of `y`
+/// under the key of the prover is not proved, as the prover keeps `-y` itself.
+#[derive(Serialize, Deserialize)]
+pub struct AffineProof {
+    #[serde(with = "hex")]
+    a: BigUint,
+    b_x: Public,
+    #[serde(with = "hex")]
+    e: BigUint,
+    #[serde(with = "hex")]
+    s: BigUint,
+    #[serde(with = "hex")]
+    f: BigUint,
+    #[serde(with = "hex")]
+    t: BigUint,
+    #[serde(with = "signed_hex")]
+    z1: BigInt,
+    #[serde(with = "signed_hex")]
+    z2: BigInt,
+    #[serde(with = "signed_hex")]
+    z3: BigInt,
+    #[serde(with = "signed_hex")]
+    z4: BigInt,
+    #[serde(with = "hex")]
+    w: BigUint,
+}
+
+/// The statement of an `AffineProof`.
+pub struct Affine<'a> {
+    /// The ciphertext of the verifier.
+    pub c: &'a BigUint,
+    /// The converted ciphertext.
+    pub d: &'a BigUint,
+    pub x: &'a Public,
+}
+
+impl AffineProof {
+    pub fn new(
+        context: &Context, verifier: &PublicKey, statement: &Affine,
+        x: &BigUint, y: &BigUint, rho: &BigUint,
+    ) -> Result<Self, String>
+    {
+        let one = BigUint::from(1u32);
+        let (n, nn) = (verifier.n(), verifier.nn());
+        let alpha = random_signed(L + EPSILON, &one);
+        let beta = random_signed(L_PRIME + EPSILON, &one);
+        let r = verifier.random_nonce();
+        let gamma = random_signed(L + EPSILON, n);
+        let m = random_signed(L, n);
+        let delta = random_signed(L + EPSILON, n);
+        let mu = random_signed(L, n);
+
+        let a = pow(statement.c, &alpha, nn)?
+            * verifier.encrypt_signed(&beta, &r)
+            % nn;
+        let b_x = base_mul(&modulo(&alpha, &ORDER))?;
+        let e_ = commit(verifier, &alpha, &gamma)?;
+        let s = commit(verifier, &int(x), &m)?;
+        let f = commit(verifier, &beta, &delta)?;
+        let t = commit(verifier, &int(y), &mu)?;
+        let e = affine_challenge(
+            context,
+            verifier,
+            statement,
+            &a,
+            &b_x,
+            &[&e_, &s, &f, &t],
+        );
+        Ok(AffineProof {
+            z1: &alpha + &e * int(x),
+            z2: &beta + &e * int(y),
+            z3: &gamma + &e * &m,
+            z4: &delta + &e * &mu,
+            w: r * pow(rho, &e, n)? % n,
+            a,
+            b_x,
+            e: e_,
+            s,
+            f,
+            t,
+        })
+    }
+
+    pub fn verify(
+        &self, context: &Context, verifier: &PublicKey, statement: &Affine,
+    ) -> bool {
+        self.check(context, verifier, statement).unwrap_or(false)
+    }
+
+    fn check(
+        &self, context: &Context, verifier: &PublicKey, statement: &Affine,
+    ) -> Result<bool, String> {
+        let one = BigUint::from(1u32);
+        let (n, nn) = (verifier.n(), verifier.nn());
+        if !in_range(&self.z1, L + EPSILON, &one)
+            || !in_range(&self.z2, L_PRIME + EPSILON, &one)
+            || !is_unit(&self.a, nn)
+            || !is_unit(&self.w, n)
+            || [&self.e, &self.s, &self.f, &self.t]
+                .iter()
+                .any(|c| !is_unit(c, n))
+        {
+            return Ok(false);
+        }
+        let e = affine_challenge(
+            context,
+            verifier,
+            statement,
+            &self.a,
+            &self.b_x,
+            &[&self.e, &self.s, &self.f, &self.t],
+        );
+        let z1_g = base_mul(&modulo(&self.z1, &ORDER))?;
+        let e_x = point_mul(statement.x, &modulo(&e, &ORDER))?;
+        Ok(pow(statement.c, &self.z1, nn)?
+            * verifier.encrypt_signed(&self.z2, &self.w)
+            % nn
+            == &self.a * pow(statement.d, &e, nn)? % nn
+            && z1_g == point_add(&self.b_x, &e_x)?
+            && commit(verifier, &self.z1, &self.z3)?
+                == &self.e * pow(&self.s, &e, n)? % n
+            && commit(verifier, &self.z2, &self.z4)?
+                == &self.f * pow(&self.t, &e, n)? % n)
+    }
+}
+
+fn affine_challenge(
+    context: &Context, verifier: &PublicKey, statement: &Affine, a: &BigUint,
+    b_x: &Public, commitments: &[&BigUint],
+) -> BigInt
+{
+    commitments
+        .iter()
+        .fold(
+            Transcript::new("affine", context)
+                .parameters(verifier)
+                .number(statement.c)
+                .number(statement.d)
+                .point(statement.x)
+                .number(a)
+                .point(b_x),
+            |transcript, c| transcript.number(c),
+        )
+        .challenge()
+}
+
+/// `Π^log*`, the proof that `X = x * g` for the `x` encrypted to the
+/// ciphertext `c` under the key of the prover, with `x` of at most
+/// `L + EPSILON` bits.
+#[derive(Serialize, Deserialize)]
+pub struct LogProof {
+    #[serde(with = "hex")]
+    s: BigUint,
+    #[serde(with = "hex")]
+    a: BigUint,
+    y: Public,
+    #[serde(with = "hex")]
+    d: BigUint,
+    #[serde(with = "signed_hex")]
+    z1: BigInt,
+    #[serde(with = "hex")]
+    z2: BigUint,
+    #[serde(with = "signed_hex")]
+    z3: BigInt,
+}
+
+/// The statement of a `LogProof`.
+pub struct Log<'a> {
+    pub c: &'a BigUint,
+    pub g: &'a Public,
+    pub x: &'a Public,
+}
+
+impl LogProof {
+    pub fn new(
+        context: &Context, key: &PublicKey, verifier: &PublicKey,
+        statement: &Log, x: &BigUint, rho: &BigUint,
+    ) -> Result<Self, String>
+    {
+        let n = verifier.n();
+        let alpha = random_signed(L + EPSILON, &BigUint::from(1u32));
+        let mu = random_signed(L, n);
+        let r = key.random_nonce();
+        let gamma = random_signed(L + EPSILON, n);
+
+        let s = commit(verifier, &int(x), &mu)?;
+        let a = key.encrypt_signed(&alpha, &r);
+        let y = point_mul(statement.g, &modulo(&alpha, &ORDER))?;
+        let d = commit(verifier, &alpha, &gamma)?;
+        let e =
+            log_challenge(context, key, verifier, statement, &s, &a, &y, &d);
+        Ok(LogProof {
+            z1: &alpha + &e * int(x),
+            z2: r * pow(rho, &e, key.n())? % key.n(),
+            z3: &gamma + &e * &mu,
+            s,
+            a,
+            y,
+            d,
+        })
+    }
+
+    pub fn verify(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+        statement: &Log,
+    ) -> bool
+    {
+        self.check(context, key, verifier, statement)
+            .unwrap_or(false)
+    }
+
+    fn check(
+        &self, context: &Context, key: &PublicKey, verifier: &PublicKey,
+        statement: &Log,
+    ) -> Result<bool, String>
+    {
+        let n = verifier.n();
+        if !in_range(&self.z1, L + EPSILON, &BigUint::from(1u32))
+            || !is_unit(&self.s, n)
+            || !is_unit(&self.d, n)
+            || !is_unit(&self.a, key.nn())
+            || !is_unit(&self.z2, key.n())
+        {
+            return Ok(false);
+        }
+        let e = log_challenge(
+            context, key, verifier, statement, &self.s, &self.a, &self.y,
+            &self.d,
+        );
+        let z1_g = point_mul(statement.g, &modulo(&self.z1, &ORDER))?;
+        let e_x = point_mul(statement.x, &modulo(&e, &ORDER))?;
+        Ok(key.encrypt_signed(&self.z1, &self.z2)
+            == &self.a * pow(statement.c, &e, key.nn())? % key.nn()
+            && z1_g == point_add(&self.y, &e_x)?
+            && commit(verifier, &self.z1, &self.z3)?
+                == &self.d * pow(&self.s, &e, n)? % n)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn log_challenge(
+    context: &Context, key: &PublicKey, verifier: &PublicKey, statement: &Log,
+    s: &BigUint, a: &BigUint, y: &Public, d: &BigUint,
+) -> BigInt
+{
+    Transcript::new("log", context)
+        .number(key.n())
+        .parameters(verifier)
+        .number(statement.c)
+        .point(statement.g)
+        .point(statement.x)
+        .number(s)
+        .number(a)
+        .point(y)
+        .number(d)
+        .challenge()
+}
+
+/// The data hashed into the challenges of a proof.
+struct Transcript(Vec<u8>);
+
+impl Transcript {
+    fn new(tag: &str, context: &Context) -> Self {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(tag.len() as u64).to_be_bytes());
+        data.extend_from_slice(tag.as_bytes());
+        data.extend_from_slice(context.session.as_bytes());
+        data.extend_from_slice(&(context.prover as u64).to_be_bytes());
+        let verifier = context.verifier.map_or(u64::MAX, |v| v as u64);
+        data.extend_from_slice(&verifier.to_be_bytes());
+        Transcript(data)
+    }
+
+    fn number(mut self, n: &BigUint) -> Self {
+        let bytes = n.to_bytes_be();
+        self.0
+            .extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+        self.0.extend_from_slice(&bytes);
+        self
+    }
+
+    fn signed(mut self, n: &BigInt) -> Self {
+        self.0.push((n.sign() == Sign::Minus) as u8);
+        self.number(&magnitude(n))
+    }
+
+    fn point(mut self, point: &Public) -> Self {
+        self.0.extend_from_slice(point.as_bytes());
+        self
+    }
+
+    fn parameters(self, key: &PublicKey) -> Self {
+        self.number(key.n()).number(key.s()).number(key.t())
+    }
+
+    /// `len` bytes derived from the transcript.
+    fn bytes(&self, len: usize) -> Vec<u8> {
+        let seed = keccak(&self.0);
+        let mut bytes = Vec::with_capacity(len + 32);
+        let mut counter = 0u64;
+        while bytes.len() < len {
+            let mut block = seed.as_bytes().to_vec();
+            block.extend_from_slice(&counter.to_be_bytes());
+            bytes.extend_from_slice(keccak(&block).as_bytes());
+            counter += 1;
+        }
+        bytes.truncate(len);
+        bytes
+    }
+
+    /// The challenge in `±q`.
+    fn challenge(&self) -> BigInt {
+        let q = &*ORDER;
+        let e = BigUint::from_bytes_be(&self.bytes(48)) % (q * 2u32 + 1u32);
+        int(&e) - int(q)
+    }
+
+    /// `count` challenges in `[0, n)`.
+    fn residues(&self, n: &BigUint, count: usize) -> Vec<BigUint> {
+        let len = (n.bits() + 7) / 8 + 16;
+        self.bytes(len * count)
+            .chunks(len)
+            .map(|chunk| BigUint::from_bytes_be(chunk) % n)
+            .collect()
+    }
+
+    /// `count` binary challenges.
+    fn bits(&self, count: usize) -> Vec<bool> {
+        let bytes = self.bytes((count + 7) / 8);
+        (0..count)
+            .map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+            .collect()
+    }
+}
+
+/// A uniformly random number in `±2^bits * factor`.
+fn random_signed(bits: usize, factor: &BigUint) -> BigInt {
+    let bound = factor << bits;
+    int(&paillier::random_below(&((&bound << 1usize) + 1u32))) - int(&bound)
+}
+
+/// `|n|`.
+fn magnitude(n: &BigInt) -> BigUint {
+    BigUint::from_bytes_be(&n.to_bytes_be().1)
+}
+
+/// Whether `|z| <= 2^bits * factor`.
+fn in_range(z: &BigInt, bits: usize, factor: &BigUint) -> bool {
+    magnitude(z) <= factor << bits
+}
+
+/// Whether `a` is in `[1, m)` and coprime to `m`.
+fn is_unit(a: &BigUint, m: &BigUint) -> bool {
+    *a < *m && inverse(a, m).is_some()
+}
+
+/// `base^exponent mod m`, whose negative exponents need the inverse of `base`.
+fn pow(
+    base: &BigUint, exponent: &BigInt, m: &BigUint,
+) -> Result<BigUint, String> {
+    let result = base.modpow(&magnitude(exponent), m);
+    if exponent.sign() == Sign::Minus {
+        inverse(&result, m).ok_or_else(|| "no inverse of the base".into())
+    } else {
+        Ok(result)
+    }
+}
+
+/// The ring-Pedersen commitment `s^x * t^y mod n` in the parameters of `key`.
+fn commit(key: &PublicKey, x: &BigInt, y: &BigInt) -> Result<BigUint, String> {
+    Ok(pow(key.s(), x, key.n())? * pow(key.t(), y, key.n())? % key.n())
+}
+
+/// The inverse of `a` modulo `m` by the extended Euclidean algorithm, if they
+/// are coprime.
+fn inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let zero = BigInt::from(0);
+    let (mut r0, mut r1) = (int(m), int(&(a % m)));
+    let (mut t0, mut t1) = (zero.clone(), BigInt::from(1));
+    while r1 != zero {
+        let quotient = &r0 / &r1;
+        let r2 = &r0 - &quotient * &r1;
+        r0 = r1;
+        r1 = r2;
+        let t2 = &t0 - &quotient * &t1;
+        t0 = t1;
+        t1 = t2;
+    }
+    if r0 != BigInt::from(1) {
+        return None;
+    }
+    Some(modulo(&t0, m))
+}
+
+/// Whether `a` is a nonzero square modulo the prime `p`, by Euler's criterion.
+fn is_residue(a: &BigUint, p: &BigUint) -> bool {
+    a.modpow(&((p - 1u32) >> 1usize), p) == BigUint::from(1u32)
+}
+
+/// `(-1)^a * w^b * y mod n`.
+fn twist(y: &BigUint, a: bool, b: bool, w: &BigUint, n: &BigUint) -> BigUint {
+    let mut result = y % n;
+    if a {
+        result = (n - result) % n;
+    }
+    if b {
+        result = result * w % n;
+    }
+    result
+}
+
+/// The fourth root of the square `a` modulo the prime `p`, congruent to 3 mod
+/// 4, which is `a^((p + 1) / 4)^2`.
+fn fourth_root(a: &BigUint, p: &BigUint) -> BigUint {
+    let e = (p + 1u32) >> 2usize;
+    a.modpow(&(&e * &e % (p - 1u32)), p)
+}
+
+/// The number modulo `p * q` which is `a` modulo `p` and `b` modulo `q`.
+fn crt(a: &BigUint, b: &BigUint, p: &BigUint, q: &BigUint) -> BigUint {
+    let p_inverse = inverse(p, q).expect("the primes are distinct; qed");
+    let difference = (b + q - a % q) % q;
+    a + p * (difference * p_inverse % q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threshold_cosigner::random_scalar;
+
+    fn context(prover: usize, verifier: Option<usize>) -> Context {
+        Context {
+            session: H256::random(),
+            prover,
+            verifier,
+        }
+    }
+
+    #[test]
+    fn test_key_proofs() {
+        let key = SecretKey::generate();
+        let other = SecretKey::generate();
+        let context = context(0, None);
+
+        let proof = ModulusProof::new(&context, &key);
+        assert!(proof.verify(&context, key.public()));
+        assert!(!proof.verify(&context, other.public()));
+        let proof = ParametersProof::new(&context, &key);
+        assert!(proof.verify(&context, key.public()));
+        assert!(!proof.verify(&context, other.public()));
+
+        let context = Context {
+            verifier: Some(1),
+            ..context
+        };
+        let proof = FactorProof::new(&context, &key, other.public()).unwrap();
+        assert!(proof.verify(&context, key.public(), other.public()));
+        let other_context = Context {
+            prover: 2,
+            ..context
+        };
+        assert!(!proof.verify(&other_context, key.public(), other.public()));
+    }
+
+    #[test]
+    fn test_range_proofs() {
+        let key = SecretKey::generate();
+        let verifier = SecretKey::generate();
+        let (public, verifier) = (key.public(), verifier.public());
+        let context = context(0, Some(1));
+
+        let k = random_scalar();
+        let rho = public.random_nonce();
+        let ciphertext = public.encrypt_with(&k, &rho);
+        let proof = EncryptionProof::new(
+            &context,
+            public,
+            verifier,
+            &ciphertext,
+            &k,
+            &rho,
+        )
+        .unwrap();
+        assert!(proof.verify(&context, public, verifier, &ciphertext));
+
+        // An oversized `k` is rejected.
+        let oversized = BigUint::from(1u32) << 1000usize;
+        let ciphertext = public.encrypt_with(&oversized, &rho);
+        let proof = EncryptionProof::new(
+            &context,
+            public,
+            verifier,
+            &ciphertext,
+            &oversized,
+            &rho,
+        )
+        .unwrap();
+        assert!(!proof.verify(&context, public, verifier, &ciphertext));
+
+        // `k * G` from the encrypted `k`.
+        let ciphertext = public.encrypt_with(&k, &rho);
+        let g = base_mul(&random_scalar()).unwrap();
+        let x = point_mul(&g, &k).unwrap();
+        let statement = Log {
+            c: &ciphertext,
+            g: &g,
+            x: &x,
+        };
+        let proof =
+            LogProof::new(&context, public, verifier, &statement, &k, &rho)
+                .unwrap();
+        assert!(proof.verify(&context, public, verifier, &statement));
+        let wrong = Log { x: &g, ..statement };
+        assert!(!proof.verify(&context, public, verifier, &wrong));
+    }
+
+    #[test]
+    fn test_affine_proof() {
+        let verifier = SecretKey::generate();
+        let public = verifier.public();
+        let context = context(0, Some(1));
+
+        let c = public.encrypt_with(&random_scalar(), &public.random_nonce());
+        let x = random_scalar();
+        let y = paillier::random_below(&(BigUint::from(1u32) << L_PRIME));
+        let rho = public.random_nonce();
+        let d = public.add(&public.mul(&c, &x), &public.encrypt_with(&y, &rho));
+        let x_point = base_mul(&x).unwrap();
+        let statement = Affine {
+            c: &c,
+            d: &d,
+            x: &x_point,
+        };
+        let proof =
+            AffineProof::new(&context, public, &statement, &x, &y, &rho)
+                .unwrap();
+        assert!(proof.verify(&context, public, &statement));
+
+        // The conversion with another `x` is rejected.
+        let other = base_mul(&random_scalar()).unwrap();
+        let wrong = Affine {
+            x: &other,
+            ..statement
+        };
+        assert!(!proof.verify(&context, public, &wrong));
+    }
+}
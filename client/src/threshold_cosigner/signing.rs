@@ -0,0 +1,384 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The signing with the shares of a key. In round 0 every cosigner sends `k_i`
+//! encrypted with its Paillier key, in round 1 it sends `gamma_i * G` and the
+//! MtA conversions to the others, in round 2 it reveals `delta_i` and
+//! `k_i * Gamma`, and in round 3 it returns its share of `s`. Every message
+//! carries the proofs to each other cosigner that it follows the protocol.
+
+use super::{
+    base_mul,
+    paillier::{self, hex},
+    point_add, point_mul,
+    proofs::{
+        Affine, AffineProof, Context, EncryptionProof, Log, LogProof, L_PRIME,
+    },
+    random_scalar, KeyShare, ORDER,
+};
+use cfx_types::{Public, H256};
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+
+/// The message of a cosigner in round 0, with the proofs that `k_i` is in
+/// range with the parameters of each other cosigner.
+#[derive(Serialize, Deserialize)]
+pub struct Encryption {
+    /// `k_i` encrypted with the Paillier key of the cosigner.
+    #[serde(with = "hex")]
+    k: BigUint,
+    proofs: BTreeMap<usize, EncryptionProof>,
+}
+
+/// The message of a cosigner in round 1.
+#[derive(Serialize, Deserialize)]
+pub struct Conversions {
+    gamma: Public,
+    conversions: BTreeMap<usize, Conversion>,
+}
+
+/// `k_j * gamma_i + beta_ji` and `k_j * w_i + nu_ji` encrypted with the
+/// Paillier key of another cosigner `j`, with the proofs that they are formed
+/// with `gamma_i * G` and `w_i * G`.
+#[derive(Serialize, Deserialize)]
+struct Conversion {
+    #[serde(with = "hex")]
+    gamma: BigUint,
+    gamma_proof: AffineProof,
+    #[serde(with = "hex")]
+    w: BigUint,
+    w_proof: AffineProof,
+}
+
+/// The message of a cosigner in round 2, with the proofs that `k_gamma` is
+/// formed with the encrypted `k_i`.
+#[derive(Serialize, Deserialize)]
+pub struct Reveal {
+    delta: H256,
+    /// `k_i * Gamma`.
+    k_gamma: Public,
+    proofs: BTreeMap<usize, LogProof>,
+}
+
+pub struct Signing {
+    key: KeyShare,
+    session: H256,
+    message: BigUint,
+    k: BigUint,
+    /// The nonce of the encryption of `k_i`.
+    nonce: BigUint,
+    gamma: BigUint,
+    /// The encrypted `k_j` of the cosigners in the session.
+    ks: BTreeMap<usize, BigUint>,
+    /// `Gamma`, the sum of `gamma_j * G`.
+    gamma_sum: Option<Public>,
+    /// The additive share of `k * gamma`.
+    delta: BigUint,
+    /// The additive share of `k * x`.
+    sigma: BigUint,
+}
+
+impl Signing {
+    pub fn start(
+        session: H256, key: KeyShare, message: &H256,
+    ) -> Result<(Signing, Encryption), String> {
+        let k = random_scalar();
+        let public = key.paillier.public();
+        let nonce = public.random_nonce();
+        let ciphertext = public.encrypt_with(&k, &nonce);
+        let mut proofs = BTreeMap::new();
+        for (j, verifier) in key.paillier_keys.iter().enumerate() {
+            if j != key.index {
+                let context = context(session, key.index, j);
+                let proof = EncryptionProof::new(
+                    &context,
+                    public,
+                    verifier,
+                    &ciphertext,
+                    &k,
+                    &nonce,
+                )?;
+                proofs.insert(j, proof);
+            }
+        }
+        let signing = Signing {
+            session,
+            message: BigUint::from_bytes_be(message.as_bytes()) % &*ORDER,
+            k,
+            nonce,
+            gamma: random_scalar(),
+            ks: BTreeMap::new(),
+            gamma_sum: None,
+            delta: BigUint::from(0u32),
+            sigma: BigUint::from(0u32),
+            key,
+        };
+        let encryption = Encryption {
+            k: ciphertext,
+            proofs,
+        };
+        Ok((signing, encryption))
+    }
+
+    pub fn convert(
+        &mut self, encryptions: BTreeMap<usize, Encryption>,
+    ) -> Result<Conversions, String> {
+        if encryptions.len() < self.key.threshold {
+            return Err(format!(
+                "{} cosigners are needed, but {} are in the session",
+                self.key.threshold,
+                encryptions.len()
+            ));
+        }
+        let index = self.key.index;
+        for (j, encryption) in &encryptions {
+            if *j == index {
+                continue;
+            }
+            let paillier = self
+                .key
+                .paillier_keys
+                .get(*j)
+                .ok_or_else(|| format!("unknown cosigner {}", j))?;
+            let valid = paillier.is_ciphertext(&encryption.k)
+                && encryption.proofs.get(&index).map_or(false, |proof| {
+                    proof.verify(
+                        &context(self.session, *j, index),
+                        paillier,
+                        self.key.paillier.public(),
+                        &encryption.k,
+                    )
+                });
+            if !valid {
+                return Err(format!("invalid k of cosigner {}", j));
+            }
+        }
+        self.ks = encryptions
+            .into_iter()
+            .map(|(j, encryption)| (j, encryption.k))
+            .collect();
+
+        let q = &*ORDER;
+        // The additive share of the key among the cosigners in the session.
+        let share = BigUint::from_bytes_be(self.key.share.as_bytes());
+        let w = lagrange(index, self.ks.keys()) * share % q;
+        let gamma_point = base_mul(&self.gamma)?;
+        let w_point = base_mul(&w)?;
+
+        self.delta = &self.k * &self.gamma % q;
+        self.sigma = &self.k * &w % q;
+        let bound = BigUint::from(1u32) << L_PRIME;
+        let mut conversions = BTreeMap::new();
+        for (j, k) in &self.ks {
+            if *j == index {
+                continue;
+            }
+            let paillier = &self.key.paillier_keys[*j];
+            let context = context(self.session, index, *j);
+            // The other cosigner gets `k_j * gamma_i + beta` and this one
+            // keeps `-beta`, and so for `w_i` and `nu`.
+            let mta = |x: &BigUint, point: &Public| {
+                let mask = paillier::random_below(&bound);
+                let nonce = paillier.random_nonce();
+                let d = paillier.add(
+                    &paillier.mul(k, x),
+                    &paillier.encrypt_with(&mask, &nonce),
+                );
+                let statement = Affine {
+                    c: k,
+                    d: &d,
+                    x: point,
+                };
+                let proof = AffineProof::new(
+                    &context, paillier, &statement, x, &mask, &nonce,
+                )?;
+                Ok::<_, String>((d, proof, mask))
+            };
+            let (gamma, gamma_proof, beta) = mta(&self.gamma, &gamma_point)?;
+            let (w, w_proof, nu) = mta(&w, &w_point)?;
+            self.delta = (&self.delta + q - beta % q) % q;
+            self.sigma = (&self.sigma + q - nu % q) % q;
+            conversions.insert(
+                *j,
+                Conversion {
+                    gamma,
+                    gamma_proof,
+                    w,
+                    w_proof,
+                },
+            );
+        }
+        Ok(Conversions {
+            gamma: gamma_point,
+            conversions,
+        })
+    }
+
+    pub fn reveal(
+        &mut self, conversions: BTreeMap<usize, Conversions>,
+    ) -> Result<Reveal, String> {
+        if !conversions.keys().eq(self.ks.keys()) {
+            return Err("the cosigners changed in the session".into());
+        }
+        let q = &*ORDER;
+        let index = self.key.index;
+        let public = self.key.paillier.public();
+        let own_k = &self.ks[&index];
+        let mut gamma_sum: Option<Public> = None;
+        for (j, conversions) in &conversions {
+            gamma_sum = Some(match gamma_sum {
+                None => conversions.gamma,
+                Some(sum) => point_add(&sum, &conversions.gamma)?,
+            });
+            if *j == index {
+                continue;
+            }
+            let conversion = conversions
+                .conversions
+                .get(&index)
+                .ok_or_else(|| format!("no conversion from cosigner {}", j))?;
+            // `w_j * G` from the share of the key of the cosigner.
+            let w_point = point_mul(
+                &self.key.public_shares[*j],
+                &lagrange(*j, self.ks.keys()),
+            )?;
+            let context = context(self.session, *j, index);
+            let gamma_statement = Affine {
+                c: own_k,
+                d: &conversion.gamma,
+                x: &conversions.gamma,
+            };
+            let w_statement = Affine {
+                c: own_k,
+                d: &conversion.w,
+                x: &w_point,
+            };
+            if !conversion.gamma_proof.verify(
+                &context,
+                public,
+                &gamma_statement,
+            ) || !conversion.w_proof.verify(&context, public, &w_statement)
+            {
+                return Err(format!("invalid conversion of cosigner {}", j));
+            }
+            let alpha = self.key.paillier.decrypt_signed(&conversion.gamma)?;
+            let mu = self.key.paillier.decrypt_signed(&conversion.w)?;
+            self.delta = (&self.delta + paillier::modulo(&alpha, q)) % q;
+            self.sigma = (&self.sigma + paillier::modulo(&mu, q)) % q;
+        }
+
+        let gamma_sum = gamma_sum.expect("the cosigner itself converted; qed");
+        let k_gamma = point_mul(&gamma_sum, &self.k)?;
+        let mut proofs = BTreeMap::new();
+        for j in self.ks.keys().filter(|j| **j != index) {
+            let statement = Log {
+                c: own_k,
+                g: &gamma_sum,
+                x: &k_gamma,
+            };
+            let proof = LogProof::new(
+                &context(self.session, index, *j),
+                public,
+                &self.key.paillier_keys[*j],
+                &statement,
+                &self.k,
+                &self.nonce,
+            )?;
+            proofs.insert(*j, proof);
+        }
+        self.gamma_sum = Some(gamma_sum);
+        Ok(Reveal {
+            delta: super::to_h256(&self.delta),
+            k_gamma,
+            proofs,
+        })
+    }
+
+    /// Compute `r` and the share of `s` of the cosigner.
+    pub fn finish(
+        self, reveals: BTreeMap<usize, Reveal>,
+    ) -> Result<(H256, H256), String> {
+        if !reveals.keys().eq(self.ks.keys()) {
+            return Err("the cosigners changed in the session".into());
+        }
+        let q = &*ORDER;
+        let index = self.key.index;
+        let gamma_sum = self.gamma_sum.expect("revealed in round 2; qed");
+        let mut delta = BigUint::from(0u32);
+        let mut k_gamma: Option<Public> = None;
+        for (j, reveal) in &reveals {
+            if *j != index {
+                let statement = Log {
+                    c: &self.ks[j],
+                    g: &gamma_sum,
+                    x: &reveal.k_gamma,
+                };
+                let valid = reveal.proofs.get(&index).map_or(false, |proof| {
+                    proof.verify(
+                        &context(self.session, *j, index),
+                        &self.key.paillier_keys[*j],
+                        self.key.paillier.public(),
+                        &statement,
+                    )
+                });
+                if !valid {
+                    return Err(format!("invalid reveal of cosigner {}", j));
+                }
+            }
+            let delta_j = BigUint::from_bytes_be(reveal.delta.as_bytes());
+            if delta_j >= *q {
+                return Err(format!("invalid delta of cosigner {}", j));
+            }
+            delta = (delta + delta_j) % q;
+            k_gamma = Some(match k_gamma {
+                None => reveal.k_gamma,
+                Some(sum) => point_add(&sum, &reveal.k_gamma)?,
+            });
+        }
+        if delta == BigUint::from(0u32) {
+            return Err("the nonce is zero".into());
+        }
+        // `delta * G = k * Gamma` unless a cosigner lied about its `delta_i`.
+        if Some(base_mul(&delta)?) != k_gamma {
+            return Err("the shares of delta are inconsistent".into());
+        }
+
+        // `R = delta^-1 * Gamma = k^-1 * G`.
+        let point = point_mul(&gamma_sum, &inverse(&delta))?;
+        let r = BigUint::from_bytes_be(&point.as_bytes()[..32]) % q;
+        let s = (&self.message * &self.k + &r * &self.sigma) % q;
+        Ok((super::to_h256(&r), super::to_h256(&s)))
+    }
+}
+
+fn context(session: H256, prover: usize, verifier: usize) -> Context {
+    Context {
+        session,
+        prover,
+        verifier: Some(verifier),
+    }
+}
+
+/// The inverse of a nonzero scalar by Fermat's little theorem.
+fn inverse(value: &BigUint) -> BigUint {
+    let q = &*ORDER;
+    value.modpow(&(q - &BigUint::from(2u32)), q)
+}
+
+/// The Lagrange coefficient of the cosigner `index` to interpolate the
+/// polynomial at 0 from the cosigners `parties`.
+fn lagrange<'a>(
+    index: usize, parties: impl Iterator<Item = &'a usize>,
+) -> BigUint {
+    let q = &*ORDER;
+    let x = BigUint::from(index as u64 + 1);
+    let mut numerator = BigUint::from(1u32);
+    let mut denominator = BigUint::from(1u32);
+    for j in parties.filter(|j| **j != index) {
+        let x_j = BigUint::from(*j as u64 + 1);
+        denominator = denominator * ((&x_j + q - &x) % q) % q;
+        numerator = numerator * x_j % q;
+    }
+    numerator * inverse(&denominator) % q
+}
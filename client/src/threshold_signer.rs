@@ -0,0 +1,419 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A `TransactionSigner` for the accounts whose keys are split among a set of
+//! cosigners with t-of-n threshold ECDSA, so that no single machine holds a
+//! complete key. Each cosigner keeps a share of the keys, and any `threshold`
+//! of the cosigners sign together.
+//!
+//! The node coordinates the sessions of the cosigners. It starts a session
+//! with the first `threshold` cosigners which respond, relays the messages of
+//! each round of the protocol among them, and adds up their shares of the
+//! signature. The messages are opaque to the node, so the cosigners may run
+//! any protocol in which the parties end with the common `r` and additive
+//! shares of `s`. `threshold_cosigner` implements the cosigners with GG18.
+//! The cosigners are called with JSON-RPC over HTTP:
+//! - `threshold_start(session, address, message)` starts a session to sign
+//!   `message` with the key of `address`.
+//! - `threshold_round(session, round, parties, messages)` runs a round with the
+//!   `messages` of the previous round. `parties` are the indexes of the
+//!   cosigners in the session, in the order of the configuration, and
+//!   `messages` are in the same order.
+//!
+//! Both return either `{"message": ...}`, the message of the cosigner in the
+//! next round, or `{"r": ..., "s": ...}`, its share of the signature when the
+//! protocol completes. The combined signature is verified against the
+//! account, so a misbehaving cosigner can fail the signing but not forge a
+//! signature.
+//!
+//! The keys are generated by all the cosigners in the same way, with
+//! `threshold_keygen_start(session, threshold)` and
+//! `threshold_keygen_round(session, round, parties, messages)`, which end
+//! with `{"address": ...}`, the address of the generated key.
+
+use crate::remote_signer::SignerEndpoint;
+use cfx_types::{Address, BigEndianHash, H256, U256};
+use cfxcore_accounts::{SignError, TransactionSigner};
+use cfxkey::{verify_address, Message, Password, Signature};
+use lazy_static::lazy_static;
+use runtime::Runtime;
+use serde_json::{json, Value};
+use std::{str::FromStr, time::Duration};
+
+/// The maximal number of rounds of a session.
+const MAX_ROUNDS: usize = 16;
+
+lazy_static! {
+    static ref CURVE_ORDER: U256 = U256::from_str(
+        "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+    )
+    .expect("valid hex");
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdSignerConfig {
+    /// The HTTP endpoints of the cosigners.
+    pub cosigners: Vec<String>,
+    /// The number of cosigners needed to sign.
+    pub threshold: usize,
+    /// The bearer token to authenticate to the cosigners.
+    pub auth_token: Option<String>,
+    /// The timeout of a request to a cosigner.
+    pub timeout: Duration,
+}
+
+/// The output of a cosigner in a round.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RoundOutput {
+    Share { r: H256, s: H256 },
+    Key { address: Address },
+    Message { message: Value },
+}
+
+pub struct ThresholdSigner {
+    cosigners: Vec<SignerEndpoint>,
+    threshold: usize,
+    /// Runs the requests to all the cosigners.
+    _runtime: Runtime,
+}
+
+impl ThresholdSigner {
+    pub fn new(config: ThresholdSignerConfig) -> Self {
+        let runtime = Runtime::with_thread_count(1);
+        let cosigners = config
+            .cosigners
+            .into_iter()
+            .map(|url| {
+                SignerEndpoint::new(
+                    url,
+                    config.auth_token.clone(),
+                    config.timeout,
+                    runtime.executor(),
+                )
+            })
+            .collect();
+        ThresholdSigner {
+            cosigners,
+            threshold: config.threshold,
+            _runtime: runtime,
+        }
+    }
+
+    /// Generate a key shared among all the cosigners, any `threshold` of
+    /// which can sign with it, and return its address.
+    pub fn generate_key(&self) -> Result<Address, String> {
+        let session = H256::from(rand::random::<[u8; 32]>());
+        let parties: Vec<_> = (0..self.cosigners.len()).collect();
+        let outputs = self
+            .cosigners
+            .iter()
+            .map(|cosigner| {
+                let params = json!([session, self.threshold]);
+                call_round(cosigner, "threshold_keygen_start", params).map_err(
+                    |e| {
+                        format!(
+                            "cosigner {} failed to start the key generation: \
+                             {}",
+                            cosigner.url(),
+                            e
+                        )
+                    },
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut address = None;
+        for output in self.run_rounds(
+            &session,
+            "threshold_keygen_round",
+            &parties,
+            outputs,
+        )? {
+            match output {
+                RoundOutput::Key { address: a } if address.is_none() => {
+                    address = Some(a)
+                }
+                RoundOutput::Key { address: a } if address == Some(a) => {}
+                _ => return Err("the cosigners disagree on the key".into()),
+            }
+        }
+        Ok(address.expect("there is at least one cosigner; qed"))
+    }
+
+    fn run_session(
+        &self, address: Address, message: Message,
+    ) -> Result<Signature, String> {
+        let session = H256::from(rand::random::<[u8; 32]>());
+
+        let mut parties = Vec::new();
+        let mut outputs = Vec::new();
+        for (index, cosigner) in self.cosigners.iter().enumerate() {
+            if parties.len() == self.threshold {
+                break;
+            }
+            let params = json!([session, address, message]);
+            match call_round(cosigner, "threshold_start", params) {
+                Ok(output) => {
+                    parties.push(index);
+                    outputs.push(output);
+                }
+                Err(e) => warn!(
+                    "Cosigner {} failed to start session {:?}: {}",
+                    cosigner.url(),
+                    session,
+                    e
+                ),
+            }
+        }
+        if parties.len() < self.threshold {
+            return Err(format!(
+                "only {} of the {} cosigners needed are available",
+                parties.len(),
+                self.threshold
+            ));
+        }
+
+        let shares = self
+            .run_rounds(&session, "threshold_round", &parties, outputs)?
+            .into_iter()
+            .map(|output| match output {
+                RoundOutput::Share { r, s } => Ok((r, s)),
+                _ => Err("unexpected output of a cosigner".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        combine_shares(&address, &message, &shares)
+    }
+
+    /// Relay the messages among the `parties` of `session` until the protocol
+    /// completes, and return their final outputs.
+    fn run_rounds(
+        &self, session: &H256, method: &str, parties: &[usize],
+        mut outputs: Vec<RoundOutput>,
+    ) -> Result<Vec<RoundOutput>, String> {
+        for round in 1..=MAX_ROUNDS {
+            let messages: Vec<_> = outputs
+                .iter()
+                .filter_map(|output| match output {
+                    RoundOutput::Message { message } => Some(message.clone()),
+                    _ => None,
+                })
+                .collect();
+            if messages.is_empty() {
+                return Ok(outputs);
+            }
+            if messages.len() < outputs.len() {
+                return Err(format!(
+                    "the cosigners disagree on the end of the protocol in \
+                     round {}",
+                    round
+                ));
+            }
+
+            let params = json!([session, round, parties, messages]);
+            outputs = parties
+                .iter()
+                .map(|index| {
+                    let cosigner = &self.cosigners[*index];
+                    call_round(cosigner, method, params.clone()).map_err(|e| {
+                        format!(
+                            "cosigner {} failed in round {}: {}",
+                            cosigner.url(),
+                            round,
+                            e
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        Err(format!("the protocol exceeds {} rounds", MAX_ROUNDS))
+    }
+}
+
+impl TransactionSigner for ThresholdSigner {
+    fn sign(
+        &self, address: Address, _password: Option<Password>, message: Message,
+    ) -> Result<Signature, SignError> {
+        self.run_session(address, message)
+            .map_err(SignError::Remote)
+    }
+}
+
+fn call_round(
+    cosigner: &SignerEndpoint, method: &str, params: Value,
+) -> Result<RoundOutput, String> {
+    let result = cosigner.call(method, params)?;
+    serde_json::from_value(result)
+        .map_err(|e| format!("invalid round output: {}", e))
+}
+
+/// Add up `shares` of `s` into the signature of `message` by `address`.
+fn combine_shares(
+    address: &Address, message: &Message, shares: &[(H256, H256)],
+) -> Result<Signature, String> {
+    let r = shares[0].0;
+    if shares.iter().any(|(share_r, _)| *share_r != r) {
+        return Err("the cosigners disagree on r".into());
+    }
+    let mut s = U256::zero();
+    for (_, share) in shares {
+        let share = share.into_uint();
+        if share >= *CURVE_ORDER {
+            return Err("invalid share of s".into());
+        }
+        s = add_mod(s, share);
+    }
+    // Use the low s, as the signatures with a high s are rejected.
+    if s > *CURVE_ORDER / 2 {
+        s = *CURVE_ORDER - s;
+    }
+
+    let s = H256::from_uint(&s);
+    for v in 0..=1 {
+        let signature = Signature::from_rsv(&r, &s, v);
+        if let Ok(true) = verify_address(address, &signature, message) {
+            return Ok(signature);
+        }
+    }
+    Err(format!("the signature does not belong to {:?}", address))
+}
+
+/// `(a + b) mod n` for `a, b < n`.
+fn add_mod(a: U256, b: U256) -> U256 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= *CURVE_ORDER {
+        sum.overflowing_sub(*CURVE_ORDER).0
+    } else {
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threshold_cosigner::{CosignerConfig, ThresholdCosigner};
+    use cfxkey::{public_to_address, recover, sign, Generator, Random};
+    use jsonrpc_http_server::Server;
+    use std::sync::Arc;
+
+    const AUTH_TOKEN: &str = "token";
+
+    /// Start `n` cosigners without keys.
+    fn start_cosigners(n: usize) -> Vec<Server> {
+        let key_pairs: Vec<_> =
+            (0..n).map(|_| Random.generate().unwrap()).collect();
+        let peers: Vec<_> =
+            key_pairs.iter().map(|key_pair| *key_pair.public()).collect();
+        key_pairs
+            .iter()
+            .enumerate()
+            .map(|(index, key_pair)| {
+                let config = CosignerConfig {
+                    index,
+                    secret: key_pair.secret().clone(),
+                    peers: peers.clone(),
+                    auth_tokens: vec![AUTH_TOKEN.into()],
+                };
+                Arc::new(ThresholdCosigner::new(config, vec![]))
+                    .start_http(&"127.0.0.1:0".parse().unwrap())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn threshold_signer(
+        urls: Vec<String>, threshold: usize,
+    ) -> ThresholdSigner {
+        ThresholdSigner::new(ThresholdSignerConfig {
+            cosigners: urls,
+            threshold,
+            auth_token: Some(AUTH_TOKEN.into()),
+            // The Paillier keys are slow to generate in debug builds.
+            timeout: Duration::from_secs(120),
+        })
+    }
+
+    fn url(server: &Server) -> String { format!("http://{}", server.address()) }
+
+    #[test]
+    fn test_threshold_sign() {
+        let servers = start_cosigners(3);
+        let urls: Vec<_> = servers.iter().map(url).collect();
+        let address = threshold_signer(urls.clone(), 2).generate_key().unwrap();
+
+        // Any 2 of the 3 cosigners sign with their shares of the key.
+        for parties in &[[0usize, 1], [0, 2], [1, 2]] {
+            let urls = parties.iter().map(|i| urls[*i].clone()).collect();
+            let message = H256::random();
+            let signature = threshold_signer(urls, 2)
+                .sign(address, None, message)
+                .unwrap();
+            let public = recover(&signature, &message).unwrap();
+            assert_eq!(public_to_address(&public), address);
+        }
+
+        // The unavailable first cosigner is skipped.
+        let mut all = vec!["http://127.0.0.1:1".to_string()];
+        all.extend(urls.iter().cloned());
+        let message = H256::random();
+        let signature =
+            threshold_signer(all, 2).sign(address, None, message).unwrap();
+        assert!(verify_address(&address, &signature, &message).unwrap());
+
+        // A single share is not enough.
+        for url in &urls {
+            let signer = threshold_signer(vec![url.clone()], 1);
+            assert!(signer.sign(address, None, H256::random()).is_err());
+        }
+        // Not enough cosigners are available.
+        let signer = threshold_signer(urls[..2].to_vec(), 3);
+        assert!(signer.sign(address, None, H256::random()).is_err());
+        // The cosigners have no key of another account.
+        let signer = threshold_signer(urls.clone(), 2);
+        let other = Random.generate().unwrap();
+        assert!(signer.sign(other.address(), None, H256::random()).is_err());
+        // The cosigners serve only the nodes with a valid token.
+        for auth_token in &[None, Some("other".to_string())] {
+            let signer = ThresholdSigner::new(ThresholdSignerConfig {
+                cosigners: urls.clone(),
+                threshold: 2,
+                auth_token: auth_token.clone(),
+                timeout: Duration::from_secs(120),
+            });
+            assert!(signer.sign(address, None, H256::random()).is_err());
+        }
+
+        for server in servers {
+            server.close();
+        }
+    }
+
+    #[test]
+    fn test_combine_shares() {
+        let key_pair = Random.generate().unwrap();
+        let message = H256::random();
+        let signature = sign(key_pair.secret(), &message).unwrap();
+        let r = H256::from_slice(signature.r());
+        let s = H256::from_slice(signature.s()).into_uint();
+        let mask = U256::from(rand::random::<u64>());
+        let shares = [
+            (r, H256::from_uint(&add_mod(s, *CURVE_ORDER - mask))),
+            (r, H256::from_uint(&mask)),
+        ];
+        assert_eq!(
+            combine_shares(&key_pair.address(), &message, &shares).unwrap(),
+            signature
+        );
+
+        // A bad share is rejected.
+        let bad_shares =
+            [shares[0], (r, H256::from_uint(&add_mod(mask, 1.into())))];
+        assert!(
+            combine_shares(&key_pair.address(), &message, &bad_shares).is_err()
+        );
+        // So is the signature of another account.
+        let other = Random.generate().unwrap();
+        assert!(combine_shares(&other.address(), &message, &shares).is_err());
+    }
+}
@@ -6,7 +6,7 @@ use cfx_types::H256;
 use cfxcore::{
     block_data_manager::DbType,
     consensus::{ConsensusGraph, ConsensusGraphTrait},
-    pow::PowComputer,
+    pow::{PowAlgorithmType, PowComputer},
     sync::utils::{
         create_simple_block, initialize_synchronization_graph,
         initialize_synchronization_graph_with_data_manager,
@@ -352,7 +352,7 @@ fn main() {
 
     data_man.initialize_instance_id();
 
-    let pow = Arc::new(PowComputer::new(true));
+    let pow = Arc::new(PowComputer::new(PowAlgorithmType::Octopus));
     let (_sync_n, consensus_n) =
         initialize_synchronization_graph_with_data_manager(
             data_man,
@@ -259,6 +259,8 @@ pub mod light {
         pub static ref TX_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
         pub static ref TX_INFO_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
         pub static ref STORAGE_ROOT_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+        pub static ref RECEIPT_PROOF_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+        pub static ref LOG_PROOF_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
 
         /// Maximum time period we wait for a response for an on-demand query.
         /// After this timeout has been reached, we try another peer or give up.
@@ -289,6 +291,10 @@ pub mod light {
     pub const TX_REQUEST_BATCH_SIZE: usize = 30;
     pub const TX_INFO_REQUEST_BATCH_SIZE: usize = 30;
     pub const STORAGE_ROOT_REQUEST_BATCH_SIZE: usize = 30;
+    pub const RECEIPT_PROOF_REQUEST_BATCH_SIZE: usize = 30;
+    // the epochs of a log proof request share the filter, so the requests of
+    // different filters are not batched
+    pub const LOG_PROOF_REQUEST_BATCH_SIZE: usize = 1;
 
     /// Maximum number of in-flight items at any given time.
     /// If we reach this limit, we will not request any more.
@@ -302,6 +308,8 @@ pub mod light {
     pub const MAX_TXS_IN_FLIGHT: usize = 100;
     pub const MAX_TX_INFOS_IN_FLIGHT: usize = 100;
     pub const MAX_STORAGE_ROOTS_IN_FLIGHT: usize = 100;
+    pub const MAX_RECEIPT_PROOFS_IN_FLIGHT: usize = 100;
+    pub const MAX_LOG_PROOFS_IN_FLIGHT: usize = 100;
 
     /// Maximum number of in-flight epoch requests at any given time.
     /// Similar to `MAX_HEADERS_IN_FLIGHT`. However, it is hard to match
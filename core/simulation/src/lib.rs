@@ -0,0 +1,254 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! An in-process simulation of several nodes for the consensus tests. The
+//! synchronization protocol handlers of the nodes exchange their messages
+//! through an in-memory network with a virtual clock, whose latency and
+//! partitions are controlled by the test, so that forks, reorgs and
+//! convergence can be reproduced deterministically.
+
+#[macro_use]
+extern crate log;
+
+mod node;
+mod transport;
+
+pub use crate::{
+    node::SimulatedNode,
+    transport::{
+        node_id, node_index, Delivery, SimulatedContext, SimulatedNetwork,
+    },
+};
+
+use cfx_types::H256;
+use cfxcore::sync::SynchronizationProtocolHandler;
+use network::NetworkProtocolHandler;
+use tempdir::TempDir;
+
+pub struct SimulationConfig {
+    pub node_count: usize,
+    /// The default latency between two nodes in milliseconds.
+    pub latency_ms: u64,
+    pub adaptive_weight_beta: u64,
+    pub heavy_block_difficulty_ratio: u64,
+    pub timer_chain_block_difficulty_ratio: u64,
+    pub timer_chain_beta: u64,
+    pub era_epoch_count: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            node_count: 4,
+            latency_ms: 100,
+            adaptive_weight_beta: 1000,
+            heavy_block_difficulty_ratio: 1000,
+            timer_chain_block_difficulty_ratio: 180,
+            timer_chain_beta: 240,
+            era_epoch_count: 50000,
+        }
+    }
+}
+
+pub struct Simulation {
+    nodes: Vec<SimulatedNode>,
+    network: SimulatedNetwork,
+    next_nonce: u64,
+    // The databases are removed after the nodes are dropped.
+    _db_dir: TempDir,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        let db_dir = TempDir::new("cfx_simulation")
+            .expect("temporary directory is created");
+        let nodes = (0..config.node_count)
+            .map(|id| {
+                let node_dir = db_dir.path().join(format!("node{}", id));
+                SimulatedNode::new(
+                    id,
+                    node_dir.to_str().expect("path is valid unicode"),
+                    config.adaptive_weight_beta,
+                    config.heavy_block_difficulty_ratio,
+                    config.timer_chain_block_difficulty_ratio,
+                    config.timer_chain_beta,
+                    config.era_epoch_count,
+                )
+            })
+            .collect();
+        let mut simulation = Simulation {
+            nodes,
+            network: SimulatedNetwork::new(
+                config.node_count,
+                config.latency_ms,
+            ),
+            next_nonce: 0,
+            _db_dir: db_dir,
+        };
+        // Connect all the nodes, which exchange their status.
+        for a in 0..config.node_count {
+            for b in 0..config.node_count {
+                if a != b {
+                    simulation.handle(a, |handler, io| {
+                        handler.on_peer_connected(
+                            io,
+                            &node_id(b),
+                            handler.protocol_version,
+                        )
+                    });
+                }
+            }
+        }
+        simulation.run_until_idle();
+        simulation
+    }
+
+    pub fn node_count(&self) -> usize { self.nodes.len() }
+
+    pub fn node(&self, id: usize) -> &SimulatedNode { &self.nodes[id] }
+
+    /// The virtual time in milliseconds.
+    pub fn now(&self) -> u64 { self.network.now() }
+
+    pub fn set_latency(&mut self, a: usize, b: usize, latency_ms: u64) {
+        self.network.set_latency(a, b, latency_ms);
+    }
+
+    /// See `SimulatedNetwork::partition`.
+    pub fn partition(&mut self, groups: &[Vec<usize>]) {
+        self.network.partition(groups);
+    }
+
+    pub fn heal(&mut self) { self.network.heal(); }
+
+    /// Mine a block on node `id` at the current virtual time, and relay it
+    /// to the peers. Returns the block hash.
+    pub fn mine(&mut self, id: usize) -> H256 {
+        let block = self.nodes[id]
+            .assemble_block(self.network.now() / 1000, &mut self.next_nonce);
+        let hash = block.hash();
+        debug!("node {} mines block {:?} at {}ms", id, hash, self.now());
+        self.handle(id, |handler, io| {
+            handler.on_mined_block(block);
+            handler
+                .relay_blocks(io, vec![hash])
+                .expect("relaying blocks succeeds");
+        });
+        self.nodes[id].wait_for_consensus();
+        hash
+    }
+
+    /// Move the clock forward by `ms`, delivering the messages arriving in
+    /// the meantime.
+    pub fn advance(&mut self, ms: u64) {
+        let until = self.network.now() + ms;
+        self.deliver_until(until);
+        self.network.set_now(until);
+    }
+
+    /// Deliver the messages until there is none in flight, including the
+    /// messages sent on their delivery.
+    pub fn run_until_idle(&mut self) { self.deliver_until(u64::MAX); }
+
+    fn deliver_until(&mut self, until: u64) {
+        while let Some(delivery) = self.network.next_delivery(until) {
+            trace!(
+                "deliver {} bytes from node {} to node {} at {}ms",
+                delivery.msg.len(),
+                delivery.from,
+                delivery.to,
+                delivery.at
+            );
+            let Delivery { from, to, msg, .. } = delivery;
+            self.handle(to, |handler, io| {
+                handler.on_message(io, &node_id(from), &msg)
+            });
+        }
+        for node in &self.nodes {
+            node.wait_for_consensus();
+        }
+    }
+
+    /// Let the handler of node `id` handle an event with `f`, and then the
+    /// work it dispatches, e.g. to insert the received blocks. The messages
+    /// sent meanwhile are put on the network.
+    fn handle(
+        &mut self, id: usize,
+        f: impl FnOnce(&SynchronizationProtocolHandler, &SimulatedContext),
+    )
+    {
+        let handler = &self.nodes[id].handler;
+        let io = SimulatedContext::new(id);
+        f(handler, &io);
+        while let Some(work_type) = io.next_work() {
+            handler.on_work_dispatch(&io, work_type);
+        }
+        for (to, msg) in io.into_outgoing() {
+            self.network.send(id, to, msg);
+        }
+    }
+
+    /// Whether all the nodes agree on the pivot chain.
+    pub fn converged(&self) -> bool {
+        let best = self.nodes[0].best_block_hash();
+        self.nodes.iter().all(|node| node.best_block_hash() == best)
+    }
+
+    pub fn assert_converged(&self) {
+        if !self.converged() {
+            let bests: Vec<_> = self
+                .nodes
+                .iter()
+                .map(|node| (node.best_epoch_number(), node.best_block_hash()))
+                .collect();
+            panic!("nodes diverge, best (epoch, block): {:?}", bests);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Simulation, SimulationConfig};
+
+    #[test]
+    fn test_convergence_with_latency() {
+        let mut sim = Simulation::new(SimulationConfig::default());
+        sim.set_latency(0, 3, 500);
+        for i in 0..20 {
+            sim.mine(i % sim.node_count());
+            sim.advance(50);
+        }
+        sim.run_until_idle();
+        sim.assert_converged();
+        let pivot_chain = sim.node(0).pivot_chain();
+        assert_eq!(
+            pivot_chain.len() as u64,
+            sim.node(0).best_epoch_number() + 1
+        );
+    }
+
+    #[test]
+    fn test_partition_and_heal() {
+        let mut sim = Simulation::new(SimulationConfig::default());
+        sim.partition(&[vec![0, 1], vec![2, 3]]);
+        for _ in 0..3 {
+            sim.mine(0);
+            sim.advance(1000);
+        }
+        let mut heavier = None;
+        for _ in 0..5 {
+            heavier = Some(sim.mine(2));
+            sim.advance(1000);
+        }
+        assert_ne!(
+            sim.node(1).best_block_hash(),
+            sim.node(3).best_block_hash()
+        );
+
+        sim.heal();
+        sim.run_until_idle();
+        sim.assert_converged();
+        assert_eq!(Some(sim.node(0).best_block_hash()), heavier);
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_parameters::{consensus::GENESIS_GAS_LIMIT, sync::DEFAULT_CHUNK_SIZE};
+use cfx_types::{address_util::AddressUtil, Address, H256, U256};
+use cfxcore::{
+    block_data_manager::{BlockDataManager, DbType},
+    channel::Receiver,
+    consensus::ConsensusGraphTrait,
+    light_protocol::Provider as LightProvider,
+    pow::{pow_hash_to_quality, PowAlgorithmType, PowComputer},
+    sync::{
+        utils::{
+            initialize_data_manager,
+            initialize_synchronization_graph_with_data_manager,
+        },
+        ProtocolConfiguration, StateSyncConfiguration, SyncPhaseType,
+        SynchronizationGraph, SynchronizationProtocolHandler,
+    },
+    ConsensusGraph, NodeType,
+};
+use primitives::{Block, BlockHeaderBuilder};
+use std::{
+    cell::RefCell,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+/// A node with its own synchronization protocol handler, graphs and
+/// database. The execution is skipped, as in the consensus benchmark, and
+/// the blocks are sealed with the cheap `dev` proof of work, so that they
+/// are verified by the peers like on a real network.
+pub struct SimulatedNode {
+    pub id: usize,
+    pub author: Address,
+    pub handler: Arc<SynchronizationProtocolHandler>,
+    pub sync: Arc<SynchronizationGraph>,
+    pub consensus: Arc<ConsensusGraph>,
+    pub data_man: Arc<BlockDataManager>,
+    pow: Arc<PowComputer>,
+    /// The blocks of a heavier pow quality are not mined, see
+    /// `assemble_block`.
+    max_quality_ratio: u64,
+    processed_blocks: RefCell<Receiver<H256>>,
+}
+
+impl SimulatedNode {
+    pub fn new(
+        id: usize, db_dir: &str, beta: u64, h: u64, tcr: u64, tcb: u64,
+        era_epoch_count: u64,
+    ) -> Self
+    {
+        let pow = Arc::new(PowComputer::new(PowAlgorithmType::Dev));
+        let (data_man, _) =
+            initialize_data_manager(db_dir, DbType::Rocksdb, pow.clone());
+        let (sync, consensus) =
+            initialize_synchronization_graph_with_data_manager(
+                data_man.clone(),
+                beta,
+                h,
+                tcr,
+                tcb,
+                era_epoch_count,
+                pow.clone(),
+            );
+        let light_provider = Arc::new(LightProvider::new(
+            consensus.clone(),
+            sync.clone(),
+            Weak::new(), /* network */
+            consensus.txpool.clone(),
+            None, /* throttling_config_file */
+            NodeType::Archive,
+        ));
+        let handler = Arc::new(SynchronizationProtocolHandler::new(
+            NodeType::Archive,
+            protocol_config(),
+            state_sync_config(),
+            SyncPhaseType::Normal,
+            sync.clone(),
+            light_provider,
+        ));
+        let processed_blocks = RefCell::new(sync.subscribe_processed_blocks());
+        let mut author = Address::from_low_u64_be(id as u64 + 1);
+        author.set_user_account_type_bits();
+        SimulatedNode {
+            id,
+            author,
+            handler,
+            sync,
+            consensus,
+            data_man,
+            pow,
+            max_quality_ratio: h.min(tcr),
+            processed_blocks,
+        }
+    }
+
+    /// Wait until the consensus graph processes the blocks received so far.
+    pub fn wait_for_consensus(&self) {
+        self.sync
+            .wait_for_consensus_worker(&mut self.processed_blocks.borrow_mut());
+    }
+
+    /// Assemble a block on the current pivot chain, referring to the other
+    /// terminal blocks, like the block generator does. The nonces are tried
+    /// from `next_nonce`.
+    pub fn assemble_block(
+        &self, timestamp: u64, next_nonce: &mut u64,
+    ) -> Block {
+        self.wait_for_consensus();
+        let best_info = self.consensus.best_info();
+        let parent_hash = best_info.best_block_hash;
+        let mut referees = best_info.bounded_terminal_block_hashes.clone();
+        referees.retain(|r| *r != parent_hash);
+
+        let parent_height = self
+            .sync
+            .block_height_by_hash(&parent_hash)
+            .expect("best block exists");
+        let parent_timestamp = self
+            .sync
+            .block_timestamp_by_hash(&parent_hash)
+            .expect("best block exists");
+        let (difficulty, adaptive) = {
+            let mut inner = self.consensus.inner.write();
+            let difficulty = inner.expected_difficulty(&parent_hash);
+            let adaptive = self.consensus.check_mining_adaptive_block(
+                &mut inner,
+                &parent_hash,
+                &referees,
+                &difficulty,
+            );
+            (difficulty, adaptive)
+        };
+
+        let mut header = BlockHeaderBuilder::new()
+            .with_parent_hash(parent_hash)
+            .with_height(parent_height + 1)
+            .with_timestamp(timestamp.max(parent_timestamp))
+            .with_author(self.author)
+            .with_difficulty(difficulty)
+            .with_adaptive(adaptive)
+            .with_referee_hashes(referees)
+            .with_gas_limit(GENESIS_GAS_LIMIT.into())
+            .build();
+        // The heavy blocks and the timer chain blocks are skipped, so that
+        // all the blocks have the same weight.
+        let max_quality = difficulty * self.max_quality_ratio;
+        let problem_hash = header.problem_hash();
+        loop {
+            let nonce = U256::from(*next_nonce);
+            *next_nonce += 1;
+            let pow_hash =
+                self.pow.compute(&nonce, &problem_hash, header.height());
+            let quality = pow_hash_to_quality(&pow_hash, &nonce);
+            if quality >= difficulty && quality < max_quality {
+                header.set_nonce(nonce);
+                header.pow_hash = Some(pow_hash);
+                break;
+            }
+        }
+        header.compute_hash();
+        Block::new(header, vec![])
+    }
+
+    pub fn best_block_hash(&self) -> H256 { self.consensus.best_block_hash() }
+
+    pub fn best_epoch_number(&self) -> u64 {
+        self.consensus.best_epoch_number()
+    }
+
+    /// The pivot blocks from the genesis to the best block.
+    pub fn pivot_chain(&self) -> Vec<H256> {
+        let mut chain = Vec::new();
+        let mut hash = self.best_block_hash();
+        while let Some(header) = self.data_man.block_header_by_hash(&hash) {
+            chain.push(hash);
+            if header.height() == 0 {
+                break;
+            }
+            hash = *header.parent_hash();
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// The defaults of the node configuration.
+fn protocol_config() -> ProtocolConfiguration {
+    ProtocolConfiguration {
+        is_consortium: false,
+        send_tx_period: Duration::from_millis(1300),
+        check_request_period: Duration::from_millis(1000),
+        heartbeat_period_interval: Duration::from_millis(30_000),
+        heartbeat_timeout: Duration::from_millis(180_000),
+        block_cache_gc_period: Duration::from_millis(5000),
+        expire_block_gc_period: Duration::from_secs(900),
+        sync_expire_block_timeout: Duration::from_secs(7200),
+        headers_request_timeout: Duration::from_millis(10_000),
+        blocks_request_timeout: Duration::from_millis(20_000),
+        transaction_request_timeout: Duration::from_millis(30_000),
+        snapshot_candidate_request_timeout: Duration::from_millis(10_000),
+        snapshot_manifest_request_timeout: Duration::from_millis(30_000),
+        snapshot_chunk_request_timeout: Duration::from_millis(30_000),
+        tx_maintained_for_peer_timeout: Duration::from_millis(600_000),
+        max_inflight_request_count: 64,
+        received_tx_index_maintain_timeout: Duration::from_millis(300_000),
+        inflight_pending_tx_index_maintain_timeout: Duration::from_millis(
+            30_000,
+        ),
+        request_block_with_public: false,
+        max_trans_count_received_in_catch_up: 60_000,
+        min_peers_tx_propagation: 8,
+        max_peers_tx_propagation: 128,
+        max_downloading_chunks: 8,
+        test_mode: true,
+        dev_mode: false,
+        throttling_config_file: None,
+        chunk_size_byte: DEFAULT_CHUNK_SIZE,
+        timeout_observing_period_s: 600,
+        max_allowed_timeout_in_observing_period: 10,
+        demote_peer_for_timeout: false,
+        max_unprocessed_block_size: 128 * 1_000_000,
+        max_buffered_block_body_size: 256 * 1_000_000,
+        max_chunk_number_in_manifest: 500,
+    }
+}
+
+fn state_sync_config() -> StateSyncConfiguration {
+    StateSyncConfiguration {
+        max_downloading_chunks: 8,
+        candidate_request_timeout: Duration::from_millis(10_000),
+        chunk_request_timeout: Duration::from_millis(30_000),
+        manifest_request_timeout: Duration::from_millis(30_000),
+    }
+}
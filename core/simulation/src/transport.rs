@@ -0,0 +1,245 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use network::{
+    node_table::NodeId, service::ProtocolVersion, Error as NetworkError,
+    HandlerWorkType, NetworkContext, ProtocolId, TimerToken,
+    UpdateNodeOperation,
+};
+use priority_send_queue::SendQueuePriority;
+use std::{
+    cell::RefCell,
+    cmp::{Ord, Ordering, PartialOrd, Reverse},
+    collections::{BinaryHeap, HashMap, VecDeque},
+    time::Duration,
+};
+
+/// The id of the `index`-th node on the simulated network.
+pub fn node_id(index: usize) -> NodeId {
+    NodeId::from_low_u64_be(index as u64 + 1)
+}
+
+/// The index of the node `id` on the simulated network.
+pub fn node_index(id: &NodeId) -> usize { id.to_low_u64_be() as usize - 1 }
+
+/// A message of the synchronization protocol in flight to a node.
+pub struct Delivery {
+    /// The virtual time in milliseconds to deliver the message.
+    pub at: u64,
+    /// The order of sending, which breaks the ties of `at`.
+    seq: u64,
+    pub from: usize,
+    pub to: usize,
+    pub msg: Vec<u8>,
+}
+
+impl PartialEq for Delivery {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Delivery {}
+
+impl PartialOrd for Delivery {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Delivery {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+/// An in-memory transport relaying the messages among the nodes with a
+/// virtual clock. Each link has a latency, and the messages sent between the
+/// groups of a partition are held until the partition heals. The deliveries
+/// are ordered by time and then by the order of sending, so that a
+/// simulation is deterministic.
+pub struct SimulatedNetwork {
+    node_count: usize,
+    /// The virtual time in milliseconds.
+    now: u64,
+    default_latency: u64,
+    latencies: HashMap<(usize, usize), u64>,
+    /// The group of each node while the network is partitioned.
+    partition: Option<Vec<usize>>,
+    queue: BinaryHeap<Reverse<Delivery>>,
+    /// The messages sent across the partition, as `(from, to, msg)`.
+    held: Vec<(usize, usize, Vec<u8>)>,
+    next_seq: u64,
+}
+
+impl SimulatedNetwork {
+    pub fn new(node_count: usize, default_latency: u64) -> Self {
+        SimulatedNetwork {
+            node_count,
+            now: 0,
+            default_latency,
+            latencies: HashMap::new(),
+            partition: None,
+            queue: BinaryHeap::new(),
+            held: Vec::new(),
+            next_seq: 0,
+        }
+    }
+
+    pub fn now(&self) -> u64 { self.now }
+
+    /// Set the latency in milliseconds of both directions between `a` and
+    /// `b`.
+    pub fn set_latency(&mut self, a: usize, b: usize, latency: u64) {
+        self.latencies.insert((a, b), latency);
+        self.latencies.insert((b, a), latency);
+    }
+
+    fn latency(&self, from: usize, to: usize) -> u64 {
+        if from == to {
+            return 0;
+        }
+        *self
+            .latencies
+            .get(&(from, to))
+            .unwrap_or(&self.default_latency)
+    }
+
+    /// Split the nodes into `groups` which can't reach each other. The nodes
+    /// not listed form a group together.
+    pub fn partition(&mut self, groups: &[Vec<usize>]) {
+        let mut group_of = vec![groups.len(); self.node_count];
+        for (group, nodes) in groups.iter().enumerate() {
+            for node in nodes {
+                group_of[*node] = group;
+            }
+        }
+        self.partition = Some(group_of);
+    }
+
+    /// Reconnect the partitioned nodes. The held messages are sent again.
+    pub fn heal(&mut self) {
+        self.partition = None;
+        for (from, to, msg) in std::mem::take(&mut self.held) {
+            self.send(from, to, msg);
+        }
+    }
+
+    fn reachable(&self, from: usize, to: usize) -> bool {
+        match &self.partition {
+            Some(group_of) => group_of[from] == group_of[to],
+            None => true,
+        }
+    }
+
+    pub fn send(&mut self, from: usize, to: usize, msg: Vec<u8>) {
+        if !self.reachable(from, to) {
+            self.held.push((from, to, msg));
+            return;
+        }
+        let delivery = Delivery {
+            at: self.now + self.latency(from, to),
+            seq: self.next_seq,
+            from,
+            to,
+            msg,
+        };
+        self.next_seq += 1;
+        self.queue.push(Reverse(delivery));
+    }
+
+    /// Take the next message to deliver until the virtual time `until`, and
+    /// move the clock to its delivery time.
+    pub fn next_delivery(&mut self, until: u64) -> Option<Delivery> {
+        match self.queue.peek() {
+            Some(Reverse(delivery)) if delivery.at <= until => {}
+            _ => return None,
+        }
+        let Reverse(delivery) = self.queue.pop()?;
+        self.now = self.now.max(delivery.at);
+        Some(delivery)
+    }
+
+    /// Move the clock forward to `time`.
+    pub fn set_now(&mut self, time: u64) { self.now = self.now.max(time); }
+}
+
+/// The `NetworkContext` of a node handling an event. The messages sent and
+/// the work dispatched by the handler are collected, to be put on the
+/// `SimulatedNetwork` and handled after the event respectively. The timers
+/// are not simulated.
+pub struct SimulatedContext {
+    node: usize,
+    outgoing: RefCell<Vec<(usize, Vec<u8>)>>,
+    works: RefCell<VecDeque<HandlerWorkType>>,
+}
+
+impl SimulatedContext {
+    pub fn new(node: usize) -> Self {
+        SimulatedContext {
+            node,
+            outgoing: RefCell::new(Vec::new()),
+            works: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    pub fn next_work(&self) -> Option<HandlerWorkType> {
+        self.works.borrow_mut().pop_front()
+    }
+
+    /// The messages sent, as `(to, msg)`.
+    pub fn into_outgoing(self) -> Vec<(usize, Vec<u8>)> {
+        self.outgoing.into_inner()
+    }
+}
+
+impl NetworkContext for SimulatedContext {
+    fn get_protocol(&self) -> ProtocolId { *b"cfx" }
+
+    fn get_peer_connection_origin(&self, node_id: &NodeId) -> Option<bool> {
+        // The node with the smaller index originates the connection.
+        Some(self.node < node_index(node_id))
+    }
+
+    fn send(
+        &self, node_id: &NodeId, msg: Vec<u8>,
+        _min_protocol_version: ProtocolVersion,
+        _version_valid_till: ProtocolVersion, _priority: SendQueuePriority,
+        _compressible: bool,
+    ) -> Result<(), NetworkError>
+    {
+        self.outgoing.borrow_mut().push((node_index(node_id), msg));
+        Ok(())
+    }
+
+    fn disconnect_peer(
+        &self, node_id: &NodeId, _op: Option<UpdateNodeOperation>, reason: &str,
+    )
+    {
+        warn!(
+            "node {} disconnects node {}: {}",
+            self.node,
+            node_index(node_id),
+            reason
+        );
+    }
+
+    fn register_timer(
+        &self, _token: TimerToken, _delay: Duration,
+    ) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    fn dispatch_work(&self, work_type: HandlerWorkType) {
+        self.works.borrow_mut().push_back(work_type);
+    }
+
+    fn insert_peer_node_tag(&self, _peer: NodeId, _key: &str, _value: &str) {}
+
+    fn is_peer_self(&self, node_id: &NodeId) -> bool {
+        node_index(node_id) == self.node
+    }
+
+    fn self_node_id(&self) -> NodeId { node_id(self.node) }
+}
@@ -1,5 +1,6 @@
+use crate::trace::TransactionInternalTransfers;
 use cfx_internal_common::{DatabaseDecodable, DatabaseEncodable};
-use cfx_types::{Bloom, H256, U256};
+use cfx_types::{Address, Bloom, H256, U256};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use parity_bytes::Bytes;
@@ -248,6 +249,49 @@ impl MallocSizeOf for BlamedHeaderVerifiedRoots {
     fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize { 0 }
 }
 
+/// Whether an address sends or receives a transaction, by which the
+/// transactions of the address are indexed on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransactionDirection {
+    Sent = 0,
+    /// The receiver of a call, or of the value transferred by its sub-calls.
+    Received = 1,
+}
+
+/// A transaction hash indexed by an address in a direction.
+pub type AddressTransactionEntry = (Address, TransactionDirection, H256);
+
+/// The location of a log on the pivot chain, by which the logs are indexed
+/// on disk. The locations are ordered by execution.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    RlpEncodable,
+    RlpDecodable,
+)]
+pub struct LogLocation {
+    pub epoch_number: u64,
+    /// The index of the block in the epoch.
+    pub block_index: u64,
+    /// The index of the transaction in the block.
+    pub transaction_index: u64,
+    /// The index of the log in the transaction.
+    pub transaction_log_index: u64,
+}
+
+/// The logs are indexed by the emitting contract and by the first topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogIndexKey {
+    Address(Address),
+    Topic(H256),
+}
+
 pub fn db_encode_list<T>(list: &[T]) -> Bytes
 where T: DatabaseEncodable {
     let mut rlp_stream = RlpStream::new();
@@ -275,3 +319,5 @@ impl_db_encoding_as_rlp!(EpochExecutionContext);
 impl_db_encoding_as_rlp!(BlockRewardResult);
 impl_db_encoding_as_rlp!(BlockExecutionResultWithEpoch);
 impl_db_encoding_as_rlp!(BlamedHeaderVerifiedRoots);
+impl_db_encoding_as_rlp!(LogLocation);
+impl_db_encoding_as_rlp!(TransactionInternalTransfers);
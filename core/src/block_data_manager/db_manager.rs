@@ -1,29 +1,37 @@
 use crate::{
     block_data_manager::{
-        db_decode_list, db_encode_list, BlamedHeaderVerifiedRoots,
-        BlockExecutionResultWithEpoch, BlockRewardResult, CheckpointHashes,
-        EpochExecutionContext, LocalBlockInfo,
+        db_decode_list, db_encode_list, AddressTransactionEntry,
+        BlamedHeaderVerifiedRoots, BlockExecutionResultWithEpoch,
+        BlockRewardResult, CheckpointHashes, EpochExecutionContext,
+        LocalBlockInfo, LogIndexKey, LogLocation, TransactionDirection,
     },
     db::{
-        COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS, COL_EPOCH_NUMBER,
-        COL_MISC, COL_TX_INDEX,
+        COL_ADDRESS_TX_INDEX, COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS,
+        COL_EPOCH_NUMBER, COL_LOG_INDEX, COL_MISC, COL_TX_INDEX,
     },
     pow::PowComputer,
+    trace::TransactionInternalTransfers,
     verification::VerificationConfig,
 };
-use byteorder::{ByteOrder, LittleEndian};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use cfx_internal_common::{
     DatabaseDecodable, DatabaseEncodable, EpochExecutionCommitment,
 };
 use cfx_storage::{
-    storage_db::KeyValueDbTrait, KvdbRocksdb, KvdbSqlite, KvdbSqliteStatements,
+    storage_db::{KeyValueDbTrait, KeyValueDbTraitTransactionalDyn},
+    KvdbRocksdb, KvdbSqlite, KvdbSqliteStatements,
 };
-use cfx_types::H256;
+use cfx_types::{Address, H256};
 use db::SystemDB;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use primitives::{Block, BlockHeader, SignedTransaction, TransactionIndex};
 use rlp::Rlp;
-use std::{collections::HashMap, fs, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+    sync::Arc,
+};
 
 const LOCAL_BLOCK_INFO_SUFFIX_BYTE: u8 = 1;
 const BLOCK_BODY_SUFFIX_BYTE: u8 = 2;
@@ -33,8 +41,14 @@ const EPOCH_CONSENSUS_EXECUTION_INFO_SUFFIX_BYTE: u8 = 5;
 const EPOCH_EXECUTED_BLOCK_SET_SUFFIX_BYTE: u8 = 6;
 const EPOCH_SKIPPED_BLOCK_SET_SUFFIX_BYTE: u8 = 7;
 const BLOCK_REWARD_RESULT_SUFFIX_BYTE: u8 = 8;
+const EPOCH_INTERNAL_TRANSFERS_SUFFIX_BYTE: u8 = 9;
 const BLOCK_TERMINAL_KEY: &[u8] = b"block_terminals";
 const HEADER_TERMINAL_KEY: &[u8] = b"header_terminals";
+const LOG_INDEX_START_EPOCH_KEY: &[u8] = b"\x02start_epoch";
+const LAST_LOG_INDEXED_EPOCH_KEY: &[u8] = b"\x02last_epoch";
+const LOG_INDEXED_EPOCH_KEY_KIND: u8 = 3;
+const LAST_ADDRESS_INDEXED_EPOCH_KEY: &[u8] = b"last_address_indexed_epoch";
+const ADDRESS_TRANSACTION_ENTRY_LEN: usize = 53;
 
 #[derive(Clone, Copy, Hash, Ord, PartialOrd, Eq, PartialEq)]
 enum DBTable {
@@ -43,6 +57,8 @@ enum DBTable {
     Transactions,
     EpochNumbers,
     BlamedHeaderVerifiedRoots,
+    AddressTransactions,
+    Logs,
 }
 
 fn rocks_db_col(table: DBTable) -> u32 {
@@ -52,6 +68,8 @@ fn rocks_db_col(table: DBTable) -> u32 {
         DBTable::Transactions => COL_TX_INDEX,
         DBTable::EpochNumbers => COL_EPOCH_NUMBER,
         DBTable::BlamedHeaderVerifiedRoots => COL_BLAMED_HEADER_VERIFIED_ROOTS,
+        DBTable::AddressTransactions => COL_ADDRESS_TX_INDEX,
+        DBTable::Logs => COL_LOG_INDEX,
     }
 }
 
@@ -62,12 +80,46 @@ fn sqlite_db_table(table: DBTable) -> String {
         DBTable::Transactions => "transactions",
         DBTable::EpochNumbers => "epoch_numbers",
         DBTable::BlamedHeaderVerifiedRoots => "blamed_header_verified_roots",
+        DBTable::AddressTransactions => "address_transactions",
+        DBTable::Logs => "logs",
     }
     .into()
 }
 
+/// A table of the db, which can also be written in a transaction.
+trait DBTableTrait:
+    KeyValueDbTrait<ValueType = Box<[u8]>> + KeyValueDbTraitTransactionalDyn
+{
+}
+
+impl<T> DBTableTrait for T where T: KeyValueDbTrait<ValueType = Box<[u8]>> + KeyValueDbTraitTransactionalDyn
+{}
+
+/// The writes to a table committed at once by `DBManager::commit_batch`.
+struct DBBatch {
+    table: DBTable,
+    ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl DBBatch {
+    fn new(table: DBTable) -> Self {
+        Self {
+            table,
+            ops: Vec::new(),
+        }
+    }
+
+    fn put(&mut self, db_key: &[u8], value: Vec<u8>) {
+        self.ops.push((db_key.to_vec(), Some(value)));
+    }
+
+    fn delete(&mut self, db_key: &[u8]) {
+        self.ops.push((db_key.to_vec(), None));
+    }
+}
+
 pub struct DBManager {
-    table_db: HashMap<DBTable, Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>>,
+    table_db: HashMap<DBTable, Box<dyn DBTableTrait>>,
     pow: Arc<PowComputer>,
 }
 
@@ -80,14 +132,15 @@ impl DBManager {
             DBTable::Transactions,
             DBTable::EpochNumbers,
             DBTable::BlamedHeaderVerifiedRoots,
+            DBTable::AddressTransactions,
+            DBTable::Logs,
         ] {
             table_db.insert(
                 table,
                 Box::new(KvdbRocksdb {
                     kvdb: db.key_value().clone(),
                     col: rocks_db_col(table),
-                })
-                    as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
+                }) as Box<dyn DBTableTrait>,
             );
         }
         Self { table_db, pow }
@@ -106,6 +159,8 @@ impl DBManager {
             DBTable::Transactions,
             DBTable::EpochNumbers,
             DBTable::BlamedHeaderVerifiedRoots,
+            DBTable::AddressTransactions,
+            DBTable::Logs,
         ] {
             let table_str = sqlite_db_table(table);
             let (_, sqlite_db) = KvdbSqlite::open_or_create(
@@ -124,11 +179,8 @@ impl DBManager {
                 false, /* unsafe_mode */
             )
             .expect("Open sqlite failure");
-            table_db.insert(
-                table,
-                Box::new(sqlite_db)
-                    as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
-            );
+            table_db
+                .insert(table, Box::new(sqlite_db) as Box<dyn DBTableTrait>);
         }
         Self { table_db, pow }
     }
@@ -177,6 +229,348 @@ impl DBManager {
         self.load_decodable_val(DBTable::Transactions, hash.as_bytes())
     }
 
+    pub fn remove_transaction_index_from_db(&self, hash: &H256) {
+        self.remove_from_db(DBTable::Transactions, hash.as_bytes())
+    }
+
+    /// The hash of the `seq`-th transaction of `address` in `direction`. The
+    /// keys of an address are ordered by `seq` in the db.
+    pub fn address_transaction_from_db(
+        &self, address: &Address, direction: TransactionDirection, seq: u64,
+    ) -> Option<H256> {
+        let value = self.load_from_db(
+            DBTable::AddressTransactions,
+            &address_transaction_key(address, direction, seq),
+        )?;
+        Some(H256::from_slice(&value))
+    }
+
+    pub fn address_transaction_count_from_db(
+        &self, address: &Address, direction: TransactionDirection,
+    ) -> Option<u64> {
+        self.load_decodable_val(
+            DBTable::AddressTransactions,
+            &address_transaction_count_key(address, direction),
+        )
+    }
+
+    /// The entries indexed for the epoch of `pivot_hash`, which are kept to
+    /// index the epoch again if it becomes the pivot chain again.
+    pub fn epoch_address_transactions_from_db(
+        &self, pivot_hash: &H256,
+    ) -> Option<Vec<AddressTransactionEntry>> {
+        let value = self.load_from_db(
+            DBTable::AddressTransactions,
+            pivot_hash.as_bytes(),
+        )?;
+        Some(
+            value
+                .chunks(ADDRESS_TRANSACTION_ENTRY_LEN)
+                .map(decode_address_transaction_entry)
+                .collect(),
+        )
+    }
+
+    /// Remove the entries kept for the epoch of `pivot_hash`. The epoch must
+    /// not be in the index.
+    pub fn remove_epoch_address_transactions_from_db(&self, pivot_hash: &H256) {
+        self.remove_from_db(DBTable::AddressTransactions, pivot_hash.as_bytes())
+    }
+
+    /// Index `entries` as the transactions of the epoch `epoch_number` with
+    /// the pivot block `pivot_hash`. The epochs from `epoch_number` indexed
+    /// on another pivot chain are removed from the index first. All the
+    /// changes are written in one batch, so that the index is never left
+    /// partially updated.
+    pub fn insert_epoch_address_transactions_to_db(
+        &self, epoch_number: u64, pivot_hash: &H256,
+        entries: &[AddressTransactionEntry],
+    )
+    {
+        let mut batch = DBBatch::new(DBTable::AddressTransactions);
+        let mut counts =
+            self.revert_address_transactions(epoch_number, &mut batch);
+        for (address, direction, hash) in entries {
+            let count = self.address_transaction_count(
+                &mut counts,
+                address,
+                *direction,
+            );
+            batch.put(
+                &address_transaction_key(address, *direction, *count),
+                hash.as_bytes().to_vec(),
+            );
+            *count += 1;
+        }
+        Self::put_address_transaction_counts(counts, &mut batch);
+
+        let mut value =
+            Vec::with_capacity(entries.len() * ADDRESS_TRANSACTION_ENTRY_LEN);
+        for entry in entries {
+            encode_address_transaction_entry(entry, &mut value);
+        }
+        batch.put(pivot_hash.as_bytes(), value);
+        batch.put(
+            &address_indexed_epoch_key(epoch_number),
+            pivot_hash.as_bytes().to_vec(),
+        );
+        batch.put(LAST_ADDRESS_INDEXED_EPOCH_KEY, epoch_number.db_encode());
+        self.commit_batch(batch);
+    }
+
+    /// Remove the transactions of the epochs from `epoch_number` from the
+    /// index in one batch.
+    pub fn remove_address_transactions_from_db(&self, epoch_number: u64) {
+        let mut batch = DBBatch::new(DBTable::AddressTransactions);
+        let counts = self.revert_address_transactions(epoch_number, &mut batch);
+        Self::put_address_transaction_counts(counts, &mut batch);
+        self.commit_batch(batch);
+    }
+
+    /// Remove the transactions of the indexed epochs from `epoch_number` in
+    /// `batch`, and return the counts of the addresses after the removal.
+    fn revert_address_transactions(
+        &self, epoch_number: u64, batch: &mut DBBatch,
+    ) -> HashMap<(Address, TransactionDirection), u64> {
+        let mut counts = HashMap::new();
+        let last_epoch = match self.load_decodable_val(
+            DBTable::AddressTransactions,
+            LAST_ADDRESS_INDEXED_EPOCH_KEY,
+        ) {
+            Some(last_epoch) if last_epoch >= epoch_number => last_epoch,
+            _ => return counts,
+        };
+
+        // The transactions are removed from the last indexed ones.
+        for epoch in (epoch_number..=last_epoch).rev() {
+            let epoch_key = address_indexed_epoch_key(epoch);
+            let entries = self
+                .load_from_db(DBTable::AddressTransactions, &epoch_key)
+                .and_then(|pivot_hash| {
+                    self.epoch_address_transactions_from_db(&H256::from_slice(
+                        &pivot_hash,
+                    ))
+                })
+                .unwrap_or_default();
+            for (address, direction, _) in entries.iter().rev() {
+                let count = self.address_transaction_count(
+                    &mut counts,
+                    address,
+                    *direction,
+                );
+                *count = count.saturating_sub(1);
+                batch.delete(&address_transaction_key(
+                    address, *direction, *count,
+                ));
+            }
+            batch.delete(&epoch_key);
+        }
+
+        match epoch_number.checked_sub(1) {
+            Some(last_epoch) => batch
+                .put(LAST_ADDRESS_INDEXED_EPOCH_KEY, last_epoch.db_encode()),
+            None => batch.delete(LAST_ADDRESS_INDEXED_EPOCH_KEY),
+        }
+        counts
+    }
+
+    fn address_transaction_count<'a>(
+        &self, counts: &'a mut HashMap<(Address, TransactionDirection), u64>,
+        address: &Address, direction: TransactionDirection,
+    ) -> &'a mut u64
+    {
+        counts.entry((*address, direction)).or_insert_with(|| {
+            self.address_transaction_count_from_db(address, direction)
+                .unwrap_or(0)
+        })
+    }
+
+    fn put_address_transaction_counts(
+        counts: HashMap<(Address, TransactionDirection), u64>,
+        batch: &mut DBBatch,
+    )
+    {
+        for ((address, direction), count) in counts {
+            batch.put(
+                &address_transaction_count_key(&address, direction),
+                count.db_encode(),
+            );
+        }
+    }
+
+    pub fn log_location_from_db(
+        &self, key: &LogIndexKey, seq: u64,
+    ) -> Option<LogLocation> {
+        self.load_decodable_val(DBTable::Logs, &log_location_key(key, seq))
+    }
+
+    pub fn log_location_count_from_db(&self, key: &LogIndexKey) -> Option<u64> {
+        self.load_decodable_val(DBTable::Logs, &log_location_count_key(key))
+    }
+
+    /// The first epoch whose logs are indexed. The index covers the epochs
+    /// from it to the last indexed epoch without a gap.
+    pub fn log_index_start_epoch_from_db(&self) -> Option<u64> {
+        self.load_decodable_val(DBTable::Logs, LOG_INDEX_START_EPOCH_KEY)
+    }
+
+    /// The last epoch whose logs are indexed.
+    pub fn last_log_indexed_epoch_from_db(&self) -> Option<u64> {
+        self.load_decodable_val(DBTable::Logs, LAST_LOG_INDEXED_EPOCH_KEY)
+    }
+
+    /// Return the locations of the logs of `key` in the epochs from
+    /// `from_epoch` to `to_epoch`, in the order of execution. Return `None`
+    /// if the logs of any of the epochs are not indexed.
+    pub fn log_locations_from_db(
+        &self, key: &LogIndexKey, from_epoch: u64, to_epoch: u64,
+    ) -> Option<Vec<LogLocation>> {
+        match (
+            self.log_index_start_epoch_from_db(),
+            self.last_log_indexed_epoch_from_db(),
+        ) {
+            (Some(start_epoch), Some(last_epoch))
+                if start_epoch <= from_epoch && to_epoch <= last_epoch => {}
+            _ => return None,
+        }
+        let count = self.log_location_count_from_db(key).unwrap_or(0);
+        let epoch_of = |seq| {
+            self.log_location_from_db(key, seq)
+                .map_or(0, |location| location.epoch_number)
+        };
+
+        // Find the first location from `from_epoch`.
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if epoch_of(mid) < from_epoch {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let mut locations = Vec::new();
+        for seq in low..count {
+            match self.log_location_from_db(key, seq) {
+                Some(location) if location.epoch_number <= to_epoch => {
+                    locations.push(location)
+                }
+                _ => break,
+            }
+        }
+        Some(locations)
+    }
+
+    /// Index the logs of the epoch `epoch_number` on the pivot chain at
+    /// `locations`. The epochs from `epoch_number` indexed on another pivot
+    /// chain are removed from the index first, and the index starts again
+    /// from `epoch_number` after a gap, e.g. of the epochs executed without
+    /// `persist_log_index`. All the changes are written in one batch, so that
+    /// the index is never left partially updated.
+    pub fn insert_epoch_log_locations_to_db(
+        &self, epoch_number: u64,
+        locations: &BTreeMap<LogIndexKey, Vec<LogLocation>>,
+    )
+    {
+        let contiguous = match self.last_log_indexed_epoch_from_db() {
+            Some(last_epoch) => epoch_number <= last_epoch + 1,
+            None => false,
+        };
+        let mut batch = DBBatch::new(DBTable::Logs);
+        let mut counts = self.revert_log_locations(epoch_number, &mut batch);
+        let mut keys = Vec::new();
+        for (key, key_locations) in locations {
+            let count = self.log_location_count(&mut counts, key);
+            for location in key_locations {
+                batch.put(&log_location_key(key, *count), location.db_encode());
+                *count += 1;
+            }
+            keys.extend_from_slice(&log_location_count_key(key));
+        }
+        Self::put_log_location_counts(counts, &mut batch);
+
+        match self.log_index_start_epoch_from_db() {
+            Some(start_epoch) if start_epoch < epoch_number && contiguous => {}
+            _ => batch.put(LOG_INDEX_START_EPOCH_KEY, epoch_number.db_encode()),
+        }
+        batch.put(&log_indexed_epoch_key(epoch_number), keys);
+        batch.put(LAST_LOG_INDEXED_EPOCH_KEY, epoch_number.db_encode());
+        self.commit_batch(batch);
+    }
+
+    /// Remove the logs of the epochs from `epoch_number` from the index in one
+    /// batch.
+    pub fn remove_log_locations_from_db(&self, epoch_number: u64) {
+        let mut batch = DBBatch::new(DBTable::Logs);
+        let counts = self.revert_log_locations(epoch_number, &mut batch);
+        Self::put_log_location_counts(counts, &mut batch);
+        if let Some(start_epoch) = self.log_index_start_epoch_from_db() {
+            if start_epoch >= epoch_number {
+                batch.delete(LOG_INDEX_START_EPOCH_KEY);
+            }
+        }
+        self.commit_batch(batch);
+    }
+
+    /// Remove the locations in the indexed epochs from `epoch_number` in
+    /// `batch`, and return the counts of the keys after the removal.
+    fn revert_log_locations(
+        &self, epoch_number: u64, batch: &mut DBBatch,
+    ) -> HashMap<LogIndexKey, u64> {
+        let mut counts = HashMap::new();
+        let last_epoch = match self
+            .load_decodable_val(DBTable::Logs, LAST_LOG_INDEXED_EPOCH_KEY)
+        {
+            Some(last_epoch) if last_epoch >= epoch_number => last_epoch,
+            _ => return counts,
+        };
+
+        for epoch in epoch_number..=last_epoch {
+            let epoch_key = log_indexed_epoch_key(epoch);
+            let keys = self
+                .load_from_db(DBTable::Logs, &epoch_key)
+                .map_or_else(Vec::new, |value| decode_log_index_keys(&value));
+            for key in keys {
+                let count = self.log_location_count(&mut counts, &key);
+                // The locations of a key are ordered by epoch.
+                while *count > 0 {
+                    match self.log_location_from_db(&key, *count - 1) {
+                        Some(last) if last.epoch_number >= epoch_number => {
+                            *count -= 1;
+                            batch.delete(&log_location_key(&key, *count));
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            batch.delete(&epoch_key);
+        }
+
+        match epoch_number.checked_sub(1) {
+            Some(last_epoch) => {
+                batch.put(LAST_LOG_INDEXED_EPOCH_KEY, last_epoch.db_encode())
+            }
+            None => batch.delete(LAST_LOG_INDEXED_EPOCH_KEY),
+        }
+        counts
+    }
+
+    fn log_location_count<'a>(
+        &self, counts: &'a mut HashMap<LogIndexKey, u64>, key: &LogIndexKey,
+    ) -> &'a mut u64 {
+        counts.entry(*key).or_insert_with(|| {
+            self.log_location_count_from_db(key).unwrap_or(0)
+        })
+    }
+
+    fn put_log_location_counts(
+        counts: HashMap<LogIndexKey, u64>, batch: &mut DBBatch,
+    ) {
+        for (key, count) in counts {
+            batch.put(&log_location_count_key(&key), count.db_encode());
+        }
+    }
+
     /// Store block info to db. Block info includes block status and
     /// the sequence number when the block enters consensus graph.
     /// The db key is the block hash plus one extra byte, so we can get better
@@ -205,6 +599,10 @@ impl DBManager {
         )
     }
 
+    pub fn remove_local_block_info_from_db(&self, block_hash: &H256) {
+        self.remove_from_db(DBTable::Blocks, &local_block_info_key(block_hash))
+    }
+
     pub fn insert_blamed_header_verified_roots_to_db(
         &self, block_height: u64, value: &BlamedHeaderVerifiedRoots,
     ) {
@@ -356,6 +754,18 @@ impl DBManager {
         )
     }
 
+    /// Remove both the executed and the skipped block sets of the epoch.
+    pub fn remove_epoch_set_hashes_from_db(&self, epoch: u64) {
+        self.remove_from_db(
+            DBTable::EpochNumbers,
+            &executed_epoch_set_key(epoch)[0..9],
+        );
+        self.remove_from_db(
+            DBTable::EpochNumbers,
+            &skipped_epoch_set_key(epoch)[0..9],
+        );
+    }
+
     pub fn insert_block_terminals_to_db(&self, terminals: &Vec<H256>) {
         self.insert_encodable_list(
             DBTable::Misc,
@@ -406,6 +816,32 @@ impl DBManager {
         );
     }
 
+    pub fn insert_epoch_internal_transfers_to_db(
+        &self, hash: &H256, transfers: &Vec<TransactionInternalTransfers>,
+    ) {
+        self.insert_encodable_list(
+            DBTable::Blocks,
+            &epoch_internal_transfers_key(hash),
+            transfers,
+        )
+    }
+
+    pub fn epoch_internal_transfers_from_db(
+        &self, hash: &H256,
+    ) -> Option<Vec<TransactionInternalTransfers>> {
+        self.load_decodable_list(
+            DBTable::Blocks,
+            &epoch_internal_transfers_key(hash),
+        )
+    }
+
+    pub fn remove_epoch_internal_transfers_from_db(&self, hash: &H256) {
+        self.remove_from_db(
+            DBTable::Blocks,
+            &epoch_internal_transfers_key(hash),
+        )
+    }
+
     pub fn insert_instance_id_to_db(&self, instance_id: u64) {
         self.insert_encodable_val(DBTable::Misc, b"instance", &instance_id);
     }
@@ -455,6 +891,24 @@ impl DBManager {
             .expect("db removal failure");
     }
 
+    /// Commit the writes in `batch` in one transaction.
+    fn commit_batch(&self, batch: DBBatch) {
+        let db = &**self.table_db.get(&batch.table).unwrap();
+        let mut transaction = db
+            .start_transaction_dyn(true /* immediate_write */)
+            .expect("db transaction failure");
+        for (db_key, value) in batch.ops {
+            match value {
+                Some(value) => transaction.put(&db_key, &value),
+                None => transaction.delete(&db_key),
+            }
+            .expect("db batch failure");
+        }
+        transaction
+            .commit(db.as_any())
+            .expect("db batch commit failure");
+    }
+
     fn load_from_db(&self, table: DBTable, db_key: &[u8]) -> Option<Box<[u8]>> {
         self.table_db
             .get(&table)
@@ -509,6 +963,106 @@ fn blamed_header_verified_roots_key(block_height: u64) -> [u8; 8] {
     height_key
 }
 
+/// The key is the address followed by the direction, so that it does not
+/// collide with the 32-byte pivot block hashes in the same table.
+fn address_transaction_count_key(
+    address: &Address, direction: TransactionDirection,
+) -> [u8; 21] {
+    let mut key = [0; 21];
+    key[0..20].copy_from_slice(address.as_bytes());
+    key[20] = direction as u8;
+    key
+}
+
+/// `seq` is big endian, so that the transactions of an address are stored in
+/// order.
+fn address_transaction_key(
+    address: &Address, direction: TransactionDirection, seq: u64,
+) -> [u8; 29] {
+    let mut key = [0; 29];
+    key[0..21]
+        .copy_from_slice(&address_transaction_count_key(address, direction));
+    BigEndian::write_u64(&mut key[21..29], seq);
+    key
+}
+
+/// The key of the pivot block hash of an indexed epoch. It is 8 bytes, so that
+/// it does not collide with the other keys in the same table.
+fn address_indexed_epoch_key(epoch_number: u64) -> [u8; 8] {
+    let mut key = [0; 8];
+    BigEndian::write_u64(&mut key, epoch_number);
+    key
+}
+
+/// An entry is encoded as the address, the direction and the transaction
+/// hash.
+fn encode_address_transaction_entry(
+    (address, direction, hash): &AddressTransactionEntry, value: &mut Vec<u8>,
+) {
+    value.extend_from_slice(address.as_bytes());
+    value.push(*direction as u8);
+    value.extend_from_slice(hash.as_bytes());
+}
+
+fn decode_address_transaction_entry(value: &[u8]) -> AddressTransactionEntry {
+    let direction = match value[20] {
+        0 => TransactionDirection::Sent,
+        _ => TransactionDirection::Received,
+    };
+    (
+        Address::from_slice(&value[0..20]),
+        direction,
+        H256::from_slice(&value[21..ADDRESS_TRANSACTION_ENTRY_LEN]),
+    )
+}
+
+/// The key is a byte for the kind of the key followed by the address or the
+/// topic. The other keys in the same table start with the kinds 2 and
+/// `LOG_INDEXED_EPOCH_KEY_KIND`.
+fn log_location_count_key(key: &LogIndexKey) -> Vec<u8> {
+    let mut db_key = Vec::with_capacity(H256::len_bytes() + 9);
+    match key {
+        LogIndexKey::Address(address) => {
+            db_key.push(0);
+            db_key.extend_from_slice(address.as_bytes());
+        }
+        LogIndexKey::Topic(topic) => {
+            db_key.push(1);
+            db_key.extend_from_slice(topic.as_bytes());
+        }
+    }
+    db_key
+}
+
+fn log_location_key(key: &LogIndexKey, seq: u64) -> Vec<u8> {
+    let mut db_key = log_location_count_key(key);
+    let mut seq_key = [0; 8];
+    BigEndian::write_u64(&mut seq_key, seq);
+    db_key.extend_from_slice(&seq_key);
+    db_key
+}
+
+/// The key of the log index keys with the logs in an indexed epoch.
+fn log_indexed_epoch_key(epoch_number: u64) -> [u8; 9] {
+    let mut key = [LOG_INDEXED_EPOCH_KEY_KIND; 9];
+    BigEndian::write_u64(&mut key[1..9], epoch_number);
+    key
+}
+
+/// Decode the concatenated `log_location_count_key` of the keys.
+fn decode_log_index_keys(mut value: &[u8]) -> Vec<LogIndexKey> {
+    let mut keys = Vec::new();
+    while !value.is_empty() {
+        let (key, len) = match value[0] {
+            0 => (LogIndexKey::Address(Address::from_slice(&value[1..21])), 21),
+            _ => (LogIndexKey::Topic(H256::from_slice(&value[1..33])), 33),
+        };
+        keys.push(key);
+        value = &value[len..];
+    }
+    keys
+}
+
 fn block_body_key(block_hash: &H256) -> Vec<u8> {
     append_suffix(block_hash, BLOCK_BODY_SUFFIX_BYTE)
 }
@@ -543,6 +1097,10 @@ fn epoch_consensus_epoch_execution_commitment_key(hash: &H256) -> Vec<u8> {
     append_suffix(hash, EPOCH_CONSENSUS_EXECUTION_INFO_SUFFIX_BYTE)
 }
 
+fn epoch_internal_transfers_key(hash: &H256) -> Vec<u8> {
+    append_suffix(hash, EPOCH_INTERNAL_TRANSFERS_SUFFIX_BYTE)
+}
+
 impl MallocSizeOf for DBManager {
     fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
         // Here we only handle the case that all columns are stored within the
@@ -553,3 +1111,297 @@ impl MallocSizeOf for DBManager {
             .size_of(ops)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::DBManager;
+    use crate::{
+        block_data_manager::{
+            LogIndexKey, LogLocation,
+            TransactionDirection::{Received, Sent},
+        },
+        db::NUM_COLUMNS,
+        pow::{PowAlgorithmType, PowComputer},
+        trace::{InternalTransfer, TransactionInternalTransfers},
+    };
+    use cfx_types::{Address, H256};
+    use std::{collections::BTreeMap, path::Path, sync::Arc};
+    use tempdir::TempDir;
+
+    fn new_db_manager(dir: &TempDir) -> DBManager {
+        let path = dir.path().to_str().unwrap();
+        let db = db::open_database(
+            path,
+            &db::db_config(
+                Path::new(path),
+                None,
+                Default::default(),
+                NUM_COLUMNS,
+                false, /* disable_wal */
+            ),
+        )
+        .unwrap();
+        let pow = Arc::new(PowComputer::new(PowAlgorithmType::Dev));
+        DBManager::new_from_rocksdb(db, pow)
+    }
+
+    fn address(n: u64) -> Address { Address::from_low_u64_be(n) }
+
+    fn hash(n: u64) -> H256 { H256::from_low_u64_be(n) }
+
+    fn sent(db_manager: &DBManager, n: u64) -> Vec<H256> {
+        let count = db_manager
+            .address_transaction_count_from_db(&address(n), Sent)
+            .unwrap_or(0);
+        (0..count)
+            .map(|seq| {
+                db_manager
+                    .address_transaction_from_db(&address(n), Sent, seq)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_address_transaction_reorg() {
+        let dir = TempDir::new("address_transaction_reorg").unwrap();
+        let db_manager = new_db_manager(&dir);
+
+        // epochs 1 and 2 on the first pivot chain
+        db_manager.insert_epoch_address_transactions_to_db(
+            1,
+            &hash(101),
+            &[(address(1), Sent, hash(1)), (address(2), Received, hash(1))],
+        );
+        db_manager.insert_epoch_address_transactions_to_db(
+            2,
+            &hash(102),
+            &[(address(1), Sent, hash(2)), (address(3), Sent, hash(3))],
+        );
+        assert_eq!(sent(&db_manager, 1), vec![hash(1), hash(2)]);
+        assert_eq!(sent(&db_manager, 3), vec![hash(3)]);
+
+        // epoch 2 on another pivot chain replaces the transactions of the
+        // addresses not in the new epoch too
+        db_manager.insert_epoch_address_transactions_to_db(
+            2,
+            &hash(202),
+            &[(address(1), Sent, hash(4))],
+        );
+        assert_eq!(sent(&db_manager, 1), vec![hash(1), hash(4)]);
+        assert!(sent(&db_manager, 3).is_empty());
+        assert!(db_manager
+            .address_transaction_from_db(&address(3), Sent, 0)
+            .is_none());
+
+        // a reorg from an earlier epoch reverts all the later epochs
+        db_manager.insert_epoch_address_transactions_to_db(
+            3,
+            &hash(203),
+            &[(address(3), Sent, hash(5))],
+        );
+        db_manager.insert_epoch_address_transactions_to_db(
+            1,
+            &hash(301),
+            &[(address(2), Sent, hash(6))],
+        );
+        assert!(sent(&db_manager, 1).is_empty());
+        assert!(sent(&db_manager, 3).is_empty());
+        assert_eq!(sent(&db_manager, 2), vec![hash(6)]);
+        assert_eq!(
+            db_manager.address_transaction_count_from_db(&address(2), Received),
+            Some(0)
+        );
+
+        // the entries of the epochs are kept to be indexed again
+        assert_eq!(
+            db_manager.epoch_address_transactions_from_db(&hash(102)),
+            Some(vec![
+                (address(1), Sent, hash(2)),
+                (address(3), Sent, hash(3))
+            ])
+        );
+
+        db_manager.remove_address_transactions_from_db(1);
+        assert!(sent(&db_manager, 2).is_empty());
+    }
+
+    fn location(epoch_number: u64, transaction_index: u64) -> LogLocation {
+        LogLocation {
+            epoch_number,
+            block_index: 0,
+            transaction_index,
+            transaction_log_index: 0,
+        }
+    }
+
+    fn insert_logs(
+        db_manager: &DBManager, epoch_number: u64, logs: &[(LogIndexKey, u64)],
+    ) {
+        let mut locations: BTreeMap<LogIndexKey, Vec<LogLocation>> =
+            BTreeMap::new();
+        for (key, transaction_index) in logs {
+            locations
+                .entry(*key)
+                .or_default()
+                .push(location(epoch_number, *transaction_index));
+        }
+        db_manager.insert_epoch_log_locations_to_db(epoch_number, &locations);
+    }
+
+    #[test]
+    fn test_log_locations() {
+        let dir = TempDir::new("log_locations").unwrap();
+        let db_manager = new_db_manager(&dir);
+        let contract = LogIndexKey::Address(address(1));
+        let topic = LogIndexKey::Topic(hash(1));
+
+        // nothing is indexed yet
+        assert_eq!(db_manager.log_locations_from_db(&contract, 0, 10), None);
+
+        for epoch_number in 3..8 {
+            insert_logs(
+                &db_manager,
+                epoch_number,
+                &[(contract, 0), (contract, 1), (topic, epoch_number)],
+            );
+        }
+
+        // the epochs before the index are not covered
+        assert_eq!(db_manager.log_locations_from_db(&contract, 2, 5), None);
+
+        assert_eq!(
+            db_manager.log_locations_from_db(&contract, 4, 5),
+            Some(vec![
+                location(4, 0),
+                location(4, 1),
+                location(5, 0),
+                location(5, 1)
+            ])
+        );
+        assert_eq!(
+            db_manager.log_locations_from_db(&topic, 3, 7),
+            Some((3..8).map(|epoch| location(epoch, epoch)).collect())
+        );
+        // nor the epochs after the index
+        assert_eq!(db_manager.log_locations_from_db(&topic, 3, 100), None);
+        assert_eq!(
+            db_manager.log_locations_from_db(
+                &LogIndexKey::Topic(hash(2)),
+                3,
+                7
+            ),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_log_index_reorg() {
+        let dir = TempDir::new("log_index_reorg").unwrap();
+        let db_manager = new_db_manager(&dir);
+        let contract = LogIndexKey::Address(address(1));
+        let other = LogIndexKey::Address(address(2));
+
+        insert_logs(&db_manager, 1, &[(contract, 0)]);
+        insert_logs(&db_manager, 2, &[(contract, 0), (other, 1)]);
+        insert_logs(&db_manager, 3, &[(other, 0)]);
+
+        // epoch 2 on another pivot chain removes the locations of epochs 2
+        // and 3, including those of the keys not in the new epoch
+        insert_logs(&db_manager, 2, &[(contract, 2)]);
+        assert_eq!(db_manager.log_locations_from_db(&contract, 1, 3), None);
+        assert_eq!(
+            db_manager.log_locations_from_db(&contract, 1, 2),
+            Some(vec![location(1, 0), location(2, 2)])
+        );
+        assert_eq!(
+            db_manager.log_locations_from_db(&other, 1, 2),
+            Some(vec![])
+        );
+        assert_eq!(db_manager.log_location_count_from_db(&other), Some(0));
+        assert!(db_manager.log_location_from_db(&other, 0).is_none());
+
+        // the index is emptied by a reorg from its first epoch
+        db_manager.remove_log_locations_from_db(1);
+        assert_eq!(db_manager.log_locations_from_db(&contract, 1, 3), None);
+        assert_eq!(db_manager.log_location_count_from_db(&contract), Some(0));
+
+        // and starts again from the next indexed epoch
+        insert_logs(&db_manager, 1, &[(other, 0)]);
+        assert_eq!(
+            db_manager.log_locations_from_db(&other, 1, 1),
+            Some(vec![location(1, 0)])
+        );
+    }
+
+    #[test]
+    fn test_log_index_gap() {
+        let dir = TempDir::new("log_index_gap").unwrap();
+        let db_manager = new_db_manager(&dir);
+        let contract = LogIndexKey::Address(address(1));
+
+        for epoch_number in 1..3 {
+            insert_logs(&db_manager, epoch_number, &[(contract, 0)]);
+        }
+        // epochs 3 and 4 are executed without the index, so it starts again
+        // from epoch 5
+        for epoch_number in 5..7 {
+            insert_logs(&db_manager, epoch_number, &[(contract, 0)]);
+        }
+        assert_eq!(db_manager.log_index_start_epoch_from_db(), Some(5));
+        assert_eq!(db_manager.log_locations_from_db(&contract, 1, 6), None);
+        assert_eq!(db_manager.log_locations_from_db(&contract, 2, 5), None);
+        assert_eq!(
+            db_manager.log_locations_from_db(&contract, 5, 6),
+            Some(vec![location(5, 0), location(6, 0)])
+        );
+
+        // a reorg within the index keeps its start
+        insert_logs(&db_manager, 6, &[(contract, 1)]);
+        assert_eq!(
+            db_manager.log_locations_from_db(&contract, 5, 6),
+            Some(vec![location(5, 0), location(6, 1)])
+        );
+    }
+
+    #[test]
+    fn test_epoch_internal_transfers() {
+        let dir = TempDir::new("epoch_internal_transfers").unwrap();
+        let db_manager = new_db_manager(&dir);
+
+        let transfers = vec![TransactionInternalTransfers {
+            block_hash: hash(1),
+            transaction_hash: hash(2),
+            transfers: vec![
+                InternalTransfer {
+                    from: address(1),
+                    to: address(2),
+                    value: 5.into(),
+                },
+                InternalTransfer {
+                    from: address(2),
+                    to: address(3),
+                    value: 1.into(),
+                },
+            ],
+        }];
+        db_manager
+            .insert_epoch_internal_transfers_to_db(&hash(101), &transfers);
+        // an epoch traced without internal transfers
+        db_manager.insert_epoch_internal_transfers_to_db(&hash(102), &vec![]);
+
+        assert_eq!(
+            db_manager.epoch_internal_transfers_from_db(&hash(101)),
+            Some(transfers)
+        );
+        assert_eq!(
+            db_manager.epoch_internal_transfers_from_db(&hash(102)),
+            Some(vec![])
+        );
+        // an epoch not traced
+        assert_eq!(
+            db_manager.epoch_internal_transfers_from_db(&hash(103)),
+            None
+        );
+    }
+}
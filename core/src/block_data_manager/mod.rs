@@ -7,12 +7,13 @@ use crate::{
     cache_manager::{CacheId, CacheManager, CacheSize},
     ext_db::SystemDB,
     pow::{PowComputer, TargetDifficultyManager},
+    trace::{EpochInternalTransfers, TransactionInternalTransfers},
 };
 use cfx_storage::{
     state_manager::StateIndex, utils::guarded_value::*, StorageManager,
     StorageManagerTrait, StorageStateTrait,
 };
-use cfx_types::{Bloom, H256};
+use cfx_types::{Address, Bloom, H256};
 use malloc_size_of::{new_malloc_size_ops, MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard};
@@ -22,17 +23,18 @@ use primitives::{
         BlockReceipts, TRANSACTION_OUTCOME_EXCEPTION_WITH_NONCE_BUMPING,
         TRANSACTION_OUTCOME_SUCCESS,
     },
-    Block, BlockHeader, EpochId, SignedTransaction, TransactionIndex,
+    Action, Block, BlockHeader, EpochId, SignedTransaction, TransactionIndex,
     TransactionWithSignature, NULL_EPOCH,
 };
 use rlp::DecoderError;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 use threadpool::ThreadPool;
 pub mod block_data_types;
 pub mod db_manager;
+mod rollback;
 pub mod tx_data_manager;
 use crate::block_data_manager::{
     db_manager::DBManager, tx_data_manager::TransactionDataManager,
@@ -42,6 +44,7 @@ use cfx_internal_common::{
     EpochExecutionCommitment, StateAvailabilityBoundary, StateRootWithAuxInfo,
 };
 use metrics::{register_meter_with_group, Meter, MeterTimer};
+pub use rollback::RollbackResult;
 use std::{hash::Hash, path::Path, time::Duration};
 
 lazy_static! {
@@ -704,6 +707,184 @@ impl BlockDataManager {
         }
     }
 
+    /// Index the transactions of the epoch `epoch_number` on the pivot chain
+    /// by their senders, the receivers of the calls and the receivers of the
+    /// internal transfers, replacing the epochs from `epoch_number` indexed
+    /// on another pivot chain. It is called before the execution commitment
+    /// of the epoch is stored, so that an epoch interrupted by a crash is
+    /// indexed again when it is executed again.
+    pub fn insert_epoch_address_transaction_index(
+        &self, epoch_number: u64, pivot_hash: &H256,
+        transactions: &[(&SignedTransaction, Vec<Address>)],
+    )
+    {
+        if !self.config.persist_address_tx_index {
+            return;
+        }
+        let mut entries = Vec::new();
+        for (transaction, internal_receivers) in transactions {
+            let hash = transaction.hash();
+            entries.push((
+                transaction.sender,
+                TransactionDirection::Sent,
+                hash,
+            ));
+
+            let mut receivers =
+                Vec::with_capacity(internal_receivers.len() + 1);
+            if let Action::Call(ref receiver) = transaction.action {
+                receivers.push(*receiver);
+            }
+            for receiver in internal_receivers {
+                if !receivers.contains(receiver) {
+                    receivers.push(*receiver);
+                }
+            }
+            entries.extend(receivers.into_iter().map(|receiver| {
+                (receiver, TransactionDirection::Received, hash)
+            }));
+        }
+        self.db_manager.insert_epoch_address_transactions_to_db(
+            epoch_number,
+            pivot_hash,
+            &entries,
+        );
+    }
+
+    /// Index the transactions of an epoch whose execution is skipped on the
+    /// pivot chain. The internal transfers are not traced without execution,
+    /// so the entries indexed when the epoch was executed are reused if any.
+    fn recover_epoch_address_transaction_index(
+        &self, epoch_number: u64, pivot_hash: &H256,
+        transactions: &[(&SignedTransaction, Vec<Address>)],
+    )
+    {
+        if !self.config.persist_address_tx_index {
+            return;
+        }
+        match self
+            .db_manager
+            .epoch_address_transactions_from_db(pivot_hash)
+        {
+            Some(entries) => {
+                self.db_manager.insert_epoch_address_transactions_to_db(
+                    epoch_number,
+                    pivot_hash,
+                    &entries,
+                )
+            }
+            None => self.insert_epoch_address_transaction_index(
+                epoch_number,
+                pivot_hash,
+                transactions,
+            ),
+        }
+    }
+
+    /// Return the number of the transactions of `address` in `direction`, and
+    /// the hashes of at most `limit` of them from the `offset`-th, in the
+    /// order of execution. Return `None` if the index is not persisted.
+    pub fn address_transactions(
+        &self, address: &Address, direction: TransactionDirection, offset: u64,
+        limit: u64,
+    ) -> Option<(u64, Vec<H256>)>
+    {
+        if !self.config.persist_address_tx_index {
+            return None;
+        }
+        let count = self
+            .db_manager
+            .address_transaction_count_from_db(address, direction)
+            .unwrap_or(0);
+        let end = count.min(offset.saturating_add(limit));
+        let hashes = (offset..end)
+            .filter_map(|seq| {
+                self.db_manager
+                    .address_transaction_from_db(address, direction, seq)
+            })
+            .collect();
+        Some((count, hashes))
+    }
+
+    /// Index the logs of the epoch on the pivot chain by their addresses and
+    /// first topics, replacing the epochs from `epoch_number` indexed on
+    /// another pivot chain. Like the address index, it is written before the
+    /// execution commitment of the epoch.
+    pub fn insert_epoch_log_index(
+        &self, epoch_number: u64, epoch_receipts: &[Arc<BlockReceipts>],
+    ) {
+        if !self.config.persist_log_index {
+            return;
+        }
+
+        let mut epoch_locations: BTreeMap<LogIndexKey, Vec<LogLocation>> =
+            BTreeMap::new();
+        for (block_index, block_receipts) in epoch_receipts.iter().enumerate() {
+            for (transaction_index, receipt) in
+                block_receipts.receipts.iter().enumerate()
+            {
+                for (transaction_log_index, log) in
+                    receipt.logs.iter().enumerate()
+                {
+                    let location = LogLocation {
+                        epoch_number,
+                        block_index: block_index as u64,
+                        transaction_index: transaction_index as u64,
+                        transaction_log_index: transaction_log_index as u64,
+                    };
+                    let mut keys = vec![LogIndexKey::Address(log.address)];
+                    if let Some(topic) = log.topics.first() {
+                        keys.push(LogIndexKey::Topic(*topic));
+                    }
+                    for key in keys {
+                        epoch_locations.entry(key).or_default().push(location);
+                    }
+                }
+            }
+        }
+        self.db_manager
+            .insert_epoch_log_locations_to_db(epoch_number, &epoch_locations);
+    }
+
+    /// Return the locations of the logs of `key` in the epochs from
+    /// `from_epoch` to `to_epoch`, in the order of execution. Return `None` if
+    /// the logs of the epochs are not indexed.
+    pub fn log_locations(
+        &self, key: &LogIndexKey, from_epoch: u64, to_epoch: u64,
+    ) -> Option<Vec<LogLocation>> {
+        if !self.config.persist_log_index {
+            return None;
+        }
+        self.db_manager
+            .log_locations_from_db(key, from_epoch, to_epoch)
+    }
+
+    /// Store the internal transfers traced when the epoch of `pivot_hash` is
+    /// executed. Like the indices, they are stored before the execution
+    /// commitment of the epoch.
+    pub fn insert_epoch_internal_transfers(
+        &self, pivot_hash: &H256, transfers: &Vec<TransactionInternalTransfers>,
+    ) {
+        self.db_manager
+            .insert_epoch_internal_transfers_to_db(pivot_hash, transfers);
+    }
+
+    /// Return the internal transfers stored for the epoch `epoch_number` with
+    /// the pivot block `pivot_hash`, or `None` if they were not traced when the
+    /// epoch was executed.
+    pub fn epoch_internal_transfers(
+        &self, epoch_number: u64, pivot_hash: &H256,
+    ) -> Option<EpochInternalTransfers> {
+        let transactions = self
+            .db_manager
+            .epoch_internal_transfers_from_db(pivot_hash)?;
+        Some(EpochInternalTransfers {
+            epoch_number,
+            pivot_hash: *pivot_hash,
+            transactions,
+        })
+    }
+
     pub fn insert_local_block_info(&self, hash: &H256, info: LocalBlockInfo) {
         self.insert(
             *hash,
@@ -1026,11 +1207,19 @@ impl BlockDataManager {
                 }
             }
             // Recover tx address if we will skip pivot chain execution
-            for (block_idx, block_hash) in epoch_block_hashes.iter().enumerate()
+            let epoch_blocks: Vec<_> = epoch_block_hashes
+                .iter()
+                .map(|block_hash| {
+                    self.block_by_hash(block_hash, true /* update_cache */)
+                        .expect("block exists")
+                })
+                .collect();
+            let mut indexed_transactions = Vec::new();
+            for (block_idx, (block_hash, block)) in epoch_block_hashes
+                .iter()
+                .zip(epoch_blocks.iter())
+                .enumerate()
             {
-                let block = self
-                    .block_by_hash(block_hash, true /* update_cache */)
-                    .expect("block exists");
                 for (tx_idx, tx) in block.transactions.iter().enumerate() {
                     match epoch_receipts[block_idx]
                         .receipts
@@ -1046,12 +1235,24 @@ impl BlockDataManager {
                                     block_hash: *block_hash,
                                     index: tx_idx,
                                 },
-                            )
+                            );
+                            indexed_transactions.push((&**tx, Vec::new()));
                         }
                         _ => {}
                     }
                 }
             }
+            if let Some(pivot_header) = self.block_header_by_hash(epoch_hash) {
+                self.recover_epoch_address_transaction_index(
+                    pivot_header.height(),
+                    epoch_hash,
+                    &indexed_transactions,
+                );
+                self.insert_epoch_log_index(
+                    pivot_header.height(),
+                    &epoch_receipts,
+                );
+            }
         }
         true
     }
@@ -1302,6 +1503,8 @@ pub enum DbType {
 
 pub struct DataManagerConfiguration {
     persist_tx_index: bool,
+    persist_address_tx_index: bool,
+    persist_log_index: bool,
     tx_cache_index_maintain_timeout: Duration,
     db_type: DbType,
 }
@@ -1312,12 +1515,15 @@ impl MallocSizeOf for DataManagerConfiguration {
 
 impl DataManagerConfiguration {
     pub fn new(
-        persist_tx_index: bool, tx_cache_index_maintain_timeout: Duration,
+        persist_tx_index: bool, persist_address_tx_index: bool,
+        persist_log_index: bool, tx_cache_index_maintain_timeout: Duration,
         db_type: DbType,
     ) -> Self
     {
         Self {
             persist_tx_index,
+            persist_address_tx_index,
+            persist_log_index,
             tx_cache_index_maintain_timeout,
             db_type,
         }
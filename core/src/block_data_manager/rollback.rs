@@ -0,0 +1,416 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Roll the local database back to an executed epoch, for recovering from
+//! bad local data or re-executing the epochs after a hardfork without
+//! resyncing. It runs on the database of a stopped node, before the graphs
+//! are recovered.
+
+use super::BlockDataManager;
+use cfx_storage::{StorageManagerTrait, StorageStateTrait};
+use cfx_types::H256;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Debug, PartialEq)]
+pub struct RollbackResult {
+    pub epoch_number: u64,
+    pub pivot_hash: H256,
+    /// The number of the removed executed epochs on the pivot chain.
+    pub removed_epochs: u64,
+    pub removed_blocks: usize,
+}
+
+impl BlockDataManager {
+    /// Remove the blocks which are not in the past of the pivot block of
+    /// `epoch_number`, with their execution results and transaction indices,
+    /// the later epochs in the epoch sets and the address and log indices,
+    /// and make the remaining terminal blocks the terminals, so that the node
+    /// recovers the graph until the epoch and executes the later epochs
+    /// again. The state of the epoch must be retained.
+    pub fn rollback_to_epoch(
+        &self, epoch_number: u64,
+    ) -> Result<RollbackResult, String> {
+        let stable_hash = self.get_cur_consensus_era_stable_hash();
+        let stable_height = self
+            .block_header_by_hash(&stable_hash)
+            .ok_or("The stable checkpoint block is missing")?
+            .height();
+        if epoch_number < stable_height {
+            return Err(format!(
+                "Epoch {} is before the stable checkpoint at epoch {}",
+                epoch_number, stable_height
+            ));
+        }
+        let pivot_hash = self
+            .executed_epoch_set_hashes_from_db(epoch_number)
+            .and_then(|hashes| hashes.last().cloned())
+            .ok_or_else(|| format!("Epoch {} is not executed", epoch_number))?;
+        self.check_state_retained(&pivot_hash).map_err(|e| {
+            format!("The state of epoch {} is unavailable: {}", epoch_number, e)
+        })?;
+        // The blocks before the checkpoint are not recovered, see
+        // `SynchronizationGraph::recover_graph_from_db`.
+        let stable_seq_num = self
+            .local_block_info_by_hash(&stable_hash)
+            .map_or(0, |info| info.get_seq_num());
+
+        // The blocks in the past of the pivot block since the checkpoint.
+        let mut past = HashSet::new();
+        past.insert(stable_hash);
+        for epoch in stable_height + 1..=epoch_number {
+            past.extend(self.all_epoch_set_hashes_from_db(epoch).ok_or_else(
+                || format!("The blocks of epoch {} are missing", epoch),
+            )?);
+        }
+        // The blocks of the later epochs, which may be on a stale pivot chain.
+        let mut later = HashSet::new();
+        let mut last_epoch = epoch_number;
+        while let Some(hashes) =
+            self.all_epoch_set_hashes_from_db(last_epoch + 1)
+        {
+            later.extend(hashes);
+            last_epoch += 1;
+        }
+        let removed_epochs =
+            self.later_pivot_epoch_count(epoch_number, pivot_hash, last_epoch);
+
+        // Traverse backward from the terminals until the past of the pivot
+        // block. The blocks of the later epochs and the blocks not executed
+        // yet are removed.
+        let mut terminals = self.block_terminals_from_db().unwrap_or_default();
+        terminals.extend(self.header_terminals_from_db().unwrap_or_default());
+        let mut removed = HashMap::new();
+        let mut queue: VecDeque<H256> = terminals.iter().cloned().collect();
+        while let Some(hash) = queue.pop_front() {
+            if past.contains(&hash) || removed.contains_key(&hash) {
+                continue;
+            }
+            if !later.contains(&hash)
+                && self.before_checkpoint(&hash, stable_seq_num)
+            {
+                continue;
+            }
+            let header = match self.block_header_by_hash(&hash) {
+                Some(header) => header,
+                None => continue,
+            };
+            queue.push_back(*header.parent_hash());
+            queue.extend(header.referee_hashes().iter().cloned());
+            removed.insert(hash, header);
+        }
+
+        // The remaining blocks whose children are all removed become the
+        // terminals, unless they are in the past of another remaining block.
+        let mut candidates: HashSet<H256> = terminals.into_iter().collect();
+        for header in removed.values() {
+            candidates.insert(*header.parent_hash());
+            candidates.extend(header.referee_hashes().iter().cloned());
+        }
+        candidates.retain(|hash| !removed.contains_key(hash));
+        candidates.insert(pivot_hash);
+        let mut new_terminals =
+            self.remaining_terminals(candidates, stable_seq_num);
+        new_terminals.sort();
+
+        // The indices are reverted with the entries of the removed epochs,
+        // so they are truncated before the blocks are removed.
+        self.db_manager
+            .remove_address_transactions_from_db(epoch_number + 1);
+        self.db_manager
+            .remove_log_locations_from_db(epoch_number + 1);
+        for hash in removed.keys() {
+            self.remove_block_data(hash);
+        }
+        for epoch in epoch_number + 1..=last_epoch {
+            self.db_manager.remove_epoch_set_hashes_from_db(epoch);
+        }
+        self.insert_block_terminals_to_db(new_terminals.clone());
+        self.insert_header_terminals_to_db(new_terminals);
+
+        info!(
+            "Rolled back to epoch {} {:?}, removed {} epochs and {} blocks",
+            epoch_number,
+            pivot_hash,
+            removed_epochs,
+            removed.len()
+        );
+        Ok(RollbackResult {
+            epoch_number,
+            pivot_hash,
+            removed_epochs,
+            removed_blocks: removed.len(),
+        })
+    }
+
+    /// The number of the epochs after `epoch_number` until `last_epoch` on
+    /// the pivot chain of `pivot_hash`. The epoch sets are linked by the
+    /// parents of the pivot blocks, so that the stale epochs of a previous
+    /// pivot chain are not counted.
+    fn later_pivot_epoch_count(
+        &self, epoch_number: u64, pivot_hash: H256, last_epoch: u64,
+    ) -> u64 {
+        let mut parent_hash = pivot_hash;
+        for epoch in epoch_number + 1..=last_epoch {
+            let epoch_pivot = match self
+                .all_epoch_set_hashes_from_db(epoch)
+                .and_then(|hashes| hashes.last().cloned())
+            {
+                Some(hash) => hash,
+                None => return epoch - epoch_number - 1,
+            };
+            match self.block_header_by_hash(&epoch_pivot) {
+                Some(header) if *header.parent_hash() == parent_hash => {}
+                _ => return epoch - epoch_number - 1,
+            }
+            parent_hash = epoch_pivot;
+        }
+        last_epoch - epoch_number
+    }
+
+    fn before_checkpoint(&self, hash: &H256, stable_seq_num: u64) -> bool {
+        self.local_block_info_by_hash(hash)
+            .map_or(false, |info| info.get_seq_num() < stable_seq_num)
+    }
+
+    /// The `candidates` which are not in the past of another candidate. The
+    /// past is traversed until the checkpoint.
+    fn remaining_terminals(
+        &self, candidates: HashSet<H256>, stable_seq_num: u64,
+    ) -> Vec<H256> {
+        let mut reached = HashSet::new();
+        let mut queue = VecDeque::new();
+        let push_parents = |queue: &mut VecDeque<H256>, hash: &H256| {
+            if let Some(header) = self.block_header_by_hash(hash) {
+                queue.push_back(*header.parent_hash());
+                queue.extend(header.referee_hashes().iter().cloned());
+            }
+        };
+        for hash in &candidates {
+            push_parents(&mut queue, hash);
+        }
+        while let Some(hash) = queue.pop_front() {
+            if !reached.insert(hash)
+                || self.before_checkpoint(&hash, stable_seq_num)
+            {
+                continue;
+            }
+            push_parents(&mut queue, &hash);
+        }
+        candidates
+            .into_iter()
+            .filter(|hash| !reached.contains(hash))
+            .collect()
+    }
+
+    fn check_state_retained(&self, pivot_hash: &H256) -> Result<(), String> {
+        let state_index = self
+            .get_state_readonly_index(pivot_hash)
+            .ok_or_else(|| "the execution commitment is missing".to_string())?;
+        let state = self
+            .storage_manager
+            .get_state_no_commit(state_index, /* try_open = */ true)
+            .map_err(|e| format!("{:?}", e))?
+            .ok_or_else(|| "the state is pruned".to_string())?;
+        state.get_state_root().map_err(|e| format!("{:?}", e))?;
+        Ok(())
+    }
+
+    /// Remove the block and its data from both the caches and the db.
+    fn remove_block_data(&self, hash: &H256) {
+        if let Some(transactions) = self.db_manager.block_body_from_db(hash) {
+            for transaction in transactions {
+                let indexed_in_block = self
+                    .db_manager
+                    .transaction_index_from_db(&transaction.hash)
+                    .map_or(false, |index| index.block_hash == *hash);
+                if indexed_in_block {
+                    self.transaction_indices.write().remove(&transaction.hash);
+                    self.db_manager
+                        .remove_transaction_index_from_db(&transaction.hash);
+                }
+            }
+        }
+        self.remove_block_result(hash, true /* remove_db */);
+        self.remove_epoch_execution_commitment(hash);
+        self.remove_epoch_execution_commitment_from_db(hash);
+        self.remove_epoch_execution_context(hash);
+        self.remove_epoch_execution_context_from_db(hash);
+        self.db_manager
+            .remove_epoch_address_transactions_from_db(hash);
+        self.db_manager
+            .remove_epoch_internal_transfers_from_db(hash);
+        self.remove_block_header(hash, true /* remove_db */);
+        self.remove_block_body(hash, true /* remove_db */);
+        self.local_block_info.write().remove(hash);
+        self.db_manager.remove_local_block_info_from_db(hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block_data_manager::{
+            BlockDataManager, BlockStatus, DbType, LocalBlockInfo, LogIndexKey,
+            LogLocation, TransactionDirection::Sent,
+        },
+        pow::{PowAlgorithmType, PowComputer},
+        sync::utils::initialize_data_manager,
+    };
+    use cfx_storage::{StateIndex, StorageManagerTrait, StorageStateTrait};
+    use cfx_types::{Address, H256};
+    use primitives::{Block, BlockHeaderBuilder};
+    use std::{collections::BTreeMap, sync::Arc};
+    use tempdir::TempDir;
+
+    fn insert_block(
+        data_man: &BlockDataManager, parent: &H256, referees: Vec<H256>,
+        seq_num: u64,
+    ) -> H256
+    {
+        let height = data_man.block_header_by_hash(parent).unwrap().height();
+        let mut header = BlockHeaderBuilder::new()
+            .with_parent_hash(*parent)
+            .with_height(height + 1)
+            .with_referee_hashes(referees)
+            .with_nonce(seq_num.into())
+            .build();
+        header.compute_hash();
+        let hash = header.hash();
+        data_man.insert_block(Arc::new(Block::new(header, vec![])), true);
+        data_man.insert_local_block_info(
+            &hash,
+            LocalBlockInfo::new(BlockStatus::Valid, seq_num, 0),
+        );
+        hash
+    }
+
+    /// Commit the state of the epoch of `pivot_hash` as the state of its
+    /// parent.
+    fn commit_state(data_man: &BlockDataManager, pivot_hash: &H256) {
+        let header = data_man.block_header_by_hash(pivot_hash).unwrap();
+        let parent_state_root = data_man
+            .get_epoch_execution_commitment(header.parent_hash())
+            .unwrap()
+            .state_root_with_aux_info
+            .clone();
+        let mut state = data_man
+            .storage_manager
+            .get_state_for_next_epoch(StateIndex::new_for_next_epoch(
+                header.parent_hash(),
+                &parent_state_root,
+                header.height() - 1,
+                data_man.get_snapshot_epoch_count(),
+            ))
+            .unwrap()
+            .unwrap();
+        state.compute_state_root().unwrap();
+        let state_root = state.commit(*pivot_hash).unwrap();
+        data_man.insert_epoch_execution_commitment(
+            *pivot_hash,
+            state_root,
+            H256::zero(),
+            H256::zero(),
+        );
+    }
+
+    fn location(epoch_number: u64) -> LogLocation {
+        LogLocation {
+            epoch_number,
+            block_index: 0,
+            transaction_index: 0,
+            transaction_log_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_rollback_to_epoch() {
+        let dir = TempDir::new("rollback_to_epoch").unwrap();
+        let pow = Arc::new(PowComputer::new(PowAlgorithmType::Dev));
+        let (data_man, genesis) = initialize_data_manager(
+            dir.path().to_str().unwrap(),
+            DbType::Rocksdb,
+            pow,
+        );
+        let genesis = genesis.hash();
+
+        // epoch 1: [a], epoch 2: [b], epoch 3: [c, d], and e is not executed
+        let a = insert_block(&data_man, &genesis, vec![], 1);
+        let b = insert_block(&data_man, &a, vec![], 2);
+        let c = insert_block(&data_man, &a, vec![], 3);
+        let d = insert_block(&data_man, &b, vec![c], 4);
+        let e = insert_block(&data_man, &a, vec![], 5);
+        for (epoch_number, epoch) in
+            vec![vec![a], vec![b], vec![c, d]].into_iter().enumerate()
+        {
+            let epoch_number = epoch_number as u64 + 1;
+            data_man
+                .insert_executed_epoch_set_hashes_to_db(epoch_number, &epoch);
+            data_man
+                .insert_skipped_epoch_set_hashes_to_db(epoch_number, &vec![]);
+            let pivot_hash = epoch.last().unwrap();
+            data_man.db_manager.insert_epoch_address_transactions_to_db(
+                epoch_number,
+                pivot_hash,
+                &[(Address::zero(), Sent, H256::from_low_u64_be(epoch_number))],
+            );
+            let mut locations = BTreeMap::new();
+            locations.insert(
+                LogIndexKey::Address(Address::zero()),
+                vec![location(epoch_number)],
+            );
+            data_man
+                .db_manager
+                .insert_epoch_log_locations_to_db(epoch_number, &locations);
+            data_man.insert_epoch_internal_transfers(pivot_hash, &vec![]);
+        }
+        commit_state(&data_man, &a);
+        data_man.insert_block_terminals_to_db(vec![d, e]);
+        data_man.insert_header_terminals_to_db(vec![d, e]);
+
+        assert!(data_man.rollback_to_epoch(4).is_err());
+        // the state of epoch 2 is not committed
+        assert!(data_man.rollback_to_epoch(2).is_err());
+
+        let result = data_man.rollback_to_epoch(1).unwrap();
+        assert_eq!(result.pivot_hash, a);
+        assert_eq!(result.removed_epochs, 2);
+        assert_eq!(result.removed_blocks, 4);
+
+        for hash in &[b, c, d, e] {
+            assert!(data_man.block_header_by_hash(hash).is_none());
+            assert!(data_man.local_block_info_by_hash(hash).is_none());
+        }
+        assert!(data_man.block_header_by_hash(&a).is_some());
+        assert!(data_man.all_epoch_set_hashes_from_db(2).is_none());
+        assert_eq!(data_man.block_terminals_from_db(), Some(vec![a]));
+        assert_eq!(data_man.header_terminals_from_db(), Some(vec![a]));
+
+        // the indices are truncated to epoch 1
+        assert_eq!(
+            data_man
+                .db_manager
+                .address_transaction_count_from_db(&Address::zero(), Sent),
+            Some(1)
+        );
+        assert_eq!(
+            data_man.db_manager.log_locations_from_db(
+                &LogIndexKey::Address(Address::zero()),
+                1,
+                1
+            ),
+            Some(vec![location(1)])
+        );
+        assert_eq!(
+            data_man.db_manager.last_log_indexed_epoch_from_db(),
+            Some(1)
+        );
+        // and the entries of the removed epochs are not indexed again
+        assert!(data_man
+            .db_manager
+            .epoch_address_transactions_from_db(&b)
+            .is_none());
+        assert!(data_man.epoch_internal_transfers(2, &b).is_none());
+        assert!(data_man.epoch_internal_transfers(1, &a).is_some());
+    }
+}
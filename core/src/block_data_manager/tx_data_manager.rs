@@ -1,7 +1,4 @@
-use crate::{
-    sync::request_manager::tx_handler::TransactionCacheContainer,
-    WORKER_COMPUTATION_PARALLELISM,
-};
+use crate::sync::request_manager::tx_handler::TransactionCacheContainer;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use metrics::{register_queue, Queue};
 use parking_lot::{Mutex, RwLock};
@@ -9,10 +6,7 @@ use primitives::{
     block::CompactBlock, Block, SignedTransaction, TransactionWithSignature,
 };
 use rlp::DecoderError;
-use std::{
-    sync::{mpsc::channel, Arc},
-    time::Duration,
-};
+use std::{sync::Arc, time::Duration};
 use threadpool::ThreadPool;
 
 lazy_static! {
@@ -156,84 +150,25 @@ impl TransactionDataManager {
     fn recover_uncached_tx(
         &self, uncached_trans: Vec<(usize, TransactionWithSignature)>,
     ) -> Result<Vec<(usize, Arc<SignedTransaction>)>, DecoderError> {
-        let mut recovered_trans = Vec::new();
-        if uncached_trans.len() < WORKER_COMPUTATION_PARALLELISM * 8 {
-            for (idx, tx) in uncached_trans {
-                if let Ok(public) = tx.recover_public() {
-                    recovered_trans.push((
-                        idx,
-                        Arc::new(SignedTransaction::new(public, tx.clone())),
-                    ));
-                } else {
-                    info!(
-                        "Unable to recover the public key of transaction {:?}",
-                        tx.hash()
-                    );
-                    return Err(DecoderError::Custom(
-                        "Cannot recover public key",
-                    ));
-                }
-            }
-        } else {
-            let tx_num = uncached_trans.len();
-            let tx_num_per_worker = tx_num / WORKER_COMPUTATION_PARALLELISM;
-            let mut remainder =
-                tx_num - (tx_num_per_worker * WORKER_COMPUTATION_PARALLELISM);
-            let mut start_idx = 0;
-            let mut end_idx = 0;
-            let mut unsigned_trans = Vec::new();
-
-            for tx in uncached_trans {
-                if start_idx == end_idx {
-                    // a new segment of transactions
-                    end_idx = start_idx + tx_num_per_worker;
-                    if remainder > 0 {
-                        end_idx += 1;
-                        remainder -= 1;
-                    }
-                    let unsigned_txns = Vec::new();
-                    unsigned_trans.push(unsigned_txns);
-                }
-
-                unsigned_trans.last_mut().unwrap().push(tx);
-
-                start_idx += 1;
-            }
-
-            let (sender, receiver) = channel();
-            let n_thread = unsigned_trans.len();
-            for unsigned_txns in unsigned_trans {
-                RECOVER_PUB_KEY_QUEUE.enqueue(unsigned_txns.len());
-                let sender = sender.clone();
-                self.worker_pool.lock().execute(move || {
-                    let mut signed_txns = Vec::new();
-                    for (idx, tx) in unsigned_txns {
-                        if let Ok(public) = tx.recover_public() {
-                            signed_txns.push((idx, Arc::new(SignedTransaction::new(
-                                public,
-                                tx.clone(),
-                            ))));
-                        } else {
-                            info!(
-                                "Unable to recover the public key of transaction {:?}",
-                                tx.hash()
-                            );
-                            break;
-                        }
-                    }
-                    sender.send(signed_txns).unwrap();
-                });
-            }
-
-            let mut total_recovered_num = 0 as usize;
-            for tx_publics in receiver.iter().take(n_thread) {
-                RECOVER_PUB_KEY_QUEUE.dequeue(tx_publics.len());
-                total_recovered_num += tx_publics.len();
-                for (idx, tx) in tx_publics {
-                    recovered_trans.push((idx, tx));
-                }
-            }
-            if total_recovered_num != tx_num {
+        // Clone the pool so that the lock is not held during the recovery.
+        let worker_pool = self.worker_pool.lock().clone();
+        RECOVER_PUB_KEY_QUEUE.enqueue(uncached_trans.len());
+        let publics = TransactionWithSignature::recover_public_batch(
+            uncached_trans.iter().map(|(_, tx)| tx),
+            &worker_pool,
+        );
+        RECOVER_PUB_KEY_QUEUE.dequeue(uncached_trans.len());
+
+        let mut recovered_trans = Vec::with_capacity(uncached_trans.len());
+        for ((idx, tx), public) in uncached_trans.into_iter().zip(publics) {
+            if let Ok(public) = public {
+                recovered_trans
+                    .push((idx, Arc::new(SignedTransaction::new(public, tx))));
+            } else {
+                info!(
+                    "Unable to recover the public key of transaction {:?}",
+                    tx.hash()
+                );
                 return Err(DecoderError::Custom("Cannot recover public key"));
             }
         }
@@ -22,6 +22,7 @@
 
 use std::{
     cmp::{max, min},
+    collections::BTreeMap,
     io::{self, Read},
 };
 
@@ -177,6 +178,69 @@ impl ModexpPricer {
     }
 }
 
+/// A precompiled contract with its gas pricing and native execution.
+pub trait Precompile: Send + Sync {
+    /// The gas cost of running this precompile for the given input data.
+    fn cost(&self, input: &[u8]) -> U256;
+
+    /// Execute this precompile on the given input, writing to the given
+    /// output.
+    fn execute(&self, input: &[u8], output: &mut BytesRef)
+        -> Result<(), Error>;
+
+    /// The epoch height from which this precompile is active.
+    fn activate_at(&self) -> u64;
+}
+
+/// The precompiled contracts keyed by address and activation epoch.
+///
+/// A precompile is active from its activation epoch until another one is
+/// activated at the same address, so that a new precompile, or a new pricing
+/// or implementation of an existing one, can be introduced by a hardfork
+/// without changing the executive.
+#[derive(Default)]
+pub struct BuiltinRegistry {
+    builtins: BTreeMap<Address, BTreeMap<u64, Box<dyn Precompile>>>,
+}
+
+impl BuiltinRegistry {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn register(
+        &mut self, address: Address, precompile: Box<dyn Precompile>,
+    ) {
+        let activate_at = precompile.activate_at();
+        let previous = self
+            .builtins
+            .entry(address)
+            .or_default()
+            .insert(activate_at, precompile);
+        assert!(
+            previous.is_none(),
+            "builtin at {:?} registered twice at epoch {}",
+            address,
+            activate_at
+        );
+    }
+
+    /// Return the precompile active at `epoch_height`.
+    pub fn get(
+        &self, address: &Address, epoch_height: u64,
+    ) -> Option<&dyn Precompile> {
+        self.builtins
+            .get(address)?
+            .range(..=epoch_height)
+            .next_back()
+            .map(|(_, precompile)| precompile.as_ref())
+    }
+
+    /// The addresses of all the registered precompiles, including those not
+    /// activated yet.
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.builtins.keys()
+    }
+}
+
 /// Pricing scheme, execution definition, and activation block for a built-in
 /// contract.
 ///
@@ -216,6 +280,18 @@ impl Builtin {
     }
 }
 
+impl Precompile for Builtin {
+    fn cost(&self, input: &[u8]) -> U256 { Builtin::cost(self, input) }
+
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        Builtin::execute(self, input, output)
+    }
+
+    fn activate_at(&self) -> u64 { self.activate_at }
+}
+
 /// Built-in instruction factory.
 pub fn builtin_factory(name: &str) -> Box<dyn Impl> {
     match name {
@@ -662,10 +738,11 @@ impl Bn128PairingImpl {
 #[cfg(test)]
 mod tests {
     use super::{
-        builtin_factory, modexp as me, Builtin, Linear, ModexpPricer, Pricer,
+        builtin_factory, modexp as me, Builtin, BuiltinRegistry, Linear,
+        ModexpPricer, Pricer,
     };
     use crate::bytes::BytesRef;
-    use cfx_types::U256;
+    use cfx_types::{Address, U256};
     use num::{BigUint, One, Zero};
     use rustc_hex::FromHex;
 
@@ -1186,4 +1263,34 @@ mod tests {
             .expect("Builtin should not fail");
         assert_eq!(i, o);
     }
+
+    #[test]
+    fn registry() {
+        let address = Address::from_low_u64_be(4);
+        let mut registry = BuiltinRegistry::new();
+        registry.register(
+            address,
+            Box::new(Builtin::new(
+                Box::new(Linear::new(15, 3)),
+                builtin_factory("identity"),
+                10,
+            )),
+        );
+        // Repriced at a later epoch.
+        registry.register(
+            address,
+            Box::new(Builtin::new(
+                Box::new(Linear::new(30, 6)),
+                builtin_factory("identity"),
+                100,
+            )),
+        );
+
+        assert!(registry.get(&address, 9).is_none());
+        assert!(registry.get(&Address::from_low_u64_be(5), 100).is_none());
+        assert_eq!(registry.get(&address, 10).unwrap().cost(&[]), 15.into());
+        assert_eq!(registry.get(&address, 99).unwrap().cost(&[]), 15.into());
+        assert_eq!(registry.get(&address, 100).unwrap().cost(&[]), 30.into());
+        assert_eq!(registry.addresses().collect::<Vec<_>>(), vec![&address]);
+    }
 }
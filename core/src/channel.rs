@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::UniqueId;
+use crate::{trace::EpochInternalTransfers, UniqueId};
 use cfx_types::H256;
 use parking_lot::RwLock;
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
@@ -111,18 +111,27 @@ impl<T: Clone> Channel<T> {
 
 pub struct Notifications {
     pub new_block_hashes: Arc<Channel<(H256, bool)>>,
+    /// The blocks processed by the consensus worker, in the order of
+    /// processing.
+    pub blocks_processed: Arc<Channel<H256>>,
     pub epochs_ordered: Arc<Channel<(u64, Vec<H256>)>>,
     pub blame_verification_results: Arc<Channel<(u64, Option<u64>)>>, /* <height, witness> */
+    /// The internal transfers of the pivot epochs, either traced when the
+    /// epoch is executed or stored when it was executed before. They are
+    /// only traced while there are subscribers or they are indexed.
+    pub internal_transfers: Arc<Channel<Arc<EpochInternalTransfers>>>,
 }
 
 impl Notifications {
     pub fn init() -> Arc<Self> {
         Arc::new(Notifications {
             new_block_hashes: Arc::new(Channel::new("new-block-hashes")),
+            blocks_processed: Arc::new(Channel::new("blocks-processed")),
             epochs_ordered: Arc::new(Channel::new("epochs-executed")),
             blame_verification_results: Arc::new(Channel::new(
                 "blame-verification-results",
             )),
+            internal_transfers: Arc::new(Channel::new("internal-transfers")),
         })
     }
 }
@@ -4,6 +4,7 @@
 
 use crate::{
     block_data_manager::{BlockDataManager, BlockRewardResult},
+    channel::Notifications,
     consensus::{
         consensus_inner::{
             consensus_new_block_handler::ConsensusNewBlockHandler,
@@ -11,7 +12,9 @@ use crate::{
         },
         ConsensusGraphInner,
     },
-    executive::{ExecutionOutcome, Executive, InternalContractMap},
+    executive::{
+        ExecutionOutcome, Executive, InternalContractMap, ParallelExecutive,
+    },
     machine::Machine,
     rpc_errors::{invalid_params_check, Result as RpcResult},
     state::{
@@ -20,9 +23,15 @@ use crate::{
         },
         CleanupMode, State,
     },
+    trace::{
+        CollateralTrace, CollateralTracer, EpochInternalTransfers,
+        InternalTransfer, NoopTracer, Tracer, TransactionInternalTransfers,
+        TransferTracer,
+    },
     verification::{compute_receipts_root, VerificationConfig},
     vm::{Env, Spec},
     vm_factory::VmFactory,
+    watchdog::{Heartbeat, IDLE_BEAT_INTERVAL, WATCHDOG},
     SharedTransactionPool,
 };
 use cfx_internal_common::{
@@ -34,7 +43,7 @@ use cfx_storage::{
     defaults::DEFAULT_EXECUTION_PREFETCH_THREADS, StateIndex,
     StorageManagerTrait,
 };
-use cfx_types::{BigEndianHash, H256, KECCAK_EMPTY_BLOOM, U256, U512};
+use cfx_types::{Address, BigEndianHash, H256, KECCAK_EMPTY_BLOOM, U256, U512};
 use core::convert::TryFrom;
 use hash::KECCAK_EMPTY_LIST_RLP;
 use metrics::{register_meter_with_group, Meter, MeterTimer};
@@ -47,7 +56,7 @@ use primitives::{
         TRANSACTION_OUTCOME_EXCEPTION_WITH_NONCE_BUMPING,
         TRANSACTION_OUTCOME_SUCCESS,
     },
-    Action, Block, BlockHeaderBuilder, EpochId, SignedTransaction,
+    AccessList, Action, Block, BlockHeaderBuilder, EpochId, SignedTransaction,
     TransactionIndex, MERKLE_NULL_NODE,
 };
 use std::{
@@ -56,7 +65,7 @@ use std::{
     fmt::{Debug, Formatter},
     sync::{
         atomic::{AtomicBool, Ordering::Relaxed},
-        mpsc::{channel, RecvError, Sender, TryRecvError},
+        mpsc::{channel, RecvTimeoutError, Sender, TryRecvError},
         Arc,
     },
     thread::{self, JoinHandle},
@@ -72,6 +81,8 @@ lazy_static! {
         );
     static ref GOOD_TPS_METER: Arc<dyn Meter> =
         register_meter_with_group("system_metrics", "good_tps");
+    static ref EXECUTION_HEARTBEAT: Arc<Heartbeat> =
+        WATCHDOG.heartbeat("execution");
 }
 
 /// The RewardExecutionInfo struct includes most information to compute rewards
@@ -111,6 +122,21 @@ enum ExecutionTask {
     Stop,
 }
 
+impl ExecutionTask {
+    /// The description of the task in the watchdog diagnostics.
+    fn stage(&self) -> String {
+        match self {
+            ExecutionTask::ExecuteEpoch(task) => {
+                format!("execute epoch {:?}", task.epoch_hash)
+            }
+            ExecutionTask::GetResult(task) => {
+                format!("get result of epoch {:?}", task.epoch_hash)
+            }
+            ExecutionTask::Stop => "stop".into(),
+        }
+    }
+}
+
 /// The EpochExecutionTask struct includes all the information needed to execute
 /// an epoch
 #[derive(Debug)]
@@ -178,6 +204,7 @@ impl ConsensusExecutor {
         vm: VmFactory, consensus_inner: Arc<RwLock<ConsensusGraphInner>>,
         config: ConsensusExecutionConfiguration,
         verification_config: VerificationConfig, bench_mode: bool,
+        notifications: Arc<Notifications>,
     ) -> Arc<Self>
     {
         let machine = tx_pool.machine();
@@ -188,6 +215,7 @@ impl ConsensusExecutor {
             config,
             verification_config,
             machine,
+            notifications,
         ));
         let (sender, receiver) = channel();
 
@@ -204,6 +232,7 @@ impl ConsensusExecutor {
         let handle = thread::Builder::new()
             .name("Consensus Execution Worker".into())
             .spawn(move || loop {
+                EXECUTION_HEARTBEAT.beat();
                 if executor_thread.stopped.load(Relaxed) {
                     // The thread should be stopped. The rest tasks in the queue
                     // will be discarded.
@@ -251,15 +280,18 @@ impl ConsensusExecutor {
                         //  and new tasks will be sent to `receiver` in this
                         // case, so this waiting will
                         // not prevent new optimistic tasks from being executed.
-                        match receiver.recv() {
+                        // The wait is bounded, so that the idle loop beats.
+                        match receiver.recv_timeout(IDLE_BEAT_INTERVAL) {
                             Ok(task) => task,
-                            Err(RecvError) => {
+                            Err(RecvTimeoutError::Timeout) => continue,
+                            Err(RecvTimeoutError::Disconnected) => {
                                 info!("Channel receive error, stop thread");
                                 break;
                             }
                         }
                     }
                 };
+                let _task = EXECUTION_HEARTBEAT.task(task.stage());
                 if !handler.handle_execution_work(task) {
                     // `task` is `Stop`, so just stop.
                     break;
@@ -608,8 +640,35 @@ impl ConsensusExecutor {
 
     pub fn call_virtual(
         &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
-    ) -> RpcResult<ExecutionOutcome> {
-        self.handler.call_virtual(tx, epoch_id, epoch_size)
+        pending: &[Arc<SignedTransaction>],
+    ) -> RpcResult<ExecutionOutcome>
+    {
+        self.handler.call_virtual(tx, epoch_id, epoch_size, pending)
+    }
+
+    pub fn create_access_list(
+        &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
+    ) -> RpcResult<(ExecutionOutcome, AccessList)> {
+        self.handler.create_access_list(tx, epoch_id, epoch_size)
+    }
+
+    pub fn trace_collateral(
+        &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
+    ) -> RpcResult<(ExecutionOutcome, Vec<CollateralTrace>)> {
+        self.handler.trace_collateral(tx, epoch_id, epoch_size)
+    }
+
+    pub fn differential_execute_epoch(
+        &self, epoch_hash: &H256, epoch_block_hashes: &Vec<H256>,
+        start_block_number: u64, variants: &[ExecutionVariant; 2],
+    ) -> Result<Option<ExecutionDivergence>, String>
+    {
+        self.handler.differential_execute_epoch(
+            epoch_hash,
+            epoch_block_hashes,
+            start_block_number,
+            variants,
+        )
     }
 
     pub fn stop(&self) {
@@ -789,6 +848,7 @@ pub struct ConsensusExecutionHandler {
     verification_config: VerificationConfig,
     machine: Arc<Machine>,
     execution_state_prefetcher: Option<Arc<ExecutionStatePrefetcher>>,
+    notifications: Arc<Notifications>,
 }
 
 impl ConsensusExecutionHandler {
@@ -796,6 +856,7 @@ impl ConsensusExecutionHandler {
         tx_pool: SharedTransactionPool, data_man: Arc<BlockDataManager>,
         vm: VmFactory, config: ConsensusExecutionConfiguration,
         verification_config: VerificationConfig, machine: Arc<Machine>,
+        notifications: Arc<Notifications>,
     ) -> Self
     {
         ConsensusExecutionHandler {
@@ -805,6 +866,7 @@ impl ConsensusExecutionHandler {
             config,
             verification_config,
             machine,
+            notifications,
             execution_state_prefetcher: if DEFAULT_EXECUTION_PREFETCH_THREADS
                 > 0
             {
@@ -891,6 +953,8 @@ impl ConsensusExecutionHandler {
         force_recompute: bool,
     )
     {
+        let _epoch_context =
+            log_mdc::insert_scoped("epoch_hash", format!("{:?}", epoch_hash));
         // FIXME: Question: where to calculate if we should make a snapshot?
         // FIXME: Currently we make the snapshotting decision when committing
         // FIXME: a new state.
@@ -940,6 +1004,12 @@ impl ConsensusExecutionHandler {
                         ));
                 }
             }
+            if on_local_pivot {
+                self.publish_stored_internal_transfers(
+                    pivot_block_header.height(),
+                    epoch_hash,
+                );
+            }
             self.data_man
                 .state_availability_boundary
                 .write()
@@ -965,28 +1035,9 @@ impl ConsensusExecutionHandler {
             epoch_blocks.len(),
         );
 
-        let spec = Spec::new_spec();
+        let spec = self.machine.spec(pivot_block.block_header.height());
         let mut state = State::new(
-            StateDb::new(
-                self.data_man
-                    .storage_manager
-                    .get_state_for_next_epoch(StateIndex::new_for_next_epoch(
-                        pivot_block.block_header.parent_hash(),
-                        &self
-                            .data_man
-                            .get_epoch_execution_commitment(
-                                pivot_block.block_header.parent_hash(),
-                            )
-                            // Unwrapping is safe because the state exists.
-                            .unwrap()
-                            .state_root_with_aux_info,
-                        pivot_block.block_header.height() - 1,
-                        self.data_man.get_snapshot_epoch_count(),
-                    ))
-                    .expect("No db error")
-                    // Unwrapping is safe because the state exists.
-                    .expect("State exists"),
-            ),
+            Self::state_db_for_next_epoch(&self.data_man, pivot_block),
             self.vm.clone(),
             &spec,
             start_block_number - 1, /* block_number */
@@ -1056,6 +1107,58 @@ impl ConsensusExecutionHandler {
             .adjust_upper_bound(&pivot_block.block_header);
     }
 
+    /// Open the state db of the parent of `pivot_block` to execute the
+    /// epoch.
+    fn state_db_for_next_epoch(
+        data_man: &BlockDataManager, pivot_block: &Block,
+    ) -> StateDb {
+        StateDb::new(
+            data_man
+                .storage_manager
+                .get_state_for_next_epoch(StateIndex::new_for_next_epoch(
+                    pivot_block.block_header.parent_hash(),
+                    &data_man
+                        .get_epoch_execution_commitment(
+                            pivot_block.block_header.parent_hash(),
+                        )
+                        // Unwrapping is safe because the state exists.
+                        .unwrap()
+                        .state_root_with_aux_info,
+                    pivot_block.block_header.height() - 1,
+                    data_man.get_snapshot_epoch_count(),
+                ))
+                .expect("No db error")
+                // Unwrapping is safe because the state exists.
+                .expect("State exists"),
+        )
+    }
+
+    /// Publish the internal transfers stored for a pivot epoch whose
+    /// execution is skipped, so that the subscribers receive the transfers of
+    /// every pivot epoch.
+    fn publish_stored_internal_transfers(
+        &self, epoch_number: u64, pivot_hash: &H256,
+    ) {
+        if self.notifications.internal_transfers.num_subscriptions() == 0 {
+            return;
+        }
+        match self
+            .data_man
+            .epoch_internal_transfers(epoch_number, pivot_hash)
+        {
+            Some(transfers) => {
+                self.notifications
+                    .internal_transfers
+                    .send(Arc::new(transfers));
+            }
+            None => warn!(
+                "The internal transfers of epoch {} {:?} were not traced \
+                 when it was executed",
+                epoch_number, pivot_hash
+            ),
+        }
+    }
+
     fn process_epoch_transactions(
         &self, spec: &Spec, epoch_id: EpochId, state: &mut State,
         epoch_blocks: &Vec<Arc<Block>>, start_block_number: u64,
@@ -1098,6 +1201,131 @@ impl ConsensusExecutionHandler {
         prefetch_join_handles.wait_for_task();
         drop(prefetch_join_handles);
 
+        let publish_internal_transfers =
+            self.notifications.internal_transfers.num_subscriptions() > 0;
+        // The transfers are also traced off the local pivot chain, so that
+        // they can be published when the epoch becomes a pivot epoch and its
+        // execution is skipped.
+        let trace_internal_transfers =
+            self.config.index_internal_transfers || publish_internal_transfers;
+        let mut internal_transfers = Vec::new();
+        let (epoch_receipts, to_pending) = self.execute_epoch_transactions(
+            spec,
+            self.config.parallel_execution,
+            state,
+            epoch_blocks,
+            start_block_number,
+            if trace_internal_transfers {
+                Some(&mut internal_transfers)
+            } else {
+                None
+            },
+        )?;
+
+        let pivot_block = epoch_blocks.last().expect("Epoch not empty");
+        let mut traced_transfers = Vec::new();
+        let mut indexed_transactions = Vec::new();
+        let mut internal_transfers = internal_transfers.into_iter();
+        for (block, block_receipts) in
+            epoch_blocks.iter().zip(epoch_receipts.iter())
+        {
+            for (idx, (transaction, receipt)) in block
+                .transactions
+                .iter()
+                .zip(block_receipts.receipts.iter())
+                .enumerate()
+            {
+                let transfers = internal_transfers.next().unwrap_or_default();
+                if on_local_pivot
+                    && receipt.outcome_status
+                        != TRANSACTION_OUTCOME_EXCEPTION_WITHOUT_NONCE_BUMPING
+                {
+                    let tx_index = TransactionIndex {
+                        block_hash: block.hash(),
+                        index: idx,
+                    };
+                    self.data_man.insert_transaction_index(
+                        &transaction.hash(),
+                        &tx_index,
+                    );
+                    // The transfers traced only for the subscribers are not
+                    // indexed.
+                    let internal_receivers: Vec<Address> =
+                        if self.config.index_internal_transfers {
+                            transfers.iter().map(|t| t.to).collect()
+                        } else {
+                            Vec::new()
+                        };
+                    indexed_transactions
+                        .push((&**transaction, internal_receivers));
+                }
+                if !transfers.is_empty() {
+                    traced_transfers.push(TransactionInternalTransfers {
+                        block_hash: block.hash(),
+                        transaction_hash: transaction.hash(),
+                        transfers,
+                    });
+                }
+            }
+            self.data_man.insert_block_execution_result(
+                block.hash(),
+                pivot_block.hash(),
+                block_receipts.clone(),
+                on_local_pivot,
+            );
+        }
+
+        if trace_internal_transfers {
+            self.data_man.insert_epoch_internal_transfers(
+                &pivot_block.hash(),
+                &traced_transfers,
+            );
+        }
+        if on_local_pivot {
+            self.data_man.insert_epoch_address_transaction_index(
+                pivot_block.block_header.height(),
+                &pivot_block.hash(),
+                &indexed_transactions,
+            );
+            self.data_man.insert_epoch_log_index(
+                pivot_block.block_header.height(),
+                &epoch_receipts,
+            );
+            if publish_internal_transfers {
+                self.notifications.internal_transfers.send(Arc::new(
+                    EpochInternalTransfers {
+                        epoch_number: pivot_block.block_header.height(),
+                        pivot_hash: pivot_block.hash(),
+                        transactions: traced_transfers,
+                    },
+                ));
+            }
+            trace!(
+                "To re-add transactions to transaction pool. \
+                 transactions={:?}",
+                to_pending
+            );
+            self.tx_pool.recycle_transactions(to_pending);
+        }
+
+        debug!("Finish processing tx for epoch");
+        Ok(epoch_receipts)
+    }
+
+    /// Execute the transactions of the epoch on `state`, and return the
+    /// receipts of the blocks and the transactions to reconsider packing.
+    /// Nothing is written to the db here, so that an epoch can also be
+    /// re-executed with another configuration for comparison.
+    ///
+    /// If `internal_transfers` is set, the internal transfers of each
+    /// transaction are traced into it, and the transactions are executed
+    /// serially.
+    fn execute_epoch_transactions(
+        &self, spec: &Spec, parallel_execution: bool, state: &mut State,
+        epoch_blocks: &Vec<Arc<Block>>, start_block_number: u64,
+        mut internal_transfers: Option<&mut Vec<Vec<InternalTransfer>>>,
+    ) -> DbResult<(Vec<Arc<BlockReceipts>>, Vec<Arc<SignedTransaction>>)>
+    {
         let pivot_block = epoch_blocks.last().expect("Epoch not empty");
         let internal_contract_map = InternalContractMap::new();
         let mut epoch_receipts = Vec::with_capacity(epoch_blocks.len());
@@ -1132,22 +1360,45 @@ impl ConsensusExecutionHandler {
 
             block_number += 1;
             last_block_hash = block.hash();
-            for (idx, transaction) in block.transactions.iter().enumerate() {
+            // With parallel execution, all the transactions in the block are
+            // executed before the receipts are built.
+            let mut parallel_outcomes = if parallel_execution
+                && internal_transfers.is_none()
+            {
+                ParallelExecutive::new(
+                    &env,
+                    self.machine.as_ref(),
+                    &spec,
+                    &internal_contract_map,
+                )
+                .transact_all(state, &block.transactions, || {
+                    Self::state_db_for_next_epoch(&self.data_man, pivot_block)
+                })?
+                .into_iter()
+            } else {
+                Vec::new().into_iter()
+            };
+            for transaction in block.transactions.iter() {
                 let tx_outcome_status;
                 let mut transaction_logs = Vec::new();
                 let mut storage_released = Vec::new();
                 let mut storage_collateralized = Vec::new();
 
-                let r = {
-                    Executive::new(
+                let mut tracer = TransferTracer::new();
+                let r = match parallel_outcomes.next() {
+                    Some(r) => r,
+                    None => Executive::new(
                         state,
                         &env,
                         self.machine.as_ref(),
                         &spec,
                         &internal_contract_map,
                     )
-                    .transact(transaction)?
+                    .transact_with_tracer(transaction, &mut tracer)?,
                 };
+                if let Some(ref mut internal_transfers) = internal_transfers {
+                    internal_transfers.push(tracer.into_transfers());
+                }
 
                 let gas_fee;
                 let mut gas_sponsor_paid = false;
@@ -1176,14 +1427,7 @@ impl ConsensusExecutionHandler {
                             transaction,
                             e
                         );
-                        if on_local_pivot {
-                            trace!(
-                                "To re-add transaction to transaction pool. \
-                                 transaction={:?}",
-                                transaction
-                            );
-                            to_pending.push(transaction.clone())
-                        }
+                        to_pending.push(transaction.clone());
                         gas_fee = U256::zero();
                     }
                     ExecutionOutcome::ExecutionErrorBumpNonce(
@@ -1232,20 +1476,6 @@ impl ConsensusExecutionHandler {
                 );
                 receipts.push(receipt);
                 tx_exec_error_messages.push(tx_exec_error_msg);
-
-                if on_local_pivot {
-                    let hash = transaction.hash();
-                    let tx_index = TransactionIndex {
-                        block_hash: block.hash(),
-                        index: idx,
-                    };
-                    if tx_outcome_status
-                        != TRANSACTION_OUTCOME_EXCEPTION_WITHOUT_NONCE_BUMPING
-                    {
-                        self.data_man
-                            .insert_transaction_index(&hash, &tx_index);
-                    }
-                }
             }
 
             let block_receipts = Arc::new(BlockReceipts {
@@ -1253,22 +1483,10 @@ impl ConsensusExecutionHandler {
                 secondary_reward,
                 tx_execution_error_messages: tx_exec_error_messages,
             });
-            self.data_man.insert_block_execution_result(
-                block.hash(),
-                pivot_block.hash(),
-                block_receipts.clone(),
-                on_local_pivot,
-            );
-
             epoch_receipts.push(block_receipts);
         }
 
-        if on_local_pivot {
-            self.tx_pool.recycle_transactions(to_pending);
-        }
-
-        debug!("Finish processing tx for epoch");
-        Ok(epoch_receipts)
+        Ok((epoch_receipts, to_pending))
     }
 
     fn compute_block_base_reward(&self, past_block_count: u64) -> U512 {
@@ -1593,7 +1811,7 @@ impl ConsensusExecutionHandler {
             epoch_blocks.len(),
         );
         let pivot_block = epoch_blocks.last().expect("Not empty");
-        let spec = Spec::new_spec();
+        let spec = self.machine.spec(pivot_block.block_header.height());
         let mut state = State::new(
             StateDb::new(
                 self.data_man
@@ -1629,10 +1847,146 @@ impl ConsensusExecutionHandler {
         )
     }
 
+    /// Execute the transactions of the epoch from the state of its parent
+    /// with each of the two variants, without committing anything, and
+    /// compare the resulting state roots and receipts.
+    ///
+    /// Return `None` if the results are identical.
+    pub fn differential_execute_epoch(
+        &self, epoch_hash: &H256, epoch_block_hashes: &Vec<H256>,
+        start_block_number: u64, variants: &[ExecutionVariant; 2],
+    ) -> Result<Option<ExecutionDivergence>, String>
+    {
+        let epoch_blocks = self
+            .data_man
+            .blocks_by_hash_list(
+                epoch_block_hashes,
+                false, /* update_cache */
+            )
+            .ok_or("Blocks of the epoch not found")?;
+        let (first_state_root, first_receipts) = self.dry_run_epoch(
+            &epoch_blocks,
+            start_block_number,
+            &variants[0],
+        )?;
+        let (second_state_root, second_receipts) = self.dry_run_epoch(
+            &epoch_blocks,
+            start_block_number,
+            &variants[1],
+        )?;
+
+        Ok(ExecutionDivergence::between(
+            *epoch_hash,
+            epoch_block_hashes,
+            (first_state_root, &first_receipts),
+            (second_state_root, &second_receipts),
+        ))
+    }
+
+    /// Execute the transactions of the epoch with `variant` and compute the
+    /// state root without committing the state or storing the receipts.
+    /// The rewards are not processed, because they do not depend on the
+    /// interpreter.
+    fn dry_run_epoch(
+        &self, epoch_blocks: &Vec<Arc<Block>>, start_block_number: u64,
+        variant: &ExecutionVariant,
+    ) -> Result<(StateRootWithAuxInfo, Vec<Arc<BlockReceipts>>), String>
+    {
+        let pivot_block = epoch_blocks.last().expect("Not empty");
+        let pivot_height = pivot_block.block_header.height();
+        // Keep the lock until we get the desired State, otherwise the State
+        // may expire.
+        let state_availability_boundary =
+            self.data_man.state_availability_boundary.read();
+        if !state_availability_boundary.check_availability(
+            pivot_height - 1,
+            pivot_block.block_header.parent_hash(),
+        ) {
+            return Err("State of the parent epoch is not available".into());
+        }
+        let state_db =
+            Self::state_db_for_next_epoch(&self.data_man, pivot_block);
+        drop(state_availability_boundary);
+
+        let spec = self
+            .machine
+            .spec(variant.spec_epoch_height.unwrap_or(pivot_height));
+        let mut state = State::new(
+            state_db,
+            self.vm.clone(),
+            &spec,
+            start_block_number - 1, /* block_number */
+        );
+        let (epoch_receipts, _) = self
+            .execute_epoch_transactions(
+                &spec,
+                variant.parallel_execution,
+                &mut state,
+                epoch_blocks,
+                start_block_number,
+                None, /* internal_transfers */
+            )
+            .map_err(|e| format!("Db error: {:?}", e))?;
+        let state_root = state
+            .compute_state_root(None)
+            .map_err(|e| format!("Db error: {:?}", e))?;
+        Ok((state_root, epoch_receipts))
+    }
+
     pub fn call_virtual(
         &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
-    ) -> RpcResult<ExecutionOutcome> {
-        let spec = Spec::new_spec();
+        pending: &[Arc<SignedTransaction>],
+    ) -> RpcResult<ExecutionOutcome>
+    {
+        Ok(self
+            .execute_virtual(
+                tx,
+                epoch_id,
+                epoch_size,
+                pending,
+                &mut NoopTracer,
+            )?
+            .0)
+    }
+
+    /// Execute the transaction virtually and return the accounts and
+    /// storage keys accessed.
+    pub fn create_access_list(
+        &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
+    ) -> RpcResult<(ExecutionOutcome, AccessList)> {
+        let (outcome, state) = self.execute_virtual(
+            tx,
+            epoch_id,
+            epoch_size,
+            &[],
+            &mut NoopTracer,
+        )?;
+        Ok((outcome, state.access_list()))
+    }
+
+    /// Execute the transaction virtually and return the storage collateral
+    /// changes of each frame.
+    pub fn trace_collateral(
+        &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
+    ) -> RpcResult<(ExecutionOutcome, Vec<CollateralTrace>)> {
+        let mut tracer = CollateralTracer::new();
+        let (outcome, _) = self.execute_virtual(
+            tx,
+            epoch_id,
+            epoch_size,
+            &[],
+            &mut tracer,
+        )?;
+        Ok((outcome, tracer.into_traces()))
+    }
+
+    /// Execute the transaction virtually after the `pending` transactions,
+    /// which are executed as in a block and skipped if not executable.
+    fn execute_virtual(
+        &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
+        pending: &[Arc<SignedTransaction>], tracer: &mut dyn Tracer,
+    ) -> RpcResult<(ExecutionOutcome, State)>
+    {
         let internal_contract_map = InternalContractMap::new();
         let best_block_header = self.data_man.block_header_by_hash(epoch_id);
         if best_block_header.is_none() {
@@ -1640,6 +1994,7 @@ impl ConsensusExecutionHandler {
         }
         let best_block_header = best_block_header.unwrap();
         let block_height = best_block_header.height() + 1;
+        let spec = self.machine.spec(block_height);
         let start_block_number = match self.data_man.get_epoch_execution_context(epoch_id) {
             Some(v) => v.start_block_number + epoch_size as u64,
             None => bail!("cannot obtain the execution context. Database is potentially corrupted!"),
@@ -1651,6 +2006,7 @@ impl ConsensusExecutionHandler {
                 tx,
                 tx.chain_id,
                 block_height,
+                &spec,
             ),
         )?;
 
@@ -1696,6 +2052,17 @@ impl ConsensusExecutionHandler {
                 .transaction_epoch_bound,
         };
         assert_eq!(state.block_number(), env.number);
+        for pending_tx in pending {
+            let r = Executive::new(
+                &mut state,
+                &env,
+                self.machine.as_ref(),
+                &spec,
+                &internal_contract_map,
+            )
+            .transact(pending_tx)?;
+            trace!("Execution result of pending tx {:?}", r);
+        }
         let mut ex = Executive::new(
             &mut state,
             &env,
@@ -1703,9 +2070,71 @@ impl ConsensusExecutionHandler {
             &spec,
             &internal_contract_map,
         );
-        let r = ex.transact_virtual(tx);
+        let r = ex.transact_virtual_with_tracer(tx, tracer);
         trace!("Execution result {:?}", r);
-        Ok(r?)
+        Ok((r?, state))
+    }
+}
+
+/// An interpreter configuration to re-execute the epochs with in the
+/// differential execution.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionVariant {
+    /// Execute the transactions in a block optimistically in parallel.
+    pub parallel_execution: bool,
+    /// Execute with the spec activated at this epoch height instead of the
+    /// one of the executed epoch, e.g. to compare two gas schedules.
+    pub spec_epoch_height: Option<u64>,
+}
+
+/// The differences found by executing an epoch with two variants.
+#[derive(Clone, Debug)]
+pub struct ExecutionDivergence {
+    pub epoch_hash: H256,
+    /// The state roots after executing the transactions with each variant,
+    /// before the rewards are processed.
+    pub state_roots: [StateRootWithAuxInfo; 2],
+    /// The transactions whose receipts differ.
+    pub receipt_mismatches: Vec<TransactionIndex>,
+}
+
+impl ExecutionDivergence {
+    /// Compare the state roots and the receipts of the blocks in
+    /// `epoch_block_hashes` of two executions of an epoch. Return `None` if
+    /// they are identical.
+    fn between(
+        epoch_hash: H256, epoch_block_hashes: &[H256],
+        first: (StateRootWithAuxInfo, &[Arc<BlockReceipts>]),
+        second: (StateRootWithAuxInfo, &[Arc<BlockReceipts>]),
+    ) -> Option<Self>
+    {
+        let mut receipt_mismatches = Vec::new();
+        for (block_hash, (first, second)) in epoch_block_hashes
+            .iter()
+            .zip(first.1.iter().zip(second.1.iter()))
+        {
+            for (index, (first, second)) in first
+                .receipts
+                .iter()
+                .zip(second.receipts.iter())
+                .enumerate()
+            {
+                if first != second {
+                    receipt_mismatches.push(TransactionIndex {
+                        block_hash: *block_hash,
+                        index,
+                    });
+                }
+            }
+        }
+        if first.0 == second.0 && receipt_mismatches.is_empty() {
+            return None;
+        }
+        Some(ExecutionDivergence {
+            epoch_hash,
+            state_roots: [first.0, second.0],
+            receipt_mismatches,
+        })
     }
 }
 
@@ -1714,4 +2143,93 @@ pub struct ConsensusExecutionConfiguration {
     /// It should be less than `timer_chain_beta`.
     pub anticone_penalty_ratio: u64,
     pub base_reward_table_in_ucfx: Vec<u64>,
+    /// Execute the transactions in a block optimistically in parallel.
+    pub parallel_execution: bool,
+    /// Trace the internal transfers of the transactions on the pivot chain
+    /// to index them by their receivers.
+    pub index_internal_transfers: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutionDivergence;
+    use cfx_internal_common::StateRootWithAuxInfo;
+    use cfx_types::{H256, U256};
+    use primitives::{
+        receipt::{BlockReceipts, Receipt, TRANSACTION_OUTCOME_SUCCESS},
+        TransactionIndex,
+    };
+    use std::sync::Arc;
+
+    fn block_receipts(gas_used: &[u64]) -> Arc<BlockReceipts> {
+        Arc::new(BlockReceipts {
+            receipts: gas_used
+                .iter()
+                .map(|gas_used| {
+                    Receipt::new(
+                        TRANSACTION_OUTCOME_SUCCESS,
+                        U256::from(*gas_used),
+                        U256::zero(),
+                        false,
+                        vec![],
+                        false,
+                        vec![],
+                        vec![],
+                    )
+                })
+                .collect(),
+            secondary_reward: U256::zero(),
+            tx_execution_error_messages: vec![String::new(); gas_used.len()],
+        })
+    }
+
+    #[test]
+    fn test_execution_divergence() {
+        let epoch_hash = H256::from_low_u64_be(10);
+        let block_hashes =
+            vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        let root = StateRootWithAuxInfo::genesis(&H256::from_low_u64_be(100));
+        let receipts = vec![block_receipts(&[21000]), block_receipts(&[])];
+
+        // Identical executions.
+        assert!(ExecutionDivergence::between(
+            epoch_hash,
+            &block_hashes,
+            (root.clone(), &receipts),
+            (root.clone(), &receipts),
+        )
+        .is_none());
+
+        // Only the state roots differ.
+        let other_root =
+            StateRootWithAuxInfo::genesis(&H256::from_low_u64_be(200));
+        let divergence = ExecutionDivergence::between(
+            epoch_hash,
+            &block_hashes,
+            (root.clone(), &receipts),
+            (other_root.clone(), &receipts),
+        )
+        .unwrap();
+        assert_eq!(divergence.epoch_hash, epoch_hash);
+        assert_eq!(divergence.state_roots, [root.clone(), other_root]);
+        assert!(divergence.receipt_mismatches.is_empty());
+
+        // The receipts of the 2nd transaction of the 2nd block differ.
+        let first = vec![block_receipts(&[21000]), block_receipts(&[1, 2])];
+        let second = vec![block_receipts(&[21000]), block_receipts(&[1, 3])];
+        let divergence = ExecutionDivergence::between(
+            epoch_hash,
+            &block_hashes,
+            (root.clone(), &first),
+            (root, &second),
+        )
+        .unwrap();
+        assert_eq!(
+            divergence.receipt_mismatches,
+            vec![TransactionIndex {
+                block_hash: block_hashes[1],
+                index: 1,
+            }]
+        );
+    }
 }
@@ -97,6 +97,13 @@ pub trait ConsensusGraphTrait: Send + Sync {
     fn get_state_db_by_epoch_number(
         &self, epoch_number: EpochNumber,
     ) -> RpcResult<StateDb>;
+
+    /// Returns the state of the epoch `epoch_number` refers to now, with the
+    /// number and the hash of the epoch. All the reads from the state are
+    /// consistent, however the chain advances meanwhile.
+    fn get_pinned_state_db_by_epoch_number(
+        &self, epoch_number: EpochNumber,
+    ) -> RpcResult<(u64, H256, StateDb)>;
 }
 
 pub type SharedConsensusGraph =
@@ -19,9 +19,16 @@ use super::consensus::consensus_inner::{
     consensus_new_block_handler::ConsensusNewBlockHandler,
 };
 use crate::{
-    block_data_manager::{BlockDataManager, BlockExecutionResultWithEpoch},
+    block_data_manager::{
+        BlockDataManager, BlockExecutionResultWithEpoch, LogIndexKey,
+        LogLocation,
+    },
     consensus::consensus_inner::{
-        consensus_executor::ConsensusExecutionConfiguration, StateBlameInfo,
+        consensus_executor::{
+            ConsensusExecutionConfiguration, ExecutionDivergence,
+            ExecutionVariant,
+        },
+        StateBlameInfo,
     },
     evm::Spec,
     executive::ExecutionOutcome,
@@ -29,6 +36,7 @@ use crate::{
     rpc_errors::{invalid_params_check, Result as RpcResult},
     state::State,
     statistics::SharedStatistics,
+    trace::CollateralTrace,
     transaction_pool::SharedTransactionPool,
     verification::VerificationConfig,
     vm_factory::VmFactory,
@@ -51,13 +59,14 @@ use primitives::{
     filter::{Filter, FilterError},
     log_entry::LocalizedLogEntry,
     receipt::Receipt,
-    ChainIdParams, EpochId, EpochNumber, SignedTransaction, TransactionIndex,
+    AccessList, ChainIdParams, EpochId, EpochNumber, SignedTransaction,
+    TransactionIndex,
 };
 use rayon::prelude::*;
 use std::{
     any::Any,
     cmp::min,
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
     thread::sleep,
     time::Duration,
@@ -217,6 +226,7 @@ impl ConsensusGraph {
             execution_conf,
             verification_config,
             conf.bench_mode,
+            notifications.clone(),
         );
         let confirmation_meter = ConfirmationMeter::new();
 
@@ -546,7 +556,7 @@ impl ConsensusGraph {
         invalid_params_check("address", state.nonce(&address))
     }
 
-    fn earliest_epoch_available(&self) -> u64 {
+    pub fn earliest_epoch_available(&self) -> u64 {
         match self.node_type {
             NodeType::Archive => 0,
             _ => self.latest_checkpoint_epoch_number(),
@@ -747,6 +757,14 @@ impl ConsensusGraph {
     pub fn get_filter_epoch_range(
         &self, filter: &Filter,
     ) -> Result<impl Iterator<Item = u64>, FilterError> {
+        let (from_epoch, to_epoch) = self.get_filter_epoch_bounds(filter)?;
+        Ok((from_epoch..=to_epoch).rev())
+    }
+
+    /// The first and the last epochs of the filter, both included.
+    fn get_filter_epoch_bounds(
+        &self, filter: &Filter,
+    ) -> Result<(u64, u64), FilterError> {
         // lock so that we have a consistent view
         let _inner = self.inner.read();
 
@@ -780,13 +798,138 @@ impl ConsensusGraph {
             }
         }
 
-        return Ok((from_epoch..=to_epoch).rev());
+        Ok((from_epoch, to_epoch))
+    }
+
+    /// Filter the logs with the log index if the filter matches by address or
+    /// by the first topic, and the index covers the executed epochs of the
+    /// filter. Return `None` if the logs should be filtered by the epochs.
+    fn filter_logs_by_index(
+        &self, filter: &Filter,
+    ) -> Result<Option<Vec<LocalizedLogEntry>>, FilterError> {
+        let address_keys: Vec<LogIndexKey> = match filter.address {
+            Some(ref addresses) => {
+                addresses.iter().map(|a| LogIndexKey::Address(*a)).collect()
+            }
+            None => vec![],
+        };
+        let topic_keys: Vec<LogIndexKey> = match filter.topics.first() {
+            Some(Some(topics)) => {
+                topics.iter().map(|t| LogIndexKey::Topic(*t)).collect()
+            }
+            _ => vec![],
+        };
+        if address_keys.is_empty() && topic_keys.is_empty() {
+            return Ok(None);
+        }
+        let (from_epoch, to_epoch) = self.get_filter_epoch_bounds(filter)?;
+        if to_epoch > self.best_executed_state_epoch_number() {
+            return Ok(None);
+        }
+
+        // keep a consistent view during filtering
+        let inner = self.inner.read();
+
+        // The logs should match one of the addresses and one of the topics.
+        let mut candidates: Option<BTreeSet<LogLocation>> = None;
+        for keys in vec![address_keys, topic_keys] {
+            if keys.is_empty() {
+                continue;
+            }
+            let mut locations = BTreeSet::new();
+            for key in keys {
+                match self.data_man.log_locations(&key, from_epoch, to_epoch) {
+                    Some(key_locations) => locations.extend(key_locations),
+                    None => return Ok(None),
+                }
+            }
+            candidates = Some(match candidates {
+                Some(other) => {
+                    other.intersection(&locations).cloned().collect()
+                }
+                None => locations,
+            });
+        }
+
+        let limit = filter.limit.unwrap_or(::std::usize::MAX);
+        let mut logs = Vec::new();
+        // The receipts and the transaction hashes of the last block visited.
+        let mut block: Option<((u64, u64), H256, Vec<Receipt>, Vec<H256>)> =
+            None;
+        for location in candidates.unwrap_or_default().into_iter().rev() {
+            if logs.len() >= limit {
+                break;
+            }
+            let block_position = (location.epoch_number, location.block_index);
+            if block.as_ref().map(|b| b.0) != Some(block_position) {
+                let epoch_hashes =
+                    inner.block_hashes_by_epoch(location.epoch_number)?;
+                let pivot_hash =
+                    *epoch_hashes.last().expect("Epoch set not empty");
+                let block_hash =
+                    match epoch_hashes.get(location.block_index as usize) {
+                        Some(hash) => *hash,
+                        // The location is from another pivot chain.
+                        None => continue,
+                    };
+                let receipts = self
+                    .data_man
+                    .block_execution_result_by_hash_with_epoch(
+                        &block_hash,
+                        &pivot_hash,
+                        false, /* update_pivot_assumption */
+                        false, /* update_cache */
+                    )
+                    .ok_or(FilterError::BlockNotExecutedYet { block_hash })?
+                    .block_receipts
+                    .receipts
+                    .clone();
+                let tx_hashes = self
+                    .data_man
+                    .block_by_hash(&block_hash, false)
+                    .ok_or(FilterError::UnknownBlock { hash: block_hash })?
+                    .transaction_hashes();
+                block = Some((block_position, block_hash, receipts, tx_hashes));
+            }
+            let (_, block_hash, receipts, tx_hashes) =
+                block.as_ref().expect("block is set above");
+
+            let transaction_index = location.transaction_index as usize;
+            let transaction_log_index = location.transaction_log_index as usize;
+            let log = match receipts
+                .get(transaction_index)
+                .and_then(|receipt| receipt.logs.get(transaction_log_index))
+            {
+                Some(log) if filter.matches(log) => log.clone(),
+                _ => continue,
+            };
+            let log_index = receipts[..transaction_index]
+                .iter()
+                .map(|receipt| receipt.logs.len())
+                .sum::<usize>()
+                + transaction_log_index;
+            logs.push(LocalizedLogEntry {
+                entry: log,
+                block_hash: *block_hash,
+                epoch_number: location.epoch_number,
+                transaction_hash: tx_hashes[transaction_index],
+                transaction_index,
+                transaction_log_index,
+                log_index,
+            });
+        }
+
+        logs.reverse();
+        Ok(Some(logs))
     }
 
     fn filter_logs_by_epochs(
         &self, filter: Filter,
     ) -> Result<Vec<LocalizedLogEntry>, FilterError> {
         assert!(filter.block_hashes.is_none());
+        if let Some(logs) = self.filter_logs_by_index(&filter)? {
+            return Ok(logs);
+        }
         let bloom_possibilities = filter.bloom_possibilities();
         let limit = filter.limit.unwrap_or(::std::usize::MAX);
 
@@ -930,19 +1073,86 @@ impl ConsensusGraph {
         }
     }
 
+    /// Execute the transaction virtually against the state of `epoch`,
+    /// after the `pending` transactions are executed in order, e.g. the
+    /// transactions of the sender in the transaction pool.
     pub fn call_virtual(
         &self, tx: &SignedTransaction, epoch: EpochNumber,
-    ) -> RpcResult<ExecutionOutcome> {
+        pending: &[Arc<SignedTransaction>],
+    ) -> RpcResult<ExecutionOutcome>
+    {
+        let (epoch_id, epoch_size) = self.virtual_call_epoch(epoch)?;
+        self.executor.call_virtual(tx, &epoch_id, epoch_size, pending)
+    }
+
+    /// Execute the transaction virtually like `call_virtual`, and return
+    /// the accounts and storage keys accessed during the execution.
+    pub fn create_access_list(
+        &self, tx: &SignedTransaction, epoch: EpochNumber,
+    ) -> RpcResult<(ExecutionOutcome, AccessList)> {
+        let (epoch_id, epoch_size) = self.virtual_call_epoch(epoch)?;
+        self.executor.create_access_list(tx, &epoch_id, epoch_size)
+    }
+
+    /// Execute the transaction virtually like `call_virtual`, and return
+    /// the storage collateral charged and refunded by each frame.
+    pub fn trace_collateral(
+        &self, tx: &SignedTransaction, epoch: EpochNumber,
+    ) -> RpcResult<(ExecutionOutcome, Vec<CollateralTrace>)> {
+        let (epoch_id, epoch_size) = self.virtual_call_epoch(epoch)?;
+        self.executor.trace_collateral(tx, &epoch_id, epoch_size)
+    }
+
+    fn virtual_call_epoch(
+        &self, epoch: EpochNumber,
+    ) -> RpcResult<(H256, usize)> {
         // only allow to call against stated epoch
         self.validate_stated_epoch(&epoch)?;
-        let (epoch_id, epoch_size) = if let Ok(v) =
-            self.get_block_hashes_by_epoch(epoch)
-        {
-            (v.last().expect("pivot block always exist").clone(), v.len())
+        if let Ok(v) = self.get_block_hashes_by_epoch(epoch) {
+            Ok((v.last().expect("pivot block always exist").clone(), v.len()))
         } else {
             bail!("cannot get block hashes in the specified epoch, maybe it does not exist?");
-        };
-        self.executor.call_virtual(tx, &epoch_id, epoch_size)
+        }
+    }
+
+    /// Re-execute the epochs in `[start_epoch, end_epoch]` with both
+    /// variants and return the epochs whose state roots or receipts differ.
+    /// This is used to check that a change of the execution, e.g. the
+    /// parallel execution or a new gas schedule, is deterministic over the
+    /// historical blocks.
+    pub fn differential_execute(
+        &self, start_epoch: u64, end_epoch: u64,
+        variants: &[ExecutionVariant; 2],
+    ) -> Result<Vec<ExecutionDivergence>, String>
+    {
+        if start_epoch == 0 {
+            return Err("Genesis epoch cannot be re-executed".into());
+        }
+        let mut divergences = Vec::new();
+        for epoch in start_epoch..=end_epoch {
+            let epoch_block_hashes =
+                self.get_block_hashes_by_epoch(EpochNumber::Number(epoch))?;
+            let epoch_hash =
+                epoch_block_hashes.last().expect("pivot block always exist");
+            let start_block_number = self
+                .data_man
+                .get_epoch_execution_context(epoch_hash)
+                .ok_or("Epoch execution context not found")?
+                .start_block_number;
+            if let Some(divergence) = self.executor.differential_execute_epoch(
+                epoch_hash,
+                &epoch_block_hashes,
+                start_block_number,
+                variants,
+            )? {
+                warn!(
+                    "Execution diverges in epoch {}: {:?}",
+                    epoch, divergence
+                );
+                divergences.push(divergence);
+            }
+        }
+        Ok(divergences)
     }
 
     /// Get the number of processed blocks (i.e., the number of calls to
@@ -1014,6 +1224,8 @@ impl ConsensusGraphTrait for ConsensusGraph {
     ) {
         let _timer =
             MeterTimer::time_func(CONSENSIS_ON_NEW_BLOCK_TIMER.as_ref());
+        let _block_context =
+            log_mdc::insert_scoped("block_hash", format!("{:?}", hash));
         self.statistics.inc_consensus_graph_processed_block_count();
 
         let block_opt = if ignore_body {
@@ -1316,6 +1528,13 @@ impl ConsensusGraphTrait for ConsensusGraph {
     fn get_state_db_by_epoch_number(
         &self, epoch_number: EpochNumber,
     ) -> RpcResult<StateDb> {
+        self.get_pinned_state_db_by_epoch_number(epoch_number)
+            .map(|(_, _, state_db)| state_db)
+    }
+
+    fn get_pinned_state_db_by_epoch_number(
+        &self, epoch_number: EpochNumber,
+    ) -> RpcResult<(u64, H256, StateDb)> {
         invalid_params_check(
             "epoch_number",
             self.validate_stated_epoch(&epoch_number),
@@ -1326,6 +1545,7 @@ impl ConsensusGraphTrait for ConsensusGraph {
         )?;
         let hash =
             self.inner.read().get_pivot_hash_from_epoch_number(height)?;
-        self.get_state_db_by_height_and_hash(height, &hash)
+        let state_db = self.get_state_db_by_height_and_hash(height, &hash)?;
+        Ok((height, hash, state_db))
     }
 }
@@ -37,8 +37,12 @@ pub const COL_TX_INDEX: u32 = 2;
 pub const COL_EPOCH_NUMBER: u32 = 3;
 /// Column for verified roots of blamed headers on light nodes
 pub const COL_BLAMED_HEADER_VERIFIED_ROOTS: u32 = 4;
+/// Column for the transactions sent and received by each address
+pub const COL_ADDRESS_TX_INDEX: u32 = 5;
+/// Column for the locations of the logs by address and first topic
+pub const COL_LOG_INDEX: u32 = 6;
 /// Number of columns in DB
-pub const NUM_COLUMNS: u32 = 5;
+pub const NUM_COLUMNS: u32 = 7;
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
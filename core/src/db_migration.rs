@@ -0,0 +1,288 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Versioned migrations of the ledger database.
+//!
+//! The version of the database layout is recorded in `COL_MISC`. When a
+//! release changes the layout, it increases `DB_SCHEMA_VERSION` and adds a
+//! `Migration` to `MIGRATIONS`, which upgrades the databases of the previous
+//! releases on startup, so that the operators don't need to resync.
+//!
+//! The database is backed up before the migrations, and restored if any of
+//! them fails, so that the node can still run with the previous release. Like
+//! a RocksDB checkpoint, the backup hard links the immutable table files and
+//! only copies the others, so it takes little space even for a large ledger.
+
+use crate::db::COL_MISC;
+use kvdb::DBTransaction;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use std::{fs, io, path::Path};
+
+/// The version of the database layout of this release.
+pub const DB_SCHEMA_VERSION: u32 = 1;
+
+/// The version of the databases created before the version is recorded.
+const INITIAL_DB_SCHEMA_VERSION: u32 = 1;
+
+const DB_SCHEMA_VERSION_KEY: &[u8] = b"db_schema_version";
+
+pub struct Migration {
+    /// The version of the database after the migration.
+    pub version: u32,
+    pub description: &'static str,
+    /// The columns changed by the migration.
+    pub columns: &'static [u32],
+    /// Add the changes of the migration to the transaction, which is written
+    /// atomically with the new version.
+    pub migrate: fn(&Database, &mut DBTransaction) -> Result<(), String>,
+}
+
+/// The migrations from `INITIAL_DB_SCHEMA_VERSION` to `DB_SCHEMA_VERSION`,
+/// in the order of their versions.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Upgrade the database at `path` to `DB_SCHEMA_VERSION`. It should be called
+/// before the database is opened by the node.
+pub fn migrate_database(
+    path: &str, config: &DatabaseConfig,
+) -> Result<(), String> {
+    run_migrations(path, config, MIGRATIONS, DB_SCHEMA_VERSION)
+}
+
+fn run_migrations(
+    path: &str, config: &DatabaseConfig, migrations: &[Migration],
+    target_version: u32,
+) -> Result<(), String>
+{
+    // The directory may be created before the database, so check the
+    // `CURRENT` file which RocksDB creates with a database.
+    let is_new = !Path::new(path).join("CURRENT").exists();
+    let db = open(path, config)?;
+    let version = match read_version(&db)? {
+        Some(version) => version,
+        // There is nothing to migrate in a new database.
+        None if is_new => target_version,
+        None => INITIAL_DB_SCHEMA_VERSION,
+    };
+    if version > target_version {
+        return Err(format!(
+            "The database version {} is newer than the version {} supported \
+             by this release",
+            version, target_version
+        ));
+    }
+
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|m| m.version > version && m.version <= target_version)
+        .collect();
+    if pending.is_empty() {
+        if version != target_version {
+            return Err(format!(
+                "No migration of the database from version {} to {}",
+                version, target_version
+            ));
+        }
+        return write_version(&db, DBTransaction::new(), target_version);
+    }
+
+    // Back up the database while it's closed.
+    drop(db);
+    let backup_path = format!("{}.migration_backup", path);
+    if Path::new(&backup_path).exists() {
+        // Each migration is written atomically with its version, so the
+        // backup left by an interrupted migration is not needed.
+        warn!("Removing the stale database backup {}", backup_path);
+        fs::remove_dir_all(&backup_path).map_err(|e| {
+            format!(
+                "Failed to remove the stale database backup {}: {}",
+                backup_path, e
+            )
+        })?;
+    }
+    checkpoint_dir(Path::new(path), Path::new(&backup_path)).map_err(|e| {
+        format!("Failed to back up the database before migration: {}", e)
+    })?;
+    let db = open(path, config)?;
+
+    for migration in pending {
+        info!(
+            "Migrating the database to version {} (columns {:?}): {}",
+            migration.version, migration.columns, migration.description
+        );
+        let mut transaction = DBTransaction::new();
+        let result = (migration.migrate)(&db, &mut transaction)
+            .and_then(|_| write_version(&db, transaction, migration.version));
+        if let Err(e) = result {
+            warn!(
+                "Database migration to version {} failed: {}, rolling back",
+                migration.version, e
+            );
+            db.restore(&backup_path).map_err(|restore_error| {
+                format!(
+                    "Failed to restore the database from {} after migration \
+                     failure {}: {}",
+                    backup_path, e, restore_error
+                )
+            })?;
+            return Err(format!("Database migration failed: {}", e));
+        }
+    }
+
+    if let Err(e) = fs::remove_dir_all(&backup_path) {
+        warn!(
+            "Failed to remove the database backup {}, remove it manually: {}",
+            backup_path, e
+        );
+    }
+    info!("Database migrated to version {}", target_version);
+    Ok(())
+}
+
+fn open(path: &str, config: &DatabaseConfig) -> Result<Database, String> {
+    Database::open(config, path)
+        .map_err(|e| format!("Failed to open database {:?}", e))
+}
+
+fn read_version(db: &Database) -> Result<Option<u32>, String> {
+    let value = db
+        .get(COL_MISC, DB_SCHEMA_VERSION_KEY)
+        .map_err(|e| format!("Failed to read database version: {}", e))?;
+    match value {
+        Some(value) => rlp::decode(&value)
+            .map(Some)
+            .map_err(|e| format!("Invalid database version: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn write_version(
+    db: &Database, mut transaction: DBTransaction, version: u32,
+) -> Result<(), String> {
+    transaction.put(COL_MISC, DB_SCHEMA_VERSION_KEY, &rlp::encode(&version));
+    db.write(transaction)
+        .map_err(|e| format!("Failed to write database version: {}", e))
+}
+
+/// Copy the database at `from` to `to`. The table files, which RocksDB never
+/// modifies, are hard linked unless `to` is on another file system.
+fn checkpoint_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            checkpoint_dir(&source, &target)?;
+            continue;
+        }
+        let is_table = source.extension().map_or(false, |ext| ext == "sst");
+        if !is_table || fs::hard_link(&source, &target).is_err() {
+            fs::copy(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{COL_BLOCKS, NUM_COLUMNS};
+    use tempdir::TempDir;
+
+    fn rename_block(
+        db: &Database, transaction: &mut DBTransaction,
+    ) -> Result<(), String> {
+        let value = db.get(COL_BLOCKS, b"old").unwrap().unwrap();
+        transaction.delete(COL_BLOCKS, b"old");
+        transaction.put(COL_BLOCKS, b"new", &value);
+        Ok(())
+    }
+
+    fn fail(
+        _: &Database, transaction: &mut DBTransaction,
+    ) -> Result<(), String> {
+        transaction.put(COL_BLOCKS, b"partial", b"value");
+        Err("failure".into())
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 2,
+            description: "rename block",
+            columns: &[COL_BLOCKS],
+            migrate: rename_block,
+        },
+        Migration {
+            version: 3,
+            description: "fail",
+            columns: &[COL_BLOCKS],
+            migrate: fail,
+        },
+    ];
+
+    #[test]
+    fn test_migrations() {
+        let dir = TempDir::new("db_migration").unwrap();
+        let path = dir.path().join("db").to_str().unwrap().to_string();
+        let config = DatabaseConfig::with_columns(NUM_COLUMNS);
+
+        // A new database is created with the target version.
+        run_migrations(&path, &config, MIGRATIONS, 1).unwrap();
+        {
+            let db = open(&path, &config).unwrap();
+            assert_eq!(read_version(&db).unwrap(), Some(1));
+            let mut transaction = DBTransaction::new();
+            transaction.put(COL_BLOCKS, b"old", b"block");
+            db.write(transaction).unwrap();
+        }
+
+        run_migrations(&path, &config, MIGRATIONS, 2).unwrap();
+        {
+            let db = open(&path, &config).unwrap();
+            assert_eq!(read_version(&db).unwrap(), Some(2));
+            assert_eq!(db.get(COL_BLOCKS, b"old").unwrap(), None);
+            assert_eq!(
+                db.get(COL_BLOCKS, b"new").unwrap(),
+                Some(b"block".to_vec())
+            );
+        }
+
+        // The failed migration is rolled back.
+        assert!(run_migrations(&path, &config, MIGRATIONS, 3).is_err());
+        {
+            let db = open(&path, &config).unwrap();
+            assert_eq!(read_version(&db).unwrap(), Some(2));
+            assert_eq!(db.get(COL_BLOCKS, b"partial").unwrap(), None);
+        }
+
+        // A database newer than the release is rejected.
+        assert!(run_migrations(&path, &config, MIGRATIONS, 1).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_dir() {
+        let dir = TempDir::new("db_checkpoint").unwrap();
+        let from = dir.path().join("db");
+        let to = dir.path().join("backup");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("000001.sst"), b"table").unwrap();
+        fs::write(from.join("MANIFEST-000001"), b"manifest").unwrap();
+        fs::write(from.join("sub").join("000002.log"), b"log").unwrap();
+
+        checkpoint_dir(&from, &to).unwrap();
+        // The mutable files are copied, so the backup is kept when they
+        // change.
+        fs::write(from.join("MANIFEST-000001"), b"changed").unwrap();
+        fs::write(from.join("sub").join("000002.log"), b"changed").unwrap();
+        // A table file is only deleted, which doesn't remove its link.
+        fs::remove_file(from.join("000001.sst")).unwrap();
+        assert_eq!(fs::read(to.join("000001.sst")).unwrap(), b"table");
+        assert_eq!(fs::read(to.join("MANIFEST-000001")).unwrap(), b"manifest");
+        assert_eq!(
+            fs::read(to.join("sub").join("000002.log")).unwrap(),
+            b"log"
+        );
+    }
+}
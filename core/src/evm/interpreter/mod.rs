@@ -40,6 +40,7 @@ use self::{
 use super::{
     evm::CostType,
     instructions::{self, Instruction, InstructionInfo},
+    profile::{self, FrameProfile},
 };
 use crate::{
     bytes::Bytes,
@@ -192,6 +193,7 @@ pub struct Interpreter<Cost: CostType> {
     reader: CodeReader,
     return_data: ReturnData,
     informant: informant::EvmInformant,
+    profile: Option<FrameProfile>,
     do_trace: bool,
     done: bool,
     valid_jump_destinations: Option<Arc<BitSet>>,
@@ -316,6 +318,11 @@ impl<Cost: CostType> Interpreter<Cost> {
         );
         let params = InterpreterParams::from(params);
         let informant = informant::EvmInformant::new(depth);
+        let profile = if profile::is_enabled() {
+            Some(FrameProfile::new())
+        } else {
+            None
+        };
         let valid_jump_destinations = None;
         let valid_subroutine_destinations = None;
         let gasometer = Cost::from_u256(params.gas)
@@ -329,6 +336,7 @@ impl<Cost: CostType> Interpreter<Cost> {
             params,
             reader,
             informant,
+            profile,
             valid_jump_destinations,
             valid_subroutine_destinations,
             gasometer,
@@ -369,6 +377,9 @@ impl<Cost: CostType> Interpreter<Cost> {
         if let &InterpreterResult::Done(_) = &result {
             self.done = true;
             self.informant.done();
+            if let Some(profile) = self.profile.take() {
+                profile.finish(self.params.code_address);
+            }
         }
         return result;
     }
@@ -473,6 +484,10 @@ impl<Cost: CostType> Interpreter<Cost> {
                     )
                 });
 
+                if let Some(profile) = &mut self.profile {
+                    profile.before_instruction();
+                }
+
                 // Execute instruction
                 let current_gas =
                     self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas;
@@ -489,6 +504,12 @@ impl<Cost: CostType> Interpreter<Cost> {
                 };
 
                 evm_debug!({ self.informant.after_instruction(instruction) });
+                if let Some(profile) = &mut self.profile {
+                    profile.after_instruction(
+                        instruction,
+                        requirements.gas_cost.as_u256(),
+                    );
+                }
 
                 result
             }
@@ -502,6 +523,9 @@ impl<Cost: CostType> Interpreter<Cost> {
             self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas =
                 self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas
                     + *gas;
+            if let Some(profile) = &mut self.profile {
+                profile.refund_gas(gas.as_u256());
+            }
         }
 
         if self.do_trace {
@@ -630,6 +654,7 @@ impl<Cost: CostType> Interpreter<Cost> {
             || (instruction == instructions::CHAINID && !spec.have_chain_id)
             || (instruction == instructions::SELFBALANCE
                 && !spec.have_self_balance)
+            || (instruction == instructions::SUICIDE && !spec.have_suicide)
         {
             return Err(vm::Error::BadInstruction {
                 instruction: instruction as u8,
@@ -1078,6 +1103,7 @@ impl<Cost: CostType> Interpreter<Cost> {
                 let mut key = vec![0; 32];
                 self.stack.pop_back().to_big_endian(key.as_mut());
                 let word = context.storage_at(&key)?;
+                context.trace_storage_read(&key, &word);
                 self.stack.push(word);
             }
             instructions::SSTORE => {
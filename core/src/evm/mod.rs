@@ -7,6 +7,7 @@ mod evm;
 pub mod factory;
 mod instructions;
 mod interpreter;
+pub mod profile;
 mod vmtype;
 
 #[cfg(test)]
@@ -0,0 +1,222 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Profiling of the time and gas spent on each opcode and each contract.
+//!
+//! When profiling is enabled, every interpreter frame records the opcodes it
+//! executes, and merges the records into a global profile when it finishes.
+//! The global profile aggregates all the executions since profiling is
+//! enabled, and is reported periodically to guide the optimization of the
+//! interpreter.
+//!
+//! The time of an opcode calling or creating a contract does not include the
+//! execution of the callee, which is recorded in its own frame.
+
+use super::instructions::Instruction;
+use cfx_types::{Address, U256};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref PROFILE: Mutex<ExecutionProfile> = Default::default();
+}
+
+/// Start profiling the executions from now on.
+pub fn enable() { ENABLED.store(true, Ordering::Relaxed); }
+
+pub fn is_enabled() -> bool { ENABLED.load(Ordering::Relaxed) }
+
+/// The report of the `top` most time consuming opcodes and contracts
+/// executed since profiling is enabled.
+pub fn report(top: usize) -> String { PROFILE.lock().report(top) }
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The number of executed opcodes, or the number of executed frames for
+    /// a contract.
+    pub count: u64,
+    pub gas: U256,
+    pub time: Duration,
+}
+
+impl Stats {
+    fn accrue(&mut self, other: &Stats) {
+        self.count += other.count;
+        self.gas = self.gas.saturating_add(other.gas);
+        self.time += other.time;
+    }
+
+    fn average_nanos(&self) -> u128 {
+        if self.count == 0 {
+            0
+        } else {
+            self.time.as_nanos() / self.count as u128
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ExecutionProfile {
+    opcodes: HashMap<Instruction, Stats>,
+    contracts: HashMap<Address, Stats>,
+}
+
+impl ExecutionProfile {
+    fn merge(&mut self, code_address: Address, frame: FrameProfile) {
+        let mut total = Stats {
+            count: 1,
+            ..Default::default()
+        };
+        for (instruction, stats) in frame.opcodes {
+            total.gas = total.gas.saturating_add(stats.gas);
+            total.time += stats.time;
+            self.opcodes
+                .entry(instruction)
+                .or_insert_with(Default::default)
+                .accrue(&stats);
+        }
+        self.contracts
+            .entry(code_address)
+            .or_insert_with(Default::default)
+            .accrue(&total);
+    }
+
+    pub fn opcode_stats(&self, instruction: Instruction) -> Option<&Stats> {
+        self.opcodes.get(&instruction)
+    }
+
+    pub fn contract_stats(&self, code_address: &Address) -> Option<&Stats> {
+        self.contracts.get(code_address)
+    }
+
+    pub fn report(&self, top: usize) -> String {
+        let mut opcodes: Vec<_> = self.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.time.cmp(&a.1.time));
+        let mut contracts: Vec<_> = self.contracts.iter().collect();
+        contracts.sort_by(|a, b| b.1.time.cmp(&a.1.time));
+
+        let mut report = String::new();
+        writeln!(report, "EVM profile by opcode:").ok();
+        for (instruction, stats) in opcodes.into_iter().take(top) {
+            writeln!(
+                report,
+                "{:>14}(0x{:02x}) count={} gas={} time={:?} avg={}ns",
+                instruction.info().name,
+                *instruction as u8,
+                stats.count,
+                stats.gas,
+                stats.time,
+                stats.average_nanos(),
+            )
+            .ok();
+        }
+        writeln!(report, "EVM profile by contract:").ok();
+        for (address, stats) in contracts.into_iter().take(top) {
+            writeln!(
+                report,
+                "{:?} frames={} gas={} time={:?}",
+                address, stats.count, stats.gas, stats.time,
+            )
+            .ok();
+        }
+        report
+    }
+}
+
+/// The opcodes executed by an interpreter frame.
+#[derive(Default)]
+pub struct FrameProfile {
+    opcodes: HashMap<Instruction, Stats>,
+    started: Option<Instant>,
+    last_instruction: Option<Instruction>,
+}
+
+impl FrameProfile {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn before_instruction(&mut self) {
+        self.started = Some(Instant::now());
+    }
+
+    pub fn after_instruction(&mut self, instruction: Instruction, gas: U256) {
+        let time = self
+            .started
+            .take()
+            .map_or(Duration::from_secs(0), |started| started.elapsed());
+        self.opcodes
+            .entry(instruction)
+            .or_insert_with(Default::default)
+            .accrue(&Stats {
+                count: 1,
+                gas,
+                time,
+            });
+        self.last_instruction = Some(instruction);
+    }
+
+    /// The gas charged for a call or create is partly returned after the
+    /// callee finishes.
+    pub fn refund_gas(&mut self, gas: U256) {
+        if let Some(stats) = self
+            .last_instruction
+            .and_then(|instruction| self.opcodes.get_mut(&instruction))
+        {
+            stats.gas = stats.gas.saturating_sub(gas);
+        }
+    }
+
+    /// Merge the records into the global profile.
+    pub fn finish(self, code_address: Address) {
+        PROFILE.lock().merge(code_address, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExecutionProfile, FrameProfile};
+    use crate::evm::instructions::Instruction;
+    use cfx_types::{Address, U256};
+
+    #[test]
+    fn test_merge_frames() {
+        let contract = Address::from_low_u64_be(1);
+        let mut profile = ExecutionProfile::default();
+
+        let mut frame = FrameProfile::new();
+        frame.before_instruction();
+        frame.after_instruction(Instruction::PUSH1, U256::from(3));
+        frame.before_instruction();
+        frame.after_instruction(Instruction::CALL, U256::from(10_000));
+        frame.refund_gas(U256::from(7_000));
+        profile.merge(contract, frame);
+
+        let mut frame = FrameProfile::new();
+        frame.before_instruction();
+        frame.after_instruction(Instruction::PUSH1, U256::from(3));
+        profile.merge(contract, frame);
+
+        let push1 = profile.opcode_stats(Instruction::PUSH1).unwrap();
+        assert_eq!(push1.count, 2);
+        assert_eq!(push1.gas, U256::from(6));
+        let call = profile.opcode_stats(Instruction::CALL).unwrap();
+        assert_eq!(call.count, 1);
+        assert_eq!(call.gas, U256::from(3_000));
+        assert!(profile.opcode_stats(Instruction::ADD).is_none());
+
+        let stats = profile.contract_stats(&contract).unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.gas, U256::from(3_006));
+        assert_eq!(stats.time, push1.time + call.time);
+
+        let report = profile.report(1);
+        assert_eq!(report.lines().count(), 4);
+    }
+}
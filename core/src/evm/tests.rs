@@ -958,6 +958,29 @@ fn test_badinstruction_int() {
     }
 }
 
+evm_test! {test_suicide_disabled: test_suicide_disabled_int}
+fn test_suicide_disabled(factory: super::Factory) {
+    // PUSH1 0x00, SUICIDE
+    let code = "6000ff".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ctx = MockContext::new();
+    ctx.spec.have_suicide = false;
+
+    let err = {
+        let vm = factory.create(params, ctx.spec(), ctx.depth());
+        test_finalize(vm.exec(&mut ctx).ok().unwrap()).unwrap_err()
+    };
+
+    match err {
+        vm::Error::BadInstruction { instruction: 0xff } => (),
+        _ => assert!(false, "Expected bad instruction"),
+    }
+    assert!(ctx.suicides.is_empty());
+}
+
 evm_test! {test_pop: test_pop_int}
 fn test_pop(factory: super::Factory) {
     let code = "60f060aa50600055".from_hex().unwrap();
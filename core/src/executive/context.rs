@@ -8,13 +8,13 @@ use crate::{
     bytes::Bytes,
     machine::Machine,
     state::{State, Substate},
+    trace::Tracer,
     vm::{
         self, ActionParams, ActionValue, CallType, Context as ContextTrait,
         ContractCreateResult, CreateContractAddress, Env, MessageCallResult,
         ReturnData, Spec, TrapKind,
     },
 };
-use cfx_parameters::staking::COLLATERAL_PER_BYTE;
 use cfx_types::{Address, H256, U256};
 use primitives::transaction::UNSIGNED_SENDER;
 use std::sync::Arc;
@@ -76,6 +76,7 @@ pub struct Context<'a> {
     output: OutputPolicy,
     static_flag: bool,
     internal_contract_map: &'a InternalContractMap,
+    tracer: &'a mut dyn Tracer,
 }
 
 impl<'a> Context<'a> {
@@ -86,6 +87,7 @@ impl<'a> Context<'a> {
         origin: &'a OriginInfo, substate: &'a mut Substate,
         output: OutputPolicy, static_flag: bool,
         internal_contract_map: &'a InternalContractMap,
+        tracer: &'a mut dyn Tracer,
     ) -> Self
     {
         Context {
@@ -100,6 +102,7 @@ impl<'a> Context<'a> {
             output,
             static_flag,
             internal_contract_map,
+            tracer,
         }
     }
 }
@@ -115,6 +118,8 @@ impl<'a> ContextTrait for Context<'a> {
         if self.is_static_or_reentrancy() {
             Err(vm::Error::MutableCallInStaticContext)
         } else {
+            self.tracer
+                .trace_storage_write(&self.origin.address, &key, &value);
             self.substate
                 .set_storage(
                     self.state,
@@ -232,6 +237,7 @@ impl<'a> ContextTrait for Context<'a> {
             params,
             self.substate,
             self.stack_depth + 1,
+            &mut *self.tracer,
         );
         Ok(Ok(into_contract_create_result(
             out,
@@ -251,7 +257,7 @@ impl<'a> ContextTrait for Context<'a> {
         assert!(trap);
 
         let (code, code_hash) = if let Some(contract) =
-            self.internal_contract_map.contract(code_address)
+            self.internal_contract_map.contract(code_address, self.spec)
         {
             (Some(contract.code()), Some(contract.code_hash()))
         } else {
@@ -286,7 +292,9 @@ impl<'a> ContextTrait for Context<'a> {
     }
 
     fn extcode(&self, address: &Address) -> vm::Result<Option<Arc<Bytes>>> {
-        if let Some(contract) = self.internal_contract_map.contract(address) {
+        if let Some(contract) =
+            self.internal_contract_map.contract(address, self.spec)
+        {
             Ok(Some(contract.code()))
         } else {
             Ok(self.state.code(address)?)
@@ -294,7 +302,9 @@ impl<'a> ContextTrait for Context<'a> {
     }
 
     fn extcodehash(&self, address: &Address) -> vm::Result<Option<H256>> {
-        if let Some(contract) = self.internal_contract_map.contract(address) {
+        if let Some(contract) =
+            self.internal_contract_map.contract(address, self.spec)
+        {
             Ok(Some(contract.code_hash()))
         } else {
             Ok(self.state.code_hash(address)?)
@@ -302,7 +312,9 @@ impl<'a> ContextTrait for Context<'a> {
     }
 
     fn extcodesize(&self, address: &Address) -> vm::Result<Option<usize>> {
-        if let Some(contract) = self.internal_contract_map.contract(address) {
+        if let Some(contract) =
+            self.internal_contract_map.contract(address, self.spec)
+        {
             Ok(Some(contract.code_size()))
         } else {
             Ok(self.state.code_size(address)?)
@@ -327,7 +339,7 @@ impl<'a> ContextTrait for Context<'a> {
                     };
                 }
                 let collateral_for_code =
-                    U256::from(data.len()) * *COLLATERAL_PER_BYTE;
+                    U256::from(data.len()) * self.spec.collateral_per_byte;
                 debug!("ret()  collateral_for_code={:?}", collateral_for_code);
                 self.substate.record_storage_occupy(
                     &self.origin.storage_owner,
@@ -354,11 +366,13 @@ impl<'a> ContextTrait for Context<'a> {
         }
 
         let address = self.origin.address.clone();
-        self.substate.logs.push(LogEntry {
+        let log = LogEntry {
             address,
             topics,
             data: data.to_vec(),
-        });
+        };
+        self.tracer.trace_log(&log);
+        self.substate.logs.push(log);
 
         Ok(())
     }
@@ -399,25 +413,36 @@ impl<'a> ContextTrait for Context<'a> {
     }
 
     fn trace_next_instruction(
-        &mut self, _pc: usize, _instruction: u8, _current_gas: U256,
+        &mut self, pc: usize, instruction: u8, current_gas: U256,
     ) -> bool {
-        // TODO
-        false
+        self.tracer
+            .trace_next_instruction(pc, instruction, current_gas)
     }
 
     fn trace_prepare_execute(
-        &mut self, _pc: usize, _instruction: u8, _gas_cost: U256,
-        _mem_written: Option<(usize, usize)>,
-        _store_written: Option<(U256, U256)>,
+        &mut self, pc: usize, instruction: u8, gas_cost: U256,
+        mem_written: Option<(usize, usize)>,
+        store_written: Option<(U256, U256)>,
     )
     {
-        // TODO
+        self.tracer.trace_prepare_execute(
+            pc,
+            instruction,
+            gas_cost,
+            mem_written,
+            store_written,
+        );
     }
 
     fn trace_executed(
-        &mut self, _gas_used: U256, _stack_push: &[U256], _mem: &[u8],
+        &mut self, gas_used: U256, stack_push: &[U256], mem: &[u8],
     ) {
-        // TODO
+        self.tracer.trace_executed(gas_used, stack_push, mem);
+    }
+
+    fn trace_storage_read(&mut self, key: &[u8], value: &U256) {
+        self.tracer
+            .trace_storage_read(&self.origin.address, key, value);
     }
 
     fn is_reentrancy(&self, _caller: &Address, callee: &Address) -> bool {
@@ -435,6 +460,7 @@ mod tests {
         machine::{new_machine_with_builtin, Machine},
         state::{State, Substate},
         test_helpers::get_state_for_genesis_write,
+        trace::NoopTracer,
         vm::{
             CallType, Context as ContextTrait, ContractCreateResult,
             CreateContractAddress, Env, Spec,
@@ -520,6 +546,7 @@ mod tests {
         let state = &mut setup.state;
         let origin = get_test_origin();
 
+        let mut tracer = NoopTracer;
         let ctx = Context::new(
             state,
             &setup.env,
@@ -532,6 +559,7 @@ mod tests {
             OutputPolicy::InitContract,
             false, /* static_flag */
             &setup.internal_contract_map,
+            &mut tracer,
         );
 
         assert_eq!(ctx.env().number, 100);
@@ -543,6 +571,7 @@ mod tests {
         let state = &mut setup.state;
         let origin = get_test_origin();
 
+        let mut tracer = NoopTracer;
         let mut ctx = Context::new(
             state,
             &setup.env,
@@ -555,6 +584,7 @@ mod tests {
             OutputPolicy::InitContract,
             false, /* static_flag */
             &setup.internal_contract_map,
+            &mut tracer,
         );
 
         let hash = ctx.blockhash(
@@ -615,6 +645,7 @@ mod tests {
         let state = &mut setup.state;
         let origin = get_test_origin();
 
+        let mut tracer = NoopTracer;
         let mut ctx = Context::new(
             state,
             &setup.env,
@@ -627,6 +658,7 @@ mod tests {
             OutputPolicy::InitContract,
             false, /* static_flag */
             &setup.internal_contract_map,
+            &mut tracer,
         );
 
         // this should panic because we have no balance on any account
@@ -664,6 +696,7 @@ mod tests {
         let origin = get_test_origin();
 
         {
+            let mut tracer = NoopTracer;
             let mut ctx = Context::new(
                 state,
                 &setup.env,
@@ -676,6 +709,7 @@ mod tests {
                 OutputPolicy::InitContract,
                 false, /* static_flag */
                 &setup.internal_contract_map,
+                &mut tracer,
             );
             ctx.log(log_topics, &log_data).unwrap();
         }
@@ -709,6 +743,7 @@ mod tests {
             .expect(&concat!(file!(), ":", line!(), ":", column!()));
 
         {
+            let mut tracer = NoopTracer;
             let mut ctx = Context::new(
                 state,
                 &setup.env,
@@ -721,6 +756,7 @@ mod tests {
                 OutputPolicy::InitContract,
                 false, /* static_flag */
                 &setup.internal_contract_map,
+                &mut tracer,
             );
             ctx.suicide(&refund_account).unwrap();
         }
@@ -737,6 +773,7 @@ mod tests {
         let origin = get_test_origin();
 
         let address = {
+            let mut tracer = NoopTracer;
             let mut ctx = Context::new(
                 state,
                 &setup.env,
@@ -749,6 +786,7 @@ mod tests {
                 OutputPolicy::InitContract,
                 false, /* static_flag */
                 &setup.internal_contract_map,
+                &mut tracer,
             );
             match ctx
                 .create(
@@ -783,6 +821,7 @@ mod tests {
         let origin = get_test_origin();
 
         let address = {
+            let mut tracer = NoopTracer;
             let mut ctx = Context::new(
                 state,
                 &setup.env,
@@ -795,6 +834,7 @@ mod tests {
                 OutputPolicy::InitContract,
                 false, /* static_flag */
                 &setup.internal_contract_map,
+                &mut tracer,
             );
 
             match ctx
@@ -88,6 +88,10 @@ pub enum TxDropError {
     /// Although it can be verified in tx packing,
     /// by spec doc, it is checked in execution.
     InvalidRecipientAddress(Address),
+
+    /// The chain_id of the epoch is not the chain_id of the tx, e.g. after a
+    /// transition of the chain_id.
+    ChainIdMismatch(u32, u32),
 }
 
 #[derive(Debug)]
@@ -4,6 +4,7 @@
 
 use super::{
     context::{Context, OriginInfo, OutputPolicy},
+    internal_contract::InternalContractOutput,
     Executed, ExecutionError, InternalContractMap,
 };
 use crate::{
@@ -18,6 +19,7 @@ use crate::{
     state::{
         CallStackInfo, CleanupMode, CollateralCheckResult, State, Substate,
     },
+    trace::{NoopTracer, Tracer},
     verification::VerificationConfig,
     vm::{
         self, ActionParams, ActionValue, CallType, CreateContractAddress, Env,
@@ -26,7 +28,6 @@ use crate::{
     },
     vm_factory::VmFactory,
 };
-use cfx_parameters::staking::*;
 use cfx_statedb::Result as DbResult;
 use cfx_types::{address_util::AddressUtil, Address, H256, U256, U512};
 use primitives::{
@@ -173,18 +174,14 @@ impl<'a> CallCreateExecutive<'a> {
             parent_static_flag || params.call_type == CallType::StaticCall;
 
         // if destination is builtin, try to execute it
-        let kind = if let Some(builtin) =
-            machine.builtin(&params.code_address, env.number)
+        let kind = if machine
+            .builtin(&params.code_address, env.epoch_height)
+            .is_some()
         {
-            // Engines aren't supposed to return builtins until activation, but
-            // prefer to fail rather than silently break consensus.
-            if !builtin.is_active(env.number) {
-                panic!("Consensus failure: engine implementation prematurely enabled built-in at {}", params.code_address);
-            }
             trace!("CallBuiltin");
             CallCreateExecutiveKind::CallBuiltin(params)
         } else if let Some(_) =
-            internal_contract_map.contract(&params.code_address)
+            internal_contract_map.contract(&params.code_address, spec)
         {
             debug!(
                 "CallInternalContract: address={:?} data={:?}",
@@ -378,7 +375,7 @@ impl<'a> CallCreateExecutive<'a> {
     fn enact_output(
         mut self, output: ExecTrapResult<FinalizationResult>,
         origin: OriginInfo, state: &mut State, substate: &mut Substate,
-        mut unconfirmed_substate: Substate,
+        mut unconfirmed_substate: Substate, tracer: &mut dyn Tracer,
     ) -> ExecutiveTrapResult<'a, FinalizationResult>
     {
         // You should avoid calling functions for self here, since `self.kind`
@@ -422,6 +419,10 @@ impl<'a> CallCreateExecutive<'a> {
                     Ok(result)
                 }
                 Ok(_) => {
+                    tracer.trace_collateral(
+                        &unconfirmed_substate.storage_collateralized,
+                        &unconfirmed_substate.storage_released,
+                    );
                     state.discard_checkpoint();
                     substate.accrue(unconfirmed_substate);
 
@@ -455,6 +456,7 @@ impl<'a> CallCreateExecutive<'a> {
         spec: &'any Spec, depth: usize, stack_depth: usize, static_flag: bool,
         origin: &'any OriginInfo, substate: &'any mut Substate,
         output: OutputPolicy, internal_contract_map: &'any InternalContractMap,
+        tracer: &'any mut dyn Tracer,
     ) -> Context<'any>
     {
         Context::new(
@@ -469,6 +471,7 @@ impl<'a> CallCreateExecutive<'a> {
             output,
             static_flag,
             internal_contract_map,
+            tracer,
         )
     }
 
@@ -477,7 +480,20 @@ impl<'a> CallCreateExecutive<'a> {
     /// `resume_call` or `resume_create` to continue the execution.
     pub fn exec(
         mut self, state: &mut State, substate: &mut Substate,
-    ) -> ExecutiveTrapResult<'a, FinalizationResult> {
+        tracer: &mut dyn Tracer,
+    ) -> ExecutiveTrapResult<'a, FinalizationResult>
+    {
+        match &self.kind {
+            CallCreateExecutiveKind::Transfer(params)
+            | CallCreateExecutiveKind::CallBuiltin(params)
+            | CallCreateExecutiveKind::CallInternalContract(params, _)
+            | CallCreateExecutiveKind::ExecCall(params, _)
+            | CallCreateExecutiveKind::ExecCreate(params, _) => {
+                tracer.trace_call_enter(params, self.is_create)
+            }
+            _ => {}
+        }
+
         match self.kind {
             CallCreateExecutiveKind::Transfer(ref params) => {
                 assert!(!self.is_create);
@@ -506,7 +522,7 @@ impl<'a> CallCreateExecutive<'a> {
                 assert!(!self.is_create);
 
                 let mut inner = || {
-                    let builtin = self.machine.builtin(&params.code_address, self.env.number).expect("Builtin is_some is checked when creating this kind in new_call_raw; qed");
+                    let builtin = self.machine.builtin(&params.code_address, self.env.epoch_height).expect("Builtin is_some is checked when creating this kind in new_call_raw; qed");
 
                     Self::check_static_flag(
                         &params,
@@ -596,7 +612,7 @@ impl<'a> CallCreateExecutive<'a> {
                         "Incorrect call type.",
                     ))
                 } else if let Some(contract) =
-                    internal_contract_map.contract(&params.code_address)
+                    internal_contract_map.contract(&params.code_address, spec)
                 {
                     contract.execute(
                         &params,
@@ -605,11 +621,12 @@ impl<'a> CallCreateExecutive<'a> {
                         &mut unconfirmed_substate,
                     )
                 } else {
-                    Ok(GasLeft::Known(params.gas))
+                    Ok(InternalContractOutput::Return(GasLeft::Known(
+                        params.gas,
+                    )))
                 };
-                debug!("Internal Call Result: {:?}", result);
 
-                let context = Self::as_context(
+                let mut context = Self::as_context(
                     state,
                     self.env,
                     self.machine,
@@ -621,8 +638,25 @@ impl<'a> CallCreateExecutive<'a> {
                     &mut unconfirmed_substate,
                     OutputPolicy::Return,
                     self.internal_contract_map,
+                    tracer,
                 );
-                let out = Ok(result.finalize(context));
+                // A function calling other contracts continues as a VM.
+                let result = match result {
+                    Ok(InternalContractOutput::Return(gas_left)) => {
+                        Ok(Ok(gas_left))
+                    }
+                    Ok(InternalContractOutput::Exec(exec)) => {
+                        exec.exec(&mut context)
+                    }
+                    Err(err) => Ok(Err(err)),
+                };
+                let out = match result {
+                    Ok(val) => {
+                        debug!("Internal Call Result: {:?}", val);
+                        Ok(val.finalize(context))
+                    }
+                    Err(err) => Err(err),
+                };
                 self.kind = CallCreateExecutiveKind::Moved;
                 self.enact_output(
                     out,
@@ -630,6 +664,7 @@ impl<'a> CallCreateExecutive<'a> {
                     state,
                     substate,
                     unconfirmed_substate,
+                    tracer,
                 )
             }
 
@@ -679,6 +714,7 @@ impl<'a> CallCreateExecutive<'a> {
                         &mut unconfirmed_substate,
                         OutputPolicy::Return,
                         self.internal_contract_map,
+                        tracer,
                     );
                     match exec.exec(&mut context) {
                         Ok(val) => Ok(val.finalize(context)),
@@ -693,6 +729,7 @@ impl<'a> CallCreateExecutive<'a> {
                     state,
                     substate,
                     unconfirmed_substate,
+                    tracer,
                 )
             }
 
@@ -750,6 +787,7 @@ impl<'a> CallCreateExecutive<'a> {
                         &mut unconfirmed_substate,
                         OutputPolicy::InitContract,
                         self.internal_contract_map,
+                        tracer,
                     );
                     match exec.exec(&mut context) {
                         Ok(val) => Ok(val.finalize(context)),
@@ -764,6 +802,7 @@ impl<'a> CallCreateExecutive<'a> {
                     state,
                     substate,
                     unconfirmed_substate,
+                    tracer,
                 )
             }
 
@@ -781,7 +820,7 @@ impl<'a> CallCreateExecutive<'a> {
     /// Resume execution from a call trap previously trapped by `exec'.
     pub fn resume_call(
         mut self, result: vm::MessageCallResult, state: &mut State,
-        substate: &mut Substate,
+        substate: &mut Substate, tracer: &mut dyn Tracer,
     ) -> ExecutiveTrapResult<'a, FinalizationResult>
     {
         match self.kind {
@@ -809,6 +848,7 @@ impl<'a> CallCreateExecutive<'a> {
                             OutputPolicy::Return
                         },
                         self.internal_contract_map,
+                        tracer,
                     );
                     match exec.exec(&mut context) {
                         Ok(val) => Ok(val.finalize(context)),
@@ -823,6 +863,7 @@ impl<'a> CallCreateExecutive<'a> {
                     state,
                     substate,
                     unconfirmed_substate,
+                    tracer,
                 )
             }
             CallCreateExecutiveKind::ResumeCreate(..) => {
@@ -844,7 +885,7 @@ impl<'a> CallCreateExecutive<'a> {
     /// Resume execution from a create trap previously trapped by `exec`.
     pub fn resume_create(
         mut self, result: vm::ContractCreateResult, state: &mut State,
-        substate: &mut Substate,
+        substate: &mut Substate, tracer: &mut dyn Tracer,
     ) -> ExecutiveTrapResult<'a, FinalizationResult>
     {
         match self.kind {
@@ -872,6 +913,7 @@ impl<'a> CallCreateExecutive<'a> {
                             OutputPolicy::Return
                         },
                         self.internal_contract_map,
+                        tracer,
                     );
                     match exec.exec(&mut context) {
                         Ok(val) => Ok(val.finalize(context)),
@@ -886,6 +928,7 @@ impl<'a> CallCreateExecutive<'a> {
                     state,
                     substate,
                     unconfirmed_substate,
+                    tracer,
                 )
             }
             CallCreateExecutiveKind::ResumeCall(..) => {
@@ -909,9 +952,11 @@ impl<'a> CallCreateExecutive<'a> {
     /// current-level tracing.
     pub fn consume(
         self, state: &mut State, top_substate: &mut Substate,
-    ) -> vm::Result<FinalizationResult> {
+        tracer: &mut dyn Tracer,
+    ) -> vm::Result<FinalizationResult>
+    {
         let mut last_res =
-            Some((false, self.gas, self.exec(state, top_substate)));
+            Some((false, self.gas, self.exec(state, top_substate, tracer)));
 
         let mut callstack: Vec<(Option<Address>, CallCreateExecutive<'a>)> =
             Vec::new();
@@ -929,12 +974,13 @@ impl<'a> CallCreateExecutive<'a> {
                                 None => top_substate,
                             };
 
-                            last_res = Some((exec.is_create, exec.gas, exec.exec(state, parent_substate)));
+                            last_res = Some((exec.is_create, exec.gas, exec.exec(state, parent_substate, tracer)));
                         }
                         None => panic!("When callstack only had one item and it was executed, this function would return; callstack never reaches zero item; qed"),
                     }
                 }
                 Some((is_create, _gas, Ok(val))) => {
+                    tracer.trace_call_exit(&val);
                     let current = callstack.pop();
                     top_substate.pop_callstack();
 
@@ -957,6 +1003,7 @@ impl<'a> CallCreateExecutive<'a> {
                                         contract_create_result,
                                         state,
                                         parent_substate,
+                                        tracer,
                                     ),
                                 ));
                             } else {
@@ -973,6 +1020,7 @@ impl<'a> CallCreateExecutive<'a> {
                                         into_message_call_result(val),
                                         state,
                                         parent_substate,
+                                        tracer,
                                     ),
                                 ));
                             }
@@ -1104,7 +1152,7 @@ impl<'a> Executive<'a> {
 
     pub fn create_with_stack_depth(
         &mut self, params: ActionParams, substate: &mut Substate,
-        stack_depth: usize,
+        stack_depth: usize, tracer: &mut dyn Tracer,
     ) -> vm::Result<FinalizationResult>
     {
         let _address = params.address;
@@ -1123,7 +1171,7 @@ impl<'a> Executive<'a> {
             self.internal_contract_map,
             substate.contracts_in_callstack.clone(),
         )
-        .consume(self.state, substate);
+        .consume(self.state, substate, tracer);
 
         result
     }
@@ -1131,12 +1179,12 @@ impl<'a> Executive<'a> {
     pub fn create(
         &mut self, params: ActionParams, substate: &mut Substate,
     ) -> vm::Result<FinalizationResult> {
-        self.create_with_stack_depth(params, substate, 0)
+        self.create_with_stack_depth(params, substate, 0, &mut NoopTracer)
     }
 
     pub fn call_with_stack_depth(
         &mut self, params: ActionParams, substate: &mut Substate,
-        stack_depth: usize,
+        stack_depth: usize, tracer: &mut dyn Tracer,
     ) -> vm::Result<FinalizationResult>
     {
         let vm_factory = self.state.vm_factory();
@@ -1153,7 +1201,7 @@ impl<'a> Executive<'a> {
             self.internal_contract_map,
             substate.contracts_in_callstack.clone(),
         )
-        .consume(self.state, substate);
+        .consume(self.state, substate, tracer);
 
         result
     }
@@ -1161,11 +1209,19 @@ impl<'a> Executive<'a> {
     pub fn call(
         &mut self, params: ActionParams, substate: &mut Substate,
     ) -> vm::Result<FinalizationResult> {
-        self.call_with_stack_depth(params, substate, 0)
+        self.call_with_stack_depth(params, substate, 0, &mut NoopTracer)
     }
 
     pub fn transact_virtual(
         &mut self, tx: &SignedTransaction,
+    ) -> DbResult<ExecutionOutcome> {
+        self.transact_virtual_with_tracer(tx, &mut NoopTracer)
+    }
+
+    /// Execute the transaction virtually with the execution hooked by
+    /// `tracer`.
+    pub fn transact_virtual_with_tracer(
+        &mut self, tx: &SignedTransaction, tracer: &mut dyn Tracer,
     ) -> DbResult<ExecutionOutcome> {
         let sender = tx.sender();
         let balance = self.state.balance(&sender)?;
@@ -1179,11 +1235,37 @@ impl<'a> Executive<'a> {
                 CleanupMode::NoEmpty,
             )?;
         }
-        self.transact(tx)
+        // The virtual calls are signed with the latest chain_id, which is not
+        // necessarily the chain_id of the epoch they are executed on.
+        self.execute_with_tracer(tx, tracer)
     }
 
     pub fn transact(
         &mut self, tx: &SignedTransaction,
+    ) -> DbResult<ExecutionOutcome> {
+        self.transact_with_tracer(tx, &mut NoopTracer)
+    }
+
+    /// Execute the transaction with the execution hooked by `tracer`. The
+    /// transactions without the chain_id of the epoch are dropped.
+    pub fn transact_with_tracer(
+        &mut self, tx: &SignedTransaction, tracer: &mut dyn Tracer,
+    ) -> DbResult<ExecutionOutcome> {
+        let chain_id = self
+            .machine
+            .params()
+            .chain_id
+            .get_chain_id(self.env.epoch_height);
+        if tx.chain_id != chain_id {
+            return Ok(ExecutionOutcome::NotExecutedDrop(
+                TxDropError::ChainIdMismatch(chain_id, tx.chain_id),
+            ));
+        }
+        self.execute_with_tracer(tx, tracer)
+    }
+
+    fn execute_with_tracer(
+        &mut self, tx: &SignedTransaction, tracer: &mut dyn Tracer,
     ) -> DbResult<ExecutionOutcome> {
         let spec = &self.spec;
         let sender = tx.sender();
@@ -1285,7 +1367,7 @@ impl<'a> Executive<'a> {
         }
 
         let tx_storage_limit_in_drip =
-            U256::from(tx.storage_limit) * *COLLATERAL_PER_BYTE;
+            U256::from(tx.storage_limit) * self.spec.collateral_per_byte;
         let storage_sponsor_balance = if storage_sponsored {
             self.state.sponsor_balance_for_collateral(&code_address)?
         } else {
@@ -1435,7 +1517,7 @@ impl<'a> Executive<'a> {
                     params_type: vm::ParamsType::Embedded,
                     storage_limit_in_drip: total_storage_limit,
                 };
-                self.create(params, &mut substate)
+                self.create_with_stack_depth(params, &mut substate, 0, tracer)
             }
             Action::Call(ref address) => {
                 let params = ActionParams {
@@ -1454,7 +1536,7 @@ impl<'a> Executive<'a> {
                     params_type: vm::ParamsType::Separate,
                     storage_limit_in_drip: total_storage_limit,
                 };
-                self.call(params, &mut substate)
+                self.call_with_stack_depth(params, &mut substate, 0, tracer)
             }
         };
 
@@ -1468,6 +1550,7 @@ impl<'a> Executive<'a> {
                         &sender,
                         &total_storage_limit,
                         &mut substate,
+                        self.spec,
                     )?
                     .into_vm_result()
                     .and(Ok(finalize_res))
@@ -1524,7 +1607,7 @@ impl<'a> Executive<'a> {
                 .record_storage_entries_release(address, &mut substate)?;
         }
 
-        let res = self.state.settle_collateral_for_all(&substate)?;
+        let res = self.state.settle_collateral_for_all(&substate, self.spec)?;
         // The storage recycling process should never occupy new collateral.
         assert_eq!(res, CollateralCheckResult::Valid);
 
@@ -1587,11 +1670,12 @@ impl<'a> Executive<'a> {
 
         // gas_used is only used to estimate gas needed
         let gas_used = tx.gas - gas_left;
-        // gas_left should be smaller than 1/4 of gas_limit, otherwise
-        // 3/4 of gas_limit is charged.
-        let charge_all = (gas_left + gas_left + gas_left) >= gas_used;
+        // gas_left should be smaller than 1/divisor of gas_limit (1/4 by
+        // default), otherwise only 1/divisor of gas_limit is refunded.
+        let refund_divisor = U256::from(self.spec.unused_gas_refund_divisor);
+        let charge_all = gas_left * (refund_divisor - U256::one()) >= gas_used;
         let (gas_charged, fees_value, refund_value) = if charge_all {
-            let gas_refunded = tx.gas >> 2;
+            let gas_refunded = tx.gas / refund_divisor;
             let gas_charged = tx.gas - gas_refunded;
             (
                 gas_charged,
@@ -5,18 +5,21 @@
 use super::{executive::*, internal_contract::*, Executed, ExecutionError};
 use crate::{
     evm::{Factory, FinalizationResult, VMType},
-    executive::ExecutionOutcome,
+    executive::{ExecutionOutcome, TxDropError},
+    hash::keccak,
     machine::Machine,
     state::{CleanupMode, CollateralCheckResult, Substate},
     test_helpers::{
         get_state_for_genesis_write, get_state_for_genesis_write_with_factory,
     },
+    trace::{CollateralTrace, CollateralTracer, Tracer},
     vm::{
         self, ActionParams, ActionValue, CallType, CreateContractAddress, Env,
     },
 };
 use cfx_parameters::{
     internal_contract_addresses::{
+        BATCH_TRANSFER_CONTRACT_ADDRESS,
         SPONSOR_WHITELIST_CONTROL_CONTRACT_ADDRESS,
         STORAGE_INTEREST_STAKING_CONTRACT_ADDRESS,
     },
@@ -24,13 +27,16 @@ use cfx_parameters::{
 };
 use cfx_storage::tests::new_state_manager_for_unit_test;
 use cfx_types::{
-    address_util::AddressUtil, Address, BigEndianHash, U256, U512,
+    address_util::AddressUtil, Address, BigEndianHash, H256, U256, U512,
 };
 use keylib::{Generator, Random};
 use primitives::{
-    storage::STORAGE_LAYOUT_REGULAR_V0, transaction::Action, Transaction,
+    receipt::StorageChange, storage::STORAGE_LAYOUT_REGULAR_V0,
+    transaction::Action, ChainIdParams, ChainIdTransition, LogEntry,
+    Transaction,
 };
 use rustc_hex::FromHex;
+use solidity_abi::ABIEncodable;
 use std::{
     cmp::{self, min},
     str::FromStr,
@@ -117,6 +123,7 @@ fn test_sender_balance() {
                 &params.storage_owner,
                 &params.storage_limit_in_drip,
                 &mut substate,
+                &spec,
             )
             .unwrap()
             .into_vm_result()
@@ -374,6 +381,7 @@ fn test_call_to_create() {
                 &params.storage_owner,
                 &params.storage_limit_in_drip,
                 &mut substate,
+                &spec,
             )
             .unwrap()
             .into_vm_result()
@@ -465,6 +473,214 @@ fn test_revert() {
     );
 }
 
+#[derive(Default)]
+struct RecordingTracer {
+    calls: Vec<Address>,
+    exits: usize,
+    instructions: Vec<u8>,
+    storage_reads: Vec<U256>,
+    storage_writes: Vec<U256>,
+    logs: Vec<LogEntry>,
+}
+
+impl Tracer for RecordingTracer {
+    fn trace_call_enter(&mut self, params: &ActionParams, _is_create: bool) {
+        self.calls.push(params.address);
+    }
+
+    fn trace_call_exit(&mut self, _result: &vm::Result<FinalizationResult>) {
+        self.exits += 1;
+    }
+
+    fn trace_next_instruction(
+        &mut self, _pc: usize, instruction: u8, _current_gas: U256,
+    ) -> bool {
+        self.instructions.push(instruction);
+        true
+    }
+
+    fn trace_storage_read(
+        &mut self, _address: &Address, _key: &[u8], value: &U256,
+    ) {
+        self.storage_reads.push(*value);
+    }
+
+    fn trace_storage_write(
+        &mut self, _address: &Address, _key: &[u8], value: &U256,
+    ) {
+        self.storage_writes.push(*value);
+    }
+
+    fn trace_log(&mut self, log: &LogEntry) { self.logs.push(log.clone()); }
+}
+
+#[test]
+fn test_tracer() {
+    // code:
+    //
+    // 60 01 - push 1
+    // 60 00 - push 0
+    // 55 - sstore
+    // 60 00 - push 0
+    // 54 - sload
+    // 50 - pop
+    // 60 00 - push 0
+    // 60 00 - push 0
+    // a0 - log0
+    // 00 - stop
+    let code = "60016000556000545060006000a000".from_hex().unwrap();
+
+    let contract_address =
+        Address::from_str("8d1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+    let sender =
+        Address::from_str("1f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    state
+        .new_contract(&contract_address, U256::zero(), U256::one())
+        .expect(&concat!(file!(), ":", line!(), ":", column!()));
+
+    let mut params = ActionParams::default();
+    params.address = contract_address;
+    params.code_address = contract_address;
+    params.sender = sender;
+    params.original_sender = sender;
+    params.storage_owner = contract_address;
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    params.value = ActionValue::Transfer(U256::zero());
+    params.call_type = CallType::Call;
+    let env = Env::default();
+    let machine = crate::machine::new_machine_with_builtin(Default::default());
+    let internal_contract_map = InternalContractMap::new();
+    let spec = machine.spec(env.number);
+    let mut substate = Substate::new();
+    let mut tracer = RecordingTracer::default();
+
+    {
+        let mut ex = Executive::new(
+            &mut state,
+            &env,
+            &machine,
+            &spec,
+            &internal_contract_map,
+        );
+        ex.call_with_stack_depth(params, &mut substate, 0, &mut tracer)
+            .unwrap();
+    }
+
+    assert_eq!(tracer.calls, vec![contract_address]);
+    assert_eq!(tracer.exits, 1);
+    assert_eq!(
+        tracer.instructions,
+        vec![0x60, 0x60, 0x55, 0x60, 0x54, 0x50, 0x60, 0x60, 0xa0, 0x00]
+    );
+    assert_eq!(tracer.storage_writes, vec![U256::one()]);
+    assert_eq!(tracer.storage_reads, vec![U256::one()]);
+    assert_eq!(tracer.logs, substate.logs);
+}
+
+#[test]
+fn test_collateral_tracer() {
+    // code of the callee:
+    //
+    // 60 01 - push 1
+    // 60 00 - push 0
+    // 55 - sstore
+    // 00 - stop
+    let callee_code = "600160005500".from_hex().unwrap();
+    // code of the caller:
+    //
+    // 60 01 - push 1
+    // 60 00 - push 0
+    // 55 - sstore
+    // 60 00 60 00 60 00 60 00 60 00 - push 0 for the call arguments
+    // 73 <callee> - push the callee address
+    // 5a - gas
+    // f1 - call
+    // 50 - pop
+    // 00 - stop
+    let code = "60016000556000600060006000600073\
+                8e1722f3947def4cf144679da39c4c32bdc356815af15000"
+        .from_hex()
+        .unwrap();
+
+    let contract_address =
+        Address::from_str("8d1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+    let callee_address =
+        Address::from_str("8e1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+    let sender =
+        Address::from_str("1f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    state
+        .new_contract(&contract_address, U256::zero(), U256::one())
+        .expect(&concat!(file!(), ":", line!(), ":", column!()));
+    state
+        .new_contract(&callee_address, U256::zero(), U256::one())
+        .expect(&concat!(file!(), ":", line!(), ":", column!()));
+    state
+        .init_code(&callee_address, callee_code, sender)
+        .expect(&concat!(file!(), ":", line!(), ":", column!()));
+
+    let mut params = ActionParams::default();
+    params.address = contract_address;
+    params.code_address = contract_address;
+    params.sender = sender;
+    params.original_sender = sender;
+    params.storage_owner = sender;
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    params.value = ActionValue::Transfer(U256::zero());
+    params.call_type = CallType::Call;
+    let env = Env::default();
+    let machine = crate::machine::new_machine_with_builtin(Default::default());
+    let internal_contract_map = InternalContractMap::new();
+    let spec = machine.spec(env.number);
+    let mut substate = Substate::new();
+    let mut tracer = CollateralTracer::new();
+
+    {
+        let mut ex = Executive::new(
+            &mut state,
+            &env,
+            &machine,
+            &spec,
+            &internal_contract_map,
+        );
+        ex.call_with_stack_depth(params, &mut substate, 0, &mut tracer)
+            .unwrap();
+    }
+
+    let occupied = vec![StorageChange {
+        address: sender,
+        amount: BYTES_PER_STORAGE_KEY,
+    }];
+    assert_eq!(
+        tracer.into_traces(),
+        vec![
+            CollateralTrace {
+                address: contract_address,
+                depth: 0,
+                storage_collateralized: occupied.clone(),
+                storage_released: vec![],
+            },
+            CollateralTrace {
+                address: callee_address,
+                depth: 1,
+                storage_collateralized: occupied,
+                storage_released: vec![],
+            },
+        ]
+    );
+    assert_eq!(
+        substate.storage_collateralized[&sender],
+        2 * BYTES_PER_STORAGE_KEY
+    );
+}
+
 #[test]
 fn test_keccak() {
     let factory = Factory::new(VMType::Interpreter, 1024 * 32);
@@ -588,6 +804,63 @@ fn test_not_enough_cash() {
     }
 }
 
+#[test]
+fn test_chain_id_of_epoch() {
+    let keypair = Random.generate().unwrap();
+    let t = Transaction {
+        action: Action::Call(Address::zero()),
+        value: U256::zero(),
+        data: vec![],
+        gas: U256::from(21_000),
+        gas_price: U256::one(),
+        storage_limit: 0,
+        epoch_height: 0,
+        chain_id: 0,
+        nonce: U256::zero(),
+    }
+    .sign(keypair.secret());
+    let sender = t.sender();
+
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    state
+        .add_balance(&sender, &U256::from(100_000), CleanupMode::NoEmpty)
+        .unwrap();
+    let chain_id = ChainIdParams::with_schedule(
+        0,
+        vec![ChainIdTransition {
+            activation_epoch: 10,
+            chain_id: 1,
+        }],
+    )
+    .unwrap();
+    let machine = crate::machine::new_machine_with_builtin(chain_id);
+    let internal_contract_map = InternalContractMap::new();
+    let mut env = Env::default();
+    env.gas_limit = U256::from(100_000);
+    env.epoch_height = 10;
+    let spec = machine.spec(env.number);
+
+    // The transaction of the chain_id before the transition is dropped.
+    let res = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .transact(&t)
+    .unwrap();
+    match res {
+        ExecutionOutcome::NotExecutedDrop(TxDropError::ChainIdMismatch(
+            1,
+            0,
+        )) => {}
+        _ => panic!("Expected a chain_id mismatch. {:?}", res),
+    }
+    assert_eq!(state.nonce(&sender).unwrap(), U256::zero());
+}
+
 #[test]
 fn test_deposit_withdraw_lock() {
     let factory = Factory::new(VMType::Interpreter, 1024 * 32);
@@ -1510,6 +1783,7 @@ fn test_storage_commission_privilege() {
                 &privilege_control_address,
                 &U256::MAX,
                 &mut substate,
+                &spec,
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1810,6 +2084,7 @@ fn test_storage_commission_privilege() {
                 &privilege_control_address,
                 &U256::MAX,
                 &mut substate,
+                &spec,
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1916,3 +2191,185 @@ fn test_storage_commission_privilege() {
         *COLLATERAL_PER_STORAGE_KEY * U256::from(2)
     );
 }
+
+#[test]
+fn test_batch_transfer() {
+    let factory = Factory::new(VMType::Interpreter, 1024 * 32);
+    let mut sender = Address::zero();
+    sender.set_user_account_type_bits();
+    let receivers: Vec<Address> = (0..3)
+        .map(|_| Random.generate().unwrap().address())
+        .collect();
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state =
+        get_state_for_genesis_write_with_factory(&storage_manager, factory);
+    let env = Env::default();
+    let machine = make_byzantium_machine(0);
+    let internal_contract_map = InternalContractMap::new();
+    let mut spec = machine.spec(env.number);
+    spec.batch_transfer = true;
+    state
+        .add_balance(&sender, &U256::from(1_000_000), CleanupMode::NoEmpty)
+        .unwrap();
+
+    let batch_transfer_data = |receivers: &[Address], values: &[u64]| {
+        let mut data =
+            keccak("batchTransfer(address[],uint256[])")[0..4].to_vec();
+        let values: Vec<U256> = values.iter().map(|v| U256::from(*v)).collect();
+        data.extend((receivers.to_vec(), values).abi_encode());
+        data
+    };
+    let mut params = ActionParams::default();
+    params.code_address = BATCH_TRANSFER_CONTRACT_ADDRESS.clone();
+    params.address = params.code_address;
+    params.sender = sender;
+    params.original_sender = sender;
+    params.storage_owner = params.code_address;
+    params.gas = U256::from(1_000_000);
+    params.call_type = CallType::Call;
+
+    // The values do not add up to the transferred value, so nothing is
+    // transferred.
+    params.value = ActionValue::Transfer(U256::from(600));
+    params.data = Some(batch_transfer_data(&receivers, &[100, 200, 400]));
+    let mut substate = Substate::new();
+    let result = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .call(params.clone(), &mut substate);
+    assert_eq!(
+        result.unwrap_err(),
+        vm::Error::InternalContract(
+            "total value does not match the transferred value"
+        )
+    );
+    assert_eq!(state.balance(&sender).unwrap(), U256::from(1_000_000));
+    assert!(substate.logs.is_empty());
+
+    params.value = ActionValue::Transfer(U256::from(700));
+    let mut substate = Substate::new();
+    let result = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .call(params.clone(), &mut substate);
+    assert!(result.is_ok());
+    assert_eq!(state.balance(&sender).unwrap(), U256::from(999_300));
+    assert_eq!(
+        state.balance(&BATCH_TRANSFER_CONTRACT_ADDRESS).unwrap(),
+        U256::zero()
+    );
+    for (receiver, value) in receivers.iter().zip(&[100, 200, 400]) {
+        assert_eq!(state.balance(receiver).unwrap(), U256::from(*value));
+    }
+    assert_eq!(substate.logs.len(), 3);
+    assert_eq!(substate.logs[2].topics[2], H256::from(receivers[2]));
+    assert_eq!(
+        U256::from_big_endian(&substate.logs[2].data),
+        U256::from(400)
+    );
+
+    // A contract recipient is called with its value, so its code is executed.
+    // CALLVALUE PUSH1 0 MSTORE PUSH1 32 PUSH1 0 LOG0
+    let mut accepting_contract = receivers[0];
+    accepting_contract.set_contract_type_bits();
+    state
+        .new_contract(&accepting_contract, U256::zero(), U256::one())
+        .unwrap();
+    state
+        .init_code(
+            &accepting_contract,
+            "3460005260206000a0".from_hex().unwrap(),
+            sender,
+        )
+        .unwrap();
+    params.value = ActionValue::Transfer(U256::from(300));
+    params.data = Some(batch_transfer_data(
+        &[receivers[2], accepting_contract],
+        &[100, 200],
+    ));
+    let mut substate = Substate::new();
+    let result = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .call(params.clone(), &mut substate);
+    assert!(result.is_ok());
+    assert_eq!(state.balance(&sender).unwrap(), U256::from(999_000));
+    assert_eq!(state.balance(&receivers[2]).unwrap(), U256::from(500));
+    assert_eq!(state.balance(&accepting_contract).unwrap(), U256::from(200));
+    assert_eq!(substate.logs.len(), 3);
+    assert_eq!(substate.logs[1].topics[2], H256::from(accepting_contract));
+    assert_eq!(substate.logs[2].address, accepting_contract);
+    assert_eq!(
+        U256::from_big_endian(&substate.logs[2].data),
+        U256::from(200)
+    );
+
+    // A recipient reverts, so the whole batch is reverted.
+    // PUSH1 0 PUSH1 0 REVERT
+    let mut reverting_contract = receivers[1];
+    reverting_contract.set_contract_type_bits();
+    state
+        .new_contract(&reverting_contract, U256::zero(), U256::one())
+        .unwrap();
+    state
+        .init_code(
+            &reverting_contract,
+            "60006000fd".from_hex().unwrap(),
+            sender,
+        )
+        .unwrap();
+    params.data = Some(batch_transfer_data(
+        &[receivers[2], reverting_contract],
+        &[100, 200],
+    ));
+    let mut substate = Substate::new();
+    let result = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .call(params.clone(), &mut substate);
+    assert_eq!(
+        result.unwrap_err(),
+        vm::Error::InternalContract("call to recipient failed")
+    );
+    assert_eq!(state.balance(&sender).unwrap(), U256::from(999_000));
+    assert_eq!(state.balance(&receivers[2]).unwrap(), U256::from(500));
+    assert_eq!(state.balance(&reverting_contract).unwrap(), U256::zero());
+    assert!(substate.logs.is_empty());
+
+    // Before the activation, the address is an ordinary account which
+    // keeps the value.
+    spec.batch_transfer = false;
+    let mut substate = Substate::new();
+    let result = Executive::new(
+        &mut state,
+        &env,
+        &machine,
+        &spec,
+        &internal_contract_map,
+    )
+    .call(params, &mut substate);
+    assert!(result.is_ok());
+    assert_eq!(state.balance(&sender).unwrap(), U256::from(998_700));
+    assert_eq!(
+        state.balance(&BATCH_TRANSFER_CONTRACT_ADDRESS).unwrap(),
+        U256::from(300)
+    );
+    assert_eq!(state.balance(&receivers[2]).unwrap(), U256::from(500));
+    assert!(substate.logs.is_empty());
+}
@@ -0,0 +1,91 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_parameters::internal_contract_addresses::BATCH_TRANSFER_CONTRACT_ADDRESS;
+
+use super::{
+    super::{function::PreExecCheckTrait, impls::batch_transfer::*},
+    InterfaceTrait, InternalContractOutput, InternalContractTrait,
+    PreExecCheckConfTrait, SolFnTable, SolidityFunctionTrait,
+    UpfrontPaymentTrait,
+};
+use crate::{
+    evm::{ActionParams, Spec},
+    impl_function_type, make_function_table, make_solidity_contract,
+    make_solidity_function,
+    state::{State, Substate},
+    vm,
+};
+use cfx_types::{Address, U256};
+use solidity_abi::ABIDecodable;
+
+lazy_static! {
+    static ref CONTRACT_TABLE: SolFnTable = make_function_table!(BatchTransfer);
+}
+
+make_solidity_contract! {
+    pub struct BatchTransferControl(BATCH_TRANSFER_CONTRACT_ADDRESS, CONTRACT_TABLE);
+}
+
+make_solidity_function! {
+    struct BatchTransfer((Vec<Address>, Vec<U256>), "batchTransfer(address[],uint256[])");
+}
+impl_function_type!(BatchTransfer, "payable_write");
+
+impl UpfrontPaymentTrait for BatchTransfer {
+    fn upfront_gas_payment(
+        &self, (recipients, _values): &(Vec<Address>, Vec<U256>),
+        _: &ActionParams, spec: &Spec, _: &State,
+    ) -> U256
+    {
+        // A call with value and a log with 3 topics and 32 bytes data for
+        // each recipient. The gas used by the code of the recipients is paid
+        // from the gas left.
+        let gas_per_transfer = spec.call_gas
+            + spec.call_value_transfer_gas
+            + spec.log_gas
+            + 3 * spec.log_topic_gas
+            + 32 * spec.log_data_gas;
+        U256::from(gas_per_transfer) * recipients.len()
+    }
+}
+
+// The transfers are made by calls to the recipients, so the function returns
+// a VM instead of implementing `ExecutionTrait`.
+impl SolidityFunctionTrait for BatchTransfer {
+    fn execute(
+        &self, input: &[u8], params: &ActionParams, spec: &Spec,
+        state: &mut State, substate: &mut Substate,
+    ) -> vm::Result<InternalContractOutput>
+    {
+        self.pre_execution_check(params, substate)?;
+        let solidity_params =
+            <(Vec<Address>, Vec<U256>) as ABIDecodable>::abi_decode(&input)?;
+
+        let cost =
+            self.upfront_gas_payment(&solidity_params, params, spec, state);
+        if cost > params.gas {
+            return Err(vm::Error::OutOfGas);
+        }
+
+        let (recipients, values) = solidity_params;
+        let exec =
+            batch_transfer(recipients, values, params, params.gas - cost)?;
+        Ok(InternalContractOutput::Exec(exec))
+    }
+
+    fn name(&self) -> &'static str { Self::NAME_AND_PARAMS }
+}
+
+#[test]
+fn test_batch_transfer_contract_sig() {
+    /// The first 4 bytes of keccak('batchTransfer(address[],uint256[])') is
+    /// `0x88d695b2`.
+    static BATCH_TRANSFER_SIG: &'static [u8] = &[0x88, 0xd6, 0x95, 0xb2];
+
+    assert_eq!(
+        BatchTransfer {}.function_sig().to_vec(),
+        BATCH_TRANSFER_SIG.to_vec()
+    );
+}
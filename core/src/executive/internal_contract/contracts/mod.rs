@@ -3,6 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 mod admin;
+mod batch_transfer;
 mod sponsor;
 mod staking;
 
@@ -11,15 +12,17 @@ use super::{
         ExecutionTrait, InterfaceTrait, PreExecCheckConfTrait,
         UpfrontPaymentTrait,
     },
-    InternalContractTrait, SolidityFunctionTrait,
+    InternalContractOutput, InternalContractTrait, SolidityFunctionTrait,
 };
 use std::collections::{BTreeMap, HashMap};
 
 pub use self::{
-    admin::AdminControl, sponsor::SponsorWhitelistControl, staking::Staking,
+    admin::AdminControl, batch_transfer::BatchTransferControl,
+    sponsor::SponsorWhitelistControl, staking::Staking,
 };
 
 use crate::evm::Spec;
+use cfx_parameters::internal_contract_addresses::BATCH_TRANSFER_CONTRACT_ADDRESS;
 use cfx_types::Address;
 use std::sync::Arc;
 
@@ -65,7 +68,7 @@ macro_rules! rename_interface {
             fn execute(
                 &self, input: &[u8], params: &ActionParams, spec: &Spec,
                 state: &mut State, substate: &mut Substate,
-            ) -> vm::Result<vm::GasLeft> {
+            ) -> vm::Result<super::InternalContractOutput> {
                 $old_name.execute(input, params, spec, state, substate)
             }
         }
@@ -88,17 +91,24 @@ impl InternalContractMap {
         let admin = internal_contract_factory("admin");
         let sponsor = internal_contract_factory("sponsor");
         let staking = internal_contract_factory("staking");
+        let batch_transfer = internal_contract_factory("batch_transfer");
         builtin.insert(*admin.address(), admin);
         builtin.insert(*sponsor.address(), sponsor);
         builtin.insert(*staking.address(), staking);
+        builtin.insert(*batch_transfer.address(), batch_transfer);
         Self {
             builtin: Arc::new(builtin),
         }
     }
 
+    /// Return the internal contract at `address` if it is enabled by `spec`.
     pub fn contract(
-        &self, address: &Address,
+        &self, address: &Address, spec: &Spec,
     ) -> Option<&Box<dyn InternalContractTrait>> {
+        if *address == *BATCH_TRANSFER_CONTRACT_ADDRESS && !spec.batch_transfer
+        {
+            return None;
+        }
         self.builtin.get(address)
     }
 }
@@ -111,6 +121,9 @@ pub fn internal_contract_factory(name: &str) -> Box<dyn InternalContractTrait> {
         "sponsor" => {
             Box::new(SponsorWhitelistControl) as Box<dyn InternalContractTrait>
         }
+        "batch_transfer" => {
+            Box::new(BatchTransferControl) as Box<dyn InternalContractTrait>
+        }
         _ => panic!("invalid internal contract name: {}", name),
     }
 }
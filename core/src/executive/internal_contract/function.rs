@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use super::SolidityFunctionTrait;
+use super::{InternalContractOutput, SolidityFunctionTrait};
 use crate::{
     state::{State, Substate},
     vm::{self, ActionParams, CallType, GasLeft, ReturnData, Spec},
@@ -32,7 +32,7 @@ where T: InterfaceTrait
     fn execute(
         &self, input: &[u8], params: &ActionParams, spec: &Spec,
         state: &mut State, substate: &mut Substate,
-    ) -> vm::Result<GasLeft>
+    ) -> vm::Result<InternalContractOutput>
     {
         self.pre_execution_check(params, substate)?;
         let solidity_params = <T::Input as ABIDecodable>::abi_decode(&input)?;
@@ -51,11 +51,11 @@ where T: InterfaceTrait
                 if params.gas < cost + return_cost {
                     Err(vm::Error::OutOfGas)
                 } else {
-                    Ok(GasLeft::NeedsReturn {
+                    Ok(InternalContractOutput::Return(GasLeft::NeedsReturn {
                         gas_left: params.gas - cost - return_cost,
                         data: ReturnData::new(output, 0, length),
                         apply_state: true,
-                    })
+                    }))
                 }
             })
     }
@@ -0,0 +1,146 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    hash::keccak,
+    vm::{
+        self, ActionParams, CallType, Context, ExecTrapResult, GasLeft,
+        MessageCallResult, TrapError, TrapKind,
+    },
+};
+use cfx_types::{
+    address_util::AddressUtil, Address, BigEndianHash, H256, U256,
+};
+use std::collections::VecDeque;
+
+/// The maximal number of transfers in one batch.
+pub const MAX_BATCH_TRANSFER_COUNT: usize = 256;
+
+lazy_static! {
+    /// The topic of the log emitted for each transfer, i.e.
+    /// keccak('Transfer(address,address,uint256)').
+    pub static ref TRANSFER_EVENT_TOPIC: H256 =
+        keccak("Transfer(address,address,uint256)");
+}
+
+/// Implementation of `batchTransfer(address[],uint256[])`.
+/// The value of the call is paid to the recipients, so the values should add
+/// up to it exactly. Each recipient is called with its value and empty call
+/// data, so the code of a contract recipient is executed. Either all the
+/// transfers succeed, or the call fails and nothing is paid. A
+/// `Transfer(from, to, value)` log is emitted for each recipient.
+///
+/// The calls are made by the returned VM, with `gas_left` shared among them.
+pub fn batch_transfer(
+    recipients: Vec<Address>, values: Vec<U256>, params: &ActionParams,
+    gas_left: U256,
+) -> vm::Result<Box<dyn vm::Exec>>
+{
+    if recipients.len() != values.len() {
+        return Err(vm::Error::InternalContract(
+            "recipients and values have different lengths",
+        ));
+    }
+    if recipients.len() > MAX_BATCH_TRANSFER_COUNT {
+        return Err(vm::Error::InternalContract("too many transfers"));
+    }
+    let mut total_value = U256::zero();
+    for value in &values {
+        total_value = total_value
+            .checked_add(*value)
+            .ok_or(vm::Error::InternalContract("total value overflow"))?;
+    }
+    if total_value != params.value.value() {
+        return Err(vm::Error::InternalContract(
+            "total value does not match the transferred value",
+        ));
+    }
+    if recipients
+        .iter()
+        .any(|recipient| !recipient.is_valid_address())
+    {
+        return Err(vm::Error::InternalContract("invalid recipient address"));
+    }
+
+    Ok(Box::new(BatchTransferExec {
+        contract: params.address,
+        sender: params.sender,
+        transfers: recipients.into_iter().zip(values.into_iter()).collect(),
+        gas_left,
+        failed: false,
+    }))
+}
+
+/// The VM which calls the recipients of a batch transfer one by one.
+struct BatchTransferExec {
+    contract: Address,
+    sender: Address,
+    /// The transfers which are not made yet.
+    transfers: VecDeque<(Address, U256)>,
+    gas_left: U256,
+    /// Whether the last call to a recipient failed.
+    failed: bool,
+}
+
+impl vm::Exec for BatchTransferExec {
+    fn exec(
+        mut self: Box<Self>, context: &mut dyn Context,
+    ) -> ExecTrapResult<GasLeft> {
+        if self.failed {
+            return Ok(Err(vm::Error::InternalContract(
+                "call to recipient failed",
+            )));
+        }
+        let (recipient, value) = match self.transfers.pop_front() {
+            Some(transfer) => transfer,
+            None => return Ok(Ok(GasLeft::Known(self.gas_left))),
+        };
+        if context.depth() >= context.spec().max_depth {
+            return Ok(Err(vm::Error::InternalContract("call depth exceeded")));
+        }
+
+        let topics = vec![
+            *TRANSFER_EVENT_TOPIC,
+            H256::from(self.sender),
+            H256::from(recipient),
+        ];
+        if let Err(err) =
+            context.log(topics, H256::from_uint(&value).as_bytes())
+        {
+            return Ok(Err(err));
+        }
+
+        let call_result = context.call(
+            &self.gas_left,
+            &self.contract,
+            &recipient,
+            Some(value),
+            &[],
+            &recipient,
+            CallType::Call,
+            true,
+        );
+        match call_result {
+            Ok(Err(TrapKind::Call(params))) => {
+                Err(TrapError::Call(params, self))
+            }
+            Ok(_) => unreachable!("the call is trapped"),
+            Err(err) => Ok(Err(err.into())),
+        }
+    }
+}
+
+impl vm::ResumeCall for BatchTransferExec {
+    fn resume_call(
+        mut self: Box<Self>, result: MessageCallResult,
+    ) -> Box<dyn vm::Exec> {
+        match result {
+            MessageCallResult::Success(gas_left, _) => self.gas_left = gas_left,
+            MessageCallResult::Reverted(..) | MessageCallResult::Failed => {
+                self.failed = true
+            }
+        }
+        self
+    }
+}
@@ -3,6 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 pub(super) mod admin;
+pub(super) mod batch_transfer;
 pub(super) mod sponsor;
 pub(super) mod staking;
 
@@ -27,6 +27,14 @@ lazy_static! {
     static ref INTERNAL_CONTRACT_CODE_HASH: H256 = keccak([0u8, 0u8, 0u8, 0u8]);
 }
 
+/// The output of an internal contract function. A function which calls other
+/// contracts returns a VM, which the executive runs on the context of the
+/// internal contract, so that the calls are executed as sub-calls.
+pub enum InternalContractOutput {
+    Return(GasLeft),
+    Exec(Box<dyn vm::Exec>),
+}
+
 /// Native implementation of an internal contract.
 pub trait InternalContractTrait: Send + Sync {
     /// Address of the internal contract
@@ -39,7 +47,7 @@ pub trait InternalContractTrait: Send + Sync {
     fn execute(
         &self, params: &ActionParams, spec: &Spec, state: &mut State,
         substate: &mut Substate,
-    ) -> vm::Result<GasLeft>
+    ) -> vm::Result<InternalContractOutput>
     {
         let call_data = params
             .data
@@ -74,7 +82,7 @@ pub trait SolidityFunctionTrait: Send + Sync {
     fn execute(
         &self, input: &[u8], params: &ActionParams, spec: &Spec,
         state: &mut State, substate: &mut Substate,
-    ) -> vm::Result<GasLeft>;
+    ) -> vm::Result<InternalContractOutput>;
 
     /// The string for function sig
     fn name(&self) -> &'static str;
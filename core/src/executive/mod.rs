@@ -6,6 +6,7 @@ mod context;
 mod executed;
 mod executive;
 mod internal_contract;
+mod parallel;
 
 #[cfg(test)]
 mod executive_tests;
@@ -17,4 +18,5 @@ pub use self::{
         function, suicide, ABIDecodeError, InternalContractMap,
         InternalContractTrait, SolidityFunctionTrait,
     },
+    parallel::ParallelExecutive,
 };
@@ -0,0 +1,415 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Optimistic parallel execution of the transactions in a block.
+//!
+//! All the transactions are first executed concurrently, each on its own
+//! speculative state forked from the state at the beginning of the block.
+//! The results are then applied in the transaction order. A speculative
+//! result is only applied if none of the accounts and storage entries it
+//! read has been written by an earlier transaction in the block; otherwise
+//! the transaction is executed again on the real state. This guarantees the
+//! same outcome as executing all the transactions serially.
+
+use super::{ExecutionOutcome, Executive, InternalContractMap};
+use crate::{
+    machine::Machine,
+    state::{State, StateKeys},
+    vm::{Env, Spec},
+};
+use cfx_statedb::{Result as DbResult, StateDb};
+use primitives::SignedTransaction;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+pub struct ParallelExecutive<'a> {
+    env: &'a Env,
+    machine: &'a Machine,
+    spec: &'a Spec,
+    internal_contract_map: &'a InternalContractMap,
+}
+
+impl<'a> ParallelExecutive<'a> {
+    pub fn new(
+        env: &'a Env, machine: &'a Machine, spec: &'a Spec,
+        internal_contract_map: &'a InternalContractMap,
+    ) -> Self {
+        ParallelExecutive {
+            env,
+            machine,
+            spec,
+            internal_contract_map,
+        }
+    }
+
+    /// Execute the transactions and apply the results to `state`.
+    /// `open_db` opens the db at the same version as the db of `state` for
+    /// the speculative states.
+    pub fn transact_all<F>(
+        &self, state: &mut State, transactions: &[Arc<SignedTransaction>],
+        open_db: F,
+    ) -> DbResult<Vec<ExecutionOutcome>>
+    where
+        F: Fn() -> StateDb + Sync,
+    {
+        // The env at each transaction, as in a serial execution. Within a
+        // block only `accumulated_gas_used` changes, which the VM does not
+        // read, so the speculative executions can all use the env at the
+        // beginning of the block.
+        let mut env = self.env.clone();
+        if transactions.len() < 2 {
+            let mut outcomes = Vec::with_capacity(transactions.len());
+            for tx in transactions {
+                let (outcome, _) = self.transact_serially(state, &env, tx)?;
+                Self::accumulate_gas_used(&mut env, &outcome);
+                outcomes.push(outcome);
+            }
+            return Ok(outcomes);
+        }
+
+        let base = Arc::new(state.speculative_base());
+        let speculative_results: Vec<_> = transactions
+            .par_iter()
+            .map_init(
+                || None,
+                |db, tx| {
+                    let mut speculative_state = State::new_speculative(
+                        db.take().unwrap_or_else(&open_db),
+                        base.clone(),
+                    );
+                    let result = self.transact_serially(
+                        &mut speculative_state,
+                        self.env,
+                        tx,
+                    );
+                    let (outcome, written_keys) = match result {
+                        Ok(r) => (Some(r.0), r.1),
+                        Err(_) => (None, StateKeys::default()),
+                    };
+                    let (speculative_db, changes) = speculative_state
+                        .into_speculative_changes(written_keys);
+                    *db = Some(speculative_db);
+                    // A db error is retried on the real state.
+                    outcome.map(|outcome| (outcome, changes))
+                },
+            )
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(transactions.len());
+        let mut written_keys = StateKeys::default();
+        let mut reexecuted = 0;
+        for (tx, speculative_result) in
+            transactions.iter().zip(speculative_results)
+        {
+            let outcome = match speculative_result {
+                Some((outcome, changes))
+                    if !written_keys.conflicts_with(&changes.read_keys()) =>
+                {
+                    written_keys.extend(changes.written_keys());
+                    state.apply_speculative_changes(changes)?;
+                    outcome
+                }
+                _ => {
+                    let (outcome, written) =
+                        self.transact_serially(state, &env, tx)?;
+                    written_keys.extend(&written);
+                    reexecuted += 1;
+                    outcome
+                }
+            };
+            Self::accumulate_gas_used(&mut env, &outcome);
+            outcomes.push(outcome);
+        }
+        debug!(
+            "parallel execution: tx count={}, re-executed={}",
+            transactions.len(),
+            reexecuted
+        );
+
+        Ok(outcomes)
+    }
+
+    fn accumulate_gas_used(env: &mut Env, outcome: &ExecutionOutcome) {
+        match outcome {
+            ExecutionOutcome::Finished(executed)
+            | ExecutionOutcome::ExecutionErrorBumpNonce(_, executed) => {
+                env.accumulated_gas_used += executed.gas_used;
+            }
+            _ => {}
+        }
+    }
+
+    /// Execute the transaction on `state` and return the written state keys.
+    fn transact_serially(
+        &self, state: &mut State, env: &Env, tx: &SignedTransaction,
+    ) -> DbResult<(ExecutionOutcome, StateKeys)> {
+        state.checkpoint();
+        let outcome = Executive::new(
+            state,
+            env,
+            self.machine,
+            self.spec,
+            self.internal_contract_map,
+        )
+        .transact(tx);
+        let written = state.checkpoint_written_keys();
+        state.discard_checkpoint();
+        Ok((outcome?, written?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParallelExecutive;
+    use crate::{
+        executive::{ExecutionOutcome, Executive, InternalContractMap},
+        machine::new_machine_with_builtin,
+        state::{CleanupMode, State, StateKeys},
+        test_helpers::get_state_for_genesis_write,
+        vm::{Env, Spec},
+        vm_factory::VmFactory,
+    };
+    use cfx_statedb::StateDb;
+    use cfx_storage::{
+        tests::new_state_manager_for_unit_test, StateIndex, StorageManager,
+        StorageManagerTrait,
+    };
+    use cfx_types::{address_util::AddressUtil, Address, H256, U256};
+    use keylib::{Generator, KeyPair, Random};
+    use primitives::{
+        transaction::Action, EpochId, StorageLayout, Transaction,
+    };
+    use std::sync::Arc;
+
+    fn state_db(
+        storage_manager: &Arc<StorageManager>, epoch_id: &EpochId,
+    ) -> StateDb {
+        StateDb::new(
+            storage_manager
+                .get_state_for_next_epoch(
+                    StateIndex::new_for_test_only_delta_mpt(epoch_id),
+                )
+                .unwrap()
+                .unwrap(),
+        )
+    }
+
+    fn transfer(
+        sender: &KeyPair, nonce: usize, receiver: &Address, value: usize,
+    ) -> Arc<primitives::SignedTransaction> {
+        Arc::new(
+            Transaction {
+                action: Action::Call(*receiver),
+                value: U256::from(value),
+                data: vec![],
+                gas: U256::from(21_000),
+                gas_price: U256::one(),
+                storage_limit: 0,
+                epoch_height: 0,
+                chain_id: 0,
+                nonce: U256::from(nonce),
+            }
+            .sign(sender.secret()),
+        )
+    }
+
+    #[test]
+    fn test_parallel_execution() {
+        let senders: Vec<KeyPair> =
+            (0..3).map(|_| Random.generate().unwrap()).collect();
+        let receivers: Vec<Address> = (0..3)
+            .map(|_| Random.generate().unwrap().address())
+            .collect();
+        // The 3rd transaction conflicts with the 1st one, and the 4th one
+        // transfers to the sender of the 2nd one.
+        let transactions = vec![
+            transfer(&senders[0], 0, &receivers[0], 100),
+            transfer(&senders[1], 0, &receivers[1], 200),
+            transfer(&senders[0], 1, &receivers[2], 300),
+            transfer(&senders[2], 0, &senders[1].address(), 400),
+        ];
+
+        let machine = new_machine_with_builtin(Default::default());
+        let mut env = Env::default();
+        env.gas_limit = U256::from(1_000_000);
+        let spec = machine.spec(env.number);
+        let internal_contract_map = InternalContractMap::new();
+
+        let storage_manager = new_state_manager_for_unit_test();
+        let mut serial_state = get_state_for_genesis_write(&storage_manager);
+        let mut parallel_state = get_state_for_genesis_write(&storage_manager);
+        for state in &mut [&mut serial_state, &mut parallel_state] {
+            for sender in &senders {
+                state
+                    .add_balance(
+                        &sender.address(),
+                        &U256::from(1_000_000),
+                        CleanupMode::NoEmpty,
+                    )
+                    .unwrap();
+            }
+        }
+
+        let serial_outcomes: Vec<ExecutionOutcome> = transactions
+            .iter()
+            .map(|tx| {
+                Executive::new(
+                    &mut serial_state,
+                    &env,
+                    &machine,
+                    &spec,
+                    &internal_contract_map,
+                )
+                .transact(tx)
+                .unwrap()
+            })
+            .collect();
+        let parallel_outcomes = ParallelExecutive::new(
+            &env,
+            &machine,
+            &spec,
+            &internal_contract_map,
+        )
+        .transact_all(&mut parallel_state, &transactions, || {
+            state_db(&storage_manager, &EpochId::default())
+        })
+        .unwrap();
+
+        assert_eq!(parallel_outcomes.len(), serial_outcomes.len());
+        for (parallel, serial) in
+            parallel_outcomes.iter().zip(serial_outcomes.iter())
+        {
+            match (parallel, serial) {
+                (
+                    ExecutionOutcome::Finished(parallel),
+                    ExecutionOutcome::Finished(serial),
+                ) => {
+                    assert_eq!(parallel.gas_used, serial.gas_used);
+                    assert_eq!(parallel.fee, serial.fee);
+                }
+                _ => panic!("Expected all transactions to succeed"),
+            }
+        }
+        for address in senders
+            .iter()
+            .map(KeyPair::address)
+            .chain(receivers.iter().cloned())
+        {
+            assert_eq!(
+                parallel_state.balance(&address).unwrap(),
+                serial_state.balance(&address).unwrap()
+            );
+            assert_eq!(
+                parallel_state.nonce(&address).unwrap(),
+                serial_state.nonce(&address).unwrap()
+            );
+        }
+        assert_eq!(
+            parallel_state.total_issued_tokens(),
+            serial_state.total_issued_tokens()
+        );
+    }
+
+    /// Commit a contract in the genesis, and return the state of the next
+    /// epoch with the contract.
+    fn state_with_contract(
+        storage_manager: &Arc<StorageManager>,
+    ) -> (State, Address, EpochId) {
+        let mut state = get_state_for_genesis_write(storage_manager);
+        let mut contract = Address::random();
+        contract.set_contract_type_bits();
+        state
+            .new_contract(&contract, U256::zero(), U256::one())
+            .unwrap();
+        state
+            .set_storage_layout(&contract, StorageLayout::Regular(0))
+            .unwrap();
+        let epoch_id = H256::from_low_u64_be(1);
+        state.commit(epoch_id, /* debug_record = */ None).unwrap();
+
+        let state = State::new(
+            state_db(storage_manager, &epoch_id),
+            VmFactory::default(),
+            &Spec::new_spec(),
+            1, /* block_number */
+        );
+        (state, contract, epoch_id)
+    }
+
+    #[test]
+    fn test_storage_key_conflicts() {
+        let storage_manager = new_state_manager_for_unit_test();
+        let (mut state, contract, epoch_id) =
+            state_with_contract(&storage_manager);
+        let base = Arc::new(state.speculative_base());
+        let keys: Vec<Vec<u8>> = (0..2u8).map(|i| vec![i; 32]).collect();
+        // The 1st and the 2nd writes go to different storage entries of the
+        // same contract, the 3rd one overwrites the entry of the 1st one.
+        let writes = vec![(&keys[0], 1), (&keys[1], 2), (&keys[0], 3)];
+        let mut written_keys = StateKeys::default();
+        let mut conflicts = vec![];
+        for (key, value) in writes {
+            let mut speculative_state = State::new_speculative(
+                state_db(&storage_manager, &epoch_id),
+                base.clone(),
+            );
+            speculative_state.checkpoint();
+            speculative_state
+                .set_storage(&contract, key.clone(), value.into(), contract)
+                .unwrap();
+            let written = speculative_state.checkpoint_written_keys().unwrap();
+            speculative_state.discard_checkpoint();
+            let (_, changes) =
+                speculative_state.into_speculative_changes(written);
+
+            let conflict = written_keys.conflicts_with(&changes.read_keys());
+            conflicts.push(conflict);
+            if !conflict {
+                written_keys.extend(changes.written_keys());
+                state.apply_speculative_changes(changes).unwrap();
+            }
+        }
+
+        assert_eq!(conflicts, vec![false, false, true]);
+        assert_eq!(state.storage_at(&contract, &keys[0]).unwrap(), 1.into());
+        assert_eq!(state.storage_at(&contract, &keys[1]).unwrap(), 2.into());
+    }
+
+    #[test]
+    fn test_reads_of_reverted_frames() {
+        let storage_manager = new_state_manager_for_unit_test();
+        let (mut state, contract, epoch_id) =
+            state_with_contract(&storage_manager);
+        let base = Arc::new(state.speculative_base());
+        let keys: Vec<Vec<u8>> = (0..2u8).map(|i| vec![i; 32]).collect();
+
+        // A transaction writes the 1st storage entry.
+        let mut writer = State::new_speculative(
+            state_db(&storage_manager, &epoch_id),
+            base.clone(),
+        );
+        writer.checkpoint();
+        writer
+            .set_storage(&contract, keys[0].clone(), 1.into(), contract)
+            .unwrap();
+        let written_keys = writer.checkpoint_written_keys().unwrap();
+
+        // Another one reads it in an inner call which writes the 2nd entry
+        // and reverts.
+        let mut reader =
+            State::new_speculative(state_db(&storage_manager, &epoch_id), base);
+        reader.checkpoint();
+        reader.checkpoint();
+        reader
+            .set_storage(&contract, keys[1].clone(), 2.into(), contract)
+            .unwrap();
+        reader.storage_at(&contract, &keys[0]).unwrap();
+        reader.revert_to_checkpoint();
+        let written = reader.checkpoint_written_keys().unwrap();
+        reader.discard_checkpoint();
+        let (_, changes) = reader.into_speculative_changes(written);
+
+        assert!(written_keys.conflicts_with(&changes.read_keys()));
+    }
+}
@@ -31,6 +31,7 @@ pub mod channel;
 pub mod client;
 pub mod consensus;
 pub mod db;
+pub mod db_migration;
 pub mod error;
 mod evm;
 pub mod executive;
@@ -44,11 +45,15 @@ pub mod state;
 pub mod state_exposer;
 pub mod statistics;
 pub mod sync;
+pub mod trace;
 pub mod transaction_pool;
 pub mod unique_id;
 pub mod verification;
 pub mod vm;
 pub mod vm_factory;
+pub mod watchdog;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 pub mod test_helpers;
 
@@ -59,6 +64,7 @@ pub use crate::{
         BestInformation, ConsensusGraph, ConsensusGraphTrait,
         SharedConsensusGraph,
     },
+    evm::profile as evm_profile,
     light_protocol::{
         Provider as LightProvider, QueryService as LightQueryService,
     },
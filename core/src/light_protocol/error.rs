@@ -72,6 +72,11 @@ error_chain! {
             display("Invalid previous state root for epoch {} with snapshot epoch count {}: {:?}", current_epoch, snapshot_epoch_count, root),
         }
 
+        InvalidReceiptProof{ reason: String } {
+            description("Invalid receipt proof"),
+            display("Invalid receipt proof: {:?}", reason),
+        }
+
         InvalidReceipts{ epoch: u64, expected: H256, received: H256 } {
             description("Receipts root validation failed"),
             display("Receipts root validation for epoch {} failed, expected={:?}, received={:?}", epoch, expected, received),
@@ -132,6 +137,11 @@ error_chain! {
             display("Unable to produce tx info: {:?}", reason),
         }
 
+        UnableToProduceReceiptProof{ reason: String } {
+            description("Unable to produce receipt proof"),
+            display("Unable to produce receipt proof: {:?}", reason),
+        }
+
         UnexpectedMessage{ expected: Vec<MsgId>, received: MsgId } {
             description("Unexpected message"),
             display("Unexpected message id={:?}, expected one of {:?}", received, expected),
@@ -203,6 +213,7 @@ pub fn handle(
         // if the tx requested has been removed locally,
         // we should not disconnect the peer
         | ErrorKind::UnableToProduceTxInfo{..}
+        | ErrorKind::UnableToProduceReceiptProof{..}
 
         // if the witness is not available, it is probably
         // due to the local witness sync process
@@ -226,6 +237,7 @@ pub fn handle(
         | ErrorKind::InvalidLedgerProofSize{..}
         | ErrorKind::InvalidMessageFormat
         | ErrorKind::InvalidPreviousStateRoot{..}
+        | ErrorKind::InvalidReceiptProof{..}
         | ErrorKind::InvalidReceipts{..}
         | ErrorKind::InvalidStateProof{..}
         | ErrorKind::InvalidStateRoot{..}
@@ -15,8 +15,10 @@ use crate::{
             msgid, BlockHashes as GetBlockHashesResponse,
             BlockHeaders as GetBlockHeadersResponse,
             BlockTxs as GetBlockTxsResponse, Blooms as GetBloomsResponse,
-            NewBlockHashes, NodeType, Receipts as GetReceiptsResponse,
-            SendRawTx, StateEntries as GetStateEntriesResponse,
+            LogProofs as GetLogProofsResponse, NewBlockHashes, NodeType,
+            ReceiptProofs as GetReceiptProofsResponse,
+            Receipts as GetReceiptsResponse, SendRawTx,
+            StateEntries as GetStateEntriesResponse,
             StateRoots as GetStateRootsResponse, StatusPingDeprecatedV1,
             StatusPingV2, StatusPongDeprecatedV1, StatusPongV2,
             StorageRoots as GetStorageRootsResponse,
@@ -50,8 +52,8 @@ use std::{
     time::{Duration, Instant},
 };
 use sync::{
-    BlockTxs, Blooms, Epochs, HashSource, Headers, Receipts, StateEntries,
-    StateRoots, StorageRoots, TxInfos, Txs, Witnesses,
+    BlockTxs, Blooms, Epochs, HashSource, Headers, LogProofs, ReceiptProofs,
+    Receipts, StateEntries, StateRoots, StorageRoots, TxInfos, Txs, Witnesses,
 };
 use throttling::token_bucket::TokenBucketManager;
 
@@ -88,9 +90,15 @@ pub struct Handler {
     // join handle for witness worker thread
     join_handle: Option<thread::JoinHandle<()>>,
 
+    // log proof sync manager
+    pub log_proofs: LogProofs,
+
     // collection of all peers available
     pub peers: Arc<Peers<FullPeerState>>,
 
+    // receipt proof sync manager
+    pub receipt_proofs: ReceiptProofs,
+
     // receipt sync manager
     pub receipts: Arc<Receipts>,
 
@@ -199,6 +207,20 @@ impl Handler {
             witnesses.clone(),
         );
 
+        let receipt_proofs = ReceiptProofs::new(
+            consensus.clone(),
+            peers.clone(),
+            request_id_allocator.clone(),
+            witnesses.clone(),
+        );
+
+        let log_proofs = LogProofs::new(
+            consensus.clone(),
+            peers.clone(),
+            request_id_allocator.clone(),
+            witnesses.clone(),
+        );
+
         let stopped = Arc::new(AtomicBool::new(false));
 
         let join_handle = Some(Self::start_witness_worker(
@@ -217,8 +239,10 @@ impl Handler {
             epochs,
             headers,
             join_handle,
+            log_proofs,
             peers,
             protocol_version: LIGHT_PROTOCOL_VERSION,
+            receipt_proofs,
             receipts,
             state_entries,
             state_roots,
@@ -394,7 +418,9 @@ impl Handler {
             msgid::BLOCK_HEADERS => self.on_block_headers(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::BLOCK_TXS => self.on_block_txs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::BLOOMS => self.on_blooms(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
+            msgid::LOG_PROOFS => self.on_log_proofs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::NEW_BLOCK_HASHES => self.on_new_block_hashes(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
+            msgid::RECEIPT_PROOFS => self.on_receipt_proofs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::RECEIPTS => self.on_receipts(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::STATE_ENTRIES => self.on_state_entries(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::STATE_ROOTS => self.on_state_roots(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
@@ -633,6 +659,37 @@ impl Handler {
         Ok(())
     }
 
+    fn on_log_proofs(
+        &self, io: &dyn NetworkContext, peer: &NodeId,
+        resp: GetLogProofsResponse,
+    ) -> Result<()>
+    {
+        debug!("on_log_proofs resp={:?}", resp);
+
+        self.log_proofs
+            .receive(peer, resp.request_id, resp.proofs)?;
+
+        self.log_proofs.sync(io);
+        Ok(())
+    }
+
+    fn on_receipt_proofs(
+        &self, io: &dyn NetworkContext, peer: &NodeId,
+        resp: GetReceiptProofsResponse,
+    ) -> Result<()>
+    {
+        debug!("on_receipt_proofs resp={:?}", resp);
+
+        self.receipt_proofs.receive(
+            peer,
+            resp.request_id,
+            resp.proofs.into_iter(),
+        )?;
+
+        self.receipt_proofs.sync(io);
+        Ok(())
+    }
+
     fn on_receipts(
         &self, io: &dyn NetworkContext, peer: &NodeId,
         resp: GetReceiptsResponse,
@@ -765,6 +822,8 @@ impl Handler {
         self.storage_roots.sync(io);
         self.txs.sync(io);
         self.tx_infos.sync(io);
+        self.receipt_proofs.sync(io);
+        self.log_proofs.sync(io);
     }
 
     fn clean_up_requests(&self) {
@@ -773,6 +832,8 @@ impl Handler {
         self.blooms.clean_up();
         self.epochs.clean_up();
         self.headers.clean_up();
+        self.log_proofs.clean_up();
+        self.receipt_proofs.clean_up();
         self.receipts.clean_up();
         self.state_entries.clean_up();
         self.state_roots.clean_up();
@@ -852,6 +913,8 @@ impl NetworkProtocolHandler for Handler {
     }
 
     fn on_message(&self, io: &dyn NetworkContext, peer: &NodeId, raw: &[u8]) {
+        let _peer_context =
+            log_mdc::insert_scoped("peer", format!("{:?}", peer));
         trace!("on_message: peer={:?}, raw={:?}", peer, raw);
 
         let (msg_id, rlp) = match decode_msg(raw) {
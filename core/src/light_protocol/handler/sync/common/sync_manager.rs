@@ -141,6 +141,18 @@ where
         }
     }
 
+    /// The keys of the items in flight that are requested with
+    /// `request_id`, for the responses not including the keys.
+    #[inline]
+    pub fn in_flight_keys(&self, request_id: RequestId) -> Vec<Key> {
+        self.in_flight
+            .read()
+            .iter()
+            .filter(|(_, req)| req.request_id == request_id)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
     #[inline]
     pub fn remove_in_flight(&self, key: &Key) {
         self.in_flight.write().remove(&key);
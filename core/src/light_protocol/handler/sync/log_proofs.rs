@@ -0,0 +1,258 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+extern crate lru_time_cache;
+
+use super::{
+    common::{FutureItem, PendingItem, SyncManager, TimeOrdered},
+    receipt_proofs::validate_receipt_proof,
+    Witnesses,
+};
+use crate::{
+    consensus::SharedConsensusGraph,
+    light_protocol::{
+        common::{FullPeerState, LedgerInfo, Peers},
+        error::*,
+        message::{
+            msgid, GetLogProofs, LogFilter, ReceiptKey, ReceiptWithProof,
+        },
+    },
+    message::{Message, RequestId},
+    UniqueId,
+};
+use cfx_parameters::light::{
+    CACHE_TIMEOUT, LOG_PROOF_REQUEST_BATCH_SIZE, LOG_PROOF_REQUEST_TIMEOUT,
+    MAX_LOG_PROOFS_IN_FLIGHT,
+};
+use futures::future::FutureExt;
+use lru_time_cache::LruCache;
+use network::{node_table::NodeId, NetworkContext};
+use parking_lot::RwLock;
+use primitives::Receipt;
+use std::{future::Future, sync::Arc};
+
+#[derive(Debug)]
+struct Statistics {
+    cached: usize,
+    in_flight: usize,
+    waiting: usize,
+}
+
+/// The receipts in `epoch` with logs matching `filter`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogProofsKey {
+    pub epoch: u64,
+    pub filter: LogFilter,
+}
+
+// prioritize earlier requests
+type MissingLogProofs = TimeOrdered<LogProofsKey>;
+
+pub type LogProofsValidated = Vec<(ReceiptKey, Receipt)>;
+
+type PendingLogProofs = PendingItem<LogProofsValidated, ClonableError>;
+
+pub struct LogProofs {
+    // helper API for retrieving ledger information
+    ledger: LedgerInfo,
+
+    // series of unique request ids
+    request_id_allocator: Arc<UniqueId>,
+
+    // sync and request manager
+    sync_manager: SyncManager<LogProofsKey, MissingLogProofs>,
+
+    // receipts received from full node
+    verified: Arc<RwLock<LruCache<LogProofsKey, PendingLogProofs>>>,
+
+    // witness sync manager
+    witnesses: Arc<Witnesses>,
+}
+
+impl LogProofs {
+    pub fn new(
+        consensus: SharedConsensusGraph, peers: Arc<Peers<FullPeerState>>,
+        request_id_allocator: Arc<UniqueId>, witnesses: Arc<Witnesses>,
+    ) -> Self
+    {
+        let ledger = LedgerInfo::new(consensus);
+        let sync_manager = SyncManager::new(peers, msgid::GET_LOG_PROOFS);
+
+        let cache = LruCache::with_expiry_duration(*CACHE_TIMEOUT);
+        let verified = Arc::new(RwLock::new(cache));
+
+        LogProofs {
+            ledger,
+            request_id_allocator,
+            sync_manager,
+            verified,
+            witnesses,
+        }
+    }
+
+    #[inline]
+    fn get_statistics(&self) -> Statistics {
+        Statistics {
+            cached: self.verified.read().len(),
+            in_flight: self.sync_manager.num_in_flight(),
+            waiting: self.sync_manager.num_waiting(),
+        }
+    }
+
+    /// Request the receipts in `key.epoch` with logs matching `key.filter`.
+    /// Each receipt is verified with its proof; however, peers cannot prove
+    /// that no matching receipt is left out.
+    #[inline]
+    pub fn request_now(
+        &self, io: &dyn NetworkContext, key: LogProofsKey,
+    ) -> impl Future<Output = Result<LogProofsValidated>> {
+        let mut verified = self.verified.write();
+
+        if !verified.contains_key(&key) {
+            let missing = std::iter::once(MissingLogProofs::new(key.clone()));
+
+            self.sync_manager.request_now(missing, |peer, keys| {
+                self.send_request(io, peer, keys)
+            });
+        }
+
+        verified
+            .entry(key.clone())
+            .or_insert(PendingItem::pending())
+            .clear_error();
+
+        FutureItem::new(key, self.verified.clone())
+            .map(|res| res.map_err(|e| e.into()))
+    }
+
+    #[inline]
+    pub fn receive(
+        &self, peer: &NodeId, id: RequestId, proofs: Vec<ReceiptWithProof>,
+    ) -> Result<()> {
+        // the response does not include the filter, so we find the key
+        // through the request id
+        for key in self.sync_manager.in_flight_keys(id) {
+            debug!("Validating log proofs {:?} of {:?}", proofs, key);
+
+            match self.sync_manager.check_if_requested(peer, id, &key)? {
+                None => continue,
+                Some(_) => self.validate_and_store(key, proofs.clone())?,
+            };
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn validate_and_store(
+        &self, key: LogProofsKey, proofs: Vec<ReceiptWithProof>,
+    ) -> Result<()> {
+        // validate log proofs
+        if let Err(e) = self.validate_proofs(&key, &proofs) {
+            // forward error to both rpc caller(s) and sync handler
+            // so we need to make it clonable
+            let e = ClonableError::from(e);
+
+            self.verified
+                .write()
+                .entry(key)
+                .or_insert(PendingItem::pending())
+                .set_error(e.clone());
+
+            bail!(e);
+        }
+
+        let receipts = proofs
+            .into_iter()
+            .map(|proof| (proof.key, proof.receipt))
+            .collect();
+
+        self.verified
+            .write()
+            .entry(key.clone())
+            .or_insert(PendingItem::pending())
+            .set(receipts);
+
+        self.sync_manager.remove_in_flight(&key);
+        Ok(())
+    }
+
+    #[inline]
+    fn validate_proofs(
+        &self, key: &LogProofsKey, proofs: &Vec<ReceiptWithProof>,
+    ) -> Result<()> {
+        let epoch_hashes = self.ledger.block_hashes_in(key.epoch)?;
+        let epoch_receipts_root =
+            self.witnesses.root_hashes_of(key.epoch)?.receipts_root_hash;
+
+        for proof in proofs {
+            if proof.key.epoch != key.epoch {
+                bail!(ErrorKind::InvalidReceiptProof {
+                    reason: format!(
+                        "Receipt of epoch {} in log proofs of epoch {}",
+                        proof.key.epoch, key.epoch
+                    )
+                });
+            }
+
+            if !proof.receipt.logs.iter().any(|log| key.filter.matches(log)) {
+                bail!(ErrorKind::InvalidReceiptProof {
+                    reason: format!(
+                        "Receipt {:?} has no logs matching {:?}",
+                        proof.key, key.filter
+                    )
+                });
+            }
+
+            validate_receipt_proof(proof, &epoch_hashes, epoch_receipts_root)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn clean_up(&self) {
+        // remove timeout in-flight requests
+        let timeout = *LOG_PROOF_REQUEST_TIMEOUT;
+        let keys = self.sync_manager.remove_timeout_requests(timeout);
+        self.sync_manager.insert_waiting(keys.into_iter());
+
+        // trigger cache cleanup
+        self.verified.write().get(&Default::default());
+    }
+
+    #[inline]
+    fn send_request(
+        &self, io: &dyn NetworkContext, peer: &NodeId, keys: Vec<LogProofsKey>,
+    ) -> Result<Option<RequestId>> {
+        debug!("send_request peer={:?} keys={:?}", peer, keys);
+
+        // one key per request, see `LOG_PROOF_REQUEST_BATCH_SIZE`
+        let LogProofsKey { epoch, filter } = match keys.into_iter().next() {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        let request_id = self.request_id_allocator.next();
+        let msg: Box<dyn Message> = Box::new(GetLogProofs {
+            request_id,
+            epochs: vec![epoch],
+            filter,
+        });
+
+        msg.send(io, peer)?;
+        Ok(Some(request_id))
+    }
+
+    #[inline]
+    pub fn sync(&self, io: &dyn NetworkContext) {
+        debug!("log proof sync statistics: {:?}", self.get_statistics());
+
+        self.sync_manager.sync(
+            MAX_LOG_PROOFS_IN_FLIGHT,
+            LOG_PROOF_REQUEST_BATCH_SIZE,
+            |peer, keys| self.send_request(io, peer, keys),
+        );
+    }
+}
@@ -7,6 +7,8 @@ mod blooms;
 mod common;
 mod epochs;
 mod headers;
+mod log_proofs;
+mod receipt_proofs;
 mod receipts;
 mod state_entries;
 mod state_roots;
@@ -19,6 +21,8 @@ pub use block_txs::BlockTxs;
 pub use blooms::Blooms;
 pub use epochs::Epochs;
 pub use headers::{HashSource, Headers};
+pub use log_proofs::{LogProofs, LogProofsKey, LogProofsValidated};
+pub use receipt_proofs::ReceiptProofs;
 pub use receipts::Receipts;
 pub use state_entries::StateEntries;
 pub use state_roots::StateRoots;
@@ -0,0 +1,399 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+extern crate lru_time_cache;
+
+use super::{
+    common::{FutureItem, PendingItem, SyncManager, TimeOrdered},
+    Witnesses,
+};
+use crate::{
+    consensus::SharedConsensusGraph,
+    light_protocol::{
+        common::{FullPeerState, LedgerInfo, Peers},
+        error::*,
+        message::{msgid, GetReceiptProofs, ReceiptKey, ReceiptWithProof},
+    },
+    message::{Message, RequestId},
+    verification::is_valid_receipt_inclusion_proof,
+    UniqueId,
+};
+use cfx_parameters::light::{
+    CACHE_TIMEOUT, MAX_RECEIPT_PROOFS_IN_FLIGHT,
+    RECEIPT_PROOF_REQUEST_BATCH_SIZE, RECEIPT_PROOF_REQUEST_TIMEOUT,
+};
+use cfx_types::H256;
+use futures::future::FutureExt;
+use lru_time_cache::LruCache;
+use network::{node_table::NodeId, NetworkContext};
+use parking_lot::RwLock;
+use primitives::Receipt;
+use std::{future::Future, sync::Arc};
+
+#[derive(Debug)]
+struct Statistics {
+    cached: usize,
+    in_flight: usize,
+    waiting: usize,
+}
+
+// prioritize earlier requests
+type MissingReceiptProof = TimeOrdered<ReceiptKey>;
+
+type PendingReceiptProof = PendingItem<Receipt, ClonableError>;
+
+/// Check that `proof` proves a receipt of the block `proof.key.block_hash`,
+/// in an epoch of the blocks `epoch_hashes` with the verified receipts root
+/// `epoch_receipts_root`.
+pub fn validate_receipt_proof(
+    proof: &ReceiptWithProof, epoch_hashes: &[H256], epoch_receipts_root: H256,
+) -> Result<()> {
+    let ReceiptWithProof {
+        key,
+        receipt,
+        block_index_in_epoch,
+        num_blocks_in_epoch,
+        num_txs_in_block,
+        block_index_proof,
+        receipt_proof,
+    } = proof;
+
+    // quick check for well-formedness
+    if block_index_in_epoch >= num_blocks_in_epoch {
+        bail!(ErrorKind::InvalidReceiptProof {
+            reason: format!(
+                "Inconsisent block index: {} >= {}",
+                block_index_in_epoch, num_blocks_in_epoch
+            )
+        });
+    }
+
+    if key.tx_index_in_block >= *num_txs_in_block {
+        bail!(ErrorKind::InvalidReceiptProof {
+            reason: format!(
+                "Inconsisent tx index: {} >= {}",
+                key.tx_index_in_block, num_txs_in_block
+            )
+        });
+    }
+
+    if epoch_hashes.len() != *num_blocks_in_epoch {
+        bail!(ErrorKind::InvalidReceiptProof {
+            reason: format!(
+                "Number of blocks in epoch mismatch: local = {}, received = {}",
+                epoch_hashes.len(),
+                num_blocks_in_epoch
+            ),
+        });
+    }
+
+    if epoch_hashes[*block_index_in_epoch] != key.block_hash {
+        bail!(ErrorKind::InvalidReceiptProof {
+            reason: format!(
+                "Block {:?} is not at index {} of epoch {}",
+                key.block_hash, block_index_in_epoch, key.epoch
+            ),
+        });
+    }
+
+    if !is_valid_receipt_inclusion_proof(
+        epoch_receipts_root,
+        *block_index_in_epoch,
+        *num_blocks_in_epoch,
+        block_index_proof,
+        key.tx_index_in_block,
+        *num_txs_in_block,
+        receipt,
+        receipt_proof,
+    ) {
+        bail!(ErrorKind::InvalidReceiptProof {
+            reason: "Receipt proof verification failed".to_owned()
+        });
+    }
+
+    Ok(())
+}
+
+pub struct ReceiptProofs {
+    // helper API for retrieving ledger information
+    ledger: LedgerInfo,
+
+    // series of unique request ids
+    request_id_allocator: Arc<UniqueId>,
+
+    // sync and request manager
+    sync_manager: SyncManager<ReceiptKey, MissingReceiptProof>,
+
+    // receipts received from full node
+    verified: Arc<RwLock<LruCache<ReceiptKey, PendingReceiptProof>>>,
+
+    // witness sync manager
+    witnesses: Arc<Witnesses>,
+}
+
+impl ReceiptProofs {
+    pub fn new(
+        consensus: SharedConsensusGraph, peers: Arc<Peers<FullPeerState>>,
+        request_id_allocator: Arc<UniqueId>, witnesses: Arc<Witnesses>,
+    ) -> Self
+    {
+        let ledger = LedgerInfo::new(consensus);
+        let sync_manager = SyncManager::new(peers, msgid::GET_RECEIPT_PROOFS);
+
+        let cache = LruCache::with_expiry_duration(*CACHE_TIMEOUT);
+        let verified = Arc::new(RwLock::new(cache));
+
+        ReceiptProofs {
+            ledger,
+            request_id_allocator,
+            sync_manager,
+            verified,
+            witnesses,
+        }
+    }
+
+    #[inline]
+    fn get_statistics(&self) -> Statistics {
+        Statistics {
+            cached: self.verified.read().len(),
+            in_flight: self.sync_manager.num_in_flight(),
+            waiting: self.sync_manager.num_waiting(),
+        }
+    }
+
+    #[inline]
+    pub fn request_now(
+        &self, io: &dyn NetworkContext, key: ReceiptKey,
+    ) -> impl Future<Output = Result<Receipt>> {
+        let mut verified = self.verified.write();
+
+        if !verified.contains_key(&key) {
+            let missing =
+                std::iter::once(MissingReceiptProof::new(key.clone()));
+
+            self.sync_manager.request_now(missing, |peer, keys| {
+                self.send_request(io, peer, keys)
+            });
+        }
+
+        verified
+            .entry(key.clone())
+            .or_insert(PendingItem::pending())
+            .clear_error();
+
+        FutureItem::new(key, self.verified.clone())
+            .map(|res| res.map_err(|e| e.into()))
+    }
+
+    #[inline]
+    pub fn receive(
+        &self, peer: &NodeId, id: RequestId,
+        proofs: impl Iterator<Item = ReceiptWithProof>,
+    ) -> Result<()>
+    {
+        for proof in proofs {
+            debug!("Validating receipt proof {:?}", proof);
+
+            match self.sync_manager.check_if_requested(peer, id, &proof.key)? {
+                None => continue,
+                Some(_) => self.validate_and_store(proof)?,
+            };
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn validate_and_store(&self, proof: ReceiptWithProof) -> Result<()> {
+        let key = proof.key.clone();
+
+        // validate receipt proof
+        if let Err(e) = self.validate_proof(&proof) {
+            // forward error to both rpc caller(s) and sync handler
+            // so we need to make it clonable
+            let e = ClonableError::from(e);
+
+            self.verified
+                .write()
+                .entry(key)
+                .or_insert(PendingItem::pending())
+                .set_error(e.clone());
+
+            bail!(e);
+        }
+
+        self.verified
+            .write()
+            .entry(key.clone())
+            .or_insert(PendingItem::pending())
+            .set(proof.receipt);
+
+        self.sync_manager.remove_in_flight(&key);
+        Ok(())
+    }
+
+    #[inline]
+    fn validate_proof(&self, proof: &ReceiptWithProof) -> Result<()> {
+        let epoch = proof.key.epoch;
+        let epoch_hashes = self.ledger.block_hashes_in(epoch)?;
+        let epoch_receipts_root =
+            self.witnesses.root_hashes_of(epoch)?.receipts_root_hash;
+        validate_receipt_proof(proof, &epoch_hashes, epoch_receipts_root)
+    }
+
+    #[inline]
+    pub fn clean_up(&self) {
+        // remove timeout in-flight requests
+        let timeout = *RECEIPT_PROOF_REQUEST_TIMEOUT;
+        let proofs = self.sync_manager.remove_timeout_requests(timeout);
+        self.sync_manager.insert_waiting(proofs.into_iter());
+
+        // trigger cache cleanup
+        self.verified.write().get(&Default::default());
+    }
+
+    #[inline]
+    fn send_request(
+        &self, io: &dyn NetworkContext, peer: &NodeId, keys: Vec<ReceiptKey>,
+    ) -> Result<Option<RequestId>> {
+        debug!("send_request peer={:?} keys={:?}", peer, keys);
+
+        if keys.is_empty() {
+            return Ok(None);
+        }
+
+        let request_id = self.request_id_allocator.next();
+        let msg: Box<dyn Message> =
+            Box::new(GetReceiptProofs { request_id, keys });
+
+        msg.send(io, peer)?;
+        Ok(Some(request_id))
+    }
+
+    #[inline]
+    pub fn sync(&self, io: &dyn NetworkContext) {
+        debug!("receipt proof sync statistics: {:?}", self.get_statistics());
+
+        self.sync_manager.sync(
+            MAX_RECEIPT_PROOFS_IN_FLIGHT,
+            RECEIPT_PROOF_REQUEST_BATCH_SIZE,
+            |peer, keys| self.send_request(io, peer, keys),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_receipt_proof;
+    use crate::{
+        light_protocol::message::{ReceiptKey, ReceiptWithProof},
+        verification::{compute_epoch_receipt_proof, compute_receipts_root},
+    };
+    use cfx_types::{H256, U256};
+    use primitives::{BlockReceipts, Receipt};
+    use std::sync::Arc;
+
+    fn receipt(accumulated_gas_used: u64) -> Receipt {
+        Receipt::new(
+            0,
+            U256::from(accumulated_gas_used),
+            U256::zero(),
+            false,
+            vec![],
+            false,
+            vec![],
+            vec![],
+        )
+    }
+
+    fn proof_of(
+        epoch_receipts: &Vec<Arc<BlockReceipts>>, epoch_hashes: &[H256],
+        block_index_in_epoch: usize, tx_index_in_block: usize,
+    ) -> ReceiptWithProof
+    {
+        let block_receipts = &epoch_receipts[block_index_in_epoch].receipts;
+        let proof = compute_epoch_receipt_proof(
+            epoch_receipts,
+            block_index_in_epoch,
+            tx_index_in_block,
+        );
+
+        ReceiptWithProof {
+            key: ReceiptKey {
+                epoch: 5,
+                block_hash: epoch_hashes[block_index_in_epoch],
+                tx_index_in_block,
+            },
+            receipt: block_receipts[tx_index_in_block].clone(),
+            block_index_in_epoch,
+            num_blocks_in_epoch: epoch_receipts.len(),
+            num_txs_in_block: block_receipts.len(),
+            block_index_proof: proof.block_index_proof,
+            receipt_proof: proof.block_receipt_proof,
+        }
+    }
+
+    #[test]
+    fn test_validate_receipt_proof() {
+        let epoch_hashes =
+            vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        let epoch_receipts: Vec<_> = vec![
+            vec![receipt(21000)],
+            vec![receipt(21000), receipt(42000), receipt(63000)],
+        ]
+        .into_iter()
+        .map(|receipts| {
+            Arc::new(BlockReceipts {
+                receipts,
+                secondary_reward: U256::zero(),
+                tx_execution_error_messages: vec![],
+            })
+        })
+        .collect();
+        let root = compute_receipts_root(&epoch_receipts);
+
+        for (block_index, tx_index) in &[(0, 0), (1, 0), (1, 2)] {
+            let proof = proof_of(
+                &epoch_receipts,
+                &epoch_hashes,
+                *block_index,
+                *tx_index,
+            );
+            assert!(validate_receipt_proof(&proof, &epoch_hashes, root).is_ok());
+        }
+
+        let proof = proof_of(&epoch_receipts, &epoch_hashes, 1, 1);
+
+        // not the verified receipts root
+        assert!(validate_receipt_proof(
+            &proof,
+            &epoch_hashes,
+            H256::from_low_u64_be(3)
+        )
+        .is_err());
+
+        // not the blocks of the epoch
+        let other_hashes =
+            vec![H256::from_low_u64_be(1), H256::from_low_u64_be(3)];
+        assert!(validate_receipt_proof(&proof, &other_hashes, root).is_err());
+        assert!(
+            validate_receipt_proof(&proof, &epoch_hashes[..1], root).is_err()
+        );
+
+        // a forged receipt
+        let mut forged = proof.clone();
+        forged.receipt = receipt(50000);
+        assert!(validate_receipt_proof(&forged, &epoch_hashes, root).is_err());
+
+        // the proof of another receipt
+        let mut forged = proof.clone();
+        forged.key.tx_index_in_block = 2;
+        assert!(validate_receipt_proof(&forged, &epoch_hashes, root).is_err());
+
+        // an index out of the block
+        let mut forged = proof;
+        forged.key.tx_index_in_block = 3;
+        assert!(validate_receipt_proof(&forged, &epoch_hashes, root).is_err());
+    }
+}
@@ -39,6 +39,10 @@ build_msgid! {
     STATUS_PONG_V2 = 0x19
     GET_STORAGE_ROOTS = 0x1a
     STORAGE_ROOTS = 0x1b
+    GET_RECEIPT_PROOFS = 0x1c
+    RECEIPT_PROOFS = 0x1d
+    GET_LOG_PROOFS = 0x1e
+    LOG_PROOFS = 0x1f
 
     THROTTLED = 0xfe
     INVALID = 0xff
@@ -60,7 +64,6 @@ build_msg_impl! { BlockHeaders, msgid::BLOCK_HEADERS, "BlockHeaders", LIGHT_PROT
 build_msg_impl! { NewBlockHashes, msgid::NEW_BLOCK_HASHES, "NewBlockHashes", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { SendRawTx, msgid::SEND_RAW_TX, "SendRawTx", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetReceipts, msgid::GET_RECEIPTS, "GetReceipts", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
-build_msg_impl! { Receipts, msgid::RECEIPTS, "Receipts", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetTxs, msgid::GET_TXS, "GetTxs", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { Txs, msgid::TXS, "Txs", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetWitnessInfo, msgid::GET_WITNESS_INFO, "GetWitnessInfo", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
@@ -68,8 +71,50 @@ build_msg_impl! { WitnessInfo, msgid::WITNESS_INFO, "WitnessInfo", LIGHT_PROTO_V
 build_msg_impl! { GetBlooms, msgid::GET_BLOOMS, "GetBlooms", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { Blooms, msgid::BLOOMS, "Blooms", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetBlockTxs, msgid::GET_BLOCK_TXS, "GetBlockTxs", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
-build_msg_impl! { BlockTxs, msgid::BLOCK_TXS, "BlockTxs", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetTxInfos, msgid::GET_TX_INFOS, "GetTxInfos", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { TxInfos, msgid::TX_INFOS, "TxInfos", LIGHT_PROTO_V1, LIGHT_PROTO_V2 }
 build_msg_impl! { GetStorageRoots, msgid::GET_STORAGE_ROOTS, "GetStorageRoots", LIGHT_PROTO_V2, LIGHT_PROTO_V2 }
 build_msg_impl! { StorageRoots, msgid::STORAGE_ROOTS, "StorageRoots", LIGHT_PROTO_V2, LIGHT_PROTO_V2 }
+build_msg_impl! { GetReceiptProofs, msgid::GET_RECEIPT_PROOFS, "GetReceiptProofs", LIGHT_PROTO_V2, LIGHT_PROTO_V2 }
+build_msg_impl! { GetLogProofs, msgid::GET_LOG_PROOFS, "GetLogProofs", LIGHT_PROTO_V2, LIGHT_PROTO_V2 }
+
+// large messages that are compressed when sent to peers supporting it
+impl GetMaybeRequestId for Receipts {}
+mark_msg_version_bound!(Receipts, LIGHT_PROTO_V1, LIGHT_PROTO_V2);
+impl Message for Receipts {
+    fn is_compressible(&self) -> bool { true }
+
+    fn msg_id(&self) -> MsgId { msgid::RECEIPTS }
+
+    fn msg_name(&self) -> &'static str { "Receipts" }
+}
+
+impl GetMaybeRequestId for BlockTxs {}
+mark_msg_version_bound!(BlockTxs, LIGHT_PROTO_V1, LIGHT_PROTO_V2);
+impl Message for BlockTxs {
+    fn is_compressible(&self) -> bool { true }
+
+    fn msg_id(&self) -> MsgId { msgid::BLOCK_TXS }
+
+    fn msg_name(&self) -> &'static str { "BlockTxs" }
+}
+
+impl GetMaybeRequestId for ReceiptProofs {}
+mark_msg_version_bound!(ReceiptProofs, LIGHT_PROTO_V2, LIGHT_PROTO_V2);
+impl Message for ReceiptProofs {
+    fn is_compressible(&self) -> bool { true }
+
+    fn msg_id(&self) -> MsgId { msgid::RECEIPT_PROOFS }
+
+    fn msg_name(&self) -> &'static str { "ReceiptProofs" }
+}
+
+impl GetMaybeRequestId for LogProofs {}
+mark_msg_version_bound!(LogProofs, LIGHT_PROTO_V2, LIGHT_PROTO_V2);
+impl Message for LogProofs {
+    fn is_compressible(&self) -> bool { true }
+
+    fn msg_id(&self) -> MsgId { msgid::LOG_PROOFS }
+
+    fn msg_name(&self) -> &'static str { "LogProofs" }
+}
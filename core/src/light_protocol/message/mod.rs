@@ -10,11 +10,13 @@ pub use message::msgid;
 pub use protocol::{
     BlockHashes, BlockHeaders, BlockTxs, BlockTxsWithHash, BloomWithEpoch,
     Blooms, GetBlockHashesByEpoch, GetBlockHeaders, GetBlockTxs, GetBlooms,
-    GetReceipts, GetStateEntries, GetStateRoots, GetStorageRoots, GetTxInfos,
-    GetTxs, GetWitnessInfo, NewBlockHashes, Receipts, ReceiptsWithEpoch,
-    SendRawTx, StateEntries, StateEntryProof, StateEntryWithKey, StateKey,
-    StateRootWithEpoch, StateRoots, StatusPingDeprecatedV1, StatusPingV2,
-    StatusPongDeprecatedV1, StatusPongV2, StorageRootKey, StorageRootProof,
-    StorageRootWithKey, StorageRoots, TxInfo, TxInfos, Txs, WitnessInfo,
+    GetLogProofs, GetReceiptProofs, GetReceipts, GetStateEntries,
+    GetStateRoots, GetStorageRoots, GetTxInfos, GetTxs, GetWitnessInfo,
+    LogFilter, LogProofs, NewBlockHashes, ReceiptKey, ReceiptProofs,
+    ReceiptWithProof, Receipts, ReceiptsWithEpoch, SendRawTx, StateEntries,
+    StateEntryProof, StateEntryWithKey, StateKey, StateRootWithEpoch,
+    StateRoots, StatusPingDeprecatedV1, StatusPingV2, StatusPongDeprecatedV1,
+    StatusPongV2, StorageRootKey, StorageRootProof, StorageRootWithKey,
+    StorageRoots, TopicFilter, TxInfo, TxInfos, Txs, WitnessInfo,
     WitnessInfoWithHeight,
 };
@@ -9,8 +9,8 @@ use super::NodeType;
 use crate::message::RequestId;
 use cfx_storage::{NodeMerkleProof, StateProof, TrieProof};
 use primitives::{
-    BlockHeader, BlockReceipts, ChainIdParams, Receipt, SignedTransaction,
-    StateRoot, StorageRoot,
+    log_entry::LogEntry, BlockHeader, BlockReceipts, ChainIdParams, Receipt,
+    SignedTransaction, StateRoot, StorageRoot,
 };
 
 #[derive(Clone, Debug, Default, RlpEncodable, RlpDecodable)]
@@ -313,3 +313,161 @@ pub struct StorageRoots {
     pub request_id: RequestId,
     pub roots: Vec<StorageRootWithKey>,
 }
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    RlpEncodable,
+    RlpDecodable,
+)]
+pub struct ReceiptKey {
+    pub epoch: u64,
+    pub block_hash: H256,
+    pub tx_index_in_block: usize,
+}
+
+#[derive(Clone, Debug, Default, RlpEncodable, RlpDecodable)]
+pub struct GetReceiptProofs {
+    pub request_id: RequestId,
+    pub keys: Vec<ReceiptKey>,
+}
+
+// the receipts root of the epoch is validated against witness info retrieved
+// previously; `block_index_proof` and `receipt_proof` are checked against it
+// using `is_valid_receipt_inclusion_proof`
+#[derive(Clone, Debug, RlpEncodable, RlpDecodable)]
+pub struct ReceiptWithProof {
+    pub key: ReceiptKey,
+    pub receipt: Receipt,
+    pub block_index_in_epoch: usize,
+    pub num_blocks_in_epoch: usize,
+    pub num_txs_in_block: usize,
+    pub block_index_proof: TrieProof,
+    pub receipt_proof: TrieProof,
+}
+
+#[derive(Clone, Debug, Default, RlpEncodable, RlpDecodable)]
+pub struct ReceiptProofs {
+    pub request_id: RequestId,
+    pub proofs: Vec<ReceiptWithProof>,
+}
+
+// the accepted topics at a position of the logs; empty means any topic
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    RlpEncodable,
+    RlpDecodable,
+)]
+pub struct TopicFilter {
+    pub topics: Vec<H256>,
+}
+
+// the logs of `addresses` with `topics`, e.g. the transfers to an address;
+// an empty `addresses` matches any address
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    RlpEncodable,
+    RlpDecodable,
+)]
+pub struct LogFilter {
+    pub addresses: Vec<H160>,
+    pub topics: Vec<TopicFilter>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &LogEntry) -> bool {
+        let matches_address =
+            self.addresses.is_empty() || self.addresses.contains(&log.address);
+
+        let matches_topics =
+            self.topics.iter().enumerate().all(|(ii, filter)| {
+                filter.topics.is_empty()
+                    || log
+                        .topics
+                        .get(ii)
+                        .map_or(false, |topic| filter.topics.contains(topic))
+            });
+
+        matches_address && matches_topics
+    }
+}
+
+// request the receipts in `epochs` with logs matching `filter`
+#[derive(Clone, Debug, Default, RlpEncodable, RlpDecodable)]
+pub struct GetLogProofs {
+    pub request_id: RequestId,
+    pub epochs: Vec<u64>,
+    pub filter: LogFilter,
+}
+
+#[derive(Clone, Debug, Default, RlpEncodable, RlpDecodable)]
+pub struct LogProofs {
+    pub request_id: RequestId,
+    pub proofs: Vec<ReceiptWithProof>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogFilter, TopicFilter};
+    use cfx_types::{H160, H256};
+    use primitives::log_entry::LogEntry;
+
+    #[test]
+    fn test_log_filter_matches() {
+        let log = LogEntry {
+            address: H160::from_low_u64_be(1),
+            topics: vec![H256::from_low_u64_be(10), H256::from_low_u64_be(11)],
+            data: vec![],
+        };
+
+        assert!(LogFilter::default().matches(&log));
+
+        let filter = LogFilter {
+            addresses: vec![H160::from_low_u64_be(1), H160::from_low_u64_be(2)],
+            topics: vec![
+                TopicFilter { topics: vec![] },
+                TopicFilter {
+                    topics: vec![H256::from_low_u64_be(11)],
+                },
+            ],
+        };
+        assert!(filter.matches(&log));
+
+        // a different address
+        let mut other = filter.clone();
+        other.addresses = vec![H160::from_low_u64_be(2)];
+        assert!(!other.matches(&log));
+
+        // a different topic
+        let mut other = filter.clone();
+        other.topics[0].topics = vec![H256::from_low_u64_be(11)];
+        assert!(!other.matches(&log));
+
+        // more topics than the log has
+        let mut other = filter.clone();
+        other.topics.push(TopicFilter {
+            topics: vec![H256::from_low_u64_be(12)],
+        });
+        assert!(!other.matches(&log));
+    }
+}
@@ -23,5 +23,6 @@ use error::{handle as handle_error, ErrorKind};
 
 pub use error::Error;
 pub use handler::Handler;
+pub use message::{LogFilter, ReceiptKey, TopicFilter};
 pub use provider::Provider;
 pub use query_service::QueryService;
@@ -16,10 +16,13 @@ use crate::{
             BlockHeaders as GetBlockHeadersResponse,
             BlockTxs as GetBlockTxsResponse, BlockTxsWithHash, BloomWithEpoch,
             Blooms as GetBloomsResponse, GetBlockHashesByEpoch,
-            GetBlockHeaders, GetBlockTxs, GetBlooms, GetReceipts,
-            GetStateEntries, GetStateRoots, GetStorageRoots, GetTxInfos,
-            GetTxs, GetWitnessInfo, NewBlockHashes, NodeType,
-            Receipts as GetReceiptsResponse, ReceiptsWithEpoch, SendRawTx,
+            GetBlockHeaders, GetBlockTxs, GetBlooms, GetLogProofs,
+            GetReceiptProofs, GetReceipts, GetStateEntries, GetStateRoots,
+            GetStorageRoots, GetTxInfos, GetTxs, GetWitnessInfo, LogFilter,
+            LogProofs as GetLogProofsResponse, NewBlockHashes, NodeType,
+            ReceiptKey, ReceiptProofs as GetReceiptProofsResponse,
+            ReceiptWithProof, Receipts as GetReceiptsResponse,
+            ReceiptsWithEpoch, SendRawTx,
             StateEntries as GetStateEntriesResponse, StateEntryProof,
             StateEntryWithKey, StateKey, StateRootWithEpoch,
             StateRoots as GetStateRootsResponse, StatusPingDeprecatedV1,
@@ -50,10 +53,13 @@ use network::{
     NetworkService,
 };
 use parking_lot::RwLock;
-use primitives::{SignedTransaction, TransactionWithSignature};
+use primitives::{BlockReceipts, SignedTransaction, TransactionWithSignature};
 use rand::prelude::SliceRandom;
 use rlp::Rlp;
-use std::sync::{Arc, Weak};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
 use throttling::token_bucket::{ThrottleResult, TokenBucketManager};
 
 #[derive(DeriveMallocSizeOf)]
@@ -190,6 +196,8 @@ impl Provider {
             msgid::GET_BLOCK_TXS => self.on_get_block_txs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::GET_TX_INFOS => self.on_get_tx_infos(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             msgid::GET_STORAGE_ROOTS => self.on_get_storage_roots(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
+            msgid::GET_RECEIPT_PROOFS => self.on_get_receipt_proofs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
+            msgid::GET_LOG_PROOFS => self.on_get_log_proofs(io, peer, decode_rlp_and_check_deprecation(&rlp, min_supported_ver, protocol)?),
             _ => bail!(ErrorKind::UnknownMessage{id: msg_id}),
         }
     }
@@ -860,6 +868,200 @@ impl Provider {
         Ok(())
     }
 
+    /// The hashes and the receipts of the blocks in `epoch`, in the same
+    /// order.
+    fn epoch_receipts(
+        &self, epoch: u64,
+    ) -> Result<(Vec<H256>, Vec<Arc<BlockReceipts>>)> {
+        let epoch_hashes = self.ledger.block_hashes_in(epoch)?;
+        let epoch_receipts = self
+            .ledger
+            .receipts_of(epoch)?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        Ok((epoch_hashes, epoch_receipts))
+    }
+
+    fn receipt_with_proof(
+        key: ReceiptKey, epoch_hashes: &[H256],
+        epoch_receipts: &Vec<Arc<BlockReceipts>>,
+    ) -> Result<ReceiptWithProof>
+    {
+        let block_index_in_epoch =
+            match epoch_hashes.iter().position(|h| *h == key.block_hash) {
+                Some(index) => index,
+                None => {
+                    bail!(ErrorKind::UnableToProduceReceiptProof {
+                        reason: format!(
+                            "Unable to find {:?} in epoch {}",
+                            key.block_hash, key.epoch
+                        )
+                    });
+                }
+            };
+
+        let block_receipts = &epoch_receipts[block_index_in_epoch].receipts;
+        let receipt = match block_receipts.get(key.tx_index_in_block) {
+            Some(receipt) => receipt.clone(),
+            None => {
+                bail!(ErrorKind::UnableToProduceReceiptProof {
+                    reason: format!(
+                        "Block {:?} has {} receipts, receipt {} requested",
+                        key.block_hash,
+                        block_receipts.len(),
+                        key.tx_index_in_block
+                    )
+                });
+            }
+        };
+
+        let proof = compute_epoch_receipt_proof(
+            epoch_receipts,
+            block_index_in_epoch,
+            key.tx_index_in_block,
+        );
+
+        Ok(ReceiptWithProof {
+            num_txs_in_block: block_receipts.len(),
+            key,
+            receipt,
+            block_index_in_epoch,
+            num_blocks_in_epoch: epoch_hashes.len(),
+            block_index_proof: proof.block_index_proof,
+            receipt_proof: proof.block_receipt_proof,
+        })
+    }
+
+    fn on_get_receipt_proofs(
+        &self, io: &dyn NetworkContext, peer: &NodeId, req: GetReceiptProofs,
+    ) -> Result<()> {
+        debug!("on_get_receipt_proofs req={:?}", req);
+        self.throttle(peer, &req)?;
+        let request_id = req.request_id;
+
+        // load the receipts of each epoch once
+        let mut keys_by_epoch = BTreeMap::new();
+
+        for key in req.keys.into_iter().take(MAX_ITEMS_TO_SEND) {
+            keys_by_epoch
+                .entry(key.epoch)
+                .or_insert_with(Vec::new)
+                .push(key);
+        }
+
+        let mut proofs = vec![];
+        let mut errors = vec![];
+
+        for (epoch, keys) in keys_by_epoch {
+            let (epoch_hashes, epoch_receipts) =
+                match self.epoch_receipts(epoch) {
+                    Ok(receipts) => receipts,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+
+            for key in keys {
+                match Self::receipt_with_proof(
+                    key,
+                    &epoch_hashes,
+                    &epoch_receipts,
+                ) {
+                    Ok(proof) => proofs.push(proof),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            debug!(
+                "Errors while serving GetReceiptProofs request: {:?}",
+                errors
+            );
+        }
+
+        let msg: Box<dyn Message> =
+            Box::new(GetReceiptProofsResponse { request_id, proofs });
+
+        msg.send(io, peer)?;
+        Ok(())
+    }
+
+    /// The receipts in `epoch` with logs matching `filter`, at most
+    /// `max_items` of them.
+    fn log_proofs_in(
+        &self, epoch: u64, filter: &LogFilter, max_items: usize,
+    ) -> Result<Vec<ReceiptWithProof>> {
+        let (epoch_hashes, epoch_receipts) = self.epoch_receipts(epoch)?;
+        let mut proofs = vec![];
+
+        for (block_hash, block_receipts) in
+            epoch_hashes.iter().zip(epoch_receipts.iter())
+        {
+            for (tx_index_in_block, receipt) in
+                block_receipts.receipts.iter().enumerate()
+            {
+                if proofs.len() == max_items {
+                    return Ok(proofs);
+                }
+
+                if receipt.logs.iter().any(|log| filter.matches(log)) {
+                    let key = ReceiptKey {
+                        epoch,
+                        block_hash: *block_hash,
+                        tx_index_in_block,
+                    };
+
+                    proofs.push(Self::receipt_with_proof(
+                        key,
+                        &epoch_hashes,
+                        &epoch_receipts,
+                    )?);
+                }
+            }
+        }
+
+        Ok(proofs)
+    }
+
+    fn on_get_log_proofs(
+        &self, io: &dyn NetworkContext, peer: &NodeId, req: GetLogProofs,
+    ) -> Result<()> {
+        debug!("on_get_log_proofs req={:?}", req);
+        self.throttle(peer, &req)?;
+        let request_id = req.request_id;
+
+        // NOTE: if the limit is reached, the remaining receipts of the last
+        // epoch are not sent; the requester should ask for them separately
+        let mut proofs = vec![];
+        let mut errors = vec![];
+
+        for epoch in req.epochs.into_iter().take(MAX_EPOCHS_TO_SEND) {
+            if proofs.len() == MAX_ITEMS_TO_SEND {
+                break;
+            }
+
+            let max_items = MAX_ITEMS_TO_SEND - proofs.len();
+
+            match self.log_proofs_in(epoch, &req.filter, max_items) {
+                Ok(epoch_proofs) => proofs.extend(epoch_proofs),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if !errors.is_empty() {
+            debug!("Errors while serving GetLogProofs request: {:?}", errors);
+        }
+
+        let msg: Box<dyn Message> =
+            Box::new(GetLogProofsResponse { request_id, proofs });
+
+        msg.send(io, peer)?;
+        Ok(())
+    }
+
     fn broadcast(
         &self, io: &dyn NetworkContext, mut peers: Vec<NodeId>,
         msg: &dyn Message,
@@ -6,8 +6,8 @@ use crate::{
     consensus::SharedConsensusGraph,
     light_protocol::{
         common::{FullPeerFilter, LedgerInfo},
-        handler::sync::TxInfoValidated,
-        message::msgid,
+        handler::sync::{LogProofsKey, LogProofsValidated, TxInfoValidated},
+        message::{msgid, LogFilter, ReceiptKey},
         Error, ErrorKind, Handler as LightHandler, LIGHT_PROTOCOL_ID,
         LIGHT_PROTOCOL_VERSION,
     },
@@ -82,6 +82,9 @@ pub struct QueryService {
 
     // shared network service
     network: Arc<NetworkService>,
+
+    // shared synchronization graph
+    graph: Arc<SynchronizationGraph>,
 }
 
 impl QueryService {
@@ -93,7 +96,7 @@ impl QueryService {
     {
         let handler = Arc::new(LightHandler::new(
             consensus.clone(),
-            graph,
+            graph.clone(),
             throttling_config_file,
             notifications,
         ));
@@ -105,9 +108,18 @@ impl QueryService {
             handler,
             ledger,
             network,
+            graph,
         }
     }
 
+    /// Stop receiving headers and responses from peers.
+    pub fn stop_network(&self) { self.network.stop(); }
+
+    /// Whether there are received headers not processed by consensus yet.
+    pub fn is_importing_blocks(&self) -> bool {
+        self.graph.is_consensus_worker_busy()
+    }
+
     pub fn register(&self) -> Result<(), String> {
         self.network
             .register_protocol(
@@ -227,6 +239,32 @@ impl QueryService {
         .await
     }
 
+    async fn retrieve_receipt_proof(
+        &self, key: ReceiptKey,
+    ) -> Result<Receipt, Error> {
+        trace!("retrieve_receipt_proof key = {:?}", key);
+
+        with_timeout(
+            *MAX_POLL_TIME,
+            format!("Timeout while retrieving receipt proof for {:?}", key),
+            self.with_io(|io| self.handler.receipt_proofs.request_now(io, key)),
+        )
+        .await
+    }
+
+    async fn retrieve_log_proofs(
+        &self, key: LogProofsKey,
+    ) -> Result<LogProofsValidated, Error> {
+        trace!("retrieve_log_proofs key = {:?}", key);
+
+        with_timeout(
+            *MAX_POLL_TIME,
+            format!("Timeout while retrieving log proofs for {:?}", key),
+            self.with_io(|io| self.handler.log_proofs.request_now(io, key)),
+        )
+        .await
+    }
+
     fn account_key(address: &H160) -> Vec<u8> {
         StorageKey::new_account_key(&address).to_key_bytes()
     }
@@ -336,6 +374,68 @@ impl QueryService {
         Ok((tx, receipt, address, epoch, root, prior_gas_used))
     }
 
+    /// Get the `tx_index_in_block`-th receipt of block `block_hash` in
+    /// `epoch`, verified against the receipts root of the epoch.
+    pub async fn get_receipt(
+        &self, epoch: u64, block_hash: H256, tx_index_in_block: usize,
+    ) -> Result<Receipt, Error> {
+        debug!(
+            "get_receipt epoch={} block_hash={:?} tx_index_in_block={}",
+            epoch, block_hash, tx_index_in_block
+        );
+
+        self.retrieve_receipt_proof(ReceiptKey {
+            epoch,
+            block_hash,
+            tx_index_in_block,
+        })
+        .await
+    }
+
+    /// Get the receipts in `epochs` with logs matching `filter`, e.g. the
+    /// transfers to an address. Each receipt is verified against the receipts
+    /// root of its epoch, but the peers may leave out matching receipts.
+    pub async fn get_log_receipts(
+        &self, epochs: Vec<u64>, filter: LogFilter,
+    ) -> Result<Vec<(ReceiptKey, Receipt)>, Error> {
+        debug!("get_log_receipts epochs={:?} filter={:?}", epochs, filter);
+
+        let fut = epochs.into_iter().map(|epoch| {
+            self.retrieve_log_proofs(LogProofsKey {
+                epoch,
+                filter: filter.clone(),
+            })
+        });
+
+        let receipts = future::try_join_all(fut).await?;
+        Ok(receipts.into_iter().flatten().collect())
+    }
+
+    /// Get the transactions of each block in `epoch`, verified against the
+    /// transaction roots of the block headers.
+    pub async fn get_epoch_txs(
+        &self, epoch: u64,
+    ) -> Result<Vec<(H256, Vec<SignedTransaction>)>, Error> {
+        debug!("get_epoch_txs epoch = {}", epoch);
+
+        let hashes = self.ledger.block_hashes_in(epoch)?;
+
+        let fut = hashes.into_iter().map(|hash| async move {
+            with_timeout(
+                *MAX_POLL_TIME,
+                format!(
+                    "Timeout while retrieving block txs for block {}",
+                    hash
+                ),
+                self.handler.block_txs.request(hash),
+            )
+            .await
+            .map(|block_txs| (hash, block_txs))
+        });
+
+        future::try_join_all(fut).await
+    }
+
     /// Relay raw transaction to all peers.
     // TODO(thegaram): consider returning TxStatus instead of bool,
     // e.g. Failed, Sent/Pending, Confirmed, etc.
@@ -4,7 +4,7 @@
 
 use super::builtin::Builtin;
 use crate::{
-    builtin::{builtin_factory, Linear},
+    builtin::{builtin_factory, BuiltinRegistry, Linear, Precompile},
     vm::Spec,
 };
 use cfx_types::{Address, H256, U256};
@@ -65,21 +65,52 @@ pub type SpecCreationRules = dyn Fn(&mut Spec, BlockNumber) + Sync + Send;
 
 pub struct Machine {
     params: CommonParams,
-    builtins: Arc<BTreeMap<Address, Builtin>>,
+    builtins: Arc<BuiltinRegistry>,
+    /// The specs keyed by the epoch height from which they are active.
+    specs: BTreeMap<u64, Spec>,
     spec_rules: Option<Box<SpecCreationRules>>,
 }
 
 impl Machine {
+    /// Return the builtin at `address` which is active at `epoch_height`.
     pub fn builtin(
-        &self, address: &Address, block_number: BlockNumber,
-    ) -> Option<&Builtin> {
-        self.builtins.get(address).and_then(|b| {
-            if b.is_active(block_number) {
-                Some(b)
-            } else {
-                None
-            }
-        })
+        &self, address: &Address, epoch_height: u64,
+    ) -> Option<&dyn Precompile> {
+        self.builtins.get(address, epoch_height)
+    }
+
+    /// Register a builtin, which must be done before the machine is shared.
+    pub fn register_builtin(
+        &mut self, address: Address, builtin: Box<dyn Precompile>,
+    ) {
+        Arc::get_mut(&mut self.builtins)
+            .expect("builtins are not shared")
+            .register(address, builtin);
+    }
+
+    /// Activate `spec` from `epoch_height`. A protocol upgrade which reprices
+    /// operations or storage is expressed as a new entry of the schedule.
+    pub fn set_spec_at(&mut self, epoch_height: u64, spec: Spec) {
+        self.specs.insert(epoch_height, spec);
+    }
+
+    /// Apply `update` to the spec active at `epoch_height` and to all the
+    /// specs scheduled after it, so that a parameter change from
+    /// `epoch_height` is kept by the later activations.
+    pub fn update_spec_from<F>(&mut self, epoch_height: u64, update: F)
+    where F: Fn(&mut Spec) {
+        if !self.specs.contains_key(&epoch_height) {
+            let spec = self
+                .specs
+                .range(..=epoch_height)
+                .next_back()
+                .map(|(_, spec)| spec.clone())
+                .unwrap_or_else(Spec::new_spec);
+            self.specs.insert(epoch_height, spec);
+        }
+        for (_, spec) in self.specs.range_mut(epoch_height..) {
+            update(spec);
+        }
     }
 
     /// Attach special rules to the creation of spec.
@@ -90,63 +121,112 @@ impl Machine {
     /// Get the general parameters of the chain.
     pub fn params(&self) -> &CommonParams { &self.params }
 
-    pub fn spec(&self, number: BlockNumber) -> Spec {
-        let mut spec = Spec::new_spec();
+    /// Return the spec of the latest activation at or before
+    /// `epoch_height`.
+    pub fn spec(&self, epoch_height: u64) -> Spec {
+        let mut spec = self
+            .specs
+            .range(..=epoch_height)
+            .next_back()
+            .map(|(_, spec)| spec.clone())
+            .unwrap_or_else(Spec::new_spec);
         if let Some(ref rules) = self.spec_rules {
-            (rules)(&mut spec, number)
+            (rules)(&mut spec, epoch_height)
         }
         spec
     }
 
     /// Builtin-contracts for the chain..
-    pub fn builtins(&self) -> &BTreeMap<Address, Builtin> { &*self.builtins }
+    pub fn builtins(&self) -> &BuiltinRegistry { &*self.builtins }
 }
 
 pub fn new_machine(chain_id: ChainIdParams) -> Machine {
     Machine {
         params: CommonParams::common_params(chain_id),
-        builtins: Arc::new(BTreeMap::new()),
+        builtins: Arc::new(BuiltinRegistry::new()),
+        specs: BTreeMap::new(),
         spec_rules: None,
     }
 }
 
 pub fn new_machine_with_builtin(chain_id: ChainIdParams) -> Machine {
-    let mut btree = BTreeMap::new();
-    btree.insert(
+    let mut builtins = BuiltinRegistry::new();
+    builtins.register(
         Address::from(H256::from_low_u64_be(1)),
-        Builtin::new(
+        Box::new(Builtin::new(
             Box::new(Linear::new(3000, 0)),
             builtin_factory("ecrecover"),
             0,
-        ),
+        )),
     );
-    btree.insert(
+    builtins.register(
         Address::from(H256::from_low_u64_be(2)),
-        Builtin::new(
+        Box::new(Builtin::new(
             Box::new(Linear::new(60, 12)),
             builtin_factory("sha256"),
             0,
-        ),
+        )),
     );
-    btree.insert(
+    builtins.register(
         Address::from(H256::from_low_u64_be(3)),
-        Builtin::new(
+        Box::new(Builtin::new(
             Box::new(Linear::new(600, 120)),
             builtin_factory("ripemd160"),
             0,
-        ),
+        )),
     );
-    btree.insert(
+    builtins.register(
         Address::from(H256::from_low_u64_be(4)),
-        Builtin::new(
+        Box::new(Builtin::new(
             Box::new(Linear::new(15, 3)),
             builtin_factory("identity"),
             0,
-        ),
+        )),
     );
     Machine {
         params: CommonParams::common_params(chain_id),
-        builtins: Arc::new(btree),
+        builtins: Arc::new(builtins),
+        specs: BTreeMap::new(),
         spec_rules: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::new_machine;
+    use crate::vm::Spec;
+
+    #[test]
+    fn test_spec_schedule() {
+        let mut machine = new_machine(Default::default());
+        let mut repriced = Spec::new_spec();
+        repriced.sload_gas = 800;
+        repriced.collateral_per_byte = repriced.collateral_per_byte * 2;
+        machine.set_spec_at(100, repriced.clone());
+
+        let genesis = machine.spec(0);
+        assert_eq!(genesis.sload_gas, Spec::new_spec().sload_gas);
+        assert_eq!(machine.spec(99).sload_gas, genesis.sload_gas);
+        assert_eq!(machine.spec(100).sload_gas, 800);
+        assert_eq!(
+            machine.spec(1000).collateral_per_byte,
+            repriced.collateral_per_byte
+        );
+    }
+
+    #[test]
+    fn test_update_spec_from() {
+        let mut machine = new_machine(Default::default());
+        let mut repriced = Spec::new_spec();
+        repriced.sload_gas = 800;
+        machine.set_spec_at(100, repriced);
+        machine.update_spec_from(50, |spec| spec.have_suicide = false);
+
+        assert!(machine.spec(49).have_suicide);
+        assert!(!machine.spec(50).have_suicide);
+        assert_eq!(machine.spec(50).sload_gas, Spec::new_spec().sload_gas);
+        // The later activation keeps the update.
+        assert!(!machine.spec(100).have_suicide);
+        assert_eq!(machine.spec(100).sload_gas, 800);
+    }
+}
@@ -59,6 +59,9 @@ pub trait Message:
 {
     // If true, message may be throttled when sent to remote peer.
     fn is_size_sensitive(&self) -> bool { false }
+    // If true, message may be compressed when sent to remote peer, which is
+    // for messages that are large and compress well.
+    fn is_compressible(&self) -> bool { false }
     fn msg_id(&self) -> MsgId;
     fn push_msg_id_leb128_encoding(&self, buffer: &mut Vec<u8>) {
         let msg_id = self.msg_id();
@@ -109,6 +112,7 @@ pub trait Message:
             self.version_introduced(),
             self.version_valid_till(),
             self.priority(),
+            self.is_compressible(),
         ) {
             debug!("Error sending message: {:?}", e);
             return Err(e);
@@ -0,0 +1,245 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{CacheBuilder, ProofOfWorkProblem, ProofOfWorkSolution};
+use crate::hash::keccak;
+use cfx_types::{H256, U256};
+use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
+use std::str::FromStr;
+
+/// The proof-of-work algorithm used to seal and verify the blocks.
+pub trait PowAlgorithm: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Computes the PoW hash of `nonce` for the block with the problem hash
+    /// `block_hash` at `block_height`.
+    fn compute(
+        &self, nonce: &U256, block_hash: &H256, block_height: u64,
+    ) -> H256;
+
+    /// Checks whether `solution` solves `problem`.
+    fn validate(
+        &self, problem: &ProofOfWorkProblem, solution: &ProofOfWorkSolution,
+    ) -> bool {
+        let nonce = solution.nonce;
+        let hash =
+            self.compute(&nonce, &problem.block_hash, problem.block_height);
+        ProofOfWorkProblem::validate_hash_against_boundary(
+            &hash,
+            &nonce,
+            &problem.boundary,
+        )
+    }
+}
+
+/// The algorithms known by the node, which are selected in the configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, DeriveMallocSizeOf)]
+pub enum PowAlgorithmType {
+    Octopus,
+    Dev,
+}
+
+impl PowAlgorithmType {
+    pub fn build(&self) -> Box<dyn PowAlgorithm> {
+        match self {
+            PowAlgorithmType::Octopus => Box::new(Octopus::new()),
+            PowAlgorithmType::Dev => Box::new(DevPow),
+        }
+    }
+}
+
+impl FromStr for PowAlgorithmType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let algorithm = match s {
+            "octopus" => Self::Octopus,
+            "dev" => Self::Dev,
+            _ => return Err("invalid pow algorithm".into()),
+        };
+        Ok(algorithm)
+    }
+}
+
+/// The algorithm of the blocks since `activation_height`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, DeriveMallocSizeOf)]
+pub struct PowAlgorithmTransition {
+    pub activation_height: u64,
+    pub algorithm: PowAlgorithmType,
+}
+
+/// The algorithms of the chain keyed by the block height.
+#[derive(Debug, Clone, PartialEq, Eq, DeriveMallocSizeOf)]
+pub struct PowAlgorithmSpec {
+    /// The algorithm since genesis.
+    pub algorithm: PowAlgorithmType,
+    /// The transitions of the algorithm, ordered by their activation heights.
+    pub schedule: Vec<PowAlgorithmTransition>,
+}
+
+impl PowAlgorithmSpec {
+    pub fn new(algorithm: PowAlgorithmType) -> Self {
+        PowAlgorithmSpec {
+            algorithm,
+            schedule: Vec::new(),
+        }
+    }
+
+    /// Check that the activation heights are positive and strictly
+    /// increasing.
+    pub fn with_schedule(
+        algorithm: PowAlgorithmType, schedule: Vec<PowAlgorithmTransition>,
+    ) -> Result<Self, String> {
+        let mut last_height = 0;
+        for transition in &schedule {
+            if transition.activation_height <= last_height {
+                return Err(format!(
+                    "The pow algorithm transition at height {} is not after height {}",
+                    transition.activation_height, last_height
+                ));
+            }
+            last_height = transition.activation_height;
+        }
+        Ok(PowAlgorithmSpec {
+            algorithm,
+            schedule,
+        })
+    }
+
+    pub fn algorithm_at(&self, block_height: u64) -> PowAlgorithmType {
+        self.schedule
+            .iter()
+            .rev()
+            .find(|transition| transition.activation_height <= block_height)
+            .map_or(self.algorithm, |transition| transition.algorithm)
+    }
+
+    /// Whether `algorithm` is used at any height of the chain.
+    pub fn uses(&self, algorithm: PowAlgorithmType) -> bool {
+        self.algorithm == algorithm
+            || self
+                .schedule
+                .iter()
+                .any(|transition| transition.algorithm == algorithm)
+    }
+}
+
+/// The memory-hard algorithm of the main network.
+pub struct Octopus {
+    cache_builder: CacheBuilder,
+}
+
+impl Octopus {
+    pub fn new() -> Self {
+        Octopus {
+            cache_builder: CacheBuilder::new(),
+        }
+    }
+}
+
+impl PowAlgorithm for Octopus {
+    fn name(&self) -> &'static str { "octopus" }
+
+    fn compute(
+        &self, nonce: &U256, block_hash: &H256, block_height: u64,
+    ) -> H256 {
+        let light = self.cache_builder.light(block_height);
+        light
+            .compute(block_hash.as_fixed_bytes(), nonce.low_u64())
+            .into()
+    }
+}
+
+/// A cheap algorithm based on keccak for the test and private networks.
+pub struct DevPow;
+
+impl PowAlgorithm for DevPow {
+    fn name(&self) -> &'static str { "dev" }
+
+    fn compute(
+        &self, nonce: &U256, block_hash: &H256, _block_height: u64,
+    ) -> H256 {
+        let mut buf = [0u8; 64];
+        for i in 0..32 {
+            buf[i] = block_hash[i];
+        }
+        nonce.to_little_endian(&mut buf[32..64]);
+        let intermediate = keccak(&buf[..]);
+        let mut tmp = [0u8; 32];
+        for i in 0..32 {
+            tmp[i] = intermediate[i] ^ block_hash[i]
+        }
+        keccak(tmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DevPow, PowAlgorithm, PowAlgorithmSpec, PowAlgorithmTransition,
+        PowAlgorithmType,
+    };
+    use crate::pow::{ProofOfWorkProblem, ProofOfWorkSolution};
+    use cfx_types::{H256, U256};
+
+    #[test]
+    fn test_algorithm_type() {
+        assert_eq!("octopus".parse(), Ok(PowAlgorithmType::Octopus));
+        assert_eq!("dev".parse(), Ok(PowAlgorithmType::Dev));
+        assert!("ethash".parse::<PowAlgorithmType>().is_err());
+        assert_eq!(PowAlgorithmType::Dev.build().name(), "dev");
+    }
+
+    #[test]
+    fn test_algorithm_spec() {
+        let transition =
+            |activation_height, algorithm| PowAlgorithmTransition {
+                activation_height,
+                algorithm,
+            };
+        let spec = PowAlgorithmSpec::with_schedule(
+            PowAlgorithmType::Dev,
+            vec![transition(100, PowAlgorithmType::Octopus)],
+        )
+        .unwrap();
+        assert_eq!(spec.algorithm_at(0), PowAlgorithmType::Dev);
+        assert_eq!(spec.algorithm_at(99), PowAlgorithmType::Dev);
+        assert_eq!(spec.algorithm_at(100), PowAlgorithmType::Octopus);
+        assert!(spec.uses(PowAlgorithmType::Dev));
+        assert!(spec.uses(PowAlgorithmType::Octopus));
+        assert!(!PowAlgorithmSpec::new(PowAlgorithmType::Octopus)
+            .uses(PowAlgorithmType::Dev));
+
+        assert!(PowAlgorithmSpec::with_schedule(
+            PowAlgorithmType::Dev,
+            vec![transition(0, PowAlgorithmType::Octopus)],
+        )
+        .is_err());
+        assert!(PowAlgorithmSpec::with_schedule(
+            PowAlgorithmType::Dev,
+            vec![
+                transition(100, PowAlgorithmType::Octopus),
+                transition(100, PowAlgorithmType::Dev),
+            ],
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_dev_validate() {
+        let block_hash = H256::from_low_u64_be(1);
+        let solution = ProofOfWorkSolution {
+            nonce: U256::from(7),
+        };
+        let hash = DevPow.compute(&solution.nonce, &block_hash, 1);
+        // The hash does not depend on the height.
+        assert_eq!(hash, DevPow.compute(&solution.nonce, &block_hash, 2));
+
+        // Any nonce solves the problem of difficulty 1.
+        let problem = ProofOfWorkProblem::new(1, block_hash, 1.into());
+        assert!(DevPow.validate(&problem, &solution));
+        let problem = ProofOfWorkProblem::new(1, block_hash, U256::MAX);
+        assert!(!DevPow.validate(&problem, &solution));
+    }
+}
@@ -2,14 +2,21 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
+mod algorithm;
 mod cache;
 mod compute;
 mod keccak;
 mod seed_compute;
 mod shared;
 
-pub use self::{cache::CacheBuilder, shared::POW_STAGE_LENGTH};
-use crate::hash::keccak as keccak_hash;
+pub use self::{
+    algorithm::{
+        DevPow, Octopus, PowAlgorithm, PowAlgorithmSpec,
+        PowAlgorithmTransition, PowAlgorithmType,
+    },
+    cache::CacheBuilder,
+    shared::POW_STAGE_LENGTH,
+};
 
 use crate::block_data_manager::BlockDataManager;
 use cfx_parameters::pow::*;
@@ -87,6 +94,10 @@ impl FromStr for MiningType {
 pub struct ProofOfWorkConfig {
     pub test_mode: bool,
     pub use_octopus_in_test_mode: bool,
+    /// The algorithms to seal and verify the blocks by their heights. It's
+    /// Octopus unless the node is in test mode without
+    /// `use_octopus_in_test_mode`.
+    pub pow_spec: PowAlgorithmSpec,
     pub mining_type: MiningType,
     pub initial_difficulty: u64,
     pub block_generation_period: u64,
@@ -94,6 +105,15 @@ pub struct ProofOfWorkConfig {
     pub stratum_listen_addr: String,
     pub stratum_port: u16,
     pub stratum_secret: Option<H256>,
+    /// The initial share difficulty of a stratum worker. If it's `None`, the
+    /// solutions are only accepted at the block difficulty.
+    pub stratum_share_difficulty: Option<u64>,
+    pub stratum_min_share_difficulty: u64,
+    /// The expected interval between the shares of a stratum worker in
+    /// milliseconds. If it's `None`, the share difficulty is fixed.
+    pub stratum_share_interval_ms: Option<u64>,
+    /// The number of threads of the CPU miner.
+    pub cpu_miner_threads: usize,
 }
 
 impl ProofOfWorkConfig {
@@ -103,10 +123,17 @@ impl ProofOfWorkConfig {
         stratum_port: u16, stratum_secret: Option<H256>,
     ) -> Self
     {
+        let pow_algorithm = if !test_mode || use_octopus_in_test_mode {
+            PowAlgorithmType::Octopus
+        } else {
+            PowAlgorithmType::Dev
+        };
+        let pow_spec = PowAlgorithmSpec::new(pow_algorithm);
         if test_mode {
             ProofOfWorkConfig {
                 test_mode,
                 use_octopus_in_test_mode,
+                pow_spec: pow_spec.clone(),
                 mining_type: mining_type.parse().expect("Invalid mining type"),
                 initial_difficulty: initial_difficulty.unwrap_or(4),
                 block_generation_period: 1000000,
@@ -114,11 +141,16 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_share_difficulty: None,
+                stratum_min_share_difficulty: 1,
+                stratum_share_interval_ms: None,
+                cpu_miner_threads: 1,
             }
         } else {
             ProofOfWorkConfig {
                 test_mode,
                 use_octopus_in_test_mode,
+                pow_spec,
                 mining_type: mining_type.parse().expect("Invalid mining type"),
                 initial_difficulty: INITIAL_DIFFICULTY,
                 block_generation_period: TARGET_AVERAGE_BLOCK_GENERATION_PERIOD,
@@ -127,14 +159,14 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_share_difficulty: None,
+                stratum_min_share_difficulty: 1,
+                stratum_share_interval_ms: None,
+                cpu_miner_threads: 1,
             }
         }
     }
 
-    pub fn use_octopus(&self) -> bool {
-        !self.test_mode || self.use_octopus_in_test_mode
-    }
-
     pub fn use_stratum(&self) -> bool {
         matches!(self.mining_type, MiningType::Stratum)
     }
@@ -254,39 +286,48 @@ pub fn compute_inv_x_times_2_pow_256_floor(x: &U256) -> U256 {
 }
 
 pub struct PowComputer {
-    use_octopus: bool,
-    cache_builder: CacheBuilder,
+    /// The algorithms with their activation heights, ordered by the heights
+    /// from 0.
+    algorithms: Vec<(u64, Box<dyn PowAlgorithm>)>,
 }
 
 impl PowComputer {
-    pub fn new(use_octopus: bool) -> Self {
+    pub fn new(algorithm_type: PowAlgorithmType) -> Self {
+        Self::with_algorithm(algorithm_type.build())
+    }
+
+    pub fn from_spec(spec: &PowAlgorithmSpec) -> Self {
+        let mut algorithms = vec![(0, spec.algorithm.build())];
+        for transition in &spec.schedule {
+            algorithms.push((
+                transition.activation_height,
+                transition.algorithm.build(),
+            ));
+        }
+        PowComputer { algorithms }
+    }
+
+    pub fn with_algorithm(algorithm: Box<dyn PowAlgorithm>) -> Self {
         PowComputer {
-            use_octopus,
-            cache_builder: CacheBuilder::new(),
+            algorithms: vec![(0, algorithm)],
         }
     }
 
+    fn algorithm_at(&self, block_height: u64) -> &dyn PowAlgorithm {
+        let (_, algorithm) = self
+            .algorithms
+            .iter()
+            .rev()
+            .find(|(activation_height, _)| *activation_height <= block_height)
+            .expect("the first algorithm is activated at height 0");
+        algorithm.as_ref()
+    }
+
     pub fn compute(
         &self, nonce: &U256, block_hash: &H256, block_height: u64,
     ) -> H256 {
-        if !self.use_octopus {
-            let mut buf = [0u8; 64];
-            for i in 0..32 {
-                buf[i] = block_hash[i];
-            }
-            nonce.to_little_endian(&mut buf[32..64]);
-            let intermediate = keccak_hash(&buf[..]);
-            let mut tmp = [0u8; 32];
-            for i in 0..32 {
-                tmp[i] = intermediate[i] ^ block_hash[i]
-            }
-            keccak_hash(tmp)
-        } else {
-            let light = self.cache_builder.light(block_height);
-            light
-                .compute(block_hash.as_fixed_bytes(), nonce.low_u64())
-                .into()
-        }
+        self.algorithm_at(block_height)
+            .compute(nonce, block_hash, block_height)
     }
 }
 
@@ -295,13 +336,8 @@ pub fn validate(
     solution: &ProofOfWorkSolution,
 ) -> bool
 {
-    let nonce = solution.nonce;
-    let hash = pow.compute(&nonce, &problem.block_hash, problem.block_height);
-    ProofOfWorkProblem::validate_hash_against_boundary(
-        &hash,
-        &nonce,
-        &problem.boundary,
-    )
+    pow.algorithm_at(problem.block_height)
+        .validate(problem, solution)
 }
 
 /// This function computes the target difficulty of the next period
@@ -461,7 +497,7 @@ impl TargetDifficultyManager {
 
 #[test]
 fn test_octopus() {
-    let pow = PowComputer::new(true);
+    let pow = PowComputer::new(PowAlgorithmType::Octopus);
 
     let block_hash =
         "4d99d0b41c7eb0dd1a801c35aae2df28ae6b53bc7743f0818a34b6ec97f5b4ae"
@@ -8,7 +8,7 @@ use crate::{
     state::{AccountEntryProtectedMethods, StateGeneric, Substate},
 };
 use cfx_internal_common::debug::ComputeEpochDebugRecord;
-use cfx_parameters::staking::BYTES_PER_STORAGE_KEY;
+use cfx_parameters::staking::{BYTES_PER_STORAGE_KEY, COLLATERAL_PER_BYTE};
 use cfx_statedb::{Result as DbResult, StateDbExt, StateDbGeneric};
 use cfx_storage::StorageStateTrait;
 use cfx_types::{address_util::AddressUtil, Address, H256, U256};
@@ -18,7 +18,10 @@ use primitives::{
     SponsorInfo, StorageKey, StorageLayout, StorageValue, VoteStakeInfo,
     VoteStakeList,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 lazy_static! {
     static ref SPONSOR_ADDRESS_STORAGE_KEY: Vec<u8> =
@@ -70,6 +73,10 @@ pub struct OverlayAccount {
     // This is the number of tokens used as collateral for storage, which will
     // be returned to balance if the storage is released.
     collateral_for_storage: U256,
+    // This is the number of bytes of storage collateralized by
+    // `collateral_for_storage`, which are refunded at the average price they
+    // were charged at.
+    collateralized_bytes: u64,
     // This is the accumulated interest return.
     accumulated_interest_return: U256,
     // This is the list of deposit info, sorted in increasing order of
@@ -105,6 +112,13 @@ pub struct OverlayAccount {
 impl OverlayAccount {
     /// Create an OverlayAccount from loaded account.
     pub fn from_loaded(address: &Address, account: Account) -> Self {
+        // The collateral is charged at the initial price per byte unless the
+        // number of bytes is kept.
+        let collateralized_bytes =
+            account.collateralized_bytes.unwrap_or_else(|| {
+                (account.collateral_for_storage / *COLLATERAL_PER_BYTE)
+                    .low_u64()
+            });
         let overlay_account = OverlayAccount {
             address: address.clone(),
             balance: account.balance,
@@ -118,6 +132,7 @@ impl OverlayAccount {
             storage_layout_change: None,
             staking_balance: account.staking_balance,
             collateral_for_storage: account.collateral_for_storage,
+            collateralized_bytes,
             accumulated_interest_return: account.accumulated_interest_return,
             deposit_list: None,
             vote_stake_list: None,
@@ -153,6 +168,7 @@ impl OverlayAccount {
             },
             staking_balance: 0.into(),
             collateral_for_storage: 0.into(),
+            collateralized_bytes: 0,
             accumulated_interest_return: 0.into(),
             deposit_list: None,
             vote_stake_list: None,
@@ -198,6 +214,7 @@ impl OverlayAccount {
             storage_layout_change: storage_layout,
             staking_balance: 0.into(),
             collateral_for_storage: 0.into(),
+            collateralized_bytes: 0,
             accumulated_interest_return: 0.into(),
             deposit_list: None,
             vote_stake_list: None,
@@ -215,6 +232,14 @@ impl OverlayAccount {
         account.code_hash = self.code_hash;
         account.staking_balance = self.staking_balance;
         account.collateral_for_storage = self.collateral_for_storage;
+        account.collateralized_bytes = if U256::from(self.collateralized_bytes)
+            * *COLLATERAL_PER_BYTE
+            == self.collateral_for_storage
+        {
+            None
+        } else {
+            Some(self.collateralized_bytes)
+        };
         account.accumulated_interest_return = self.accumulated_interest_return;
         account.admin = self.admin;
         account.sponsor_info = self.sponsor_info.clone();
@@ -331,6 +356,8 @@ impl OverlayAccount {
         &self.collateral_for_storage
     }
 
+    pub fn collateralized_bytes(&self) -> u64 { self.collateralized_bytes }
+
     #[cfg(test)]
     pub fn accumulated_interest_return(&self) -> &U256 {
         &self.accumulated_interest_return
@@ -385,7 +412,6 @@ impl OverlayAccount {
         &self.ownership_changes
     }
 
-    #[cfg(test)]
     pub fn is_newly_created_contract(&self) -> bool {
         self.is_newly_created_contract
     }
@@ -509,23 +535,35 @@ impl OverlayAccount {
         }
     }
 
-    pub fn add_collateral_for_storage(&mut self, by: &U256) {
+    /// Charges `by` as the collateral of `bytes` of storage.
+    pub fn add_collateral_for_storage(&mut self, by: &U256, bytes: u64) {
         if self.is_contract() {
             self.sub_sponsor_balance_for_collateral(by);
         } else {
             self.sub_balance(by);
         }
         self.collateral_for_storage += *by;
+        self.collateralized_bytes += bytes;
     }
 
-    pub fn sub_collateral_for_storage(&mut self, by: &U256) {
-        assert!(self.collateral_for_storage >= *by);
+    /// Refunds the collateral of `bytes` of storage at the average price they
+    /// were charged at, and returns the refund.
+    pub fn sub_collateral_for_storage(&mut self, bytes: u64) -> U256 {
+        assert!(self.collateralized_bytes >= bytes);
+        let refund = if bytes == self.collateralized_bytes {
+            self.collateral_for_storage
+        } else {
+            self.collateral_for_storage * U256::from(bytes)
+                / U256::from(self.collateralized_bytes)
+        };
         if self.is_contract() {
-            self.add_sponsor_balance_for_collateral(by);
+            self.add_sponsor_balance_for_collateral(&refund);
         } else {
-            self.add_balance(by);
+            self.add_balance(&refund);
         }
-        self.collateral_for_storage -= *by;
+        self.collateral_for_storage -= refund;
+        self.collateralized_bytes -= bytes;
+        refund
     }
 
     pub fn cache_code<StateDbStorage: StorageStateTrait>(
@@ -590,6 +628,7 @@ impl OverlayAccount {
             storage_layout_change: None,
             staking_balance: self.staking_balance,
             collateral_for_storage: self.collateral_for_storage,
+            collateralized_bytes: self.collateralized_bytes,
             accumulated_interest_return: self.accumulated_interest_return,
             deposit_list: self.deposit_list.clone(),
             vote_stake_list: self.vote_stake_list.clone(),
@@ -610,6 +649,103 @@ impl OverlayAccount {
         account
     }
 
+    pub fn storage_changes_keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.storage_changes.keys().cloned()
+    }
+
+    /// Returns true if the fields other than the storage entries are the
+    /// same as in `other`.
+    pub fn same_fields_as(&self, other: &Self) -> bool {
+        self.balance == other.balance
+            && self.nonce == other.nonce
+            && self.admin == other.admin
+            && self.sponsor_info == other.sponsor_info
+            && self.staking_balance == other.staking_balance
+            && self.collateral_for_storage == other.collateral_for_storage
+            && self.collateralized_bytes == other.collateralized_bytes
+            && self.accumulated_interest_return
+                == other.accumulated_interest_return
+            && self.deposit_list == other.deposit_list
+            && self.vote_stake_list == other.vote_stake_list
+            && self.code_hash == other.code_hash
+            && self.storage_layout_change == other.storage_layout_change
+            && self.is_newly_created_contract
+                == other.is_newly_created_contract
+    }
+
+    /// Returns the storage keys whose value or owner differs from `other`.
+    pub fn storage_keys_changed_from(&self, other: &Self) -> HashSet<Vec<u8>> {
+        let ownership_cache = self.ownership_cache.read();
+        let other_ownership_cache = other.ownership_cache.read();
+        self.storage_changes
+            .keys()
+            .chain(other.storage_changes.keys())
+            .filter(|key| {
+                self.storage_changes.get(*key)
+                    != other.storage_changes.get(*key)
+                    || ownership_cache.get(*key)
+                        != other_ownership_cache.get(*key)
+                    || self.ownership_changes.get(*key)
+                        != other.ownership_changes.get(*key)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Takes the changes made on `other` by a speculative execution: the
+    /// fields other than the storage entries if `fields`, and the storage
+    /// entries of `keys`.
+    pub fn apply_changes_from(
+        &mut self, other: &Self, fields: bool, keys: &HashSet<Vec<u8>>,
+    ) {
+        if fields {
+            self.balance = other.balance;
+            self.nonce = other.nonce;
+            self.admin = other.admin;
+            self.sponsor_info = other.sponsor_info.clone();
+            self.staking_balance = other.staking_balance;
+            self.collateral_for_storage = other.collateral_for_storage;
+            self.collateralized_bytes = other.collateralized_bytes;
+            self.accumulated_interest_return =
+                other.accumulated_interest_return;
+            self.deposit_list = other.deposit_list.clone();
+            self.vote_stake_list = other.vote_stake_list.clone();
+            self.code_hash = other.code_hash;
+            self.code = other.code.clone();
+            self.storage_layout_change = other.storage_layout_change.clone();
+            self.is_newly_created_contract = other.is_newly_created_contract;
+        }
+
+        fn copy_entry<V: Clone>(
+            to: &mut HashMap<Vec<u8>, V>, from: &HashMap<Vec<u8>, V>,
+            key: &Vec<u8>,
+        )
+        {
+            match from.get(key) {
+                Some(value) => {
+                    to.insert(key.clone(), value.clone());
+                }
+                None => {
+                    to.remove(key);
+                }
+            }
+        }
+        let other_ownership_cache = other.ownership_cache.read();
+        for key in keys {
+            copy_entry(&mut self.storage_changes, &other.storage_changes, key);
+            copy_entry(
+                self.ownership_cache.get_mut(),
+                &other_ownership_cache,
+                key,
+            );
+            copy_entry(
+                &mut self.ownership_changes,
+                &other.ownership_changes,
+                key,
+            );
+        }
+    }
+
     pub fn set_storage(&mut self, key: Vec<u8>, value: U256, owner: Address) {
         self.storage_changes.insert(key.clone(), value);
         if value.is_zero() {
@@ -628,6 +764,20 @@ impl OverlayAccount {
         self.storage_layout_change = Some(layout);
     }
 
+    /// Return the storage keys read or written, in ascending order.
+    pub fn accessed_storage_keys(&self) -> Vec<Vec<u8>> {
+        let mut keys: Vec<Vec<u8>> = self
+            .storage_cache
+            .read()
+            .keys()
+            .chain(self.storage_changes.keys())
+            .cloned()
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
     pub fn cached_storage_at(&self, key: &[u8]) -> Option<U256> {
         if let Some(value) = self.storage_changes.get(key) {
             return Some(value.clone());
@@ -711,6 +861,7 @@ impl OverlayAccount {
         self.storage_layout_change = other.storage_layout_change;
         self.staking_balance = other.staking_balance;
         self.collateral_for_storage = other.collateral_for_storage;
+        self.collateralized_bytes = other.collateralized_bytes;
         self.accumulated_interest_return = other.accumulated_interest_return;
         self.deposit_list = other.deposit_list;
         self.vote_stake_list = other.vote_stake_list;
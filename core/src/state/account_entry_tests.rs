@@ -60,6 +60,7 @@ fn test_overlay_account_create() {
             accumulated_interest_return: 2.into(),
             admin,
             sponsor_info: sponsor_info.clone(),
+            collateralized_bytes: None,
         },
     )
     .unwrap();
@@ -314,7 +315,7 @@ fn test_deposit_and_withdraw() {
 
     // add storage
     assert_eq!(*overlay_account.collateral_for_storage(), U256::from(0));
-    overlay_account.add_collateral_for_storage(&11116.into());
+    overlay_account.add_collateral_for_storage(&11116.into(), 64);
     assert_eq!(
         *overlay_account.collateral_for_storage(),
         U256::from(11_116)
@@ -333,7 +334,10 @@ fn test_deposit_and_withdraw() {
     );
 
     // sub storage
-    overlay_account.sub_collateral_for_storage(&11116.into());
+    assert_eq!(
+        overlay_account.sub_collateral_for_storage(64),
+        U256::from(11_116)
+    );
     assert_eq!(*overlay_account.collateral_for_storage(), U256::zero());
     assert_eq!(
         *overlay_account.balance(),
@@ -665,6 +669,7 @@ fn test_clone_overwrite() {
             accumulated_interest_return: 456.into(),
             admin,
             sponsor_info,
+            collateralized_bytes: None,
         },
     )
     .unwrap();
@@ -688,6 +693,7 @@ fn test_clone_overwrite() {
             accumulated_interest_return: 457.into(),
             admin,
             sponsor_info,
+            collateralized_bytes: None,
         },
     )
     .unwrap();
@@ -33,8 +33,8 @@ use parking_lot::{
 #[cfg(test)]
 use primitives::storage::STORAGE_LAYOUT_REGULAR_V0;
 use primitives::{
-    Account, DepositList, EpochId, SponsorInfo, StorageKey, StorageLayout,
-    StorageValue, VoteStakeList,
+    AccessList, AccessListItem, Account, DepositList, EpochId, SponsorInfo,
+    StorageKey, StorageLayout, StorageValue, VoteStakeList,
 };
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
@@ -108,6 +108,129 @@ struct StakingState {
 
 pub type State = StateGeneric<StorageState>;
 
+/// A snapshot of the uncommitted changes of a state, on top of which
+/// transactions are executed speculatively. See
+/// `StateGeneric::new_speculative`.
+pub struct SpeculativeBase {
+    cache: HashMap<Address, AccountEntry>,
+    staking_state: StakingState,
+    account_start_nonce: U256,
+    contract_start_nonce: U256,
+    block_number: u64,
+    vm: VmFactory,
+}
+
+/// The changes made by a transaction executed on a speculative state.
+pub struct SpeculativeChanges {
+    // Contains all the accounts accessed by the transaction.
+    cache: HashMap<Address, AccountEntry>,
+    base_staking_state: StakingState,
+    staking_state: StakingState,
+    written_keys: StateKeys,
+    // The keys accessed in the reverted frames of the transaction.
+    reverted_keys: StateKeys,
+}
+
+impl SpeculativeChanges {
+    /// The state keys read by the transaction, including the reads of the
+    /// reverted frames, whose results still affect the transaction.
+    pub fn read_keys(&self) -> StateKeys {
+        let mut keys = self.reverted_keys.clone();
+        for (address, entry) in &self.cache {
+            keys.insert_entry(address, entry);
+        }
+        keys
+    }
+
+    /// The state keys written by the transaction.
+    pub fn written_keys(&self) -> &StateKeys { &self.written_keys }
+}
+
+/// A set of accounts and storage entries of a state, used to detect the
+/// conflicts between transactions.
+#[derive(Clone, Default)]
+pub struct StateKeys {
+    // The accounts, excluding their storage entries.
+    accounts: HashSet<Address>,
+    storage: HashMap<Address, HashSet<Vec<u8>>>,
+    // The accounts whose storage entries are all read, e.g. because a
+    // killed or re-created account does not look up the db.
+    all_storage: HashSet<Address>,
+}
+
+impl StateKeys {
+    /// Insert the keys accessed through the cache `entry` of `address`.
+    fn insert_entry(&mut self, address: &Address, entry: &AccountEntry) {
+        self.accounts.insert(*address);
+        match &entry.account {
+            Some(account) => {
+                if account.is_newly_created_contract() {
+                    self.all_storage.insert(*address);
+                }
+                self.storage
+                    .entry(*address)
+                    .or_default()
+                    .extend(account.accessed_storage_keys());
+            }
+            None if entry.is_dirty() => {
+                self.all_storage.insert(*address);
+            }
+            None => {}
+        }
+    }
+
+    pub fn extend(&mut self, other: &StateKeys) {
+        self.accounts.extend(other.accounts.iter().cloned());
+        for (address, keys) in &other.storage {
+            self.storage
+                .entry(*address)
+                .or_default()
+                .extend(keys.iter().cloned());
+        }
+        self.all_storage.extend(other.all_storage.iter().cloned());
+    }
+
+    /// The accounts with their storage keys, sorted by address.
+    fn access_list(&self) -> AccessList {
+        let mut access_list: AccessList = self
+            .accounts
+            .iter()
+            .map(|address| {
+                let mut storage_keys: Vec<Vec<u8>> = self
+                    .storage
+                    .get(address)
+                    .map_or(vec![], |keys| keys.iter().cloned().collect());
+                storage_keys.sort();
+                AccessListItem {
+                    address: *address,
+                    storage_keys,
+                }
+            })
+            .collect();
+        access_list.sort_by(|a, b| a.address.cmp(&b.address));
+        access_list
+    }
+
+    /// Return true if any of the keys `read` is written in `self`.
+    pub fn conflicts_with(&self, read: &StateKeys) -> bool {
+        if !self.accounts.is_disjoint(&read.accounts) {
+            return true;
+        }
+        if read
+            .all_storage
+            .iter()
+            .any(|address| self.storage.contains_key(address))
+        {
+            return true;
+        }
+        read.storage.iter().any(|(address, keys)| {
+            self.storage
+                .get(address)
+                .map_or(false, |written| !written.is_disjoint(keys))
+        })
+    }
+}
+
 pub struct StateGeneric<StateDbStorage: StorageStateTrait> {
     db: StateDb<StateDbStorage>,
 
@@ -124,6 +247,9 @@ pub struct StateGeneric<StateDbStorage: StorageStateTrait> {
     // Checkpoint to the changes.
     staking_state_checkpoints: RwLock<Vec<StakingState>>,
     checkpoints: RwLock<Vec<HashMap<Address, Option<AccountEntry>>>>,
+    // The keys accessed through the cache entries discarded by the reverts,
+    // which are not rolled back with the checkpoints.
+    reverted_keys: StateKeys,
 
     // Environment variables.
     account_start_nonce: U256,
@@ -132,6 +258,10 @@ pub struct StateGeneric<StateDbStorage: StorageStateTrait> {
     // the `number` entry in EVM Environment.
     block_number: u64,
     vm: VmFactory,
+
+    // The accounts not in the cache are loaded from the base before the db
+    // for a speculative state.
+    speculative_base: Option<Arc<SpeculativeBase>>,
 }
 
 impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
@@ -207,13 +337,182 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
             cache: Default::default(),
             staking_state_checkpoints: Default::default(),
             checkpoints: Default::default(),
+            reverted_keys: Default::default(),
             account_start_nonce,
             contract_start_nonce,
             staking_state,
             block_number,
             vm,
             accounts_to_notify: Default::default(),
+            speculative_base: None,
+        }
+    }
+
+    /// Create a state to execute a transaction speculatively on top of the
+    /// `base`. The `db` must be opened at the same version as the db of the
+    /// state where the `base` is taken from. The speculative state is never
+    /// committed, its changes are merged back by
+    /// `apply_speculative_changes`.
+    pub fn new_speculative(
+        db: StateDb<StateDbStorage>, base: Arc<SpeculativeBase>,
+    ) -> Self {
+        StateGeneric {
+            db,
+            cache: Default::default(),
+            staking_state_checkpoints: Default::default(),
+            checkpoints: Default::default(),
+            reverted_keys: Default::default(),
+            account_start_nonce: base.account_start_nonce,
+            contract_start_nonce: base.contract_start_nonce,
+            staking_state: base.staking_state,
+            block_number: base.block_number,
+            vm: base.vm.clone(),
+            accounts_to_notify: Default::default(),
+            speculative_base: Some(base),
+        }
+    }
+
+    /// Take a snapshot of the changes in this state to execute transactions
+    /// speculatively.
+    pub fn speculative_base(&mut self) -> SpeculativeBase {
+        assert!(self.checkpoints.get_mut().is_empty());
+        SpeculativeBase {
+            cache: self
+                .cache
+                .get_mut()
+                .iter()
+                .map(|(address, entry)| (*address, entry.clone_dirty()))
+                .collect(),
+            staking_state: self.staking_state,
+            account_start_nonce: self.account_start_nonce,
+            contract_start_nonce: self.contract_start_nonce,
+            block_number: self.block_number,
+            vm: self.vm.clone(),
+        }
+    }
+
+    /// Consume the speculative state and return its db for reuse, together
+    /// with the changes made on it. `written_keys` are the keys written by
+    /// the transaction, see `checkpoint_written_keys`.
+    pub fn into_speculative_changes(
+        mut self, written_keys: StateKeys,
+    ) -> (StateDb<StateDbStorage>, SpeculativeChanges) {
+        assert!(self.checkpoints.get_mut().is_empty());
+        let base_staking_state = self
+            .speculative_base
+            .as_ref()
+            .expect("not a speculative state")
+            .staking_state;
+        let changes = SpeculativeChanges {
+            cache: self.cache.into_inner(),
+            base_staking_state,
+            staking_state: self.staking_state,
+            written_keys,
+            reverted_keys: self.reverted_keys,
+        };
+        (self.db, changes)
+    }
+
+    /// Merge the changes of a speculative execution. The caller must make
+    /// sure that none of the keys read by the speculative execution has been
+    /// written since the base was taken.
+    pub fn apply_speculative_changes(
+        &mut self, mut changes: SpeculativeChanges,
+    ) -> DbResult<()> {
+        assert!(self.checkpoints.get_mut().is_empty());
+        let written = &changes.written_keys;
+        let mut addresses: HashSet<Address> =
+            written.accounts.iter().cloned().collect();
+        addresses.extend(written.storage.keys().cloned());
+        let no_keys = HashSet::new();
+        for address in addresses {
+            let speculative = match changes.cache.remove(&address) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if !self.cache.get_mut().contains_key(&address) {
+                let account = self.load_account(&address)?;
+                self.cache
+                    .get_mut()
+                    .insert(address, AccountEntry::new_clean(account));
+            }
+            let entry = self.cache.get_mut().get_mut(&address).unwrap();
+            match (&mut entry.account, &speculative.account) {
+                (Some(account), Some(speculative_account)) => {
+                    account.apply_changes_from(
+                        speculative_account,
+                        written.accounts.contains(&address),
+                        written.storage.get(&address).unwrap_or(&no_keys),
+                    );
+                    entry.state = AccountState::Dirty;
+                }
+                _ => {
+                    *entry = speculative;
+                }
+            }
+        }
+        // The total tokens are never read during tx execution, so the
+        // changes are accumulated.
+        let base = &changes.base_staking_state;
+        let speculative = &changes.staking_state;
+        let staking_state = &mut self.staking_state;
+        staking_state.total_issued_tokens = staking_state.total_issued_tokens
+            + speculative.total_issued_tokens
+            - base.total_issued_tokens;
+        staking_state.total_staking_tokens = staking_state
+            .total_staking_tokens
+            + speculative.total_staking_tokens
+            - base.total_staking_tokens;
+        staking_state.total_storage_tokens = staking_state
+            .total_storage_tokens
+            + speculative.total_storage_tokens
+            - base.total_storage_tokens;
+        Ok(())
+    }
+
+    /// Return the state keys written since the last checkpoint.
+    pub fn checkpoint_written_keys(&self) -> DbResult<StateKeys> {
+        let mut keys = StateKeys::default();
+        let checkpoints = self.checkpoints.read();
+        let checkpoint = match checkpoints.last() {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(keys),
+        };
+        let cache = self.cache.read();
+        for (address, prev_entry) in checkpoint {
+            let loaded;
+            let prev = match prev_entry {
+                Some(entry) => entry.account.as_ref(),
+                None => {
+                    loaded = self.load_account(address)?;
+                    loaded.as_ref()
+                }
+            };
+            let cur = cache
+                .get(address)
+                .and_then(|entry| entry.account.as_ref());
+            match (prev, cur) {
+                (Some(prev), Some(cur)) => {
+                    if !cur.same_fields_as(prev) {
+                        keys.accounts.insert(*address);
+                    }
+                    let storage_keys = cur.storage_keys_changed_from(prev);
+                    if !storage_keys.is_empty() {
+                        keys.storage.insert(*address, storage_keys);
+                    }
+                }
+                (None, None) => {}
+                // Created or killed.
+                (prev, cur) => {
+                    keys.accounts.insert(*address);
+                    let storage = keys.storage.entry(*address).or_default();
+                    for account in prev.into_iter().chain(cur) {
+                        storage.extend(account.storage_changes_keys());
+                    }
+                }
+            }
         }
+        Ok(keys)
     }
 
     pub fn contract_start_nonce(&self) -> U256 { self.contract_start_nonce }
@@ -269,18 +568,15 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
 
     /// Charges or refund storage collateral and update `total_storage_tokens`.
     pub fn settle_collateral_for_address(
-        &mut self, addr: &Address, substate: &Substate,
+        &mut self, addr: &Address, substate: &Substate, spec: &Spec,
     ) -> DbResult<CollateralCheckResult> {
         let (inc_bytes, sub_bytes) = substate.get_collateral_change(addr);
-        let (inc, sub) = (
-            *COLLATERAL_PER_BYTE * inc_bytes,
-            *COLLATERAL_PER_BYTE * sub_bytes,
-        );
+        let inc = spec.collateral_per_byte * inc_bytes;
 
-        if !sub.is_zero() {
-            self.sub_collateral_for_storage(addr, &sub)?;
+        if sub_bytes != 0 {
+            self.sub_collateral_for_storage(addr, sub_bytes)?;
         }
-        if !inc.is_zero() {
+        if inc_bytes != 0 {
             let balance = if addr.is_contract_address() {
                 self.sponsor_balance_for_collateral(addr)?
             } else {
@@ -293,7 +589,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                     got: balance,
                 });
             }
-            self.add_collateral_for_storage(addr, &inc)?;
+            self.add_collateral_for_storage(addr, &inc, inc_bytes)?;
         }
         Ok(CollateralCheckResult::Valid)
     }
@@ -326,10 +622,11 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
     /// checked out. This function should only be called in post-processing
     /// of a transaction.
     pub fn settle_collateral_for_all(
-        &mut self, substate: &Substate,
+        &mut self, substate: &Substate, spec: &Spec,
     ) -> DbResult<CollateralCheckResult> {
         for address in substate.keys_for_collateral_changed().iter() {
-            match self.settle_collateral_for_address(address, substate)? {
+            match self.settle_collateral_for_address(address, substate, spec)?
+            {
                 CollateralCheckResult::Valid => {}
                 res => return Ok(res),
             }
@@ -356,11 +653,11 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
     // test cases breaks this assumption, which will be fixed in a separated PR.
     pub fn collect_and_settle_collateral(
         &mut self, original_sender: &Address, storage_limit: &U256,
-        substate: &mut Substate,
+        substate: &mut Substate, spec: &Spec,
     ) -> DbResult<CollateralCheckResult>
     {
         self.collect_ownership_changed(substate)?;
-        let res = match self.settle_collateral_for_all(substate)? {
+        let res = match self.settle_collateral_for_all(substate, spec)? {
             CollateralCheckResult::Valid => {
                 self.check_storage_limit(original_sender, storage_limit)?
             }
@@ -399,6 +696,9 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                 .pop()
                 .expect("staking_state_checkpoint should exist");
             for (k, v) in checkpoint.drain() {
+                if let Some(entry) = self.cache.get_mut().get(&k) {
+                    self.reverted_keys.insert_entry(&k, entry);
+                }
                 match v {
                     Some(v) => match self.cache.get_mut().entry(k) {
                         Entry::Occupied(mut e) => {
@@ -731,6 +1031,12 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         })
     }
 
+    pub fn collateralized_bytes(&self, address: &Address) -> DbResult<u64> {
+        self.ensure_account_loaded(address, RequireCache::None, |acc| {
+            acc.map_or(0, |account| account.collateralized_bytes())
+        })
+    }
+
     pub fn admin(&self, address: &Address) -> DbResult<Address> {
         self.ensure_account_loaded(address, RequireCache::None, |acc| {
             acc.map_or(Address::zero(), |acc| *acc.admin())
@@ -844,30 +1150,35 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         Ok(())
     }
 
-    /// Caller should make sure that staking_balance for this account is
-    /// sufficient enough.
+    /// Charges `by` as the collateral of `bytes` of storage. Caller should
+    /// make sure that staking_balance for this account is sufficient enough.
     pub fn add_collateral_for_storage(
-        &mut self, address: &Address, by: &U256,
+        &mut self, address: &Address, by: &U256, bytes: u64,
     ) -> DbResult<()> {
-        if !by.is_zero() {
+        if !by.is_zero() || bytes != 0 {
             self.require_exists(address, false)?
-                .add_collateral_for_storage(by);
+                .add_collateral_for_storage(by, bytes);
             self.staking_state.total_storage_tokens += *by;
         }
         Ok(())
     }
 
+    /// Refunds the collateral of `bytes` of storage at the price it was
+    /// charged at. The bytes without collateral, e.g. of the genesis, are
+    /// burnt at the initial price.
     pub fn sub_collateral_for_storage(
-        &mut self, address: &Address, by: &U256,
+        &mut self, address: &Address, bytes: u64,
     ) -> DbResult<()> {
-        let collateral = self.collateral_for_storage(address)?;
-        let refundable = if by > &collateral { &collateral } else { by };
-        let burnt = *by - *refundable;
-        if !refundable.is_zero() {
+        let collateralized_bytes = self.collateralized_bytes(address)?;
+        let refundable_bytes = bytes.min(collateralized_bytes);
+        let burnt = U256::from(bytes - refundable_bytes) * *COLLATERAL_PER_BYTE;
+        let refund = if refundable_bytes != 0 {
             self.require_or_new_basic_account(address)?
-                .sub_collateral_for_storage(refundable);
-        }
-        self.staking_state.total_storage_tokens -= *by;
+                .sub_collateral_for_storage(refundable_bytes)
+        } else {
+            U256::zero()
+        };
+        self.staking_state.total_storage_tokens -= refund + burnt;
         self.staking_state.total_issued_tokens -= burnt;
 
         Ok(())
@@ -1320,6 +1631,17 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         Ok(())
     }
 
+    /// Return the accounts and storage keys accessed since the state was
+    /// created, including those accessed in the reverted frames. All of them
+    /// are loaded into the cache on access.
+    pub fn access_list(&self) -> AccessList {
+        let mut keys = self.reverted_keys.clone();
+        for (address, entry) in self.cache.read().iter() {
+            keys.insert_entry(address, entry);
+        }
+        keys.access_list()
+    }
+
     pub fn storage_at(&self, address: &Address, key: &[u8]) -> DbResult<U256> {
         self.ensure_account_loaded(address, RequireCache::None, |acc| {
             acc.map_or(U256::zero(), |account| {
@@ -1439,6 +1761,25 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         }
     }
 
+    /// Load an account which is not in the cache, from the speculative base
+    /// if there is one, or from the db.
+    fn load_account(
+        &self, address: &Address,
+    ) -> DbResult<Option<OverlayAccount>> {
+        if let Some(base) = &self.speculative_base {
+            if let Some(entry) = base.cache.get(address) {
+                return Ok(entry
+                    .account
+                    .as_ref()
+                    .map(OverlayAccount::clone_dirty));
+            }
+        }
+        Ok(self
+            .db
+            .get_account(address)?
+            .map(|acc| OverlayAccount::from_loaded(address, acc)))
+    }
+
     fn ensure_account_loaded<F, U>(
         &self, address: &Address, require: RequireCache, f: F,
     ) -> DbResult<U>
@@ -1463,10 +1804,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                 RwLockUpgradableReadGuard::upgrade(upgradable_lock)
             } else {
                 // Load the account from db.
-                let mut maybe_loaded_acc = self
-                    .db
-                    .get_account(address)?
-                    .map(|acc| OverlayAccount::from_loaded(address, acc));
+                let mut maybe_loaded_acc = self.load_account(address)?;
                 if let Some(account) = &mut maybe_loaded_acc {
                     Self::update_account_cache(require, account, &self.db)?;
                 }
@@ -1539,10 +1877,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
     where F: FnOnce(&Address) -> DbResult<OverlayAccount> {
         let mut cache;
         if !self.cache.read().contains_key(address) {
-            let account = self
-                .db
-                .get_account(address)?
-                .map(|acc| OverlayAccount::from_loaded(address, acc));
+            let account = self.load_account(address)?;
             cache = self.cache.write();
             Self::insert_cache_if_fresh_account(&mut *cache, address, account);
         } else {
@@ -1593,6 +1928,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         assert!(self.checkpoints.get_mut().is_empty());
         assert!(self.staking_state_checkpoints.get_mut().is_empty());
         self.cache.get_mut().clear();
+        self.reverted_keys = Default::default();
         self.staking_state.interest_rate_per_block =
             self.db.get_annual_interest_rate().expect("no db error")
                 / U256::from(BLOCKS_PER_YEAR);
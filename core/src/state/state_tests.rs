@@ -16,7 +16,7 @@ use cfx_storage::{
 };
 use cfx_types::{address_util::AddressUtil, Address, BigEndianHash, U256};
 use keccak_hash::{keccak, KECCAK_EMPTY};
-use primitives::{EpochId, StorageKey, StorageLayout};
+use primitives::{AccessListItem, EpochId, StorageKey, StorageLayout};
 use std::sync::Arc;
 
 #[cfg(test)]
@@ -55,7 +55,7 @@ fn checkpoint_basic() {
         .add_balance(&address, &U256::from(1069u64), CleanupMode::NoEmpty)
         .unwrap();
     state
-        .add_collateral_for_storage(&address, &U256::from(1000))
+        .add_collateral_for_storage(&address, &U256::from(1000), 1)
         .unwrap();
     assert_eq!(state.balance(&address).unwrap(), U256::from(69u64));
     assert_eq!(
@@ -70,7 +70,7 @@ fn checkpoint_basic() {
         .add_balance(&address, &U256::from(1u64), CleanupMode::NoEmpty)
         .unwrap();
     state
-        .sub_collateral_for_storage(&address, &U256::from(1000))
+        .sub_collateral_for_storage(&address, 1)
         .unwrap();
     assert_eq!(
         state.collateral_for_storage(&address).unwrap(),
@@ -105,7 +105,7 @@ fn checkpoint_nested() {
         .add_balance(&address, &U256::from(1069u64), CleanupMode::NoEmpty)
         .unwrap();
     state
-        .add_collateral_for_storage(&address, &U256::from(1000))
+        .add_collateral_for_storage(&address, &U256::from(1000), 1)
         .unwrap();
     assert_eq!(*state.total_storage_tokens(), U256::from(1000));
     assert_eq!(
@@ -246,7 +246,8 @@ fn checkpoint_from_empty_get_storage_at() {
             .collect_and_settle_collateral(
                 &a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -311,7 +312,8 @@ fn checkpoint_from_empty_get_storage_at() {
             .collect_and_settle_collateral(
                 &a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -362,7 +364,8 @@ fn checkpoint_from_empty_get_storage_at() {
             .collect_and_settle_collateral(
                 &a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -431,7 +434,8 @@ fn checkpoint_get_storage_at() {
             .collect_and_settle_collateral(
                 &a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -563,7 +567,8 @@ fn checkpoint_get_storage_at() {
             .collect_and_settle_collateral(
                 &contract_a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -647,7 +652,8 @@ fn checkpoint_get_storage_at() {
             .collect_and_settle_collateral(
                 &contract_a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -719,7 +725,8 @@ fn checkpoint_get_storage_at() {
             .collect_and_settle_collateral(
                 &contract_a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -779,7 +786,11 @@ fn kill_account_with_checkpoints() {
         .add_sponsor_balance_for_collateral(&a, &COLLATERAL_PER_STORAGE_KEY)
         .unwrap();
     state_0
-        .add_collateral_for_storage(&a, &COLLATERAL_PER_STORAGE_KEY)
+        .add_collateral_for_storage(
+            &a,
+            &COLLATERAL_PER_STORAGE_KEY,
+            BYTES_PER_STORAGE_KEY,
+        )
         .unwrap();
     state_0.discard_checkpoint();
     let epoch_id_1 = EpochId::from_uint(&U256::from(1));
@@ -852,7 +863,11 @@ fn check_result_of_simple_payment_to_killed_account() {
         .commit_ownership_change(&state_0.db, &mut Substate::new())
         .unwrap();
     state_0
-        .add_collateral_for_storage(&sender_addr, &COLLATERAL_PER_STORAGE_KEY)
+        .add_collateral_for_storage(
+            &sender_addr,
+            &COLLATERAL_PER_STORAGE_KEY,
+            BYTES_PER_STORAGE_KEY,
+        )
         .unwrap();
     state_0.discard_checkpoint();
     let epoch_id_1 = EpochId::from_uint(&U256::from(1));
@@ -901,7 +916,12 @@ fn create_contract_fail() {
         .unwrap();
     assert_eq!(
         state
-            .collect_and_settle_collateral(&a, &U256::MAX, &mut substate)
+            .collect_and_settle_collateral(
+                &a,
+                &U256::MAX,
+                &mut substate,
+                &Spec::new_spec(),
+            )
             .unwrap(),
         CollateralCheckResult::Valid
     );
@@ -948,7 +968,8 @@ fn create_contract_fail_previous_storage() {
             .collect_and_settle_collateral(
                 &a,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1091,7 +1112,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1132,7 +1154,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1172,7 +1195,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1204,7 +1228,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1245,7 +1270,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1285,7 +1311,8 @@ fn test_automatic_collateral_normal_account() {
             .collect_and_settle_collateral(
                 &normal_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1371,7 +1398,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1407,7 +1435,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1456,7 +1485,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::NotEnoughBalance {
@@ -1498,7 +1528,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid,
@@ -1541,7 +1572,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1582,7 +1614,8 @@ fn test_automatic_collateral_contract_account() {
             .collect_and_settle_collateral(
                 &contract_account,
                 &U256::MAX,
-                &mut substates.last_mut().unwrap()
+                &mut substates.last_mut().unwrap(),
+                &Spec::new_spec(),
             )
             .unwrap(),
         CollateralCheckResult::Valid
@@ -1607,3 +1640,203 @@ fn test_automatic_collateral_contract_account() {
     assert_eq!(*state.total_storage_tokens(), U256::from(0));
     assert_eq!(state.increase_block_number(), U256::from(0));
 }
+
+/// Sets `key` of `contract` owned by `owner` and settles the collateral at
+/// the price of `spec`.
+fn set_storage_and_settle(
+    state: &mut State, contract: &Address, key: Vec<u8>, value: U256,
+    owner: &Address, spec: &Spec,
+)
+{
+    let mut substate = Substate::new();
+    state.checkpoint();
+    state.set_storage(contract, key, value, *owner).unwrap();
+    assert_eq!(
+        state
+            .collect_and_settle_collateral(
+                owner,
+                &U256::MAX,
+                &mut substate,
+                spec,
+            )
+            .unwrap(),
+        CollateralCheckResult::Valid
+    );
+    state.discard_checkpoint();
+}
+
+#[test]
+fn test_collateral_refund_after_repricing() {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let mut owner = Address::from_low_u64_be(1);
+    owner.set_user_account_type_bits();
+    let mut contract = Address::from_low_u64_be(2);
+    contract.set_contract_type_bits();
+    let k1 = u256_to_vec(&U256::from(1));
+    let k2 = u256_to_vec(&U256::from(2));
+
+    let initial_spec = Spec::new_spec();
+    let mut repriced_spec = Spec::new_spec();
+    repriced_spec.collateral_per_byte = initial_spec.collateral_per_byte * 2;
+    let balance = *COLLATERAL_PER_STORAGE_KEY * 4;
+    state
+        .add_balance(&owner, &balance, CleanupMode::NoEmpty)
+        .unwrap();
+    state
+        .new_contract(&contract, U256::zero(), U256::zero())
+        .unwrap();
+    state.add_total_issued(balance);
+
+    // One key at the initial price and one at the doubled price.
+    set_storage_and_settle(
+        &mut state,
+        &contract,
+        k1.clone(),
+        U256::one(),
+        &owner,
+        &initial_spec,
+    );
+    set_storage_and_settle(
+        &mut state,
+        &contract,
+        k2.clone(),
+        U256::one(),
+        &owner,
+        &repriced_spec,
+    );
+    let charged = *COLLATERAL_PER_STORAGE_KEY * 3;
+    assert_eq!(state.collateral_for_storage(&owner).unwrap(), charged);
+    assert_eq!(
+        state.collateralized_bytes(&owner).unwrap(),
+        BYTES_PER_STORAGE_KEY * 2
+    );
+    assert_eq!(*state.total_storage_tokens(), charged);
+
+    // The number of bytes is kept in the db, since it is not implied by the
+    // collateral at the initial price.
+    let epoch_id = EpochId::from_uint(&U256::from(1));
+    state.commit(epoch_id, /* debug_record = */ None).unwrap();
+    let mut state = get_state(&storage_manager, &epoch_id);
+    assert_eq!(
+        state.collateralized_bytes(&owner).unwrap(),
+        BYTES_PER_STORAGE_KEY * 2
+    );
+
+    // Released at the initial price, the keys are refunded at the average
+    // price they were charged at.
+    set_storage_and_settle(
+        &mut state,
+        &contract,
+        k1,
+        U256::zero(),
+        &owner,
+        &initial_spec,
+    );
+    assert_eq!(
+        state.collateral_for_storage(&owner).unwrap(),
+        charged / 2
+    );
+    assert_eq!(*state.total_storage_tokens(), charged / 2);
+
+    // Released at a quadrupled price, the rest is refunded, but no more.
+    repriced_spec.collateral_per_byte = initial_spec.collateral_per_byte * 4;
+    set_storage_and_settle(
+        &mut state,
+        &contract,
+        k2,
+        U256::zero(),
+        &owner,
+        &repriced_spec,
+    );
+    assert_eq!(state.collateral_for_storage(&owner).unwrap(), U256::zero());
+    assert_eq!(state.collateralized_bytes(&owner).unwrap(), 0);
+    assert_eq!(state.balance(&owner).unwrap(), balance);
+    assert_eq!(*state.total_storage_tokens(), U256::zero());
+    assert_eq!(*state.total_issued_tokens(), balance);
+}
+
+#[test]
+fn test_access_list() {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let mut contract = Address::from_low_u64_be(2);
+    contract.set_contract_type_bits();
+    let mut account = Address::from_low_u64_be(1);
+    account.set_user_account_type_bits();
+    let k1 = u256_to_vec(&U256::from(1));
+    let k2 = u256_to_vec(&U256::from(2));
+
+    state
+        .new_contract(&contract, U256::zero(), U256::one())
+        .unwrap();
+    let epoch_id = EpochId::from_uint(&U256::from(1));
+    state.commit(epoch_id, /* debug_record = */ None).unwrap();
+
+    let mut state = get_state(&storage_manager, &epoch_id);
+    assert_eq!(state.access_list(), vec![]);
+    state
+        .set_storage(&contract, k2.clone(), U256::one(), contract)
+        .unwrap();
+    assert_eq!(state.storage_at(&contract, &k1).unwrap(), U256::zero());
+    assert_eq!(state.balance(&account).unwrap(), U256::zero());
+    assert_eq!(
+        state.access_list(),
+        vec![
+            AccessListItem {
+                address: account,
+                storage_keys: vec![],
+            },
+            AccessListItem {
+                address: contract,
+                storage_keys: vec![k1, k2],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_access_list_of_reverted_frames() {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let mut contract = Address::from_low_u64_be(2);
+    contract.set_contract_type_bits();
+    let mut account = Address::from_low_u64_be(1);
+    account.set_user_account_type_bits();
+    let k1 = u256_to_vec(&U256::from(1));
+    let k2 = u256_to_vec(&U256::from(2));
+
+    state
+        .new_contract(&contract, U256::zero(), U256::one())
+        .unwrap();
+    let epoch_id = EpochId::from_uint(&U256::from(1));
+    state.commit(epoch_id, /* debug_record = */ None).unwrap();
+
+    // The outer call reads the 1st storage entry, and an inner call reads an
+    // account, writes the 2nd entry and reverts.
+    let mut state = get_state(&storage_manager, &epoch_id);
+    state.checkpoint();
+    assert_eq!(state.storage_at(&contract, &k1).unwrap(), U256::zero());
+    state.checkpoint();
+    assert_eq!(state.balance(&account).unwrap(), U256::zero());
+    state
+        .set_storage(&contract, k2.clone(), U256::one(), contract)
+        .unwrap();
+    state.revert_to_checkpoint();
+    state.discard_checkpoint();
+
+    // The reverted accesses still affect the transaction.
+    assert_eq!(
+        state.access_list(),
+        vec![
+            AccessListItem {
+                address: account,
+                storage_keys: vec![],
+            },
+            AccessListItem {
+                address: contract,
+                storage_keys: vec![k1, k2],
+            },
+        ]
+    );
+}
@@ -0,0 +1,319 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A time series of the statistics of the executed pivot epochs, kept in a
+//! ring buffer of the latest epochs for the dashboards of a node.
+
+use crate::{
+    consensus::ConsensusGraphTrait, ConsensusGraph, SharedTransactionPool,
+    TransactionPool,
+};
+use cfx_types::{H256, U256};
+use network::NetworkService;
+use parking_lot::RwLock;
+use primitives::EpochNumber;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Weak},
+    thread,
+    time::Duration,
+};
+
+/// The interval to collect the statistics of the newly executed epochs.
+const COLLECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The statistics of an executed pivot epoch. The pool size and the peer
+/// count are sampled when the epoch is collected, shortly after it's executed.
+/// They are not available for the epochs executed before the previous
+/// collection, e.g. those backfilled at start or after a pivot chain reorg.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EpochStatistics {
+    pub epoch_number: u64,
+    pub pivot_hash: H256,
+    /// The timestamp of the pivot block.
+    pub timestamp: u64,
+    pub block_count: usize,
+    pub transaction_count: usize,
+    pub gas_used: U256,
+    /// The seconds since the pivot block of the previous epoch.
+    pub block_interval: u64,
+    /// The number of unpacked transactions in the pool.
+    pub pool_size: Option<usize>,
+    pub peer_count: Option<usize>,
+}
+
+pub type SharedChainStatistics = Arc<ChainStatistics>;
+
+/// The statistics of the latest `capacity` executed epochs, in the order of
+/// the epoch numbers. The epochs which are unavailable when collected, e.g.
+/// pruned, are missing.
+pub struct ChainStatistics {
+    capacity: usize,
+    epochs: RwLock<VecDeque<EpochStatistics>>,
+}
+
+impl ChainStatistics {
+    pub fn new(capacity: usize) -> Self {
+        ChainStatistics {
+            capacity,
+            epochs: RwLock::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Append the statistics of the next epoch. The statistics of the epochs
+    /// from `stats.epoch_number` on, which are on a reverted pivot chain, are
+    /// replaced.
+    pub fn insert(&self, stats: EpochStatistics) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut epochs = self.epochs.write();
+        while epochs
+            .back()
+            .map_or(false, |last| last.epoch_number >= stats.epoch_number)
+        {
+            epochs.pop_back();
+        }
+        if epochs.len() == self.capacity {
+            epochs.pop_front();
+        }
+        epochs.push_back(stats);
+    }
+
+    /// Remove the statistics of the epochs from `epoch_number` on.
+    pub fn revert_to(&self, epoch_number: u64) {
+        let mut epochs = self.epochs.write();
+        while epochs
+            .back()
+            .map_or(false, |last| last.epoch_number >= epoch_number)
+        {
+            epochs.pop_back();
+        }
+    }
+
+    /// The last recorded epoch.
+    pub fn latest(&self) -> Option<EpochStatistics> {
+        self.epochs.read().back().cloned()
+    }
+
+    /// The recorded statistics of the epochs in `[from, to]`.
+    pub fn range(&self, from: u64, to: u64) -> Vec<EpochStatistics> {
+        self.epochs
+            .read()
+            .iter()
+            .skip_while(|stats| stats.epoch_number < from)
+            .take_while(|stats| stats.epoch_number <= to)
+            .cloned()
+            .collect()
+    }
+
+    /// Start a thread which records the statistics of the newly executed
+    /// epochs. It stops once the consensus graph is released.
+    pub fn start_collector(
+        this: &SharedChainStatistics, consensus: &Arc<ConsensusGraph>,
+        txpool: &SharedTransactionPool, network: &Arc<NetworkService>,
+    ) {
+        let stats = this.clone();
+        let consensus = Arc::downgrade(consensus);
+        let txpool = Arc::downgrade(txpool);
+        let network = Arc::downgrade(network);
+        thread::Builder::new()
+            .name("Chain Statistics".into())
+            .spawn(move || {
+                let mut last_executed = None;
+                loop {
+                    match stats.collect(
+                        &consensus,
+                        &txpool,
+                        &network,
+                        last_executed,
+                    ) {
+                        Some(best_executed) => {
+                            last_executed = Some(best_executed)
+                        }
+                        None => break,
+                    }
+                    thread::sleep(COLLECT_INTERVAL);
+                }
+            })
+            .expect("Chain statistics thread start fails");
+    }
+
+    /// Record the epochs executed since the last collection, and return the
+    /// best executed epoch. Only the epochs after `last_executed`, the best
+    /// executed epoch in the previous collection, are sampled. Returns `None`
+    /// if the node is shutting down.
+    fn collect(
+        &self, consensus: &Weak<ConsensusGraph>,
+        txpool: &Weak<TransactionPool>, network: &Weak<NetworkService>,
+        last_executed: Option<u64>,
+    ) -> Option<u64>
+    {
+        let (consensus, txpool, network) =
+            match (consensus.upgrade(), txpool.upgrade(), network.upgrade()) {
+                (Some(c), Some(t), Some(n)) => (c, t, n),
+                _ => return None,
+            };
+
+        // Drop the epochs whose pivot blocks are no longer on the pivot
+        // chain.
+        while let Some(last) = self.latest() {
+            if pivot_hash(&consensus, last.epoch_number)
+                == Some(last.pivot_hash)
+            {
+                break;
+            }
+            self.revert_to(last.epoch_number);
+        }
+
+        let best_executed = consensus.best_executed_state_epoch_number();
+        let from = match self.latest() {
+            Some(last) => last.epoch_number + 1,
+            None => (best_executed + 1).saturating_sub(self.capacity as u64),
+        };
+        let pool_size = txpool.total_unpacked();
+        let peer_count = network.get_peer_info().map_or(0, |p| p.len());
+        for epoch_number in from..=best_executed {
+            match epoch_statistics(&consensus, epoch_number) {
+                Some((mut stats, parent_hash)) => {
+                    // Stop at a pivot chain reorg during the collection, which
+                    // is handled in the next one.
+                    if let Some(last) = self.latest() {
+                        if last.epoch_number + 1 == epoch_number
+                            && last.pivot_hash != parent_hash
+                        {
+                            break;
+                        }
+                    }
+                    if last_executed.map_or(false, |last| epoch_number > last) {
+                        stats.pool_size = Some(pool_size);
+                        stats.peer_count = Some(peer_count);
+                    }
+                    self.insert(stats);
+                }
+                None => {
+                    debug!("Skip the statistics of epoch {}", epoch_number)
+                }
+            }
+        }
+        Some(best_executed)
+    }
+}
+
+fn pivot_hash(consensus: &ConsensusGraph, epoch_number: u64) -> Option<H256> {
+    consensus
+        .get_block_hashes_by_epoch(EpochNumber::Number(epoch_number))
+        .ok()
+        .and_then(|hashes| hashes.last().cloned())
+}
+
+/// The statistics of an epoch and the parent of its pivot block.
+fn epoch_statistics(
+    consensus: &ConsensusGraph, epoch_number: u64,
+) -> Option<(EpochStatistics, H256)> {
+    let data_man = consensus.get_data_manager();
+    let hashes = consensus
+        .get_block_hashes_by_epoch(EpochNumber::Number(epoch_number))
+        .ok()?;
+    let pivot_hash = *hashes.last()?;
+    let pivot_header = data_man.block_header_by_hash(&pivot_hash)?;
+    let block_interval = data_man
+        .block_header_by_hash(pivot_header.parent_hash())
+        .map_or(0, |parent| {
+            pivot_header.timestamp().saturating_sub(parent.timestamp())
+        });
+
+    let mut transaction_count = 0;
+    let mut gas_used = U256::zero();
+    for hash in &hashes {
+        let block =
+            data_man.block_by_hash(hash, false /* update_cache */)?;
+        transaction_count += block.transactions.len();
+        let receipts = data_man
+            .block_execution_result_by_hash_with_epoch(
+                hash,
+                &pivot_hash,
+                false, /* update_pivot_assumption */
+                false, /* update_cache */
+            )?
+            .block_receipts;
+        if let Some(last) = receipts.receipts.last() {
+            gas_used += last.accumulated_gas_used;
+        }
+    }
+
+    let stats = EpochStatistics {
+        epoch_number,
+        pivot_hash,
+        timestamp: pivot_header.timestamp(),
+        block_count: hashes.len(),
+        transaction_count,
+        gas_used,
+        block_interval,
+        ..Default::default()
+    };
+    Some((stats, *pivot_header.parent_hash()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(epoch_number: u64, pivot: u64) -> EpochStatistics {
+        EpochStatistics {
+            epoch_number,
+            pivot_hash: H256::from_low_u64_be(pivot),
+            ..Default::default()
+        }
+    }
+
+    fn epochs(stats: Vec<EpochStatistics>) -> Vec<(u64, H256)> {
+        stats
+            .iter()
+            .map(|s| (s.epoch_number, s.pivot_hash))
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_statistics() {
+        let chain = ChainStatistics::new(3);
+        assert!(chain.latest().is_none());
+        assert!(chain.range(0, 10).is_empty());
+
+        for epoch in 1..=4 {
+            chain.insert(stats(epoch, epoch));
+        }
+        // The oldest epoch is dropped.
+        assert_eq!(
+            epochs(chain.range(0, 10)),
+            vec![
+                (2, H256::from_low_u64_be(2)),
+                (3, H256::from_low_u64_be(3)),
+                (4, H256::from_low_u64_be(4)),
+            ]
+        );
+        assert_eq!(
+            epochs(chain.range(3, 3)),
+            vec![(3, H256::from_low_u64_be(3))]
+        );
+        assert!(chain.range(5, 10).is_empty());
+
+        // A pivot chain reorg replaces the later epochs.
+        chain.insert(stats(3, 30));
+        assert_eq!(
+            epochs(chain.range(0, 10)),
+            vec![
+                (2, H256::from_low_u64_be(2)),
+                (3, H256::from_low_u64_be(30))
+            ]
+        );
+        chain.revert_to(3);
+        assert_eq!(chain.latest().map(|s| s.epoch_number), Some(2));
+
+        // Nothing is kept with no capacity.
+        let disabled = ChainStatistics::new(0);
+        disabled.insert(stats(1, 1));
+        assert!(disabled.latest().is_none());
+    }
+}
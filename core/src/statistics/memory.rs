@@ -0,0 +1,214 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Periodic accounting of the heap memory used by the major components of a
+//! node, measured with their `MallocSizeOf` implementations, to track down
+//! the growth on long-running nodes.
+
+use crate::{
+    block_data_manager::BlockDataManager, ConsensusGraph,
+    SharedTransactionPool, SynchronizationService, TransactionPool,
+};
+use malloc_size_of::{new_malloc_size_ops, MallocSizeOf};
+use metrics::{Gauge, GaugeUsize};
+use parking_lot::RwLock;
+use secret_store::{SecretStore, SharedSecretStore};
+use std::{
+    sync::{Arc, Weak},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+    static ref SECRET_STORE_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "secret_store");
+    static ref DATA_MANAGER_DB_CACHE_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "data_manager_db_cache");
+    static ref STORAGE_MANAGER_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "storage_manager");
+    static ref DATA_MANAGER_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "data_manager");
+    static ref TX_POOL_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "tx_pool");
+    static ref CONSENSUS_GRAPH_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "consensus_graph");
+    static ref SYNC_GRAPH_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "sync_graph");
+    static ref SYNC_SERVICE_GAUGE: Arc<dyn Gauge<usize>> =
+        GaugeUsize::register_with_group("memory", "sync_service");
+}
+
+/// The heap usage of the components in bytes. The components are measured
+/// in the order of the fields, and the data shared through an `Arc` is only
+/// counted in the first component reaching it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryUsage {
+    /// The unix timestamp of the measurement.
+    pub timestamp: u64,
+    pub secret_store: usize,
+    /// The caches of the db manager, which are also included in
+    /// `data_manager`.
+    pub data_manager_db_cache: usize,
+    /// The storage manager with the cached trie nodes.
+    pub storage_manager: usize,
+    pub data_manager: usize,
+    pub tx_pool: usize,
+    pub consensus_graph: usize,
+    pub sync_graph: usize,
+    pub sync_service: usize,
+    /// The time taken by the measurement.
+    pub elapsed: Duration,
+}
+
+pub type SharedMemoryAccountant = Arc<MemoryAccountant>;
+
+/// Keeps the latest measurement of the memory usage, which is also reported
+/// to the `memory` metrics group and the log.
+#[derive(Default)]
+pub struct MemoryAccountant {
+    latest: RwLock<Option<MemoryUsage>>,
+}
+
+impl MemoryAccountant {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn latest(&self) -> Option<MemoryUsage> { self.latest.read().clone() }
+
+    /// Start a thread which measures the memory usage every `period`. It
+    /// stops once the components are released.
+    pub fn start(
+        this: &SharedMemoryAccountant, period: Duration,
+        secret_store: &SharedSecretStore, data_man: &Arc<BlockDataManager>,
+        txpool: &SharedTransactionPool, consensus: &Arc<ConsensusGraph>,
+        sync: &Arc<SynchronizationService>,
+    )
+    {
+        let accountant = this.clone();
+        let secret_store = Arc::downgrade(secret_store);
+        let data_man = Arc::downgrade(data_man);
+        let txpool = Arc::downgrade(txpool);
+        let consensus = Arc::downgrade(consensus);
+        let sync = Arc::downgrade(sync);
+        thread::Builder::new()
+            .name("MallocSizeOf".into())
+            .spawn(move || loop {
+                match measure(
+                    &secret_store,
+                    &data_man,
+                    &txpool,
+                    &consensus,
+                    &sync,
+                ) {
+                    Some(usage) => accountant.record(usage),
+                    None => break,
+                }
+                thread::sleep(period);
+            })
+            .expect("Memory usage thread start fails");
+    }
+
+    fn record(&self, usage: MemoryUsage) {
+        SECRET_STORE_GAUGE.update(usage.secret_store);
+        DATA_MANAGER_DB_CACHE_GAUGE.update(usage.data_manager_db_cache);
+        STORAGE_MANAGER_GAUGE.update(usage.storage_manager);
+        DATA_MANAGER_GAUGE.update(usage.data_manager);
+        TX_POOL_GAUGE.update(usage.tx_pool);
+        CONSENSUS_GRAPH_GAUGE.update(usage.consensus_graph);
+        SYNC_GRAPH_GAUGE.update(usage.sync_graph);
+        SYNC_SERVICE_GAUGE.update(usage.sync_service);
+
+        let mb = 1_000_000;
+        info!(
+            "Malloc Size(MB): secret_store={} data_manager_db_cache_size={} \
+             storage_manager_size={} data_man={} txpool={} consensus={} \
+             sync_graph={} sync_service={}, time elapsed={:?}",
+            usage.secret_store / mb,
+            usage.data_manager_db_cache / mb,
+            usage.storage_manager / mb,
+            usage.data_manager / mb,
+            usage.tx_pool / mb,
+            usage.consensus_graph / mb,
+            usage.sync_graph / mb,
+            usage.sync_service / mb,
+            usage.elapsed,
+        );
+        *self.latest.write() = Some(usage);
+    }
+}
+
+/// Measure the memory usage. Returns `None` if the node is shutting down.
+fn measure(
+    secret_store: &Weak<SecretStore>, data_man: &Weak<BlockDataManager>,
+    txpool: &Weak<TransactionPool>, consensus: &Weak<ConsensusGraph>,
+    sync: &Weak<SynchronizationService>,
+) -> Option<MemoryUsage>
+{
+    let secret_store = secret_store.upgrade()?;
+    let data_man = data_man.upgrade()?;
+    let txpool = txpool.upgrade()?;
+    let consensus = consensus.upgrade()?;
+    let sync = sync.upgrade()?;
+
+    let start = Instant::now();
+    let mut ops = new_malloc_size_ops();
+    Some(MemoryUsage {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        secret_store: secret_store.size_of(&mut ops),
+        // Note `db_manager` is not wrapped in Arc, so it will still be
+        // included in `data_manager`.
+        data_manager_db_cache: data_man.db_manager.size_of(&mut ops),
+        storage_manager: data_man.storage_manager.size_of(&mut ops),
+        data_manager: data_man.size_of(&mut ops),
+        tx_pool: txpool.size_of(&mut ops),
+        consensus_graph: consensus.size_of(&mut ops),
+        sync_graph: sync.get_synchronization_graph().size_of(&mut ops),
+        sync_service: sync.size_of(&mut ops),
+        elapsed: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{measure, MemoryAccountant, MemoryUsage};
+    use std::sync::Weak;
+
+    #[test]
+    fn test_record_latest() {
+        let accountant = MemoryAccountant::new();
+        assert_eq!(accountant.latest(), None);
+
+        let usage = MemoryUsage {
+            timestamp: 1,
+            tx_pool: 2,
+            ..Default::default()
+        };
+        accountant.record(usage.clone());
+        assert_eq!(accountant.latest(), Some(usage));
+
+        let usage = MemoryUsage {
+            timestamp: 2,
+            tx_pool: 3,
+            ..Default::default()
+        };
+        accountant.record(usage.clone());
+        assert_eq!(accountant.latest(), Some(usage));
+    }
+
+    #[test]
+    fn test_measure_released_components() {
+        // The measurement stops once the node releases the components.
+        assert_eq!(
+            measure(
+                &Weak::new(),
+                &Weak::new(),
+                &Weak::new(),
+                &Weak::new(),
+                &Weak::new()
+            ),
+            None
+        );
+    }
+}
@@ -1,3 +1,11 @@
+mod chain;
+mod memory;
+
+pub use self::{
+    chain::{ChainStatistics, EpochStatistics, SharedChainStatistics},
+    memory::{MemoryAccountant, MemoryUsage, SharedMemoryAccountant},
+};
+
 use crate::{consensus::ConsensusGraphStatistics, sync::SyncGraphStatistics};
 use metrics::{Gauge, GaugeUsize};
 use parking_lot::RwLock;
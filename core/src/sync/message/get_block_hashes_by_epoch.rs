@@ -56,6 +56,38 @@ impl Request for GetBlockHashesByEpoch {
     }
 }
 
+/// Request the block hashes of skeleton anchor epochs. It is the same as
+/// `GetBlockHashesByEpoch` on the wire, but its response is used to build the
+/// epoch skeleton instead of requesting headers, so the epochs are not
+/// tracked as inflight and the request is not resent.
+#[derive(
+    Debug, PartialEq, Clone, RlpDecodable, RlpEncodable, DeriveMallocSizeOf,
+)]
+pub struct GetSkeletonBlockHashes {
+    pub request_id: RequestId,
+    pub epochs: Vec<u64>,
+}
+
+impl AsAny for GetSkeletonBlockHashes {
+    fn as_any(&self) -> &dyn Any { self }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+impl Request for GetSkeletonBlockHashes {
+    fn timeout(&self, conf: &ProtocolConfiguration) -> Duration {
+        conf.headers_request_timeout
+    }
+
+    fn on_removed(&self, _inflight_keys: &KeyContainer) {}
+
+    fn with_inflight(&mut self, _inflight_keys: &KeyContainer) {}
+
+    fn is_empty(&self) -> bool { self.epochs.is_empty() }
+
+    fn resend(&self) -> Option<Box<dyn Request>> { None }
+}
+
 impl Handleable for GetBlockHashesByEpoch {
     fn handle(self, ctx: &Context) -> Result<(), Error> {
         let hashes = self
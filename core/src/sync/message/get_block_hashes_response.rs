@@ -5,7 +5,9 @@
 use crate::{
     message::RequestId,
     sync::{
-        message::{Context, GetBlockHashesByEpoch, Handleable},
+        message::{
+            Context, GetBlockHashesByEpoch, GetSkeletonBlockHashes, Handleable,
+        },
         Error,
     },
 };
@@ -24,6 +26,22 @@ impl Handleable for GetBlockHashesResponse {
 
         let req = ctx.match_request(self.request_id)?;
         let delay = req.delay;
+        if let Some(skeleton_req) = req
+            .request
+            .as_any()
+            .downcast_ref::<GetSkeletonBlockHashes>()
+        {
+            if let Some(anchor) = skeleton_req.epochs.first() {
+                ctx.manager.epoch_skeleton.lock().on_anchor_received(
+                    &ctx.node_id,
+                    *anchor,
+                    self.hashes,
+                );
+            }
+            // The segments of the received anchor can be requested now.
+            ctx.manager.start_sync(ctx.io);
+            return Ok(());
+        }
         let epoch_req = req.downcast_ref::<GetBlockHashesByEpoch>(
             ctx.io,
             &ctx.manager.request_manager,
@@ -37,6 +55,22 @@ impl Handleable for GetBlockHashesResponse {
             .request_manager
             .epochs_received(ctx.io, req, rec, delay);
 
+        // A segment that does not link to the epoch skeleton is requested
+        // again from another peer.
+        let linked = ctx.manager.epoch_skeleton.lock().check_segment(
+            &ctx.node_id,
+            &epoch_req.epochs,
+            &self.hashes,
+        );
+        if !linked {
+            ctx.manager.request_epoch_segment(
+                ctx.io,
+                &ctx.node_id,
+                epoch_req.epochs.clone(),
+            );
+            return Ok(());
+        }
+
         // request missing headers
         let missing_headers = self
             .hashes
@@ -98,6 +98,10 @@ impl Handleable for GetBlockHeadersResponse {
                 Ok(())
             };
 
+        ctx.manager
+            .sync_progress
+            .on_headers_received(&ctx.node_id, self.headers.len());
+
         let chosen_peer = if timestamp_validation_result.is_ok() {
             Some(ctx.node_id.clone())
         } else {
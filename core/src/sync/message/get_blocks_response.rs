@@ -63,16 +63,22 @@ impl Handleable for GetBlocksResponse {
             .cloned()
             .collect();
 
+        ctx.manager
+            .sync_progress
+            .on_blocks_received(&ctx.node_id, self.blocks.len());
+        let (blocks, requested_blocks) =
+            ctx.manager.buffer_block_bodies(self.blocks, requested_blocks);
         ctx.manager.recover_public_queue.dispatch(
             ctx.io,
             RecoverPublicTask::new(
-                self.blocks,
+                blocks,
                 requested_blocks,
                 ctx.node_id.clone(),
                 false,
                 delay,
             ),
         );
+        ctx.manager.request_scheduled_block_bodies(ctx.io);
 
         Ok(())
     }
@@ -111,16 +117,22 @@ impl Handleable for GetBlocksWithPublicResponse {
             req.hashes.iter().cloned().collect()
         };
 
+        ctx.manager
+            .sync_progress
+            .on_blocks_received(&ctx.node_id, self.blocks.len());
+        let (blocks, req_hashes) =
+            ctx.manager.buffer_block_bodies(self.blocks, req_hashes);
         ctx.manager.recover_public_queue.dispatch(
             ctx.io,
             RecoverPublicTask::new(
-                self.blocks,
+                blocks,
                 req_hashes,
                 ctx.node_id.clone(),
                 false, /* compact */
                 delay,
             ),
         );
+        ctx.manager.request_scheduled_block_bodies(ctx.io);
 
         Ok(())
     }
@@ -8,7 +8,7 @@ use crate::{
         request_manager::RequestMessage, Error, SynchronizationProtocolHandler,
     },
 };
-use network::{node_table::NodeId, NetworkContext};
+use network::{node_table::NodeId, service::ProtocolVersion, NetworkContext};
 
 pub struct Context<'a> {
     pub io: &'a dyn NetworkContext,
@@ -35,6 +35,12 @@ impl<'a> Context<'a> {
     }
 
     pub fn node_id(&self) -> NodeId { self.node_id.clone() }
+
+    /// The protocol version negotiated with the peer, or `None` for local
+    /// messages.
+    pub fn peer_version(&self) -> Option<ProtocolVersion> {
+        self.manager.syn.get_negotiated_version(&self.node_id)
+    }
 }
 
 // todo merge with Request and RequestContext!!!
@@ -9,8 +9,8 @@ use crate::{
         MessageProtocolVersionBound, MsgId, RequestId, SetRequestId,
     },
     sync::{
-        message::throttling::Throttle, Error, SYNC_PROTO_V1, SYNC_PROTO_V2,
-        SYNC_PROTO_V3,
+        message::throttling::Throttle, Error, ErrorKind, SYNC_PROTO_V1,
+        SYNC_PROTO_V2, SYNC_PROTO_V3,
     },
 };
 use network::{service::ProtocolVersion, NetworkProtocolHandler};
@@ -81,6 +81,7 @@ build_msg_impl! { GetCompactBlocksResponse, msgid::GET_CMPCT_BLOCKS_RESPONSE, "G
 build_msg_with_request_id_impl! { GetBlockTxn, msgid::GET_BLOCK_TXN, "GetBlockTxn", SYNC_PROTO_V1, SYNC_PROTO_V3 }
 build_msg_impl! { DynamicCapabilityChange, msgid::DYNAMIC_CAPABILITY_CHANGE, "DynamicCapabilityChange", SYNC_PROTO_V1, SYNC_PROTO_V3 }
 build_msg_with_request_id_impl! { GetBlockHashesByEpoch, msgid::GET_BLOCK_HASHES_BY_EPOCH, "GetBlockHashesByEpoch", SYNC_PROTO_V1, SYNC_PROTO_V3 }
+build_msg_with_request_id_impl! { GetSkeletonBlockHashes, msgid::GET_BLOCK_HASHES_BY_EPOCH, "GetSkeletonBlockHashes", SYNC_PROTO_V1, SYNC_PROTO_V3 }
 build_msg_impl! { Throttled, msgid::THROTTLED, "Throttled", SYNC_PROTO_V1, SYNC_PROTO_V3 }
 
 impl GetMaybeRequestId for GetBlockHashesResponse {}
@@ -109,6 +110,8 @@ mark_msg_version_bound!(GetBlocksResponse, SYNC_PROTO_V1, SYNC_PROTO_V3);
 impl Message for GetBlocksResponse {
     fn is_size_sensitive(&self) -> bool { self.blocks.len() > 0 }
 
+    fn is_compressible(&self) -> bool { true }
+
     fn msg_id(&self) -> MsgId { msgid::GET_BLOCKS_RESPONSE }
 
     fn msg_name(&self) -> &'static str { "GetBlocksResponse" }
@@ -123,6 +126,8 @@ mark_msg_version_bound!(
 impl Message for GetBlocksWithPublicResponse {
     fn is_size_sensitive(&self) -> bool { self.blocks.len() > 0 }
 
+    fn is_compressible(&self) -> bool { true }
+
     fn msg_id(&self) -> MsgId { msgid::GET_BLOCKS_WITH_PUBLIC_RESPONSE }
 
     fn msg_name(&self) -> &'static str { "GetBlocksWithPublicResponse" }
@@ -182,6 +187,12 @@ impl Message for GetTransactionsFromTxHashesResponse {
 /// If the message not handled, return `Ok(false)`.
 /// Otherwise, return `Ok(true)` if handled successfully
 /// or Err(e) on any error.
+///
+/// Peers of different protocol versions are served at the same time. A
+/// message is only accepted if it is valid in the version negotiated with the
+/// peer. When the wire format of a message changes, the new message could
+/// reuse the message id and be routed by the negotiated version, e.g.
+/// `msgid::FOO if ctx.peer_version() >= Some(SYNC_PROTO_V4) => ...`.
 pub fn handle_rlp_message(
     id: MsgId, ctx: &Context, rlp: &Rlp,
 ) -> Result<bool, Error> {
@@ -291,6 +302,18 @@ fn handle_message<T: Decodable + Handleable + Message>(
     let msg_name = msg.msg_name();
     let req_id = msg.get_request_id();
 
+    // Local messages are not bound to any negotiated version.
+    if let Some(peer_version) = ctx.peer_version() {
+        if peer_version < msg.version_introduced()
+            || peer_version > msg.version_valid_till()
+        {
+            bail!(ErrorKind::NotSupported(format!(
+                "message {} is not valid in negotiated version {}",
+                msg_name, peer_version
+            )));
+        }
+    }
+
     trace!(
         "handle sync protocol message, peer = {}, id = {}, name = {}, request_id = {:?}",
         ctx.node_id, msg_id, msg_name, req_id,
@@ -36,7 +36,9 @@ pub use self::{
     capability::{
         DynamicCapability, DynamicCapabilityChange, DynamicCapabilitySet,
     },
-    get_block_hashes_by_epoch::GetBlockHashesByEpoch,
+    get_block_hashes_by_epoch::{
+        GetBlockHashesByEpoch, GetSkeletonBlockHashes,
+    },
     get_block_hashes_response::GetBlockHashesResponse,
     get_block_headers::GetBlockHeaders,
     get_block_headers_response::GetBlockHeadersResponse,
@@ -19,9 +19,14 @@ pub struct SnapshotChunkResponse {
     pub chunk: Chunk,
 }
 
-build_msg_impl! {
-    SnapshotChunkResponse, msgid::GET_SNAPSHOT_CHUNK_RESPONSE,
-    "SnapshotChunkResponse", SYNC_PROTO_V1, SYNC_PROTO_V3
+impl GetMaybeRequestId for SnapshotChunkResponse {}
+mark_msg_version_bound!(SnapshotChunkResponse, SYNC_PROTO_V1, SYNC_PROTO_V3);
+impl Message for SnapshotChunkResponse {
+    fn is_compressible(&self) -> bool { true }
+
+    fn msg_id(&self) -> MsgId { msgid::GET_SNAPSHOT_CHUNK_RESPONSE }
+
+    fn msg_name(&self) -> &'static str { "SnapshotChunkResponse" }
 }
 
 impl Handleable for SnapshotChunkResponse {
@@ -48,6 +53,7 @@ impl Handleable for SnapshotChunkResponse {
                 .resend_request_to_another_peer(ctx.io, &message);
             return Err(e);
         }
+        ctx.manager.sync_progress.on_chunk_received(&ctx.node_id);
 
         ctx.manager.state_sync.handle_snapshot_chunk_response(
             ctx,
@@ -9,10 +9,13 @@ mod state;
 #[cfg(test)]
 pub mod tests;
 
+mod synchronization_body_scheduler;
 mod synchronization_graph;
 mod synchronization_phases;
+mod synchronization_progress;
 mod synchronization_protocol_handler;
 mod synchronization_service;
+mod synchronization_skeleton;
 mod synchronization_state;
 pub mod utils;
 
@@ -30,6 +33,7 @@ pub use self::{
         CatchUpSyncBlockPhase, NormalSyncPhase, SyncPhaseType,
         SynchronizationPhaseManager, SynchronizationPhaseTrait,
     },
+    synchronization_progress::{PeerContribution, SyncStatus},
     synchronization_protocol_handler::{
         LocalMessageTask, ProtocolConfiguration, SyncHandlerWorkType,
         SynchronizationProtocolHandler, CHECK_RPC_REQUEST_TIMER,
@@ -206,6 +206,16 @@ impl SnapshotChunkSync {
 
     pub fn status(&self) -> Status { self.inner.read().status }
 
+    /// Return the number of `(restored, total)` chunks if the chunks are
+    /// being downloaded.
+    pub fn chunk_progress(&self) -> Option<(usize, usize)> {
+        let inner = self.inner.read();
+        if !matches!(inner.status, Status::DownloadingChunks(_)) {
+            return None;
+        }
+        inner.chunk_manager.as_ref().map(|m| m.progress())
+    }
+
     pub fn handle_snapshot_manifest_response(
         &self, ctx: &Context, response: SnapshotManifestResponse,
         request: &SnapshotManifestRequest,
@@ -222,25 +232,33 @@ impl SnapshotChunkSync {
             let r = manifest_manager
                 .handle_snapshot_manifest_response(ctx, response, request)?;
             if let Some(related_data) = r {
+                let chunk_manager = SnapshotChunkManager::new_and_start(
+                    ctx,
+                    manifest_manager.snapshot_candidate.clone(),
+                    related_data.snapshot_info.clone(),
+                    manifest_manager.chunk_boundaries.clone(),
+                    manifest_manager.chunk_boundary_proofs.clone(),
+                    manifest_manager.active_peers.clone(),
+                    self.config.chunk_config(),
+                )?;
                 // update status
-                inner.status = Status::DownloadingChunks(Instant::now());
-                inner.chunk_manager =
-                    Some(SnapshotChunkManager::new_and_start(
-                        ctx,
-                        manifest_manager.snapshot_candidate.clone(),
-                        related_data.snapshot_info.clone(),
-                        manifest_manager.chunk_boundaries.clone(),
-                        manifest_manager.chunk_boundary_proofs.clone(),
-                        manifest_manager.active_peers.clone(),
-                        self.config.chunk_config(),
-                    )?);
+                inner.status = if chunk_manager.is_completed() {
+                    // All chunks were restored before restart.
+                    Status::Completed
+                } else {
+                    Status::DownloadingChunks(Instant::now())
+                };
+                inner.chunk_manager = Some(chunk_manager);
                 inner.related_data = Some(related_data);
             }
             debug!("sync state progress: {:?}", *inner);
         } else {
             error!("manifest manager is None in status {:?}", inner.status);
         }
-        if matches!(inner.status, Status::DownloadingChunks(_)) {
+        if matches!(
+            inner.status,
+            Status::DownloadingChunks(_) | Status::Completed
+        ) {
             inner.manifest_manager = None;
         }
         Ok(())
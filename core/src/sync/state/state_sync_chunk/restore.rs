@@ -37,6 +37,19 @@ impl Restorer {
         self.verifier = Some(verifier);
     }
 
+    /// Returns true if the chunk has already been restored, possibly before
+    /// a restart.
+    pub fn is_chunk_restored(&self, key: &ChunkKey) -> bool {
+        self.verifier
+            .as_ref()
+            .map_or(false, |v| v.is_chunk_verified(&key.upper_bound_excl))
+    }
+
+    /// Returns true if all chunks have been restored.
+    pub fn is_completed(&self) -> bool {
+        self.verifier.as_ref().map_or(false, |v| v.is_completed())
+    }
+
     /// Append a chunk for restoration.
     pub fn append(&mut self, key: ChunkKey, chunk: Chunk) -> bool {
         match &mut self.verifier {
@@ -26,9 +26,11 @@ pub struct SnapshotChunkManager {
     pending_chunks: VecDeque<ChunkKey>,
     downloading_chunks: HashMap<ChunkKey, DownloadingChunkStatus>,
     num_downloaded: usize,
+    num_chunks: usize,
     config: SnapshotChunkConfig,
 
     restorer: Restorer,
+    completed: bool,
 }
 
 impl SnapshotChunkManager {
@@ -62,20 +64,60 @@ impl SnapshotChunkManager {
         restorer.initialize_verifier(verifier);
         let chunks =
             RangedManifest::convert_boundaries_to_chunks(chunk_boundaries);
+        let num_chunks = chunks.len();
+        // Skip the chunks restored before restart.
+        let pending_chunks: VecDeque<ChunkKey> = chunks
+            .into_iter()
+            .filter(|chunk_key| !restorer.is_chunk_restored(chunk_key))
+            .collect();
+        if pending_chunks.len() < num_chunks {
+            info!(
+                "Resume snapshot chunk sync, {}/{} chunks are restored",
+                num_chunks - pending_chunks.len(),
+                num_chunks
+            );
+        }
         let mut chunk_manager = Self {
             snapshot_candidate,
             snapshot_info,
             active_peers,
-            pending_chunks: chunks.into(),
+            num_downloaded: num_chunks - pending_chunks.len(),
+            num_chunks,
+            pending_chunks,
             downloading_chunks: Default::default(),
-            num_downloaded: 0,
             config,
             restorer,
+            completed: false,
         };
-        chunk_manager.request_chunks(ctx);
+        if chunk_manager.restorer.is_completed() {
+            chunk_manager.finalize_restoration(ctx)?;
+        } else {
+            chunk_manager.request_chunks(ctx);
+        }
         Ok(chunk_manager)
     }
 
+    /// Returns true if all chunks have been restored and the snapshot is
+    /// reconstructed.
+    pub fn is_completed(&self) -> bool { self.completed }
+
+    /// Return the number of `(restored, total)` chunks.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.num_downloaded, self.num_chunks)
+    }
+
+    fn finalize_restoration(&mut self, ctx: &Context) -> StorageResult<()> {
+        debug!("Snapshot chunks are all downloaded",);
+
+        // start to restore and update status
+        self.restorer.finalize_restoration(
+            ctx.manager.graph.data_man.storage_manager.clone(),
+            self.snapshot_info.clone(),
+        )?;
+        self.completed = true;
+        Ok(())
+    }
+
     /// Add a received chunk, and request new ones if needed.
     /// Return `Ok(true)` if all chunks have been received and the snapshot is
     /// reconstructed. Return `Ok(false)` if there are chunks missing.
@@ -95,9 +137,9 @@ impl SnapshotChunkManager {
             return Ok(false);
         }
 
-        self.num_downloaded += 1;
-
-        if !self.restorer.append(chunk_key.clone(), chunk) {
+        if self.restorer.append(chunk_key.clone(), chunk) {
+            self.num_downloaded += 1;
+        } else {
             warn!("Receive invalid chunk during appending {:?}", chunk_key);
             self.pending_chunks.push_back(chunk_key);
             self.note_failure(&ctx.node_id)
@@ -106,13 +148,7 @@ impl SnapshotChunkManager {
         // begin to restore if all chunks downloaded
         if self.downloading_chunks.is_empty() && self.pending_chunks.is_empty()
         {
-            debug!("Snapshot chunks are all downloaded",);
-
-            // start to restore and update status
-            self.restorer.finalize_restoration(
-                ctx.manager.graph.data_man.storage_manager.clone(),
-                self.snapshot_info.clone(),
-            )?;
+            self.finalize_restoration(ctx)?;
             return Ok(true);
         }
         self.request_chunks(ctx);
@@ -148,19 +184,37 @@ impl SnapshotChunkManager {
         Some(chunk_key)
     }
 
-    /// Request multiple chunks from random peers.
+    /// Request chunks in parallel, striping them over the active peers. Each
+    /// chunk is assigned to the peer with the fewest chunks in flight, so
+    /// that the download is spread evenly over all peers.
     fn request_chunks(&mut self, ctx: &Context) {
-        let chosen_peers = PeerFilter::new(msgid::GET_SNAPSHOT_CHUNK)
+        let peers = PeerFilter::new(msgid::GET_SNAPSHOT_CHUNK)
             .choose_from(&self.active_peers)
-            .select_n(
-                self.config.max_downloading_chunks
-                    - self.downloading_chunks.len(),
-                &ctx.manager.syn,
-            );
-        for peer in chosen_peers {
+            .select_all(&ctx.manager.syn);
+        if peers.is_empty() {
+            return;
+        }
+        let mut inflight_by_peer: HashMap<NodeId, usize> =
+            peers.into_iter().map(|peer| (peer, 0)).collect();
+        for status in self.downloading_chunks.values() {
+            if let Some(inflight) = inflight_by_peer.get_mut(&status.peer) {
+                *inflight += 1;
+            }
+        }
+
+        while self.downloading_chunks.len() < self.config.max_downloading_chunks
+        {
+            let peer = match inflight_by_peer
+                .iter()
+                .min_by_key(|(_, inflight)| **inflight)
+            {
+                Some((peer, _)) => *peer,
+                None => break,
+            };
             if self.request_chunk_from_peer(ctx, &peer).is_none() {
                 break;
             }
+            *inflight_by_peer.get_mut(&peer).expect("peer exists") += 1;
         }
     }
 
@@ -188,6 +242,18 @@ impl SnapshotChunkManager {
 
     pub fn on_peer_disconnected(&mut self, peer: &NodeId) {
         self.active_peers.remove(peer);
+        // Chunks requested from the peer will never be returned, so request
+        // them from other peers without waiting for timeout.
+        let lost_chunks: Vec<ChunkKey> = self
+            .downloading_chunks
+            .iter()
+            .filter(|(_, status)| status.peer == *peer)
+            .map(|(chunk_key, _)| chunk_key.clone())
+            .collect();
+        for chunk_key in lost_chunks {
+            self.downloading_chunks.remove(&chunk_key);
+            self.pending_chunks.push_front(chunk_key);
+        }
     }
 
     fn note_failure(&mut self, node_id: &NodeId) {
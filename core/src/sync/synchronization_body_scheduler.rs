@@ -0,0 +1,343 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Out-of-order block body download during catch-up.
+//!
+//! The block hashes of the epochs to sync are added in epoch order, and the
+//! bodies are requested in batches from many peers concurrently, each peer
+//! with a bounded number of blocks in flight. Received bodies are buffered
+//! until all the blocks of the first epoch are received, and then released
+//! in epoch order, so that the consensus layer is fed steadily while the
+//! later epochs are still being downloaded.
+//!
+//! Once the buffered bodies reach the memory cap, only the blocks of the
+//! first epoch are requested, so that the buffer can always be drained.
+
+use cfx_types::H256;
+use network::node_table::NodeId;
+use primitives::Block;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// The maximum number of blocks requested in a request.
+const BODY_REQUEST_BATCH_SIZE: usize = 32;
+/// The maximum number of blocks in flight from a peer.
+const MAX_INFLIGHT_BODIES_PER_PEER: usize = 4 * BODY_REQUEST_BATCH_SIZE;
+
+#[derive(Default)]
+struct EpochBodies {
+    /// The blocks that are not received yet.
+    missing: HashSet<H256>,
+    received: Vec<Block>,
+}
+
+pub struct BlockBodyScheduler {
+    max_buffer_size: usize,
+    epochs: BTreeMap<u64, EpochBodies>,
+    /// The blocks to request, ordered by epoch.
+    pending: BTreeSet<(u64, H256)>,
+    /// The epoch of each block that is not received yet.
+    scheduled: HashMap<H256, u64>,
+    /// The peer that each block in flight is requested from.
+    requested: HashMap<H256, NodeId>,
+    /// The number of blocks in flight from each peer.
+    inflight: HashMap<NodeId, usize>,
+    /// The approximated size of the buffered bodies.
+    buffer_size: usize,
+    /// The last time that some epoch was released.
+    last_release: Instant,
+}
+
+impl BlockBodyScheduler {
+    pub fn new(max_buffer_size: usize) -> Self {
+        BlockBodyScheduler {
+            max_buffer_size,
+            epochs: BTreeMap::new(),
+            pending: BTreeSet::new(),
+            scheduled: HashMap::new(),
+            requested: HashMap::new(),
+            inflight: HashMap::new(),
+            buffer_size: 0,
+            last_release: Instant::now(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.epochs.is_empty() }
+
+    pub fn buffer_size(&self) -> usize { self.buffer_size }
+
+    /// Add the blocks of `epoch` to download. The hashes that are already
+    /// scheduled are ignored.
+    pub fn add_epoch(&mut self, epoch: u64, hashes: Vec<H256>) {
+        if self.epochs.is_empty() {
+            self.last_release = Instant::now();
+        }
+        for hash in hashes {
+            if self.scheduled.contains_key(&hash) {
+                continue;
+            }
+            self.scheduled.insert(hash, epoch);
+            self.epochs
+                .entry(epoch)
+                .or_insert_with(Default::default)
+                .missing
+                .insert(hash);
+            self.pending.insert((epoch, hash));
+        }
+    }
+
+    /// Assign the pending blocks to `peers` in batches, in epoch order.
+    /// Each batch goes to the peer with the fewest blocks in flight.
+    pub fn schedule(&mut self, peers: &[NodeId]) -> Vec<(NodeId, Vec<H256>)> {
+        let first_epoch = match self.epochs.keys().next() {
+            Some(epoch) => *epoch,
+            None => return Vec::new(),
+        };
+        // Beyond the memory cap, only the first epoch is requested so that
+        // the buffer can still be drained.
+        let over_cap = self.buffer_size >= self.max_buffer_size;
+
+        let mut requests = Vec::new();
+        loop {
+            let peer = match peers
+                .iter()
+                .map(|peer| (*peer, *self.inflight.get(peer).unwrap_or(&0)))
+                .filter(|(_, inflight)| {
+                    *inflight + BODY_REQUEST_BATCH_SIZE
+                        <= MAX_INFLIGHT_BODIES_PER_PEER
+                })
+                .min_by_key(|(_, inflight)| *inflight)
+            {
+                Some((peer, _)) => peer,
+                None => break,
+            };
+            let batch: Vec<(u64, H256)> = self
+                .pending
+                .iter()
+                .take_while(|(epoch, _)| !over_cap || *epoch == first_epoch)
+                .take(BODY_REQUEST_BATCH_SIZE)
+                .cloned()
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+
+            for (epoch, hash) in &batch {
+                self.pending.remove(&(*epoch, *hash));
+                self.requested.insert(*hash, peer);
+            }
+            *self.inflight.entry(peer).or_insert(0) += batch.len();
+            requests.push((peer, batch.into_iter().map(|(_, h)| h).collect()));
+        }
+        requests
+    }
+
+    /// Buffer the scheduled blocks among `blocks`, and return the others.
+    pub fn on_blocks_received(&mut self, blocks: Vec<Block>) -> Vec<Block> {
+        let mut unscheduled = Vec::new();
+        for block in blocks {
+            let hash = block.hash();
+            let epoch = match self.scheduled.remove(&hash) {
+                Some(epoch) => epoch,
+                None => {
+                    unscheduled.push(block);
+                    continue;
+                }
+            };
+            // The block may be received before it is requested by us, e.g.
+            // as a dependency of other blocks.
+            self.pending.remove(&(epoch, hash));
+            self.on_request_finished(&hash);
+            let bodies = self.epochs.get_mut(&epoch).expect("epoch exists");
+            bodies.missing.remove(&hash);
+            self.buffer_size += block.approximated_rlp_size();
+            bodies.received.push(block);
+        }
+        unscheduled
+    }
+
+    /// Return the buffered blocks of the leading epochs whose blocks are all
+    /// received, in epoch order.
+    pub fn pop_ready(&mut self) -> Vec<Block> {
+        let mut ready = Vec::new();
+        while let Some(epoch) = self.epochs.keys().next().cloned() {
+            if !self.epochs[&epoch].missing.is_empty() {
+                break;
+            }
+            self.release_epoch(epoch, &mut ready);
+        }
+        ready
+    }
+
+    /// If no epoch has been released within `timeout`, e.g. some block of
+    /// the first epoch can not be received, give up the ordering of the first
+    /// epoch and return its received blocks along with the following ready
+    /// ones.
+    pub fn pop_stalled(&mut self, timeout: Duration) -> Vec<Block> {
+        if self.last_release.elapsed() < timeout {
+            return Vec::new();
+        }
+        let epoch = match self.epochs.keys().next() {
+            Some(epoch) => *epoch,
+            None => return Vec::new(),
+        };
+        debug!(
+            "Block bodies of epoch {} stalled, missing {:?}",
+            epoch, self.epochs[&epoch].missing
+        );
+        let mut ready = Vec::new();
+        self.release_epoch(epoch, &mut ready);
+        ready.extend(self.pop_ready());
+        ready
+    }
+
+    /// Return all the buffered blocks in epoch order and clear the scheduler.
+    pub fn drain(&mut self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        while let Some(epoch) = self.epochs.keys().next().cloned() {
+            self.release_epoch(epoch, &mut blocks);
+        }
+        self.inflight.clear();
+        blocks
+    }
+
+    pub fn on_peer_disconnected(&mut self, peer: &NodeId) {
+        // The requests to the peer are sent to other peers by the request
+        // manager, so the blocks are still expected.
+        self.inflight.remove(peer);
+    }
+
+    fn on_request_finished(&mut self, hash: &H256) {
+        if let Some(peer) = self.requested.remove(hash) {
+            if let Some(inflight) = self.inflight.get_mut(&peer) {
+                *inflight = inflight.saturating_sub(1);
+            }
+        }
+    }
+
+    fn release_epoch(&mut self, epoch: u64, ready: &mut Vec<Block>) {
+        let bodies = match self.epochs.remove(&epoch) {
+            Some(bodies) => bodies,
+            None => return,
+        };
+        for hash in &bodies.missing {
+            self.pending.remove(&(epoch, *hash));
+            self.scheduled.remove(hash);
+            self.on_request_finished(hash);
+        }
+        for block in &bodies.received {
+            self.buffer_size -= block.approximated_rlp_size();
+        }
+        ready.extend(bodies.received);
+        self.last_release = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockBodyScheduler, BODY_REQUEST_BATCH_SIZE};
+    use cfx_types::H256;
+    use network::node_table::NodeId;
+    use primitives::{Block, BlockHeaderBuilder};
+    use std::time::Duration;
+
+    fn new_block(height: u64) -> Block {
+        let mut header = BlockHeaderBuilder::new()
+            .with_parent_hash(H256::random())
+            .with_height(height)
+            .build();
+        header.compute_hash();
+        Block::new(header, vec![])
+    }
+
+    #[test]
+    fn test_release_in_epoch_order() {
+        let mut scheduler = BlockBodyScheduler::new(usize::max_value());
+        let blocks: Vec<Block> = (1..=3).map(new_block).collect();
+        for (epoch, block) in blocks.iter().enumerate() {
+            scheduler.add_epoch(epoch as u64 + 1, vec![block.hash()]);
+        }
+
+        let peers = vec![NodeId::random(), NodeId::random()];
+        let requests = scheduler.schedule(&peers);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1.len(), 3);
+
+        // Later epochs are buffered until the first epoch is received.
+        let unscheduled = scheduler.on_blocks_received(vec![
+            blocks[2].clone(),
+            blocks[1].clone(),
+            new_block(4),
+        ]);
+        assert_eq!(unscheduled.len(), 1);
+        assert!(scheduler.pop_ready().is_empty());
+        assert!(scheduler.buffer_size() > 0);
+
+        scheduler.on_blocks_received(vec![blocks[0].clone()]);
+        let ready: Vec<H256> =
+            scheduler.pop_ready().iter().map(|b| b.hash()).collect();
+        assert_eq!(
+            ready,
+            blocks.iter().map(|b| b.hash()).collect::<Vec<H256>>()
+        );
+        assert!(scheduler.is_empty());
+        assert_eq!(scheduler.buffer_size(), 0);
+    }
+
+    #[test]
+    fn test_schedule_over_peers() {
+        let mut scheduler = BlockBodyScheduler::new(usize::max_value());
+        for epoch in 0..(BODY_REQUEST_BATCH_SIZE as u64 * 3) {
+            scheduler.add_epoch(epoch, vec![H256::random()]);
+        }
+        let peers = vec![NodeId::random(), NodeId::random()];
+        let requests = scheduler.schedule(&peers);
+        assert_eq!(requests.len(), 3);
+        // Batches are spread over peers.
+        assert_ne!(requests[0].0, requests[1].0);
+        // Nothing is left to schedule.
+        assert!(scheduler.schedule(&peers).is_empty());
+    }
+
+    #[test]
+    fn test_memory_cap() {
+        let mut scheduler = BlockBodyScheduler::new(1);
+        let blocks: Vec<Block> = (0..3).map(new_block).collect();
+        scheduler.add_epoch(0, vec![blocks[0].hash()]);
+        scheduler.add_epoch(1, vec![blocks[1].hash()]);
+        let peers = vec![NodeId::random()];
+        scheduler.schedule(&peers);
+        scheduler.on_blocks_received(vec![blocks[1].clone()]);
+
+        // Over the cap, only the first epoch is requested.
+        scheduler.add_epoch(2, vec![blocks[2].hash()]);
+        assert!(scheduler.schedule(&peers).is_empty());
+
+        scheduler.on_blocks_received(vec![blocks[0].clone()]);
+        assert_eq!(scheduler.pop_ready().len(), 2);
+        let requests = scheduler.schedule(&peers);
+        assert_eq!(requests, vec![(peers[0], vec![blocks[2].hash()])]);
+    }
+
+    #[test]
+    fn test_stalled() {
+        let mut scheduler = BlockBodyScheduler::new(usize::max_value());
+        let blocks: Vec<Block> = (0..3).map(new_block).collect();
+        scheduler.add_epoch(0, vec![blocks[0].hash(), blocks[1].hash()]);
+        scheduler.add_epoch(1, vec![blocks[2].hash()]);
+        scheduler.schedule(&[NodeId::random()]);
+        scheduler
+            .on_blocks_received(vec![blocks[1].clone(), blocks[2].clone()]);
+        assert!(scheduler.pop_stalled(Duration::from_secs(60)).is_empty());
+
+        let ready = scheduler.pop_stalled(Duration::from_secs(0));
+        assert_eq!(ready.len(), 2);
+        assert!(scheduler.is_empty());
+        // The given up block is not buffered any more.
+        let unscheduled = scheduler.on_blocks_received(vec![blocks[0].clone()]);
+        assert_eq!(unscheduled.len(), 1);
+    }
+}
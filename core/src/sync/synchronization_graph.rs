@@ -4,7 +4,7 @@
 
 use crate::{
     block_data_manager::{BlockDataManager, BlockStatus},
-    channel::Channel,
+    channel::{Channel, Receiver},
     consensus::SharedConsensusGraph,
     error::{BlockError, Error, ErrorKind},
     machine::Machine,
@@ -13,11 +13,11 @@ use crate::{
     statistics::SharedStatistics,
     sync::synchronization_protocol_handler::FutureBlockContainer,
     verification::*,
+    watchdog::{Heartbeat, IDLE_BEAT_INTERVAL, WATCHDOG},
     ConsensusGraph, NodeType, Notifications,
 };
 use cfx_parameters::sync::OLD_ERA_BLOCK_GC_BATCH_SIZE;
 use cfx_types::{H256, U256};
-use futures::executor::block_on;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use metrics::{
@@ -49,6 +49,8 @@ lazy_static! {
         register_meter_with_group("timer", "sync::insert_block");
     static ref CONSENSUS_WORKER_QUEUE: Arc<dyn Queue> =
         register_queue("consensus_worker_queue");
+    static ref CONSENSUS_HEARTBEAT: Arc<Heartbeat> =
+        WATCHDOG.heartbeat("consensus");
 }
 
 const NULL: usize = !0;
@@ -1042,6 +1044,9 @@ pub struct SynchronizationGraph {
     /// Each element is <block_hash, ignore_body>
     new_block_hashes: Arc<Channel<(H256, bool)>>,
 
+    /// Channel used by the consensus worker to notify the processed blocks.
+    blocks_processed: Arc<Channel<H256>>,
+
     /// The blocks whose timestamps are near future.
     /// They will be inserted into sync graph inner at their timestamp.
     pub future_blocks: FutureBlockContainer,
@@ -1096,6 +1101,7 @@ impl SynchronizationGraph {
         // worker will be blocked on waiting the first block forever.
         let consensus_unprocessed_count = Arc::new(AtomicUsize::new(0));
         let mut consensus_receiver = notifications.new_block_hashes.subscribe();
+        let blocks_processed = notifications.blocks_processed.clone();
         let inner = Arc::new(RwLock::new(
             SynchronizationGraphInner::with_genesis_block(
                 genesis_block_header.clone(),
@@ -1120,6 +1126,7 @@ impl SynchronizationGraph {
             statistics: consensus.get_statistics().clone(),
             consensus_unprocessed_count: consensus_unprocessed_count.clone(),
             new_block_hashes: notifications.new_block_hashes.clone(),
+            blocks_processed: blocks_processed.clone(),
             node_type,
             machine,
         };
@@ -1139,6 +1146,7 @@ impl SynchronizationGraph {
                 let mut counter_map = HashMap::new();
 
                 'outer: loop {
+                    CONSENSUS_HEARTBEAT.beat();
                     // Only block when we have processed all received blocks.
                     let mut blocking = priority_queue.is_empty();
                     'inner: loop {
@@ -1146,9 +1154,11 @@ impl SynchronizationGraph {
                         // with non-blocking `try_recv`.
                         let maybe_item = if blocking {
                             blocking = false;
-                            match block_on(consensus_receiver.recv()) {
-                                Some(item) => Ok(item),
-                                None => break 'outer,
+                            // The wait is bounded, so that the idle loop beats.
+                            match consensus_receiver.recv_with_timeout(IDLE_BEAT_INTERVAL) {
+                                Ok(Some(item)) => Ok(item),
+                                Ok(None) => break 'outer,
+                                Err(_) => continue 'outer,
                             }
                         } else {
                             consensus_receiver.try_recv()
@@ -1200,12 +1210,15 @@ impl SynchronizationGraph {
                                 priority_queue.push((epoch_number, succ, ignore_body));
                             }
                         }
+                        let _task = CONSENSUS_HEARTBEAT
+                            .task(format!("on_new_block {:?}", hash));
                         consensus.on_new_block(
                             &hash,
                             ignore_body,
                             true, /* update_best_info */
                         );
                         consensus_unprocessed_count.fetch_sub(1, Ordering::SeqCst);
+                        blocks_processed.send(hash);
                     }
                 }
             })
@@ -1917,9 +1930,13 @@ impl SynchronizationGraph {
         inner.arena[me].block_ready = true;
 
         if need_to_verify {
-            let r = self
-                .verification_config
-                .verify_block_basic(&block, self.consensus.best_chain_id());
+            let height = block.block_header.height();
+            let spec = self.machine.spec(height);
+            let r = self.verification_config.verify_block_basic(
+                &block,
+                &self.consensus.get_config().chain_id,
+                &spec,
+            );
             match r {
                 Err(Error(
                     ErrorKind::Block(BlockError::InvalidTransactionsRoot(e)),
@@ -2187,6 +2204,68 @@ impl SynchronizationGraph {
     pub fn is_consensus_worker_busy(&self) -> bool {
         self.consensus_unprocessed_count.load(Ordering::SeqCst) != 0
     }
+
+    /// Block until the consensus worker processes all the blocks received so
+    /// far. `receiver` is a subscription to the processed blocks taken before
+    /// the blocks are inserted, see `subscribe_processed_blocks`.
+    pub fn wait_for_consensus_worker(&self, receiver: &mut Receiver<H256>) {
+        while self.is_consensus_worker_busy() {
+            if receiver.recv_blocking().is_none() {
+                return;
+            }
+        }
+    }
+
+    pub fn subscribe_processed_blocks(&self) -> Receiver<H256> {
+        self.blocks_processed.subscribe()
+    }
+
+    /// Insert a block read from a local source, e.g. a block archive file,
+    /// with full verification. Its parent and referees must be inserted
+    /// before. Returns whether the block is new.
+    pub fn import_block(&self, mut block: Block) -> Result<bool, String> {
+        self.data_man
+            .recover_block(&mut block)
+            .map_err(|e| format!("Invalid transactions: {:?}", e))?;
+        let hash = block.hash();
+        if self.contains_block(&hash)
+            || self.data_man.block_by_hash(&hash, false).is_some()
+        {
+            return Ok(false);
+        }
+        let dependencies = std::iter::once(block.block_header.parent_hash())
+            .chain(block.block_header.referee_hashes());
+        for dependency in dependencies {
+            if !self.contains_block_header(dependency) {
+                return Err(format!(
+                    "The parent or referee {:?} is not imported",
+                    dependency
+                ));
+            }
+        }
+
+        if !self.contains_block_header(&hash) {
+            let (insert_result, _) = self.insert_block_header(
+                &mut block.block_header,
+                true,  /* need_to_verify */
+                false, /* bench_mode */
+                false, /* insert_to_consensus */
+                true,  /* persistent */
+            );
+            if insert_result.is_invalid() {
+                return Err("Invalid block header".into());
+            }
+        }
+        let insert_result = self.insert_block(
+            block, true,  /* need_to_verify */
+            true,  /* persistent */
+            false, /* recover_from_db */
+        );
+        if !insert_result.is_valid() {
+            return Err("Invalid block".into());
+        }
+        Ok(true)
+    }
 }
 
 pub enum BlockInsertionResult {
@@ -671,6 +671,7 @@ impl SynchronizationPhaseTrait for NormalSyncPhase {
     )
     {
         info!("start phase {:?}", self.name());
+        sync_handler.flush_block_bodies(io);
         sync_handler.request_missing_terminals(io);
     }
 }
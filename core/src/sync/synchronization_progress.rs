@@ -0,0 +1,277 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Progress of synchronization.
+//!
+//! The headers, blocks and state chunks received during sync are counted
+//! along with the contribution of each peer. The best epoch and the restored
+//! state chunks are sampled periodically to estimate the sync speed, so that
+//! operators can tell whether catch-up is making progress and when it is
+//! expected to finish.
+
+use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
+use network::node_table::NodeId;
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The number of recent samples used to estimate the sync speed.
+const MAX_RATE_SAMPLES: usize = 12;
+
+/// The data received from a peer during sync.
+#[derive(Clone, Copy, Debug, Default, PartialEq, DeriveMallocSizeOf)]
+pub struct PeerContribution {
+    pub headers: u64,
+    pub blocks: u64,
+    pub chunks: u64,
+}
+
+/// A snapshot of the sync progress.
+#[derive(Clone, Debug)]
+pub struct SyncStatus {
+    pub phase: &'static str,
+    pub catch_up_mode: bool,
+    pub best_epoch: u64,
+    /// The median epoch of peers, which is the target to catch up with.
+    pub target_epoch: Option<u64>,
+    pub headers_received: u64,
+    pub blocks_received: u64,
+    /// `(restored, total)` chunks of the state being synced.
+    pub state_chunks: Option<(usize, usize)>,
+    /// The estimated time to finish the current phase.
+    pub eta: Option<Duration>,
+    pub peers: Vec<(NodeId, PeerContribution)>,
+}
+
+impl fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "phase={} epoch={}", self.phase, self.best_epoch)?;
+        if let Some(target_epoch) = self.target_epoch {
+            write!(f, "/{}", target_epoch)?;
+        }
+        write!(
+            f,
+            " headers={} blocks={}",
+            self.headers_received, self.blocks_received
+        )?;
+        if let Some((restored, total)) = self.state_chunks {
+            write!(f, " chunks={}/{}", restored, total)?;
+        }
+        match self.eta {
+            Some(eta) => write!(f, " eta={}s", eta.as_secs())?,
+            None => write!(f, " eta=unknown")?,
+        }
+        write!(f, " peers={}", self.peers.len())
+    }
+}
+
+/// Estimate the time to reach a target value from the speed of recent
+/// samples.
+#[derive(Default, DeriveMallocSizeOf)]
+struct RateEstimator {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateEstimator {
+    fn sample(&mut self, time: Instant, value: u64) {
+        // The value goes back if the sync restarts, e.g. a new snapshot is
+        // chosen, so the earlier samples are useless.
+        if let Some((_, last)) = self.samples.back() {
+            if value < *last {
+                self.samples.clear();
+            }
+        }
+        self.samples.push_back((time, value));
+        while self.samples.len() > MAX_RATE_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    fn clear(&mut self) { self.samples.clear(); }
+
+    /// Return `None` if there is no progress in the recent samples.
+    fn eta(&self, target: u64) -> Option<Duration> {
+        let (first_time, first) = self.samples.front()?;
+        let (last_time, last) = self.samples.back()?;
+        if target <= *last {
+            return Some(Duration::from_secs(0));
+        }
+        let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+        if last <= first || elapsed == 0.0 {
+            return None;
+        }
+        let rate = (last - first) as f64 / elapsed;
+        Some(Duration::from_secs_f64((target - last) as f64 / rate))
+    }
+}
+
+#[derive(Default, DeriveMallocSizeOf)]
+struct SyncProgressInner {
+    headers: u64,
+    blocks: u64,
+    chunks: u64,
+    /// The contribution of connected peers.
+    peers: HashMap<NodeId, PeerContribution>,
+    epoch_rate: RateEstimator,
+    chunk_rate: RateEstimator,
+}
+
+#[derive(Default, DeriveMallocSizeOf)]
+pub struct SyncProgress {
+    inner: Mutex<SyncProgressInner>,
+}
+
+impl SyncProgress {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn on_headers_received(&self, peer: &NodeId, count: usize) {
+        let mut inner = self.inner.lock();
+        inner.headers += count as u64;
+        inner.peers.entry(*peer).or_default().headers += count as u64;
+    }
+
+    pub fn on_blocks_received(&self, peer: &NodeId, count: usize) {
+        let mut inner = self.inner.lock();
+        inner.blocks += count as u64;
+        inner.peers.entry(*peer).or_default().blocks += count as u64;
+    }
+
+    pub fn on_chunk_received(&self, peer: &NodeId) {
+        let mut inner = self.inner.lock();
+        inner.chunks += 1;
+        inner.peers.entry(*peer).or_default().chunks += 1;
+    }
+
+    pub fn on_peer_disconnected(&self, peer: &NodeId) {
+        self.inner.lock().peers.remove(peer);
+    }
+
+    /// Sample the best epoch and the restored state chunks, if the state is
+    /// being synced, to estimate the sync speed.
+    pub fn sample(&self, best_epoch: u64, chunks_restored: Option<usize>) {
+        self.sample_at(Instant::now(), best_epoch, chunks_restored);
+    }
+
+    fn sample_at(
+        &self, now: Instant, best_epoch: u64, chunks_restored: Option<usize>,
+    ) {
+        let mut inner = self.inner.lock();
+        inner.epoch_rate.sample(now, best_epoch);
+        match chunks_restored {
+            Some(restored) => inner.chunk_rate.sample(now, restored as u64),
+            None => inner.chunk_rate.clear(),
+        }
+    }
+
+    /// Build the sync status. The ETA is estimated from the restored chunks
+    /// while the state is being synced, and from the best epoch otherwise.
+    pub fn status(
+        &self, phase: &'static str, catch_up_mode: bool, best_epoch: u64,
+        target_epoch: Option<u64>, state_chunks: Option<(usize, usize)>,
+    ) -> SyncStatus
+    {
+        let inner = self.inner.lock();
+        let eta = if !catch_up_mode {
+            None
+        } else if let Some((_, total)) = state_chunks {
+            inner.chunk_rate.eta(total as u64)
+        } else {
+            target_epoch.and_then(|target| inner.epoch_rate.eta(target))
+        };
+        let mut peers: Vec<(NodeId, PeerContribution)> = inner
+            .peers
+            .iter()
+            .map(|(peer, contribution)| (*peer, *contribution))
+            .collect();
+        peers.sort_by_key(|(_, c)| {
+            std::cmp::Reverse(c.headers + c.blocks + c.chunks)
+        });
+
+        SyncStatus {
+            phase,
+            catch_up_mode,
+            best_epoch,
+            target_epoch,
+            headers_received: inner.headers,
+            blocks_received: inner.blocks,
+            state_chunks,
+            eta,
+            peers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PeerContribution, SyncProgress};
+    use network::node_table::NodeId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_peer_contribution() {
+        let progress = SyncProgress::new();
+        let peer1 = NodeId::random();
+        let peer2 = NodeId::random();
+        progress.on_headers_received(&peer1, 10);
+        progress.on_blocks_received(&peer2, 20);
+        progress.on_chunk_received(&peer2);
+
+        let status = progress.status("phase", true, 0, None, None);
+        assert_eq!(status.headers_received, 10);
+        assert_eq!(status.blocks_received, 20);
+        // Peers are sorted by contribution.
+        assert_eq!(
+            status.peers,
+            vec![
+                (
+                    peer2,
+                    PeerContribution {
+                        headers: 0,
+                        blocks: 20,
+                        chunks: 1
+                    }
+                ),
+                (
+                    peer1,
+                    PeerContribution {
+                        headers: 10,
+                        blocks: 0,
+                        chunks: 0
+                    }
+                ),
+            ]
+        );
+
+        progress.on_peer_disconnected(&peer2);
+        let status = progress.status("phase", true, 0, None, None);
+        assert_eq!(status.peers.len(), 1);
+        assert_eq!(status.blocks_received, 20);
+    }
+
+    #[test]
+    fn test_eta() {
+        let progress = SyncProgress::new();
+        let start = Instant::now();
+        progress.sample_at(start, 100, None);
+        // No progress yet.
+        let status = progress.status("phase", true, 100, Some(1100), None);
+        assert_eq!(status.eta, None);
+
+        progress.sample_at(start + Duration::from_secs(10), 200, None);
+        let status = progress.status("phase", true, 200, Some(1100), None);
+        assert_eq!(status.eta, Some(Duration::from_secs(90)));
+        let status = progress.status("phase", false, 200, Some(1100), None);
+        assert_eq!(status.eta, None);
+
+        // Estimated by chunks while syncing state.
+        progress.sample_at(start + Duration::from_secs(20), 200, Some(5));
+        progress.sample_at(start + Duration::from_secs(30), 200, Some(10));
+        let status =
+            progress.status("phase", true, 200, Some(1100), Some((10, 20)));
+        assert_eq!(status.eta, Some(Duration::from_secs(20)));
+    }
+}
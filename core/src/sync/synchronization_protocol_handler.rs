@@ -13,17 +13,21 @@ use crate::{
     sync::{
         message::{
             handle_rlp_message, msgid, Context, DynamicCapability,
-            GetBlockHeadersResponse, Heartbeat, NewBlockHashes, StatusV2,
-            StatusV3, TransactionDigests,
+            GetBlockHeadersResponse, GetSkeletonBlockHashes, Heartbeat,
+            NewBlockHashes, StatusV2, StatusV3, TransactionDigests,
         },
         request_manager::{try_get_block_hashes, Request},
         state::SnapshotChunkSync,
+        synchronization_body_scheduler::BlockBodyScheduler,
         synchronization_phases::{SyncPhaseType, SynchronizationPhaseManager},
+        synchronization_progress::{SyncProgress, SyncStatus},
+        synchronization_skeleton::EpochSkeleton,
         synchronization_state::PeerFilter,
         StateSyncConfiguration,
         SYNCHRONIZATION_PROTOCOL_OLD_VERSIONS_TO_SUPPORT,
         SYNCHRONIZATION_PROTOCOL_VERSION, SYNC_PROTO_V1, SYNC_PROTO_V2,
     },
+    watchdog::{self, WATCHDOG},
     NodeType,
 };
 use cfx_parameters::{block::MAX_BLOCK_SIZE_IN_BYTES, sync::*};
@@ -60,6 +64,8 @@ lazy_static! {
         register_meter_with_group("timer", "sync:recover_block");
     static ref PROPAGATE_TX_TIMER: Arc<dyn Meter> =
         register_meter_with_group("timer", "sync:propagate_tx_timer");
+    static ref SYNC_HEARTBEAT: Arc<watchdog::Heartbeat> =
+        WATCHDOG.heartbeat("sync");
 }
 
 const TX_TIMER: TimerToken = 0;
@@ -83,6 +89,9 @@ const EPOCH_SYNC_MAX_GAP: u64 = 20000;
 const EPOCH_SYNC_RESTART_TIMEOUT_S: u64 = 60 * 10;
 const EPOCH_SYNC_MAX_INFLIGHT: u64 = 300;
 const EPOCH_SYNC_BATCH_SIZE: u64 = 30;
+/// The number of anchors requested from the skeleton peer at a time.
+const EPOCH_SYNC_SKELETON_SIZE: u64 =
+    EPOCH_SYNC_MAX_INFLIGHT / EPOCH_SYNC_BATCH_SIZE;
 
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 pub enum SyncHandlerWorkType {
@@ -351,6 +360,13 @@ pub struct SynchronizationProtocolHandler {
     pub request_manager: Arc<RequestManager>,
     /// The latest `(requested_epoch_number, request_time)`
     pub latest_epoch_requested: Mutex<(u64, Instant)>,
+    /// The skeleton used to sync epochs from multiple peers.
+    pub epoch_skeleton: Mutex<EpochSkeleton>,
+    /// The data received during sync, to report the sync progress.
+    pub sync_progress: SyncProgress,
+    /// The scheduler to download block bodies out of order during catch-up.
+    #[ignore_malloc_size_of = "limited by max_buffered_block_body_size"]
+    pub body_scheduler: Mutex<BlockBodyScheduler>,
     #[ignore_malloc_size_of = "only stores reference to others"]
     pub phase_manager: SynchronizationPhaseManager,
     pub phase_manager_lock: Mutex<u32>,
@@ -408,6 +424,7 @@ pub struct ProtocolConfiguration {
     pub max_allowed_timeout_in_observing_period: u64,
     pub demote_peer_for_timeout: bool,
     pub max_unprocessed_block_size: usize,
+    pub max_buffered_block_body_size: usize,
     pub max_chunk_number_in_manifest: usize,
 }
 
@@ -436,6 +453,9 @@ impl SynchronizationProtocolHandler {
         ));
 
         let state_sync = Arc::new(SnapshotChunkSync::new(state_sync_config));
+        let body_scheduler = Mutex::new(BlockBodyScheduler::new(
+            protocol_config.max_buffered_block_body_size,
+        ));
 
         Self {
             protocol_version: SYNCHRONIZATION_PROTOCOL_VERSION,
@@ -444,6 +464,11 @@ impl SynchronizationProtocolHandler {
             syn: sync_state.clone(),
             request_manager,
             latest_epoch_requested: Mutex::new((0, Instant::now())),
+            epoch_skeleton: Mutex::new(EpochSkeleton::new(
+                EPOCH_SYNC_BATCH_SIZE,
+            )),
+            sync_progress: SyncProgress::new(),
+            body_scheduler,
             phase_manager: SynchronizationPhaseManager::new(
                 initial_sync_phase,
                 sync_state.clone(),
@@ -719,6 +744,7 @@ impl SynchronizationProtocolHandler {
         }
 
         if current_phase_type != SyncPhaseType::Normal {
+            self.request_scheduled_block_bodies(io);
             self.request_epochs(io);
             let best_peer_epoch = self.syn.best_peer_epoch().unwrap_or(0);
             let my_best_epoch = self.graph.consensus.best_epoch_number();
@@ -849,7 +875,7 @@ impl SynchronizationProtocolHandler {
             {
                 debug!("Recovered epoch {} from db", from);
                 if self.need_requesting_blocks() {
-                    self.request_blocks(io, None, epoch_hashes);
+                    self.schedule_block_bodies(io, from, epoch_hashes);
                 } else {
                     self.request_block_headers(
                         io,
@@ -866,11 +892,32 @@ impl SynchronizationProtocolHandler {
                 break;
             }
 
-            // Epoch hashes are not in db, so should be requested from another
-            // peer
-            let peer = PeerFilter::new(msgid::GET_BLOCK_HASHES_BY_EPOCH)
+            // Epoch hashes are not in db, so should be requested from other
+            // peers. The anchors of the epoch skeleton are requested from one
+            // peer, and the segments between anchors are filled by different
+            // peers concurrently.
+            let mut skeleton = self.epoch_skeleton.lock();
+            skeleton.prune(my_best_epoch);
+            if skeleton.is_stale(self.protocol_config.headers_request_timeout)
+            {
+                skeleton.reset();
+            }
+            let anchor = skeleton.segment_end(from);
+            if !skeleton.contains(anchor) {
+                self.request_epoch_skeleton(io, &mut skeleton, from);
+            }
+            if skeleton.is_pending(anchor) {
+                // The segment is requested once its anchor is received.
+                break;
+            }
+
+            // If no peer has the whole segment, the skeleton is not built and
+            // the segment is requested without checking its linkage.
+            let candidates = PeerFilter::new(msgid::GET_BLOCK_HASHES_BY_EPOCH)
                 .with_min_best_epoch(from)
-                .select(&self.syn);
+                .select_all(&self.syn);
+            let peer = skeleton.choose_segment_peer(anchor, candidates);
+            drop(skeleton);
 
             // no peer has the epoch we need; try later
             if peer.is_none() {
@@ -892,7 +939,7 @@ impl SynchronizationProtocolHandler {
                 let best_of_this_peer =
                     maybe_peer_info.unwrap().read().best_epoch;
 
-                let until = from + cmp::min(EPOCH_SYNC_BATCH_SIZE, max_to_send);
+                let until = cmp::min(anchor + 1, from + max_to_send);
                 cmp::min(until, best_of_this_peer + 1)
             };
 
@@ -911,6 +958,74 @@ impl SynchronizationProtocolHandler {
             latest_requested_epoch = until - 1;
         }
         *latest_requested = (latest_requested_epoch, Instant::now());
+        drop(latest_requested);
+
+        if self.need_requesting_blocks() {
+            self.request_scheduled_block_bodies(io);
+        }
+    }
+
+    /// Start a new epoch skeleton from the segment of `from`, and request
+    /// its anchors from a single peer that has all of them.
+    fn request_epoch_skeleton(
+        &self, io: &dyn NetworkContext, skeleton: &mut EpochSkeleton, from: u64,
+    ) {
+        let first_anchor = skeleton.segment_end(from);
+        let peer = match PeerFilter::new(msgid::GET_BLOCK_HASHES_BY_EPOCH)
+            .with_min_best_epoch(first_anchor)
+            .select(&self.syn)
+        {
+            Some(peer) => peer,
+            None => return,
+        };
+        let best_of_this_peer = match self.syn.get_peer_info(&peer) {
+            Ok(info) => info.read().best_epoch,
+            Err(_) => return,
+        };
+
+        let anchors = skeleton.start(
+            peer,
+            from,
+            best_of_this_peer,
+            EPOCH_SYNC_SKELETON_SIZE,
+        );
+        debug!("requesting epoch skeleton {:?} from peer {:?}", anchors, peer);
+        // Each anchor is requested separately, because the response does
+        // not tell which epoch the hashes belong to.
+        for anchor in anchors {
+            let request = GetSkeletonBlockHashes {
+                request_id: 0,
+                epochs: vec![anchor],
+            };
+            self.request_manager.request_with_delay(
+                io,
+                Box::new(request),
+                Some(peer),
+                None,
+            );
+        }
+    }
+
+    /// Request the epochs of a segment that does not link to the epoch
+    /// skeleton again from a peer other than `bad_peer`.
+    pub fn request_epoch_segment(
+        &self, io: &dyn NetworkContext, bad_peer: &NodeId, epochs: Vec<u64>,
+    ) {
+        let last_epoch = match epochs.iter().max() {
+            Some(epoch) => *epoch,
+            None => return,
+        };
+        let candidates = PeerFilter::new(msgid::GET_BLOCK_HASHES_BY_EPOCH)
+            .exclude(*bad_peer)
+            .with_min_best_epoch(last_epoch)
+            .select_all(&self.syn);
+        let peer = {
+            let mut skeleton = self.epoch_skeleton.lock();
+            let anchor = skeleton.segment_end(last_epoch);
+            skeleton.choose_segment_peer(anchor, candidates)
+        };
+        self.request_manager
+            .request_epoch_hashes(io, peer, epochs, None);
     }
 
     pub fn request_block_headers(
@@ -1535,7 +1650,28 @@ impl SynchronizationProtocolHandler {
 
     fn cache_gc(&self) { self.graph.data_man.cache_gc() }
 
-    fn log_statistics(&self) { self.graph.log_statistics(); }
+    fn log_statistics(&self) {
+        self.graph.log_statistics();
+
+        let best_epoch = self.graph.consensus.best_epoch_number();
+        let state_chunks = self.state_sync.chunk_progress();
+        self.sync_progress
+            .sample(best_epoch, state_chunks.map(|(restored, _)| restored));
+        if self.catch_up_mode() {
+            info!("Sync progress: {}", self.sync_status());
+        }
+    }
+
+    /// Return the current sync progress.
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync_progress.status(
+            self.phase_manager.get_current_phase().name(),
+            self.catch_up_mode(),
+            self.graph.consensus.best_epoch_number(),
+            self.syn.median_epoch_from_normal_peers(),
+            self.state_sync.chunk_progress(),
+        )
+    }
 
     fn update_total_weight_delta_heartbeat(&self) {
         self.graph.update_total_weight_delta_heartbeat();
@@ -1586,7 +1722,26 @@ impl SynchronizationProtocolHandler {
     {
         let catch_up_mode = self.catch_up_mode();
         if catch_up_mode {
-            self.request_blocks(io, peer_id, hashes);
+            // The bodies are released in the order of block heights, which are
+            // the epoch numbers of pivot blocks.
+            let mut unknown_hashes = Vec::new();
+            let mut hashes_by_height = BTreeMap::new();
+            for hash in hashes {
+                match self.graph.block_header_by_hash(&hash) {
+                    Some(header) => hashes_by_height
+                        .entry(header.height())
+                        .or_insert_with(Vec::new)
+                        .push(hash),
+                    None => unknown_hashes.push(hash),
+                }
+            }
+            for (height, hashes) in hashes_by_height {
+                self.schedule_block_bodies(io, height, hashes);
+            }
+            self.request_scheduled_block_bodies(io);
+            if !unknown_hashes.is_empty() {
+                self.request_blocks(io, peer_id, unknown_hashes);
+            }
         } else {
             self.request_manager
                 .request_compact_blocks(io, peer_id, hashes, None);
@@ -1613,6 +1768,109 @@ impl SynchronizationProtocolHandler {
         );
     }
 
+    /// Schedule the block bodies of `epoch` to be downloaded out of order and
+    /// released in epoch order. They are requested by
+    /// `request_scheduled_block_bodies`.
+    fn schedule_block_bodies(
+        &self, io: &dyn NetworkContext, epoch: u64, mut hashes: Vec<H256>,
+    ) {
+        hashes.retain(|hash| !self.try_request_block_from_db(io, hash));
+        hashes.retain(|h| !self.graph.contains_block(h));
+        self.body_scheduler.lock().add_epoch(epoch, hashes);
+    }
+
+    /// Request the scheduled block bodies from the peers that are not busy,
+    /// and release the epochs that stall for too long.
+    pub fn request_scheduled_block_bodies(&self, io: &dyn NetworkContext) {
+        if self.body_scheduler.lock().is_empty() {
+            return;
+        }
+        let preferred_node_type = self.preferred_peer_node_type_for_get_block();
+        let mut filter = PeerFilter::new(msgid::GET_BLOCKS);
+        if let Some(node_type) = preferred_node_type {
+            filter = filter.with_preferred_node_type(node_type);
+        }
+        let peers = filter.select_all(&self.syn);
+
+        let (stalled, requests) = {
+            let mut scheduler = self.body_scheduler.lock();
+            // A stalled epoch has some block request timed out more than
+            // once.
+            let stalled = scheduler
+                .pop_stalled(2 * self.protocol_config.blocks_request_timeout);
+            (stalled, scheduler.schedule(&peers))
+        };
+        self.release_block_bodies(io, stalled);
+        for (peer, hashes) in requests {
+            self.request_manager.request_blocks(
+                io,
+                Some(peer),
+                hashes,
+                self.request_block_need_public(),
+                None,
+                preferred_node_type,
+            );
+        }
+    }
+
+    /// Buffer the received `blocks` that are scheduled by the body scheduler.
+    /// Return the blocks to process now along with the hashes requested for
+    /// them.
+    pub fn buffer_block_bodies(
+        &self, blocks: Vec<Block>, mut requested: HashSet<H256>,
+    ) -> (Vec<Block>, HashSet<H256>) {
+        let mut scheduler = self.body_scheduler.lock();
+        if scheduler.is_empty() {
+            return (blocks, requested);
+        }
+
+        let received: Vec<H256> = blocks.iter().map(|b| b.hash()).collect();
+        let unscheduled = scheduler.on_blocks_received(blocks);
+        let unscheduled_hashes: HashSet<H256> =
+            unscheduled.iter().map(|b| b.hash()).collect();
+        // The buffered blocks are no longer in the network, but they are kept
+        // in flight until released so that they are not requested again.
+        let buffered: Vec<H256> = received
+            .into_iter()
+            .filter(|hash| !unscheduled_hashes.contains(hash))
+            .collect();
+        for hash in &buffered {
+            requested.remove(hash);
+        }
+        self.request_manager
+            .remove_net_inflight_blocks(buffered.iter());
+
+        let mut ready = scheduler.pop_ready();
+        requested.extend(ready.iter().map(|b| b.hash()));
+        ready.extend(unscheduled);
+        (ready, requested)
+    }
+
+    /// Release all the buffered block bodies, e.g. when catch-up finishes.
+    pub fn flush_block_bodies(&self, io: &dyn NetworkContext) {
+        let blocks = self.body_scheduler.lock().drain();
+        self.release_block_bodies(io, blocks);
+    }
+
+    fn release_block_bodies(
+        &self, io: &dyn NetworkContext, blocks: Vec<Block>,
+    ) {
+        if blocks.is_empty() {
+            return;
+        }
+        let requested = blocks.iter().map(|b| b.hash()).collect();
+        self.recover_public_queue.dispatch(
+            io,
+            RecoverPublicTask::new(
+                blocks,
+                requested,
+                io.self_node_id(),
+                false, /* compact */
+                None,
+            ),
+        );
+    }
+
     /// Try to get the block from db. Return `true` if the block exists in db or
     /// is inserted before. Handle the block if its seq_num is less
     /// than that of the current era genesis.
@@ -1804,6 +2062,8 @@ impl NetworkProtocolHandler for SynchronizationProtocolHandler {
     }
 
     fn on_message(&self, io: &dyn NetworkContext, peer: &NodeId, raw: &[u8]) {
+        let _peer_context =
+            log_mdc::insert_scoped("peer", format!("{:?}", peer));
         let (msg_id, rlp) = match decode_msg(raw) {
             Some(msg) => msg,
             None => {
@@ -1818,6 +2078,7 @@ impl NetworkProtocolHandler for SynchronizationProtocolHandler {
 
         debug!("on_message: peer={}, msgid={:?}", peer, msg_id);
 
+        let _task = SYNC_HEARTBEAT.task(format!("message {:?}", msg_id));
         self.dispatch_message(io, peer, msg_id.into(), rlp)
             .unwrap_or_else(|e| self.handle_error(io, peer, msg_id.into(), e));
 
@@ -1872,10 +2133,15 @@ impl NetworkProtocolHandler for SynchronizationProtocolHandler {
         self.syn.handshaking_peers.write().remove(peer);
         self.request_manager.on_peer_disconnected(io, peer);
         self.state_sync.on_peer_disconnected(&peer);
+        self.sync_progress.on_peer_disconnected(peer);
+        self.body_scheduler.lock().on_peer_disconnected(peer);
     }
 
     fn on_timeout(&self, io: &dyn NetworkContext, timer: TimerToken) {
         trace!("Timeout: timer={:?}", timer);
+        // The timers fire at least every second, see `initialize`.
+        SYNC_HEARTBEAT.beat();
+        let _task = SYNC_HEARTBEAT.task(format!("timer {}", timer));
         match timer {
             TX_TIMER => {
                 self.propagate_new_transactions(io);
@@ -10,7 +10,7 @@ use crate::{
     sync::{
         request_manager::RequestManager, synchronization_phases::SyncPhaseType,
         synchronization_protocol_handler::ProtocolConfiguration,
-        StateSyncConfiguration, SynchronizationPhaseTrait,
+        StateSyncConfiguration, SyncStatus, SynchronizationPhaseTrait,
     },
     NodeType,
 };
@@ -64,6 +64,14 @@ impl SynchronizationService {
         self.protocol_handler.get_synchronization_graph()
     }
 
+    /// Stop receiving blocks and transactions from peers.
+    pub fn stop_network(&self) { self.network.stop(); }
+
+    /// Whether there are received blocks not processed by consensus yet.
+    pub fn is_importing_blocks(&self) -> bool {
+        self.get_synchronization_graph().is_consensus_worker_busy()
+    }
+
     pub fn get_request_manager(&self) -> Arc<RequestManager> {
         self.protocol_handler.get_request_manager()
     }
@@ -72,6 +80,10 @@ impl SynchronizationService {
         self.protocol_handler.phase_manager.get_current_phase()
     }
 
+    pub fn sync_status(&self) -> SyncStatus {
+        self.protocol_handler.sync_status()
+    }
+
     pub fn append_received_transactions(
         &self, transactions: Vec<Arc<SignedTransaction>>,
     ) {
@@ -0,0 +1,286 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Skeleton-based epoch synchronization.
+//!
+//! The epochs to catch up are split into segments of `segment_size` epochs,
+//! and the last epoch of each segment is an anchor. The block hashes of a
+//! run of anchors (the skeleton) are requested from a single peer, and then
+//! the segments between anchors are filled by different peers concurrently.
+//!
+//! A segment is only accepted if its response contains all the blocks of the
+//! anchor it ends with, so a peer can not feed us a segment that does not
+//! link to the skeleton. If several peers disagree with the same anchor, the
+//! skeleton peer is the one to blame, and the skeleton is rebuilt.
+
+use cfx_types::H256;
+use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
+use network::node_table::NodeId;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// The number of distinct peers that should disagree with an anchor before
+/// the skeleton itself is considered wrong.
+const MAX_ANCHOR_MISMATCHES: usize = 2;
+
+#[derive(Debug, DeriveMallocSizeOf)]
+enum AnchorState {
+    /// The anchor is requested from the skeleton peer at the given time.
+    Requested(Instant),
+    /// The block hashes of the anchor epoch.
+    Received(HashSet<H256>),
+}
+
+#[derive(DeriveMallocSizeOf)]
+pub struct EpochSkeleton {
+    segment_size: u64,
+    /// The peer that serves all the anchors of the current skeleton.
+    peer: Option<NodeId>,
+    anchors: HashMap<u64, AnchorState>,
+    /// Peers whose segment did not link to the anchor, keyed by anchor.
+    mismatches: HashMap<u64, HashSet<NodeId>>,
+    /// Round-robin cursor to spread segments over peers.
+    next_peer: usize,
+}
+
+impl EpochSkeleton {
+    pub fn new(segment_size: u64) -> Self {
+        assert!(segment_size > 0);
+        EpochSkeleton {
+            segment_size,
+            peer: None,
+            anchors: HashMap::new(),
+            mismatches: HashMap::new(),
+            next_peer: 0,
+        }
+    }
+
+    /// Return the anchor of the segment that `epoch` belongs to.
+    pub fn segment_end(&self, epoch: u64) -> u64 {
+        (epoch + self.segment_size - 1) / self.segment_size * self.segment_size
+    }
+
+    pub fn peer(&self) -> Option<NodeId> { self.peer }
+
+    pub fn reset(&mut self) {
+        self.peer = None;
+        self.anchors.clear();
+        self.mismatches.clear();
+    }
+
+    /// Start a new skeleton served by `peer`, with at most `count` anchors
+    /// from the segment of `from` and no anchor beyond `max_epoch`.
+    /// Return the anchor epochs to request from `peer`.
+    pub fn start(
+        &mut self, peer: NodeId, from: u64, max_epoch: u64, count: u64,
+    ) -> Vec<u64> {
+        self.reset();
+        let mut anchor = self.segment_end(from);
+        let now = Instant::now();
+        let mut requested = Vec::new();
+        while anchor <= max_epoch && (requested.len() as u64) < count {
+            self.anchors.insert(anchor, AnchorState::Requested(now));
+            requested.push(anchor);
+            anchor += self.segment_size;
+        }
+        if !requested.is_empty() {
+            self.peer = Some(peer);
+        }
+        requested
+    }
+
+    /// Return `true` if `anchor` belongs to the current skeleton.
+    pub fn contains(&self, anchor: u64) -> bool {
+        self.anchors.contains_key(&anchor)
+    }
+
+    /// Return `true` if `anchor` is requested but not received yet.
+    pub fn is_pending(&self, anchor: u64) -> bool {
+        match self.anchors.get(&anchor) {
+            Some(AnchorState::Requested(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Return `true` if some anchor has not been received within `timeout`,
+    /// e.g. the skeleton peer is disconnected or does not respond.
+    pub fn is_stale(&self, timeout: Duration) -> bool {
+        self.anchors.values().any(|state| match state {
+            AnchorState::Requested(time) => time.elapsed() > timeout,
+            AnchorState::Received(_) => false,
+        })
+    }
+
+    /// Drop the anchors that are not after `epoch`, as their segments have
+    /// already been synced.
+    pub fn prune(&mut self, epoch: u64) {
+        self.anchors.retain(|anchor, _| *anchor > epoch);
+        self.mismatches.retain(|anchor, _| *anchor > epoch);
+        if self.anchors.is_empty() {
+            self.peer = None;
+        }
+    }
+
+    /// Record the block hashes of `anchor` received from `peer`.
+    /// Return `false` if the response is not expected. An empty response
+    /// means that the skeleton peer can not serve the anchor, so the skeleton
+    /// is dropped.
+    pub fn on_anchor_received(
+        &mut self, peer: &NodeId, anchor: u64, hashes: Vec<H256>,
+    ) -> bool {
+        if self.peer.as_ref() != Some(peer) || !self.is_pending(anchor) {
+            return false;
+        }
+        if hashes.is_empty() {
+            debug!("Empty skeleton anchor {} from {:?}", anchor, peer);
+            self.reset();
+            return false;
+        }
+        self.anchors.insert(
+            anchor,
+            AnchorState::Received(hashes.into_iter().collect()),
+        );
+        true
+    }
+
+    /// Check that the segment response of `epochs` from `peer` contains all
+    /// the blocks of the received anchors among `epochs`.
+    ///
+    /// On mismatch, the peer is recorded for the anchor, and the skeleton is
+    /// dropped once enough distinct peers disagree with it.
+    pub fn check_segment(
+        &mut self, peer: &NodeId, epochs: &[u64], hashes: &[H256],
+    ) -> bool {
+        let hashes: HashSet<&H256> = hashes.iter().collect();
+        let mut mismatched = None;
+        for epoch in epochs {
+            if let Some(AnchorState::Received(anchor_hashes)) =
+                self.anchors.get(epoch)
+            {
+                if !anchor_hashes.iter().all(|h| hashes.contains(h)) {
+                    mismatched = Some(*epoch);
+                    break;
+                }
+            }
+        }
+        let anchor = match mismatched {
+            None => return true,
+            Some(anchor) => anchor,
+        };
+        debug!(
+            "Segment of anchor {} from {:?} does not link to the skeleton \
+             from {:?}",
+            anchor, peer, self.peer
+        );
+        let peers = self.mismatches.entry(anchor).or_insert_with(HashSet::new);
+        peers.insert(*peer);
+        if peers.len() >= MAX_ANCHOR_MISMATCHES {
+            info!(
+                "Drop epoch skeleton from {:?}: anchor {} is rejected by {} \
+                 peers",
+                self.peer,
+                anchor,
+                peers.len()
+            );
+            self.reset();
+        }
+        false
+    }
+
+    /// Choose a peer among `candidates` to fill the segment of `anchor`.
+    /// Peers are chosen round-robin, and peers that have sent a segment not
+    /// linking to `anchor` are skipped.
+    pub fn choose_segment_peer(
+        &mut self, anchor: u64, mut candidates: Vec<NodeId>,
+    ) -> Option<NodeId> {
+        if let Some(excluded) = self.mismatches.get(&anchor) {
+            candidates.retain(|peer| !excluded.contains(peer));
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        // Keep the order stable so that the cursor walks over all peers.
+        candidates.sort();
+        let peer = candidates[self.next_peer % candidates.len()];
+        self.next_peer = self.next_peer.wrapping_add(1);
+        Some(peer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EpochSkeleton;
+    use cfx_types::H256;
+    use network::node_table::NodeId;
+
+    #[test]
+    fn test_segment_end() {
+        let skeleton = EpochSkeleton::new(30);
+        assert_eq!(skeleton.segment_end(1), 30);
+        assert_eq!(skeleton.segment_end(30), 30);
+        assert_eq!(skeleton.segment_end(31), 60);
+    }
+
+    #[test]
+    fn test_start_and_receive_anchors() {
+        let peer = NodeId::random();
+        let mut skeleton = EpochSkeleton::new(10);
+        assert_eq!(skeleton.start(peer, 5, 35, 5), vec![10, 20, 30]);
+        assert!(skeleton.is_pending(10));
+
+        // Only the skeleton peer can serve anchors.
+        let other = NodeId::random();
+        assert!(!skeleton.on_anchor_received(&other, 10, vec![H256::random()]));
+        assert!(skeleton.on_anchor_received(&peer, 10, vec![H256::random()]));
+        assert!(!skeleton.is_pending(10));
+        assert!(skeleton.is_pending(20));
+
+        skeleton.prune(20);
+        assert!(!skeleton.contains(20));
+        assert!(skeleton.contains(30));
+
+        // An empty anchor drops the whole skeleton.
+        assert!(!skeleton.on_anchor_received(&peer, 30, vec![]));
+        assert!(skeleton.peer().is_none());
+        assert!(!skeleton.contains(30));
+    }
+
+    #[test]
+    fn test_check_segment() {
+        let skeleton_peer = NodeId::random();
+        let mut skeleton = EpochSkeleton::new(3);
+        skeleton.start(skeleton_peer, 1, 6, 2);
+        let anchor = H256::random();
+        skeleton.on_anchor_received(&skeleton_peer, 3, vec![anchor]);
+
+        let linked = vec![H256::random(), anchor];
+        let peer1 = NodeId::random();
+        assert!(skeleton.check_segment(&peer1, &[1, 2, 3], &linked));
+        // Anchors that are not received yet can not be checked.
+        assert!(skeleton.check_segment(&peer1, &[4, 5, 6], &linked));
+
+        let unlinked = vec![H256::random()];
+        assert!(!skeleton.check_segment(&peer1, &[1, 2, 3], &unlinked));
+        assert_eq!(skeleton.choose_segment_peer(3, vec![peer1]), None);
+        assert!(skeleton.contains(3));
+
+        // The skeleton is dropped when another peer disagrees with it.
+        let peer2 = NodeId::random();
+        assert!(!skeleton.check_segment(&peer2, &[1, 2, 3], &unlinked));
+        assert!(skeleton.peer().is_none());
+        assert!(!skeleton.contains(3));
+    }
+
+    #[test]
+    fn test_choose_segment_peer_round_robin() {
+        let mut skeleton = EpochSkeleton::new(10);
+        let peers = vec![NodeId::random(), NodeId::random()];
+        let first = skeleton.choose_segment_peer(10, peers.clone()).unwrap();
+        let second = skeleton.choose_segment_peer(20, peers.clone()).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(skeleton.choose_segment_peer(30, peers), Some(first));
+    }
+}
@@ -162,6 +162,20 @@ impl SynchronizationState {
         }
     }
 
+    /// Returns the protocol version negotiated with the specified peer, which
+    /// could be either handshaking or handshaked.
+    pub fn get_negotiated_version(
+        &self, peer: &NodeId,
+    ) -> Option<ProtocolVersion> {
+        if let Some(info) = self.peers.read().get(peer) {
+            return Some(info.read().protocol_version);
+        }
+        self.handshaking_peers
+            .read()
+            .get(peer)
+            .map(|(version, _)| *version)
+    }
+
     /// Updates the heartbeat for the specified peer. It takes no effect if the
     /// peer is in handshaking status or not found.
     pub fn update_heartbeat(&self, node_id: &NodeId) {
@@ -8,7 +8,7 @@ use crate::{
     db::NUM_COLUMNS,
     genesis::genesis_block,
     machine::new_machine_with_builtin,
-    pow::{self, PowComputer, ProofOfWorkConfig},
+    pow::{self, PowAlgorithmType, PowComputer, ProofOfWorkConfig},
     statistics::Statistics,
     sync::{SyncGraphConfig, SynchronizationGraph},
     transaction_pool::TxPoolConfig,
@@ -143,6 +143,8 @@ pub fn initialize_data_manager(
         DataManagerConfiguration::new(
             false,                          /* do not persist transaction
                                              * address */
+            false, /* do not index transactions by address */
+            false, /* do not index logs */
             Duration::from_millis(300_000), /* max cached tx count */
             dbtype,
         ),
@@ -191,7 +193,7 @@ pub fn initialize_synchronization_graph_with_data_manager(
     let notifications = Notifications::init();
     let consensus = Arc::new(ConsensusGraph::new(
         ConsensusConfig {
-            chain_id: ChainIdParams { chain_id: 0 },
+            chain_id: ChainIdParams::new(0),
             inner_conf: ConsensusInnerConfig {
                 adaptive_weight_beta: beta,
                 heavy_block_difficulty_ratio: h,
@@ -220,6 +222,8 @@ pub fn initialize_synchronization_graph_with_data_manager(
         ConsensusExecutionConfiguration {
             anticone_penalty_ratio: tcr - 1,
             base_reward_table_in_ucfx: vec![INITIAL_BASE_MINING_REWARD_IN_UCFX],
+            parallel_execution: false,
+            index_internal_transfers: false,
         },
         verification_config.clone(),
         NodeType::Archive,
@@ -250,7 +254,7 @@ pub fn initialize_synchronization_graph(
     Arc<Block>,
 )
 {
-    let pow = Arc::new(PowComputer::new(true));
+    let pow = Arc::new(PowComputer::new(PowAlgorithmType::Octopus));
 
     let (data_man, genesis_block) =
         initialize_data_manager(db_dir, dbtype, pow.clone());
@@ -0,0 +1,144 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Tracing of the storage collateral charged and refunded by each frame.
+//!
+//! The executive reports the storage occupied and released by a frame
+//! including its sub-calls. The tracer subtracts the part of the sub-calls,
+//! so that each storage write is attributed to the frame making it.
+
+use super::Tracer;
+use crate::{
+    evm::FinalizationResult,
+    vm::{self, ActionParams},
+};
+use cfx_types::Address;
+use primitives::receipt::StorageChange;
+use std::collections::HashMap;
+
+/// The storage collateral changes made by a frame, not including its
+/// sub-calls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollateralTrace {
+    /// The address called or created by the frame.
+    pub address: Address,
+    /// The depth of the frame, which is 0 for the transaction itself.
+    pub depth: usize,
+    /// The storage occupied for each storage owner, in bytes.
+    pub storage_collateralized: Vec<StorageChange>,
+    /// The storage released for each storage owner, in bytes.
+    pub storage_released: Vec<StorageChange>,
+}
+
+type StorageChanges = HashMap<Address, u64>;
+
+#[derive(Default)]
+struct Frame {
+    address: Address,
+    /// The changes including the sub-calls, which are only set if the
+    /// changes of the frame are kept.
+    total: Option<(StorageChanges, StorageChanges)>,
+    /// The changes of the finished sub-calls.
+    sub_calls: (StorageChanges, StorageChanges),
+    /// The traces of the finished sub-calls.
+    traces: Vec<CollateralTrace>,
+}
+
+/// Collects the storage collateral changes of the frames whose changes are
+/// kept. The frames without any collateral change are skipped.
+#[derive(Default)]
+pub struct CollateralTracer {
+    frames: Vec<Frame>,
+    traces: Vec<CollateralTrace>,
+}
+
+impl CollateralTracer {
+    pub fn new() -> Self { Self::default() }
+
+    /// The traces in the order of entering the frames.
+    pub fn traces(&self) -> &[CollateralTrace] { &self.traces }
+
+    pub fn into_traces(self) -> Vec<CollateralTrace> { self.traces }
+
+    /// The changes in `total` but not in `sub_calls`, sorted by address.
+    fn own_changes(
+        total: &StorageChanges, sub_calls: &StorageChanges,
+    ) -> Vec<StorageChange> {
+        let mut changes: Vec<StorageChange> = total
+            .iter()
+            .filter_map(|(address, amount)| {
+                let amount =
+                    amount - sub_calls.get(address).cloned().unwrap_or(0);
+                if amount == 0 {
+                    None
+                } else {
+                    Some(StorageChange {
+                        address: *address,
+                        amount,
+                    })
+                }
+            })
+            .collect();
+        changes.sort_by_key(|change| change.address);
+        changes
+    }
+
+    fn accrue(changes: &mut StorageChanges, other: StorageChanges) {
+        for (address, amount) in other {
+            *changes.entry(address).or_insert(0) += amount;
+        }
+    }
+}
+
+impl Tracer for CollateralTracer {
+    fn trace_call_enter(&mut self, params: &ActionParams, _is_create: bool) {
+        self.frames.push(Frame {
+            address: params.address,
+            ..Default::default()
+        });
+    }
+
+    fn trace_call_exit(&mut self, _result: &vm::Result<FinalizationResult>) {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        // The changes of a reverted frame are discarded with its sub-calls.
+        let (occupied, released) = match frame.total {
+            Some(total) => total,
+            None => return,
+        };
+
+        let storage_collateralized =
+            Self::own_changes(&occupied, &frame.sub_calls.0);
+        let storage_released = Self::own_changes(&released, &frame.sub_calls.1);
+        let mut traces = Vec::with_capacity(frame.traces.len() + 1);
+        if !storage_collateralized.is_empty() || !storage_released.is_empty() {
+            traces.push(CollateralTrace {
+                address: frame.address,
+                depth: self.frames.len(),
+                storage_collateralized,
+                storage_released,
+            });
+        }
+        traces.extend(frame.traces);
+
+        match self.frames.last_mut() {
+            Some(parent) => {
+                Self::accrue(&mut parent.sub_calls.0, occupied);
+                Self::accrue(&mut parent.sub_calls.1, released);
+                parent.traces.extend(traces);
+            }
+            None => self.traces.extend(traces),
+        }
+    }
+
+    fn trace_collateral(
+        &mut self, occupied: &StorageChanges, released: &StorageChanges,
+    ) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.total = Some((occupied.clone(), released.clone()));
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+mod collateral;
+mod tracer;
+mod transfer;
+
+pub use self::{
+    collateral::{CollateralTrace, CollateralTracer},
+    tracer::{NoopTracer, Tracer},
+    transfer::{
+        EpochInternalTransfers, InternalTransfer, TransactionInternalTransfers,
+        TransferTracer,
+    },
+};
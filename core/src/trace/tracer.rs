@@ -0,0 +1,78 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    evm::FinalizationResult,
+    vm::{self, ActionParams},
+};
+use cfx_types::{Address, U256};
+use primitives::LogEntry;
+use std::collections::HashMap;
+
+/// Hooks into transaction execution, e.g. for debug RPCs and profilers.
+///
+/// All the hooks do nothing by default. The opcode step hooks of a frame are
+/// only invoked while `trace_next_instruction` returns `true`, so a tracer
+/// that does not trace steps adds nothing to the interpreter loop.
+pub trait Tracer {
+    /// Called before a call or create frame is executed.
+    fn trace_call_enter(&mut self, _params: &ActionParams, _is_create: bool) {}
+
+    /// Called after the innermost frame is finished.
+    fn trace_call_exit(&mut self, _result: &vm::Result<FinalizationResult>) {}
+
+    /// Decide if the next instruction of the current frame is traced. Once it
+    /// returns `false`, the rest of the frame is not traced.
+    fn trace_next_instruction(
+        &mut self, _pc: usize, _instruction: u8, _current_gas: U256,
+    ) -> bool {
+        false
+    }
+
+    /// Called before a traced instruction is executed.
+    fn trace_prepare_execute(
+        &mut self, _pc: usize, _instruction: u8, _gas_cost: U256,
+        _mem_written: Option<(usize, usize)>,
+        _store_written: Option<(U256, U256)>,
+    )
+    {
+    }
+
+    /// Called after a traced instruction is executed.
+    fn trace_executed(
+        &mut self, _gas_left: U256, _stack_push: &[U256], _mem: &[u8],
+    ) {
+    }
+
+    /// Called when a contract loads a storage value.
+    fn trace_storage_read(
+        &mut self, _address: &Address, _key: &[u8], _value: &U256,
+    ) {
+    }
+
+    /// Called when a contract stores a storage value.
+    fn trace_storage_write(
+        &mut self, _address: &Address, _key: &[u8], _value: &U256,
+    ) {
+    }
+
+    /// Called when a contract emits a log.
+    fn trace_log(&mut self, _log: &LogEntry) {}
+
+    /// Called when the changes of a frame are kept, with the storage in
+    /// bytes occupied and released for each storage owner by the frame,
+    /// including its sub-calls. It is followed by `trace_call_exit` of the
+    /// frame.
+    fn trace_collateral(
+        &mut self, _occupied: &HashMap<Address, u64>,
+        _released: &HashMap<Address, u64>,
+    )
+    {
+    }
+}
+
+/// The tracer used when execution is not traced.
+pub struct NoopTracer;
+
+impl Tracer for NoopTracer {}
@@ -0,0 +1,155 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Tracing of the value transferred by the sub-calls of a transaction, e.g.
+//! to index the internal transfers received by each address, or to publish
+//! them to the subscribers.
+
+use super::Tracer;
+use crate::{
+    evm::FinalizationResult,
+    vm::{self, ActionParams, ActionValue},
+};
+use cfx_types::{Address, H256, U256};
+use rlp_derive::{RlpDecodable, RlpEncodable};
+
+/// The value transferred by a sub-call or a sub-create.
+#[derive(Clone, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct InternalTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+}
+
+/// The internal transfers of a transaction executed on the pivot chain. The
+/// traced transfers of an epoch are stored with its pivot block hash, to be
+/// published again when the execution of the epoch is skipped.
+#[derive(Clone, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct TransactionInternalTransfers {
+    pub block_hash: H256,
+    pub transaction_hash: H256,
+    pub transfers: Vec<InternalTransfer>,
+}
+
+/// The internal transfers of the transactions in a pivot epoch, which are
+/// published through `Notifications::internal_transfers` when the epoch is
+/// executed or its execution is skipped. The transactions without internal
+/// transfers are omitted.
+#[derive(Clone, Debug)]
+pub struct EpochInternalTransfers {
+    pub epoch_number: u64,
+    pub pivot_hash: H256,
+    pub transactions: Vec<TransactionInternalTransfers>,
+}
+
+/// Collects the value transferred by the sub-calls and sub-creates whose
+/// changes are kept. The transfer of the transaction itself is not included.
+#[derive(Default)]
+pub struct TransferTracer {
+    /// The transfers of the finished sub-calls of each frame.
+    frames: Vec<Vec<InternalTransfer>>,
+    transfers: Vec<InternalTransfer>,
+}
+
+impl TransferTracer {
+    pub fn new() -> Self { Self::default() }
+
+    /// The transfers in the order of entering the frames.
+    pub fn into_transfers(self) -> Vec<InternalTransfer> { self.transfers }
+}
+
+impl Tracer for TransferTracer {
+    fn trace_call_enter(&mut self, params: &ActionParams, _is_create: bool) {
+        let mut transfers = Vec::new();
+        // The value of a CALLCODE stays in the calling contract.
+        if let ActionValue::Transfer(value) = params.value {
+            if !self.frames.is_empty()
+                && !value.is_zero()
+                && params.address != params.sender
+            {
+                transfers.push(InternalTransfer {
+                    from: params.sender,
+                    to: params.address,
+                    value,
+                });
+            }
+        }
+        self.frames.push(transfers);
+    }
+
+    fn trace_call_exit(&mut self, result: &vm::Result<FinalizationResult>) {
+        let transfers = match self.frames.pop() {
+            Some(transfers) => transfers,
+            None => return,
+        };
+        // The transfers of a reverted frame are discarded with its
+        // sub-calls.
+        match result {
+            Ok(FinalizationResult {
+                apply_state: true, ..
+            }) => {}
+            _ => return,
+        }
+        match self.frames.last_mut() {
+            Some(parent) => parent.extend(transfers),
+            None => self.transfers.extend(transfers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::ReturnData;
+
+    fn params(sender: u64, address: u64, value: u64) -> ActionParams {
+        ActionParams {
+            sender: Address::from_low_u64_be(sender),
+            address: Address::from_low_u64_be(address),
+            value: ActionValue::Transfer(value.into()),
+            ..Default::default()
+        }
+    }
+
+    fn result(apply_state: bool) -> vm::Result<FinalizationResult> {
+        Ok(FinalizationResult {
+            gas_left: U256::zero(),
+            apply_state,
+            return_data: ReturnData::empty(),
+        })
+    }
+
+    #[test]
+    fn test_transfer_tracer() {
+        let mut tracer = TransferTracer::new();
+        // The transaction itself.
+        tracer.trace_call_enter(&params(1, 2, 10), false);
+        // A kept sub-call with a kept transfer and a zero transfer.
+        tracer.trace_call_enter(&params(2, 3, 5), false);
+        tracer.trace_call_enter(&params(3, 4, 1), false);
+        tracer.trace_call_exit(&result(true));
+        tracer.trace_call_enter(&params(3, 5, 0), false);
+        tracer.trace_call_exit(&result(true));
+        tracer.trace_call_exit(&result(true));
+        // A reverted sub-call, whose sub-calls are reverted too.
+        tracer.trace_call_enter(&params(2, 6, 5), false);
+        tracer.trace_call_enter(&params(6, 7, 1), false);
+        tracer.trace_call_exit(&result(true));
+        tracer.trace_call_exit(&result(false));
+        // A CALLCODE.
+        tracer.trace_call_enter(&params(2, 2, 5), false);
+        tracer.trace_call_exit(&result(true));
+        tracer.trace_call_exit(&result(true));
+
+        let transfer = |from: u64, to: u64, value: u64| InternalTransfer {
+            from: Address::from_low_u64_be(from),
+            to: Address::from_low_u64_be(to),
+            value: value.into(),
+        };
+        assert_eq!(
+            tracer.into_transfers(),
+            vec![transfer(2, 3, 5), transfer(3, 4, 1)]
+        );
+    }
+}
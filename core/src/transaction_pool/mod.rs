@@ -10,27 +10,39 @@ mod test_treap;
 mod account_cache;
 mod garbage_collector;
 mod nonce_pool;
+mod packing;
 mod transaction_pool_inner;
 
 extern crate rand;
 
-pub use self::impls::TreapMap;
+pub use self::{impls::TreapMap, packing::PackingStrategy};
 use crate::{
-    block_data_manager::BlockDataManager, consensus::BestInformation,
-    machine::Machine, state::State, verification::VerificationConfig, vm::Spec,
+    block_data_manager::BlockDataManager,
+    consensus::BestInformation,
+    machine::Machine,
+    state::State,
+    verification::VerificationConfig,
+    vm::Spec,
+    watchdog::{Heartbeat, WATCHDOG},
 };
 use account_cache::AccountCache;
-use cfx_parameters::block::DEFAULT_TARGET_BLOCK_GAS_LIMIT;
+use cfx_parameters::block::{
+    DEFAULT_TARGET_BLOCK_GAS_LIMIT, MAX_BLOCK_SIZE_IN_BYTES,
+};
 use cfx_statedb::{Result as StateDbResult, StateDb};
 use cfx_storage::{Result as StorageResult, StateIndex, StorageManagerTrait};
 use cfx_types::{Address, H256, U256};
+use lru_time_cache::LruCache;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use metrics::{
     register_meter_with_group, Gauge, GaugeUsize, Lock, Meter, MeterTimer,
     RwLockExtensions,
 };
 use parking_lot::{Mutex, RwLock};
-use primitives::{Account, SignedTransaction, TransactionWithSignature};
+use primitives::{
+    transaction::TransactionError, Account, SignedTransaction,
+    TransactionWithSignature,
+};
 use std::{
     cmp::{max, min},
     collections::hash_map::HashMap,
@@ -71,6 +83,7 @@ lazy_static! {
         Lock::register("txpool_notify_best_info");
     static ref NOTIFY_MODIFIED_LOCK: Lock =
         Lock::register("txpool_notify_modified_info");
+    static ref TX_POOL_HEARTBEAT: Arc<Heartbeat> = WATCHDOG.heartbeat("txpool");
 }
 
 // FIXME: obviously the max tx gas limit follows the max block gas limit.
@@ -78,13 +91,73 @@ lazy_static! {
 // FIXME: (according to the formular).
 pub const DEFAULT_MAX_TRANSACTION_GAS_LIMIT: u64 = 100_000_000;
 
+/// A transaction with more data never fits in a block.
+pub const DEFAULT_MAX_TRANSACTION_DATA_BYTES: usize = MAX_BLOCK_SIZE_IN_BYTES;
+
+/// The number of senders whose latest rejected transaction is kept.
+const REJECTED_TRANSACTIONS_CACHE_SIZE: usize = 10_000;
+
+/// The latest transaction of a sender refused by the pool, kept to explain why
+/// the transactions of the sender are pending.
+#[derive(Clone, Debug)]
+pub struct RejectedTransaction {
+    pub hash: H256,
+    pub nonce: U256,
+    pub reason: String,
+}
+
+/// The limits of the transactions admitted into the pool, which can be changed
+/// at runtime with `TransactionPool::set_admission_limits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxAdmissionLimits {
+    pub max_tx_data_bytes: usize,
+    pub max_tx_gas: u64,
+    pub min_tx_gas_price: u64,
+}
+
+impl Default for TxAdmissionLimits {
+    fn default() -> Self {
+        TxAdmissionLimits {
+            max_tx_data_bytes: DEFAULT_MAX_TRANSACTION_DATA_BYTES,
+            max_tx_gas: DEFAULT_MAX_TRANSACTION_GAS_LIMIT,
+            min_tx_gas_price: 1,
+        }
+    }
+}
+
+impl TxAdmissionLimits {
+    pub fn check(
+        &self, transaction: &TransactionWithSignature,
+    ) -> Result<(), TransactionError> {
+        if transaction.data.len() > self.max_tx_data_bytes {
+            return Err(TransactionError::DataTooLarge {
+                maximal: self.max_tx_data_bytes,
+                got: transaction.data.len(),
+            });
+        }
+        if transaction.gas > self.max_tx_gas.into() {
+            return Err(TransactionError::TooMuchGas {
+                maximal: self.max_tx_gas.into(),
+                got: transaction.gas,
+            });
+        }
+        if transaction.gas_price < self.min_tx_gas_price.into() {
+            return Err(TransactionError::InsufficientGasPrice {
+                minimal: self.min_tx_gas_price.into(),
+                got: transaction.gas_price,
+            });
+        }
+        Ok(())
+    }
+}
+
 pub struct TxPoolConfig {
     pub capacity: usize,
-    pub min_tx_price: u64,
-    pub max_tx_gas: u64,
+    pub admission_limits: TxAdmissionLimits,
     pub tx_weight_scaling: u64,
     pub tx_weight_exp: u8,
     pub target_block_gas_limit: u64,
+    pub packing_strategy: PackingStrategy,
 }
 
 impl MallocSizeOf for TxPoolConfig {
@@ -95,19 +168,19 @@ impl Default for TxPoolConfig {
     fn default() -> Self {
         TxPoolConfig {
             capacity: 500_000,
-            min_tx_price: 1,
-            max_tx_gas: DEFAULT_MAX_TRANSACTION_GAS_LIMIT,
+            admission_limits: TxAdmissionLimits::default(),
             // TODO: Set a proper default scaling since tx pool uses u128 as
             // weight.
             tx_weight_scaling: 1,
             tx_weight_exp: 1,
             target_block_gas_limit: DEFAULT_TARGET_BLOCK_GAS_LIMIT,
+            packing_strategy: PackingStrategy::default(),
         }
     }
 }
 
 pub struct TransactionPool {
-    config: TxPoolConfig,
+    config: RwLock<TxPoolConfig>,
     verification_config: VerificationConfig,
     inner: RwLock<TransactionPoolInner>,
     to_propagate_trans: Arc<RwLock<HashMap<H256, Arc<SignedTransaction>>>>,
@@ -117,6 +190,7 @@ pub struct TransactionPool {
     set_tx_requests: Mutex<Vec<Arc<SignedTransaction>>>,
     recycle_tx_requests: Mutex<Vec<Arc<SignedTransaction>>>,
     machine: Arc<Machine>,
+    rejected_transactions: Mutex<LruCache<Address, RejectedTransaction>>,
 }
 
 impl MallocSizeOf for TransactionPool {
@@ -129,14 +203,14 @@ impl MallocSizeOf for TransactionPool {
         let set_tx_requests_size = self.set_tx_requests.lock().size_of(ops);
         let recycle_tx_requests_size =
             self.recycle_tx_requests.lock().size_of(ops);
-        self.config.size_of(ops)
+        self.config.read().size_of(ops)
             + inner_size
             + to_propagate_trans_size
             + self.data_man.size_of(ops)
             + consensus_best_info_size
             + set_tx_requests_size
             + recycle_tx_requests_size
-        // Does not count size_of machine
+        // Does not count size_of machine and rejected_transactions
     }
 }
 
@@ -165,7 +239,7 @@ impl TransactionPool {
             .expect("The genesis state is guaranteed to exist."),
         );
         TransactionPool {
-            config,
+            config: RwLock::new(config),
             verification_config,
             inner: RwLock::new(inner),
             to_propagate_trans: Arc::new(RwLock::new(HashMap::new())),
@@ -175,11 +249,31 @@ impl TransactionPool {
             set_tx_requests: Mutex::new(Default::default()),
             recycle_tx_requests: Mutex::new(Default::default()),
             machine,
+            rejected_transactions: Mutex::new(LruCache::with_capacity(
+                REJECTED_TRANSACTIONS_CACHE_SIZE,
+            )),
         }
     }
 
     pub fn machine(&self) -> Arc<Machine> { self.machine.clone() }
 
+    /// Change the strategy to pack transactions, which takes effect
+    /// immediately.
+    pub fn set_policy(&self, packing_strategy: PackingStrategy) {
+        self.config.write().packing_strategy = packing_strategy;
+    }
+
+    pub fn admission_limits(&self) -> TxAdmissionLimits {
+        self.config.read().admission_limits
+    }
+
+    /// Change the limits of the received transactions, which take effect
+    /// immediately. The transactions already in the pool are kept.
+    pub fn set_admission_limits(&self, admission_limits: TxAdmissionLimits) {
+        info!("Transaction pool admission limits: {:?}", admission_limits);
+        self.config.write().admission_limits = admission_limits;
+    }
+
     pub fn get_transaction(
         &self, tx_hash: &H256,
     ) -> Option<Arc<SignedTransaction>> {
@@ -197,6 +291,27 @@ impl TransactionPool {
             .unwrap_or((0.into(), 0.into()))
     }
 
+    /// Returns the latest transaction of `address` refused by the pool. The
+    /// transactions refused before their sender is recovered are not kept.
+    pub fn get_rejected_transaction(
+        &self, address: &Address,
+    ) -> Option<RejectedTransaction> {
+        self.rejected_transactions.lock().get(address).cloned()
+    }
+
+    fn record_rejected_transaction(
+        &self, transaction: &SignedTransaction, reason: &str,
+    ) {
+        self.rejected_transactions.lock().insert(
+            transaction.sender,
+            RejectedTransaction {
+                hash: transaction.hash(),
+                nonce: transaction.nonce,
+                reason: reason.into(),
+            },
+        );
+    }
+
     pub fn get_state_account_info(
         &self, address: &Address,
     ) -> StateDbResult<(U256, U256)> {
@@ -216,6 +331,8 @@ impl TransactionPool {
         INSERT_TPS.mark(1);
         INSERT_TXS_TPS.mark(transactions.len());
         let _timer = MeterTimer::time_func(TX_POOL_INSERT_TIMER.as_ref());
+        let _task = TX_POOL_HEARTBEAT
+            .task(format!("insert {} transactions", transactions.len()));
 
         let mut passed_transactions = Vec::new();
         let mut failure = HashMap::new();
@@ -288,6 +405,7 @@ impl TransactionPool {
                             "tx {:?} fails to be inserted to pool, err={:?}",
                             &tx.hash, e
                         );
+                        self.record_rejected_transaction(&tx, &e);
                         failure.insert(tx.hash(), e);
                         continue;
                     }
@@ -326,6 +444,8 @@ impl TransactionPool {
         INSERT_TPS.mark(1);
         INSERT_TXS_TPS.mark(signed_transactions.len());
         let _timer = MeterTimer::time_func(TX_POOL_INSERT_TIMER.as_ref());
+        let _task = TX_POOL_HEARTBEAT
+            .task(format!("insert {} transactions", signed_transactions.len()));
 
         let mut passed_transactions = Vec::new();
         let mut failure = HashMap::new();
@@ -352,6 +472,7 @@ impl TransactionPool {
                 Err(e) => {
                     let removed = signed_transactions.swap_remove(index);
                     debug!("failed to insert tx into pool (validation failed), hash = {:?}, error = {:?}", removed.hash, e);
+                    self.record_rejected_transaction(&removed, &e);
                     failure.insert(removed.hash, e);
                 }
             }
@@ -365,6 +486,7 @@ impl TransactionPool {
         if quota < signed_transactions.len() {
             for tx in signed_transactions.split_off(quota) {
                 trace!("failed to insert tx into pool (quota not enough), hash = {:?}", tx.hash);
+                self.record_rejected_transaction(&tx, "txpool is full");
                 failure.insert(tx.hash, "txpool is full".into());
             }
         }
@@ -397,6 +519,7 @@ impl TransactionPool {
                         "tx {:?} fails to be inserted to pool, err={:?}",
                         &tx.hash, e
                     );
+                    self.record_rejected_transaction(&tx, &e);
                     failure.insert(tx.hash(), e);
                     continue;
                 }
@@ -418,6 +541,26 @@ impl TransactionPool {
         (passed_transactions, failure)
     }
 
+    /// Verifies `transaction` as it would be before being inserted into the
+    /// pool, without inserting it.
+    pub fn verify_transaction(
+        &self, transaction: &TransactionWithSignature,
+    ) -> Result<(), String> {
+        let best_info = self.consensus_best_info.lock().clone();
+        self.verify_transaction_tx_pool(
+            transaction,
+            /* basic_check = */ true,
+            best_info.best_chain_id(),
+            best_info.best_epoch_number,
+        )
+    }
+
+    /// The spec of the epoch after `best_height`, where the transactions
+    /// admitted now are packed at the earliest.
+    pub fn admission_spec(&self, best_height: u64) -> Spec {
+        self.machine.spec(best_height + 1)
+    }
+
     /// verify transactions based on the rules that have nothing to do with
     /// readiness
     fn verify_transaction_tx_pool(
@@ -428,10 +571,12 @@ impl TransactionPool {
         let _timer = MeterTimer::time_func(TX_POOL_VERIFY_TIMER.as_ref());
 
         if basic_check {
-            if let Err(e) = self
-                .verification_config
-                .verify_transaction_common(transaction, chain_id)
-            {
+            let spec = self.admission_spec(best_height);
+            if let Err(e) = self.verification_config.verify_transaction_common(
+                transaction,
+                chain_id,
+                &spec,
+            ) {
                 warn!("Transaction {:?} discarded due to not passing basic verification.", transaction.hash());
                 return Err(format!("{:?}", e));
             }
@@ -464,25 +609,11 @@ impl TransactionPool {
             }
         }
 
-        // check transaction gas limit
-        if transaction.gas > self.config.max_tx_gas.into() {
-            warn!(
-                "Transaction discarded due to above gas limit: {} > {}",
-                transaction.gas, self.config.max_tx_gas
-            );
-            return Err(format!(
-                "transaction gas {} exceeds the maximum value {}",
-                transaction.gas, self.config.max_tx_gas
-            ));
-        }
-
-        // check transaction gas price
-        if transaction.gas_price < self.config.min_tx_price.into() {
-            trace!("Transaction {} discarded due to below minimal gas price: price {}", transaction.hash(), transaction.gas_price);
-            return Err(format!(
-                "transaction gas price {} less than the minimum value {}",
-                transaction.gas_price, self.config.min_tx_price
-            ));
+        // check transaction data size, gas limit and gas price
+        let admission_limits = self.config.read().admission_limits;
+        if let Err(e) = admission_limits.check(transaction) {
+            debug!("Transaction {:?} discarded: {}", transaction.hash(), e);
+            return Err(e.to_string());
         }
 
         Ok(())
@@ -557,6 +688,7 @@ impl TransactionPool {
         mut best_epoch_height: u64,
     ) -> Vec<Arc<SignedTransaction>>
     {
+        let _task = TX_POOL_HEARTBEAT.task("pack transactions");
         let mut inner = self.inner.write_with_metric(&PACK_TRANSACTION_LOCK);
         best_epoch_height += 1;
         let transaction_epoch_bound =
@@ -568,12 +700,20 @@ impl TransactionPool {
             0
         };
         let height_upper_bound = best_epoch_height + transaction_epoch_bound;
+        // The block is verified with the chain_id at its height.
+        let chain_id = self
+            .machine
+            .params()
+            .chain_id
+            .get_chain_id(best_epoch_height);
         inner.pack_transactions(
             num_txs,
             block_gas_limit,
             block_size_limit,
             height_lower_bound,
             height_upper_bound,
+            chain_id,
+            self.config.read().packing_strategy,
         )
     }
 
@@ -631,12 +771,16 @@ impl TransactionPool {
     pub fn notify_new_best_info(
         &self, best_info: Arc<BestInformation>,
     ) -> StateDbResult<()> {
+        let _task = TX_POOL_HEARTBEAT.task("notify new best info");
         let mut set_tx_buffer = self.set_tx_requests.lock();
         let mut recycle_tx_buffer = self.recycle_tx_requests.lock();
-        {
+        let chain_id_changed = {
             let mut consensus_best_info = self.consensus_best_info.lock();
+            let chain_id_changed = consensus_best_info.best_chain_id()
+                != best_info.best_chain_id();
             *consensus_best_info = best_info.clone();
-        }
+            chain_id_changed
+        };
 
         let account_cache = self.get_best_state_account_cache();
         let mut inner = self.inner.write_with_metric(&NOTIFY_BEST_INFO_LOCK);
@@ -657,6 +801,10 @@ impl TransactionPool {
             { (best_info.best_chain_id(), best_info.best_epoch_number) };
 
         while let Some(tx) = recycle_tx_buffer.pop() {
+            // Signed for the chain_id before a transition.
+            if tx.chain_id != chain_id {
+                continue;
+            }
             debug!(
                 "should not trigger recycle transaction, nonce = {}, sender = {:?}, \
                 account nonce = {}, hash = {:?} .",
@@ -684,6 +832,18 @@ impl TransactionPool {
             .ok();
         }
 
+        // The transactions admitted before a chain_id transition are dropped
+        // when it activates on the best epoch.
+        if chain_id_changed {
+            let removed = inner.remove_transactions_of_other_chains(chain_id);
+            if removed > 0 {
+                info!(
+                    "chain_id changes to {} at epoch {}, {} transactions removed",
+                    chain_id, best_height, removed
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -717,7 +877,7 @@ impl TransactionPool {
             + parent_block_gas_limit / gas_limit_divisor
             - 1;
 
-        let target_gas_limit = self.config.target_block_gas_limit.into();
+        let target_gas_limit = self.config.read().target_block_gas_limit.into();
         let self_gas_limit = min(max(target_gas_limit, gas_lower), gas_upper);
 
         let transactions_from_pool = self.pack_transactions(
@@ -773,3 +933,48 @@ impl TransactionPool {
         AccountCache::new((&*self.best_executed_state.lock()).clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TxAdmissionLimits;
+    use cfx_types::{Address, U256};
+    use primitives::{transaction::TransactionError, Transaction};
+
+    #[test]
+    fn test_admission_limits() {
+        let limits = TxAdmissionLimits {
+            max_tx_data_bytes: 4,
+            max_tx_gas: 100_000,
+            min_tx_gas_price: 10,
+        };
+        let tx = |data_len: usize, gas: u64, gas_price: u64| {
+            Transaction {
+                gas: gas.into(),
+                gas_price: gas_price.into(),
+                data: vec![0; data_len],
+                ..Default::default()
+            }
+            .fake_sign(Address::zero())
+        };
+
+        assert_eq!(limits.check(&tx(4, 100_000, 10)), Ok(()));
+        assert_eq!(
+            limits.check(&tx(5, 100_000, 10)),
+            Err(TransactionError::DataTooLarge { maximal: 4, got: 5 })
+        );
+        assert_eq!(
+            limits.check(&tx(4, 100_001, 10)),
+            Err(TransactionError::TooMuchGas {
+                maximal: U256::from(100_000),
+                got: U256::from(100_001),
+            })
+        );
+        assert_eq!(
+            limits.check(&tx(4, 100_000, 9)),
+            Err(TransactionError::InsufficientGasPrice {
+                minimal: U256::from(10),
+                got: U256::from(9),
+            })
+        );
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The strategies to select the ready transactions packed into a block.
+
+use cfx_types::U256;
+use primitives::SignedTransaction;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    str::FromStr,
+    sync::Arc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackingStrategy {
+    /// Sample the senders randomly, weighted by the gas price.
+    Random,
+    /// Pack the transactions with the highest gas price first.
+    GasPrice,
+    /// Pack the transactions with the highest fee per byte first, where the
+    /// fee is bounded by `gas_price * gas_limit`.
+    FeePerByte,
+    /// Pack one transaction of each sender by the gas price in a round, so
+    /// that a sender with many transactions can't fill up the block.
+    RoundRobin,
+}
+
+impl Default for PackingStrategy {
+    fn default() -> Self { PackingStrategy::Random }
+}
+
+impl FromStr for PackingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strategy = match s {
+            "random" => Self::Random,
+            "gas_price" => Self::GasPrice,
+            "fee_per_byte" => Self::FeePerByte,
+            "round_robin" => Self::RoundRobin,
+            _ => return Err("invalid packing strategy".into()),
+        };
+        Ok(strategy)
+    }
+}
+
+struct Candidate {
+    /// The number of transactions of the sender packed before.
+    round: usize,
+    priority: U256,
+    tx: Arc<SignedTransaction>,
+}
+
+impl Candidate {
+    fn key(&self) -> (Reverse<usize>, U256) {
+        (Reverse(self.round), self.priority)
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool { self.key() == other.key() }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering { self.key().cmp(&other.key()) }
+}
+
+/// Orders the ready transactions of the senders for the deterministic
+/// strategies. It holds at most one transaction of each sender, which is
+/// replaced by the next ready one of the sender after it's packed.
+pub struct PackingQueue {
+    strategy: PackingStrategy,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl PackingQueue {
+    pub fn new(strategy: PackingStrategy) -> Self {
+        PackingQueue {
+            strategy,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Add the ready transaction of a sender of which `packed` transactions
+    /// are packed before.
+    pub fn push(&mut self, tx: Arc<SignedTransaction>, packed: usize) {
+        let priority = match self.strategy {
+            PackingStrategy::FeePerByte => {
+                tx.gas_price.saturating_mul(tx.gas)
+                    / U256::from(tx.rlp_size().max(1))
+            }
+            _ => tx.gas_price,
+        };
+        let round = match self.strategy {
+            PackingStrategy::RoundRobin => packed,
+            _ => 0,
+        };
+        self.heap.push(Candidate {
+            round,
+            priority,
+            tx,
+        });
+    }
+
+    /// Take the next transaction to pack, with the number of transactions of
+    /// its sender packed before.
+    pub fn pop(&mut self) -> Option<(Arc<SignedTransaction>, usize)> {
+        self.heap
+            .pop()
+            .map(|candidate| (candidate.tx, candidate.round))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfx_types::Address;
+    use keylib::{Generator, KeyPair, Random};
+    use primitives::{Action, Transaction};
+
+    fn new_test_tx(
+        sender: &KeyPair, nonce: usize, gas_price: usize, data: usize,
+    ) -> Arc<SignedTransaction> {
+        Arc::new(
+            Transaction {
+                nonce: U256::from(nonce),
+                gas_price: U256::from(gas_price),
+                gas: U256::from(50000),
+                action: Action::Call(Address::random()),
+                value: U256::from(1),
+                storage_limit: 0,
+                epoch_height: 0,
+                chain_id: 0,
+                data: vec![0; data],
+            }
+            .sign(sender.secret()),
+        )
+    }
+
+    fn pop_all(queue: &mut PackingQueue) -> Vec<U256> {
+        let mut gas_prices = Vec::new();
+        while let Some((tx, _)) = queue.pop() {
+            gas_prices.push(tx.gas_price);
+        }
+        gas_prices
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("random".parse(), Ok(PackingStrategy::Random));
+        assert_eq!("gas_price".parse(), Ok(PackingStrategy::GasPrice));
+        assert_eq!("fee_per_byte".parse(), Ok(PackingStrategy::FeePerByte));
+        assert_eq!("round_robin".parse(), Ok(PackingStrategy::RoundRobin));
+        assert!("fifo".parse::<PackingStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_gas_price_and_fee_per_byte() {
+        let sender = Random.generate().unwrap();
+        let cheap = new_test_tx(&sender, 0, 10, 0);
+        let large = new_test_tx(&sender, 0, 20, 1000);
+
+        let mut queue = PackingQueue::new(PackingStrategy::GasPrice);
+        queue.push(cheap.clone(), 0);
+        queue.push(large.clone(), 0);
+        assert_eq!(pop_all(&mut queue), vec![20.into(), 10.into()]);
+
+        // The large transaction pays less per byte.
+        let mut queue = PackingQueue::new(PackingStrategy::FeePerByte);
+        queue.push(cheap, 0);
+        queue.push(large, 0);
+        assert_eq!(pop_all(&mut queue), vec![10.into(), 20.into()]);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let sender = Random.generate().unwrap();
+        let mut queue = PackingQueue::new(PackingStrategy::RoundRobin);
+        queue.push(new_test_tx(&sender, 0, 100, 0), 1);
+        queue.push(new_test_tx(&sender, 0, 10, 0), 0);
+        queue.push(new_test_tx(&sender, 0, 20, 0), 0);
+        assert_eq!(pop_all(&mut queue), vec![20.into(), 10.into(), 100.into()]);
+
+        // The rounds are ignored by the other strategies.
+        let mut queue = PackingQueue::new(PackingStrategy::GasPrice);
+        queue.push(new_test_tx(&sender, 0, 100, 0), 1);
+        queue.push(new_test_tx(&sender, 0, 10, 0), 0);
+        assert_eq!(pop_all(&mut queue), vec![100.into(), 10.into()]);
+    }
+}
@@ -3,6 +3,7 @@ use super::{
     garbage_collector::GarbageCollector,
     impls::TreapMap,
     nonce_pool::{InsertResult, NoncePool, TxWithReadyInfo},
+    packing::{PackingQueue, PackingStrategy},
 };
 use cfx_statedb::Result as StateDbResult;
 use cfx_types::{address_util::AddressUtil, Address, H256, U256};
@@ -15,7 +16,7 @@ use primitives::{
 };
 use rlp::*;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -110,6 +111,21 @@ impl DeferredPool {
         }
     }
 
+    fn remove(
+        &mut self, addr: &Address, nonce: &U256,
+    ) -> Option<TxWithReadyInfo> {
+        match self.buckets.get_mut(addr) {
+            None => None,
+            Some(bucket) => {
+                let ret = bucket.remove(nonce);
+                if bucket.is_empty() {
+                    self.buckets.remove(addr);
+                }
+                ret
+            }
+        }
+    }
+
     fn get_lowest_nonce(&self, addr: &Address) -> Option<&U256> {
         self.buckets
             .get(addr)
@@ -387,6 +403,49 @@ impl TransactionPoolInner {
         GC_METER.mark(count_before_gc - self.total_deferred());
     }
 
+    /// Remove the transactions not signed for `chain_id`, which can't be
+    /// packed after a chain_id transition. Return the number of the removed
+    /// transactions.
+    pub fn remove_transactions_of_other_chains(
+        &mut self, chain_id: u32,
+    ) -> usize {
+        let stale: Vec<_> = self
+            .txs
+            .values()
+            .filter(|tx| tx.chain_id != chain_id)
+            .cloned()
+            .collect();
+        let mut senders = HashSet::new();
+        for tx in &stale {
+            let sender = tx.sender();
+            if let Some(removed) =
+                self.deferred_pool.remove(&sender, &tx.nonce())
+            {
+                if !removed.is_already_packed() {
+                    self.unpacked_transaction_count -= 1;
+                }
+            }
+            if let Some(ready_tx) = self.ready_account_pool.get(&sender) {
+                if ready_tx.hash() == tx.hash() {
+                    self.ready_account_pool.remove(&sender);
+                }
+            }
+            self.txs.remove(&tx.hash());
+            self.tx_sponsored_gas_map.remove(&tx.hash());
+            senders.insert(sender);
+        }
+        // The senders without transactions are ignored by the garbage
+        // collector.
+        for sender in &senders {
+            if self.deferred_pool.contain_address(sender) {
+                self.recalculate_readiness_with_local_info(sender);
+            } else {
+                self.ready_nonces_and_balances.remove(sender);
+            }
+        }
+        stale.len()
+    }
+
     /// Collect garbage and return the remaining quota of the pool to insert new
     /// transactions.
     pub fn remaining_quota(&mut self) -> usize {
@@ -571,11 +630,12 @@ impl TransactionPoolInner {
         }
     }
 
-    /// pack at most num_txs transactions randomly
+    /// pack at most num_txs transactions selected by the packing strategy
     pub fn pack_transactions<'a>(
         &mut self, num_txs: usize, block_gas_limit: U256,
         block_size_limit: usize, epoch_height_lower_bound: u64,
-        epoch_height_upper_bound: u64,
+        epoch_height_upper_bound: u64, chain_id: u32,
+        strategy: PackingStrategy,
     ) -> Vec<Arc<SignedTransaction>>
     {
         let mut packed_transactions: Vec<Arc<SignedTransaction>> = Vec::new();
@@ -589,7 +649,31 @@ impl TransactionPoolInner {
         let mut big_tx_resample_times_limit = 10;
         let mut recycle_txs = Vec::new();
 
-        'out: while let Some(tx) = self.ready_account_pool.pop() {
+        // The deterministic strategies order the ready transactions of all
+        // the senders, while the random one samples the ready pool directly.
+        let mut queue = PackingQueue::new(strategy);
+        if strategy != PackingStrategy::Random {
+            for (_, tx) in self.ready_account_pool.treap.iter() {
+                queue.push(tx.clone(), 0);
+            }
+        }
+
+        'out: loop {
+            let (tx, packed_of_sender) = match strategy {
+                PackingStrategy::Random => {
+                    match self.ready_account_pool.pop() {
+                        Some(tx) => (tx, 0),
+                        None => break 'out,
+                    }
+                }
+                _ => match queue.pop() {
+                    Some((tx, packed_of_sender)) => {
+                        self.ready_account_pool.remove(&tx.sender());
+                        (tx, packed_of_sender)
+                    }
+                    None => break 'out,
+                },
+            };
             let tx_size = tx.rlp_size();
             if block_gas_limit - total_tx_gas_limit < *tx.gas_limit()
                 || block_size_limit - total_tx_size < tx_size
@@ -611,6 +695,13 @@ impl TransactionPoolInner {
                 recycle_txs.push(tx.clone());
                 continue 'out;
             }
+            // The transactions signed for the chain_id before a transition
+            // can't be packed from its activation epoch, and are removed from
+            // the pool when the best epoch reaches the activation epoch.
+            if tx.chain_id != chain_id {
+                recycle_txs.push(tx.clone());
+                continue 'out;
+            }
 
             total_tx_gas_limit += *tx.gas_limit();
             total_tx_size += tx_size;
@@ -627,6 +718,12 @@ impl TransactionPoolInner {
                     .unwrap_or(U256::from(0)),
             );
             self.recalculate_readiness_with_local_info(&tx.sender());
+            if strategy != PackingStrategy::Random {
+                if let Some(next_tx) = self.ready_account_pool.get(&tx.sender())
+                {
+                    queue.push(next_tx, packed_of_sender + 1);
+                }
+            }
 
             if packed_transactions.len() >= num_txs {
                 break 'out;
@@ -814,7 +911,10 @@ impl TransactionPoolInner {
 
 #[cfg(test)]
 mod test_transaction_pool_inner {
-    use super::{DeferredPool, InsertResult, TxWithReadyInfo};
+    use super::{
+        DeferredPool, InsertResult, TransactionPoolInner, TxWithReadyInfo,
+    };
+    use crate::transaction_pool::packing::PackingStrategy;
     use cfx_types::{Address, U256};
     use keylib::{Generator, KeyPair, Random};
     use primitives::{Action, SignedTransaction, Transaction};
@@ -1066,4 +1166,61 @@ mod test_transaction_pool_inner {
             None
         );
     }
+
+    #[test]
+    fn test_transactions_of_other_chains() {
+        let mut pool = TransactionPoolInner::new(100, 1, 1);
+        let alice = Random.generate().unwrap();
+        let bob = Random.generate().unwrap();
+        let new_tx = |sender: &KeyPair, nonce: usize, chain_id: u32| {
+            Arc::new(
+                Transaction {
+                    nonce: U256::from(nonce),
+                    gas_price: U256::from(1),
+                    gas: U256::from(50000),
+                    action: Action::Call(Address::random()),
+                    value: U256::from(1),
+                    storage_limit: 0,
+                    epoch_height: 0,
+                    chain_id,
+                    data: Vec::new(),
+                }
+                .sign(sender.secret()),
+            )
+        };
+        let alice_txs = vec![new_tx(&alice, 0, 1), new_tx(&alice, 1, 1)];
+        let bob_tx = new_tx(&bob, 0, 2);
+        for tx in alice_txs.iter().chain(Some(&bob_tx)) {
+            pool.insert_transaction_without_readiness_check(
+                tx.clone(),
+                false, /* packed */
+                false, /* force */
+                Some((0.into(), 1_000_000_000.into())),
+                0.into(),
+            );
+            pool.recalculate_readiness_with_local_info(&tx.sender());
+        }
+        assert_eq!(pool.total_ready_accounts(), 2);
+
+        // only the transactions of the chain_id at the packed height
+        let packed = pool.pack_transactions(
+            10,
+            1_000_000.into(),
+            1_000_000,
+            0,
+            100,
+            2, /* chain_id */
+            PackingStrategy::GasPrice,
+        );
+        assert_eq!(packed, vec![bob_tx.clone()]);
+        assert_eq!(pool.total_ready_accounts(), 2);
+
+        assert_eq!(pool.remove_transactions_of_other_chains(2), 2);
+        assert_eq!(pool.total_deferred(), 1);
+        assert_eq!(pool.total_unpacked(), 1);
+        assert_eq!(pool.total_ready_accounts(), 1);
+        assert!(pool.get(&alice_txs[0].hash()).is_none());
+        assert!(pool.get(&bob_tx.hash()).is_some());
+        assert!(pool.get_local_nonce_and_balance(&alice.address()).is_none());
+    }
 }
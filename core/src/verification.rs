@@ -17,7 +17,8 @@ use cfx_storage::{
 use cfx_types::{BigEndianHash, H256, U256};
 use primitives::{
     transaction::TransactionError, Action, Block, BlockHeader, BlockReceipts,
-    MerkleHash, Receipt, SignedTransaction, TransactionWithSignature,
+    ChainIdParams, MerkleHash, Receipt, ReceiptProof, SignedTransaction,
+    TransactionWithSignature,
 };
 use rlp::Encodable;
 use std::{collections::HashSet, convert::TryInto, sync::Arc};
@@ -29,7 +30,6 @@ pub struct VerificationConfig {
     pub referee_bound: usize,
     pub max_block_size_in_bytes: usize,
     pub transaction_epoch_bound: u64,
-    vm_spec: vm::Spec,
 }
 
 /// Create an MPT from the ordered list of block transactions.
@@ -135,6 +135,59 @@ pub fn compute_epoch_receipt_proof(
     }
 }
 
+/// Prove the `tx_index_in_block`-th receipt in the `block_index_in_epoch`-th
+/// block of `epoch_receipts`, if it exists.
+pub fn compute_receipt_proof(
+    epoch_receipts: &Vec<Arc<BlockReceipts>>, block_index_in_epoch: usize,
+    tx_index_in_block: usize,
+) -> Option<ReceiptProof>
+{
+    let block_receipts = &epoch_receipts.get(block_index_in_epoch)?.receipts;
+    let receipt = block_receipts.get(tx_index_in_block)?.clone();
+    let proof = compute_epoch_receipt_proof(
+        epoch_receipts,
+        block_index_in_epoch,
+        tx_index_in_block,
+    );
+
+    Some(ReceiptProof {
+        receipt,
+        block_index_in_epoch,
+        num_blocks_in_epoch: epoch_receipts.len(),
+        block_index_proof: rlp::encode(&proof.block_index_proof),
+        tx_index_in_block,
+        num_txs_in_block: block_receipts.len(),
+        block_receipt_proof: rlp::encode(&proof.block_receipt_proof),
+    })
+}
+
+/// Whether the receipt of `proof` is included under
+/// `verified_epoch_receipts_root`.
+pub fn is_valid_receipt_proof(
+    proof: &ReceiptProof, verified_epoch_receipts_root: MerkleHash,
+) -> bool {
+    let block_index_proof: TrieProof =
+        match rlp::decode(&proof.block_index_proof) {
+            Ok(trie_proof) => trie_proof,
+            Err(_) => return false,
+        };
+    let block_receipt_proof: TrieProof =
+        match rlp::decode(&proof.block_receipt_proof) {
+            Ok(trie_proof) => trie_proof,
+            Err(_) => return false,
+        };
+    is_valid_receipt_inclusion_proof(
+        verified_epoch_receipts_root,
+        proof.block_index_in_epoch,
+        proof.num_blocks_in_epoch,
+        &block_index_proof,
+        proof.tx_index_in_block,
+        proof.num_txs_in_block,
+        &proof.receipt,
+        &block_receipt_proof,
+    )
+}
+
 /// Use `proof` to verify that `tx_hash` is indeed the `tx_index_in_block`-th
 /// transaction in a block with `num_txs_in_block` transactions and transaction
 /// root `block_tx_root`.
@@ -208,7 +261,6 @@ impl VerificationConfig {
                 referee_bound,
                 max_block_size_in_bytes,
                 transaction_epoch_bound,
-                vm_spec: vm::Spec::new_spec(),
             }
         } else {
             VerificationConfig {
@@ -216,7 +268,6 @@ impl VerificationConfig {
                 referee_bound,
                 max_block_size_in_bytes,
                 transaction_epoch_bound,
-                vm_spec: vm::Spec::new_spec(),
             }
         }
     }
@@ -383,9 +434,13 @@ impl VerificationConfig {
     /// body again from others. However, if the body matches the header and
     /// the body is incorrect, this means the block is invalid, and we
     /// should discard this block and all its descendants.
+    ///
+    /// The block is executed in an epoch not lower than its height, so its
+    /// transactions may have the chain_id of any later epoch. The chain_id of
+    /// the epoch is enforced in the execution.
     #[inline]
     pub fn verify_block_basic(
-        &self, block: &Block, chain_id: u32,
+        &self, block: &Block, chain_id: &ChainIdParams, spec: &vm::Spec,
     ) -> Result<(), Error> {
         self.verify_block_integrity(block)?;
 
@@ -394,7 +449,18 @@ impl VerificationConfig {
 
         let block_height = block.block_header.height();
         for t in &block.transactions {
-            self.verify_transaction_in_block(t, chain_id, block_height)?;
+            if !chain_id.is_chain_id_since(t.chain_id, block_height) {
+                return Err(From::from(TransactionError::ChainIdMismatch {
+                    expected: chain_id.get_chain_id(block_height),
+                    got: t.chain_id,
+                }));
+            }
+            self.verify_transaction_in_block(
+                t,
+                t.chain_id,
+                block_height,
+                spec,
+            )?;
             block_size += t.rlp_size();
             block_total_gas += *t.gas_limit();
         }
@@ -459,8 +525,10 @@ impl VerificationConfig {
 
     pub fn verify_transaction_in_block(
         &self, tx: &TransactionWithSignature, chain_id: u32, block_height: u64,
-    ) -> Result<(), TransactionError> {
-        self.verify_transaction_common(tx, chain_id)?;
+        spec: &vm::Spec,
+    ) -> Result<(), TransactionError>
+    {
+        self.verify_transaction_common(tx, chain_id, spec)?;
         Self::verify_transaction_epoch_height(
             tx,
             block_height,
@@ -468,11 +536,11 @@ impl VerificationConfig {
         )
     }
 
+    /// Checks the transaction against the rules of `spec`, which should be
+    /// the spec of the epoch the transaction is packed or to be packed in.
     pub fn verify_transaction_common(
-        &self, tx: &TransactionWithSignature, chain_id: u32,
+        &self, tx: &TransactionWithSignature, chain_id: u32, spec: &vm::Spec,
     ) -> Result<(), TransactionError> {
-        tx.check_low_s()?;
-
         // Disallow unsigned transactions
         if tx.is_unsigned() {
             bail!(TransactionError::InvalidSignature(
@@ -480,6 +548,8 @@ impl VerificationConfig {
             ));
         }
 
+        tx.verify(&spec.signature_policy)?;
+
         if tx.chain_id != chain_id {
             bail!(TransactionError::ChainIdMismatch {
                 expected: chain_id,
@@ -491,7 +561,7 @@ impl VerificationConfig {
         let tx_intrinsic_gas = Executive::gas_required_for(
             tx.action == Action::Create,
             &tx.data,
-            &self.vm_spec,
+            spec,
         );
         if tx.gas < (tx_intrinsic_gas as usize).into() {
             bail!(TransactionError::NotEnoughBaseGas {
@@ -503,3 +573,70 @@ impl VerificationConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        compute_receipt_proof, compute_receipts_root, is_valid_receipt_proof,
+    };
+    use primitives::{BlockReceipts, Receipt, ReceiptProof};
+    use std::sync::Arc;
+
+    fn block_receipts(num_txs: usize) -> Arc<BlockReceipts> {
+        let receipts = (0..num_txs)
+            .map(|i| {
+                Receipt::new(
+                    0,
+                    (21_000 * (i + 1)).into(),
+                    i.into(),
+                    false,
+                    vec![],
+                    false,
+                    vec![],
+                    vec![],
+                )
+            })
+            .collect();
+        Arc::new(BlockReceipts {
+            receipts,
+            secondary_reward: 0.into(),
+            tx_execution_error_messages: vec![],
+        })
+    }
+
+    #[test]
+    fn test_receipt_proof() {
+        let epoch_receipts =
+            vec![block_receipts(3), block_receipts(0), block_receipts(5)];
+        let root = compute_receipts_root(&epoch_receipts);
+
+        let proof = compute_receipt_proof(&epoch_receipts, 2, 4).unwrap();
+        assert_eq!(proof.receipt, epoch_receipts[2].receipts[4]);
+        assert!(is_valid_receipt_proof(&proof, root));
+        let decoded: ReceiptProof = rlp::decode(&rlp::encode(&proof)).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(is_valid_receipt_proof(&decoded, root));
+
+        // The receipt is not at another position.
+        let mut moved = proof.clone();
+        moved.tx_index_in_block = 3;
+        assert!(!is_valid_receipt_proof(&moved, root));
+
+        // The receipt is changed.
+        let mut changed = proof.clone();
+        changed.receipt.gas_fee = 0.into();
+        assert!(!is_valid_receipt_proof(&changed, root));
+
+        // The trie proof is malformed.
+        let mut malformed = proof.clone();
+        malformed.block_receipt_proof.truncate(1);
+        assert!(!is_valid_receipt_proof(&malformed, root));
+
+        // The proof is against another epoch.
+        let other_root = compute_receipts_root(&vec![block_receipts(5)]);
+        assert!(!is_valid_receipt_proof(&proof, other_root));
+
+        assert!(compute_receipt_proof(&epoch_receipts, 1, 0).is_none());
+        assert!(compute_receipt_proof(&epoch_receipts, 3, 0).is_none());
+    }
+}
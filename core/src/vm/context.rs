@@ -181,6 +181,9 @@ pub trait Context {
     ) {
     }
 
+    /// Trace a value loaded from storage. Passthrough for the tracer.
+    fn trace_storage_read(&mut self, _key: &[u8], _value: &U256) {}
+
     /// Check if running in static context.
     fn is_static(&self) -> bool;
 
@@ -20,6 +20,10 @@
 
 //! Cost spec and other parameterisations for the EVM.
 
+use cfx_parameters::staking::COLLATERAL_PER_BYTE;
+use cfx_types::U256;
+use primitives::SignaturePolicy;
+
 /// Definition of the cost spec and other parameterisations for the VM.
 #[derive(Debug, Clone)]
 pub struct Spec {
@@ -103,6 +107,8 @@ pub struct Spec {
     pub extcodehash_gas: usize,
     /// Price of SUICIDE
     pub suicide_gas: usize,
+    /// SUICIDE opcode enabled.
+    pub have_suicide: bool,
     /// Amount of additional gas to pay when SUICIDE credits a non-existant
     /// account
     pub suicide_to_new_account_cost: usize,
@@ -138,8 +144,17 @@ pub struct Spec {
     /// VM execution does not increase null signed address nonce if this field
     /// is true.
     pub keep_unsigned_nonce: bool,
+    /// If more than `1 / unused_gas_refund_divisor` of the gas limit is left
+    /// unused, only this portion of the gas limit is refunded.
+    pub unused_gas_refund_divisor: usize,
     /// Wasm extra specs, if wasm activated
     pub wasm: Option<WasmCosts>,
+    /// The storage collateral in drip for one byte.
+    pub collateral_per_byte: U256,
+    /// The rules of the transaction signatures.
+    pub signature_policy: SignaturePolicy,
+    /// Whether the batch transfer internal contract is enabled.
+    pub batch_transfer: bool,
 }
 
 /// Wasm cost table
@@ -260,6 +275,7 @@ impl Spec {
             extcodehash_gas: 400,
             balance_gas: 400,
             suicide_gas: 5000,
+            have_suicide: true,
             suicide_to_new_account_cost: 25000,
             sub_gas_cap_divisor: Some(64),
             no_empty,
@@ -268,7 +284,11 @@ impl Spec {
             have_static_call: false,
             kill_dust: CleanDustMode::Off,
             keep_unsigned_nonce: false,
+            unused_gas_refund_divisor: 4,
             wasm: None,
+            collateral_per_byte: *COLLATERAL_PER_BYTE,
+            signature_policy: SignaturePolicy::legacy(),
+            batch_transfer: false,
         }
     }
 
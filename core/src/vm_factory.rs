@@ -2,6 +2,8 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
+#[cfg(feature = "wasm")]
+use crate::wasm;
 use crate::{
     evm::{Factory as EvmFactory, VMType},
     vm::{ActionParams, Exec, Spec},
@@ -17,6 +19,17 @@ impl VmFactory {
     pub fn create(
         &self, params: ActionParams, spec: &Spec, depth: usize,
     ) -> Box<dyn Exec> {
+        #[cfg(feature = "wasm")]
+        {
+            if spec.wasm.is_some()
+                && params
+                    .code
+                    .as_ref()
+                    .map_or(false, |code| wasm::is_wasm_code(code))
+            {
+                return Box::new(wasm::WasmInterpreter::new(params));
+            }
+        }
         self.evm.create(params, spec, depth)
     }
 
@@ -0,0 +1,190 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The "env" module imported by the wasm contracts, which provides the
+//! memory and the host functions.
+
+use std::cell::RefCell;
+use wasmi::{
+    self, memory_units::Pages, Error, FuncInstance, FuncRef, MemoryDescriptor,
+    MemoryInstance, MemoryRef, Signature,
+};
+
+/// The indices of the host functions, dispatched by the runtime.
+pub mod ids {
+    pub const STORAGE_READ_FUNC: usize = 0;
+    pub const STORAGE_WRITE_FUNC: usize = 1;
+    pub const RET_FUNC: usize = 2;
+    pub const GAS_FUNC: usize = 3;
+    pub const INPUT_LENGTH_FUNC: usize = 4;
+    pub const FETCH_INPUT_FUNC: usize = 5;
+    pub const PANIC_FUNC: usize = 6;
+    pub const ADDRESS_FUNC: usize = 7;
+    pub const SENDER_FUNC: usize = 8;
+    pub const ORIGIN_FUNC: usize = 9;
+    pub const VALUE_FUNC: usize = 10;
+    pub const BALANCE_FUNC: usize = 11;
+    pub const BLOCKNUMBER_FUNC: usize = 12;
+    pub const TIMESTAMP_FUNC: usize = 13;
+    pub const ELOG_FUNC: usize = 14;
+    pub const GASLEFT_FUNC: usize = 15;
+}
+
+/// The signatures of the host functions.
+pub mod signatures {
+    use wasmi::{self, ValueType, ValueType::*};
+
+    pub struct StaticSignature(pub &'static [ValueType], pub Option<ValueType>);
+
+    pub const STORAGE_READ: StaticSignature =
+        StaticSignature(&[I32, I32], None);
+
+    pub const STORAGE_WRITE: StaticSignature =
+        StaticSignature(&[I32, I32], None);
+
+    pub const RET: StaticSignature = StaticSignature(&[I32, I32], None);
+
+    pub const GAS: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const INPUT_LENGTH: StaticSignature = StaticSignature(&[], Some(I32));
+
+    pub const FETCH_INPUT: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const PANIC: StaticSignature = StaticSignature(&[I32, I32], None);
+
+    pub const ADDRESS: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const SENDER: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const ORIGIN: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const VALUE: StaticSignature = StaticSignature(&[I32], None);
+
+    pub const BALANCE: StaticSignature = StaticSignature(&[I32, I32], None);
+
+    pub const BLOCKNUMBER: StaticSignature = StaticSignature(&[], Some(I64));
+
+    pub const TIMESTAMP: StaticSignature = StaticSignature(&[], Some(I64));
+
+    pub const ELOG: StaticSignature =
+        StaticSignature(&[I32, I32, I32, I32], None);
+
+    pub const GASLEFT: StaticSignature = StaticSignature(&[], Some(I64));
+
+    impl Into<wasmi::Signature> for StaticSignature {
+        fn into(self) -> wasmi::Signature {
+            wasmi::Signature::new(self.0, self.1)
+        }
+    }
+}
+
+fn host(signature: signatures::StaticSignature, idx: usize) -> FuncRef {
+    FuncInstance::alloc_host(signature.into(), idx)
+}
+
+/// Resolves the imports of a contract, and keeps the memory imported by it.
+pub struct ImportResolver {
+    /// The maximal number of memory pages a contract can use.
+    max_memory: u32,
+    memory: RefCell<Option<MemoryRef>>,
+    have_gasleft: bool,
+}
+
+impl ImportResolver {
+    pub fn with_limit(max_memory: u32, have_gasleft: bool) -> ImportResolver {
+        ImportResolver {
+            max_memory,
+            memory: RefCell::new(None),
+            have_gasleft,
+        }
+    }
+
+    /// The memory imported by the contract, or an empty memory if the
+    /// contract does not import one.
+    pub fn memory_ref(&self) -> MemoryRef {
+        let mut memory = self.memory.borrow_mut();
+        if memory.is_none() {
+            *memory = Some(
+                MemoryInstance::alloc(Pages(0), Some(Pages(0)))
+                    .expect("Allocating an empty memory never fails; qed"),
+            );
+        }
+        memory.as_ref().expect("Memory is set above; qed").clone()
+    }
+
+    /// The initial number of memory pages.
+    pub fn memory_size(&self) -> u32 {
+        self.memory_ref().current_size().0 as u32
+    }
+}
+
+impl wasmi::ModuleImportResolver for ImportResolver {
+    fn resolve_func(
+        &self, field_name: &str, _signature: &Signature,
+    ) -> Result<FuncRef, Error> {
+        // The signature is checked against the declared one by wasmi on
+        // instantiation.
+        let func_ref = match field_name {
+            "storage_read" => {
+                host(signatures::STORAGE_READ, ids::STORAGE_READ_FUNC)
+            }
+            "storage_write" => {
+                host(signatures::STORAGE_WRITE, ids::STORAGE_WRITE_FUNC)
+            }
+            "ret" => host(signatures::RET, ids::RET_FUNC),
+            "gas" => host(signatures::GAS, ids::GAS_FUNC),
+            "input_length" => {
+                host(signatures::INPUT_LENGTH, ids::INPUT_LENGTH_FUNC)
+            }
+            "fetch_input" => {
+                host(signatures::FETCH_INPUT, ids::FETCH_INPUT_FUNC)
+            }
+            "panic" => host(signatures::PANIC, ids::PANIC_FUNC),
+            "address" => host(signatures::ADDRESS, ids::ADDRESS_FUNC),
+            "sender" => host(signatures::SENDER, ids::SENDER_FUNC),
+            "origin" => host(signatures::ORIGIN, ids::ORIGIN_FUNC),
+            "value" => host(signatures::VALUE, ids::VALUE_FUNC),
+            "balance" => host(signatures::BALANCE, ids::BALANCE_FUNC),
+            "blocknumber" => {
+                host(signatures::BLOCKNUMBER, ids::BLOCKNUMBER_FUNC)
+            }
+            "timestamp" => host(signatures::TIMESTAMP, ids::TIMESTAMP_FUNC),
+            "elog" => host(signatures::ELOG, ids::ELOG_FUNC),
+            "gasleft" if self.have_gasleft => {
+                host(signatures::GASLEFT, ids::GASLEFT_FUNC)
+            }
+            _ => {
+                return Err(Error::Instantiation(format!(
+                    "Export {} not found",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+
+    fn resolve_memory(
+        &self, field_name: &str, descriptor: &MemoryDescriptor,
+    ) -> Result<MemoryRef, Error> {
+        if field_name != "memory" {
+            return Err(Error::Instantiation(
+                "Memory imported under unknown name".to_owned(),
+            ));
+        }
+        let effective_max = descriptor.maximum().unwrap_or(self.max_memory + 1);
+        if descriptor.initial() > self.max_memory
+            || effective_max > self.max_memory
+        {
+            return Err(Error::Instantiation(
+                "Module requested too much memory".to_owned(),
+            ));
+        }
+        let memory = MemoryInstance::alloc(
+            Pages(descriptor.initial() as usize),
+            descriptor.maximum().map(|x| Pages(x as usize)),
+        )?;
+        *self.memory.borrow_mut() = Some(memory.clone());
+        Ok(memory)
+    }
+}
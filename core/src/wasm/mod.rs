@@ -0,0 +1,154 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Experimental WebAssembly contract engine.
+//!
+//! Once `Spec::wasm` is activated, the code starting with the wasm magic
+//! number is executed by this engine instead of the EVM. The contract imports
+//! its memory and the host functions from the "env" module, which access the
+//! state through the same `vm::Context` as the EVM. The creation runs the
+//! exported `deploy` function, which returns the code of the contract with
+//! `ret`, and a call runs the exported `call` function.
+//!
+//! The constructor takes no input, and calling or creating other contracts
+//! from a wasm contract is not supported yet.
+
+mod env;
+mod parser;
+mod runtime;
+
+#[cfg(test)]
+mod tests;
+
+use self::runtime::{Runtime, RuntimeContext};
+use crate::vm::{
+    self, ActionParams, CallType, Context, ExecTrapResult, GasLeft, ReturnData,
+};
+use cfx_types::U256;
+use wasmi::{self, ImportsBuilder, ModuleInstance};
+
+/// The prefix of the wasm code.
+pub const WASM_MAGIC_NUMBER: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+
+/// The maximal number of memory pages (64KB each) a contract can use.
+const MAX_MEMORY_PAGES: u32 = 16;
+
+pub fn is_wasm_code(code: &[u8]) -> bool {
+    code.len() > 4 && code[..4] == WASM_MAGIC_NUMBER
+}
+
+pub struct WasmInterpreter {
+    params: ActionParams,
+}
+
+impl WasmInterpreter {
+    pub fn new(params: ActionParams) -> Self { WasmInterpreter { params } }
+
+    fn run(self, context: &mut dyn Context) -> vm::Result<GasLeft> {
+        let wasm_costs = context.spec().wasm().clone();
+        let (module, data) = parser::payload(&self.params, &wasm_costs)?;
+        let loaded_module = wasmi::Module::from_parity_wasm_module(module)
+            .map_err(|err| vm::Error::Wasm(format!("{}", err)))?;
+        let resolver = env::ImportResolver::with_limit(
+            MAX_MEMORY_PAGES,
+            wasm_costs.have_gasleft,
+        );
+        let imports = ImportsBuilder::new().with_resolver("env", &resolver);
+        let module_instance = ModuleInstance::new(&loaded_module, &imports)
+            .map_err(|err| vm::Error::Wasm(format!("{}", err)))?;
+
+        // The gas is measured in finer units in wasm.
+        let adjusted_gas = self.params.gas * U256::from(wasm_costs.opcodes_div)
+            / U256::from(wasm_costs.opcodes_mul);
+        if adjusted_gas > U256::from(u64::max_value()) {
+            return Err(vm::Error::Wasm(
+                "Wasm interpreter cannot run contracts with gas (wasm \
+                 adjusted) >= 2^64"
+                    .to_owned(),
+            ));
+        }
+        let initial_memory = resolver.memory_size();
+        let export = match self.params.call_type {
+            CallType::None => "deploy",
+            _ => "call",
+        };
+
+        let (gas_left, result) = {
+            let mut runtime = Runtime::with_params(
+                context,
+                resolver.memory_ref(),
+                adjusted_gas.low_u64(),
+                data.to_vec(),
+                RuntimeContext {
+                    address: self.params.address,
+                    sender: self.params.sender,
+                    origin: self.params.original_sender,
+                    value: self.params.value.value(),
+                },
+            );
+            // The initial memory is less than `MAX_MEMORY_PAGES`, so the
+            // charge never overflows.
+            runtime
+                .charge_gas(
+                    initial_memory as u64 * wasm_costs.initial_mem as u64,
+                )
+                .map_err(|_| vm::Error::OutOfGas)?;
+            let invoke_result = module_instance
+                .run_start(&mut runtime)
+                .map_err(wasmi::Error::Trap)
+                .and_then(|instance| {
+                    instance.invoke_export(export, &[], &mut runtime)
+                });
+            let returned = match invoke_result {
+                Ok(_) => false,
+                Err(err) => match err
+                    .as_host_error()
+                    .and_then(|err| err.downcast_ref::<runtime::Error>())
+                {
+                    Some(runtime::Error::Return) => true,
+                    Some(runtime::Error::GasLimit) => {
+                        return Err(vm::Error::OutOfGas);
+                    }
+                    Some(runtime::Error::Context) => {
+                        return Err(runtime
+                            .take_vm_error()
+                            .expect("Context error is kept by runtime; qed"));
+                    }
+                    _ => return Err(vm::Error::Wasm(format!("{}", err))),
+                },
+            };
+            let gas_left = U256::from(runtime.gas_left())
+                * U256::from(wasm_costs.opcodes_mul)
+                / U256::from(wasm_costs.opcodes_div);
+            (
+                gas_left,
+                if returned {
+                    Some(runtime.into_result())
+                } else {
+                    None
+                },
+            )
+        };
+
+        Ok(match result {
+            Some(result) => {
+                let len = result.len();
+                GasLeft::NeedsReturn {
+                    gas_left,
+                    data: ReturnData::new(result, 0, len),
+                    apply_state: true,
+                }
+            }
+            None => GasLeft::Known(gas_left),
+        })
+    }
+}
+
+impl vm::Exec for WasmInterpreter {
+    fn exec(
+        self: Box<Self>, context: &mut dyn Context,
+    ) -> ExecTrapResult<GasLeft> {
+        Ok(self.run(context))
+    }
+}
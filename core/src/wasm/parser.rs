@@ -0,0 +1,88 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Loading of the wasm contract code with gas metering injected.
+
+use crate::vm::{self, ActionParams, WasmCosts};
+use parity_wasm::elements::{self, Deserialize};
+use pwasm_utils::{self, rules};
+
+fn gas_rules(wasm_costs: &WasmCosts) -> rules::Set {
+    rules::Set::new(wasm_costs.regular, {
+        let mut vals = ::std::collections::BTreeMap::new();
+        vals.insert(
+            rules::InstructionType::Load,
+            rules::Metering::Fixed(wasm_costs.mem),
+        );
+        vals.insert(
+            rules::InstructionType::Store,
+            rules::Metering::Fixed(wasm_costs.mem),
+        );
+        vals.insert(
+            rules::InstructionType::Div,
+            rules::Metering::Fixed(wasm_costs.div),
+        );
+        vals.insert(
+            rules::InstructionType::Mul,
+            rules::Metering::Fixed(wasm_costs.mul),
+        );
+        vals
+    })
+    .with_grow_cost(wasm_costs.grow_mem)
+    .with_forbidden_floats()
+}
+
+/// Load the module of the contract code, with the gas counter and the stack
+/// height limiter injected, and return it with the input data.
+pub fn payload<'a>(
+    params: &'a ActionParams, wasm_costs: &WasmCosts,
+) -> Result<(elements::Module, &'a [u8]), vm::Error> {
+    let code = match params.code {
+        Some(ref code) => &code[..],
+        None => {
+            return Err(vm::Error::Wasm("Invalid wasm call".to_owned()));
+        }
+    };
+
+    let module =
+        elements::Module::deserialize(&mut ::std::io::Cursor::new(code))
+            .map_err(|err| {
+                vm::Error::Wasm(format!(
+                    "Error deserializing contract code ({:?})",
+                    err
+                ))
+            })?;
+
+    if module
+        .memory_section()
+        .map_or(false, |ms| ms.entries().len() > 0)
+    {
+        // The internal memory of a module is hidden from the embedder, so
+        // the contract must import its memory instead.
+        return Err(vm::Error::Wasm(
+            "Malformed wasm module: internal memory".to_owned(),
+        ));
+    }
+
+    let module =
+        pwasm_utils::inject_gas_counter(module, &gas_rules(wasm_costs))
+            .map_err(|_| {
+                vm::Error::Wasm(
+                    "Wasm contract error: bytecode invalid".to_owned(),
+                )
+            })?;
+    let module = pwasm_utils::stack_height::inject_limiter(
+        module,
+        wasm_costs.max_stack_height,
+    )
+    .map_err(|_| {
+        vm::Error::Wasm("Wasm contract error: stack limiter failure".to_owned())
+    })?;
+
+    let data = match params.data {
+        Some(ref data) => &data[..],
+        None => &[],
+    };
+    Ok((module, data))
+}
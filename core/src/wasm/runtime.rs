@@ -0,0 +1,335 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The host functions of the wasm contracts, implemented on the same
+//! `vm::Context` as the EVM, so that the state access and the charging are
+//! shared by both engines.
+
+use super::env::ids::*;
+use crate::vm::{self, Spec};
+use cfx_types::{Address, BigEndianHash, H256, U256};
+use std::fmt;
+use wasmi::{self, Externals, MemoryRef, RuntimeArgs, RuntimeValue, Trap};
+
+/// The values of the frame provided to the contract.
+pub struct RuntimeContext {
+    pub address: Address,
+    pub sender: Address,
+    pub origin: Address,
+    pub value: U256,
+}
+
+/// The errors raised by the host functions, which abort the execution.
+#[derive(Debug)]
+pub enum Error {
+    /// The contract returns normally with the result set by `ret`.
+    Return,
+    /// The contract panics with a message.
+    Panic(String),
+    /// The gas is exhausted.
+    GasLimit,
+    /// The contract accesses the memory out of bounds.
+    MemoryAccessViolation,
+    /// The contract calls a host function with invalid arguments.
+    InvalidArguments,
+    /// The host function is not known.
+    UnknownFunction(usize),
+    /// The context fails, e.g. on a mutable call in the static context.
+    /// The error is kept by the runtime.
+    Context,
+}
+
+impl wasmi::HostError for Error {}
+
+impl From<wasmi::Error> for Error {
+    fn from(_: wasmi::Error) -> Self { Error::MemoryAccessViolation }
+}
+
+impl From<Trap> for Error {
+    fn from(_: Trap) -> Self { Error::InvalidArguments }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Return => write!(f, "Return result"),
+            Error::Panic(msg) => write!(f, "Panic: {}", msg),
+            Error::GasLimit => {
+                write!(f, "Invocation resulted in gas limit violated")
+            }
+            Error::MemoryAccessViolation => {
+                write!(f, "Memory access violation")
+            }
+            Error::InvalidArguments => {
+                write!(f, "Invalid host function arguments")
+            }
+            Error::UnknownFunction(idx) => {
+                write!(f, "Unknown host function {}", idx)
+            }
+            Error::Context => write!(f, "Context error"),
+        }
+    }
+}
+
+pub struct Runtime<'a> {
+    gas_counter: u64,
+    gas_limit: u64,
+    context: &'a mut dyn vm::Context,
+    memory: MemoryRef,
+    args: Vec<u8>,
+    result: Vec<u8>,
+    runtime_context: RuntimeContext,
+    vm_error: Option<vm::Error>,
+}
+
+impl<'a> Runtime<'a> {
+    /// `gas_limit` is measured in wasm gas units.
+    pub fn with_params(
+        context: &'a mut dyn vm::Context, memory: MemoryRef, gas_limit: u64,
+        args: Vec<u8>, runtime_context: RuntimeContext,
+    ) -> Runtime<'a>
+    {
+        Runtime {
+            gas_counter: 0,
+            gas_limit,
+            context,
+            memory,
+            args,
+            result: Vec::new(),
+            runtime_context,
+            vm_error: None,
+        }
+    }
+
+    /// The gas left in wasm gas units.
+    pub fn gas_left(&self) -> u64 { self.gas_limit - self.gas_counter }
+
+    /// The result set by `ret`.
+    pub fn into_result(self) -> Vec<u8> { self.result }
+
+    /// The error of the context which aborts the execution.
+    pub fn take_vm_error(&mut self) -> Option<vm::Error> {
+        self.vm_error.take()
+    }
+
+    /// Keep the error of the context to abort the execution.
+    fn context_error(&mut self, err: vm::Error) -> Error {
+        self.vm_error = Some(err);
+        Error::Context
+    }
+
+    /// Charge `amount` in wasm gas units.
+    pub fn charge_gas(&mut self, amount: u64) -> Result<(), Error> {
+        match self.gas_counter.checked_add(amount) {
+            Some(gas_counter) if gas_counter <= self.gas_limit => {
+                self.gas_counter = gas_counter;
+                Ok(())
+            }
+            _ => Err(Error::GasLimit),
+        }
+    }
+
+    /// Charge the gas of the EVM gas units computed by `f`.
+    fn adjusted_charge<F>(&mut self, f: F) -> Result<(), Error>
+    where F: FnOnce(&Spec) -> u64 {
+        let spec = self.context.spec();
+        let wasm_costs = spec.wasm();
+        let amount = U256::from(f(spec)) * U256::from(wasm_costs.opcodes_div)
+            / U256::from(wasm_costs.opcodes_mul);
+        if amount > U256::from(u64::max_value()) {
+            return Err(Error::GasLimit);
+        }
+        self.charge_gas(amount.low_u64())
+    }
+
+    fn charge_memcpy(&mut self, len: u64) -> Result<(), Error> {
+        let memcpy = self.context.spec().wasm().memcpy as u64;
+        self.charge_gas(len.checked_mul(memcpy).ok_or(Error::GasLimit)?)
+    }
+
+    fn read_h256(&self, ptr: u32) -> Result<H256, Error> {
+        let mut buf = [0u8; 32];
+        self.memory.get_into(ptr, &mut buf[..])?;
+        Ok(H256::from(buf))
+    }
+
+    fn read_address(&self, ptr: u32) -> Result<Address, Error> {
+        let mut buf = [0u8; 20];
+        self.memory.get_into(ptr, &mut buf[..])?;
+        Ok(Address::from(buf))
+    }
+
+    fn write_u256(&self, ptr: u32, value: &U256) -> Result<(), Error> {
+        let value: H256 = BigEndianHash::from_uint(value);
+        Ok(self.memory.set(ptr, value.as_bytes())?)
+    }
+
+    fn write_address(&self, ptr: u32, address: &Address) -> Result<(), Error> {
+        Ok(self.memory.set(ptr, address.as_bytes())?)
+    }
+
+    /// Read the 32 bytes storage value at `key_ptr` into `val_ptr`.
+    fn storage_read(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let key = self.read_h256(args.nth_checked(0)?)?;
+        let val_ptr: u32 = args.nth_checked(1)?;
+        self.adjusted_charge(|spec| spec.sload_gas as u64)?;
+        let value = self
+            .context
+            .storage_at(&key.as_bytes().to_vec())
+            .map_err(|err| self.context_error(err))?;
+        self.write_u256(val_ptr, &value)
+    }
+
+    /// Write the 32 bytes storage value at `val_ptr` to the key at
+    /// `key_ptr`.
+    fn storage_write(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let key = self.read_h256(args.nth_checked(0)?)?;
+        let value = self.read_h256(args.nth_checked(1)?)?.into_uint();
+        if self.context.is_static_or_reentrancy() {
+            return Err(
+                self.context_error(vm::Error::MutableCallInStaticContext)
+            );
+        }
+        self.adjusted_charge(|spec| spec.sstore_reset_gas as u64)?;
+        self.context
+            .set_storage(key.as_bytes().to_vec(), value)
+            .map_err(|err| self.context_error(err))
+    }
+
+    /// Set the result and stop the execution.
+    fn ret(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let len: u32 = args.nth_checked(1)?;
+        self.charge_memcpy(len as u64)?;
+        self.result = self.memory.get(ptr, len as usize)?;
+        Err(Error::Return)
+    }
+
+    fn panic(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let len: u32 = args.nth_checked(1)?;
+        let msg = self.memory.get(ptr, len as usize)?;
+        Err(Error::Panic(String::from_utf8_lossy(&msg).into_owned()))
+    }
+
+    fn fetch_input(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let ptr: u32 = args.nth_checked(0)?;
+        self.charge_memcpy(self.args.len() as u64)?;
+        Ok(self.memory.set(ptr, &self.args[..])?)
+    }
+
+    fn balance(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let address = self.read_address(args.nth_checked(0)?)?;
+        let return_ptr: u32 = args.nth_checked(1)?;
+        self.adjusted_charge(|spec| spec.balance_gas as u64)?;
+        let balance = self
+            .context
+            .balance(&address)
+            .map_err(|err| self.context_error(err))?;
+        self.write_u256(return_ptr, &balance)
+    }
+
+    /// Emit a log with `topic_count` topics at `topic_ptr` and the data of
+    /// `data_len` bytes at `data_ptr`.
+    fn elog(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+        let topic_ptr: u32 = args.nth_checked(0)?;
+        let topic_count: u32 = args.nth_checked(1)?;
+        let data_ptr: u32 = args.nth_checked(2)?;
+        let data_len: u32 = args.nth_checked(3)?;
+        if topic_count > 4 {
+            return Err(Error::InvalidArguments);
+        }
+        self.adjusted_charge(|spec| {
+            spec.log_gas as u64
+                + spec.log_topic_gas as u64 * topic_count as u64
+                + spec.log_data_gas as u64 * data_len as u64
+        })?;
+        let topics = (0..topic_count)
+            .map(|i| {
+                let ptr = topic_ptr
+                    .checked_add(i * 32)
+                    .ok_or(Error::MemoryAccessViolation)?;
+                self.read_h256(ptr)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = self.memory.get(data_ptr, data_len as usize)?;
+        self.context
+            .log(topics, &data)
+            .map_err(|err| self.context_error(err))
+    }
+
+    fn static_u256(
+        &mut self, args: RuntimeArgs, value: U256,
+    ) -> Result<(), Error> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let cost = self.context.spec().wasm().static_u256 as u64;
+        self.charge_gas(cost)?;
+        self.write_u256(ptr, &value)
+    }
+
+    fn static_address(
+        &mut self, args: RuntimeArgs, address: Address,
+    ) -> Result<(), Error> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let cost = self.context.spec().wasm().static_address as u64;
+        self.charge_gas(cost)?;
+        self.write_address(ptr, &address)
+    }
+}
+
+impl<'a> Externals for Runtime<'a> {
+    fn invoke_index(
+        &mut self, index: usize, args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = match index {
+            STORAGE_READ_FUNC => self.storage_read(args).map(|_| None),
+            STORAGE_WRITE_FUNC => self.storage_write(args).map(|_| None),
+            RET_FUNC => self.ret(args).map(|_| None),
+            GAS_FUNC => {
+                let amount: u32 = args.nth_checked(0)?;
+                self.charge_gas(amount as u64).map(|_| None)
+            }
+            INPUT_LENGTH_FUNC => {
+                Ok(Some(RuntimeValue::I32(self.args.len() as i32)))
+            }
+            FETCH_INPUT_FUNC => self.fetch_input(args).map(|_| None),
+            PANIC_FUNC => self.panic(args).map(|_| None),
+            ADDRESS_FUNC => {
+                let address = self.runtime_context.address;
+                self.static_address(args, address).map(|_| None)
+            }
+            SENDER_FUNC => {
+                let sender = self.runtime_context.sender;
+                self.static_address(args, sender).map(|_| None)
+            }
+            ORIGIN_FUNC => {
+                let origin = self.runtime_context.origin;
+                self.static_address(args, origin).map(|_| None)
+            }
+            VALUE_FUNC => {
+                let value = self.runtime_context.value;
+                self.static_u256(args, value).map(|_| None)
+            }
+            BALANCE_FUNC => self.balance(args).map(|_| None),
+            BLOCKNUMBER_FUNC => {
+                let number = self.context.env().number;
+                Ok(Some(RuntimeValue::I64(number as i64)))
+            }
+            TIMESTAMP_FUNC => {
+                let timestamp = self.context.env().timestamp;
+                Ok(Some(RuntimeValue::I64(timestamp as i64)))
+            }
+            ELOG_FUNC => self.elog(args).map(|_| None),
+            GASLEFT_FUNC => {
+                let wasm_costs = self.context.spec().wasm();
+                let gas_left = U256::from(self.gas_left())
+                    * U256::from(wasm_costs.opcodes_mul)
+                    / U256::from(wasm_costs.opcodes_div);
+                Ok(Some(RuntimeValue::I64(gas_left.low_u64() as i64)))
+            }
+            _ => Err(Error::UnknownFunction(index)),
+        };
+        result.map_err(Into::into)
+    }
+}
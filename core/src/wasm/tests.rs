@@ -0,0 +1,173 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{is_wasm_code, WasmInterpreter};
+use crate::{
+    vm::{
+        self,
+        tests::{test_finalize, MockContext},
+        ActionParams, CallType, Exec, GasLeft,
+    },
+    vm_factory::VmFactory,
+};
+use cfx_types::U256;
+use std::sync::Arc;
+
+fn params(wat: &str, call_type: CallType) -> ActionParams {
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(wat::parse_str(wat).unwrap()));
+    params.call_type = call_type;
+    params
+}
+
+fn exec(
+    params: ActionParams, context: &mut MockContext,
+) -> vm::Result<GasLeft> {
+    match Box::new(WasmInterpreter::new(params)).exec(context) {
+        Ok(result) => result,
+        Err(_) => panic!("Wasm execution never traps"),
+    }
+}
+
+const STORAGE: &str = r#"
+(module
+  (import "env" "memory" (memory 1 1))
+  (import "env" "storage_write" (func $storage_write (param i32 i32)))
+  (import "env" "storage_read" (func $storage_read (param i32 i32)))
+  (import "env" "ret" (func $ret (param i32 i32)))
+  (data (i32.const 0) "\01")
+  (func (export "call")
+    (i32.store8 (i32.const 63) (i32.const 42))
+    (call $storage_write (i32.const 0) (i32.const 32))
+    (call $storage_read (i32.const 0) (i32.const 64))
+    (call $ret (i32.const 64) (i32.const 32))))
+"#;
+
+#[test]
+fn test_storage() {
+    let mut context = MockContext::new().with_wasm();
+    let result = exec(params(STORAGE, CallType::Call), &mut context).unwrap();
+
+    let mut key = vec![0; 32];
+    key[0] = 1;
+    assert_eq!(context.store.get(&key), Some(&U256::from(42)));
+    match result {
+        GasLeft::NeedsReturn {
+            gas_left,
+            data,
+            apply_state,
+        } => {
+            assert!(gas_left < U256::from(100_000));
+            assert_eq!(U256::from_big_endian(&data), U256::from(42));
+            assert!(apply_state);
+        }
+        GasLeft::Known(_) => panic!("Expected the result to be returned"),
+    }
+}
+
+#[test]
+fn test_static_storage_write() {
+    let mut context = MockContext::new().with_wasm();
+    context.is_static = true;
+    assert_eq!(
+        exec(params(STORAGE, CallType::StaticCall), &mut context).err(),
+        Some(vm::Error::MutableCallInStaticContext)
+    );
+    assert!(context.store.is_empty());
+}
+
+#[test]
+fn test_deploy() {
+    let code = r#"
+(module
+  (import "env" "memory" (memory 1 1))
+  (import "env" "ret" (func $ret (param i32 i32)))
+  (data (i32.const 0) "code")
+  (func (export "deploy")
+    (call $ret (i32.const 0) (i32.const 4))))
+"#;
+    let mut context = MockContext::new().with_wasm();
+    match exec(params(code, CallType::None), &mut context).unwrap() {
+        GasLeft::NeedsReturn { data, .. } => assert_eq!(&*data, b"code"),
+        GasLeft::Known(_) => panic!("Expected the code to be returned"),
+    }
+}
+
+#[test]
+fn test_out_of_gas() {
+    let code = r#"
+(module
+  (func (export "call")
+    (loop (br 0))))
+"#;
+    let mut context = MockContext::new().with_wasm();
+    assert_eq!(
+        exec(params(code, CallType::Call), &mut context).err(),
+        Some(vm::Error::OutOfGas)
+    );
+}
+
+#[test]
+fn test_elog_topic_overflow() {
+    // The topics run past the end of the address space.
+    let code = r#"
+(module
+  (import "env" "memory" (memory 1 1))
+  (import "env" "elog" (func $elog (param i32 i32 i32 i32)))
+  (func (export "call")
+    (call $elog (i32.const -16) (i32.const 2) (i32.const 0) (i32.const 0))))
+"#;
+    let mut context = MockContext::new().with_wasm();
+    match exec(params(code, CallType::Call), &mut context) {
+        Err(vm::Error::Wasm(_)) => {}
+        _ => panic!("Expected the memory access to be rejected"),
+    }
+    assert!(context.logs.is_empty());
+}
+
+#[test]
+fn test_internal_memory() {
+    let code = r#"
+(module
+  (memory 1)
+  (func (export "call")))
+"#;
+    let mut context = MockContext::new().with_wasm();
+    match exec(params(code, CallType::Call), &mut context) {
+        Err(vm::Error::Wasm(_)) => {}
+        _ => panic!("Expected the internal memory to be rejected"),
+    }
+}
+
+#[test]
+fn test_factory() {
+    let code = r#"
+(module
+  (func (export "call")
+    (drop (i32.const 1))))
+"#;
+    let params = params(code, CallType::Call);
+    assert!(is_wasm_code(params.code.as_ref().unwrap()));
+
+    // The wasm code is only executed by the wasm engine if it's activated.
+    let mut context = MockContext::new().with_wasm();
+    let exec = VmFactory::default().create(params.clone(), &context.spec, 0);
+    match exec.exec(&mut context) {
+        Ok(result) => {
+            assert!(test_finalize(result).unwrap() < U256::from(100_000))
+        }
+        Err(_) => panic!("Wasm execution never traps"),
+    }
+
+    // Otherwise it starts with STOP in the EVM.
+    let mut context = MockContext::new();
+    let exec = VmFactory::default().create(params, &context.spec, 0);
+    match exec.exec(&mut context) {
+        Ok(result) => {
+            assert_eq!(test_finalize(result).unwrap(), U256::from(100_000))
+        }
+        Err(_) => panic!("EVM execution with STOP never traps"),
+    }
+}
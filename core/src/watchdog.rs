@@ -0,0 +1,447 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The watchdog of the worker loops of the node.
+//!
+//! Each loop marks the tasks it works on, e.g. a block in consensus or a
+//! message in sync, with the heartbeat it registers. A loop is stalled when one
+//! of its tasks makes no progress within the stall timeout, or when the loop
+//! beats on its iterations and does not iterate within the stall timeout. The
+//! watchdog then logs the state of all the loops, and exits the process with
+//! `WATCHDOG_EXIT_CODE` if configured, so that the supervisor of the node can
+//! restart it. A stalled loop is not restarted in the process, since its
+//! thread may hold locks shared with the other loops. The loops whose tasks
+//! may legitimately take long, e.g. the RPC calls of the clients, are
+//! registered with `logged_heartbeat` and never make the process exit.
+
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    process,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+/// The exit code of the process stopped by the watchdog.
+pub const WATCHDOG_EXIT_CODE: i32 = 3;
+
+/// The longest wait of an idle loop for work before it beats again.
+pub const IDLE_BEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The shortest stall timeout, well above `IDLE_BEAT_INTERVAL`, so that the
+/// idle loops are not reported.
+pub const MIN_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchdogAction {
+    /// Log the diagnostics of the stalled loops.
+    Log,
+    /// Log the diagnostics and exit with `WATCHDOG_EXIT_CODE`.
+    Exit,
+}
+
+impl FromStr for WatchdogAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let action = match s {
+            "log" => Self::Log,
+            "exit" => Self::Exit,
+            "restart" => {
+                return Err("restarting a stalled loop is not supported, \
+                            use \"exit\" with a supervisor instead"
+                    .into())
+            }
+            _ => return Err("invalid watchdog action".into()),
+        };
+        Ok(action)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// The time a task may run without progress before its loop is
+    /// considered stalled.
+    pub stall_timeout: Duration,
+    pub action: WatchdogAction,
+}
+
+struct Task {
+    thread: Thread,
+    stage: String,
+    started: Instant,
+    last_beat: Instant,
+    reported: bool,
+}
+
+struct LoopBeat {
+    last_beat: Instant,
+    reported: bool,
+}
+
+/// The heartbeat of a worker loop, which tracks the tasks running in it.
+pub struct Heartbeat {
+    name: &'static str,
+    /// Whether the stalls of the loop are only logged, whatever the action.
+    log_only: bool,
+    next_task_id: AtomicU64,
+    finished_tasks: AtomicU64,
+    tasks: Mutex<HashMap<u64, Task>>,
+    /// The last iteration of the loop, if it beats on its iterations.
+    loop_beat: Mutex<Option<LoopBeat>>,
+}
+
+impl Heartbeat {
+    fn new(name: &'static str, log_only: bool) -> Self {
+        Heartbeat {
+            name,
+            log_only,
+            next_task_id: AtomicU64::new(0),
+            finished_tasks: AtomicU64::new(0),
+            tasks: Default::default(),
+            loop_beat: Default::default(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str { self.name }
+
+    /// Marks an iteration of the loop. Once a loop beats, it is stalled when
+    /// it does not beat again within the stall timeout, even without a
+    /// running task, so it should not wait for work longer than
+    /// `IDLE_BEAT_INTERVAL`.
+    pub fn beat(&self) {
+        *self.loop_beat.lock() = Some(LoopBeat {
+            last_beat: Instant::now(),
+            reported: false,
+        });
+    }
+
+    /// Marks the start of a task on the current thread. The task is finished
+    /// when the returned guard is dropped.
+    pub fn task<S: Into<String>>(&self, stage: S) -> TaskGuard<'_> {
+        let id = self.next_task_id.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        self.tasks.lock().insert(
+            id,
+            Task {
+                thread: thread::current(),
+                stage: stage.into(),
+                started: now,
+                last_beat: now,
+                reported: false,
+            },
+        );
+        TaskGuard {
+            heartbeat: self,
+            id,
+        }
+    }
+
+    /// Returns the descriptions of the tasks without progress within
+    /// `timeout`, each of them only once until it makes progress again.
+    fn take_stalled(&self, now: Instant, timeout: Duration) -> Vec<String> {
+        let mut stalled = Vec::new();
+        if let Some(beat) = &mut *self.loop_beat.lock() {
+            let idle = now.saturating_duration_since(beat.last_beat);
+            if !beat.reported && idle >= timeout {
+                beat.reported = true;
+                stalled.push(format!(
+                    "{}: no iteration for {:?}",
+                    self.name, idle
+                ));
+            }
+        }
+        for task in self.tasks.lock().values_mut() {
+            let idle = now.saturating_duration_since(task.last_beat);
+            if !task.reported && idle >= timeout {
+                task.reported = true;
+                stalled.push(format!(
+                    "{}: \"{}\" on thread {} without progress for {:?}",
+                    self.name,
+                    task.stage,
+                    thread_name(&task.thread),
+                    idle
+                ));
+            }
+        }
+        stalled
+    }
+
+    fn write_diagnostics(&self, now: Instant, out: &mut String) {
+        let tasks = self.tasks.lock();
+        let _ = writeln!(
+            out,
+            "  {}: {} tasks finished, {} running",
+            self.name,
+            self.finished_tasks.load(Ordering::Relaxed),
+            tasks.len()
+        );
+        if let Some(beat) = &*self.loop_beat.lock() {
+            let _ = writeln!(
+                out,
+                "    last iteration {:?} ago",
+                now.saturating_duration_since(beat.last_beat)
+            );
+        }
+        let mut tasks: Vec<_> = tasks.values().collect();
+        tasks.sort_by_key(|task| task.started);
+        for task in tasks {
+            let _ = writeln!(
+                out,
+                "    at \"{}\" on thread {}, started {:?} ago, last progress \
+                 {:?} ago",
+                task.stage,
+                thread_name(&task.thread),
+                now.saturating_duration_since(task.started),
+                now.saturating_duration_since(task.last_beat)
+            );
+        }
+    }
+}
+
+fn thread_name(thread: &Thread) -> String {
+    format!(
+        "\"{}\" ({:?})",
+        thread.name().unwrap_or("<unnamed>"),
+        thread.id()
+    )
+}
+
+#[cfg(feature = "deadlock_detection")]
+fn write_deadlocks(out: &mut String) {
+    for (i, threads) in
+        parking_lot::deadlock::check_deadlock().iter().enumerate()
+    {
+        let _ = writeln!(out, "  deadlock #{}:", i);
+        for thread in threads {
+            let _ = writeln!(
+                out,
+                "    thread {:?}\n{:?}",
+                thread.thread_id(),
+                thread.backtrace()
+            );
+        }
+    }
+}
+
+/// A running task of a loop, which is finished when dropped.
+pub struct TaskGuard<'a> {
+    heartbeat: &'a Heartbeat,
+    id: u64,
+}
+
+impl TaskGuard<'_> {
+    /// Reports the progress of a long task, which moves on to `stage`.
+    pub fn beat<S: Into<String>>(&self, stage: S) {
+        if let Some(task) = self.heartbeat.tasks.lock().get_mut(&self.id) {
+            task.stage = stage.into();
+            task.last_beat = Instant::now();
+            task.reported = false;
+        }
+    }
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        self.heartbeat.tasks.lock().remove(&self.id);
+        self.heartbeat
+            .finished_tasks
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub struct Watchdog {
+    heartbeats: Mutex<BTreeMap<&'static str, Arc<Heartbeat>>>,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Watchdog {
+            heartbeats: Default::default(),
+        }
+    }
+
+    /// Returns the heartbeat of the loop `name`, which is registered on the
+    /// first call.
+    pub fn heartbeat(&self, name: &'static str) -> Arc<Heartbeat> {
+        self.register(name, false)
+    }
+
+    /// Returns the heartbeat of the loop `name` like `heartbeat`, but the
+    /// stalls of the loop are only logged and never make the process exit.
+    pub fn logged_heartbeat(&self, name: &'static str) -> Arc<Heartbeat> {
+        self.register(name, true)
+    }
+
+    fn register(&self, name: &'static str, log_only: bool) -> Arc<Heartbeat> {
+        self.heartbeats
+            .lock()
+            .entry(name)
+            .or_insert_with(|| Arc::new(Heartbeat::new(name, log_only)))
+            .clone()
+    }
+
+    /// Returns the descriptions of the tasks newly stalled for `timeout`.
+    pub fn check(&self, timeout: Duration) -> Vec<String> {
+        self.check_loops(timeout)
+            .into_iter()
+            .map(|(_, stalled)| stalled)
+            .collect()
+    }
+
+    /// Returns the tasks newly stalled for `timeout`, each with whether its
+    /// loop is only logged.
+    fn check_loops(&self, timeout: Duration) -> Vec<(bool, String)> {
+        let now = Instant::now();
+        self.heartbeats
+            .lock()
+            .values()
+            .flat_map(|heartbeat| {
+                heartbeat
+                    .take_stalled(now, timeout)
+                    .into_iter()
+                    .map(move |stalled| (heartbeat.log_only, stalled))
+            })
+            .collect()
+    }
+
+    /// Returns the state of all the loops and their running tasks.
+    pub fn diagnostics(&self) -> String {
+        let now = Instant::now();
+        let mut out = String::from("Watchdog diagnostics:\n");
+        for heartbeat in self.heartbeats.lock().values() {
+            heartbeat.write_diagnostics(now, &mut out);
+        }
+        #[cfg(feature = "deadlock_detection")]
+        write_deadlocks(&mut out);
+        out
+    }
+
+    /// Starts the thread checking the loops a few times per stall timeout,
+    /// which should be at least `MIN_STALL_TIMEOUT`.
+    pub fn start(&'static self, config: WatchdogConfig) {
+        let interval = config.stall_timeout / 4;
+        info!("Watchdog started with {:?}", config);
+        thread::Builder::new()
+            .name("Watchdog".into())
+            .spawn(move || loop {
+                thread::sleep(interval);
+                let stalled = self.check_loops(config.stall_timeout);
+                if stalled.is_empty() {
+                    continue;
+                }
+                for (_, task) in &stalled {
+                    error!("Worker loop stalled: {}", task);
+                }
+                error!("{}", self.diagnostics());
+                if config.action == WatchdogAction::Exit
+                    && stalled.iter().any(|(log_only, _)| !log_only)
+                {
+                    error!(
+                        "Exit with code {} due to the stalled worker loops",
+                        WATCHDOG_EXIT_CODE
+                    );
+                    process::exit(WATCHDOG_EXIT_CODE);
+                }
+            })
+            .expect("Cannot fail");
+    }
+}
+
+lazy_static! {
+    pub static ref WATCHDOG: Watchdog = Watchdog::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Watchdog, WatchdogAction};
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn test_watchdog_action() {
+        assert_eq!("log".parse(), Ok(WatchdogAction::Log));
+        assert_eq!("exit".parse(), Ok(WatchdogAction::Exit));
+        assert!("restart".parse::<WatchdogAction>().is_err());
+    }
+
+    #[test]
+    fn test_stalled_loop() {
+        let watchdog = Watchdog::new();
+        let heartbeat = watchdog.heartbeat("execution");
+
+        // A loop which never beats is only watched through its tasks.
+        assert!(watchdog.check(Duration::from_secs(0)).is_empty());
+
+        // An idle loop is stalled without beats, but only reported once.
+        heartbeat.beat();
+        assert!(watchdog.check(Duration::from_secs(60)).is_empty());
+        let stalled = watchdog.check(Duration::from_secs(0));
+        assert_eq!(stalled.len(), 1);
+        assert!(stalled[0].starts_with("execution: no iteration for"));
+        assert!(watchdog.check(Duration::from_secs(0)).is_empty());
+        assert!(watchdog.diagnostics().contains("last iteration"));
+
+        // The loop is reported again after its next iteration.
+        heartbeat.beat();
+        assert_eq!(watchdog.check(Duration::from_secs(0)).len(), 1);
+    }
+
+    #[test]
+    fn test_stalled_tasks() {
+        let watchdog = Watchdog::new();
+        let heartbeat = watchdog.heartbeat("consensus");
+        assert!(Arc::ptr_eq(&heartbeat, &watchdog.heartbeat("consensus")));
+        let timeout = Duration::from_secs(60);
+
+        let task = heartbeat.task("block 1");
+        assert!(watchdog.check(timeout).is_empty());
+
+        // Every running task is stalled without a timeout, but only reported
+        // once.
+        let stalled = watchdog.check(Duration::from_secs(0));
+        assert_eq!(stalled.len(), 1);
+        assert!(stalled[0].starts_with("consensus: \"block 1\""));
+        assert!(watchdog.check(Duration::from_secs(0)).is_empty());
+
+        // The task is reported again after its progress.
+        task.beat("block 1 executed");
+        let stalled = watchdog.check(Duration::from_secs(0));
+        assert!(stalled[0].starts_with("consensus: \"block 1 executed\""));
+
+        let diagnostics = watchdog.diagnostics();
+        assert!(diagnostics.contains("consensus: 0 tasks finished, 1 running"));
+        assert!(diagnostics.contains("at \"block 1 executed\""));
+
+        drop(task);
+        assert!(watchdog.check(Duration::from_secs(0)).is_empty());
+        assert!(watchdog
+            .diagnostics()
+            .contains("consensus: 1 tasks finished, 0 running"));
+    }
+
+    #[test]
+    fn test_logged_loop() {
+        let watchdog = Watchdog::new();
+        let rpc = watchdog.logged_heartbeat("rpc");
+        let consensus = watchdog.heartbeat("consensus");
+
+        // The stalls of a logged loop are reported, but only logged.
+        let _call = rpc.task("cfx_call");
+        let stalled = watchdog.check_loops(Duration::from_secs(0));
+        assert_eq!(stalled.len(), 1);
+        assert!(stalled[0].0);
+        assert!(stalled[0].1.starts_with("rpc: \"cfx_call\""));
+
+        let _block = consensus.task("block 1");
+        let stalled = watchdog.check_loops(Duration::from_secs(0));
+        assert_eq!(stalled.len(), 1);
+        assert!(!stalled[0].0);
+    }
+}
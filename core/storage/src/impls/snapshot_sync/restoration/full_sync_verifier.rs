@@ -15,6 +15,8 @@ pub struct FullSyncVerifier<SnapshotDbManager: SnapshotDbManagerTrait> {
     chunk_index_by_upper_key: HashMap<Vec<u8>, usize>,
 
     temp_snapshot_db: SnapshotDbManager::SnapshotDb,
+    /// Where the restoration progress is persisted after each chunk.
+    progress_path: Option<PathBuf>,
 }
 
 impl<SnapshotDbManager: SnapshotDbManagerTrait>
@@ -54,7 +56,11 @@ impl<SnapshotDbManager: SnapshotDbManagerTrait>
                 .insert(chunk_boundary.clone(), chunk_index);
         }
 
-        Ok(Self {
+        let temp_snapshot_db = snapshot_db_manager
+            .new_temp_snapshot_for_full_sync(epoch_id, &merkle_root)?;
+        let progress_path = snapshot_db_manager
+            .get_full_sync_progress_path(epoch_id, &merkle_root);
+        let mut verifier = Self {
             number_chunks,
             merkle_root,
             chunk_boundaries,
@@ -64,31 +70,56 @@ impl<SnapshotDbManager: SnapshotDbManagerTrait>
             pending_boundary_nodes: Default::default(),
             boundary_subtree_total_size: Default::default(),
             chunk_index_by_upper_key,
-            temp_snapshot_db: snapshot_db_manager
-                .new_temp_snapshot_for_full_sync(epoch_id, &merkle_root)?,
-        })
+            temp_snapshot_db,
+            progress_path,
+        };
+        verifier.load_progress()?;
+        if verifier.is_completed() {
+            // All chunks were restored before restart, but the restoration
+            // may not have been finalized.
+            verifier.finalize()?;
+        }
+        Ok(verifier)
     }
 
     pub fn is_completed(&self) -> bool { self.number_incomplete_chunk == 0 }
 
+    /// Returns true if the chunk has been restored, either in this run or
+    /// before a restart.
+    pub fn is_chunk_verified(&self, chunk_upper_key: &Option<Vec<u8>>) -> bool {
+        self.chunk_index(chunk_upper_key)
+            .map_or(false, |index| self.chunk_verified[index])
+    }
+
+    fn chunk_index(&self, chunk_upper_key: &Option<Vec<u8>>) -> Option<usize> {
+        match chunk_upper_key {
+            None => Some(self.number_chunks - 1),
+            Some(upper_key) => {
+                self.chunk_index_by_upper_key.get(upper_key).cloned()
+            }
+        }
+    }
+
     // FIXME: multi-threading, where &mut can be dropped.
     pub fn restore_chunk<Key: Borrow<[u8]> + Debug>(
         &mut self, chunk_upper_key: &Option<Vec<u8>>, keys: &Vec<Key>,
         values: Vec<Vec<u8>>,
     ) -> Result<bool>
     {
-        let chunk_index = match chunk_upper_key {
-            None => self.number_chunks - 1,
-            Some(upper_key) => {
-                match self.chunk_index_by_upper_key.get(upper_key) {
-                    Some(index) => *index,
-                    None => {
-                        warn!("chunk key {:?} does not match boundaries in manifest", upper_key);
-                        return Ok(false);
-                    }
-                }
+        let chunk_index = match self.chunk_index(chunk_upper_key) {
+            Some(index) => index,
+            None => {
+                warn!(
+                    "chunk key {:?} does not match boundaries in manifest",
+                    chunk_upper_key
+                );
+                return Ok(false);
             }
         };
+        if self.chunk_verified[chunk_index] {
+            debug!("chunk {:?} is already restored", chunk_upper_key);
+            return Ok(true);
+        }
         // Check key monotone.
         if !keys.is_empty() {
             let mut previous = keys.first().unwrap();
@@ -212,6 +243,7 @@ impl<SnapshotDbManager: SnapshotDbManagerTrait>
                     .entry(subtree_index)
                     .or_default() += subtree_size;
             }
+            self.save_progress()?;
         }
 
         if self.is_completed() {
@@ -254,6 +286,104 @@ impl<SnapshotDbManager: SnapshotDbManagerTrait>
         self.temp_snapshot_db.commit_transaction()?;
         Ok(())
     }
+
+    /// Persist the verification state which isn't in the temp snapshot db.
+    /// It's saved after the chunk is committed to the temp snapshot db, so
+    /// a chunk marked as verified is always fully written. A chunk written
+    /// but not yet marked is simply restored again after restart.
+    fn save_progress(&self) -> Result<()> {
+        let progress_path = match &self.progress_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let verified_chunks: Vec<u64> = self
+            .chunk_verified
+            .iter()
+            .enumerate()
+            .filter(|(_, verified)| **verified)
+            .map(|(index, _)| index as u64)
+            .collect();
+
+        let mut rlp = RlpStream::new_list(4);
+        rlp.begin_list(self.chunk_boundaries.len());
+        for boundary in &self.chunk_boundaries {
+            rlp.append(boundary);
+        }
+        rlp.append_list(&verified_chunks);
+        rlp.begin_list(self.pending_boundary_nodes.len());
+        for (path, node) in &self.pending_boundary_nodes {
+            rlp.begin_list(2).append(path).append(&node.0);
+        }
+        rlp.begin_list(self.boundary_subtree_total_size.len());
+        for (subtree_index, subtree_size) in &self.boundary_subtree_total_size
+        {
+            rlp.begin_list(3)
+                .append(&subtree_index.parent_node)
+                .append(&subtree_index.child_index)
+                .append(subtree_size);
+        }
+
+        // Write to a temp file and rename, so that the progress file is never
+        // partially written.
+        let temp_path = progress_path.with_extension("tmp");
+        fs::write(&temp_path, rlp.out())?;
+        fs::rename(&temp_path, progress_path)?;
+        Ok(())
+    }
+
+    /// Load the progress saved before restart. The progress is discarded if
+    /// it was saved for a manifest with different chunk boundaries.
+    fn load_progress(&mut self) -> Result<()> {
+        let progress_path = match &self.progress_path {
+            Some(path) if path.exists() => path,
+            _ => return Ok(()),
+        };
+        let bytes = fs::read(progress_path)?;
+        let rlp = Rlp::new(&bytes);
+
+        let chunk_boundaries: Vec<Vec<u8>> = rlp.list_at(0)?;
+        if chunk_boundaries != self.chunk_boundaries {
+            info!("Discard full sync progress saved for another manifest");
+            return Ok(());
+        }
+        let verified_chunks: Vec<u64> = rlp.list_at(1)?;
+        let mut pending_boundary_nodes = HashMap::new();
+        for item in rlp.at(2)?.iter() {
+            pending_boundary_nodes.insert(
+                item.val_at::<CompressedPathRaw>(0)?,
+                SnapshotMptNode(item.val_at(1)?),
+            );
+        }
+        let mut boundary_subtree_total_size = HashMap::new();
+        for item in rlp.at(3)?.iter() {
+            boundary_subtree_total_size.insert(
+                BoundarySubtreeIndex {
+                    parent_node: item.val_at(0)?,
+                    child_index: item.val_at(1)?,
+                },
+                item.val_at(2)?,
+            );
+        }
+
+        for index in verified_chunks {
+            let index = index as usize;
+            if index >= self.number_chunks {
+                bail!(DecoderError::RlpIncorrectListLen);
+            }
+            if !self.chunk_verified[index] {
+                self.chunk_verified[index] = true;
+                self.number_incomplete_chunk -= 1;
+            }
+        }
+        self.pending_boundary_nodes = pending_boundary_nodes;
+        self.boundary_subtree_total_size = boundary_subtree_total_size;
+        info!(
+            "Resume full sync with {}/{} chunks restored",
+            self.number_chunks - self.number_incomplete_chunk,
+            self.number_chunks
+        );
+        Ok(())
+    }
 }
 
 use crate::{
@@ -275,4 +405,7 @@ use crate::{
     TrieProof,
 };
 use primitives::{EpochId, MerkleHash};
-use std::{borrow::Borrow, collections::HashMap, fmt::Debug};
+use rlp::{DecoderError, Rlp, RlpStream};
+use std::{
+    borrow::Borrow, collections::HashMap, fmt::Debug, fs, path::PathBuf,
+};
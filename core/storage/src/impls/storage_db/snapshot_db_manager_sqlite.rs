@@ -23,6 +23,8 @@ pub type AlreadyOpenSnapshots<T> =
     Arc<RwLock<HashMap<PathBuf, Option<Weak<T>>>>>;
 
 impl SnapshotDbManagerSqlite {
+    const FULL_SYNC_PROGRESS_FILE_NAME: &'static str = "full_sync_progress";
+    const FULL_SYNC_TEMP_DIR_PREFIX: &'static str = "full_sync_temp_";
     const SNAPSHOT_DB_SQLITE_DIR_PREFIX: &'static str = "sqlite_";
 
     pub fn new(
@@ -213,7 +215,7 @@ impl SnapshotDbManagerSqlite {
     ) -> PathBuf {
         self.snapshot_path.join(
             Self::SNAPSHOT_DB_SQLITE_DIR_PREFIX.to_string()
-                + "full_sync_temp_"
+                + Self::FULL_SYNC_TEMP_DIR_PREFIX
                 + &snapshot_epoch_id.as_ref().to_hex()
                 + &merkle_root.as_ref().to_hex(),
         )
@@ -508,6 +510,22 @@ impl SnapshotDbManagerTrait for SnapshotDbManagerSqlite {
             snapshot_epoch_id,
             merkle_root,
         );
+        // A temp snapshot of the same snapshot is reused to resume the
+        // restoration. Temp snapshots of other snapshots are outdated.
+        for entry in fs::read_dir(&self.snapshot_path)? {
+            let path = entry?.path();
+            let is_outdated_temp_snapshot = path != temp_db_path
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| {
+                        self.is_full_sync_temp_snapshot_db_name(name)
+                    })
+                && !self.already_open_snapshots.read().contains_key(&path);
+            if is_outdated_temp_snapshot {
+                Self::fs_remove_snapshot(&path)?;
+            }
+        }
         self.open_snapshot_write(
             temp_db_path.to_path_buf(),
             /* create = */ true,
@@ -524,10 +542,37 @@ impl SnapshotDbManagerTrait for SnapshotDbManagerSqlite {
             merkle_root,
         );
         let final_db_path = self.get_snapshot_db_path(snapshot_epoch_id);
+        if let Some(progress_path) =
+            self.get_full_sync_progress_path(snapshot_epoch_id, merkle_root)
+        {
+            if progress_path.exists() {
+                fs::remove_file(progress_path)?;
+            }
+        }
         let locked = snapshot_info_map_rwlock.write();
         Self::rename_snapshot_db(&temp_db_path, &final_db_path)?;
         Ok(locked)
     }
+
+    fn is_full_sync_temp_snapshot_db_name(&self, dir_name: &str) -> bool {
+        dir_name
+            .strip_prefix(Self::SNAPSHOT_DB_SQLITE_DIR_PREFIX)
+            .map_or(false, |name| {
+                name.starts_with(Self::FULL_SYNC_TEMP_DIR_PREFIX)
+            })
+    }
+
+    fn get_full_sync_progress_path(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+    ) -> Option<PathBuf> {
+        Some(
+            self.get_full_sync_temp_snapshot_db_path(
+                snapshot_epoch_id,
+                merkle_root,
+            )
+            .join(Self::FULL_SYNC_PROGRESS_FILE_NAME),
+        )
+    }
 }
 
 use crate::{
@@ -38,6 +38,11 @@ pub trait SnapshotDbManagerTrait {
                 continue;
             }
             let dir_name = dir_name.unwrap();
+            if self.is_full_sync_temp_snapshot_db_name(dir_name) {
+                // Keep the snapshot of an unfinished full sync so that the
+                // restoration can be resumed.
+                continue;
+            }
             if !missing_snapshots.contains_key(dir_name.as_bytes()) {
                 error!(
                     "Unexpected snapshot path {}, deleted.",
@@ -75,6 +80,21 @@ pub trait SnapshotDbManagerTrait {
         &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
         snapshot_info_map_rwlock: &'m RwLock<HashMap<EpochId, SnapshotInfo>>,
     ) -> Result<RwLockWriteGuard<'m, HashMap<EpochId, SnapshotInfo>>>;
+
+    /// Returns true if the directory under the snapshot dir holds the temp
+    /// snapshot of an unfinished full sync.
+    fn is_full_sync_temp_snapshot_db_name(&self, _dir_name: &str) -> bool {
+        false
+    }
+
+    /// The file to persist the progress of a full sync restoration, so that
+    /// an interrupted restoration can be resumed after restart. None means
+    /// the progress is not persisted.
+    fn get_full_sync_progress_path(
+        &self, _snapshot_epoch_id: &EpochId, _merkle_root: &MerkleHash,
+    ) -> Option<PathBuf> {
+        None
+    }
 }
 
 use super::{
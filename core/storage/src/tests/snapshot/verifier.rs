@@ -348,6 +348,7 @@ impl SnapshotDbTrait for Arc<Mutex<FakeSnapshotDb>> {
 #[derive(Default)]
 struct FakeSnapshotDbManager {
     temp_snapshot: Arc<Mutex<FakeSnapshotDb>>,
+    progress_path: Option<PathBuf>,
 }
 
 impl SnapshotDbManagerTrait for FakeSnapshotDbManager {
@@ -404,6 +405,12 @@ impl SnapshotDbManagerTrait for FakeSnapshotDbManager {
     {
         unreachable!()
     }
+
+    fn get_full_sync_progress_path(
+        &self, _snapshot_epoch_id: &EpochId, _merkle_root: &MerkleHash,
+    ) -> Option<PathBuf> {
+        self.progress_path.clone()
+    }
 }
 
 #[test]
@@ -462,6 +469,136 @@ fn test_full_sync_verifier_one_chunk() {
     temp_snapshot.mpt_db.lock().assert_eq(&snapshot_mpt);
 }
 
+/// An MPT of sorted `keys` sliced into about 5 chunks, with the proofs of the
+/// chunk boundaries, to restore with a `FullSyncVerifier`.
+struct SlicedMpt {
+    mpt_kv_iter: DumpedMptKvIterator,
+    snapshot_mpt: FakeSnapshotMptDb,
+    merkle_root: MerkleHash,
+    /// The end of each chunk in `mpt_kv_iter.kv`.
+    right_bounds: Vec<usize>,
+    chunk_bounds: Vec<Vec<u8>>,
+    chunk_proofs: Vec<TrieProof>,
+}
+
+impl SlicedMpt {
+    fn new(keys: &[Vec<u8>], rng: &mut ChaChaRng) -> Self {
+        let mpt_kv_iter = DumpedMptKvIterator {
+            kv: keys
+                .iter()
+                .map(|k| {
+                    (
+                        k[..].into(),
+                        [&k[..], &k[..], &k[..], &k[..]].concat()
+                            [0..(6 + rng.gen::<usize>() % 10)]
+                            .into(),
+                    )
+                })
+                .collect(),
+        };
+
+        let mut snapshot_mpt = FakeSnapshotMptDb::default();
+        let merkle_root = MptMerger::new(None, &mut snapshot_mpt)
+            .merge(&mpt_kv_iter)
+            .unwrap();
+
+        let mut size_sum = Vec::with_capacity(keys.len());
+        let mut total_rlp_size = 0;
+        for (key, value) in &mpt_kv_iter.kv {
+            total_rlp_size += rlp_key_value_len(key.len() as u16, value.len());
+            size_sum.push(total_rlp_size);
+        }
+        let chunk_size = size_sum.last().unwrap() / 5 as u64;
+
+        // Slice by scanning to get chunk contents.
+        let mut right_bound = 0;
+        let mut start_size = 0;
+        let mut right_bounds = vec![];
+        while right_bound < keys.len() {
+            if size_sum[right_bound] > chunk_size + start_size {
+                right_bounds.push(right_bound);
+                start_size = size_sum[right_bound - 1];
+            }
+            right_bound += 1;
+        }
+        right_bounds.push(right_bound);
+
+        // Slice by MptSlicer to get proofs.
+        let mut slicer = MptSlicer::new(&mut snapshot_mpt).unwrap();
+        let mut chunk_bounds = vec![];
+        let mut chunk_proofs = vec![];
+        loop {
+            slicer.advance(chunk_size).unwrap();
+            match slicer.get_range_end_key() {
+                Some(key) => {
+                    chunk_bounds.push(Vec::from(key));
+                    chunk_proofs.push(slicer.to_proof());
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+        drop(slicer);
+
+        SlicedMpt {
+            mpt_kv_iter,
+            snapshot_mpt,
+            merkle_root,
+            right_bounds,
+            chunk_bounds,
+            chunk_proofs,
+        }
+    }
+
+    fn number_chunks(&self) -> usize { self.right_bounds.len() }
+
+    fn new_verifier(
+        &self, snapshot_db_manager: &FakeSnapshotDbManager,
+    ) -> FullSyncVerifier<FakeSnapshotDbManager> {
+        FullSyncVerifier::new(
+            self.number_chunks(),
+            self.chunk_bounds.clone(),
+            self.chunk_proofs.clone(),
+            self.merkle_root,
+            snapshot_db_manager,
+            &NULL_EPOCH,
+        )
+        .unwrap()
+    }
+
+    fn upper_key(&self, i: usize) -> Option<Vec<u8>> {
+        self.chunk_bounds.get(i).cloned()
+    }
+
+    fn restore_chunk(
+        &self, verifier: &mut FullSyncVerifier<FakeSnapshotDbManager>, i: usize,
+    ) -> bool {
+        let chunk_start = if i == 0 { 0 } else { self.right_bounds[i - 1] };
+        let kv = &self.mpt_kv_iter.kv[chunk_start..self.right_bounds[i]];
+        verifier
+            .restore_chunk(
+                &self.upper_key(i),
+                &kv.iter().map(|kv| kv.0.clone()).collect(),
+                kv.iter().map(|kv| kv.1.to_vec()).collect(),
+            )
+            .unwrap()
+    }
+
+    /// Check that the snapshot restored by `snapshot_db_manager` is the MPT.
+    fn assert_restored(&self, snapshot_db_manager: &FakeSnapshotDbManager) {
+        // Check key-values.
+        let temp_snapshot = &*snapshot_db_manager.temp_snapshot.lock();
+        assert_eq!(temp_snapshot.kv.len(), self.mpt_kv_iter.kv.len());
+        for (key, value) in &self.mpt_kv_iter.kv {
+            assert_eq!(temp_snapshot.kv.get(key), Some(value));
+        }
+
+        // Check MPT key-values and subtree size.
+        temp_snapshot.mpt_db.lock().assert_eq(&self.snapshot_mpt);
+    }
+}
+
 #[test]
 fn test_full_sync_verifier() {
     // Slice big mpt.
@@ -472,109 +609,60 @@ fn test_full_sync_verifier() {
         .cloned()
         .collect();
     keys.sort();
-    let mpt_kv_iter = DumpedMptKvIterator {
-        kv: keys
-            .iter()
-            .map(|k| {
-                (
-                    k[..].into(),
-                    [&k[..], &k[..], &k[..], &k[..]].concat()
-                        [0..(6 + rng.gen::<usize>() % 10)]
-                        .into(),
-                )
-            })
-            .collect(),
-    };
-
-    let mut snapshot_mpt = FakeSnapshotMptDb::default();
-    let merkle_root = MptMerger::new(None, &mut snapshot_mpt)
-        .merge(&mpt_kv_iter)
-        .unwrap();
+    let sliced_mpt = SlicedMpt::new(&keys, &mut rng);
 
-    let mut size_sum = Vec::with_capacity(keys.len());
-    let mut total_rlp_size = 0;
-    for (key, value) in &mpt_kv_iter.kv {
-        total_rlp_size += rlp_key_value_len(key.len() as u16, value.len());
-        size_sum.push(total_rlp_size);
-    }
-    let chunk_size = size_sum.last().unwrap() / 5 as u64;
-
-    // Slice by scanning to get chunk contents.
-    let mut right_bound = 0;
-    let mut start_size = 0;
-    let mut right_bounds = vec![];
-    while right_bound < keys.len() {
-        if size_sum[right_bound] > chunk_size + start_size {
-            right_bounds.push(right_bound);
-            start_size = size_sum[right_bound - 1];
-        }
-        right_bound += 1;
+    let snapshot_db_manager = FakeSnapshotDbManager::default();
+    let mut full_sync_verifier = sliced_mpt.new_verifier(&snapshot_db_manager);
+    for i in 0..sliced_mpt.number_chunks() {
+        assert!(sliced_mpt.restore_chunk(&mut full_sync_verifier, i));
     }
-    right_bounds.push(right_bound);
 
-    // Slice by MptSlicer to get proofs.
-    let mut slicer = MptSlicer::new(&mut snapshot_mpt).unwrap();
-    let mut slicer_chunk_bounds = vec![];
-    let mut slicer_chunk_proofs = vec![];
-    loop {
-        slicer.advance(chunk_size).unwrap();
-        match slicer.get_range_end_key() {
-            Some(key) => {
-                slicer_chunk_bounds.push(Vec::from(key));
-                slicer_chunk_proofs.push(slicer.to_proof());
-            }
-            None => {
-                break;
-            }
-        }
-    }
-    drop(slicer);
+    sliced_mpt.assert_restored(&snapshot_db_manager);
+}
 
-    let snapshot_db_manager = FakeSnapshotDbManager::default();
+#[test]
+fn test_full_sync_verifier_resume() {
+    let mut rng = get_rng_for_test();
+    let mut keys: Vec<Vec<u8>> = generate_keys(TEST_NUMBER_OF_KEYS);
+    keys.sort();
+    let sliced_mpt = SlicedMpt::new(&keys, &mut rng);
+    let number_chunks = sliced_mpt.number_chunks();
+
+    let progress_path =
+        std::env::temp_dir().join("conflux_unit_test_full_sync_progress");
+    fs::remove_file(&progress_path).ok();
+    let snapshot_db_manager = FakeSnapshotDbManager {
+        progress_path: Some(progress_path.clone()),
+        ..Default::default()
+    };
 
-    let mut full_sync_verifier = FullSyncVerifier::new(
-        right_bounds.len(),
-        slicer_chunk_bounds.clone(),
-        slicer_chunk_proofs,
-        merkle_root,
-        &snapshot_db_manager,
-        &NULL_EPOCH,
-    )
-    .unwrap();
+    // Restore half of the chunks before the "restart".
+    let restored_before_restart = number_chunks / 2;
+    let mut full_sync_verifier = sliced_mpt.new_verifier(&snapshot_db_manager);
+    for i in 0..restored_before_restart {
+        assert!(sliced_mpt.restore_chunk(&mut full_sync_verifier, i));
+    }
+    drop(full_sync_verifier);
 
-    let mut chunk_start_offset = 0;
-    for i in 0..right_bounds.len() {
-        let upper_key = if i < right_bounds.len() - 1 {
-            Some(slicer_chunk_bounds[i].clone())
-        } else {
-            None
-        };
-        let chunk_restored = full_sync_verifier
-            .restore_chunk(
-                &upper_key,
-                &mpt_kv_iter.kv[chunk_start_offset..right_bounds[i]]
-                    .iter()
-                    .map(|kv| kv.0.clone())
-                    .collect(),
-                mpt_kv_iter.kv[chunk_start_offset..right_bounds[i]]
-                    .iter()
-                    .map(|kv| kv.1.to_vec())
-                    .collect(),
-            )
-            .unwrap();
-        chunk_start_offset = right_bounds[i];
-        assert!(chunk_restored);
+    // The verifier created after restart loads the progress.
+    let mut full_sync_verifier = sliced_mpt.new_verifier(&snapshot_db_manager);
+    for i in 0..number_chunks {
+        assert_eq!(
+            full_sync_verifier.is_chunk_verified(&sliced_mpt.upper_key(i)),
+            i < restored_before_restart
+        );
     }
+    assert!(!full_sync_verifier.is_completed());
 
-    // Check key-values.
-    let temp_snapshot = &*snapshot_db_manager.temp_snapshot.lock();
-    assert_eq!(temp_snapshot.kv.len(), mpt_kv_iter.kv.len());
-    for (key, value) in &mpt_kv_iter.kv {
-        assert_eq!(temp_snapshot.kv.get(key), Some(value));
+    // A restored chunk received again is ignored.
+    assert!(sliced_mpt.restore_chunk(&mut full_sync_verifier, 0));
+    for i in restored_before_restart..number_chunks {
+        assert!(sliced_mpt.restore_chunk(&mut full_sync_verifier, i));
     }
+    assert!(full_sync_verifier.is_completed());
+    fs::remove_file(&progress_path).ok();
 
-    // Check MPT key-values and subtree size.
-    temp_snapshot.mpt_db.lock().assert_eq(&snapshot_mpt);
+    sliced_mpt.assert_restored(&snapshot_db_manager);
 }
 
 use crate::{
@@ -600,14 +688,16 @@ use crate::{
         generate_keys, get_rng_for_test, snapshot::FakeSnapshotMptDb,
         DumpedMptKvIterator, TEST_NUMBER_OF_KEYS,
     },
-    DeltaMptIterator, MptSlicer,
+    DeltaMptIterator, MptSlicer, TrieProof,
 };
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use primitives::{EpochId, MerkleHash, MERKLE_NULL_NODE, NULL_EPOCH};
 use rand::Rng;
+use rand_chacha::ChaChaRng;
 use std::{
     cmp::{max, min},
     collections::HashMap,
+    fs,
     path::{Path, PathBuf},
     sync::Arc,
 };
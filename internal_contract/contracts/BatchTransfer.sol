@@ -0,0 +1,8 @@
+pragma solidity >=0.4.15;
+
+contract BatchTransfer {
+    event Transfer(address indexed from, address indexed to, uint value);
+
+    function batchTransfer(address[] calldata recipients, uint[] calldata values) external payable {
+    }
+}
\ No newline at end of file
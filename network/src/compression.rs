@@ -0,0 +1,124 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Optional compression of protocol packets.
+//!
+//! Peers exchange the supported compression algorithms in Hello, and the
+//! first algorithm in our preference order that is also supported by the
+//! remote peer is used for the session. Only the protocol messages marked as
+//! compressible and not smaller than `COMPRESSION_MIN_SIZE` are compressed.
+
+/// The minimum size of a packet to compress. Smaller packets are sent as is,
+/// because the CPU time outweighs the bandwidth saved.
+pub const COMPRESSION_MIN_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Lz4,
+    Snappy,
+}
+
+/// Supported compression algorithms in preference order.
+pub const SUPPORTED_COMPRESSIONS: [Compression; 2] =
+    [Compression::Lz4, Compression::Snappy];
+
+impl Compression {
+    /// The identifier of the algorithm on the wire.
+    pub fn id(&self) -> u8 {
+        match self {
+            Compression::Lz4 => 1,
+            Compression::Snappy => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Compression> {
+        SUPPORTED_COMPRESSIONS.iter().find(|c| c.id() == id).cloned()
+    }
+
+    /// Choose the algorithm to use with the peer that supports the algorithms
+    /// of `peer_ids`.
+    pub fn negotiate(peer_ids: &[u8]) -> Option<Compression> {
+        SUPPORTED_COMPRESSIONS
+            .iter()
+            .find(|c| peer_ids.contains(&c.id()))
+            .cloned()
+    }
+
+    /// Compress `data`. Return `None` if failed or the compressed data is
+    /// not smaller, in which case the data should be sent as is.
+    pub fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let compressed = match self {
+            Compression::Lz4 => lz4_flex::compress_prepend_size(data),
+            Compression::Snappy => {
+                snap::raw::Encoder::new().compress_vec(data).ok()?
+            }
+        };
+
+        if compressed.len() < data.len() {
+            Some(compressed)
+        } else {
+            None
+        }
+    }
+
+    /// Decompress `data`. Return `None` if the data is invalid or the
+    /// decompressed size exceeds `max_size`.
+    pub fn decompress(&self, data: &[u8], max_size: usize) -> Option<Vec<u8>> {
+        match self {
+            Compression::Lz4 => {
+                // The decompressed size is prepended as u32 in little endian.
+                if data.len() < 4 {
+                    return None;
+                }
+                let mut size = [0u8; 4];
+                size.copy_from_slice(&data[..4]);
+                if u32::from_le_bytes(size) as usize > max_size {
+                    return None;
+                }
+                lz4_flex::decompress_size_prepended(data).ok()
+            }
+            Compression::Snappy => {
+                if snap::raw::decompress_len(data).ok()? > max_size {
+                    return None;
+                }
+                snap::raw::Decoder::new().decompress_vec(data).ok()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compression, SUPPORTED_COMPRESSIONS};
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Compression::negotiate(&[]), None);
+        assert_eq!(Compression::negotiate(&[2]), Some(Compression::Snappy));
+        assert_eq!(Compression::negotiate(&[2, 1]), Some(Compression::Lz4));
+        assert_eq!(Compression::negotiate(&[9]), None);
+    }
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let data = vec![7u8; 4096];
+        for compression in &SUPPORTED_COMPRESSIONS {
+            let compressed = compression.compress(&data).unwrap();
+            assert!(compressed.len() < data.len());
+            assert_eq!(
+                compression.decompress(&compressed, data.len()).unwrap(),
+                data
+            );
+            // Oversized after decompression.
+            assert!(compression
+                .decompress(&compressed, data.len() - 1)
+                .is_none());
+            // Invalid data.
+            assert!(compression.decompress(&[1, 2, 3], data.len()).is_none());
+        }
+
+        // Incompressible data.
+        assert!(Compression::Snappy.compress(&[1, 2, 3]).is_none());
+    }
+}
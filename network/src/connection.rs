@@ -83,7 +83,7 @@ pub enum WriteStatus {
     Complete,
 }
 
-const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
+pub const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
 
 pub trait GenericSocket: Read + Write {}
 
@@ -329,6 +329,48 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
         Ok(status)
     }
 
+    /// Send the specified data out immediately, or, if a packet is being sent,
+    /// queue it after the high priority packets, e.g. the Hello, so that it
+    /// neither overtakes nor interleaves with them.
+    pub fn write_after_queued(
+        &mut self, data: Vec<u8>,
+    ) -> Result<usize, Error> {
+        if !self.is_sending() {
+            return self.write_raw_data(data);
+        }
+        // The connection is registered as writable already.
+        self.enqueue(data, SendQueuePriority::High)?;
+        Ok(0)
+    }
+
+    fn enqueue(
+        &mut self, data: Vec<u8>, priority: SendQueuePriority,
+    ) -> Result<(), Error> {
+        let size = data.len();
+        if self.assembler.is_oversized(size) {
+            return Err(ErrorKind::OversizedPacket.into());
+        }
+
+        trace!("Sending packet, token = {}, size = {}", self.token, size);
+
+        let packet = Packet::new(data, priority)?;
+        self.send_queue.push_back(packet, priority);
+
+        SEND_METER.mark(size);
+        match priority {
+            SendQueuePriority::High => {
+                SEND_HIGH_PRIORITY_METER.mark(size);
+            }
+            SendQueuePriority::Normal => {
+                SEND_NORMAL_PRIORITY_METER.mark(size);
+            }
+            SendQueuePriority::Low => {
+                SEND_LOW_PRIORITY_METER.mark(size);
+            }
+        }
+        Ok(())
+    }
+
     /// Add a packet to send queue.
     pub fn send<Message: Sync + Send + Clone + 'static>(
         &mut self, io: &IoContext<Message>, data: Vec<u8>,
@@ -336,28 +378,7 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     ) -> Result<SendQueueStatus, Error>
     {
         if !data.is_empty() {
-            let size = data.len();
-            if self.assembler.is_oversized(size) {
-                return Err(ErrorKind::OversizedPacket.into());
-            }
-
-            trace!("Sending packet, token = {}, size = {}", self.token, size);
-
-            let packet = Packet::new(data, priority)?;
-            self.send_queue.push_back(packet, priority);
-
-            SEND_METER.mark(size);
-            match priority {
-                SendQueuePriority::High => {
-                    SEND_HIGH_PRIORITY_METER.mark(size);
-                }
-                SendQueuePriority::Normal => {
-                    SEND_NORMAL_PRIORITY_METER.mark(size);
-                }
-                SendQueuePriority::Low => {
-                    SEND_LOW_PRIORITY_METER.mark(size);
-                }
-            }
+            self.enqueue(data, priority)?;
 
             if !self.interest.is_writable() {
                 self.interest.insert(Ready::writable());
@@ -710,6 +731,28 @@ mod tests {
         assert_eq!(sending_packet.sending_pos, 10);
     }
 
+    #[test]
+    fn connection_write_after_queued() {
+        let mut connection = TestConnection::new();
+        let mut expected = Vec::new();
+        for data in &[vec![1], vec![2, 2], vec![3]] {
+            let mut data = data.clone();
+            connection.assembler.assemble(&mut data).unwrap();
+            expected.extend(data);
+        }
+
+        assert_eq!(connection.write_after_queued(vec![1]).unwrap(), 2);
+        connection
+            .send(&test_io(), vec![2, 2], SendQueuePriority::High)
+            .unwrap();
+        // Queued after the packet being sent.
+        assert_eq!(connection.write_after_queued(vec![3]).unwrap(), 0);
+        while connection.is_sending() {
+            connection.writable(&test_io()).unwrap();
+        }
+        assert_eq!(connection.socket.write_buf, expected);
+    }
+
     #[test]
     fn connection_read() {
         let mut connection = TestConnection::new();
@@ -0,0 +1,259 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Authenticated encryption of session packets.
+//!
+//! After the handshake, peers exchange ephemeral public keys in Hello. An
+//! ephemeral key is signed by the node key along with the nonce that the
+//! remote peer chose in the handshake, so that it can neither be replaced by
+//! a man in the middle nor replayed in another session. The session keys are
+//! derived from the Diffie-Hellman agreement of the ephemeral keys and both
+//! handshake nonces. Ephemeral secrets are dropped once the keys are derived,
+//! so a leaked node key does not reveal the packets of past sessions.
+//!
+//! Packets are sealed with ChaCha20-Poly1305. Packets of different priorities
+//! may be reordered in the send queue, so every priority is sent on its own
+//! channel with an independent nonce counter, and the channel is appended to
+//! the sealed packet.
+
+use crate::{node_table::NodeId, Error, ErrorKind};
+use cfx_types::{Public, H256};
+use chacha20poly1305::{
+    aead::{generic_array::GenericArray, AeadInPlace, NewAead},
+    ChaCha20Poly1305,
+};
+use keccak_hash::keccak;
+use keylib::{crypto::ecdh, sign, verify_public, KeyPair, Secret, Signature};
+use priority_send_queue::SendQueuePriority;
+
+/// Channel of packets that are written to socket immediately, bypassing the
+/// send queue.
+pub const CHANNEL_IMMEDIATE: u8 = 3;
+const CHANNEL_COUNT: usize = 4;
+
+/// Return the channel of packets sent with `priority`.
+pub fn priority_channel(priority: SendQueuePriority) -> u8 { priority as u8 }
+
+fn ephemeral_hash(ephemeral: &Public, nonce: &H256) -> H256 {
+    let mut data = Vec::with_capacity(Public::len_bytes() + H256::len_bytes());
+    data.extend_from_slice(ephemeral.as_bytes());
+    data.extend_from_slice(nonce.as_bytes());
+    keccak(&data)
+}
+
+/// Sign the `ephemeral` key for the remote peer that chose `remote_nonce` in
+/// handshake.
+pub fn sign_ephemeral(
+    secret: &Secret, ephemeral: &Public, remote_nonce: &H256,
+) -> Result<Signature, Error> {
+    Ok(sign(secret, &ephemeral_hash(ephemeral, remote_nonce))?)
+}
+
+/// Check that the `ephemeral` key is signed by the remote peer `node_id` for
+/// this session, in which we chose `local_nonce` in handshake.
+pub fn verify_ephemeral(
+    node_id: &NodeId, ephemeral: &Public, local_nonce: &H256,
+    signature: &Signature,
+) -> bool
+{
+    verify_public(node_id, signature, &ephemeral_hash(ephemeral, local_nonce))
+        .unwrap_or(false)
+}
+
+fn derive_key(
+    shared: &Secret, initiator_nonce: &H256, responder_nonce: &H256,
+    label: &[u8],
+) -> H256
+{
+    let mut data = Vec::with_capacity(3 * H256::len_bytes() + label.len());
+    data.extend_from_slice(shared.as_bytes());
+    data.extend_from_slice(initiator_nonce.as_bytes());
+    data.extend_from_slice(responder_nonce.as_bytes());
+    data.extend_from_slice(label);
+    keccak(&data)
+}
+
+fn channel_nonce(channel: u8, counter: u64) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[0] = channel;
+    nonce[4..].copy_from_slice(&counter.to_le_bytes());
+    nonce
+}
+
+/// Keys and nonce counters to seal and open the packets of a session.
+pub struct SessionCipher {
+    sealer: ChaCha20Poly1305,
+    opener: ChaCha20Poly1305,
+    send_counters: [u64; CHANNEL_COUNT],
+    recv_counters: [u64; CHANNEL_COUNT],
+}
+
+impl SessionCipher {
+    /// Derive the session keys from the local `ephemeral` key pair and the
+    /// remote ephemeral key. `originated` tells if we are the initiator of
+    /// the handshake.
+    pub fn new(
+        ephemeral: &KeyPair, remote_ephemeral: &Public, initiator_nonce: &H256,
+        responder_nonce: &H256, originated: bool,
+    ) -> Result<Self, Error>
+    {
+        let shared = ecdh::agree(ephemeral.secret(), remote_ephemeral)?;
+        let initiator_key =
+            derive_key(&shared, initiator_nonce, responder_nonce, b"initiator");
+        let responder_key =
+            derive_key(&shared, initiator_nonce, responder_nonce, b"responder");
+        let (send_key, recv_key) = if originated {
+            (initiator_key, responder_key)
+        } else {
+            (responder_key, initiator_key)
+        };
+
+        Ok(SessionCipher {
+            sealer: ChaCha20Poly1305::new(GenericArray::from_slice(
+                send_key.as_bytes(),
+            )),
+            opener: ChaCha20Poly1305::new(GenericArray::from_slice(
+                recv_key.as_bytes(),
+            )),
+            send_counters: [0; CHANNEL_COUNT],
+            recv_counters: [0; CHANNEL_COUNT],
+        })
+    }
+
+    /// Encrypt `data` in place and append the authentication tag and
+    /// `channel`. The nonce of `channel` is advanced by `commit` once the
+    /// packet is accepted to send, so that a packet rejected afterwards, e.g.
+    /// as oversized, does not leave a gap the peer could not open past.
+    pub fn seal(&self, channel: u8, data: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce =
+            channel_nonce(channel, self.send_counters[channel as usize]);
+        self.sealer
+            .encrypt_in_place(GenericArray::from_slice(&nonce), &[], data)
+            .map_err(|_| Error::from(ErrorKind::Auth))?;
+        data.push(channel);
+        Ok(())
+    }
+
+    /// Advance the nonce of `channel` after the packet sealed last on it is
+    /// accepted to send.
+    pub fn commit(&mut self, channel: u8) {
+        self.send_counters[channel as usize] += 1;
+    }
+
+    /// Decrypt the sealed `data` in place. Packets of the same channel must be
+    /// opened in the order they are sealed, so replayed or reordered packets
+    /// are rejected.
+    pub fn open(&mut self, data: &mut Vec<u8>) -> Result<(), Error> {
+        let channel = match data.pop() {
+            Some(channel) if (channel as usize) < CHANNEL_COUNT => channel,
+            _ => bail!(ErrorKind::BadProtocol),
+        };
+        let counter = &mut self.recv_counters[channel as usize];
+        let nonce = channel_nonce(channel, *counter);
+        self.opener
+            .decrypt_in_place(GenericArray::from_slice(&nonce), &[], data)
+            .map_err(|_| Error::from(ErrorKind::Auth))?;
+        *counter += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keylib::{Generator, Random};
+
+    fn cipher_pair() -> (SessionCipher, SessionCipher) {
+        let initiator = Random.generate().unwrap();
+        let responder = Random.generate().unwrap();
+        let initiator_nonce = H256::random();
+        let responder_nonce = H256::random();
+        (
+            SessionCipher::new(
+                &initiator,
+                responder.public(),
+                &initiator_nonce,
+                &responder_nonce,
+                true,
+            )
+            .unwrap(),
+            SessionCipher::new(
+                &responder,
+                initiator.public(),
+                &initiator_nonce,
+                &responder_nonce,
+                false,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_ephemeral_signature() {
+        let node = Random.generate().unwrap();
+        let ephemeral = Random.generate().unwrap();
+        let nonce = H256::random();
+        let signature =
+            sign_ephemeral(node.secret(), ephemeral.public(), &nonce).unwrap();
+        assert!(verify_ephemeral(
+            node.public(),
+            ephemeral.public(),
+            &nonce,
+            &signature
+        ));
+        // Replayed in another session.
+        assert!(!verify_ephemeral(
+            node.public(),
+            ephemeral.public(),
+            &H256::random(),
+            &signature
+        ));
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let (mut initiator, mut responder) = cipher_pair();
+
+        let mut high = b"high".to_vec();
+        initiator.seal(0, &mut high).unwrap();
+        initiator.commit(0);
+        let mut low = b"low".to_vec();
+        initiator.seal(2, &mut low).unwrap();
+        initiator.commit(2);
+        let mut replayed = low.clone();
+
+        // Channels are independent of each other.
+        responder.open(&mut low).unwrap();
+        assert_eq!(low, b"low".to_vec());
+        responder.open(&mut high).unwrap();
+        assert_eq!(high, b"high".to_vec());
+        assert!(responder.open(&mut replayed).is_err());
+
+        // Each direction has its own key.
+        let mut data = b"data".to_vec();
+        responder.seal(0, &mut data).unwrap();
+        responder.commit(0);
+        let mut tampered = data.clone();
+        tampered[0] ^= 1;
+        assert!(initiator.open(&mut tampered).is_err());
+        initiator.open(&mut data).unwrap();
+        assert_eq!(data, b"data".to_vec());
+    }
+    #[test]
+    fn test_uncommitted_seal() {
+        let (mut initiator, mut responder) = cipher_pair();
+
+        // Rejected by the connection, so never sent.
+        let mut rejected = b"rejected".to_vec();
+        initiator.seal(1, &mut rejected).unwrap();
+
+        for data in &[b"first", b"again"] {
+            let mut sealed = data.to_vec();
+            initiator.seal(1, &mut sealed).unwrap();
+            initiator.commit(1);
+            responder.open(&mut sealed).unwrap();
+            assert_eq!(sealed, data.to_vec());
+        }
+    }
+}
@@ -64,6 +64,8 @@ pub struct Handshake {
     state: HandshakeState,
     /// nonce for verification
     nonce: H256,
+    /// nonce of remote peer
+    remote_nonce: Option<H256>,
 }
 
 impl Handshake {
@@ -76,6 +78,7 @@ impl Handshake {
             connection: Connection::new(token, socket),
             state: HandshakeState::New,
             nonce: H256::random(),
+            remote_nonce: None,
         }
     }
 
@@ -98,6 +101,18 @@ impl Handshake {
     /// Check if handshake is complete
     pub fn done(&self) -> bool { self.state == HandshakeState::StartSession }
 
+    /// Get the nonces of initiator and responder, which are used to derive
+    /// the keys of an encrypted session. Return `None` if the cryptography is
+    /// bypassed.
+    pub fn nonces(&self, originated: bool) -> Option<(H256, H256)> {
+        let remote_nonce = self.remote_nonce?;
+        if originated {
+            Some((self.nonce, remote_nonce))
+        } else {
+            Some((remote_nonce, self.nonce))
+        }
+    }
+
     /// Readable IO handler. Drives the state change.
     pub fn readable<Message>(
         &mut self, io: &IoContext<Message>, host: &HostMetadata,
@@ -189,6 +204,7 @@ impl Handshake {
 
         let (remote_public, remote_nonce) = auth.split_at(NodeId::len_bytes());
         self.id.assign_from_slice(remote_public);
+        self.remote_nonce = Some(H256::from_slice(remote_nonce));
 
         self.write_ack_of_auth(io, remote_nonce)
     }
@@ -265,6 +281,7 @@ impl Handshake {
             return Err(ErrorKind::BadProtocol.into());
         }
 
+        self.remote_nonce = Some(H256::from_slice(remote_nonce));
         self.write_ack_of_ack(io, remote_nonce)
     }
 
@@ -23,8 +23,12 @@ impl SubnetType {
                 num >> (32 - prefix_bits)
             }
             IpAddr::V6(ipv6) => {
+                // IPv6 networks are allocated with much longer prefixes, so
+                // scale the prefix (/8, /16, /24 -> /16, /32, /48) and fold
+                // the result into 32 bits.
                 let num: u128 = ipv6.clone().into();
-                (num >> (128 - prefix_bits)) as u32
+                let subnet = (num >> (128 - 2 * prefix_bits)) as u64;
+                (subnet as u32) ^ ((subnet >> 32) as u32)
             }
         }
     }
@@ -50,6 +54,26 @@ mod tests {
 
     fn new_ip(ip: &'static str) -> IpAddr { IpAddr::from_str(ip).unwrap() }
 
+    #[test]
+    fn test_subnet_ipv6() {
+        assert_eq!(
+            SubnetType::C.subnet(&new_ip("2400:cb00:2048:1::1")),
+            SubnetType::C.subnet(&new_ip("2400:cb00:2048:ffff::2"))
+        );
+        assert_ne!(
+            SubnetType::C.subnet(&new_ip("2400:cb00:2048::1")),
+            SubnetType::C.subnet(&new_ip("2400:cb00:2049::1"))
+        );
+        assert_ne!(
+            SubnetType::C.subnet(&new_ip("2400:cb00:2048::1")),
+            SubnetType::C.subnet(&new_ip("2401:cb00:2048::1"))
+        );
+        assert_eq!(
+            SubnetType::A.subnet(&new_ip("2400:cb00::1")),
+            SubnetType::A.subnet(&new_ip("2400:1234::1"))
+        );
+    }
+
     #[test]
     fn test_subnet() {
         assert_eq!(
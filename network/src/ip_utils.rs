@@ -293,29 +293,52 @@ fn get_if_addrs() -> io::Result<Vec<IpAddr>> { getinterfaces::get_all() }
 #[cfg(any(windows, target_os = "android"))]
 fn get_if_addrs() -> io::Result<Vec<IpAddr>> { Ok(Vec::new()) }
 
-/// Select the best available public address
-pub fn select_public_address(port: u16) -> SocketAddr {
+/// Score an interface address as a candidate for the advertised public
+/// address. Higher is better, and `None` means the address must never be
+/// advertised.
+///
+/// Usable public addresses of either family are preferred over private ones,
+/// so that a host with a global IPv6 address and only a private IPv4 address
+/// (a common setup for IPv6-only VPSes) advertises the reachable one.
+fn public_address_score(addr: &IpAddr) -> Option<u8> {
+    match addr {
+        IpAddr::V4(a) if a.is_usable_public() => Some(4),
+        IpAddr::V6(a) if ipv4_mapped(a).is_none() && a.is_usable_public() => {
+            Some(3)
+        }
+        IpAddr::V4(a) if !SocketAddrExt::is_reserved(a) => Some(2),
+        IpAddr::V6(a) if ipv4_mapped(a).is_none() && !a.is_reserved() => {
+            Some(1)
+        }
+        _ => None,
+    }
+}
+
+fn select_best_address(
+    list: &[IpAddr], port: u16, allow_ipv6: bool,
+) -> Option<SocketAddr> {
+    list.iter()
+        .filter(|addr| allow_ipv6 || addr.is_ipv4())
+        .filter_map(|addr| public_address_score(addr).map(|s| (s, addr)))
+        // Keep the first address among those with the best score.
+        .fold(
+            None,
+            |best: Option<(u8, &IpAddr)>, (score, addr)| match best {
+                Some((best_score, _)) if best_score >= score => best,
+                _ => Some((score, addr)),
+            },
+        )
+        .map(|(_, addr)| SocketAddr::new(*addr, port))
+}
+
+/// Select the best available public address. IPv6 addresses are only
+/// considered if `allow_ipv6` is set, i.e. when the node listens on an IPv6
+/// socket and is able to accept IPv6 connections.
+pub fn select_public_address(port: u16, allow_ipv6: bool) -> SocketAddr {
     match get_if_addrs() {
         Ok(list) => {
-            //prefer IPV4 bindings
-            for addr in &list {
-                //TODO: use better criteria than just the first in the list
-                match addr {
-                    IpAddr::V4(a) if !SocketAddrExt::is_reserved(a) => {
-                        return SocketAddr::V4(SocketAddrV4::new(*a, port));
-                    }
-                    _ => {}
-                }
-            }
-            for addr in &list {
-                match addr {
-                    IpAddr::V6(a) if !a.is_reserved() => {
-                        return SocketAddr::V6(SocketAddrV6::new(
-                            *a, port, 0, 0,
-                        ));
-                    }
-                    _ => {}
-                }
+            if let Some(addr) = select_best_address(&list, port, allow_ipv6) {
+                return addr;
             }
         }
         Err(e) => debug!("Error listing public interfaces: {:?}", e),
@@ -323,6 +346,44 @@ pub fn select_public_address(port: u16) -> SocketAddr {
     SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port))
 }
 
+/// Returns the IPv4 address embedded in an IPv4-mapped IPv6 address
+/// (`::ffff:a.b.c.d`).
+fn ipv4_mapped(ip: &Ipv6Addr) -> Option<Ipv4Addr> {
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0xffff, ..] => {
+            let o = ip.octets();
+            Some(Ipv4Addr::new(o[12], o[13], o[14], o[15]))
+        }
+        _ => None,
+    }
+}
+
+/// Dual-stack sockets report IPv4 peers as IPv4-mapped IPv6 addresses.
+/// Convert such addresses back to plain IPv4 so that the node table,
+/// IP filters and per-subnet limits treat a peer the same way regardless
+/// of the socket it came through.
+pub fn to_canonical_address(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(a) => match ipv4_mapped(a.ip()) {
+            Some(ip) => SocketAddr::V4(SocketAddrV4::new(ip, a.port())),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
+
+/// Convert `addr` so that it can be used as a destination for a socket bound
+/// to `local`. An IPv6 socket can only reach IPv4 peers via IPv4-mapped
+/// addresses.
+pub fn to_local_family(addr: SocketAddr, local: &SocketAddr) -> SocketAddr {
+    match (addr, local) {
+        (SocketAddr::V4(a), SocketAddr::V6(_)) => SocketAddr::V6(
+            SocketAddrV6::new(a.ip().to_ipv6_mapped(), a.port(), 0, 0),
+        ),
+        _ => addr,
+    }
+}
+
 fn search_upnp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
     if let SocketAddr::V4(ref local_addr) = local.address {
         let local_ip = *local_addr.ip();
@@ -531,14 +592,62 @@ pub fn map_external_address(
 
 #[test]
 fn can_select_public_address() {
-    let pub_address = select_public_address(40477);
+    let pub_address = select_public_address(40477, true);
     assert!(pub_address.port() == 40477);
 }
 
+#[test]
+fn select_best_address_by_score() {
+    let list: Vec<IpAddr> = vec![
+        "::1".parse().unwrap(),
+        "127.0.0.1".parse().unwrap(),
+        "fe80::1".parse().unwrap(),
+        "192.168.1.2".parse().unwrap(),
+        "2400:cb00::1".parse().unwrap(),
+    ];
+    // A global IPv6 address wins over a private IPv4 address.
+    assert_eq!(
+        select_best_address(&list, 1234, true),
+        Some("[2400:cb00::1]:1234".parse().unwrap())
+    );
+    assert_eq!(
+        select_best_address(&list, 1234, false),
+        Some("192.168.1.2:1234".parse().unwrap())
+    );
+
+    let mut list = list;
+    list.push("8.8.8.8".parse().unwrap());
+    assert_eq!(
+        select_best_address(&list, 1234, true),
+        Some("8.8.8.8:1234".parse().unwrap())
+    );
+
+    let reserved: Vec<IpAddr> =
+        vec!["::1".parse().unwrap(), "127.0.0.1".parse().unwrap()];
+    assert_eq!(select_best_address(&reserved, 1234, true), None);
+}
+
+#[test]
+fn canonical_and_local_family_address() {
+    let v4: SocketAddr = "1.2.3.4:5".parse().unwrap();
+    let mapped: SocketAddr = "[::ffff:1.2.3.4]:5".parse().unwrap();
+    let v6: SocketAddr = "[2400:cb00::1]:5".parse().unwrap();
+
+    assert_eq!(to_canonical_address(mapped), v4);
+    assert_eq!(to_canonical_address(v4), v4);
+    assert_eq!(to_canonical_address(v6), v6);
+
+    let local_v4: SocketAddr = "0.0.0.0:1".parse().unwrap();
+    let local_v6: SocketAddr = "[::]:1".parse().unwrap();
+    assert_eq!(to_local_family(v4, &local_v6), mapped);
+    assert_eq!(to_local_family(v4, &local_v4), v4);
+    assert_eq!(to_local_family(v6, &local_v6), v6);
+}
+
 #[ignore]
 #[test]
 fn can_map_external_address_or_fail() {
-    let pub_address = select_public_address(40478);
+    let pub_address = select_public_address(40478, true);
     let _ = map_external_address(
         &NodeEndpoint {
             address: pub_address,
@@ -37,8 +37,10 @@ pub type ProtocolId = [u8; PROTOCOL_ID_SIZE];
 pub type HandlerWorkType = u8;
 pub type PeerId = usize;
 
+mod compression;
 mod connection;
 mod discovery;
+mod encryption;
 mod error;
 mod handshake;
 mod ip;
@@ -54,7 +56,7 @@ pub use crate::{
     error::{DisconnectReason, Error, ErrorKind, ThrottlingReason},
     ip::SessionIpLimitConfig,
     node_table::Node,
-    service::NetworkService,
+    service::{NetworkService, NodeLists},
     session::SessionDetails,
 };
 pub use io::TimerToken;
@@ -93,6 +95,9 @@ pub struct NetworkConfiguration {
     /// Directory path to store general network configuration. None means
     /// nothing will be saved
     pub config_path: Option<String>,
+    /// Address to listen on for TCP and UDP. Listening on the unspecified
+    /// IPv6 address `[::]` accepts both IPv4 and IPv6 peers on dual-stack
+    /// hosts.
     pub listen_address: Option<SocketAddr>,
     /// IP address to advertise. Detected automatically if none.
     pub public_address: Option<SocketAddr>,
@@ -114,8 +119,13 @@ pub struct NetworkConfiguration {
     pub max_incoming_peers: usize,
     /// Maximum number of ongoing handshakes
     pub max_handshakes: usize,
-    /// List of reserved node addresses.
-    pub reserved_nodes: Vec<String>,
+    /// List of static node addresses, which are always connected and
+    /// reconnected once disconnected.
+    pub static_nodes: Vec<String>,
+    /// List of trusted node addresses, which are exempt from the limits of
+    /// peer slots and IP policy. Not to be confused with the trusted node
+    /// table of node database, which is maintained automatically.
+    pub trusted_nodes: Vec<String>,
     /// IP filter
     pub ip_filter: IpFilter,
     /// Timeout duration for initiating peer connection management
@@ -136,6 +146,13 @@ pub struct NetworkConfiguration {
     /// Maximum number of P2P nodes for subnet B (ip/16).
     pub subnet_quota: usize,
     pub session_ip_limit_config: SessionIpLimitConfig,
+    /// Enable compression of large protocol messages if the peer supports.
+    pub enable_compression: bool,
+    /// Encrypt protocol packets if the peer supports.
+    pub enable_encryption: bool,
+    /// Refuse peers that do not support encryption. It should only be set
+    /// once most of the peers are upgraded to support encryption.
+    pub require_encryption: bool,
 
     pub discovery_config: DiscoveryConfiguration,
 }
@@ -157,7 +174,8 @@ impl NetworkConfiguration {
             max_outgoing_peers_archive: 0,
             max_incoming_peers: 0,
             max_handshakes: 0,
-            reserved_nodes: Vec::new(),
+            static_nodes: Vec::new(),
+            trusted_nodes: Vec::new(),
             ip_filter: IpFilter::default(),
             housekeeping_timeout: DEFAULT_HOUSEKEEPING_TIMEOUT,
             discovery_refresh_timeout: DEFAULT_DISCOVERY_REFRESH_TIMEOUT,
@@ -170,6 +188,9 @@ impl NetworkConfiguration {
             test_mode: false,
             subnet_quota: 32,
             session_ip_limit_config: SessionIpLimitConfig::default(),
+            enable_compression: true,
+            enable_encryption: true,
+            require_encryption: false,
             discovery_config,
         }
     }
@@ -284,10 +305,13 @@ pub trait NetworkContext {
 
     fn get_peer_connection_origin(&self, node_id: &NodeId) -> Option<bool>;
 
+    /// Send a message to the peer. If `compressible`, the message may be
+    /// compressed when it is large enough and the peer supports compression.
     fn send(
         &self, node_id: &NodeId, msg: Vec<u8>,
         min_protocol_version: ProtocolVersion,
         version_valid_till: ProtocolVersion, priority: SendQueuePriority,
+        compressible: bool,
     ) -> Result<(), Error>;
 
     fn disconnect_peer(
@@ -312,6 +336,7 @@ pub trait NetworkContext {
 #[derive(Debug, Clone)]
 pub struct SessionMetadata {
     pub id: Option<NodeId>,
+    /// The common protocols with the versions negotiated in Hello.
     /// There won't be many protocols so it's faster to use Vec than Map.
     pub peer_protocols: Vec<ProtocolInfo>,
     pub originated: bool,
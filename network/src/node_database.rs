@@ -8,11 +8,18 @@ use crate::{
     IpFilter,
 };
 use io::StreamToken;
-use std::{collections::HashSet, net::IpAddr, time::Duration};
+use std::{
+    collections::HashSet,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 const TRUSTED_NODES_FILE: &str = "trusted_nodes.json";
 const UNTRUSTED_NODES_FILE: &str = "untrusted_nodes.json";
 const BLACKLISTED_NODES_FILE: &str = "blacklisted_nodes.json";
+const BANNED_NODES_FILE: &str = "banned_nodes.json";
 
 /// Node database maintains all P2P nodes in trusted and untrusted node tables,
 /// and supports to limit the number of nodes for the same IP address.
@@ -92,6 +99,11 @@ pub struct NodeDatabase {
     // Maximum duration to blacklist a node since last contact.
     blacklisted_lifetime: Duration,
 
+    // Banned nodes are refused to connect until unbanned manually. Unlike
+    // blacklisted nodes, they never expire and are persisted once changed.
+    banned_nodes: HashSet<NodeId>,
+    banned_nodes_path: Option<PathBuf>,
+
     // IP address/subnet index for trusted and untrusted nodes.
     ip_limit: NodeIpLimit,
 
@@ -112,12 +124,17 @@ impl NodeDatabase {
         let ip_limit = NodeIpLimit::new(subnet_quota);
         let trusted_node_tag_index =
             NodeTagIndex::new_with_node_table(&trusted_nodes);
+        let banned_nodes_path = path
+            .as_ref()
+            .map(|dir| Path::new(dir).join(BANNED_NODES_FILE));
 
         let mut db = NodeDatabase {
             trusted_nodes,
             untrusted_nodes,
             blacklisted_nodes: NodeTable::new(path, BLACKLISTED_NODES_FILE),
             blacklisted_lifetime: Duration::from_secs(7 * 24 * 3600),
+            banned_nodes: Self::load_banned_nodes(&banned_nodes_path),
+            banned_nodes_path,
             ip_limit,
             trusted_node_tag_index,
         };
@@ -584,7 +601,13 @@ impl NodeDatabase {
     /// Check if the specified node is blacklisted.
     /// If blacklisted for a long time, it will be removed from blacklisted node
     /// table.
+    ///
+    /// Banned nodes are always treated as blacklisted.
     pub fn evaluate_blacklisted(&mut self, id: &NodeId) -> bool {
+        if self.banned_nodes.contains(id) {
+            return true;
+        }
+
         let node = match self.blacklisted_nodes.get_mut(id) {
             Some(node) => node,
             None => return false,
@@ -610,6 +633,74 @@ impl NodeDatabase {
 
         true
     }
+
+    /// Ban the specified node, which is removed from database and refused to
+    /// connect until unbanned.
+    pub fn ban(&mut self, id: &NodeId) {
+        self.remove(id);
+        if self.banned_nodes.insert(*id) {
+            self.save_banned_nodes();
+        }
+    }
+
+    /// Unban the specified node. Return `false` if the node is not banned.
+    pub fn unban(&mut self, id: &NodeId) -> bool {
+        if !self.banned_nodes.remove(id) {
+            return false;
+        }
+        self.save_banned_nodes();
+        true
+    }
+
+    pub fn is_banned(&self, id: &NodeId) -> bool {
+        self.banned_nodes.contains(id)
+    }
+
+    pub fn banned_nodes(&self) -> Vec<NodeId> {
+        self.banned_nodes.iter().cloned().collect()
+    }
+
+    fn load_banned_nodes(path: &Option<PathBuf>) -> HashSet<NodeId> {
+        let file = match path.as_ref().map(fs::File::open) {
+            Some(Ok(file)) => file,
+            _ => return HashSet::new(),
+        };
+
+        match serde_json::from_reader::<_, Vec<NodeId>>(file) {
+            Ok(nodes) => nodes.into_iter().collect(),
+            Err(e) => {
+                warn!("Error reading banned nodes file: {:?}", e);
+                HashSet::new()
+            }
+        }
+    }
+
+    fn save_banned_nodes(&self) {
+        let path = match self.banned_nodes_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("Error creating banned nodes directory: {:?}", e);
+                return;
+            }
+        }
+
+        match fs::File::create(path) {
+            Ok(file) => {
+                if let Err(e) =
+                    serde_json::to_writer_pretty(file, &self.banned_nodes())
+                {
+                    warn!("Error writing banned nodes file: {:?}", e);
+                }
+            }
+            Err(e) => {
+                warn!("Error creating banned nodes file: {:?}", e);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -797,4 +888,23 @@ mod tests {
         assert_eq!(db.evaluate_blacklisted(&n.id), false);
         assert_eq!(db.get(&n.id, false), None);
     }
+
+    #[test]
+    fn test_banned() {
+        let mut db = NodeDatabase::new(None, 2);
+
+        let n = new_entry("127.0.0.1:999");
+        db.insert_trusted(n.clone());
+        db.ban(&n.id);
+        assert_eq!(db.get(&n.id, false), None);
+        assert_eq!(db.evaluate_blacklisted(&n.id), true);
+
+        // refuse to add banned node
+        db.insert_trusted(n.clone());
+        assert_eq!(db.get(&n.id, false), None);
+
+        assert_eq!(db.unban(&n.id), true);
+        assert_eq!(db.unban(&n.id), false);
+        assert_eq!(db.evaluate_blacklisted(&n.id), false);
+    }
 }
@@ -20,7 +20,6 @@ use std::{
         ToSocketAddrs,
     },
     path::{Path, PathBuf},
-    slice,
     str::FromStr,
     time::{self, Duration, SystemTime},
 };
@@ -95,14 +94,14 @@ impl NodeEndpoint {
                     o[i] = ((addr_bytes[2 * i + 1] as u16) << 8)
                         | (addr_bytes[2 * i] as u16);
                 }
-                Ok(SocketAddr::V6(SocketAddrV6::new(
+                Ok(to_canonical_address(SocketAddr::V6(SocketAddrV6::new(
                     Ipv6Addr::new(
                         o[0], o[1], o[2], o[3], o[4], o[5], o[6], o[7],
                     ),
                     tcp_port,
                     0,
                     0,
-                )))
+                ))))
             }
             _ => Err(DecoderError::RlpInconsistentLengthAndData),
         }?;
@@ -114,10 +113,16 @@ impl NodeEndpoint {
             SocketAddr::V4(a) => {
                 rlp.append(&(&a.ip().octets()[..]));
             }
-            SocketAddr::V6(a) => unsafe {
-                let o: *const u8 = a.ip().segments().as_ptr() as *const u8;
-                rlp.append(&slice::from_raw_parts(o, 16));
-            },
+            SocketAddr::V6(a) => {
+                // Each segment is encoded in little-endian, which is the
+                // format used by the deployed nodes and the persisted node
+                // tables.
+                let mut o = [0u8; 16];
+                for (i, segment) in a.ip().segments().iter().enumerate() {
+                    o[2 * i..2 * i + 2].copy_from_slice(&segment.to_le_bytes());
+                }
+                rlp.append(&(&o[..]));
+            }
         };
         rlp.append(&self.udp_port);
         rlp.append(&self.address.port());
@@ -964,3 +969,53 @@ mod json {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::NodeEndpoint;
+    use rlp::{Rlp, RlpStream};
+
+    fn rlp_round_trip(endpoint: &NodeEndpoint) -> NodeEndpoint {
+        let mut rlp = RlpStream::new();
+        endpoint.to_rlp_list(&mut rlp);
+        let encoded = rlp.out();
+        NodeEndpoint::from_rlp(&Rlp::new(&encoded)).unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_rlp_ipv4() {
+        let endpoint = NodeEndpoint {
+            address: "1.2.3.4:32323".parse().unwrap(),
+            udp_port: 32324,
+        };
+        assert_eq!(rlp_round_trip(&endpoint), endpoint);
+    }
+
+    #[test]
+    fn test_endpoint_rlp_ipv6() {
+        let endpoint = NodeEndpoint {
+            address: "[2400:cb00:2048:1::6810:1234]:32323".parse().unwrap(),
+            udp_port: 32324,
+        };
+        assert_eq!(rlp_round_trip(&endpoint), endpoint);
+
+        // The segments are encoded in little-endian as the deployed nodes do.
+        let mut rlp = RlpStream::new();
+        endpoint.to_rlp_list(&mut rlp);
+        let encoded = rlp.out();
+        let addr_bytes = Rlp::new(&encoded).at(0).unwrap().data().unwrap();
+        assert_eq!(&addr_bytes[..4], &[0x00, 0x24, 0x00, 0xcb]);
+    }
+
+    #[test]
+    fn test_endpoint_rlp_ipv4_mapped() {
+        let endpoint = NodeEndpoint {
+            address: "[::ffff:1.2.3.4]:32323".parse().unwrap(),
+            udp_port: 32324,
+        };
+        assert_eq!(
+            rlp_round_trip(&endpoint).address,
+            "1.2.3.4:32323".parse().unwrap()
+        );
+    }
+}
@@ -7,7 +7,10 @@ use crate::{
     discovery::Discovery,
     handshake::BYPASS_CRYPTOGRAPHY,
     io::*,
-    ip_utils::{map_external_address, select_public_address},
+    ip_utils::{
+        map_external_address, select_public_address, to_canonical_address,
+        to_local_family,
+    },
     node_database::NodeDatabase,
     node_table::*,
     parse_msg_id_leb128_2_bytes_at_most,
@@ -36,7 +39,10 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{atomic::Ordering as AtomicOrdering, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -84,6 +90,7 @@ const DEFAULT_CHECK_SESSIONS_TIMEOUT: Duration = Duration::from_secs(10);
     Debug,
     Default,
     Eq,
+    Ord,
     PartialOrd,
     PartialEq,
     Serialize,
@@ -265,6 +272,76 @@ impl NetworkService {
         }
     }
 
+    /// Add a static node, which is always connected and reconnected once
+    /// disconnected.
+    pub fn add_static_node(&self, node: NodeEntry) -> Result<(), Error> {
+        if let Some(ref x) = self.inner {
+            x.add_static_node(node)
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Remove a static node. Return `false` if the node is not static.
+    pub fn remove_static_node(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.static_nodes.write().remove(id))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Add a trusted node, which is exempt from the limits of peer slots and
+    /// IP policy.
+    pub fn add_trusted_node(&self, node: NodeEntry) -> Result<(), Error> {
+        if let Some(ref x) = self.inner {
+            x.add_trusted_node(node)
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Remove a trusted node. Return `false` if the node is not trusted.
+    /// The established session with the node is not affected.
+    pub fn remove_trusted_node(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.trusted_nodes.write().remove(id))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Ban a node persistently, and disconnect it if connected.
+    pub fn ban_node(&self, id: &NodeId) -> Result<(), Error> {
+        match (&self.inner, &self.io_service) {
+            (Some(inner), Some(io_service)) => {
+                let io = IoContext::new(io_service.channel(), 0);
+                inner.ban_node(id, &io);
+                Ok(())
+            }
+            _ => Err("Network service not started yet!".into()),
+        }
+    }
+
+    /// Unban a node. Return `false` if the node is not banned.
+    pub fn unban_node(&self, id: &NodeId) -> Result<bool, Error> {
+        if let Some(ref x) = self.inner {
+            Ok(x.node_db.write().unban(id))
+        } else {
+            Err("Network service not started yet!".into())
+        }
+    }
+
+    /// Get the static, trusted and banned nodes.
+    pub fn get_node_lists(&self) -> Option<NodeLists> {
+        let inner = self.inner.as_ref()?;
+        Some(NodeLists {
+            static_nodes: inner.static_nodes.read().iter().cloned().collect(),
+            trusted_nodes: inner.trusted_nodes.read().iter().cloned().collect(),
+            banned_nodes: inner.node_db.read().banned_nodes(),
+        })
+    }
+
     /// Get the local address of the client
     pub fn local_addr(&self) -> Option<SocketAddr> {
         self.inner.as_ref().map(|inner_ref| inner_ref.local_addr())
@@ -394,6 +471,41 @@ impl NetworkService {
             inner.node_db.write().save();
         }
     }
+
+    /// Change the maximal numbers of outgoing and incoming peers. The
+    /// connected peers are not disconnected if the new limits are exceeded.
+    pub fn set_peer_limits(
+        &self, max_outgoing_peers: usize, max_incoming_peers: usize,
+    ) -> Result<(), Error> {
+        match self.inner {
+            Some(ref inner) => {
+                inner
+                    .max_outgoing_peers
+                    .store(max_outgoing_peers, AtomicOrdering::Relaxed);
+                inner.sessions.set_max_ingress_sessions(max_incoming_peers);
+                Ok(())
+            }
+            None => Err("Network service not started yet!".into()),
+        }
+    }
+
+    /// Stop the event loop, so that no more connections and messages are
+    /// handled, and save the node database.
+    pub fn stop(&self) {
+        if let Some(io_service) = &self.io_service {
+            io_service.stop();
+        }
+        self.save_node_db();
+    }
+}
+
+/// Nodes managed by user, which is specially for Debug RPC.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeLists {
+    pub static_nodes: Vec<NodeId>,
+    pub trusted_nodes: Vec<NodeId>,
+    pub banned_nodes: Vec<NodeId>,
 }
 
 type SharedSession = Arc<RwLock<Session>>;
@@ -438,8 +550,14 @@ pub struct NetworkServiceInner {
     timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
     timer_counter: RwLock<usize>,
     pub node_db: RwLock<NodeDatabase>,
-    reserved_nodes: RwLock<HashSet<NodeId>>,
+    static_nodes: RwLock<HashSet<NodeId>>,
+    /// Trusted nodes, which are identified by node id. Ingress sessions are
+    /// only trusted after handshake, when the node id is known.
+    trusted_nodes: RwLock<HashSet<NodeId>>,
     dropped_nodes: RwLock<HashSet<NodeId>>,
+    /// Initialized with `config.max_outgoing_peers`, and can be changed at
+    /// runtime.
+    max_outgoing_peers: AtomicUsize,
 
     is_consortium: bool,
 
@@ -466,9 +584,11 @@ impl DelayedQueue {
             &context.io,
             Some(context.protocol),
             context.min_protocol_version,
+            context.version_valid_till,
             session::PACKET_USER,
             context.msg,
             context.priority,
+            context.compressible,
         );
         match r {
             Ok(_) => {}
@@ -555,8 +675,10 @@ impl NetworkServiceInner {
         let public_address = config.public_address;
         let public_endpoint = match public_address {
             None => {
-                let public_address =
-                    select_public_address(local_endpoint.address.port());
+                let public_address = select_public_address(
+                    local_endpoint.address.port(),
+                    local_endpoint.address.is_ipv6(),
+                );
                 let public_endpoint = NodeEndpoint {
                     address: public_address,
                     udp_port: local_endpoint.udp_port,
@@ -598,7 +720,7 @@ impl NetworkServiceInner {
 
         let nodes_path = config.config_path.clone();
 
-        let mut inner = NetworkServiceInner {
+        let inner = NetworkServiceInner {
             metadata: HostMetadata {
                 network_id: config.id,
                 keys,
@@ -626,8 +748,10 @@ impl NetworkServiceInner {
                 nodes_path,
                 config.subnet_quota,
             )),
-            reserved_nodes: RwLock::new(HashSet::new()),
+            static_nodes: RwLock::new(HashSet::new()),
+            trusted_nodes: RwLock::new(HashSet::new()),
             dropped_nodes: RwLock::new(HashSet::new()),
+            max_outgoing_peers: AtomicUsize::new(config.max_outgoing_peers),
             is_consortium: config.is_consortium,
             delayed_queue: None,
         };
@@ -636,10 +760,25 @@ impl NetworkServiceInner {
             inner.add_boot_node(n);
         }
 
-        let reserved_nodes = config.reserved_nodes.clone();
-        for n in reserved_nodes {
-            if let Err(e) = inner.add_reserved_node(&n) {
-                debug!("Error parsing node id: {}: {:?}", n, e);
+        for n in &config.static_nodes {
+            if let Err(e) = Node::from_str(n).and_then(|n| {
+                inner.add_static_node(NodeEntry {
+                    id: n.id,
+                    endpoint: n.endpoint,
+                })
+            }) {
+                debug!("Error adding static node: {}: {:?}", n, e);
+            }
+        }
+
+        for n in &config.trusted_nodes {
+            if let Err(e) = Node::from_str(n).and_then(|n| {
+                inner.add_trusted_node(NodeEntry {
+                    id: n.id,
+                    endpoint: n.endpoint,
+                })
+            }) {
+                debug!("Error adding trusted node: {}: {:?}", n, e);
             }
         }
 
@@ -691,16 +830,44 @@ impl NetworkServiceInner {
         }
     }
 
-    fn add_reserved_node(&mut self, id: &str) -> Result<(), Error> {
-        let n = Node::from_str(id)?;
-        self.node_db.write().insert_trusted(NodeEntry {
-            id: n.id.clone(),
-            endpoint: n.endpoint.clone(),
-        });
-        self.reserved_nodes.write().insert(n.id);
+    fn add_static_node(&self, node: NodeEntry) -> Result<(), Error> {
+        if self.node_db.read().is_banned(&node.id) {
+            return Err("Node is banned".into());
+        }
+
+        let id = node.id;
+        self.node_db.write().insert_trusted(node);
+        self.static_nodes.write().insert(id);
+        Ok(())
+    }
+
+    fn add_trusted_node(&self, node: NodeEntry) -> Result<(), Error> {
+        if self.node_db.read().is_banned(&node.id) {
+            return Err("Node is banned".into());
+        }
+
+        self.trusted_nodes.write().insert(node.id);
+        self.node_db.write().insert_trusted(node);
         Ok(())
     }
 
+    pub fn is_trusted_node(&self, id: &NodeId) -> bool {
+        self.trusted_nodes.read().contains(id)
+    }
+
+    fn ban_node(&self, id: &NodeId, io: &IoContext<NetworkIoMessage>) {
+        self.static_nodes.write().remove(id);
+        self.trusted_nodes.write().remove(id);
+        self.node_db.write().ban(id);
+        self.kill_connection(
+            id,
+            io,
+            true,
+            None,
+            "node banned", // reason
+        );
+    }
+
     fn initialize_udp_protocols(
         &self, io: &IoContext<NetworkIoMessage>,
     ) -> Result<(), Error> {
@@ -792,7 +959,7 @@ impl NetworkServiceInner {
         self.drop_peers(io);
     }
 
-    // Connect to all reserved and trusted peers if not yet
+    // Connect to all static and trusted peers if not yet
     fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
         if self.metadata.minimum_peer_protocol_version.read().len() == 0 {
             // The protocol handler has not been registered, we just wait for
@@ -808,12 +975,12 @@ impl NetworkServiceInner {
             self.sessions.stat();
         let samples;
         {
-            let egress_attempt_count = if self.config.max_outgoing_peers
+            let max_outgoing_peers =
+                self.max_outgoing_peers.load(AtomicOrdering::Relaxed);
+            let egress_attempt_count = if max_outgoing_peers
                 > egress_count + sampled_archive_nodes.len()
             {
-                self.config.max_outgoing_peers
-                    - egress_count
-                    - sampled_archive_nodes.len()
+                max_outgoing_peers - egress_count - sampled_archive_nodes.len()
             } else {
                 0
             };
@@ -823,9 +990,9 @@ impl NetworkServiceInner {
             );
         }
 
-        let reserved_nodes = self.reserved_nodes.read();
-        // Try to connect all reserved peers and trusted peers
-        let nodes = reserved_nodes
+        let static_nodes = self.static_nodes.read();
+        // Try to connect all static peers and trusted peers
+        let nodes = static_nodes
             .iter()
             .cloned()
             .chain(sampled_archive_nodes)
@@ -922,7 +1089,9 @@ impl NetworkServiceInner {
                 }
             };
 
-            if !self.sessions.is_ip_allowed(&address.ip()) {
+            if !self.is_trusted_node(id)
+                && !self.sessions.is_ip_allowed(&address.ip())
+            {
                 debug!("cannot create outgoing connection to node, id = {:?}, address = {:?}", id, address);
                 return;
             }
@@ -1003,7 +1172,10 @@ impl NetworkServiceInner {
         io: &IoContext<NetworkIoMessage>,
     ) -> Result<(), Error>
     {
-        match self.sessions.create(socket, address, id, io, self) {
+        // Ingress sessions are trusted after handshake, see
+        // `SessionManager::update_ingress_node_id`.
+        let trusted = id.map_or(false, |id| self.is_trusted_node(id));
+        match self.sessions.create(socket, address, id, trusted, io, self) {
             Ok(token) => {
                 debug!("new session created, token = {}, address = {:?}, id = {:?}", token, address, id);
                 if let Some(id) = id {
@@ -1197,7 +1369,7 @@ impl NetworkServiceInner {
         trace!("Accepting incoming connection");
         loop {
             let (socket, address) = match self.tcp_listener.lock().accept() {
-                Ok((sock, addr)) => (sock, addr),
+                Ok((sock, addr)) => (sock, to_canonical_address(addr)),
                 Err(e) => {
                     if e.kind() != io::ErrorKind::WouldBlock {
                         debug!("Error accepting connection: {:?}", e);
@@ -1382,7 +1554,7 @@ impl NetworkServiceInner {
         let mut buf = [0u8; MAX_DATAGRAM_SIZE];
         match udp_socket.recv_from(&mut buf) {
             Ok(Some((len, address))) => self
-                .on_udp_packet(&buf[0..len], address)
+                .on_udp_packet(&buf[0..len], to_canonical_address(address))
                 .unwrap_or_else(|e| {
                     debug!("Error processing UDP packet: {:?}", e);
                 }),
@@ -1410,9 +1582,19 @@ impl NetworkServiceInner {
 
     fn udp_writable(&self, io: &IoContext<NetworkIoMessage>) {
         let udp_socket = self.udp_socket.lock();
+        let local_address = self.metadata.local_endpoint.address;
         let mut udp_channel = self.udp_channel.write();
         while let Some(data) = udp_channel.dequeue_send() {
-            match udp_socket.send_to(&data.payload, &data.address) {
+            let address = to_local_family(data.address, &local_address);
+            if address.is_ipv4() != local_address.is_ipv4() {
+                trace!(
+                    "Skip UDP send to {:?}, not reachable from {:?}",
+                    address,
+                    local_address
+                );
+                continue;
+            }
+            match udp_socket.send_to(&data.payload, &address) {
                 Ok(Some(size)) if size == data.payload.len() => {}
                 Ok(Some(_)) => {
                     warn!("UDP sent incomplete datagram");
@@ -1545,7 +1727,7 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the _slow_ discovery if enough peers are connected
                 let disc_general = self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
@@ -1566,7 +1748,7 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the fast discovery if not enough peers are connected
                 let disc_general = !self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = !self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
@@ -1847,14 +2029,19 @@ struct DelayMessageContext {
     msg: Vec<u8>,
     /// The minimum peer protocol version since which the message is supported.
     min_protocol_version: ProtocolVersion,
+    /// The maximum peer protocol version until which the message is valid.
+    version_valid_till: ProtocolVersion,
     priority: SendQueuePriority,
+    compressible: bool,
 }
 
 impl DelayMessageContext {
     pub fn new(
         ts: Instant, io: IoContext<NetworkIoMessage>, protocol: ProtocolId,
         session: SharedSession, peer: NodeId, msg: Vec<u8>,
-        min_protocol_version: ProtocolVersion, priority: SendQueuePriority,
+        min_protocol_version: ProtocolVersion,
+        version_valid_till: ProtocolVersion, priority: SendQueuePriority,
+        compressible: bool,
     ) -> Self
     {
         DelayMessageContext {
@@ -1865,7 +2052,9 @@ impl DelayMessageContext {
             peer,
             msg,
             min_protocol_version,
+            version_valid_till,
             priority,
+            compressible,
         }
     }
 }
@@ -1935,6 +2124,7 @@ impl<'a> NetworkContextTrait for NetworkContext<'a> {
         &self, node_id: &NodeId, msg: Vec<u8>,
         min_protocol_version: ProtocolVersion,
         version_valid_till: ProtocolVersion, priority: SendQueuePriority,
+        compressible: bool,
     ) -> Result<(), Error>
     {
         if version_valid_till < self.min_supported_version {
@@ -1976,7 +2166,9 @@ impl<'a> NetworkContextTrait for NetworkContext<'a> {
                         *node_id,
                         msg,
                         min_protocol_version,
+                        version_valid_till,
                         priority,
+                        compressible,
                     ));
                     self.io.register_timer_once_nocancel(
                         SEND_DELAYED_MESSAGES,
@@ -1989,9 +2181,11 @@ impl<'a> NetworkContextTrait for NetworkContext<'a> {
                         self.io,
                         Some(self.protocol),
                         min_protocol_version,
+                        version_valid_till,
                         session::PACKET_USER,
                         msg,
                         priority,
+                        compressible,
                     )?;
                 }
             }
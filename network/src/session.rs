@@ -3,7 +3,15 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
-    connection::{Connection, ConnectionDetails, SendQueueStatus, WriteStatus},
+    compression::{Compression, COMPRESSION_MIN_SIZE, SUPPORTED_COMPRESSIONS},
+    connection::{
+        Connection, ConnectionDetails, SendQueueStatus, WriteStatus,
+        MAX_PAYLOAD_SIZE,
+    },
+    encryption::{
+        priority_channel, sign_ephemeral, verify_ephemeral, SessionCipher,
+        CHANNEL_IMMEDIATE,
+    },
     handshake::Handshake,
     node_table::{NodeEndpoint, NodeEntry, NodeId},
     parse_msg_id_leb128_2_bytes_at_most,
@@ -12,13 +20,15 @@ use crate::{
     SessionMetadata, UpdateNodeOperation, PROTOCOL_ID_SIZE,
 };
 use bytes::Bytes;
+use cfx_types::{Public, H256, H520};
 use io::*;
+use keylib::{Generator, KeyPair, Random};
 use mio::{tcp::*, *};
 use priority_send_queue::SendQueuePriority;
 use rlp::{Rlp, RlpStream};
 use serde_derive::Serialize;
 use std::{
-    fmt,
+    cmp, fmt,
     net::SocketAddr,
     str,
     time::{Duration, Instant},
@@ -31,9 +41,12 @@ use std::{
 /// packet to exchange the supported protocols. Then, session is ready to send
 /// and receive protocol packets.
 ///
-/// Conflux do not use AES based encrypted connection to send protocol packets.
-/// This is because that Conflux has high TPS, and the encryption/decryption
-/// workloads are very heavy (about 20% CPU time in 3000 TPS).
+/// Protocol packets are sealed with ChaCha20-Poly1305 if both peers support
+/// it, and the keys are derived from ephemeral keys exchanged in Hello, see
+/// `encryption` for details. ChaCha20 is used instead of AES because Conflux
+/// has high TPS, and AES is too heavy on CPUs without hardware acceleration.
+/// Peers of old versions do not support encryption, and they are accepted
+/// unless `require_encryption` is configured.
 pub struct Session {
     /// Session information
     pub metadata: SessionMetadata,
@@ -47,6 +60,15 @@ pub struct Session {
     had_hello: Option<Instant>,
     /// Session is no longer active flag.
     expired: Option<Instant>,
+    /// Compression algorithm negotiated in Hello, if any.
+    compression: Option<Compression>,
+    /// Nonces of initiator and responder in handshake.
+    handshake_nonces: Option<(H256, H256)>,
+    /// Ephemeral key pair sent in Hello, which is dropped once the session
+    /// keys are derived.
+    ephemeral: Option<KeyPair>,
+    /// Cipher to seal and open packets if encryption is negotiated in Hello.
+    cipher: Option<SessionCipher>,
 
     // statistics for read/write
     last_read: Instant,
@@ -85,6 +107,8 @@ pub struct SessionDataWithDisconnectInfo {
 const PACKET_HELLO: u8 = 0x80;
 // id for Disconnect packet
 const PACKET_DISCONNECT: u8 = 0x01;
+// id for encrypted packet, whose data is another sealed session packet
+const PACKET_SEALED: u8 = 0x02;
 // id for protocol packet
 pub const PACKET_USER: u8 = 0x10;
 /// header_version for protocol packet.
@@ -92,6 +116,9 @@ pub const PACKET_USER: u8 = 0x10;
 pub const PACKET_HEADER_VERSION: u8 = 0;
 /// The header version where extension is introduced.
 const HEADER_VERSION_WITH_EXTENSION: u8 = 0;
+/// Extension to indicate that the packet data is compressed, followed by the
+/// id of compression algorithm.
+const EXTENSION_COMPRESSION: u8 = 1;
 
 impl Session {
     /// Create a new instance of `Session`, which starts to handshake with
@@ -119,6 +146,10 @@ impl Session {
             sent_hello: Instant::now(),
             had_hello: None,
             expired: None,
+            compression: None,
+            handshake_nonces: None,
+            ephemeral: None,
+            cipher: None,
             last_read: Instant::now(),
             last_write: (Instant::now(), WriteStatus::Complete),
         })
@@ -215,6 +246,8 @@ impl Session {
             self.metadata.id = Some(id);
         }
 
+        self.handshake_nonces = wrapper.get().nonces(self.metadata.originated);
+
         // write HELLO packet to remote peer
         self.state = State::Session(wrapper.take().connection);
         self.write_hello(io, host)?;
@@ -274,7 +307,15 @@ impl Session {
     fn read_packet(
         &mut self, data: Bytes, host: &NetworkServiceInner,
     ) -> Result<SessionDataWithDisconnectInfo, Error> {
-        let packet = SessionPacket::parse(data)?;
+        let mut packet = SessionPacket::parse(data)?;
+
+        if packet.id == PACKET_SEALED {
+            packet = self.open_packet(packet)?;
+        } else if self.cipher.is_some() {
+            // Otherwise, anyone on the path could inject a Disconnect.
+            debug!("Unencrypted packet {:?}, session = {:?}", packet, self);
+            return Err(ErrorKind::BadProtocol.into());
+        }
 
         // For protocol packet, the Hello packet should already been received.
         // So that dispatch it to the corresponding protocol handler.
@@ -318,7 +359,7 @@ impl Session {
             }
             PACKET_USER => Ok(SessionDataWithDisconnectInfo {
                 session_data: SessionData::Message {
-                    data: packet.data.to_vec(),
+                    data: self.decompress_packet_data(&packet)?,
                     protocol: packet
                         .protocol
                         .expect("protocol should available for USER packet"),
@@ -335,6 +376,60 @@ impl Session {
         }
     }
 
+    /// Decrypt the sealed packet. Only protocol and Disconnect packets could be
+    /// sealed.
+    fn open_packet(
+        &mut self, packet: SessionPacket,
+    ) -> Result<SessionPacket, Error> {
+        let cipher = match self.cipher {
+            Some(ref mut cipher) => cipher,
+            None => {
+                debug!("Unexpected sealed packet, session = {:?}", self);
+                bail!(ErrorKind::BadProtocol);
+            }
+        };
+
+        let mut data = packet.data.to_vec();
+        cipher.open(&mut data)?;
+        let packet = SessionPacket::parse(data.into())?;
+        if packet.id != PACKET_USER && packet.id != PACKET_DISCONNECT {
+            debug!("Invalid sealed packet {:?}, session = {:?}", packet, self);
+            bail!(ErrorKind::BadProtocol);
+        }
+
+        Ok(packet)
+    }
+
+    /// Get the data of protocol packet, which is decompressed if the packet
+    /// has the compression extension.
+    fn decompress_packet_data(
+        &self, packet: &SessionPacket,
+    ) -> Result<Vec<u8>, Error> {
+        let compression = match packet
+            .extensions
+            .iter()
+            .find(|ext| ext.first() == Some(&EXTENSION_COMPRESSION))
+        {
+            Some(ext) => ext,
+            None => return Ok(packet.data.to_vec()),
+        };
+
+        let compression =
+            compression.get(1).and_then(|id| Compression::from_id(*id));
+        match compression
+            .and_then(|c| c.decompress(&packet.data, MAX_PAYLOAD_SIZE))
+        {
+            Some(data) => Ok(data),
+            None => {
+                debug!(
+                    "failed to decompress packet, compression = {:?}, session = {:?}",
+                    compression, self
+                );
+                Err(ErrorKind::BadProtocol.into())
+            }
+        }
+    }
+
     /// Update node Id in `SessionManager` for ingress session.
     fn update_ingress_node_id(
         &mut self, host: &NetworkServiceInner,
@@ -350,7 +445,9 @@ impl Session {
             .id
             .expect("should have node id after handshake");
 
-        host.sessions.update_ingress_node_id(token, &node_id)
+        let ip = self.address.ip();
+        let trusted = host.is_trusted_node(&node_id);
+        host.sessions.update_ingress_node_id(token, &node_id, &ip, trusted)
             .map_err(|reason| {
                 debug!(
                     "failed to update node id of ingress session, reason = {:?}, session = {:?}",
@@ -363,7 +460,9 @@ impl Session {
 
     /// Read Hello packet to exchange the supported protocols, and set the
     /// `had_hello` flag to indicates that session is ready to send/receive
-    /// protocol packets.
+    /// protocol packets. The version of each common protocol is negotiated
+    /// with remote peer, and only packets valid in the negotiated version
+    /// could be sent to the peer.
     ///
     /// Besides, the node endpoint of remote peer will be added or updated in
     /// node database, which is used to establish outgoing connections.
@@ -380,7 +479,7 @@ impl Session {
             )));
         }
 
-        let mut peer_caps: Vec<ProtocolInfo> = rlp.list_at(1)?;
+        let peer_caps: Vec<ProtocolInfo> = rlp.list_at(1)?;
         for i in 1..peer_caps.len() {
             for j in 0..i {
                 if peer_caps[j].protocol == peer_caps[i].protocol {
@@ -396,20 +495,87 @@ impl Session {
             }
         }
 
-        peer_caps.retain(|c| {
-            host.metadata
-                .minimum_peer_protocol_version
-                .read()
-                .iter()
-                .any(|hc| hc.protocol == c.protocol && hc.version <= c.version)
-        });
+        // The minimum versions supported by the peer. Peers of old versions
+        // do not send them.
+        let peer_min_versions: Vec<ProtocolInfo> = if rlp.item_count()? > 3 {
+            rlp.list_at(3)?
+        } else {
+            Vec::new()
+        };
 
-        self.metadata.peer_protocols = peer_caps;
+        self.metadata.peer_protocols = {
+            let protocols = host.metadata.protocols.read();
+            let min_versions =
+                host.metadata.minimum_peer_protocol_version.read();
+            let mut negotiated = Vec::new();
+            for cap in peer_caps {
+                let local =
+                    match protocols.iter().find(|p| p.protocol == cap.protocol)
+                    {
+                        Some(local) => local,
+                        None => continue,
+                    };
+                let local_min_version = min_versions
+                    .iter()
+                    .find(|p| p.protocol == cap.protocol)
+                    .map_or(ProtocolVersion::default(), |p| p.version);
+                let peer_min_version = peer_min_versions
+                    .iter()
+                    .find(|p| p.protocol == cap.protocol)
+                    .map(|p| p.version);
+                match negotiate_protocol_version(
+                    local.version,
+                    local_min_version,
+                    cap.version,
+                    peer_min_version,
+                ) {
+                    Some(version) => negotiated.push(ProtocolInfo {
+                        protocol: cap.protocol,
+                        version,
+                    }),
+                    None => debug!(
+                        "Incompatible protocol {:?} version, local = {}, \
+                         remote = {}, session = {:?}",
+                        cap.protocol, local.version, cap.version, self
+                    ),
+                }
+            }
+            negotiated
+        };
         if self.metadata.peer_protocols.is_empty() {
             debug!("No common capabilities with remote peer, peer_node_id = {:?}, session = {:?}", self.metadata.id, self);
             return Err(self.send_disconnect(DisconnectReason::UselessPeer));
         }
 
+        // The compression algorithms supported by the peer. Peers of old
+        // versions do not send them, and packets are never compressed.
+        self.compression =
+            if host.config.enable_compression && rlp.item_count()? > 4 {
+                let peer_compressions: Vec<u8> = rlp.list_at(4)?;
+                Compression::negotiate(&peer_compressions)
+            } else {
+                None
+            };
+
+        // The signed ephemeral key of the peer to encrypt the session. Peers of
+        // old versions do not send it, and packets are never encrypted.
+        let cipher = match self.ephemeral.take() {
+            Some(ephemeral) if rlp.item_count()? > 5 => {
+                self.negotiate_cipher(&ephemeral, &rlp.at(5)?)?
+            }
+            _ => None,
+        };
+        if cipher.is_none() && host.config.require_encryption {
+            debug!("Encryption not supported, session = {:?}", self);
+            return Err(self.send_disconnect(DisconnectReason::Custom(
+                "encryption required".into(),
+            )));
+        }
+        // The peer derives the same keys once it has read our Hello, which is
+        // sent before any other packet, so the packets from now on, including
+        // Disconnect, are sealed.
+        self.cipher = cipher;
+
         let mut hello_from = NodeEndpoint::from_rlp(&rlp.at(2)?)?;
         // Use the ip of the socket as endpoint ip directly.
         // We do not allow peers to specify the ip to avoid being used to DDoS
@@ -451,12 +617,60 @@ impl Session {
         Ok(())
     }
 
-    /// Assemble a packet with specified protocol id, packet id and data.
+    /// Verify the signed ephemeral key of the peer in Hello, and derive the
+    /// keys of session. Return `None` if the peer does not support encryption.
+    fn negotiate_cipher(
+        &mut self, ephemeral: &KeyPair, rlp: &Rlp,
+    ) -> Result<Option<SessionCipher>, Error> {
+        if rlp.item_count()? != 2 {
+            return Ok(None);
+        }
+        let (initiator_nonce, responder_nonce) = match self.handshake_nonces {
+            Some(nonces) => nonces,
+            None => return Ok(None),
+        };
+        let local_nonce = if self.metadata.originated {
+            &initiator_nonce
+        } else {
+            &responder_nonce
+        };
+
+        let peer_ephemeral: Public = rlp.val_at(0)?;
+        let signature: H520 = rlp.val_at(1)?;
+        let node_id = self
+            .metadata
+            .id
+            .expect("should have node id after handshake");
+        if !verify_ephemeral(
+            &node_id,
+            &peer_ephemeral,
+            local_nonce,
+            &signature.into(),
+        ) {
+            debug!("Invalid ephemeral key signature, session = {:?}", self);
+            return Err(self.send_disconnect(DisconnectReason::Custom(
+                "invalid ephemeral key".into(),
+            )));
+        }
+
+        Ok(Some(SessionCipher::new(
+            ephemeral,
+            &peer_ephemeral,
+            &initiator_nonce,
+            &responder_nonce,
+            self.metadata.originated,
+        )?))
+    }
+
+    /// Assemble a packet with specified protocol id, packet id and data, and
+    /// seal it on `channel` if encryption is negotiated.
     /// Return concrete error if session is expired or the protocol id is
     /// invalid.
     fn prepare_packet(
-        &self, protocol: Option<ProtocolId>, packet_id: u8, data: Vec<u8>,
-    ) -> Result<Vec<u8>, Error> {
+        &mut self, protocol: Option<ProtocolId>, packet_id: u8,
+        mut data: Vec<u8>, compressible: bool, channel: u8,
+    ) -> Result<Vec<u8>, Error>
+    {
         if protocol.is_some() && self.had_hello.is_none() {
             debug!(
                 "Sending to unconfirmed session {}, protocol: {:?}, packet: {}",
@@ -473,26 +687,57 @@ impl Session {
             return Err(ErrorKind::Expired.into());
         }
 
-        Ok(SessionPacket::assemble(
+        let mut extensions = Vec::new();
+        if let Some(compression) = self.compression {
+            if compressible && data.len() >= COMPRESSION_MIN_SIZE {
+                if let Some(compressed) = compression.compress(&data) {
+                    data = compressed;
+                    extensions
+                        .push(vec![EXTENSION_COMPRESSION, compression.id()]);
+                }
+            }
+        }
+
+        let packet = SessionPacket::assemble_with_extensions(
             packet_id,
             self.metadata.peer_header_version,
             protocol,
             data,
-        ))
+            extensions,
+        );
+
+        match self.cipher {
+            Some(ref cipher) => {
+                let mut sealed = packet;
+                cipher.seal(channel, &mut sealed)?;
+                Ok(SessionPacket::assemble_with_extensions(
+                    PACKET_SEALED,
+                    self.metadata.peer_header_version,
+                    None,
+                    sealed,
+                    Vec::new(),
+                ))
+            }
+            None => Ok(packet),
+        }
     }
 
     #[inline]
     pub fn check_message_protocol_version(
         &self, protocol: Option<ProtocolId>,
-        min_protocol_version: ProtocolVersion, mut msg: &[u8],
+        min_protocol_version: ProtocolVersion,
+        version_valid_till: ProtocolVersion, mut msg: &[u8],
     ) -> Result<(), Error>
     {
-        // min_protocol_version is the version when the Message is introduced.
-        // peer protocol version must be higher.
+        // min_protocol_version is the version when the Message is introduced,
+        // and version_valid_till is the last version the Message is valid in.
+        // The protocol version negotiated with peer must be in between.
         if let Some(protocol) = protocol {
             for peer_protocol in &self.metadata.peer_protocols {
                 if protocol.eq(&peer_protocol.protocol) {
-                    if min_protocol_version <= peer_protocol.version {
+                    if min_protocol_version <= peer_protocol.version
+                        && peer_protocol.version <= version_valid_till
+                    {
                         break;
                     } else {
                         bail!(ErrorKind::SendUnsupportedMessage {
@@ -511,35 +756,66 @@ impl Session {
         Ok(())
     }
 
-    /// Send a packet to remote peer asynchronously.
+    /// Send a packet to remote peer asynchronously. If `compressible`, the
+    /// packet data may be compressed with the negotiated algorithm.
     pub fn send_packet<Message: Send + Sync + Clone>(
         &mut self, io: &IoContext<Message>, protocol: Option<ProtocolId>,
-        min_proto_version: ProtocolVersion, packet_id: u8, data: Vec<u8>,
-        priority: SendQueuePriority,
+        min_proto_version: ProtocolVersion,
+        version_valid_till: ProtocolVersion, packet_id: u8, data: Vec<u8>,
+        priority: SendQueuePriority, compressible: bool,
     ) -> Result<SendQueueStatus, Error>
     {
         self.check_message_protocol_version(
             protocol.clone(),
             min_proto_version,
+            version_valid_till,
             &data,
         )?;
-        let packet = self.prepare_packet(protocol, packet_id, data)?;
-        self.connection_mut().send(io, packet, priority)
+        let packet = self.prepare_packet(
+            protocol,
+            packet_id,
+            data,
+            compressible,
+            priority_channel(priority),
+        )?;
+        let status = self.connection_mut().send(io, packet, priority)?;
+        self.commit_sealed(priority_channel(priority));
+        Ok(status)
     }
 
     /// Send a packet to remote peer immediately.
     pub fn send_packet_immediately(
         &mut self, protocol: Option<ProtocolId>,
-        min_proto_version: ProtocolVersion, packet_id: u8, data: Vec<u8>,
+        min_proto_version: ProtocolVersion,
+        version_valid_till: ProtocolVersion, packet_id: u8, data: Vec<u8>,
     ) -> Result<usize, Error>
     {
         self.check_message_protocol_version(
             protocol.clone(),
             min_proto_version,
+            version_valid_till,
             &data,
         )?;
-        let packet = self.prepare_packet(protocol, packet_id, data)?;
-        self.connection_mut().write_raw_data(packet)
+        let packet = self.prepare_packet(
+            protocol,
+            packet_id,
+            data,
+            false, /* compressible */
+            CHANNEL_IMMEDIATE,
+        )?;
+        // Sealed packets must not overtake the queued Hello, without which
+        // the peer could not open them.
+        let size = self.connection_mut().write_after_queued(packet)?;
+        self.commit_sealed(CHANNEL_IMMEDIATE);
+        Ok(size)
+    }
+
+    /// Advance the nonce of `channel` once the packet sealed on it is
+    /// accepted by the connection.
+    fn commit_sealed(&mut self, channel: u8) {
+        if let Some(ref mut cipher) = self.cipher {
+            cipher.commit(channel);
+        }
     }
 
     /// Send a Disconnect packet immediately to the remote peer.
@@ -548,6 +824,7 @@ impl Session {
         let _ = self.send_packet_immediately(
             None,
             ProtocolVersion::default(),
+            ProtocolVersion::default(),
             PACKET_DISCONNECT,
             packet,
         );
@@ -559,17 +836,55 @@ impl Session {
         &mut self, io: &IoContext<Message>, host: &NetworkServiceInner,
     ) -> Result<(), Error> {
         debug!("Sending Hello, session = {:?}", self);
-        let mut rlp = RlpStream::new_list(3);
+        let mut rlp = RlpStream::new_list(6);
         rlp.append(&host.metadata.network_id);
         rlp.append_list(&*host.metadata.protocols.read());
         host.metadata.public_endpoint.to_rlp_list(&mut rlp);
+        // Appended at the end so that peers of old versions can still decode
+        // the Hello packet.
+        rlp.append_list(&*host.metadata.minimum_peer_protocol_version.read());
+        let compressions: Vec<u8> = if host.config.enable_compression {
+            SUPPORTED_COMPRESSIONS.iter().map(|c| c.id()).collect()
+        } else {
+            Vec::new()
+        };
+        rlp.append_list(&compressions);
+        // The ephemeral key to encrypt the session, which is signed for the
+        // nonce that the peer chose in handshake.
+        match self.handshake_nonces {
+            Some((initiator_nonce, responder_nonce))
+                if host.config.enable_encryption =>
+            {
+                let remote_nonce = if self.metadata.originated {
+                    responder_nonce
+                } else {
+                    initiator_nonce
+                };
+                let ephemeral =
+                    Random.generate().expect("Fail to generate KeyPair.");
+                let signature = sign_ephemeral(
+                    host.metadata.secret(),
+                    ephemeral.public(),
+                    &remote_nonce,
+                )?;
+                rlp.begin_list(2);
+                rlp.append(ephemeral.public());
+                rlp.append(&H520::from_slice(&signature[..]));
+                self.ephemeral = Some(ephemeral);
+            }
+            _ => {
+                rlp.begin_list(0);
+            }
+        }
         self.send_packet(
             io,
             None,
             ProtocolVersion::default(),
+            ProtocolVersion::default(),
             PACKET_HELLO,
             rlp.drain(),
             SendQueuePriority::High,
+            false, /* compressible */
         )
         .map(|_| ())
     }
@@ -713,18 +1028,47 @@ struct SessionPacket {
 
 impl SessionPacket {
     // data + Option<protocol> + protocol_flag + packet_id
+    #[cfg(test)]
     fn assemble(
+        id: u8, header_version: u8, protocol: Option<ProtocolId>, data: Vec<u8>,
+    ) -> Vec<u8> {
+        Self::assemble_with_extensions(
+            id,
+            header_version,
+            protocol,
+            data,
+            Vec::new(),
+        )
+    }
+
+    // data + extensions + Option<protocol> + has_extension + protocol_flag
+    // + packet_id
+    fn assemble_with_extensions(
         id: u8, header_version: u8, protocol: Option<ProtocolId>,
-        mut data: Vec<u8>,
+        mut data: Vec<u8>, extensions: Vec<Vec<u8>>,
     ) -> Vec<u8>
     {
+        // Extensions are parsed from the end, so append them in reverse order.
+        let has_extension = !extensions.is_empty();
+        for (i, extension) in extensions.iter().enumerate().rev() {
+            assert!(extension.len() < 128);
+            let has_next_extension = i + 1 < extensions.len();
+            data.extend_from_slice(extension);
+            data.push(
+                ((extension.len() as u8) << 1) + has_next_extension as u8,
+            );
+        }
+
         let mut protocol_flag = 0;
         if let Some(protocol) = protocol {
             data.extend_from_slice(&protocol);
             protocol_flag = 1;
         }
 
-        let header_byte = (header_version << 1) + protocol_flag;
+        let mut header_byte = (header_version << 1) + protocol_flag;
+        if has_extension {
+            header_byte |= 0x10;
+        }
         data.push(header_byte);
         data.push(id);
 
@@ -834,6 +1178,28 @@ impl fmt::Debug for SessionPacket {
     }
 }
 
+/// Negotiate the version of a protocol to talk with remote peer, which is the
+/// highest version supported by both sides, so that peers of different
+/// versions can talk with each other during protocol upgrades.
+///
+/// Return `None` if either side is too old for the other. If the peer does
+/// not tell its minimum supported version, it's assumed to support all the
+/// versions up to its current one.
+fn negotiate_protocol_version(
+    local_version: ProtocolVersion, local_min_version: ProtocolVersion,
+    peer_version: ProtocolVersion, peer_min_version: Option<ProtocolVersion>,
+) -> Option<ProtocolVersion>
+{
+    let version = cmp::min(local_version, peer_version);
+    if version < local_min_version
+        || version < peer_min_version.unwrap_or_default()
+    {
+        None
+    } else {
+        Some(version)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -903,4 +1269,50 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        let v = ProtocolVersion;
+
+        // Use the lower one of the current versions.
+        assert_eq!(
+            negotiate_protocol_version(v(3), v(1), v(2), None),
+            Some(v(2))
+        );
+        assert_eq!(
+            negotiate_protocol_version(v(3), v(1), v(4), Some(v(2))),
+            Some(v(3))
+        );
+
+        // Peer is too old for us.
+        assert_eq!(negotiate_protocol_version(v(3), v(2), v(1), None), None);
+
+        // We are too old for peer.
+        assert_eq!(
+            negotiate_protocol_version(v(2), v(1), v(4), Some(v(3))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_packet_with_extensions() {
+        let packet = SessionPacket::assemble_with_extensions(
+            PACKET_USER,
+            PACKET_HEADER_VERSION,
+            Some([3; 3]),
+            vec![1, 2],
+            vec![vec![EXTENSION_COMPRESSION, 1], vec![9]],
+        );
+        let packet = SessionPacket::parse(packet.into()).unwrap();
+        assert_eq!(
+            packet,
+            SessionPacket {
+                id: PACKET_USER,
+                header_version: 0,
+                protocol: Some([3; 3]),
+                data: vec![1, 2].into(),
+                extensions: vec![vec![EXTENSION_COMPRESSION, 1], vec![9]],
+            }
+        );
+    }
 }
@@ -31,6 +31,9 @@ use std::{
 ///
 /// The session manager also limits the maximum number of incoming TCP
 /// connections, so as to establish some trusted outgoing connections.
+///
+/// Sessions with trusted nodes configured by user are exempt from all the
+/// limitations above, and do not occupy the slots of ingress or egress peers.
 pub struct SessionManager {
     sessions: RwLock<Slab<Arc<RwLock<Session>>>>,
     capacity: usize,
@@ -41,13 +44,15 @@ pub struct SessionManager {
     offset: usize,
 
     /// used to limit the ingress sessions.
-    max_ingress_sessions: usize,
+    max_ingress_sessions: AtomicUsize,
     cur_ingress_sessions: AtomicUsize,
 
     /// session indices
     node_id_index: RwLock<HashMap<NodeId, usize>>,
     ip_limit: RwLock<Box<dyn SessionIpLimit>>,
     tag_index: RwLock<SessionTagIndex>,
+    /// Indices of sessions with trusted nodes.
+    trusted_sessions: RwLock<HashSet<usize>>,
 }
 
 impl SessionManager {
@@ -61,17 +66,25 @@ impl SessionManager {
             sessions: RwLock::new(Slab::with_capacity(capacity)),
             offset,
             capacity,
-            max_ingress_sessions,
+            max_ingress_sessions: AtomicUsize::new(max_ingress_sessions),
             cur_ingress_sessions: AtomicUsize::new(0),
             node_id_index: RwLock::new(HashMap::new()),
             ip_limit: RwLock::new(new_session_ip_limit(ip_limit_config)),
             tag_index: Default::default(),
+            trusted_sessions: Default::default(),
         }
     }
 
     /// Get the number of sessions in `SessionManager`.
     pub fn count(&self) -> usize { self.sessions.read().len() }
 
+    /// Change the limit of ingress sessions. The existing sessions are kept
+    /// even if the new limit is exceeded.
+    pub fn set_max_ingress_sessions(&self, max_ingress_sessions: usize) {
+        self.max_ingress_sessions
+            .store(max_ingress_sessions, Ordering::Relaxed);
+    }
+
     /// Get the session of specified index.
     pub fn get(&self, idx: usize) -> Option<Arc<RwLock<Session>>> {
         self.sessions.read().get(idx).cloned()
@@ -106,13 +119,17 @@ impl SessionManager {
     }
 
     /// Retrieves the session count of handshakes, egress and ingress.
+    /// Established sessions with trusted nodes are not counted.
     pub fn stat(&self) -> (usize, usize, usize) {
         let mut handshakes = 0;
         let mut egress = 0;
         let mut ingress = 0;
 
-        for (_, s) in self.sessions.read().iter() {
+        let trusted_sessions = self.trusted_sessions.read();
+        for (idx, s) in self.sessions.read().iter() {
             match s.try_read() {
+                Some(ref s)
+                    if s.is_ready() && trusted_sessions.contains(&idx) => {}
                 Some(ref s) if s.is_ready() && s.metadata.originated => {
                     egress += 1
                 }
@@ -143,9 +160,12 @@ impl SessionManager {
 
     /// Creates a new session with specified TCP socket. It is egress connection
     /// if the `id` is not `None`, otherwise it is ingress connection.
+    /// If `trusted`, the session is exempt from the limitations of ingress
+    /// sessions and node IP policy.
     pub fn create(
         &self, socket: TcpStream, address: SocketAddr, id: Option<&NodeId>,
-        io: &IoContext<NetworkIoMessage>, host: &NetworkServiceInner,
+        trusted: bool, io: &IoContext<NetworkIoMessage>,
+        host: &NetworkServiceInner,
     ) -> Result<usize, String>
     {
         debug!(
@@ -159,11 +179,12 @@ impl SessionManager {
 
         // limits ingress sessions whose node id is `None`.
         let ingress = self.cur_ingress_sessions.load(Ordering::Relaxed);
-        if id.is_none() && ingress >= self.max_ingress_sessions {
+        let max_ingress = self.max_ingress_sessions.load(Ordering::Relaxed);
+        if id.is_none() && !trusted && ingress >= max_ingress {
             debug!("SessionManager.create: leave on maximum ingress sessions reached");
             return Err(format!(
                 "maximum ingress sessions reached, current = {}, max = {}",
-                ingress, self.max_ingress_sessions
+                ingress, max_ingress
             ));
         }
 
@@ -182,7 +203,7 @@ impl SessionManager {
 
         // validate against node IP policy.
         let ip = address.ip();
-        if !ip_limit.is_allowed(&ip) {
+        if !trusted && !ip_limit.is_allowed(&ip) {
             debug!("SessionManager.create: leave on IP policy limited");
             return Err(format!(
                 "IP policy limited, nodeId = {:?}, addr = {:?}",
@@ -219,10 +240,14 @@ impl SessionManager {
             node_id_index.insert(node_id.clone(), index);
         }
 
-        assert!(ip_limit.add(ip));
+        if trusted {
+            self.trusted_sessions.write().insert(index);
+        } else {
+            assert!(ip_limit.add(ip));
 
-        if id.is_none() {
-            self.cur_ingress_sessions.fetch_add(1, Ordering::Relaxed);
+            if id.is_none() {
+                self.cur_ingress_sessions.fetch_add(1, Ordering::Relaxed);
+            }
         }
 
         debug!("SessionManager.create: leave");
@@ -248,10 +273,15 @@ impl SessionManager {
                 }
             }
 
-            assert!(self.ip_limit.write().remove(&session.address().ip()));
+            if !self.trusted_sessions.write().remove(&session.token()) {
+                assert!(self
+                    .ip_limit
+                    .write()
+                    .remove(&session.address().ip()));
 
-            if !session.metadata.originated {
-                self.cur_ingress_sessions.fetch_sub(1, Ordering::Relaxed);
+                if !session.metadata.originated {
+                    self.cur_ingress_sessions.fetch_sub(1, Ordering::Relaxed);
+                }
             }
 
             self.tag_index.write().remove(session.token());
@@ -262,12 +292,14 @@ impl SessionManager {
         debug!("SessionManager.remove: leave");
     }
 
-    /// Update the node id index for ingress session.
+    /// Update the node id index for ingress session. If the node is
+    /// `trusted`, the session is exempt from the limitations of ingress
+    /// sessions and node IP policy from now on.
     /// Return error if the session index does not exist, or the node id already
     /// in use by other session.
     /// Return optional to-be-disconnected token if no error happens.
     pub fn update_ingress_node_id(
-        &self, idx: usize, node_id: &NodeId,
+        &self, idx: usize, node_id: &NodeId, ip: &IpAddr, trusted: bool,
     ) -> Result<Option<usize>, String> {
         debug!("SessionManager.update_ingress_node_id: enter");
         let mut token_to_disconnect = None;
@@ -296,6 +328,14 @@ impl SessionManager {
 
         node_id_index.insert(node_id.clone(), idx);
 
+        if trusted {
+            let mut ip_limit = self.ip_limit.write();
+            if self.trusted_sessions.write().insert(idx) {
+                assert!(ip_limit.remove(ip));
+                self.cur_ingress_sessions.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
         debug!("SessionManager.update_ingress_node_id: leave");
 
         Ok(token_to_disconnect)
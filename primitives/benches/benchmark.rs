@@ -2,9 +2,18 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use cfxkey::{recover, sign, verify_public, KeyPair};
+use cfxkey::{
+    recover, recover_batch, sign, verify_public, Generator, KeyPair, Random,
+};
 use criterion::{criterion_group, criterion_main, Criterion};
 use keccak_hash::keccak;
+use std::thread;
+use threadpool::ThreadPool;
+
+/// The number of the signatures recovered at once, as in a block.
+const BATCH_SIZE: usize = 100;
+/// The number of the threads recovering a batch, as in the node.
+const WORKERS: usize = 8;
 
 fn recover_benchmark(c: &mut Criterion) {
     let secret =
@@ -37,5 +46,58 @@ fn verify_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, recover_benchmark, verify_benchmark);
+/// Compares recovering the signatures of a block one by one with recovering
+/// them as a batch on a pool of `WORKERS` threads.
+fn recover_batch_benchmark(c: &mut Criterion) {
+    let signed: Vec<_> = (0..BATCH_SIZE)
+        .map(|i| {
+            let kp = Random.generate().unwrap();
+            let msg = keccak(i.to_string());
+            (sign(kp.secret(), &msg).unwrap(), msg)
+        })
+        .collect();
+    let one_by_one = signed.clone();
+    c.bench_function("Recover public one by one", move |b| {
+        b.iter(|| {
+            for (sig, msg) in &one_by_one {
+                recover(sig, msg).unwrap();
+            }
+        });
+    });
+    let workers = ThreadPool::new(WORKERS);
+    c.bench_function("Recover public batch", move |b| {
+        b.iter(|| {
+            recover_batch(signed.clone(), &workers);
+        });
+    });
+}
+
+/// The threads share the context of `SECP256K1`, so that a new thread does not
+/// pay for creating one, which costs about as much as a recovery.
+fn recover_on_new_thread_benchmark(c: &mut Criterion) {
+    let kp = Random.generate().unwrap();
+    let msg = keccak(b"0");
+    let sig = sign(kp.secret(), &msg).unwrap();
+    c.bench_function("Spawn a thread", move |b| {
+        b.iter(|| {
+            thread::spawn(|| {}).join().unwrap();
+        });
+    });
+    c.bench_function("Recover public on a new thread", move |b| {
+        b.iter(|| {
+            let sig = sig.clone();
+            thread::spawn(move || recover(&sig, &msg).unwrap())
+                .join()
+                .unwrap();
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    recover_benchmark,
+    verify_benchmark,
+    recover_batch_benchmark,
+    recover_on_new_thread_benchmark
+);
 criterion_main!(benches);
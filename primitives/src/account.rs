@@ -164,6 +164,10 @@ pub struct Account {
     /// This is the number of tokens used as collateral for storage, which will
     /// be returned to balance if the storage is released.
     pub collateral_for_storage: U256,
+    /// This is the number of bytes of storage collateralized by
+    /// `collateral_for_storage`. It is only kept when it is not implied by
+    /// the collateral at the initial price per byte.
+    pub collateralized_bytes: Option<u64>,
     /// This is the accumulated interest return.
     pub accumulated_interest_return: U256,
     /// This is the address of the administrator of the contract.
@@ -172,8 +176,10 @@ pub struct Account {
     pub sponsor_info: SponsorInfo,
 }
 
-/// Defined for Rlp serialization/deserialization.
-#[derive(RlpEncodable, RlpDecodable, Clone, PartialEq, Debug)]
+/// Defined for Rlp serialization/deserialization. `collateralized_bytes` is
+/// appended only when it is kept, so that the other accounts are encoded as
+/// before.
+#[derive(Clone, PartialEq, Debug)]
 pub struct BasicAccount {
     pub balance: U256,
     pub nonce: U256,
@@ -184,10 +190,12 @@ pub struct BasicAccount {
     pub collateral_for_storage: U256,
     /// This is the accumulated interest return.
     pub accumulated_interest_return: U256,
+    /// See `Account::collateralized_bytes`.
+    pub collateralized_bytes: Option<u64>,
 }
 
-/// Defined for Rlp serialization/deserialization.
-#[derive(RlpEncodable, RlpDecodable, Clone, PartialEq)]
+/// Defined for Rlp serialization/deserialization, see `BasicAccount`.
+#[derive(Clone, PartialEq)]
 pub struct ContractAccount {
     pub balance: U256,
     pub nonce: U256,
@@ -203,6 +211,80 @@ pub struct ContractAccount {
     pub admin: Address,
     /// This is the sponsor information of the contract.
     pub sponsor_info: SponsorInfo,
+    /// See `Account::collateralized_bytes`.
+    pub collateralized_bytes: Option<u64>,
+}
+
+impl Encodable for BasicAccount {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        stream
+            .begin_list(5 + self.collateralized_bytes.is_some() as usize)
+            .append(&self.balance)
+            .append(&self.nonce)
+            .append(&self.staking_balance)
+            .append(&self.collateral_for_storage)
+            .append(&self.accumulated_interest_return);
+        if let Some(bytes) = &self.collateralized_bytes {
+            stream.append(bytes);
+        }
+    }
+}
+
+impl Decodable for BasicAccount {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let collateralized_bytes = match rlp.item_count()? {
+            5 => None,
+            6 => Some(rlp.val_at(5)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+        Ok(BasicAccount {
+            balance: rlp.val_at(0)?,
+            nonce: rlp.val_at(1)?,
+            staking_balance: rlp.val_at(2)?,
+            collateral_for_storage: rlp.val_at(3)?,
+            accumulated_interest_return: rlp.val_at(4)?,
+            collateralized_bytes,
+        })
+    }
+}
+
+impl Encodable for ContractAccount {
+    fn rlp_append(&self, stream: &mut RlpStream) {
+        stream
+            .begin_list(8 + self.collateralized_bytes.is_some() as usize)
+            .append(&self.balance)
+            .append(&self.nonce)
+            .append(&self.code_hash)
+            .append(&self.staking_balance)
+            .append(&self.collateral_for_storage)
+            .append(&self.accumulated_interest_return)
+            .append(&self.admin)
+            .append(&self.sponsor_info);
+        if let Some(bytes) = &self.collateralized_bytes {
+            stream.append(bytes);
+        }
+    }
+}
+
+impl Decodable for ContractAccount {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let collateralized_bytes = match rlp.item_count()? {
+            8 => None,
+            9 => Some(rlp.val_at(8)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+        Ok(ContractAccount {
+            balance: rlp.val_at(0)?,
+            nonce: rlp.val_at(1)?,
+            code_hash: rlp.val_at(2)?,
+            staking_balance: rlp.val_at(3)?,
+            collateral_for_storage: rlp.val_at(4)?,
+            accumulated_interest_return: rlp.val_at(5)?,
+            admin: rlp.val_at(6)?,
+            sponsor_info: rlp.val_at(7)?,
+            collateralized_bytes,
+        })
+    }
 }
 
 impl Account {
@@ -235,6 +317,7 @@ impl Account {
             code_hash: KECCAK_EMPTY,
             staking_balance: 0.into(),
             collateral_for_storage: 0.into(),
+            collateralized_bytes: None,
             accumulated_interest_return: 0.into(),
             admin: Address::zero(),
             sponsor_info: Default::default(),
@@ -249,6 +332,7 @@ impl Account {
             code_hash: KECCAK_EMPTY,
             staking_balance: a.staking_balance,
             collateral_for_storage: a.collateral_for_storage,
+            collateralized_bytes: a.collateralized_bytes,
             accumulated_interest_return: a.accumulated_interest_return,
             admin: Address::zero(),
             sponsor_info: Default::default(),
@@ -266,6 +350,7 @@ impl Account {
                 code_hash: a.code_hash,
                 staking_balance: a.staking_balance,
                 collateral_for_storage: a.collateral_for_storage,
+                collateralized_bytes: a.collateralized_bytes,
                 accumulated_interest_return: a.accumulated_interest_return,
                 admin: a.admin,
                 sponsor_info: a.sponsor_info,
@@ -285,6 +370,7 @@ impl Account {
             staking_balance: self.staking_balance,
             collateral_for_storage: self.collateral_for_storage,
             accumulated_interest_return: self.accumulated_interest_return,
+            collateralized_bytes: self.collateralized_bytes,
         }
     }
 
@@ -298,6 +384,7 @@ impl Account {
             accumulated_interest_return: self.accumulated_interest_return,
             admin: self.admin,
             sponsor_info: self.sponsor_info.clone(),
+            collateralized_bytes: self.collateralized_bytes,
         }
     }
 
@@ -368,6 +455,8 @@ mod tests {
         Account,
     };
     use cfx_types::{Address, H160, U256};
+    use rlp::Rlp;
+
     #[test]
     fn test_account_basic() {
         let account = Account {
@@ -379,6 +468,7 @@ mod tests {
             code_hash: KECCAK_EMPTY,
             staking_balance: U256::zero(),
             collateral_for_storage: U256::zero(),
+            collateralized_bytes: None,
             accumulated_interest_return: U256::zero(),
             admin: H160([0x00; 20]),
             sponsor_info: Default::default(),
@@ -393,6 +483,7 @@ mod tests {
             code_hash: KECCAK_EMPTY,
             staking_balance: U256::zero(),
             collateral_for_storage: U256::zero(),
+            collateralized_bytes: None,
             accumulated_interest_return: U256::zero(),
             admin: "0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6"
                 .parse::<Address>()
@@ -418,6 +509,7 @@ mod tests {
             staking_balance: U256::zero(),
             collateral_for_storage: U256::zero(),
             accumulated_interest_return: U256::zero(),
+            collateralized_bytes: None,
         };
         assert_eq!(
             Account::from_basic_account(address.clone(), basic_account.clone()),
@@ -433,6 +525,7 @@ mod tests {
             accumulated_interest_return: U256::zero(),
             admin: H160([0x00; 20]),
             sponsor_info: Default::default(),
+            collateralized_bytes: None,
         };
         assert_eq!(
             Account::from_contract_account(
@@ -451,6 +544,7 @@ mod tests {
             code_hash: KECCAK_EMPTY,
             staking_balance: U256::zero(),
             collateral_for_storage: U256::zero(),
+            collateralized_bytes: None,
             accumulated_interest_return: U256::zero(),
             admin: H160([0x00; 20]),
             sponsor_info: Default::default(),
@@ -464,4 +558,41 @@ mod tests {
             account2.clone()
         );
     }
+
+    #[test]
+    fn test_collateralized_bytes_rlp() {
+        let mut account = BasicAccount {
+            balance: U256::from(1),
+            nonce: U256::zero(),
+            staking_balance: U256::zero(),
+            collateral_for_storage: U256::from(64),
+            accumulated_interest_return: U256::zero(),
+            collateralized_bytes: None,
+        };
+        // Encoded as before without the number of bytes.
+        let encoded = rlp::encode(&account);
+        assert_eq!(Rlp::new(&encoded).item_count(), Ok(5));
+        assert_eq!(rlp::decode::<BasicAccount>(&encoded), Ok(account.clone()));
+
+        account.collateralized_bytes = Some(128);
+        let encoded = rlp::encode(&account);
+        assert_eq!(Rlp::new(&encoded).item_count(), Ok(6));
+        assert_eq!(rlp::decode::<BasicAccount>(&encoded), Ok(account));
+
+        let contract_account = ContractAccount {
+            balance: U256::zero(),
+            nonce: U256::zero(),
+            code_hash: KECCAK_EMPTY,
+            staking_balance: U256::zero(),
+            collateral_for_storage: U256::from(64),
+            accumulated_interest_return: U256::zero(),
+            admin: H160([0x00; 20]),
+            sponsor_info: Default::default(),
+            collateralized_bytes: Some(128),
+        };
+        let encoded = rlp::encode(&contract_account);
+        assert!(
+            rlp::decode::<ContractAccount>(&encoded) == Ok(contract_account)
+        );
+    }
 }
@@ -39,14 +39,15 @@ pub use crate::{
     block_header::{BlockHeader, BlockHeaderBuilder},
     epoch::{BlockHashOrEpochNumber, EpochId, EpochNumber, NULL_EPOCH},
     log_entry::LogEntry,
-    receipt::{BlockReceipts, Receipt},
+    receipt::{BlockReceipts, Receipt, ReceiptProof},
     state_root::*,
     storage::{
         MptValue, NodeMerkleTriplet, StorageLayout, StorageRoot, StorageValue,
     },
     storage_key::*,
     transaction::{
-        Action, ChainIdParams, SignedTransaction, Transaction,
+        AccessList, AccessListItem, Action, ChainIdParams, ChainIdTransition,
+        SignaturePolicy, SignedTransaction, Transaction,
         TransactionWithSignature, TransactionWithSignatureSerializePart,
         TxPropagateId,
     },
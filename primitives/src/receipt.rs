@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::log_entry::LogEntry;
+use crate::{bytes::Bytes, log_entry::LogEntry};
 use cfx_types::{Address, Bloom, U256};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use rlp_derive::{RlpDecodable, RlpEncodable};
@@ -96,6 +96,25 @@ impl MallocSizeOf for BlockReceipts {
         self.receipts.size_of(ops)
     }
 }
+
+/// A proof that `receipt` is the `tx_index_in_block`-th receipt in the
+/// `block_index_in_epoch`-th block of an epoch, which is verified against the
+/// receipts root of the epoch, e.g. by the light clients and the bridges.
+/// The proofs of the tries are RLP encoded, as the tries are defined in the
+/// storage.
+#[derive(Debug, Clone, PartialEq, Eq, RlpDecodable, RlpEncodable)]
+pub struct ReceiptProof {
+    pub receipt: Receipt,
+    pub block_index_in_epoch: usize,
+    pub num_blocks_in_epoch: usize,
+    /// The proof of the receipts root of the block in the epoch.
+    pub block_index_proof: Bytes,
+    pub tx_index_in_block: usize,
+    pub num_txs_in_block: usize,
+    /// The proof of the receipt in the block.
+    pub block_receipt_proof: Bytes,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{receipt::StorageChange, Receipt};
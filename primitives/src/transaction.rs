@@ -5,13 +5,15 @@
 use crate::{bytes::Bytes, hash::keccak};
 use cfx_types::{Address, BigEndianHash, H160, H256, U256};
 use keylib::{
-    self, public_to_address, recover, verify_public, Public, Secret, Signature,
+    self, public_to_address, recover, recover_batch, verify_public, Public,
+    Secret, Signature,
 };
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use rlp::{self, Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use rlp_derive::{RlpDecodable, RlpEncodable};
 use serde::{Deserialize, Serialize};
-use std::{error, fmt, ops::Deref};
+use std::{convert::TryFrom, error, fmt, ops::Deref};
+use threadpool::ThreadPool;
 use unexpected::OutOfBounds;
 
 /// Fake address for unsigned transactions.
@@ -87,8 +89,20 @@ pub enum TransactionError {
     InvalidGasLimit(OutOfBounds<U256>),
     /// Signature error
     InvalidSignature(String),
-    /// Transaction too big
-    TooBig,
+    /// Transaction's gas is above currently set maximal gas.
+    TooMuchGas {
+        /// Maximal allowed gas
+        maximal: U256,
+        /// Transaction gas
+        got: U256,
+    },
+    /// Transaction's data is larger than currently set maximal size.
+    DataTooLarge {
+        /// Maximal allowed size of data in bytes
+        maximal: usize,
+        /// Size of transaction data in bytes
+        got: usize,
+    },
     /// Invalid RLP encoding
     InvalidRlp(String),
 }
@@ -147,7 +161,13 @@ impl fmt::Display for TransactionError {
             InvalidSignature(ref err) => {
                 format!("Transaction has invalid signature: {}.", err)
             }
-            TooBig => "Transaction too big".into(),
+            TooMuchGas { maximal, got } => {
+                format!("Too much gas. Max={}, Given={}", maximal, got)
+            }
+            DataTooLarge { maximal, got } => format!(
+                "Transaction data too large. Max={} bytes, Given={} bytes",
+                maximal, got
+            ),
             InvalidRlp(ref err) => {
                 format!("Transaction has invalid RLP structure: {}.", err)
             }
@@ -193,16 +213,181 @@ impl Encodable for Action {
     }
 }
 
+/// An account and its storage keys accessed by a transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<Vec<u8>>,
+}
+
+/// The accounts and storage keys accessed by a transaction, sorted by
+/// address.
+pub type AccessList = Vec<AccessListItem>;
+
+/// A chain_id activated from an epoch by a hardfork.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    RlpEncodable,
+    RlpDecodable,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainIdTransition {
+    pub activation_epoch: u64,
+    pub chain_id: u32,
+}
+
 /// The parameters needed to determine the chain_id based on epoch_number.
-#[derive(Clone, Debug, Eq, RlpEncodable, RlpDecodable, PartialEq, Default)]
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "UncheckedChainIdParams")]
 pub struct ChainIdParams {
-    /// Preconfigured chain_id.
+    /// The chain_id since genesis.
     pub chain_id: u32,
+    /// The transitions of the chain_id, ordered by their activation epochs.
+    pub schedule: Vec<ChainIdTransition>,
+}
+
+/// The deserialized `ChainIdParams` before their schedule is validated.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UncheckedChainIdParams {
+    chain_id: u32,
+    #[serde(default)]
+    schedule: Vec<ChainIdTransition>,
+}
+
+impl TryFrom<UncheckedChainIdParams> for ChainIdParams {
+    type Error = String;
+
+    fn try_from(params: UncheckedChainIdParams) -> Result<Self, String> {
+        ChainIdParams::with_schedule(params.chain_id, params.schedule)
+    }
 }
 
 impl ChainIdParams {
+    pub fn new(chain_id: u32) -> Self {
+        ChainIdParams {
+            chain_id,
+            schedule: Vec::new(),
+        }
+    }
+
+    pub fn with_schedule(
+        chain_id: u32, schedule: Vec<ChainIdTransition>,
+    ) -> Result<Self, String> {
+        let params = ChainIdParams { chain_id, schedule };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Check that the activation epochs are positive and strictly increasing.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut last_epoch = 0;
+        for transition in &self.schedule {
+            if transition.activation_epoch <= last_epoch {
+                return Err(format!(
+                    "The chain_id transition at epoch {} is not after epoch {}",
+                    transition.activation_epoch, last_epoch
+                ));
+            }
+            last_epoch = transition.activation_epoch;
+        }
+        Ok(())
+    }
+
     /// The function return the chain_id with given parameters
-    pub fn get_chain_id(&self, _epoch_number: u64) -> u32 { self.chain_id }
+    pub fn get_chain_id(&self, epoch_number: u64) -> u32 {
+        self.schedule
+            .iter()
+            .rev()
+            .find(|transition| transition.activation_epoch <= epoch_number)
+            .map_or(self.chain_id, |transition| transition.chain_id)
+    }
+
+    /// Whether `chain_id` is the chain_id of `epoch_number` or of a later
+    /// epoch, e.g. of an epoch in which a block at height `epoch_number` may
+    /// be executed.
+    pub fn is_chain_id_since(&self, chain_id: u32, epoch_number: u64) -> bool {
+        self.get_chain_id(epoch_number) == chain_id
+            || self.schedule.iter().any(|transition| {
+                transition.activation_epoch > epoch_number
+                    && transition.chain_id == chain_id
+            })
+    }
+}
+
+/// The parameters without a schedule are encoded as before, so that they are
+/// compatible with the peers not supporting the schedule.
+impl Encodable for ChainIdParams {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        if self.schedule.is_empty() {
+            s.begin_list(1).append(&self.chain_id);
+        } else {
+            s.begin_list(2)
+                .append(&self.chain_id)
+                .append_list(&self.schedule);
+        }
+    }
+}
+
+impl Decodable for ChainIdParams {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let params = match rlp.item_count()? {
+            1 => ChainIdParams::new(rlp.val_at(0)?),
+            2 => ChainIdParams {
+                chain_id: rlp.val_at(0)?,
+                schedule: rlp.list_at(1)?,
+            },
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+        params
+            .validate()
+            .map_err(|_| DecoderError::Custom("Invalid chain_id schedule"))?;
+        Ok(params)
+    }
+}
+
+/// The rules which the transaction signatures must follow. The rules are
+/// part of the spec, so that a hardfork can tighten them from an epoch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SignaturePolicy {
+    /// Require `s` in the lower half of the curve order, since the signature
+    /// with the high `s` is a malleated copy of the low `s` one.
+    pub require_low_s: bool,
+    /// Reject the signatures with a zero `r` or `s`.
+    pub reject_zero_rs: bool,
+    /// Require `r` and `s` to be less than the curve order, and `v` to be 0
+    /// or 1.
+    pub require_canonical: bool,
+}
+
+impl SignaturePolicy {
+    /// The rules since genesis, which only require a low `s`.
+    pub fn legacy() -> Self {
+        SignaturePolicy {
+            require_low_s: true,
+            reject_zero_rs: false,
+            require_canonical: false,
+        }
+    }
+
+    /// All the rules.
+    pub fn strict() -> Self {
+        SignaturePolicy {
+            require_low_s: true,
+            reject_zero_rs: true,
+            require_canonical: true,
+        }
+    }
+}
+
+impl Default for SignaturePolicy {
+    fn default() -> Self { SignaturePolicy::legacy() }
 }
 
 #[derive(
@@ -411,6 +596,22 @@ impl TransactionWithSignature {
         }
     }
 
+    /// Checks the signature against `policy`.
+    pub fn verify(
+        &self, policy: &SignaturePolicy,
+    ) -> Result<(), keylib::Error> {
+        if policy.require_low_s {
+            self.check_low_s()?;
+        }
+        if policy.reject_zero_rs && (self.r.is_zero() || self.s.is_zero()) {
+            return Err(keylib::Error::InvalidSignature);
+        }
+        if policy.require_canonical && !self.signature().is_valid() {
+            return Err(keylib::Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
     pub fn hash(&self) -> H256 { self.hash }
 
     /// Recovers the public key of the sender.
@@ -418,6 +619,22 @@ impl TransactionWithSignature {
         Ok(recover(&self.signature(), &self.unsigned.hash())?)
     }
 
+    /// Recovers the public keys of the senders of `transactions` on the
+    /// `workers` in the same order. Each unsigned transaction is hashed once,
+    /// on the calling thread.
+    pub fn recover_public_batch<'a, I>(
+        transactions: I, workers: &ThreadPool,
+    ) -> Vec<Result<Public, keylib::Error>>
+    where I: IntoIterator<Item = &'a TransactionWithSignature> {
+        recover_batch(
+            transactions
+                .into_iter()
+                .map(|tx| (tx.signature(), tx.unsigned.hash()))
+                .collect(),
+            workers,
+        )
+    }
+
     pub fn rlp_size(&self) -> usize {
         self.rlp_size.unwrap_or_else(|| self.rlp_bytes().len())
     }
@@ -544,13 +761,14 @@ impl MallocSizeOf for SignedTransaction {
 #[cfg(test)]
 mod tests {
     use crate::{
-        transaction::UNSIGNED_SENDER, Action, ChainIdParams, SignedTransaction,
-        Transaction, TransactionWithSignature,
-        TransactionWithSignatureSerializePart,
+        transaction::UNSIGNED_SENDER, Action, ChainIdParams, ChainIdTransition,
+        SignaturePolicy, SignedTransaction, Transaction,
+        TransactionWithSignature, TransactionWithSignatureSerializePart,
     };
     use cfx_types::{Address, H160, H256, H512, U256};
     use core::str::FromStr;
     use keylib::{public_to_address, Signature};
+    use rlp::{Decodable, Encodable, Rlp, RlpStream};
     #[test]
     fn test_action() {
         let action_create = Action::Create;
@@ -558,8 +776,61 @@ mod tests {
     }
     #[test]
     fn test_chain_id_params() {
-        let chain_id = ChainIdParams { chain_id: 0 };
-        assert_eq!(chain_id.get_chain_id(1), chain_id.chain_id)
+        let chain_id = ChainIdParams::new(0);
+        assert_eq!(chain_id.get_chain_id(1), chain_id.chain_id);
+        assert_eq!(
+            ChainIdParams::decode(&Rlp::new(&chain_id.rlp_bytes())),
+            Ok(chain_id)
+        );
+
+        let schedule = vec![
+            ChainIdTransition {
+                activation_epoch: 100,
+                chain_id: 1,
+            },
+            ChainIdTransition {
+                activation_epoch: 200,
+                chain_id: 2,
+            },
+        ];
+        let chain_id =
+            ChainIdParams::with_schedule(0, schedule.clone()).unwrap();
+        assert_eq!(chain_id.get_chain_id(99), 0);
+        assert_eq!(chain_id.get_chain_id(100), 1);
+        assert_eq!(chain_id.get_chain_id(199), 1);
+        assert_eq!(chain_id.get_chain_id(200), 2);
+        assert!(chain_id.is_chain_id_since(2, 150));
+        assert!(!chain_id.is_chain_id_since(0, 150));
+        assert_eq!(
+            ChainIdParams::decode(&Rlp::new(&chain_id.rlp_bytes())),
+            Ok(chain_id.clone())
+        );
+        let json = serde_json::to_string(&chain_id).unwrap();
+        assert_eq!(
+            json,
+            r#"{"chainId":0,"schedule":[{"activationEpoch":100,"chainId":1},"#
+                .to_owned()
+                + r#"{"activationEpoch":200,"chainId":2}]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ChainIdParams>(&json).unwrap(),
+            chain_id
+        );
+        assert_eq!(
+            serde_json::from_str::<ChainIdParams>(r#"{"chainId":1}"#).unwrap(),
+            ChainIdParams::new(1)
+        );
+
+        let unordered = vec![schedule[1], schedule[0]];
+        assert!(ChainIdParams::with_schedule(0, unordered.clone()).is_err());
+        let json =
+            r#"{"chainId":0,"schedule":[{"activationEpoch":200,"chainId":2},"#
+                .to_owned()
+                + r#"{"activationEpoch":100,"chainId":1}]}"#;
+        assert!(serde_json::from_str::<ChainIdParams>(&json).is_err());
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&0u32).append_list(&unordered);
+        assert!(ChainIdParams::decode(&Rlp::new(&stream.out())).is_err());
     }
     #[test]
     fn test_transaction() {
@@ -668,6 +939,46 @@ mod tests {
         assert_eq!(trans_with_sig.check_low_s().is_ok(), true);
     }
     #[test]
+    fn test_signature_policy() {
+        let with_signature =
+            |r: U256, s: U256, v: u8| TransactionWithSignature {
+                transaction: TransactionWithSignatureSerializePart {
+                    unsigned: Default::default(),
+                    v,
+                    r,
+                    s,
+                },
+                hash: H256::zero(),
+                rlp_size: None,
+            };
+        let order = U256::from_str(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        )
+        .unwrap();
+        let legacy = SignaturePolicy::legacy();
+        let strict = SignaturePolicy::strict();
+
+        let valid = with_signature(U256::one(), U256::one(), 1);
+        assert!(valid.verify(&legacy).is_ok());
+        assert!(valid.verify(&strict).is_ok());
+
+        let high_s = with_signature(U256::one(), order - 1, 0);
+        assert!(high_s.verify(&legacy).is_err());
+        assert!(high_s.verify(&strict).is_err());
+
+        let zero_r = with_signature(U256::zero(), U256::one(), 0);
+        assert!(zero_r.verify(&legacy).is_ok());
+        assert!(zero_r.verify(&strict).is_err());
+
+        let high_r = with_signature(order, U256::one(), 0);
+        assert!(high_r.verify(&legacy).is_ok());
+        assert!(high_r.verify(&strict).is_err());
+
+        let high_v = with_signature(U256::one(), U256::one(), 2);
+        assert!(high_v.verify(&legacy).is_ok());
+        assert!(high_v.verify(&strict).is_err());
+    }
+    #[test]
     fn test_signed_trans() {
         let unsigned_trans = SignedTransaction {
             transaction: TransactionWithSignature {
@@ -20,19 +20,27 @@
 
 extern crate cfxcore_accounts;
 
-use super::helpers::{password_from_file, password_prompt};
+use super::helpers::{
+    input_password, mnemonic_from_file, mnemonic_prompt, password_from_file,
+    password_prompt,
+};
+use cfx_types::Address;
+use cfxkey::mnemonic;
 use cfxstore::{
     accounts_dir::RootDiskDirectory, import_account, import_accounts,
 };
 use clap;
 use client::accounts::{account_provider, keys_dir, keys_path};
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
 #[derive(Debug, PartialEq)]
 pub enum AccountCmd {
     New(NewAccount),
     List(ListAccounts),
     Import(ImportAccounts),
+    Export(ExportAccount),
+    NewMnemonic(NewMnemonic),
+    FromMnemonic(FromMnemonic),
 }
 
 #[derive(Debug, PartialEq)]
@@ -54,7 +62,7 @@ pub struct NewAccount {
 impl NewAccount {
     pub fn new(matches: &clap::ArgMatches) -> Self {
         let iterations: u32 = matches
-            .value_of("key-iterations")
+            .value_of("keys-iterations")
             .unwrap_or("0")
             .parse()
             .unwrap();
@@ -87,11 +95,85 @@ impl ImportAccounts {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ExportAccount {
+    pub address: String,
+    pub path: Option<String>,
+    pub password_file: Option<String>,
+}
+
+impl ExportAccount {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let address = matches
+            .value_of("address")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        let password_file = matches.value_of("password").map(|x| x.to_string());
+        Self {
+            address,
+            path: None,
+            password_file,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewMnemonic {
+    pub words: usize,
+}
+
+impl NewMnemonic {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let words: usize =
+            matches.value_of("words").unwrap_or("12").parse().unwrap();
+        Self { words }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FromMnemonic {
+    pub iterations: u32,
+    pub path: Option<String>,
+    pub mnemonic_file: Option<String>,
+    pub passphrase_file: Option<String>,
+    pub password_file: Option<String>,
+    pub start: u32,
+    pub count: u32,
+}
+
+impl FromMnemonic {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let iterations: u32 = matches
+            .value_of("keys-iterations")
+            .unwrap_or("0")
+            .parse()
+            .unwrap();
+        let start: u32 =
+            matches.value_of("start").unwrap_or("0").parse().unwrap();
+        let count: u32 =
+            matches.value_of("count").unwrap_or("1").parse().unwrap();
+        Self {
+            iterations,
+            path: None,
+            mnemonic_file: matches.value_of("mnemonic").map(|x| x.to_string()),
+            passphrase_file: matches
+                .value_of("passphrase")
+                .map(|x| x.to_string()),
+            password_file: matches.value_of("password").map(|x| x.to_string()),
+            start,
+            count,
+        }
+    }
+}
+
 pub fn execute(cmd: AccountCmd) -> Result<String, String> {
     match cmd {
         AccountCmd::New(new_cmd) => new(new_cmd),
         AccountCmd::List(list_cmd) => list(list_cmd),
         AccountCmd::Import(import_cmd) => import(import_cmd),
+        AccountCmd::Export(export_cmd) => export(export_cmd),
+        AccountCmd::NewMnemonic(new_cmd) => new_mnemonic(new_cmd),
+        AccountCmd::FromMnemonic(from_cmd) => from_mnemonic(from_cmd),
     }
 }
 
@@ -153,3 +235,68 @@ fn import(import_cmd: ImportAccounts) -> Result<String, String> {
 
     Ok(format!("{} account(s) imported", imported))
 }
+
+fn export(export_cmd: ExportAccount) -> Result<String, String> {
+    let address =
+        Address::from_str(export_cmd.address.trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid address: {}", e))?;
+    let password = match export_cmd.password_file {
+        Some(file) => password_from_file(file)?,
+        None => input_password()?,
+    };
+
+    let acc_provider = account_provider(
+        export_cmd.path,
+        None, /* sstore_iterations */
+        None, /* refresh_time */
+    )?;
+
+    let key_file = acc_provider
+        .export_account(&address, password)
+        .map_err(|e| format!("Could not export account: {}", e))?;
+    serde_json::to_string_pretty(&key_file)
+        .map_err(|e| format!("Could not serialize account: {}", e))
+}
+
+fn new_mnemonic(new_cmd: NewMnemonic) -> Result<String, String> {
+    mnemonic::generate_mnemonic(new_cmd.words)
+        .map_err(|e| format!("Could not generate mnemonic: {}", e))
+}
+
+fn from_mnemonic(from_cmd: FromMnemonic) -> Result<String, String> {
+    let phrase = match from_cmd.mnemonic_file {
+        Some(file) => mnemonic_from_file(file)?,
+        None => mnemonic_prompt()?,
+    };
+    let passphrase = match from_cmd.passphrase_file {
+        Some(file) => password_from_file(file)?.as_str().to_owned(),
+        None => String::new(),
+    };
+    let password = match from_cmd.password_file {
+        Some(file) => password_from_file(file)?,
+        None => password_prompt()?,
+    };
+
+    let acc_provider = account_provider(
+        from_cmd.path,
+        Some(from_cmd.iterations), /* sstore_iterations */
+        None,                      /* refresh_time */
+    )?;
+
+    let accounts = acc_provider
+        .insert_mnemonic_accounts(
+            &phrase,
+            &passphrase,
+            from_cmd.start,
+            from_cmd.count,
+            &password,
+        )
+        .map_err(|e| format!("Could not derive accounts: {}", e))?;
+    let result = accounts
+        .into_iter()
+        .map(|a| format!("0x{:x}", a))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Ok(result)
+}
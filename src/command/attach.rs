@@ -0,0 +1,256 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! An interactive console attached to a running node through its HTTP, IPC or
+//! WebSocket RPC. The console evaluates Rhai scripts, in which the RPCs are
+//! called as functions, e.g. `cfx_getBalance("0x1...")`, or with
+//! `rpc("method", [params])` for the methods without a binding.
+
+use clap;
+use client::accounts::default_data_path;
+use dir::helpers::replace_home;
+use futures::Future;
+use jsonrpc_core::{Params, Value};
+use jsonrpc_core_client::{
+    transports::{http, ipc, ws},
+    RawClient,
+};
+use rhai::{
+    serde::{from_dynamic, to_dynamic},
+    Array, Dynamic, Engine, EvalAltResult, ImmutableString, RegisterResultFn,
+    Scope,
+};
+use runtime::Runtime;
+use rustyline::{error::ReadlineError, Editor};
+use std::{fs, rc::Rc, sync::mpsc::channel};
+use tokio::reactor::Handle;
+use url::Url;
+
+/// The RPCs bound as functions in the console, with up to 3 parameters.
+const BOUND_METHODS: &[&str] = &[
+    "cfx_clientVersion",
+    "cfx_getStatus",
+    "cfx_epochNumber",
+    "cfx_gasPrice",
+    "cfx_getBalance",
+    "cfx_getStakingBalance",
+    "cfx_getNextNonce",
+    "cfx_getCode",
+    "cfx_getStorageAt",
+    "cfx_getAccount",
+    "cfx_getBestBlockHash",
+    "cfx_getBlockByHash",
+    "cfx_getBlockByEpochNumber",
+    "cfx_getBlocksByEpoch",
+    "cfx_getTransactionByHash",
+    "cfx_getTransactionReceipt",
+    "cfx_getLogs",
+    "cfx_call",
+    "cfx_estimateGasAndCollateral",
+    "cfx_sendRawTransaction",
+    "cfx_getConfirmationRiskByHash",
+    "txpool_status",
+    "txpool_inspect",
+    "txpool_content",
+    "net_node",
+    "net_sessions",
+    "net_throttling",
+    "log_levels",
+    "log_setLevel",
+    "reload_config",
+];
+
+const PROMPT: &str = "> ";
+
+/// The scheme of the urls of IPC socket paths, e.g. `ipc:///tmp/conflux.ipc`.
+const IPC_SCHEME: &str = "ipc://";
+
+#[derive(Debug, PartialEq)]
+pub struct AttachCmd {
+    pub url: String,
+    /// The script to run instead of the interactive console.
+    pub exec: Option<String>,
+    /// The script files to run before the interactive console.
+    pub preload: Vec<String>,
+}
+
+impl AttachCmd {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let url = matches
+            .value_of("url")
+            .expect("CLI argument has default value; qed")
+            .to_string();
+        let exec = matches.value_of("exec").map(Into::into);
+        let preload = matches
+            .values_of("preload")
+            .map(|files| files.map(Into::into).collect())
+            .unwrap_or_default();
+        Self { url, exec, preload }
+    }
+}
+
+pub fn execute(cmd: AttachCmd) -> Result<String, String> {
+    let connection = Rc::new(Connection::connect(&cmd.url)?);
+    let engine = new_engine(&connection);
+    let mut scope = Scope::new();
+
+    for file in &cmd.preload {
+        let script = fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {:?}", file, e))?;
+        eval(&engine, &mut scope, &script)
+            .map_err(|e| format!("Failed to run {}: {}", file, e))?;
+    }
+    if let Some(script) = &cmd.exec {
+        return eval(&engine, &mut scope, script)
+            .map(|result| display(&result));
+    }
+
+    println!("Attached to {}, enter `exit` or Ctrl-D to quit.", cmd.url);
+    let history_file =
+        replace_home(&default_data_path(), "$BASE/console_history");
+    let mut editor = Editor::<()>::new();
+    // There is no history file on the first run.
+    let _ = editor.load_history(&history_file);
+    loop {
+        match editor.readline(PROMPT) {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line);
+                if line == "exit" {
+                    break;
+                }
+                match eval(&engine, &mut scope, line) {
+                    Ok(result) if result.is::<()>() => {}
+                    Ok(result) => println!("{}", display(&result)),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            // Ctrl-C clears the current line.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("Failed to read line: {:?}", e)),
+        }
+    }
+    if let Err(e) = editor.save_history(&history_file) {
+        eprintln!("Failed to save history to {}: {:?}", history_file, e);
+    }
+    Ok(String::new())
+}
+
+struct Connection {
+    runtime: Runtime,
+    client: RawClient,
+}
+
+impl Connection {
+    fn connect(url: &str) -> Result<Self, String> {
+        let runtime = Runtime::with_default_thread_count();
+        let client = if url.starts_with("ws://") || url.starts_with("wss://") {
+            let parsed_url = Url::parse(url)
+                .map_err(|e| format!("Invalid url {}: {:?}", url, e))?;
+            wait(&runtime, ws::connect::<RawClient>(&parsed_url))
+        } else if url.starts_with(IPC_SCHEME) {
+            let path = &url[IPC_SCHEME.len()..];
+            // The connection is registered to the reactor of the runtime when
+            // it's polled.
+            let connect =
+                ipc::connect::<_, RawClient>(path, &Handle::default())
+                    .map_err(|e| {
+                        format!("Failed to connect to {}: {:?}", url, e)
+                    })?;
+            wait(&runtime, connect)
+        } else {
+            wait(&runtime, http::connect::<RawClient>(url))
+        }
+        .map_err(|e| format!("Failed to connect to {}: {:?}", url, e))?;
+        Ok(Connection { runtime, client })
+    }
+
+    fn call(
+        &self, method: &str, params: Vec<Dynamic>,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let params = params
+            .iter()
+            .map(from_dynamic::<Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = wait(
+            &self.runtime,
+            self.client.call_method(method, Params::Array(params)),
+        )
+        .map_err(|e| format!("{}: {:?}", method, e))?;
+        to_dynamic(result)
+    }
+}
+
+/// Run `future` on `runtime` and wait for its result.
+fn wait<F>(runtime: &Runtime, future: F) -> Result<F::Item, F::Error>
+where
+    F: Future + Send + 'static,
+    F::Item: Send + 'static,
+    F::Error: Send + 'static,
+{
+    let (sender, receiver) = channel();
+    runtime.executor().spawn(future.then(move |result| {
+        sender.send(result).expect("channel should work fine");
+        Ok(())
+    }));
+    receiver.recv().expect("channel should work fine")
+}
+
+fn new_engine(connection: &Rc<Connection>) -> Engine {
+    let mut engine = Engine::new();
+
+    let conn = connection.clone();
+    engine.register_result_fn("rpc", move |method: ImmutableString| {
+        conn.call(&method, vec![])
+    });
+    let conn = connection.clone();
+    engine.register_result_fn(
+        "rpc",
+        move |method: ImmutableString, params: Array| {
+            conn.call(&method, params)
+        },
+    );
+
+    for &method in BOUND_METHODS {
+        let conn = connection.clone();
+        engine.register_result_fn(method, move || conn.call(method, vec![]));
+        let conn = connection.clone();
+        engine.register_result_fn(method, move |a: Dynamic| {
+            conn.call(method, vec![a])
+        });
+        let conn = connection.clone();
+        engine.register_result_fn(method, move |a: Dynamic, b: Dynamic| {
+            conn.call(method, vec![a, b])
+        });
+        let conn = connection.clone();
+        engine.register_result_fn(
+            method,
+            move |a: Dynamic, b: Dynamic, c: Dynamic| {
+                conn.call(method, vec![a, b, c])
+            },
+        );
+    }
+    engine
+}
+
+fn eval(
+    engine: &Engine, scope: &mut Scope, script: &str,
+) -> Result<Dynamic, String> {
+    engine
+        .eval_with_scope::<Dynamic>(scope, script)
+        .map_err(|e| e.to_string())
+}
+
+/// Print the objects and arrays returned by the RPCs as JSON.
+fn display(value: &Dynamic) -> String {
+    match from_dynamic::<Value>(value) {
+        Ok(Value::String(s)) => s,
+        Ok(json) => format!("{:#}", json),
+        Err(_) => value.to_string(),
+    }
+}
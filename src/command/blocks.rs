@@ -0,0 +1,73 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Export the blocks of the local node to a file, and import the blocks in a
+//! file to the local node. The node must not be running.
+
+use clap;
+use client::{
+    block_file::{export_blocks, import_blocks},
+    configuration::Configuration,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum BlocksCmd {
+    Export(ExportBlocks),
+    Import(ImportBlocks),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportBlocks {
+    pub file: String,
+    pub from: u64,
+    pub to: Option<u64>,
+}
+
+impl ExportBlocks {
+    pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
+        let file = matches
+            .value_of("file")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        let from = matches
+            .value_of("from")
+            .expect("CLI argument has default value; qed")
+            .parse()
+            .map_err(|e| format!("Invalid --from: {}", e))?;
+        let to = match matches.value_of("to") {
+            Some(to) => {
+                Some(to.parse().map_err(|e| format!("Invalid --to: {}", e))?)
+            }
+            None => None,
+        };
+        Ok(Self { file, from, to })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportBlocks {
+    pub file: String,
+}
+
+impl ImportBlocks {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let file = matches
+            .value_of("file")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        Self { file }
+    }
+}
+
+pub fn execute(cmd: BlocksCmd, conf: Configuration) -> Result<String, String> {
+    match cmd {
+        BlocksCmd::Export(export_cmd) => export_blocks(
+            conf,
+            export_cmd.from,
+            export_cmd.to,
+            &export_cmd.file,
+        ),
+        BlocksCmd::Import(import_cmd) => import_blocks(conf, &import_cmd.file),
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Run a cosigner of the threshold signer, and generate the keys shared by the
+//! cosigners configured for the node.
+
+use super::helpers::{input_password, password_from_file, password_prompt};
+use cfx_types::Public;
+use clap;
+use client::{
+    configuration::Configuration,
+    threshold_cosigner::{KeyFile, ThresholdCosigner},
+    threshold_signer::ThresholdSigner,
+};
+use ctrlc::CtrlC;
+use parking_lot::{Condvar, Mutex};
+use serde_derive::Deserialize;
+use std::{fs, net::SocketAddr, sync::Arc};
+
+#[derive(Debug, PartialEq)]
+pub enum CosignerCmd {
+    New(NewCosigner),
+    Run(RunCosigner),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewCosigner {
+    pub key_file: String,
+    pub iterations: u32,
+    pub password_file: Option<String>,
+}
+
+impl NewCosigner {
+    pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
+        let key_file = matches
+            .value_of("key-file")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        let iterations = matches
+            .value_of("keys-iterations")
+            .unwrap_or("10240")
+            .parse()
+            .map_err(|e| format!("Invalid --keys-iterations: {}", e))?;
+        let password_file = matches.value_of("password").map(|x| x.to_string());
+        Ok(Self {
+            key_file,
+            iterations,
+            password_file,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunCosigner {
+    pub config: String,
+    pub password_file: Option<String>,
+}
+
+impl RunCosigner {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let config = matches
+            .value_of("config")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        let password_file = matches.value_of("password").map(|x| x.to_string());
+        Self {
+            config,
+            password_file,
+        }
+    }
+}
+
+/// The configuration file of `cosigner run`.
+#[derive(Deserialize)]
+struct CosignerFile {
+    index: usize,
+    peers: Vec<Public>,
+    auth_tokens: Vec<String>,
+    listen_address: SocketAddr,
+    key_file: String,
+    #[serde(default = "default_keys_iterations")]
+    keys_iterations: u32,
+}
+
+fn default_keys_iterations() -> u32 { 10240 }
+
+pub fn execute(cmd: CosignerCmd) -> Result<String, String> {
+    match cmd {
+        CosignerCmd::New(new_cmd) => new(new_cmd),
+        CosignerCmd::Run(run_cmd) => run(run_cmd),
+    }
+}
+
+/// Generate a key with the cosigners in the configuration of the node.
+pub fn keygen(conf: Configuration) -> Result<String, String> {
+    let config = conf.threshold_signer_config()?.ok_or_else(|| {
+        String::from("threshold_signer_cosigners is not configured")
+    })?;
+    let address = ThresholdSigner::new(config).generate_key()?;
+    Ok(format!("0x{:x}", address))
+}
+
+fn new(new_cmd: NewCosigner) -> Result<String, String> {
+    let password = match new_cmd.password_file {
+        Some(file) => password_from_file(file)?,
+        None => password_prompt()?,
+    };
+    let public = KeyFile::new(new_cmd.key_file, password, new_cmd.iterations)
+        .create()
+        .map_err(|e| format!("Could not create the key file: {}", e))?;
+    Ok(format!("0x{:x}", public))
+}
+
+fn run(run_cmd: RunCosigner) -> Result<String, String> {
+    let config: CosignerFile = fs::read_to_string(&run_cmd.config)
+        .map_err(|e| format!("Could not read {}: {}", run_cmd.config, e))
+        .and_then(|s| {
+            toml::from_str(&s)
+                .map_err(|e| format!("Invalid {}: {}", run_cmd.config, e))
+        })?;
+    let password = match run_cmd.password_file {
+        Some(file) => password_from_file(file)?,
+        None => input_password()?,
+    };
+    let key_file =
+        KeyFile::new(config.key_file, password, config.keys_iterations);
+    let cosigner = ThresholdCosigner::from_key_file(
+        config.index,
+        config.peers,
+        config.auth_tokens,
+        key_file,
+    )?;
+    let server = Arc::new(cosigner)
+        .start_http(&config.listen_address)
+        .map_err(|e| format!("Could not start the cosigner: {}", e))?;
+    println!(
+        "Cosigner {} listening on {}",
+        config.index, config.listen_address
+    );
+
+    let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    CtrlC::set_handler({
+        let exit = exit.clone();
+        move || {
+            *exit.0.lock() = true;
+            exit.1.notify_all();
+        }
+    });
+    let mut stopped = exit.0.lock();
+    if !*stopped {
+        exit.1.wait(&mut stopped);
+    }
+    server.close();
+    Ok("Cosigner stopped".into())
+}
@@ -0,0 +1,37 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Maintain the database of the local node. The node must not be running.
+
+use clap;
+use client::{configuration::Configuration, db_rollback::rollback_database};
+
+#[derive(Debug, PartialEq)]
+pub enum DbCmd {
+    Rollback(RollbackDb),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RollbackDb {
+    pub epoch: u64,
+}
+
+impl RollbackDb {
+    pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
+        let epoch = matches
+            .value_of("epoch")
+            .expect("CLI argument is required; qed")
+            .parse()
+            .map_err(|e| format!("Invalid --epoch: {}", e))?;
+        Ok(Self { epoch })
+    }
+}
+
+pub fn execute(cmd: DbCmd, conf: Configuration) -> Result<String, String> {
+    match cmd {
+        DbCmd::Rollback(rollback_cmd) => {
+            rollback_database(conf, rollback_cmd.epoch)
+        }
+    }
+}
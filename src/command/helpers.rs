@@ -21,7 +21,7 @@
 use cfxkey::Password;
 use rpassword::read_password;
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader, Write},
 };
 
@@ -80,6 +80,30 @@ pub fn password_from_file(path: String) -> Result<Password, String> {
         .ok_or_else(|| "Password file seems to be empty.".to_owned())
 }
 
+/// Prompts user asking for a mnemonic phrase, which is not echoed.
+pub fn mnemonic_prompt() -> Result<String, String> {
+    print!("Type mnemonic phrase: ");
+    flush_stdout();
+
+    let phrase = read_password().map_err(|_| {
+        "Unable to ask for mnemonic on non-interactive terminal.".to_owned()
+    })?;
+    Ok(normalize_mnemonic(&phrase))
+}
+
+/// Read a mnemonic phrase from file.
+pub fn mnemonic_from_file(path: String) -> Result<String, String> {
+    let phrase = fs::read_to_string(&path).map_err(|_| {
+        format!("{} Unable to read mnemonic file. Ensure it exists and permissions are correct.", path)
+    })?;
+    Ok(normalize_mnemonic(&phrase))
+}
+
+/// Separate the words of a mnemonic phrase by single spaces.
+fn normalize_mnemonic(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// Reads passwords from files. Treats each line as a separate password.
 pub fn passwords_from_files(files: &[String]) -> Result<Vec<Password>, String> {
     let passwords = files.iter().map(|filename| {
@@ -3,5 +3,10 @@
 // See http://www.gnu.org/licenses/
 
 pub mod account;
+pub mod attach;
+pub mod blocks;
+pub mod cosigner;
+pub mod db;
 pub mod helpers;
 pub mod rpc;
+pub mod tx;
@@ -0,0 +1,225 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Sign transactions on an offline machine, and send the signed transactions
+//! through the RPC of a node.
+
+use super::{
+    helpers::{input_password, mnemonic_from_file, password_from_file},
+    rpc::RpcCommand,
+};
+use cfx_types::{Address, U256};
+use cfxkey::{mnemonic, KeyPair};
+use clap;
+use client::{
+    accounts::account_provider,
+    rpc::{set_address_format, SendTxRequest},
+};
+use jsonrpc_core::{Params, Value};
+use rustc_hex::ToHex;
+use std::{fs, io};
+
+#[derive(Debug, PartialEq)]
+pub enum TxCmd {
+    Sign(SignTx),
+    Send(SendTx),
+}
+
+/// The key to sign the transaction with.
+#[derive(Debug, PartialEq)]
+pub enum SignKey {
+    /// The account of the sender in the key store.
+    KeyStore { password_file: Option<String> },
+    /// The account at the BIP-44 address `index` derived from a mnemonic. If
+    /// `index` is not given, the account of the sender is searched among the
+    /// first `MNEMONIC_SEARCH_LIMIT` addresses.
+    Mnemonic {
+        mnemonic_file: String,
+        passphrase_file: Option<String>,
+        index: Option<u32>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SignTx {
+    pub tx_file: String,
+    pub path: Option<String>,
+    pub key: SignKey,
+}
+
+/// The number of the BIP-44 addresses to search for the sender if no address
+/// index is given.
+const MNEMONIC_SEARCH_LIMIT: u32 = 100;
+
+impl SignTx {
+    pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
+        let tx_file = matches
+            .value_of("tx")
+            .expect("CLI argument is required; qed")
+            .to_string();
+        let key = match matches.value_of("mnemonic") {
+            Some(mnemonic_file) => SignKey::Mnemonic {
+                mnemonic_file: mnemonic_file.to_string(),
+                passphrase_file: matches
+                    .value_of("passphrase")
+                    .map(|x| x.to_string()),
+                index: match matches.value_of("index") {
+                    Some(index) => Some(
+                        index
+                            .parse()
+                            .map_err(|e| format!("Invalid --index: {}", e))?,
+                    ),
+                    None => None,
+                },
+            },
+            None => SignKey::KeyStore {
+                password_file: matches
+                    .value_of("password")
+                    .map(|x| x.to_string()),
+            },
+        };
+        Ok(Self {
+            tx_file,
+            path: None,
+            key,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SendTx {
+    pub url: String,
+    pub raw: Option<String>,
+}
+
+impl SendTx {
+    pub fn new(matches: &clap::ArgMatches) -> Self {
+        let url = matches
+            .value_of("url")
+            .expect("CLI argument has default value; qed")
+            .to_string();
+        let raw = matches.value_of("raw").map(|x| x.to_string());
+        Self { url, raw }
+    }
+}
+
+pub fn execute(cmd: TxCmd) -> Result<String, String> {
+    match cmd {
+        TxCmd::Sign(sign_cmd) => sign(sign_cmd),
+        TxCmd::Send(send_cmd) => send(send_cmd),
+    }
+}
+
+fn sign(sign_cmd: SignTx) -> Result<String, String> {
+    let json = fs::read_to_string(&sign_cmd.tx_file).map_err(|e| {
+        format!("Could not read transaction {}: {}", sign_cmd.tx_file, e)
+    })?;
+    let json: Value = serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+    // Accept the hex addresses, and the base32 addresses of the chain.
+    let chain_id = json
+        .get("chainId")
+        .and_then(|id| serde_json::from_value::<U256>(id.clone()).ok())
+        .unwrap_or_default();
+    set_address_format(chain_id.low_u64(), true);
+    let request: SendTxRequest = serde_json::from_value(json)
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+
+    let tx = match sign_cmd.key {
+        SignKey::KeyStore { password_file } => {
+            let password = match password_file {
+                Some(file) => password_from_file(file)?,
+                None => input_password()?,
+            };
+            let acc_provider = account_provider(
+                sign_cmd.path,
+                None, /* sstore_iterations */
+                None, /* refresh_time */
+            )?;
+            let from = request.from;
+            let tx = request.into_offline_transaction()?;
+            let sig = acc_provider
+                .sign(from, Some(password), tx.hash())
+                .map_err(|e| format!("Could not sign transaction: {:?}", e))?;
+            tx.with_signature(sig)
+        }
+        SignKey::Mnemonic {
+            mnemonic_file,
+            passphrase_file,
+            index,
+        } => {
+            let phrase = mnemonic_from_file(mnemonic_file)?;
+            let passphrase = match passphrase_file {
+                Some(file) => password_from_file(file)?.as_str().to_owned(),
+                None => String::new(),
+            };
+            let key_pair =
+                derive_sender(&phrase, &passphrase, index, &request.from)?;
+            request.sign_offline(key_pair.secret())?
+        }
+    };
+
+    Ok(format!("0x{}", rlp::encode(&tx).to_hex::<String>()))
+}
+
+/// Derive the key of the `sender` from the mnemonic, at the address `index`
+/// if it's given.
+fn derive_sender(
+    phrase: &str, passphrase: &str, index: Option<u32>, sender: &Address,
+) -> Result<KeyPair, String> {
+    let derive = |index| {
+        mnemonic::derive_from_mnemonic(
+            phrase,
+            passphrase,
+            &mnemonic::bip44_path(0, index),
+        )
+        .map_err(|e| format!("Could not derive account: {}", e))
+    };
+    match index {
+        Some(index) => {
+            let key_pair = derive(index)?;
+            if key_pair.address() != *sender {
+                return Err(format!(
+                    "The account at index {} is {:?}, not the sender {:?}",
+                    index,
+                    key_pair.address(),
+                    sender
+                ));
+            }
+            Ok(key_pair)
+        }
+        None => {
+            for index in 0..MNEMONIC_SEARCH_LIMIT {
+                let key_pair = derive(index)?;
+                if key_pair.address() == *sender {
+                    return Ok(key_pair);
+                }
+            }
+            Err(format!(
+                "The sender {:?} is not among the first {} accounts of the mnemonic, specify its --index",
+                sender, MNEMONIC_SEARCH_LIMIT
+            ))
+        }
+    }
+}
+
+fn send(send_cmd: SendTx) -> Result<String, String> {
+    let raw = match send_cmd.raw {
+        Some(raw) => raw,
+        None => {
+            let mut raw = String::new();
+            io::stdin()
+                .read_line(&mut raw)
+                .map_err(|e| format!("Could not read transaction: {}", e))?;
+            raw
+        }
+    };
+
+    RpcCommand {
+        url: send_cmd.url,
+        method: "cfx_sendRawTransaction".into(),
+        args: Params::Array(vec![Value::String(raw.trim().into())]),
+    }
+    .execute()
+}
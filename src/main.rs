@@ -15,14 +15,20 @@ use client::{
     configuration::Configuration,
     full::FullClient,
     light::LightClient,
+    logging,
 };
-use command::account::{AccountCmd, ImportAccounts, ListAccounts, NewAccount};
-use log::{info, LevelFilter};
-use log4rs::{
-    append::{console::ConsoleAppender, file::FileAppender},
-    config::{Appender, Config as LogConfig, Logger, Root},
-    encode::pattern::PatternEncoder,
+use command::{
+    account::{
+        AccountCmd, ExportAccount, FromMnemonic, ImportAccounts, ListAccounts,
+        NewAccount, NewMnemonic,
+    },
+    attach::AttachCmd,
+    blocks::{BlocksCmd, ExportBlocks, ImportBlocks},
+    cosigner::{CosignerCmd, NewCosigner, RunCosigner},
+    db::{DbCmd, RollbackDb},
+    tx::{SendTx, SignTx, TxCmd},
 };
+use log::info;
 use network::throttling::THROTTLING_SERVICE;
 use parking_lot::{Condvar, Mutex};
 use std::sync::Arc;
@@ -63,69 +69,7 @@ fn main() -> Result<(), String> {
 
     let conf = Configuration::parse(&matches)?;
 
-    // If log_conf is provided, use it for log configuration and ignore
-    // log_file and log_level. Otherwise, set stdout to INFO and set
-    // all our crate log to log_level.
-    match conf.raw_conf.log_conf {
-        Some(ref log_conf) => {
-            log4rs::init_file(log_conf, Default::default()).map_err(|e| {
-                format!(
-                    "failed to initialize log with log config file: {:?}",
-                    e
-                )
-            })?;
-        }
-        None => {
-            let mut conf_builder =
-                LogConfig::builder().appender(Appender::builder().build(
-                    "stdout",
-                    Box::new(ConsoleAppender::builder().build()),
-                ));
-            let mut root_builder = Root::builder().appender("stdout");
-            if let Some(ref log_file) = conf.raw_conf.log_file {
-                conf_builder =
-                    conf_builder.appender(Appender::builder().build(
-                        "logfile",
-                        Box::new(
-                            FileAppender::builder().encoder(
-                                Box::new(
-                                    PatternEncoder::new(
-                                        "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}")))
-                                .build(log_file)
-                                .map_err(
-                                    |e| format!("failed to build log pattern: {:?}", e))?,
-                        ),
-                    ));
-                root_builder = root_builder.appender("logfile");
-            };
-            // Should add new crate names here
-            for crate_name in [
-                "blockgen",
-                "cfxcore",
-                "conflux",
-                "db",
-                "keymgr",
-                "network",
-                "txgen",
-                "client",
-                "primitives",
-                "io",
-            ]
-            .iter()
-            {
-                conf_builder = conf_builder.logger(
-                    Logger::builder()
-                        .build(*crate_name, conf.raw_conf.log_level),
-                );
-            }
-            let log_config = conf_builder
-                .build(root_builder.build(LevelFilter::Info))
-                .map_err(|e| format!("failed to build log config: {:?}", e))?;
-            log4rs::init_config(log_config).map_err(|e| {
-                format!("failed to initialize log with config: {:?}", e)
-            })?;
-        }
-    };
+    logging::initialize(&conf)?;
 
     THROTTLING_SERVICE.write().initialize(
         conf.raw_conf.egress_queue_capacity,
@@ -133,7 +77,41 @@ fn main() -> Result<(), String> {
         conf.raw_conf.egress_max_throttle,
     );
 
+    // block sub-commands, which run on the local database.
+    let blocks_cmd = match matches.subcommand() {
+        ("export-blocks", Some(export_matches)) => {
+            Some(BlocksCmd::Export(ExportBlocks::new(export_matches)?))
+        }
+        ("import-blocks", Some(import_matches)) => {
+            Some(BlocksCmd::Import(ImportBlocks::new(import_matches)))
+        }
+        _ => None,
+    };
+    if let Some(blocks_cmd) = blocks_cmd {
+        println!("{}", command::blocks::execute(blocks_cmd, conf)?);
+        return Ok(());
+    }
+
+    // database sub-commands, which run on the local database.
+    if let ("db", Some(db_matches)) = matches.subcommand() {
+        let db_cmd = match db_matches.subcommand() {
+            ("rollback", Some(rollback_matches)) => {
+                DbCmd::Rollback(RollbackDb::new(rollback_matches)?)
+            }
+            _ => unreachable!(),
+        };
+        println!("{}", command::db::execute(db_cmd, conf)?);
+        return Ok(());
+    }
+
+    // threshold key generation, which runs with the cosigners of the node.
+    if let ("cosigner", Some(_)) = matches.subcommand() {
+        println!("{}", command::cosigner::keygen(conf)?);
+        return Ok(());
+    }
+
     let exit = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_timeout = conf.shutdown_timeout();
 
     info!(
         "
@@ -172,7 +150,7 @@ Current Version: {}
             .map_err(|e| format!("failed to start archive client: {:?}", e))?
     };
 
-    client_methods::run(client_handle, exit);
+    client_methods::run(client_handle, exit, shutdown_timeout);
 
     Ok(())
 }
@@ -194,12 +172,61 @@ fn handle_sub_command(matches: &ArgMatches) -> Result<Option<String>, String> {
             ("import", Some(import_acc_matches)) => {
                 AccountCmd::Import(ImportAccounts::new(import_acc_matches))
             }
+            ("export", Some(export_acc_matches)) => {
+                AccountCmd::Export(ExportAccount::new(export_acc_matches))
+            }
+            ("new-mnemonic", Some(new_mnemonic_matches)) => {
+                AccountCmd::NewMnemonic(NewMnemonic::new(new_mnemonic_matches))
+            }
+            ("from-mnemonic", Some(from_mnemonic_matches)) => {
+                AccountCmd::FromMnemonic(FromMnemonic::new(
+                    from_mnemonic_matches,
+                ))
+            }
             _ => unreachable!(),
         };
         let execute_output = command::account::execute(account_cmd)?;
         return Ok(Some(execute_output));
     }
 
+    // transaction sub-commands
+    if let ("tx", Some(tx_matches)) = matches.subcommand() {
+        let tx_cmd = match tx_matches.subcommand() {
+            ("sign", Some(sign_matches)) => {
+                TxCmd::Sign(SignTx::new(sign_matches)?)
+            }
+            ("send", Some(send_matches)) => {
+                TxCmd::Send(SendTx::new(send_matches))
+            }
+            _ => unreachable!(),
+        };
+        let execute_output = command::tx::execute(tx_cmd)?;
+        return Ok(Some(execute_output));
+    }
+
+    // cosigner sub-commands, except the key generation which needs the
+    // configuration of the node.
+    if let ("cosigner", Some(cosigner_matches)) = matches.subcommand() {
+        let cosigner_cmd = match cosigner_matches.subcommand() {
+            ("new", Some(new_matches)) => {
+                CosignerCmd::New(NewCosigner::new(new_matches)?)
+            }
+            ("run", Some(run_matches)) => {
+                CosignerCmd::Run(RunCosigner::new(run_matches))
+            }
+            _ => return Ok(None),
+        };
+        let execute_output = command::cosigner::execute(cosigner_cmd)?;
+        return Ok(Some(execute_output));
+    }
+
+    // interactive console
+    if let ("attach", Some(attach_matches)) = matches.subcommand() {
+        let execute_output =
+            command::attach::execute(AttachCmd::new(attach_matches))?;
+        return Ok(Some(execute_output));
+    }
+
     // general RPC commands
     let mut subcmd_matches = matches;
     while let Some(m) = subcmd_matches.subcommand().1 {
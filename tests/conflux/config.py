@@ -46,4 +46,6 @@ small_local_test_conf = dict(
     storage_delta_mpts_slab_idle_size = 2_000_000,
     subnet_quota = 0,
     persist_tx_index = "true",
+    # The test framework still uses the hex addresses in the RPCs.
+    jsonrpc_hex_address = "true",
 )
@@ -144,6 +144,24 @@ class RpcClient:
 
         return res
 
+    def get_storage_batch(self, slots: list, epoch: str = None) -> dict:
+        if epoch is None:
+            return self.node.cfx_getStorageBatch(slots)
+        else:
+            return self.node.cfx_getStorageBatch(slots, epoch)
+
+    def get_account(self, addr: str, epoch: str = None) -> dict:
+        if epoch is None:
+            return self.node.cfx_getAccount(addr)
+        else:
+            return self.node.cfx_getAccount(addr, epoch)
+
+    def get_account_batch(self, addrs: list, epoch: str = None) -> dict:
+        if epoch is None:
+            return self.node.cfx_getAccountBatch(addrs)
+        else:
+            return self.node.cfx_getAccountBatch(addrs, epoch)
+
     def get_code(self, address: str, epoch: str = None) -> str:
         if epoch is None:
             code = self.node.cfx_getCode(address)
@@ -334,6 +352,10 @@ class RpcClient:
         assert_is_hash_string(tx_hash)
         return self.node.cfx_getTransactionReceipt(tx_hash)
 
+    def get_transaction_receipt_proof(self, tx_hash: str) -> dict:
+        assert_is_hash_string(tx_hash)
+        return self.node.cfx_getTransactionReceiptProof(tx_hash)
+
     def txpool_status(self) -> (int, int):
         status = self.node.txpool_status()
         return (status["deferred"], status["ready"])
@@ -363,13 +385,16 @@ class RpcClient:
         response = self.node.cfx_estimateGasAndCollateral(tx)
         return int(response['gasUsed'], 0)
 
-    def estimate_collateral(self, contract_addr:str, data_hex:str, sender:str=None, nonce:int=None) -> int:
+    def estimate_collateral(self, contract_addr:str, data_hex:str, sender:str=None, nonce:int=None, epoch:str=None) -> int:
         tx = self.new_tx_for_call(contract_addr, data_hex, sender=sender, nonce=nonce)
         if contract_addr == "0x":
             del tx['to']
         if sender is None:
             del tx['from']
-        response = self.node.cfx_estimateGasAndCollateral(tx)
+        if epoch is None:
+            response = self.node.cfx_estimateGasAndCollateral(tx)
+        else:
+            response = self.node.cfx_estimateGasAndCollateral(tx, epoch)
         return response['storageCollateralized']
 
     def check_balance_against_transaction(self, account_addr: str, contract_addr: str, gas_limit: int, gas_price: int, storage_limit: int) -> dict:
@@ -106,6 +106,42 @@ class TestContract(RpcClient):
         assert_equal(parse_as_int(collateral), 0)
         assert_equal(self.get_collateral_for_storage(addr), 10 ** 18 // 16)
 
+    def test_estimate_collateral_with_pending_tx(self):
+        contract_addr = self.test_contract_deploy()
+        (addr, priv_key) = self.rand_account()
+        tx = self.new_tx(
+            sender=self.GENESIS_ADDR,
+            priv_key=self.GENESIS_PRI_KEY,
+            value=10 ** 20,
+            receiver=addr)
+        assert_equal(self.send_tx(tx, True), tx.hash_hex())
+
+        # send tx to set the storage from 5 to 6, which is not executed yet
+        tx = self.new_contract_tx(
+            receiver=contract_addr,
+            data_hex="0x60fe47b10000000000000000000000000000000000000000000000000000000000000006",
+            sender=addr,
+            priv_key=priv_key,
+            storage_limit=64)
+        assert_equal(self.send_tx(tx), tx.hash_hex())
+
+        # after the pending tx, the sender owns the storage already
+        collateral = self.estimate_collateral(
+            contract_addr=contract_addr,
+            data_hex="0x60fe47b10000000000000000000000000000000000000000000000000000000000000007",
+            sender=addr)
+        assert_equal(parse_as_int(collateral), 0)
+        # while the storage is owned by the genesis account in the latest state
+        collateral = self.estimate_collateral(
+            contract_addr=contract_addr,
+            data_hex="0x60fe47b10000000000000000000000000000000000000000000000000000000000000007",
+            sender=addr,
+            epoch="latest_state")
+        assert_equal(parse_as_int(collateral), 64)
+
+        self.wait_for_receipt(tx.hash_hex())
+        assert_equal(int(self.call(contract_addr, "0x6d4ce63c"), 0), 6)
+
     def test_call_result(self):
         contract_addr = self.test_contract_deploy()
         
@@ -0,0 +1,72 @@
+import eth_utils
+import sys
+sys.path.append("..")
+
+from conflux.rpc import RpcClient
+from conflux.utils import sha3_256
+from test_framework.util import assert_equal, wait_until
+
+# The receipts root of an epoch is committed by the pivot block this many
+# epochs later.
+DEFERRED_STATE_EPOCH_COUNT = 5
+
+
+def blame_vec_root(roots: list) -> str:
+    # as `BlockHeaderBuilder::compute_blame_state_root_vec_root`
+    accumulated = eth_utils.decode_hex(roots[-1])
+    for root in reversed(roots[:-1]):
+        accumulated = sha3_256(eth_utils.decode_hex(root) + accumulated)
+    return eth_utils.encode_hex(accumulated)
+
+
+class TestReceiptProof(RpcClient):
+    def send_tx_in_new_epoch(self) -> (str, int):
+        tx = self.new_tx(receiver=self.rand_addr())
+        assert_equal(self.send_tx(tx), tx.hash_hex())
+        block_hash = self.generate_block(1)
+        assert_equal(self.block_by_hash(block_hash)["transactions"], [tx.hash_hex()])
+        return (tx.hash_hex(), self.epoch_number(self.EPOCH_LATEST_MINED))
+
+    def wait_for_proof(self, tx_hash: str) -> dict:
+        wait_until(lambda: self.get_transaction_receipt_proof(tx_hash) is not None)
+        return self.get_transaction_receipt_proof(tx_hash)
+
+    def assert_witness(self, proof: dict, witness_epoch: int):
+        witness = self.block_by_epoch(self.EPOCH_NUM(witness_epoch))
+        witness_header = eth_utils.decode_hex(proof["witnessHeader"])
+        assert_equal(eth_utils.encode_hex(sha3_256(witness_header)), witness["hash"])
+        return witness
+
+    def test_witness_without_blame(self):
+        (tx_hash, epoch) = self.send_tx_in_new_epoch()
+        self.generate_blocks(DEFERRED_STATE_EPOCH_COUNT)
+        proof = self.wait_for_proof(tx_hash)
+        assert_equal(proof["transactionHash"], tx_hash)
+        assert_equal(int(proof["epochNumber"], 0), epoch)
+        assert_equal(proof["epochHash"], self.block_by_epoch(self.EPOCH_NUM(epoch))["hash"])
+
+        witness = self.assert_witness(proof, epoch + DEFERRED_STATE_EPOCH_COUNT)
+        assert_equal(witness["blame"], "0x0")
+        assert_equal(proof["witnessReceiptsRoots"], [proof["receiptsRoot"]])
+        assert_equal(witness["deferredReceiptsRoot"], proof["receiptsRoot"])
+
+    def test_witness_with_blame(self):
+        (tx_hash, epoch) = self.send_tx_in_new_epoch()
+        self.generate_blocks(DEFERRED_STATE_EPOCH_COUNT - 1)
+        # The pivot block which should commit the receipts root has a wrong one.
+        blame_info = {"deferredReceiptsRoot": "0x" + "11" * 32}
+        self.node.test_generateblockwithblameinfo(1, 0, blame_info)
+        assert_equal(self.get_transaction_receipt_proof(tx_hash), None)
+
+        # The next pivot block blames it, and commits the correct roots of
+        # both epochs in the blame vector.
+        self.generate_block()
+        proof = self.wait_for_proof(tx_hash)
+        assert_equal(int(proof["epochNumber"], 0), epoch)
+
+        witness = self.assert_witness(proof, epoch + DEFERRED_STATE_EPOCH_COUNT + 1)
+        assert_equal(witness["blame"], "0x1")
+        roots = proof["witnessReceiptsRoots"]
+        assert_equal(len(roots), 2)
+        assert_equal(roots[1], proof["receiptsRoot"])
+        assert_equal(blame_vec_root(roots), witness["deferredReceiptsRoot"])
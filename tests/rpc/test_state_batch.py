@@ -0,0 +1,78 @@
+import sys
+sys.path.append("..")
+
+from conflux.rpc import RpcClient
+from test_framework.util import assert_equal, assert_raises_rpc_error
+
+# The limit of the entries read by one batch request.
+MAX_STATE_BATCH_SIZE = 1000
+
+# simple storage contract with default value (5) at the slot 0
+SIMPLE_STORAGE_BYTECODE = "0x608060405234801561001057600080fd5b50600560008190555060e6806100276000396000f3fe6080604052600436106043576000357c01000000000000000000000000000000000000000000000000000000009004806360fe47b11460485780636d4ce63c14607f575b600080fd5b348015605357600080fd5b50607d60048036036020811015606857600080fd5b810190808035906020019092919050505060a7565b005b348015608a57600080fd5b50609160b1565b6040518082815260200191505060405180910390f35b8060008190555050565b6000805490509056fea165627a7a72305820b5180d95fdc3813028ed47f62c7cdf708b76c0db094043f533b42a430d313e150029"
+
+SLOT_0 = "0x" + "00" * 32
+SLOT_1 = "0x" + "00" * 31 + "01"
+
+
+class TestStateBatch(RpcClient):
+    def deploy_simple_storage(self) -> str:
+        tx = self.new_contract_tx("", SIMPLE_STORAGE_BYTECODE, storage_limit=200000)
+        assert_equal(self.send_tx(tx, True), tx.hash_hex())
+        return self.get_tx(tx.hash_hex())["contractCreated"]
+
+    def assert_epoch_of_batch(self, batch: dict, epoch: str):
+        # all the entries are read from the state of the returned epoch
+        assert_equal(int(batch["epochNumber"], 0), self.epoch_number(epoch))
+        pivot = self.block_by_epoch(batch["epochNumber"])
+        assert_equal(batch["epochHash"], pivot["hash"])
+
+    def test_account_batch(self):
+        (addr, _) = self.rand_account()
+        tx = self.new_tx(receiver=addr, value=10 ** 18)
+        assert_equal(self.send_tx(tx, True), tx.hash_hex())
+
+        addrs = [self.GENESIS_ADDR, addr, self.rand_addr()]
+        batch = self.get_account_batch(addrs)
+        self.assert_epoch_of_batch(batch, self.EPOCH_LATEST_STATE)
+        epoch = batch["epochNumber"]
+        assert_equal(len(batch["accounts"]), len(addrs))
+        for (a, account) in zip(addrs, batch["accounts"]):
+            assert_equal(account, self.get_account(a, epoch))
+        assert_equal(int(batch["accounts"][1]["balance"], 0), 10 ** 18)
+        assert_equal(int(batch["accounts"][2]["balance"], 0), 0)
+
+        # the accounts before the transfer
+        batch = self.get_account_batch(addrs, self.EPOCH_EARLIEST)
+        self.assert_epoch_of_batch(batch, self.EPOCH_EARLIEST)
+        for (a, account) in zip(addrs, batch["accounts"]):
+            assert_equal(account, self.get_account(a, self.EPOCH_EARLIEST))
+        assert_equal(int(batch["accounts"][1]["balance"], 0), 0)
+
+    def test_storage_batch(self):
+        contract_addr = self.deploy_simple_storage()
+        slots = [
+            {"address": contract_addr, "position": SLOT_0},
+            {"address": contract_addr, "position": SLOT_1},
+            {"address": self.rand_addr(), "position": SLOT_0},
+        ]
+        batch = self.get_storage_batch(slots)
+        self.assert_epoch_of_batch(batch, self.EPOCH_LATEST_STATE)
+        epoch = batch["epochNumber"]
+        assert_equal(len(batch["values"]), len(slots))
+        for (slot, value) in zip(slots, batch["values"]):
+            assert_equal(value, self.get_storage_at(slot["address"], slot["position"], epoch))
+        assert_equal(int(batch["values"][0], 0), 5)
+        assert_equal(batch["values"][1], None)
+        assert_equal(batch["values"][2], None)
+
+    def test_batch_size_limit(self):
+        addrs = [self.GENESIS_ADDR] * MAX_STATE_BATCH_SIZE
+        batch = self.get_account_batch(addrs)
+        assert_equal(len(batch["accounts"]), MAX_STATE_BATCH_SIZE)
+        assert_raises_rpc_error(-32602, None, self.get_account_batch, addrs + [self.GENESIS_ADDR])
+
+        slots = [{"address": a, "position": SLOT_0} for a in addrs]
+        batch = self.get_storage_batch(slots)
+        assert_equal(len(batch["values"]), MAX_STATE_BATCH_SIZE)
+        slots.append({"address": self.GENESIS_ADDR, "position": SLOT_0})
+        assert_raises_rpc_error(-32602, None, self.get_storage_batch, slots)
@@ -662,6 +662,10 @@ where Message: Send + Sync + 'static
     }
 
     pub fn stop(&self) {
+        // The service may be stopped before it's dropped.
+        if self.thread.lock().is_none() {
+            return;
+        }
         debug!("[IoService] Closing...");
         // Network poll should be closed before the main EventLoop, otherwise it
         // will send messages to a closed EventLoop.
@@ -13,6 +13,7 @@ mod queue;
 mod registry;
 mod report;
 mod report_influxdb;
+mod report_prometheus;
 mod timer;
 
 pub use self::{
@@ -23,5 +24,6 @@ pub use self::{
     meter::{register_meter, register_meter_with_group, Meter, MeterTimer},
     metrics::{initialize, MetricsConfiguration},
     queue::{register_queue, register_queue_with_group, Queue},
+    report_prometheus::export_metrics,
     timer::{register_timer, register_timer_with_group, ScopeTimer, Timer},
 };
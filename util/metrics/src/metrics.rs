@@ -5,8 +5,11 @@
 use crate::{
     report::{report_async, FileReporter, Reportable},
     report_influxdb::{InfluxdbReportable, InfluxdbReporter},
+    report_prometheus::{serve_prometheus, PrometheusReportable},
 };
+use log::warn;
 use std::{
+    net::SocketAddr,
     sync::atomic::{AtomicBool, Ordering},
     time::Duration,
 };
@@ -19,7 +22,9 @@ pub fn is_enabled() -> bool { ENABLED.load(ORDER) }
 
 fn enable() { ENABLED.store(true, ORDER); }
 
-pub trait Metric: Send + Sync + Reportable + InfluxdbReportable {
+pub trait Metric:
+    Send + Sync + Reportable + InfluxdbReportable + PrometheusReportable
+{
     fn get_type(&self) -> &str;
 }
 
@@ -34,6 +39,8 @@ pub struct MetricsConfiguration {
     pub influxdb_report_username: Option<String>,
     pub influxdb_report_password: Option<String>,
     pub influxdb_report_node: Option<String>,
+
+    pub prometheus_listen_addr: Option<String>,
 }
 
 pub fn initialize(config: MetricsConfiguration) {
@@ -75,4 +82,14 @@ pub fn initialize(config: MetricsConfiguration) {
 
         report_async(reporter, config.report_interval);
     }
+
+    // prometheus exporter
+    if let Some(addr) = config.prometheus_listen_addr {
+        match addr.parse::<SocketAddr>() {
+            Ok(addr) => serve_prometheus(addr),
+            Err(e) => {
+                warn!("invalid prometheus listen address {}: {}", addr, e)
+            }
+        }
+    }
 }
@@ -0,0 +1,231 @@
+// Copyright 2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    counter::{Counter, CounterUsize},
+    gauge::{Gauge, GaugeUsize},
+    histogram::Histogram,
+    meter::{Meter, StandardMeter},
+    metrics::is_enabled,
+    registry::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY},
+};
+use log::{debug, info, warn};
+use std::{
+    fmt::Write as FmtWrite,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+const NAME_PREFIX: &str = "conflux_";
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+/// The connections beyond the limit are closed at once, so that the clients
+/// which don't send their requests can't exhaust the threads.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+/// The request line and the headers are never longer than this.
+const MAX_REQUEST_BYTES: u64 = 8192;
+
+/// Serve all the registered metrics in the Prometheus text format on
+/// `http://addr/metrics`.
+pub fn serve_prometheus(addr: SocketAddr) {
+    if !is_enabled() {
+        return;
+    }
+
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("failed to bind prometheus exporter to {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("prometheus exporter listening on {}", addr);
+
+    thread::Builder::new()
+        .name("prometheus_exporter".into())
+        .spawn(move || {
+            // Each connection is served in its own thread, so that a slow
+            // client doesn't block the other scrapes.
+            let active_requests = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        debug!("failed to accept connection, {}", e);
+                        continue;
+                    }
+                };
+                if active_requests.fetch_add(1, Ordering::SeqCst)
+                    >= MAX_CONCURRENT_REQUESTS
+                {
+                    active_requests.fetch_sub(1, Ordering::SeqCst);
+                    debug!("too many prometheus requests, connection closed");
+                    continue;
+                }
+                let requests = active_requests.clone();
+                let spawned = thread::Builder::new()
+                    .name("prometheus_request".into())
+                    .spawn(move || {
+                        if let Err(e) = handle_request(stream) {
+                            debug!("failed to serve prometheus request, {}", e);
+                        }
+                        requests.fetch_sub(1, Ordering::SeqCst);
+                    });
+                if let Err(e) = spawned {
+                    active_requests.fetch_sub(1, Ordering::SeqCst);
+                    warn!("failed to spawn prometheus request thread: {}", e);
+                }
+            }
+        })
+        .expect("failed to spawn prometheus exporter thread");
+}
+
+fn handle_request(stream: TcpStream) -> std::io::Result<()> {
+    let timeout = Some(Duration::from_secs(REQUEST_TIMEOUT_SECONDS));
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, which are not used.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", CONTENT_TYPE, export_metrics())
+        }
+        _ => ("404 Not Found", "text/plain", "Not Found\n".into()),
+    };
+
+    let mut stream = reader.into_inner().into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// All the registered metrics in the Prometheus text format, sorted by name.
+/// The metrics of a group are named `<group>_<metric>`.
+pub fn export_metrics() -> String {
+    let mut metrics: Vec<(String, String)> = Vec::new();
+
+    for (name, metric) in DEFAULT_REGISTRY.read().get_all() {
+        let name = prometheus_name(name);
+        let mut out = String::new();
+        metric.write_samples(&name, &mut out);
+        metrics.push((name, out));
+    }
+
+    for (group_name, group) in DEFAULT_GROUPING_REGISTRY.read().get_all() {
+        for (metric_name, metric) in group {
+            let name =
+                prometheus_name(&format!("{}_{}", group_name, metric_name));
+            let mut out = String::new();
+            metric.write_samples(&name, &mut out);
+            metrics.push((name, out));
+        }
+    }
+
+    metrics.sort();
+    metrics.into_iter().map(|(_, out)| out).collect()
+}
+
+/// Prefix `name` and replace the characters not allowed by Prometheus.
+fn prometheus_name(name: &str) -> String {
+    let mut result = String::from(NAME_PREFIX);
+    result.extend(name.chars().map(|c| {
+        if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+            c
+        } else {
+            '_'
+        }
+    }));
+    result
+}
+
+pub trait PrometheusReportable {
+    /// Append the `TYPE` line and the samples of the metric named `name` to
+    /// `out`.
+    fn write_samples(&self, name: &str, out: &mut String);
+}
+
+// `fmt::Write` for `String` never fails, so the results are ignored below.
+
+impl PrometheusReportable for CounterUsize {
+    fn write_samples(&self, name: &str, out: &mut String) {
+        // Counters may be decreased, e.g. the queued items of a `Queue`, so
+        // they are exported as gauges.
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, self.count());
+    }
+}
+
+impl PrometheusReportable for GaugeUsize {
+    fn write_samples(&self, name: &str, out: &mut String) {
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, self.value());
+    }
+}
+
+impl PrometheusReportable for StandardMeter {
+    fn write_samples(&self, name: &str, out: &mut String) {
+        let snapshot = self.snapshot();
+        let _ = writeln!(out, "# TYPE {}_total counter", name);
+        let _ = writeln!(out, "{}_total {}", name, snapshot.count());
+        let _ = writeln!(out, "# TYPE {}_rate gauge", name);
+        for (window, rate) in &[
+            ("m1", snapshot.rate1()),
+            ("m5", snapshot.rate5()),
+            ("m15", snapshot.rate15()),
+            ("mean", snapshot.rate_mean()),
+        ] {
+            let _ = writeln!(
+                out,
+                "{}_rate{{window=\"{}\"}} {}",
+                name, window, rate
+            );
+        }
+    }
+}
+
+impl<T: Histogram> PrometheusReportable for T {
+    fn write_samples(&self, name: &str, out: &mut String) {
+        let snapshot = self.snapshot();
+        let _ = writeln!(out, "# TYPE {} summary", name);
+        for quantile in &[0.5, 0.75, 0.9, 0.95, 0.99, 0.999] {
+            let _ = writeln!(
+                out,
+                "{}{{quantile=\"{}\"}} {}",
+                name,
+                quantile,
+                snapshot.percentile(*quantile)
+            );
+        }
+        // The histograms only keep a sample of the values, so the sum of all
+        // the values is estimated with the mean of the sample.
+        let _ = writeln!(
+            out,
+            "{}_sum {}",
+            name,
+            snapshot.mean() * snapshot.count() as f64
+        );
+        let _ = writeln!(out, "{}_count {}", name, snapshot.count());
+    }
+}